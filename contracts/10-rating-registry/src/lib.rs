@@ -0,0 +1,340 @@
+//!
+//! Stylus Rating Registry
+//!
+//! The owner registers items; anyone can then rate an item from 1 to 5 stars
+//! with an optional short review. Each address gets one review per item.
+//!
+//! Every item keeps a running star total and review count, so its average is
+//! available in O(1) without iterating reviews. Averages are fixed point with
+//! two decimals: `average_rating` returns `467` for 4.67 stars.
+//!
+//! Reviews are appended to a per-item vector and read back in pages of at most
+//! `MAX_PAGE_SIZE`, so a popular item never makes a view call unbounded.
+//! Review text is capped at `MAX_REVIEW_BYTES` bytes of UTF-8.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use alloy_sol_types::sol;
+use common::paging::page;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256, U64, U8},
+    prelude::*,
+};
+
+/// Longest review text accepted, in bytes.
+const MAX_REVIEW_BYTES: usize = 280;
+/// Longest item name accepted, in bytes.
+const MAX_NAME_BYTES: usize = 64;
+/// Most reviews returned by a single `get_reviews` call.
+const MAX_PAGE_SIZE: u64 = 20;
+/// Averages are returned multiplied by this factor.
+const AVERAGE_SCALE: u64 = 100;
+
+sol! {
+    event ItemRegistered(uint256 indexed itemId, string name);
+    event Reviewed(uint256 indexed itemId, address indexed reviewer, uint8 rating);
+
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error UnknownItem(uint256 itemId);
+    #[derive(Debug)]
+    error InvalidName();
+    #[derive(Debug)]
+    error InvalidRating(uint8 rating);
+    #[derive(Debug)]
+    error ReviewTooLong(uint256 length, uint256 max);
+    #[derive(Debug)]
+    error AlreadyReviewed(uint256 itemId, address reviewer);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum RatingRegistryError {
+    NotOwner(NotOwner),
+    UnknownItem(UnknownItem),
+    InvalidName(InvalidName),
+    InvalidRating(InvalidRating),
+    ReviewTooLong(ReviewTooLong),
+    AlreadyReviewed(AlreadyReviewed),
+}
+
+sol_storage! {
+    pub struct Review {
+        address reviewer;
+        uint8 rating;
+        uint64 timestamp;
+        string text;
+    }
+
+    pub struct Item {
+        string name;
+        uint256 rating_sum;
+        uint256 review_count;
+        Review[] reviews;
+        mapping(address => bool) reviewed;
+    }
+
+    #[entrypoint]
+    pub struct RatingRegistry {
+        address owner;
+        /// Items are numbered from 0 in registration order.
+        uint256 item_count;
+        mapping(uint256 => Item) items;
+    }
+}
+
+#[public]
+impl RatingRegistry {
+    #[constructor]
+    pub fn constructor(&mut self, owner: Address) {
+        self.owner.set(owner);
+    }
+
+    /// Registers a new item and returns its id.
+    pub fn register_item(&mut self, name: String) -> Result<U256, RatingRegistryError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(RatingRegistryError::NotOwner(NotOwner { caller }));
+        }
+        if name.is_empty() || name.len() > MAX_NAME_BYTES {
+            return Err(RatingRegistryError::InvalidName(InvalidName {}));
+        }
+        let item_id = self.item_count.get();
+        self.item_count.set(item_id + U256::from(1));
+        self.items.setter(item_id).name.set_str(&name);
+        log(
+            self.vm(),
+            ItemRegistered {
+                itemId: item_id,
+                name,
+            },
+        );
+        Ok(item_id)
+    }
+
+    /// Rates `item_id` from 1 to 5 stars, once per caller.
+    pub fn rate(
+        &mut self,
+        item_id: U256,
+        rating: u8,
+        text: String,
+    ) -> Result<(), RatingRegistryError> {
+        self.check_item(item_id)?;
+        if !(1..=5).contains(&rating) {
+            return Err(RatingRegistryError::InvalidRating(InvalidRating { rating }));
+        }
+        if text.len() > MAX_REVIEW_BYTES {
+            return Err(RatingRegistryError::ReviewTooLong(ReviewTooLong {
+                length: U256::from(text.len()),
+                max: U256::from(MAX_REVIEW_BYTES),
+            }));
+        }
+        let reviewer = self.vm().msg_sender();
+        let timestamp = self.vm().block_timestamp();
+        let mut item = self.items.setter(item_id);
+        if item.reviewed.get(reviewer) {
+            return Err(RatingRegistryError::AlreadyReviewed(AlreadyReviewed {
+                itemId: item_id,
+                reviewer,
+            }));
+        }
+        item.reviewed.insert(reviewer, true);
+        let rating_sum = item.rating_sum.get() + U256::from(rating);
+        item.rating_sum.set(rating_sum);
+        let review_count = item.review_count.get() + U256::from(1);
+        item.review_count.set(review_count);
+
+        let mut review = item.reviews.grow();
+        review.reviewer.set(reviewer);
+        review.rating.set(U8::from(rating));
+        review.timestamp.set(U64::from(timestamp));
+        review.text.set_str(&text);
+
+        log(
+            self.vm(),
+            Reviewed {
+                itemId: item_id,
+                reviewer,
+                rating,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn item_count(&self) -> U256 {
+        self.item_count.get()
+    }
+
+    pub fn item_name(&self, item_id: U256) -> Result<String, RatingRegistryError> {
+        self.check_item(item_id)?;
+        Ok(self.items.getter(item_id).name.get_string())
+    }
+
+    pub fn review_count(&self, item_id: U256) -> U256 {
+        self.items.getter(item_id).review_count.get()
+    }
+
+    /// Average rating times 100, e.g. `467` for 4.67 stars; zero when unrated.
+    pub fn average_rating(&self, item_id: U256) -> Result<U256, RatingRegistryError> {
+        self.check_item(item_id)?;
+        let item = self.items.getter(item_id);
+        let count = item.review_count.get();
+        if count.is_zero() {
+            return Ok(U256::ZERO);
+        }
+        Ok(item.rating_sum.get() * U256::from(AVERAGE_SCALE) / count)
+    }
+
+    pub fn has_reviewed(&self, item_id: U256, reviewer: Address) -> bool {
+        self.items.getter(item_id).reviewed.get(reviewer)
+    }
+
+    /// Returns up to `limit` reviews starting at `offset`, as `(reviewer, rating, timestamp, text)`.
+    ///
+    /// At most `MAX_PAGE_SIZE` reviews come back per call; paging past the last review
+    /// returns none.
+    pub fn get_reviews(
+        &self,
+        item_id: U256,
+        offset: U256,
+        limit: U256,
+    ) -> Result<Vec<(Address, u8, u64, String)>, RatingRegistryError> {
+        self.check_item(item_id)?;
+        let item = self.items.getter(item_id);
+        Ok(page(item.reviews.len(), offset, limit, MAX_PAGE_SIZE)
+            .filter_map(|index| item.reviews.getter(index))
+            .map(|review| {
+                (
+                    review.reviewer.get(),
+                    review.rating.get().to(),
+                    review.timestamp.get().to(),
+                    review.text.get_string(),
+                )
+            })
+            .collect())
+    }
+}
+
+impl RatingRegistry {
+    fn check_item(&self, item_id: U256) -> Result<(), RatingRegistryError> {
+        if item_id >= self.item_count.get() {
+            return Err(RatingRegistryError::UnknownItem(UnknownItem {
+                itemId: item_id,
+            }));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::new([0x01; 20]);
+
+    fn reviewer(i: u8) -> Address {
+        Address::new([0x10 + i; 20])
+    }
+
+    /// Registry with a single item, id 0.
+    fn setup() -> (TestVM, RatingRegistry) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(1_000);
+        let mut contract = RatingRegistry::from(&vm);
+        contract.constructor(OWNER);
+        vm.set_sender(OWNER);
+        contract.register_item("Red velvet".into()).unwrap();
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_running_average_in_fixed_point() {
+        let (vm, mut contract) = setup();
+        for (i, rating) in [5u8, 4, 5].into_iter().enumerate() {
+            vm.set_sender(reviewer(i as u8));
+            contract.rate(U256::ZERO, rating, String::new()).unwrap();
+        }
+        assert_eq!(contract.review_count(U256::ZERO), U256::from(3));
+        assert_eq!(
+            contract.average_rating(U256::ZERO).unwrap(),
+            U256::from(466)
+        );
+    }
+
+    #[test]
+    fn test_one_review_per_user_per_item() {
+        let (vm, mut contract) = setup();
+        contract.register_item("Lemon".into()).unwrap();
+        vm.set_sender(reviewer(0));
+        contract.rate(U256::ZERO, 3, "ok".into()).unwrap();
+        assert!(matches!(
+            contract.rate(U256::ZERO, 5, "changed my mind".into()),
+            Err(RatingRegistryError::AlreadyReviewed(_))
+        ));
+        contract.rate(U256::from(1), 5, String::new()).unwrap();
+        assert!(contract.has_reviewed(U256::ZERO, reviewer(0)));
+        assert_eq!(
+            contract.average_rating(U256::ZERO).unwrap(),
+            U256::from(300)
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_input() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(reviewer(0));
+        assert!(matches!(
+            contract.rate(U256::ZERO, 0, String::new()),
+            Err(RatingRegistryError::InvalidRating(_))
+        ));
+        assert!(contract.rate(U256::ZERO, 6, String::new()).is_err());
+        assert!(matches!(
+            contract.rate(U256::ZERO, 4, "a".repeat(MAX_REVIEW_BYTES + 1)),
+            Err(RatingRegistryError::ReviewTooLong(_))
+        ));
+        assert!(matches!(
+            contract.rate(U256::from(1), 4, String::new()),
+            Err(RatingRegistryError::UnknownItem(_))
+        ));
+        assert!(matches!(
+            contract.register_item("Carrot".into()),
+            Err(RatingRegistryError::NotOwner(_))
+        ));
+        assert_eq!(contract.review_count(U256::ZERO), U256::ZERO);
+    }
+
+    #[test]
+    fn test_paginated_reviews() {
+        let (vm, mut contract) = setup();
+        for i in 0..25u8 {
+            vm.set_sender(reviewer(i));
+            contract
+                .rate(U256::ZERO, 1 + i % 5, format!("review {i}"))
+                .unwrap();
+        }
+
+        let page = contract
+            .get_reviews(U256::ZERO, U256::ZERO, U256::from(100))
+            .unwrap();
+        assert_eq!(page.len(), MAX_PAGE_SIZE as usize);
+        assert_eq!(page[0], (reviewer(0), 1, 1_000, "review 0".into()));
+
+        let page = contract
+            .get_reviews(U256::ZERO, U256::from(20), U256::from(10))
+            .unwrap();
+        assert_eq!(page.len(), 5);
+        assert_eq!(page[4].3, "review 24");
+        assert!(contract
+            .get_reviews(U256::ZERO, U256::from(99), U256::from(10))
+            .unwrap()
+            .is_empty());
+    }
+}
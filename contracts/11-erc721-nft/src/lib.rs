@@ -0,0 +1,529 @@
+//!
+//! Stylus ERC-721 NFT
+//!
+//! "Cupcake Collectibles" (`CAKE`), an ERC-721 with per-token metadata URIs:
+//!
+//! ```solidity
+//! function ownerOf(uint256 tokenId) external view returns (address);
+//! function transferFrom(address from, address to, uint256 tokenId) external;
+//! function safeTransferFrom(address from, address to, uint256 tokenId) external;
+//! function approve(address to, uint256 tokenId) external;
+//! function setApprovalForAll(address operator, bool approved) external;
+//! function tokenURI(uint256 tokenId) external view returns (string memory);
+//! ```
+//!
+//! Tokens are minted by the owner with sequential ids starting at 0, and
+//! `tokenURI` is the stored base URI followed by the decimal token id, e.g.
+//! `ipfs://cid/7`. Every state change emits the standard `Transfer`,
+//! `Approval` or `ApprovalForAll` event, and failures revert with the ERC-6093
+//! custom errors.
+//!
+//! `safeTransferFrom` (with and without `data`) transfers like
+//! `transferFrom`, then, if the receiver is a contract, calls its
+//! `onERC721Received` and reverts unless it returns that function's selector,
+//! so tokens are not stranded in contracts that cannot move them. The two
+//! overloads are separate Rust methods sharing `#[selector(name = ...)]`.
+//!
+//! `supportsInterface` answers ERC-165 queries for ERC-165, ERC-721 and ERC-721
+//! Metadata, with the ids from `common::introspection`.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use alloy_sol_types::{sol, SolCall};
+use common::introspection::{ERC165_ID, ERC721_ID, ERC721_METADATA_ID};
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, FixedBytes, U256},
+    prelude::*,
+};
+
+sol! {
+    interface IERC721Receiver {
+        function onERC721Received(address operator, address from, uint256 tokenId, bytes data) external returns (bytes4);
+    }
+
+    event Transfer(address indexed from, address indexed to, uint256 indexed tokenId);
+    event Approval(address indexed owner, address indexed approved, uint256 indexed tokenId);
+    event ApprovalForAll(address indexed owner, address indexed operator, bool approved);
+
+    #[derive(Debug)]
+    error ERC721InvalidOwner(address owner);
+    #[derive(Debug)]
+    error ERC721NonexistentToken(uint256 tokenId);
+    #[derive(Debug)]
+    error ERC721IncorrectOwner(address sender, uint256 tokenId, address owner);
+    #[derive(Debug)]
+    error ERC721InvalidReceiver(address receiver);
+    #[derive(Debug)]
+    error ERC721InsufficientApproval(address operator, uint256 tokenId);
+    #[derive(Debug)]
+    error ERC721InvalidApprover(address approver);
+    #[derive(Debug)]
+    error ERC721InvalidOperator(address operator);
+    #[derive(Debug)]
+    error NotOwner(address caller);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum Erc721Error {
+    InvalidOwner(ERC721InvalidOwner),
+    NonexistentToken(ERC721NonexistentToken),
+    IncorrectOwner(ERC721IncorrectOwner),
+    InvalidReceiver(ERC721InvalidReceiver),
+    InsufficientApproval(ERC721InsufficientApproval),
+    InvalidApprover(ERC721InvalidApprover),
+    InvalidOperator(ERC721InvalidOperator),
+    NotOwner(NotOwner),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct Erc721Nft {
+        /// Contract owner, who may mint and change the base URI.
+        address owner;
+        string base_uri;
+        uint256 next_token_id;
+        mapping(uint256 => address) owners;
+        mapping(address => uint256) balances;
+        mapping(uint256 => address) token_approvals;
+        mapping(address => mapping(address => bool)) operator_approvals;
+    }
+}
+
+#[public]
+impl Erc721Nft {
+    #[constructor]
+    pub fn constructor(&mut self, owner: Address, base_uri: String) {
+        self.owner.set(owner);
+        self.base_uri.set_str(&base_uri);
+    }
+
+    pub fn name(&self) -> String {
+        "Cupcake Collectibles".into()
+    }
+
+    pub fn symbol(&self) -> String {
+        "CAKE".into()
+    }
+
+    /// Base URI followed by the decimal token id.
+    #[selector(name = "tokenURI")]
+    pub fn token_uri(&self, token_id: U256) -> Result<String, Erc721Error> {
+        self.require_owned(token_id)?;
+        Ok(format!("{}{}", self.base_uri.get_string(), token_id))
+    }
+
+    pub fn balance_of(&self, owner: Address) -> Result<U256, Erc721Error> {
+        if owner.is_zero() {
+            return Err(Erc721Error::InvalidOwner(ERC721InvalidOwner { owner }));
+        }
+        Ok(self.balances.get(owner))
+    }
+
+    pub fn owner_of(&self, token_id: U256) -> Result<Address, Erc721Error> {
+        self.require_owned(token_id)
+    }
+
+    pub fn get_approved(&self, token_id: U256) -> Result<Address, Erc721Error> {
+        self.require_owned(token_id)?;
+        Ok(self.token_approvals.get(token_id))
+    }
+
+    pub fn is_approved_for_all(&self, owner: Address, operator: Address) -> bool {
+        self.operator_approvals.getter(owner).get(operator)
+    }
+
+    pub fn total_minted(&self) -> U256 {
+        self.next_token_id.get()
+    }
+
+    /// Approves `to` to transfer `token_id`; the owner or one of its operators may call this.
+    pub fn approve(&mut self, to: Address, token_id: U256) -> Result<(), Erc721Error> {
+        let caller = self.vm().msg_sender();
+        let owner = self.require_owned(token_id)?;
+        if caller != owner && !self.is_approved_for_all(owner, caller) {
+            return Err(Erc721Error::InvalidApprover(ERC721InvalidApprover {
+                approver: caller,
+            }));
+        }
+        self.token_approvals.insert(token_id, to);
+        log(
+            self.vm(),
+            Approval {
+                owner,
+                approved: to,
+                tokenId: token_id,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn set_approval_for_all(
+        &mut self,
+        operator: Address,
+        approved: bool,
+    ) -> Result<(), Erc721Error> {
+        if operator.is_zero() {
+            return Err(Erc721Error::InvalidOperator(ERC721InvalidOperator {
+                operator,
+            }));
+        }
+        let owner = self.vm().msg_sender();
+        self.operator_approvals
+            .setter(owner)
+            .insert(operator, approved);
+        log(
+            self.vm(),
+            ApprovalForAll {
+                owner,
+                operator,
+                approved,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn transfer_from(
+        &mut self,
+        from: Address,
+        to: Address,
+        token_id: U256,
+    ) -> Result<(), Erc721Error> {
+        if to.is_zero() {
+            return Err(Erc721Error::InvalidReceiver(ERC721InvalidReceiver {
+                receiver: to,
+            }));
+        }
+        let caller = self.vm().msg_sender();
+        let owner = self.require_owned(token_id)?;
+        if owner != from {
+            return Err(Erc721Error::IncorrectOwner(ERC721IncorrectOwner {
+                sender: from,
+                tokenId: token_id,
+                owner,
+            }));
+        }
+        let authorized = caller == owner
+            || self.token_approvals.get(token_id) == caller
+            || self.is_approved_for_all(owner, caller);
+        if !authorized {
+            return Err(Erc721Error::InsufficientApproval(
+                ERC721InsufficientApproval {
+                    operator: caller,
+                    tokenId: token_id,
+                },
+            ));
+        }
+
+        // Clearing the single-token approval does not emit `Approval`, as in OpenZeppelin.
+        self.token_approvals.delete(token_id);
+        let from_balance = self.balances.get(from);
+        self.balances.insert(from, from_balance - U256::from(1));
+        let to_balance = self.balances.get(to);
+        self.balances.insert(to, to_balance + U256::from(1));
+        self.owners.insert(token_id, to);
+        log(
+            self.vm(),
+            Transfer {
+                from,
+                to,
+                tokenId: token_id,
+            },
+        );
+        Ok(())
+    }
+
+    /// `transferFrom`, then checks that a contract receiver accepts the token.
+    #[selector(name = "safeTransferFrom")]
+    pub fn safe_transfer_from(
+        &mut self,
+        from: Address,
+        to: Address,
+        token_id: U256,
+    ) -> Result<(), Erc721Error> {
+        self.safe_transfer_from_with_data(from, to, token_id, Bytes::from(Vec::new()))
+    }
+
+    /// As `safe_transfer_from`, passing `data` on to `onERC721Received`.
+    #[selector(name = "safeTransferFrom")]
+    pub fn safe_transfer_from_with_data(
+        &mut self,
+        from: Address,
+        to: Address,
+        token_id: U256,
+        data: Bytes,
+    ) -> Result<(), Erc721Error> {
+        self.transfer_from(from, to, token_id)?;
+        self.check_on_erc721_received(from, to, token_id, data)
+    }
+
+    /// ERC-165: whether this contract implements the interface `interface_id`.
+    pub fn supports_interface(&self, interface_id: FixedBytes<4>) -> bool {
+        [ERC165_ID, ERC721_ID, ERC721_METADATA_ID].contains(&interface_id)
+    }
+
+    /// Mints the next token id to `to` and returns it. Only the owner may mint.
+    pub fn mint(&mut self, to: Address) -> Result<U256, Erc721Error> {
+        self.only_owner()?;
+        if to.is_zero() {
+            return Err(Erc721Error::InvalidReceiver(ERC721InvalidReceiver {
+                receiver: to,
+            }));
+        }
+        let token_id = self.next_token_id.get();
+        self.next_token_id.set(token_id + U256::from(1));
+        self.owners.insert(token_id, to);
+        let balance = self.balances.get(to);
+        self.balances.insert(to, balance + U256::from(1));
+        log(
+            self.vm(),
+            Transfer {
+                from: Address::ZERO,
+                to,
+                tokenId: token_id,
+            },
+        );
+        Ok(token_id)
+    }
+
+    pub fn set_base_uri(&mut self, base_uri: String) -> Result<(), Erc721Error> {
+        self.only_owner()?;
+        self.base_uri.set_str(&base_uri);
+        Ok(())
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+}
+
+impl Erc721Nft {
+    fn only_owner(&self) -> Result<(), Erc721Error> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(Erc721Error::NotOwner(NotOwner { caller }));
+        }
+        Ok(())
+    }
+
+    /// Accepts accounts without code; a contract must answer `onERC721Received` with its selector.
+    fn check_on_erc721_received(
+        &mut self,
+        from: Address,
+        to: Address,
+        token_id: U256,
+        data: Bytes,
+    ) -> Result<(), Erc721Error> {
+        if self.vm().code_size(to) == 0 {
+            return Ok(());
+        }
+        let call = IERC721Receiver::onERC721ReceivedCall {
+            operator: self.vm().msg_sender(),
+            from,
+            tokenId: token_id,
+            data: data.0.into(),
+        };
+        let accepted = self
+            .vm()
+            .call(&calls::context::Call::new(), to, &call.abi_encode())
+            .ok()
+            .and_then(|returned| {
+                IERC721Receiver::onERC721ReceivedCall::abi_decode_returns(&returned, true).ok()
+            })
+            .is_some_and(|decoded| decoded._0 == IERC721Receiver::onERC721ReceivedCall::SELECTOR);
+        if !accepted {
+            return Err(Erc721Error::InvalidReceiver(ERC721InvalidReceiver {
+                receiver: to,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Owner of `token_id`, or `ERC721NonexistentToken` if it was never minted.
+    fn require_owned(&self, token_id: U256) -> Result<Address, Erc721Error> {
+        let owner = self.owners.get(token_id);
+        if owner.is_zero() {
+            return Err(Erc721Error::NonexistentToken(ERC721NonexistentToken {
+                tokenId: token_id,
+            }));
+        }
+        Ok(owner)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::SolEvent;
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::new([0x01; 20]);
+    const ALICE: Address = Address::new([0x02; 20]);
+    const BOB: Address = Address::new([0x03; 20]);
+
+    /// Token 0 is minted to Alice.
+    fn setup() -> (TestVM, Erc721Nft) {
+        let vm = TestVM::default();
+        let mut contract = Erc721Nft::from(&vm);
+        contract.constructor(OWNER, "ipfs://cupcakes/".into());
+        vm.set_sender(OWNER);
+        contract.mint(ALICE).unwrap();
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_mint_assigns_sequential_ids() {
+        let (vm, mut contract) = setup();
+        assert_eq!(contract.mint(BOB).unwrap(), U256::from(1));
+        assert_eq!(contract.owner_of(U256::ZERO).unwrap(), ALICE);
+        assert_eq!(contract.owner_of(U256::from(1)).unwrap(), BOB);
+        assert_eq!(contract.balance_of(ALICE).unwrap(), U256::from(1));
+        assert_eq!(contract.total_minted(), U256::from(2));
+        assert!(matches!(
+            contract.owner_of(U256::from(2)),
+            Err(Erc721Error::NonexistentToken(_))
+        ));
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.mint(ALICE),
+            Err(Erc721Error::NotOwner(_))
+        ));
+    }
+
+    #[test]
+    fn test_token_uri_uses_base_uri() {
+        let (_vm, mut contract) = setup();
+        assert_eq!(contract.token_uri(U256::ZERO).unwrap(), "ipfs://cupcakes/0");
+        contract
+            .set_base_uri("https://cake.example/".into())
+            .unwrap();
+        assert_eq!(
+            contract.token_uri(U256::ZERO).unwrap(),
+            "https://cake.example/0"
+        );
+        assert!(contract.token_uri(U256::from(9)).is_err());
+    }
+
+    #[test]
+    fn test_owner_transfers_and_emits_event() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(ALICE);
+        contract.transfer_from(ALICE, BOB, U256::ZERO).unwrap();
+        assert_eq!(contract.owner_of(U256::ZERO).unwrap(), BOB);
+        assert_eq!(contract.balance_of(ALICE).unwrap(), U256::ZERO);
+        assert_eq!(contract.balance_of(BOB).unwrap(), U256::from(1));
+
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = logs.last().unwrap();
+        let event = Transfer::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!(
+            (event.from, event.to, event.tokenId),
+            (ALICE, BOB, U256::ZERO)
+        );
+
+        assert!(matches!(
+            contract.transfer_from(ALICE, BOB, U256::ZERO),
+            Err(Erc721Error::IncorrectOwner(_))
+        ));
+    }
+
+    #[test]
+    fn test_approved_address_transfers_once() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.transfer_from(ALICE, BOB, U256::ZERO),
+            Err(Erc721Error::InsufficientApproval(_))
+        ));
+        assert!(matches!(
+            contract.approve(BOB, U256::ZERO),
+            Err(Erc721Error::InvalidApprover(_))
+        ));
+
+        vm.set_sender(ALICE);
+        contract.approve(BOB, U256::ZERO).unwrap();
+        assert_eq!(contract.get_approved(U256::ZERO).unwrap(), BOB);
+
+        vm.set_sender(BOB);
+        contract.transfer_from(ALICE, OWNER, U256::ZERO).unwrap();
+        assert_eq!(contract.get_approved(U256::ZERO).unwrap(), Address::ZERO);
+        assert!(contract.transfer_from(OWNER, BOB, U256::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_supports_erc165_erc721_and_metadata() {
+        let (_vm, contract) = setup();
+        for id in [ERC165_ID, ERC721_ID, ERC721_METADATA_ID] {
+            assert!(contract.supports_interface(id));
+        }
+        assert!(!contract.supports_interface(FixedBytes::new([0xff; 4])));
+    }
+
+    #[test]
+    fn test_safe_transfer_checks_contract_receivers() {
+        let (vm, mut contract) = setup();
+        let vault = Address::new([0x0c; 20]);
+        vm.set_code(vault, vec![0x00]);
+        vm.set_sender(ALICE);
+
+        let call = IERC721Receiver::onERC721ReceivedCall {
+            operator: ALICE,
+            from: ALICE,
+            tokenId: U256::ZERO,
+            data: b"deposit".to_vec().into(),
+        };
+        let accept = IERC721Receiver::onERC721ReceivedCall::abi_encode_returns(&(FixedBytes::new(
+            IERC721Receiver::onERC721ReceivedCall::SELECTOR,
+        ),));
+        vm.mock_call(vault, call.abi_encode(), Ok(accept));
+        contract
+            .safe_transfer_from_with_data(ALICE, vault, U256::ZERO, b"deposit".to_vec().into())
+            .unwrap();
+        assert_eq!(contract.owner_of(U256::ZERO).unwrap(), vault);
+
+        // Accounts without code need no callback.
+        vm.set_sender(OWNER);
+        let token_id = contract.mint(ALICE).unwrap();
+        vm.set_sender(ALICE);
+        contract.safe_transfer_from(ALICE, BOB, token_id).unwrap();
+        assert_eq!(contract.owner_of(token_id).unwrap(), BOB);
+
+        // A contract that does not answer `onERC721Received` is refused.
+        let rejecting = Address::new([0x0d; 20]);
+        vm.set_code(rejecting, vec![0x00]);
+        vm.set_sender(OWNER);
+        let token_id = contract.mint(ALICE).unwrap();
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.safe_transfer_from(ALICE, rejecting, token_id),
+            Err(Erc721Error::InvalidReceiver(ERC721InvalidReceiver { receiver })) if receiver == rejecting
+        ));
+    }
+
+    #[test]
+    fn test_operator_manages_all_tokens() {
+        let (vm, mut contract) = setup();
+        contract.mint(ALICE).unwrap();
+        vm.set_sender(ALICE);
+        contract.set_approval_for_all(BOB, true).unwrap();
+        assert!(contract.is_approved_for_all(ALICE, BOB));
+
+        vm.set_sender(BOB);
+        contract.approve(OWNER, U256::from(1)).unwrap();
+        contract.transfer_from(ALICE, BOB, U256::ZERO).unwrap();
+        assert_eq!(contract.balance_of(BOB).unwrap(), U256::from(1));
+
+        vm.set_sender(ALICE);
+        contract.set_approval_for_all(BOB, false).unwrap();
+        vm.set_sender(BOB);
+        assert!(contract.transfer_from(ALICE, BOB, U256::from(1)).is_err());
+    }
+}
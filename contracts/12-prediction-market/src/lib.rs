@@ -0,0 +1,428 @@
+//!
+//! Stylus Prediction Market
+//!
+//! A binary market on "will the reported value be at least `threshold`?".
+//! Until `close_time` anyone can stake ETH on YES or NO with `buy`. Pricing is
+//! parimutuel: all stakes go into one pot, and after resolution the winning
+//! side splits it in proportion to what each account staked. The implied YES
+//! probability is simply the YES share of the pot.
+//!
+//! Resolution reads the oracle value from a deployed `DataValidator`
+//! (`contracts/3-onchain-data-validator`): the market is configured with the
+//! validator's address and a trusted reporter, and `resolve()` static-calls
+//! `getLastSubmission(reporter)`, parses the decimal and compares it with the
+//! threshold. If nobody backed the winning side, every stake is refunded.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use alloy_sol_types::{sol, SolCall};
+use core::str::FromStr;
+use rust_decimal::Decimal;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    prelude::*,
+};
+
+/// Probabilities are reported in basis points.
+const BPS: u64 = 10_000;
+
+sol! {
+    /// The subset of the DataValidator ABI the market reads from.
+    interface IDataValidator {
+        function getLastSubmission(address user) external view returns (string);
+    }
+
+    event SharesBought(address indexed buyer, bool yes, uint256 amount);
+    event MarketResolved(bool yesWon, string reportedValue);
+    event Claimed(address indexed account, uint256 amount);
+
+    #[derive(Debug)]
+    error InvalidThreshold();
+    #[derive(Debug)]
+    error MarketClosed(uint256 closeTime);
+    #[derive(Debug)]
+    error MarketStillOpen(uint256 closeTime);
+    #[derive(Debug)]
+    error ZeroStake();
+    #[derive(Debug)]
+    error AlreadyResolved();
+    #[derive(Debug)]
+    error NotResolved();
+    #[derive(Debug)]
+    error OracleUnavailable(address validator);
+    #[derive(Debug)]
+    error InvalidOracleValue(string value);
+    #[derive(Debug)]
+    error NothingToClaim();
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum PredictionMarketError {
+    InvalidThreshold(InvalidThreshold),
+    MarketClosed(MarketClosed),
+    MarketStillOpen(MarketStillOpen),
+    ZeroStake(ZeroStake),
+    AlreadyResolved(AlreadyResolved),
+    NotResolved(NotResolved),
+    OracleUnavailable(OracleUnavailable),
+    InvalidOracleValue(InvalidOracleValue),
+    NothingToClaim(NothingToClaim),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct PredictionMarket {
+        string question;
+        /// Decimal string the reported value is compared against.
+        string threshold;
+        address validator;
+        /// Account whose latest DataValidator submission settles the market.
+        address reporter;
+        uint256 close_time;
+        uint256 yes_pool;
+        uint256 no_pool;
+        mapping(address => uint256) yes_stakes;
+        mapping(address => uint256) no_stakes;
+        bool resolved;
+        bool yes_won;
+    }
+}
+
+#[public]
+impl PredictionMarket {
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        question: String,
+        threshold: String,
+        validator: Address,
+        reporter: Address,
+        close_time: U256,
+    ) -> Result<(), PredictionMarketError> {
+        if Decimal::from_str(&threshold).is_err() {
+            return Err(PredictionMarketError::InvalidThreshold(InvalidThreshold {}));
+        }
+        self.question.set_str(&question);
+        self.threshold.set_str(&threshold);
+        self.validator.set(validator);
+        self.reporter.set(reporter);
+        self.close_time.set(close_time);
+        Ok(())
+    }
+
+    /// Stakes the attached ETH on YES (`true`) or NO (`false`).
+    #[payable]
+    pub fn buy(&mut self, yes: bool) -> Result<(), PredictionMarketError> {
+        let close_time = self.close_time.get();
+        if U256::from(self.vm().block_timestamp()) >= close_time {
+            return Err(PredictionMarketError::MarketClosed(MarketClosed {
+                closeTime: close_time,
+            }));
+        }
+        let amount = self.vm().msg_value();
+        if amount.is_zero() {
+            return Err(PredictionMarketError::ZeroStake(ZeroStake {}));
+        }
+        let buyer = self.vm().msg_sender();
+        let (pool, stakes) = if yes {
+            (&mut self.yes_pool, &mut self.yes_stakes)
+        } else {
+            (&mut self.no_pool, &mut self.no_stakes)
+        };
+        pool.set(pool.get() + amount);
+        let stake = stakes.get(buyer) + amount;
+        stakes.insert(buyer, stake);
+        log(self.vm(), SharesBought { buyer, yes, amount });
+        Ok(())
+    }
+
+    /// Settles the market from the reporter's latest DataValidator submission.
+    pub fn resolve(&mut self) -> Result<bool, PredictionMarketError> {
+        if self.resolved.get() {
+            return Err(PredictionMarketError::AlreadyResolved(AlreadyResolved {}));
+        }
+        let close_time = self.close_time.get();
+        if U256::from(self.vm().block_timestamp()) < close_time {
+            return Err(PredictionMarketError::MarketStillOpen(MarketStillOpen {
+                closeTime: close_time,
+            }));
+        }
+
+        let reported = self.read_oracle()?;
+        let value = Decimal::from_str(&reported).map_err(|_| {
+            PredictionMarketError::InvalidOracleValue(InvalidOracleValue {
+                value: reported.clone(),
+            })
+        })?;
+        let threshold = Decimal::from_str(&self.threshold.get_string())
+            .expect("threshold is validated by the constructor");
+        let yes_won = value >= threshold;
+
+        self.resolved.set(true);
+        self.yes_won.set(yes_won);
+        log(
+            self.vm(),
+            MarketResolved {
+                yesWon: yes_won,
+                reportedValue: reported,
+            },
+        );
+        Ok(yes_won)
+    }
+
+    /// Pays out the caller's winnings, or refunds their stakes if nobody backed the winner.
+    pub fn claim(&mut self) -> Result<U256, PredictionMarketError> {
+        if !self.resolved.get() {
+            return Err(PredictionMarketError::NotResolved(NotResolved {}));
+        }
+        let account = self.vm().msg_sender();
+        let amount = self.payout(account);
+        if amount.is_zero() {
+            return Err(PredictionMarketError::NothingToClaim(NothingToClaim {}));
+        }
+
+        // Pools stay untouched so later claims divide the same totals.
+        self.yes_stakes.delete(account);
+        self.no_stakes.delete(account);
+        self.vm().transfer_eth(account, amount).map_err(|_| {
+            PredictionMarketError::TransferFailed(TransferFailed {
+                to: account,
+                amount,
+            })
+        })?;
+        log(self.vm(), Claimed { account, amount });
+        Ok(amount)
+    }
+
+    pub fn question(&self) -> String {
+        self.question.get_string()
+    }
+
+    pub fn threshold(&self) -> String {
+        self.threshold.get_string()
+    }
+
+    pub fn close_time(&self) -> U256 {
+        self.close_time.get()
+    }
+
+    /// Returns `(yes_pool, no_pool)`.
+    pub fn pools(&self) -> (U256, U256) {
+        (self.yes_pool.get(), self.no_pool.get())
+    }
+
+    /// Returns the account's `(yes_stake, no_stake)`.
+    pub fn stakes_of(&self, account: Address) -> (U256, U256) {
+        (self.yes_stakes.get(account), self.no_stakes.get(account))
+    }
+
+    /// Market-implied probability of YES in basis points; 5000 when nothing is staked.
+    pub fn yes_probability_bps(&self) -> U256 {
+        let total = self.yes_pool.get() + self.no_pool.get();
+        if total.is_zero() {
+            return U256::from(BPS / 2);
+        }
+        self.yes_pool.get() * U256::from(BPS) / total
+    }
+
+    /// Returns `(resolved, yes_won)`.
+    pub fn outcome(&self) -> (bool, bool) {
+        (self.resolved.get(), self.yes_won.get())
+    }
+
+    /// What `claim` would pay `account` right now.
+    pub fn payout(&self, account: Address) -> U256 {
+        if !self.resolved.get() {
+            return U256::ZERO;
+        }
+        let (yes_pool, no_pool) = self.pools();
+        let (yes_stake, no_stake) = self.stakes_of(account);
+        let (winning_pool, winning_stake) = if self.yes_won.get() {
+            (yes_pool, yes_stake)
+        } else {
+            (no_pool, no_stake)
+        };
+        if winning_pool.is_zero() {
+            return yes_stake + no_stake;
+        }
+        winning_stake * (yes_pool + no_pool) / winning_pool
+    }
+}
+
+impl PredictionMarket {
+    fn read_oracle(&self) -> Result<String, PredictionMarketError> {
+        let validator = self.validator.get();
+        let unavailable =
+            || PredictionMarketError::OracleUnavailable(OracleUnavailable { validator });
+        let call = IDataValidator::getLastSubmissionCall {
+            user: self.reporter.get(),
+        };
+        let returned = self
+            .vm()
+            .static_call(&calls::context::Call::new(), validator, &call.abi_encode())
+            .map_err(|_| unavailable())?;
+        let value = IDataValidator::getLastSubmissionCall::abi_decode_returns(&returned, true)
+            .map_err(|_| unavailable())?
+            ._0;
+        // An empty string means the reporter has not submitted anything yet.
+        if value.is_empty() {
+            return Err(unavailable());
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const VALIDATOR: Address = Address::new([0xda; 20]);
+    const REPORTER: Address = Address::new([0x0e; 20]);
+    const ALICE: Address = Address::new([0x02; 20]);
+    const BOB: Address = Address::new([0x03; 20]);
+    const CAROL: Address = Address::new([0x04; 20]);
+    const CLOSE: u64 = 2_000;
+
+    fn setup() -> (TestVM, PredictionMarket) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(1_000);
+        let mut contract = PredictionMarket::from(&vm);
+        contract
+            .constructor(
+                "ETH above 3000.5?".into(),
+                "3000.5".into(),
+                VALIDATOR,
+                REPORTER,
+                U256::from(CLOSE),
+            )
+            .unwrap();
+        (vm, contract)
+    }
+
+    fn stake(vm: &TestVM, contract: &mut PredictionMarket, who: Address, yes: bool, amount: u64) {
+        vm.set_sender(who);
+        vm.set_value(U256::from(amount));
+        contract.buy(yes).unwrap();
+        vm.set_value(U256::ZERO);
+        let balance = vm.balance(vm.contract_address());
+        vm.set_balance(vm.contract_address(), balance + U256::from(amount));
+    }
+
+    fn report(vm: &TestVM, value: &str) {
+        let call = IDataValidator::getLastSubmissionCall { user: REPORTER };
+        let returned =
+            IDataValidator::getLastSubmissionCall::abi_encode_returns(&(String::from(value),));
+        vm.mock_static_call(VALIDATOR, call.abi_encode(), Ok(returned));
+    }
+
+    #[test]
+    fn test_parimutuel_payouts() {
+        let (vm, mut contract) = setup();
+        stake(&vm, &mut contract, ALICE, true, 300);
+        stake(&vm, &mut contract, BOB, true, 100);
+        stake(&vm, &mut contract, CAROL, false, 600);
+        assert_eq!(contract.yes_probability_bps(), U256::from(4_000));
+
+        vm.set_block_timestamp(CLOSE);
+        report(&vm, "3100.25");
+        assert!(contract.resolve().unwrap());
+
+        vm.set_sender(ALICE);
+        assert_eq!(contract.claim().unwrap(), U256::from(750));
+        vm.set_sender(BOB);
+        assert_eq!(contract.claim().unwrap(), U256::from(250));
+        vm.set_sender(CAROL);
+        assert!(matches!(
+            contract.claim(),
+            Err(PredictionMarketError::NothingToClaim(_))
+        ));
+        assert_eq!(vm.balance(ALICE), U256::from(750));
+    }
+
+    #[test]
+    fn test_no_side_wins_below_threshold() {
+        let (vm, mut contract) = setup();
+        stake(&vm, &mut contract, ALICE, true, 100);
+        stake(&vm, &mut contract, BOB, false, 100);
+        vm.set_block_timestamp(CLOSE);
+        report(&vm, "3000.49");
+        assert!(!contract.resolve().unwrap());
+        assert_eq!(contract.payout(BOB), U256::from(200));
+        assert_eq!(contract.payout(ALICE), U256::ZERO);
+    }
+
+    #[test]
+    fn test_refunds_when_winning_side_is_empty() {
+        let (vm, mut contract) = setup();
+        stake(&vm, &mut contract, ALICE, true, 100);
+        vm.set_block_timestamp(CLOSE);
+        report(&vm, "1");
+        contract.resolve().unwrap();
+        vm.set_sender(ALICE);
+        assert_eq!(contract.claim().unwrap(), U256::from(100));
+    }
+
+    #[test]
+    fn test_trading_and_resolution_windows() {
+        let (vm, mut contract) = setup();
+        assert!(matches!(
+            contract.resolve(),
+            Err(PredictionMarketError::MarketStillOpen(_))
+        ));
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.buy(true),
+            Err(PredictionMarketError::ZeroStake(_))
+        ));
+        assert!(matches!(
+            contract.claim(),
+            Err(PredictionMarketError::NotResolved(_))
+        ));
+
+        vm.set_block_timestamp(CLOSE);
+        vm.set_value(U256::from(1));
+        assert!(matches!(
+            contract.buy(true),
+            Err(PredictionMarketError::MarketClosed(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolution_requires_valid_report() {
+        let (vm, mut contract) = setup();
+        vm.set_block_timestamp(CLOSE);
+        assert!(matches!(
+            contract.resolve(),
+            Err(PredictionMarketError::OracleUnavailable(_))
+        ));
+        report(&vm, "");
+        assert!(contract.resolve().is_err());
+        report(&vm, "3000.5");
+        assert!(contract.resolve().unwrap());
+        assert!(matches!(
+            contract.resolve(),
+            Err(PredictionMarketError::AlreadyResolved(_))
+        ));
+    }
+
+    #[test]
+    fn test_constructor_rejects_bad_threshold() {
+        let vm = TestVM::default();
+        let mut contract = PredictionMarket::from(&vm);
+        assert!(matches!(
+            contract.constructor(String::new(), "abc".into(), VALIDATOR, REPORTER, U256::ZERO),
+            Err(PredictionMarketError::InvalidThreshold(_))
+        ));
+    }
+}
@@ -0,0 +1,331 @@
+//!
+//! Stylus Token-Gated Content
+//!
+//! Publishers register content as an off-chain pointer (an IPFS CID or URL to
+//! encrypted data) plus an access key, gated on a token: only callers holding
+//! at least `min_balance` of the gate token get the key back from
+//! `access_key`. Gates are checked live with a static call to the token's
+//! `balanceOf(address)`, so any ERC-20 or ERC-721 works as a gate.
+//!
+//! Contract storage is public, so the gate decides who the contract *serves*,
+//! not who can read the chain. Store a key that is itself wrapped, e.g. for a
+//! key server that re-checks `has_access` before unwrapping it, never a raw
+//! decryption key.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use alloy_sol_types::{sol, SolCall};
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, U256},
+    prelude::*,
+};
+
+/// Longest content pointer accepted, in bytes.
+const MAX_POINTER_BYTES: usize = 256;
+
+sol! {
+    /// `balanceOf` has the same signature on ERC-20 and ERC-721.
+    interface IBalanceOf {
+        function balanceOf(address account) external view returns (uint256);
+    }
+
+    event ContentPublished(uint256 indexed contentId, address indexed publisher, address gateToken, uint256 minBalance);
+    event AccessKeyRotated(uint256 indexed contentId);
+
+    #[derive(Debug)]
+    error UnknownContent(uint256 contentId);
+    #[derive(Debug)]
+    error InvalidGate();
+    #[derive(Debug)]
+    error InvalidPointer();
+    #[derive(Debug)]
+    error NotPublisher(address caller);
+    #[derive(Debug)]
+    error AccessDenied(address token, uint256 required, uint256 balance);
+    #[derive(Debug)]
+    error TokenQueryFailed(address token);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum TokenGatedContentError {
+    UnknownContent(UnknownContent),
+    InvalidGate(InvalidGate),
+    InvalidPointer(InvalidPointer),
+    NotPublisher(NotPublisher),
+    AccessDenied(AccessDenied),
+    TokenQueryFailed(TokenQueryFailed),
+}
+
+sol_storage! {
+    pub struct Content {
+        address publisher;
+        string pointer;
+        bytes access_key;
+        address gate_token;
+        uint256 min_balance;
+    }
+
+    #[entrypoint]
+    pub struct TokenGatedContent {
+        /// Content ids are assigned from 0 in publication order.
+        uint256 content_count;
+        mapping(uint256 => Content) contents;
+    }
+}
+
+#[public]
+impl TokenGatedContent {
+    /// Registers content gated on holding `min_balance` of `gate_token`, returning its id.
+    pub fn publish(
+        &mut self,
+        pointer: String,
+        access_key: Bytes,
+        gate_token: Address,
+        min_balance: U256,
+    ) -> Result<U256, TokenGatedContentError> {
+        if gate_token.is_zero() || min_balance.is_zero() {
+            return Err(TokenGatedContentError::InvalidGate(InvalidGate {}));
+        }
+        if pointer.is_empty() || pointer.len() > MAX_POINTER_BYTES {
+            return Err(TokenGatedContentError::InvalidPointer(InvalidPointer {}));
+        }
+        let publisher = self.vm().msg_sender();
+        let content_id = self.content_count.get();
+        self.content_count.set(content_id + U256::from(1));
+
+        let mut content = self.contents.setter(content_id);
+        content.publisher.set(publisher);
+        content.pointer.set_str(&pointer);
+        content.access_key.set_bytes(&access_key);
+        content.gate_token.set(gate_token);
+        content.min_balance.set(min_balance);
+        log(
+            self.vm(),
+            ContentPublished {
+                contentId: content_id,
+                publisher,
+                gateToken: gate_token,
+                minBalance: min_balance,
+            },
+        );
+        Ok(content_id)
+    }
+
+    /// Replaces the access key, e.g. after re-encrypting the content. Publisher only.
+    pub fn rotate_key(
+        &mut self,
+        content_id: U256,
+        access_key: Bytes,
+    ) -> Result<(), TokenGatedContentError> {
+        self.check_content(content_id)?;
+        let caller = self.vm().msg_sender();
+        let mut content = self.contents.setter(content_id);
+        if content.publisher.get() != caller {
+            return Err(TokenGatedContentError::NotPublisher(NotPublisher {
+                caller,
+            }));
+        }
+        content.access_key.set_bytes(&access_key);
+        log(
+            self.vm(),
+            AccessKeyRotated {
+                contentId: content_id,
+            },
+        );
+        Ok(())
+    }
+
+    /// Returns the access key if the caller passes the content's token gate.
+    pub fn access_key(&self, content_id: U256) -> Result<Bytes, TokenGatedContentError> {
+        self.check_content(content_id)?;
+        let caller = self.vm().msg_sender();
+        let content = self.contents.getter(content_id);
+        let token = content.gate_token.get();
+        let required = content.min_balance.get();
+        let balance = self.balance_of(token, caller)?;
+        if balance < required {
+            return Err(TokenGatedContentError::AccessDenied(AccessDenied {
+                token,
+                required,
+                balance,
+            }));
+        }
+        Ok(content.access_key.get_bytes().into())
+    }
+
+    /// Whether `account` currently passes the content's token gate.
+    pub fn has_access(
+        &self,
+        content_id: U256,
+        account: Address,
+    ) -> Result<bool, TokenGatedContentError> {
+        self.check_content(content_id)?;
+        let content = self.contents.getter(content_id);
+        let balance = self.balance_of(content.gate_token.get(), account)?;
+        Ok(balance >= content.min_balance.get())
+    }
+
+    /// Returns the public part of an entry: `(publisher, pointer, gate_token, min_balance)`.
+    pub fn content(
+        &self,
+        content_id: U256,
+    ) -> Result<(Address, String, Address, U256), TokenGatedContentError> {
+        self.check_content(content_id)?;
+        let content = self.contents.getter(content_id);
+        Ok((
+            content.publisher.get(),
+            content.pointer.get_string(),
+            content.gate_token.get(),
+            content.min_balance.get(),
+        ))
+    }
+
+    pub fn content_count(&self) -> U256 {
+        self.content_count.get()
+    }
+}
+
+impl TokenGatedContent {
+    fn check_content(&self, content_id: U256) -> Result<(), TokenGatedContentError> {
+        if content_id >= self.content_count.get() {
+            return Err(TokenGatedContentError::UnknownContent(UnknownContent {
+                contentId: content_id,
+            }));
+        }
+        Ok(())
+    }
+
+    fn balance_of(&self, token: Address, account: Address) -> Result<U256, TokenGatedContentError> {
+        let failed = || TokenGatedContentError::TokenQueryFailed(TokenQueryFailed { token });
+        let call = IBalanceOf::balanceOfCall { account };
+        let returned = self
+            .vm()
+            .static_call(&calls::context::Call::new(), token, &call.abi_encode())
+            .map_err(|_| failed())?;
+        // Also rejects gates pointing at accounts without code, which return no data.
+        let balance = IBalanceOf::balanceOfCall::abi_decode_returns(&returned, true)
+            .map_err(|_| failed())?
+            ._0;
+        Ok(balance)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const PUBLISHER: Address = Address::new([0x01; 20]);
+    const HOLDER: Address = Address::new([0x02; 20]);
+    const OUTSIDER: Address = Address::new([0x03; 20]);
+    const TOKEN: Address = Address::new([0xcc; 20]);
+
+    fn mock_balance(vm: &TestVM, account: Address, balance: u64) {
+        let call = IBalanceOf::balanceOfCall { account };
+        let returned = IBalanceOf::balanceOfCall::abi_encode_returns(&(U256::from(balance),));
+        vm.mock_static_call(TOKEN, call.abi_encode(), Ok(returned));
+    }
+
+    /// Content 0 requires holding 10 units of `TOKEN`; the holder has 10, the outsider 9.
+    fn setup() -> (TestVM, TokenGatedContent) {
+        let vm = TestVM::default();
+        let mut contract = TokenGatedContent::from(&vm);
+        vm.set_sender(PUBLISHER);
+        contract
+            .publish(
+                "ipfs://bafy-secret".into(),
+                Bytes::from(b"wrapped-key-1".to_vec()),
+                TOKEN,
+                U256::from(10),
+            )
+            .unwrap();
+        mock_balance(&vm, HOLDER, 10);
+        mock_balance(&vm, OUTSIDER, 9);
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_holder_receives_key() {
+        let (vm, contract) = setup();
+        vm.set_sender(HOLDER);
+        assert_eq!(
+            contract.access_key(U256::ZERO).unwrap(),
+            Bytes::from(b"wrapped-key-1".to_vec())
+        );
+        assert!(contract.has_access(U256::ZERO, HOLDER).unwrap());
+    }
+
+    #[test]
+    fn test_insufficient_balance_is_denied() {
+        let (vm, contract) = setup();
+        vm.set_sender(OUTSIDER);
+        assert!(matches!(
+            contract.access_key(U256::ZERO),
+            Err(TokenGatedContentError::AccessDenied(AccessDenied { balance, .. }))
+                if balance == U256::from(9)
+        ));
+        assert!(!contract.has_access(U256::ZERO, OUTSIDER).unwrap());
+    }
+
+    #[test]
+    fn test_failing_token_query_denies_access() {
+        let (vm, contract) = setup();
+        // Unmocked accounts return no data, like an address without code.
+        vm.set_sender(PUBLISHER);
+        assert!(matches!(
+            contract.access_key(U256::ZERO),
+            Err(TokenGatedContentError::TokenQueryFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_publisher_rotates_key() {
+        let (vm, mut contract) = setup();
+        contract
+            .rotate_key(U256::ZERO, Bytes::from(b"wrapped-key-2".to_vec()))
+            .unwrap();
+        vm.set_sender(HOLDER);
+        assert_eq!(
+            contract.access_key(U256::ZERO).unwrap(),
+            Bytes::from(b"wrapped-key-2".to_vec())
+        );
+        assert!(matches!(
+            contract.rotate_key(U256::ZERO, Bytes::from(Vec::new())),
+            Err(TokenGatedContentError::NotPublisher(_))
+        ));
+    }
+
+    #[test]
+    fn test_publish_validation_and_metadata() {
+        let (_vm, mut contract) = setup();
+        assert!(matches!(
+            contract.publish("x".into(), Bytes::from(Vec::new()), TOKEN, U256::ZERO),
+            Err(TokenGatedContentError::InvalidGate(_))
+        ));
+        assert!(matches!(
+            contract.publish(String::new(), Bytes::from(Vec::new()), TOKEN, U256::from(1)),
+            Err(TokenGatedContentError::InvalidPointer(_))
+        ));
+        assert_eq!(
+            contract.content(U256::ZERO).unwrap(),
+            (
+                PUBLISHER,
+                "ipfs://bafy-secret".into(),
+                TOKEN,
+                U256::from(10)
+            )
+        );
+        assert!(matches!(
+            contract.content(U256::from(1)),
+            Err(TokenGatedContentError::UnknownContent(_))
+        ));
+    }
+}
@@ -0,0 +1,450 @@
+//!
+//! Stylus Recurring Lottery
+//!
+//! Rounds run back to back on a fixed schedule: each round ends
+//! `round_duration` seconds after the previous one. While a round is open,
+//! players buy tickets at `ticket_price`; every ticket is one entry in the
+//! round's `tickets` vector, so buying three tickets triples the odds.
+//!
+//! After the end time anyone can call `draw()`, which picks the winning ticket
+//! and opens the next round. The winner then has `claim_window` seconds to
+//! pull the pot with `claim()`. Pots that nobody wins (no tickets sold) roll
+//! into the next round straight away; pots left unclaimed can be rolled into
+//! the current round by anyone via `roll_over()`.
+//!
+//! The winning ticket is derived from a hash of block data, which a block
+//! producer can influence. Use a VRF or commit-reveal scheme for real stakes.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, B256, U256},
+    prelude::*,
+};
+
+/// Most tickets one `buy_tickets` call may add, keeping its gas bounded.
+const MAX_TICKETS_PER_CALL: u32 = 100;
+
+sol! {
+    event TicketsBought(uint256 indexed round, address indexed buyer, uint32 count);
+    event RoundDrawn(uint256 indexed round, address winner, uint256 pot);
+    event PrizeClaimed(uint256 indexed round, address indexed winner, uint256 amount);
+    event PotRolledOver(uint256 indexed fromRound, uint256 indexed toRound, uint256 amount);
+
+    #[derive(Debug)]
+    error RoundClosed(uint256 endTime);
+    #[derive(Debug)]
+    error RoundStillOpen(uint256 endTime);
+    #[derive(Debug)]
+    error InvalidTicketCount(uint32 count);
+    #[derive(Debug)]
+    error WrongPayment(uint256 expected, uint256 sent);
+    #[derive(Debug)]
+    error NotDrawn(uint256 round);
+    #[derive(Debug)]
+    error NotWinner(address caller);
+    #[derive(Debug)]
+    error AlreadySettled(uint256 round);
+    #[derive(Debug)]
+    error ClaimWindowOpen(uint256 until);
+    #[derive(Debug)]
+    error ClaimWindowClosed(uint256 closedAt);
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum LotteryError {
+    RoundClosed(RoundClosed),
+    RoundStillOpen(RoundStillOpen),
+    InvalidTicketCount(InvalidTicketCount),
+    WrongPayment(WrongPayment),
+    NotDrawn(NotDrawn),
+    NotWinner(NotWinner),
+    AlreadySettled(AlreadySettled),
+    ClaimWindowOpen(ClaimWindowOpen),
+    ClaimWindowClosed(ClaimWindowClosed),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    pub struct Round {
+        uint256 end_time;
+        uint256 pot;
+        /// One entry per ticket sold, holding the buyer.
+        address[] tickets;
+        address winner;
+        bool drawn;
+        uint256 drawn_at;
+        /// Set once the pot has been claimed or rolled over.
+        bool settled;
+    }
+
+    #[entrypoint]
+    pub struct Lottery {
+        uint256 ticket_price;
+        uint256 round_duration;
+        uint256 claim_window;
+        uint256 current_round;
+        mapping(uint256 => Round) rounds;
+    }
+}
+
+#[public]
+impl Lottery {
+    #[constructor]
+    pub fn constructor(&mut self, ticket_price: U256, round_duration: U256, claim_window: U256) {
+        self.ticket_price.set(ticket_price);
+        self.round_duration.set(round_duration);
+        self.claim_window.set(claim_window);
+        let end_time = U256::from(self.vm().block_timestamp()) + round_duration;
+        self.rounds.setter(U256::ZERO).end_time.set(end_time);
+    }
+
+    /// Buys `count` tickets in the current round; `msg.value` must be exactly `count * ticket_price`.
+    #[payable]
+    pub fn buy_tickets(&mut self, count: u32) -> Result<(), LotteryError> {
+        if count == 0 || count > MAX_TICKETS_PER_CALL {
+            return Err(LotteryError::InvalidTicketCount(InvalidTicketCount {
+                count,
+            }));
+        }
+        let expected = self.ticket_price.get() * U256::from(count);
+        let sent = self.vm().msg_value();
+        if sent != expected {
+            return Err(LotteryError::WrongPayment(WrongPayment { expected, sent }));
+        }
+        let now = U256::from(self.vm().block_timestamp());
+        let buyer = self.vm().msg_sender();
+        let round_id = self.current_round.get();
+        let mut round = self.rounds.setter(round_id);
+        let end_time = round.end_time.get();
+        if now >= end_time {
+            return Err(LotteryError::RoundClosed(RoundClosed { endTime: end_time }));
+        }
+
+        for _ in 0..count {
+            round.tickets.push(buyer);
+        }
+        let pot = round.pot.get() + sent;
+        round.pot.set(pot);
+        log(
+            self.vm(),
+            TicketsBought {
+                round: round_id,
+                buyer,
+                count,
+            },
+        );
+        Ok(())
+    }
+
+    /// Closes the current round, picks a winner and opens the next round. Anyone may call this.
+    pub fn draw(&mut self) -> Result<Address, LotteryError> {
+        let now = U256::from(self.vm().block_timestamp());
+        let round_id = self.current_round.get();
+        let round = self.rounds.getter(round_id);
+        let end_time = round.end_time.get();
+        if now < end_time {
+            return Err(LotteryError::RoundStillOpen(RoundStillOpen {
+                endTime: end_time,
+            }));
+        }
+        let ticket_count = round.tickets.len();
+        let pot = round.pot.get();
+
+        let winner = if ticket_count == 0 {
+            Address::ZERO
+        } else {
+            let seed = self.random_seed(round_id, ticket_count);
+            let index = (U256::from_be_bytes(seed.0) % U256::from(ticket_count)).to::<usize>();
+            round.tickets.get(index).expect("index is below len")
+        };
+
+        let mut round = self.rounds.setter(round_id);
+        round.winner.set(winner);
+        round.drawn.set(true);
+        round.drawn_at.set(now);
+
+        // Keep to the schedule: skip whole rounds if nobody drew for a while.
+        let duration = self.round_duration.get();
+        let mut next_end = end_time + duration;
+        if next_end <= now && !duration.is_zero() {
+            next_end += (now - next_end) / duration * duration + duration;
+        }
+        let next_id = round_id + U256::from(1);
+        self.current_round.set(next_id);
+        self.rounds.setter(next_id).end_time.set(next_end);
+
+        log(
+            self.vm(),
+            RoundDrawn {
+                round: round_id,
+                winner,
+                pot,
+            },
+        );
+        if winner.is_zero() {
+            self.roll_into_current(round_id);
+        }
+        Ok(winner)
+    }
+
+    /// Pays a drawn round's pot to its winner within the claim window.
+    pub fn claim(&mut self, round_id: U256) -> Result<U256, LotteryError> {
+        let caller = self.vm().msg_sender();
+        let closes_at = self.check_drawn(round_id)? + self.claim_window.get();
+        let now = U256::from(self.vm().block_timestamp());
+        let mut round = self.rounds.setter(round_id);
+        if round.winner.get() != caller {
+            return Err(LotteryError::NotWinner(NotWinner { caller }));
+        }
+        if now >= closes_at {
+            return Err(LotteryError::ClaimWindowClosed(ClaimWindowClosed {
+                closedAt: closes_at,
+            }));
+        }
+
+        let amount = round.pot.get();
+        round.settled.set(true);
+        self.vm()
+            .transfer_eth(caller, amount)
+            .map_err(|_| LotteryError::TransferFailed(TransferFailed { to: caller, amount }))?;
+        log(
+            self.vm(),
+            PrizeClaimed {
+                round: round_id,
+                winner: caller,
+                amount,
+            },
+        );
+        Ok(amount)
+    }
+
+    /// Moves a pot whose claim window has lapsed into the current round. Anyone may call this.
+    pub fn roll_over(&mut self, round_id: U256) -> Result<U256, LotteryError> {
+        let closes_at = self.check_drawn(round_id)? + self.claim_window.get();
+        if U256::from(self.vm().block_timestamp()) < closes_at {
+            return Err(LotteryError::ClaimWindowOpen(ClaimWindowOpen {
+                until: closes_at,
+            }));
+        }
+        Ok(self.roll_into_current(round_id))
+    }
+
+    pub fn current_round(&self) -> U256 {
+        self.current_round.get()
+    }
+
+    pub fn ticket_price(&self) -> U256 {
+        self.ticket_price.get()
+    }
+
+    /// Returns `(end_time, pot, ticket_count, winner, drawn, settled)`.
+    pub fn round_info(&self, round_id: U256) -> (U256, U256, U256, Address, bool, bool) {
+        let round = self.rounds.getter(round_id);
+        (
+            round.end_time.get(),
+            round.pot.get(),
+            U256::from(round.tickets.len()),
+            round.winner.get(),
+            round.drawn.get(),
+            round.settled.get(),
+        )
+    }
+
+    /// Buyer of ticket `index` in `round_id`, or the zero address past the end.
+    pub fn ticket_owner(&self, round_id: U256, index: U256) -> Address {
+        self.rounds
+            .getter(round_id)
+            .tickets
+            .get(index)
+            .unwrap_or_default()
+    }
+}
+
+impl Lottery {
+    /// Errors unless `round_id` is drawn and unsettled; returns when it was drawn.
+    fn check_drawn(&self, round_id: U256) -> Result<U256, LotteryError> {
+        let round = self.rounds.getter(round_id);
+        if !round.drawn.get() {
+            return Err(LotteryError::NotDrawn(NotDrawn { round: round_id }));
+        }
+        if round.settled.get() {
+            return Err(LotteryError::AlreadySettled(AlreadySettled {
+                round: round_id,
+            }));
+        }
+        Ok(round.drawn_at.get())
+    }
+
+    /// Settles `round_id` by adding its pot to the current round's pot.
+    fn roll_into_current(&mut self, round_id: U256) -> U256 {
+        let mut round = self.rounds.setter(round_id);
+        let amount = round.pot.get();
+        round.settled.set(true);
+        let current = self.current_round.get();
+        let mut target = self.rounds.setter(current);
+        let pot = target.pot.get() + amount;
+        target.pot.set(pot);
+        log(
+            self.vm(),
+            PotRolledOver {
+                fromRound: round_id,
+                toRound: current,
+                amount,
+            },
+        );
+        amount
+    }
+
+    fn random_seed(&self, round_id: U256, ticket_count: usize) -> B256 {
+        let mut preimage = Vec::with_capacity(128);
+        preimage.extend_from_slice(&round_id.to_be_bytes::<32>());
+        preimage.extend_from_slice(&U256::from(ticket_count).to_be_bytes::<32>());
+        preimage.extend_from_slice(&U256::from(self.vm().block_number()).to_be_bytes::<32>());
+        preimage.extend_from_slice(&U256::from(self.vm().block_timestamp()).to_be_bytes::<32>());
+        self.vm().native_keccak256(&preimage)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const ALICE: Address = Address::new([0x02; 20]);
+    const BOB: Address = Address::new([0x03; 20]);
+    const START: u64 = 1_000;
+    const DURATION: u64 = 100;
+    const WINDOW: u64 = 50;
+    const PRICE: u64 = 10;
+
+    fn setup() -> (TestVM, Lottery) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(START);
+        let mut contract = Lottery::from(&vm);
+        contract.constructor(U256::from(PRICE), U256::from(DURATION), U256::from(WINDOW));
+        (vm, contract)
+    }
+
+    fn buy(vm: &TestVM, contract: &mut Lottery, buyer: Address, count: u32) {
+        vm.set_sender(buyer);
+        vm.set_value(U256::from(PRICE * count as u64));
+        contract.buy_tickets(count).unwrap();
+        vm.set_value(U256::ZERO);
+        let balance = vm.balance(vm.contract_address());
+        vm.set_balance(
+            vm.contract_address(),
+            balance + U256::from(PRICE * count as u64),
+        );
+    }
+
+    #[test]
+    fn test_tickets_are_accounted_per_entry() {
+        let (vm, mut contract) = setup();
+        buy(&vm, &mut contract, ALICE, 3);
+        buy(&vm, &mut contract, BOB, 1);
+        let (end, pot, tickets, ..) = contract.round_info(U256::ZERO);
+        assert_eq!(end, U256::from(START + DURATION));
+        assert_eq!(pot, U256::from(40));
+        assert_eq!(tickets, U256::from(4));
+        assert_eq!(contract.ticket_owner(U256::ZERO, U256::from(2)), ALICE);
+        assert_eq!(contract.ticket_owner(U256::ZERO, U256::from(3)), BOB);
+
+        vm.set_value(U256::from(PRICE));
+        assert!(matches!(
+            contract.buy_tickets(2),
+            Err(LotteryError::WrongPayment(_))
+        ));
+        assert!(matches!(
+            contract.buy_tickets(0),
+            Err(LotteryError::InvalidTicketCount(_))
+        ));
+    }
+
+    #[test]
+    fn test_winner_claims_pot() {
+        let (vm, mut contract) = setup();
+        buy(&vm, &mut contract, ALICE, 2);
+        assert!(matches!(
+            contract.draw(),
+            Err(LotteryError::RoundStillOpen(_))
+        ));
+
+        vm.set_block_timestamp(START + DURATION);
+        assert_eq!(contract.draw().unwrap(), ALICE);
+        assert_eq!(contract.current_round(), U256::from(1));
+
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.claim(U256::ZERO),
+            Err(LotteryError::NotWinner(_))
+        ));
+        vm.set_sender(ALICE);
+        assert_eq!(contract.claim(U256::ZERO).unwrap(), U256::from(20));
+        assert_eq!(vm.balance(ALICE), U256::from(20));
+        assert!(matches!(
+            contract.claim(U256::ZERO),
+            Err(LotteryError::AlreadySettled(_))
+        ));
+    }
+
+    #[test]
+    fn test_empty_round_rolls_into_next() {
+        let (vm, mut contract) = setup();
+        vm.set_block_timestamp(START + DURATION);
+        assert_eq!(contract.draw().unwrap(), Address::ZERO);
+        buy(&vm, &mut contract, BOB, 1);
+
+        vm.set_block_timestamp(START + 2 * DURATION);
+        assert_eq!(contract.draw().unwrap(), BOB);
+        assert!(contract.round_info(U256::ZERO).5);
+        assert_eq!(contract.round_info(U256::from(1)).1, U256::from(PRICE));
+    }
+
+    #[test]
+    fn test_unclaimed_pot_rolls_over_after_window() {
+        let (vm, mut contract) = setup();
+        buy(&vm, &mut contract, ALICE, 1);
+        vm.set_block_timestamp(START + DURATION);
+        contract.draw().unwrap();
+        assert!(matches!(
+            contract.roll_over(U256::ZERO),
+            Err(LotteryError::ClaimWindowOpen(_))
+        ));
+
+        vm.set_block_timestamp(START + DURATION + WINDOW);
+        assert_eq!(contract.roll_over(U256::ZERO).unwrap(), U256::from(PRICE));
+        assert_eq!(contract.round_info(U256::from(1)).1, U256::from(PRICE));
+        vm.set_sender(ALICE);
+        assert!(contract.claim(U256::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_schedule_skips_missed_rounds() {
+        let (vm, mut contract) = setup();
+        vm.set_block_timestamp(START + 3 * DURATION + 10);
+        contract.draw().unwrap();
+        assert_eq!(
+            contract.round_info(U256::from(1)).0,
+            U256::from(START + 4 * DURATION)
+        );
+
+        vm.set_sender(ALICE);
+        vm.set_value(U256::from(PRICE));
+        vm.set_block_timestamp(START + 4 * DURATION);
+        assert!(matches!(
+            contract.buy_tickets(1),
+            Err(LotteryError::RoundClosed(_))
+        ));
+    }
+}
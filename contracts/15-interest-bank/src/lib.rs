@@ -0,0 +1,403 @@
+//!
+//! Stylus Interest Bank
+//!
+//! Deposits earn interest every second at a continuously compounded rate,
+//! without the contract ever looping over depositors. It keeps a single
+//! growth `index`, starting at 1.0, that is multiplied by `e^(rate * dt)`
+//! whenever time has passed. A deposit is stored as a *scaled* balance,
+//! `amount / index`, so every account's balance is `scaled * index` and grows
+//! with the index automatically.
+//!
+//! Rates and the index are WAD fixed point (`1e18` = 1.0); the math lives in
+//! `math.rs`. A rate of `1_585_489_599` per second is roughly 5% a year.
+//!
+//! Interest is paid from the contract's balance, which anyone can top up with
+//! `fund()`. If reserves run short, withdrawals revert with
+//! `InsufficientLiquidity` until the bank is funded again.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+mod math;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use math::{div_wad, div_wad_up, exp_wad, mul_wad, MAX_EXP_INPUT, WAD};
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    prelude::*,
+};
+
+/// Highest accepted rate: 100% a year, continuously compounded.
+const MAX_RATE_PER_SECOND: u64 = 1_000_000_000_000_000_000 / 31_536_000;
+
+sol! {
+    event Deposited(address indexed account, uint256 amount);
+    event Withdrawn(address indexed account, uint256 amount);
+    event Funded(address indexed from, uint256 amount);
+    event RateUpdated(uint256 ratePerSecond);
+
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error RateTooHigh(uint256 rate, uint256 max);
+    #[derive(Debug)]
+    error ZeroAmount();
+    #[derive(Debug)]
+    error InsufficientBalance(uint256 balance, uint256 requested);
+    #[derive(Debug)]
+    error InsufficientLiquidity(uint256 available, uint256 requested);
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum InterestBankError {
+    NotOwner(NotOwner),
+    RateTooHigh(RateTooHigh),
+    ZeroAmount(ZeroAmount),
+    InsufficientBalance(InsufficientBalance),
+    InsufficientLiquidity(InsufficientLiquidity),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct InterestBank {
+        address owner;
+        /// Continuously compounded interest per second, WAD.
+        uint256 rate_per_second;
+        /// Growth of one unit deposited at launch, WAD. Only ever increases.
+        uint256 index;
+        uint256 last_accrual;
+        /// Sum of all scaled balances.
+        uint256 total_scaled;
+        mapping(address => uint256) scaled_balances;
+        /// Deposited amount not yet withdrawn; withdrawals draw on interest first.
+        mapping(address => uint256) principals;
+    }
+}
+
+#[public]
+impl InterestBank {
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        owner: Address,
+        rate_per_second: U256,
+    ) -> Result<(), InterestBankError> {
+        check_rate(rate_per_second)?;
+        self.owner.set(owner);
+        self.rate_per_second.set(rate_per_second);
+        self.index.set(WAD);
+        self.last_accrual
+            .set(U256::from(self.vm().block_timestamp()));
+        Ok(())
+    }
+
+    /// Deposits the attached ETH and returns the caller's new balance.
+    #[payable]
+    pub fn deposit(&mut self) -> Result<U256, InterestBankError> {
+        let amount = self.vm().msg_value();
+        if amount.is_zero() {
+            return Err(InterestBankError::ZeroAmount(ZeroAmount {}));
+        }
+        let index = self.accrue();
+        let account = self.vm().msg_sender();
+        // Rounds down, so the dust stays with the bank rather than the depositor.
+        let minted = div_wad(amount, index);
+        let scaled = self.scaled_balances.get(account) + minted;
+        self.scaled_balances.insert(account, scaled);
+        self.total_scaled.set(self.total_scaled.get() + minted);
+        let principal = self.principals.get(account) + amount;
+        self.principals.insert(account, principal);
+        log(self.vm(), Deposited { account, amount });
+        Ok(mul_wad(scaled, index))
+    }
+
+    /// Withdraws `amount` of principal plus interest and returns the remaining balance.
+    pub fn withdraw(&mut self, amount: U256) -> Result<U256, InterestBankError> {
+        if amount.is_zero() {
+            return Err(InterestBankError::ZeroAmount(ZeroAmount {}));
+        }
+        let index = self.accrue();
+        let account = self.vm().msg_sender();
+        let scaled = self.scaled_balances.get(account);
+        let balance = mul_wad(scaled, index);
+        if amount > balance {
+            return Err(InterestBankError::InsufficientBalance(
+                InsufficientBalance {
+                    balance,
+                    requested: amount,
+                },
+            ));
+        }
+        let available = self.vm().balance(self.vm().contract_address());
+        if available < amount {
+            return Err(InterestBankError::InsufficientLiquidity(
+                InsufficientLiquidity {
+                    available,
+                    requested: amount,
+                },
+            ));
+        }
+
+        // Burning rounds up so a withdrawal can never take more than it burns.
+        let burned = div_wad_up(amount, index).min(scaled);
+        let remaining_scaled = scaled - burned;
+        self.scaled_balances.insert(account, remaining_scaled);
+        self.total_scaled.set(self.total_scaled.get() - burned);
+        let remaining = mul_wad(remaining_scaled, index);
+        let principal = self.principals.get(account).min(remaining);
+        self.principals.insert(account, principal);
+
+        self.vm().transfer_eth(account, amount).map_err(|_| {
+            InterestBankError::TransferFailed(TransferFailed {
+                to: account,
+                amount,
+            })
+        })?;
+        log(self.vm(), Withdrawn { account, amount });
+        Ok(remaining)
+    }
+
+    /// Adds ETH to the reserves interest is paid from. Anyone may fund it.
+    #[payable]
+    pub fn fund(&mut self) {
+        let from = self.vm().msg_sender();
+        let amount = self.vm().msg_value();
+        log(self.vm(), Funded { from, amount });
+    }
+
+    /// Changes the rate going forward; interest up to now accrues at the old rate.
+    pub fn set_rate(&mut self, rate_per_second: U256) -> Result<(), InterestBankError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(InterestBankError::NotOwner(NotOwner { caller }));
+        }
+        check_rate(rate_per_second)?;
+        self.accrue();
+        self.rate_per_second.set(rate_per_second);
+        log(
+            self.vm(),
+            RateUpdated {
+                ratePerSecond: rate_per_second,
+            },
+        );
+        Ok(())
+    }
+
+    /// Current balance of `account`, including interest accrued up to this block.
+    pub fn balance_of(&self, account: Address) -> U256 {
+        mul_wad(self.scaled_balances.get(account), self.current_index())
+    }
+
+    /// Deposited amount `account` has not withdrawn yet.
+    pub fn principal_of(&self, account: Address) -> U256 {
+        self.principals.get(account)
+    }
+
+    /// Interest `account` has earned on top of its principal.
+    pub fn accrued_interest(&self, account: Address) -> U256 {
+        self.balance_of(account)
+            .saturating_sub(self.principals.get(account))
+    }
+
+    /// Sum of all balances, including accrued interest.
+    pub fn total_deposits(&self) -> U256 {
+        mul_wad(self.total_scaled.get(), self.current_index())
+    }
+
+    /// Growth index as of this block, WAD.
+    pub fn current_index(&self) -> U256 {
+        let now = U256::from(self.vm().block_timestamp());
+        let elapsed = now.saturating_sub(self.last_accrual.get());
+        grow(self.index.get(), self.rate_per_second.get() * elapsed)
+    }
+
+    pub fn rate_per_second(&self) -> U256 {
+        self.rate_per_second.get()
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+}
+
+impl InterestBank {
+    /// Brings the stored index up to date and returns it.
+    fn accrue(&mut self) -> U256 {
+        let index = self.current_index();
+        self.index.set(index);
+        self.last_accrual
+            .set(U256::from(self.vm().block_timestamp()));
+        index
+    }
+}
+
+/// `index * e^exponent`, applied in `MAX_EXP_INPUT` steps for long idle periods.
+fn grow(mut index: U256, mut exponent: U256) -> U256 {
+    while exponent > MAX_EXP_INPUT {
+        index = mul_wad(index, exp_wad(MAX_EXP_INPUT).expect("within range"));
+        exponent -= MAX_EXP_INPUT;
+    }
+    mul_wad(index, exp_wad(exponent).expect("within range"))
+}
+
+fn check_rate(rate: U256) -> Result<(), InterestBankError> {
+    let max = U256::from(MAX_RATE_PER_SECOND);
+    if rate > max {
+        return Err(InterestBankError::RateTooHigh(RateTooHigh { rate, max }));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::new([0x01; 20]);
+    const ALICE: Address = Address::new([0x02; 20]);
+    const BOB: Address = Address::new([0x03; 20]);
+    const START: u64 = 1_000;
+    const YEAR: u64 = 31_536_000;
+    /// About 5% a year.
+    const RATE: u64 = 1_585_489_599;
+    const ETHER: u64 = 1_000_000_000_000_000_000;
+
+    fn setup() -> (TestVM, InterestBank) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(START);
+        let mut contract = InterestBank::from(&vm);
+        contract.constructor(OWNER, U256::from(RATE)).unwrap();
+        (vm, contract)
+    }
+
+    fn deposit(vm: &TestVM, contract: &mut InterestBank, who: Address, amount: U256) {
+        vm.set_sender(who);
+        vm.set_value(amount);
+        contract.deposit().unwrap();
+        vm.set_value(U256::ZERO);
+        let balance = vm.balance(vm.contract_address());
+        vm.set_balance(vm.contract_address(), balance + amount);
+    }
+
+    /// Asserts `actual` is within `tolerance` wei of `expected`.
+    fn assert_close(actual: U256, expected: u128, tolerance: u128) {
+        let expected = U256::from(expected);
+        let diff = actual.max(expected) - actual.min(expected);
+        assert!(
+            diff <= U256::from(tolerance),
+            "{actual} is not within {tolerance} of {expected}"
+        );
+    }
+
+    #[test]
+    fn test_exp_wad_matches_known_values() {
+        assert_eq!(exp_wad(U256::ZERO), Some(WAD));
+        assert_close(exp_wad(WAD).unwrap(), 2_718_281_828_459_045_235, 100);
+        assert_close(
+            exp_wad(MAX_EXP_INPUT).unwrap(),
+            22_026_465_794_806_716_516_957,
+            1_000_000,
+        );
+        assert_eq!(exp_wad(MAX_EXP_INPUT + U256::from(1)), None);
+    }
+
+    #[test]
+    fn test_interest_compounds_continuously() {
+        let (vm, mut contract) = setup();
+        deposit(&vm, &mut contract, ALICE, U256::from(ETHER));
+        vm.set_block_timestamp(START + YEAR);
+        // 1 ETH * e^0.05 = 1.051271096... ETH
+        assert_close(
+            contract.balance_of(ALICE),
+            1_051_271_096_376_024_039,
+            1_000_000_000,
+        );
+        assert_eq!(contract.principal_of(ALICE), U256::from(ETHER));
+        assert_eq!(
+            contract.accrued_interest(ALICE),
+            contract.balance_of(ALICE) - U256::from(ETHER)
+        );
+    }
+
+    #[test]
+    fn test_late_depositor_earns_only_from_entry() {
+        let (vm, mut contract) = setup();
+        deposit(&vm, &mut contract, ALICE, U256::from(ETHER));
+        vm.set_block_timestamp(START + YEAR);
+        deposit(&vm, &mut contract, BOB, U256::from(ETHER));
+        assert_close(contract.balance_of(BOB), ETHER as u128, 2);
+
+        vm.set_block_timestamp(START + 2 * YEAR);
+        let alice = contract.balance_of(ALICE);
+        let bob = contract.balance_of(BOB);
+        assert!(alice > bob);
+        assert_close(bob, 1_051_271_096_376_024_039, 1_000_000_000);
+        assert_close(contract.total_deposits(), (alice + bob).to(), 2);
+    }
+
+    #[test]
+    fn test_withdraw_draws_on_interest_first() {
+        let (vm, mut contract) = setup();
+        deposit(&vm, &mut contract, ALICE, U256::from(ETHER));
+        vm.set_block_timestamp(START + YEAR);
+        vm.set_balance(vm.contract_address(), U256::from(2 * ETHER));
+
+        vm.set_sender(ALICE);
+        let interest = contract.accrued_interest(ALICE);
+        contract.withdraw(interest).unwrap();
+        assert_eq!(vm.balance(ALICE), interest);
+        assert_close(contract.balance_of(ALICE), ETHER as u128, 2);
+        assert_eq!(contract.principal_of(ALICE), contract.balance_of(ALICE));
+
+        let rest = contract.balance_of(ALICE);
+        contract.withdraw(rest).unwrap();
+        assert_eq!(contract.balance_of(ALICE), U256::ZERO);
+        assert!(matches!(
+            contract.withdraw(U256::from(1)),
+            Err(InterestBankError::InsufficientBalance(_))
+        ));
+    }
+
+    #[test]
+    fn test_withdraw_requires_reserves() {
+        let (vm, mut contract) = setup();
+        deposit(&vm, &mut contract, ALICE, U256::from(ETHER));
+        vm.set_block_timestamp(START + YEAR);
+        let balance = contract.balance_of(ALICE);
+        assert!(matches!(
+            contract.withdraw(balance),
+            Err(InterestBankError::InsufficientLiquidity(_))
+        ));
+        contract.withdraw(U256::from(ETHER)).unwrap();
+    }
+
+    #[test]
+    fn test_rate_changes_apply_going_forward() {
+        let (vm, mut contract) = setup();
+        deposit(&vm, &mut contract, ALICE, U256::from(ETHER));
+        assert!(matches!(
+            contract.set_rate(U256::ZERO),
+            Err(InterestBankError::NotOwner(_))
+        ));
+
+        vm.set_sender(OWNER);
+        assert!(matches!(
+            contract.set_rate(U256::from(MAX_RATE_PER_SECOND + 1)),
+            Err(InterestBankError::RateTooHigh(_))
+        ));
+        vm.set_block_timestamp(START + YEAR);
+        contract.set_rate(U256::ZERO).unwrap();
+        let frozen = contract.balance_of(ALICE);
+        vm.set_block_timestamp(START + 5 * YEAR);
+        assert_eq!(contract.balance_of(ALICE), frozen);
+    }
+}
@@ -0,0 +1,48 @@
+//! WAD fixed-point helpers: values are `U256`s scaled by `1e18`.
+//!
+//! `exp_wad` sums the Taylor series of `e^x` term by term. Each term is the
+//! previous one times `x / n`, so the loop stops as soon as a term rounds to
+//! zero, which for inputs up to `MAX_EXP_INPUT` takes fewer than 64 steps.
+
+use stylus_sdk::alloy_primitives::{uint, U256};
+
+/// `1.0` in WAD fixed point.
+pub const WAD: U256 = uint!(1_000_000_000_000_000_000_U256);
+
+/// Largest exponent `exp_wad` accepts: `e^10` is about `22026`.
+pub const MAX_EXP_INPUT: U256 = uint!(10_000_000_000_000_000_000_U256);
+
+/// Series terms evaluated before `exp_wad` gives up.
+const MAX_TERMS: u64 = 64;
+
+/// `a * b` for two WAD values, rounded down.
+pub fn mul_wad(a: U256, b: U256) -> U256 {
+    a * b / WAD
+}
+
+/// `a / b` for two WAD values, rounded down.
+pub fn div_wad(a: U256, b: U256) -> U256 {
+    a * WAD / b
+}
+
+/// `a / b` for two WAD values, rounded up.
+pub fn div_wad_up(a: U256, b: U256) -> U256 {
+    (a * WAD).div_ceil(b)
+}
+
+/// `e^x` for a non-negative WAD exponent no larger than `MAX_EXP_INPUT`.
+pub fn exp_wad(x: U256) -> Option<U256> {
+    if x > MAX_EXP_INPUT {
+        return None;
+    }
+    let mut sum = WAD;
+    let mut term = WAD;
+    for n in 1..=MAX_TERMS {
+        term = term * x / (WAD * U256::from(n));
+        if term.is_zero() {
+            return Some(sum);
+        }
+        sum += term;
+    }
+    None
+}
@@ -0,0 +1,411 @@
+//!
+//! Stylus Charity Matching
+//!
+//! The owner registers causes, each paying out to a verified beneficiary
+//! address. Sponsors escrow ETH in a shared matching pool with `fund_matching`,
+//! and every donation to a cause is matched 1:1 from that pool until it runs
+//! dry; a donation larger than what is left is only matched partially.
+//!
+//! Each cause tracks what donors gave, what the pool matched and what the
+//! beneficiary has not withdrawn yet. It also keeps a leaderboard of its
+//! `MAX_LEADERBOARD` biggest donors, sorted on every donation, so the top of
+//! the list never needs an off-chain index.
+//!
+//! Matching funds are escrowed for good: sponsors cannot take them back.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    prelude::*,
+};
+
+/// Donors listed on each cause's leaderboard.
+const MAX_LEADERBOARD: usize = 10;
+
+sol! {
+    event CauseRegistered(uint256 indexed causeId, address beneficiary);
+    event MatchingFunded(address indexed sponsor, uint256 amount);
+    event Donated(uint256 indexed causeId, address indexed donor, uint256 amount, uint256 matched);
+    event Withdrawn(uint256 indexed causeId, address indexed beneficiary, uint256 amount);
+
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error InvalidBeneficiary();
+    #[derive(Debug)]
+    error UnknownCause(uint256 causeId);
+    #[derive(Debug)]
+    error ZeroAmount();
+    #[derive(Debug)]
+    error NotBeneficiary(address caller);
+    #[derive(Debug)]
+    error NothingToWithdraw();
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum CharityMatchingError {
+    NotOwner(NotOwner),
+    InvalidBeneficiary(InvalidBeneficiary),
+    UnknownCause(UnknownCause),
+    ZeroAmount(ZeroAmount),
+    NotBeneficiary(NotBeneficiary),
+    NothingToWithdraw(NothingToWithdraw),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    pub struct Cause {
+        address beneficiary;
+        /// Total given by donors.
+        uint256 raised;
+        /// Total added from the matching pool.
+        uint256 matched;
+        /// Raised plus matched, minus what the beneficiary has withdrawn.
+        uint256 available;
+        mapping(address => uint256) donations;
+        /// Biggest donors first, at most `MAX_LEADERBOARD` entries.
+        address[] top_donors;
+    }
+
+    #[entrypoint]
+    pub struct CharityMatching {
+        address owner;
+        uint256 matching_pool;
+        /// Causes are numbered from 0 in registration order.
+        uint256 cause_count;
+        mapping(uint256 => Cause) causes;
+    }
+}
+
+#[public]
+impl CharityMatching {
+    #[constructor]
+    pub fn constructor(&mut self, owner: Address) {
+        self.owner.set(owner);
+    }
+
+    /// Registers a cause paying out to `beneficiary` and returns its id.
+    pub fn register_cause(&mut self, beneficiary: Address) -> Result<U256, CharityMatchingError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(CharityMatchingError::NotOwner(NotOwner { caller }));
+        }
+        if beneficiary.is_zero() {
+            return Err(CharityMatchingError::InvalidBeneficiary(
+                InvalidBeneficiary {},
+            ));
+        }
+        let cause_id = self.cause_count.get();
+        self.cause_count.set(cause_id + U256::from(1));
+        self.causes.setter(cause_id).beneficiary.set(beneficiary);
+        log(
+            self.vm(),
+            CauseRegistered {
+                causeId: cause_id,
+                beneficiary,
+            },
+        );
+        Ok(cause_id)
+    }
+
+    /// Escrows the attached ETH for matching future donations.
+    #[payable]
+    pub fn fund_matching(&mut self) -> Result<(), CharityMatchingError> {
+        let amount = self.vm().msg_value();
+        if amount.is_zero() {
+            return Err(CharityMatchingError::ZeroAmount(ZeroAmount {}));
+        }
+        self.matching_pool.set(self.matching_pool.get() + amount);
+        let sponsor = self.vm().msg_sender();
+        log(self.vm(), MatchingFunded { sponsor, amount });
+        Ok(())
+    }
+
+    /// Donates the attached ETH to `cause_id` and returns how much of it was matched.
+    #[payable]
+    pub fn donate(&mut self, cause_id: U256) -> Result<U256, CharityMatchingError> {
+        self.check_cause(cause_id)?;
+        let amount = self.vm().msg_value();
+        if amount.is_zero() {
+            return Err(CharityMatchingError::ZeroAmount(ZeroAmount {}));
+        }
+        let donor = self.vm().msg_sender();
+        let pool = self.matching_pool.get();
+        let matched = amount.min(pool);
+        self.matching_pool.set(pool - matched);
+
+        let mut cause = self.causes.setter(cause_id);
+        let raised = cause.raised.get() + amount;
+        cause.raised.set(raised);
+        let total_matched = cause.matched.get() + matched;
+        cause.matched.set(total_matched);
+        let available = cause.available.get() + amount + matched;
+        cause.available.set(available);
+        let donated = cause.donations.get(donor) + amount;
+        cause.donations.insert(donor, donated);
+        self.rank_donor(cause_id, donor, donated);
+
+        log(
+            self.vm(),
+            Donated {
+                causeId: cause_id,
+                donor,
+                amount,
+                matched,
+            },
+        );
+        Ok(matched)
+    }
+
+    /// Pays out everything the cause has available. Beneficiary only.
+    pub fn withdraw(&mut self, cause_id: U256) -> Result<U256, CharityMatchingError> {
+        self.check_cause(cause_id)?;
+        let caller = self.vm().msg_sender();
+        let mut cause = self.causes.setter(cause_id);
+        if cause.beneficiary.get() != caller {
+            return Err(CharityMatchingError::NotBeneficiary(NotBeneficiary {
+                caller,
+            }));
+        }
+        let amount = cause.available.get();
+        if amount.is_zero() {
+            return Err(CharityMatchingError::NothingToWithdraw(
+                NothingToWithdraw {},
+            ));
+        }
+        cause.available.set(U256::ZERO);
+        self.vm().transfer_eth(caller, amount).map_err(|_| {
+            CharityMatchingError::TransferFailed(TransferFailed { to: caller, amount })
+        })?;
+        log(
+            self.vm(),
+            Withdrawn {
+                causeId: cause_id,
+                beneficiary: caller,
+                amount,
+            },
+        );
+        Ok(amount)
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    pub fn matching_pool(&self) -> U256 {
+        self.matching_pool.get()
+    }
+
+    pub fn cause_count(&self) -> U256 {
+        self.cause_count.get()
+    }
+
+    /// Returns `(beneficiary, raised, matched, available)`.
+    pub fn cause(
+        &self,
+        cause_id: U256,
+    ) -> Result<(Address, U256, U256, U256), CharityMatchingError> {
+        self.check_cause(cause_id)?;
+        let cause = self.causes.getter(cause_id);
+        Ok((
+            cause.beneficiary.get(),
+            cause.raised.get(),
+            cause.matched.get(),
+            cause.available.get(),
+        ))
+    }
+
+    pub fn donation_of(&self, cause_id: U256, donor: Address) -> U256 {
+        self.causes.getter(cause_id).donations.get(donor)
+    }
+
+    /// The cause's biggest donors and their totals, biggest first.
+    pub fn top_donors(&self, cause_id: U256) -> Result<Vec<(Address, U256)>, CharityMatchingError> {
+        self.check_cause(cause_id)?;
+        let cause = self.causes.getter(cause_id);
+        Ok((0..cause.top_donors.len())
+            .filter_map(|i| cause.top_donors.get(i))
+            .map(|donor| (donor, cause.donations.get(donor)))
+            .collect())
+    }
+}
+
+impl CharityMatching {
+    fn check_cause(&self, cause_id: U256) -> Result<(), CharityMatchingError> {
+        if cause_id >= self.cause_count.get() {
+            return Err(CharityMatchingError::UnknownCause(UnknownCause {
+                causeId: cause_id,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Moves `donor`, whose running total is now `donated`, to its place on the leaderboard.
+    fn rank_donor(&mut self, cause_id: U256, donor: Address, donated: U256) {
+        let mut cause = self.causes.setter(cause_id);
+        let len = cause.top_donors.len();
+        let mut slot = match (0..len).find(|&i| cause.top_donors.get(i) == Some(donor)) {
+            Some(i) => i,
+            None if len < MAX_LEADERBOARD => {
+                cause.top_donors.push(donor);
+                len
+            }
+            None => {
+                let last = cause.top_donors.get(len - 1).expect("leaderboard is full");
+                if cause.donations.get(last) >= donated {
+                    return;
+                }
+                cause
+                    .top_donors
+                    .setter(len - 1)
+                    .expect("in bounds")
+                    .set(donor);
+                len - 1
+            }
+        };
+        // Totals only grow, so the donor can only move up.
+        while slot > 0 {
+            let above = cause.top_donors.get(slot - 1).expect("in bounds");
+            if cause.donations.get(above) >= donated {
+                break;
+            }
+            cause.top_donors.setter(slot).expect("in bounds").set(above);
+            cause
+                .top_donors
+                .setter(slot - 1)
+                .expect("in bounds")
+                .set(donor);
+            slot -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::new([0x01; 20]);
+    const SPONSOR: Address = Address::new([0x02; 20]);
+    const CHARITY: Address = Address::new([0x03; 20]);
+
+    fn donor(i: u8) -> Address {
+        Address::new([0x10 + i; 20])
+    }
+
+    /// One cause, id 0, paying out to `CHARITY`.
+    fn setup() -> (TestVM, CharityMatching) {
+        let vm = TestVM::default();
+        let mut contract = CharityMatching::from(&vm);
+        contract.constructor(OWNER);
+        vm.set_sender(OWNER);
+        contract.register_cause(CHARITY).unwrap();
+        (vm, contract)
+    }
+
+    fn pay(vm: &TestVM, from: Address, amount: u64) {
+        vm.set_sender(from);
+        vm.set_value(U256::from(amount));
+        let balance = vm.balance(vm.contract_address());
+        vm.set_balance(vm.contract_address(), balance + U256::from(amount));
+    }
+
+    fn donate(vm: &TestVM, contract: &mut CharityMatching, from: Address, amount: u64) -> U256 {
+        pay(vm, from, amount);
+        let matched = contract.donate(U256::ZERO).unwrap();
+        vm.set_value(U256::ZERO);
+        matched
+    }
+
+    #[test]
+    fn test_donations_matched_until_pool_runs_out() {
+        let (vm, mut contract) = setup();
+        pay(&vm, SPONSOR, 150);
+        contract.fund_matching().unwrap();
+
+        assert_eq!(donate(&vm, &mut contract, donor(0), 100), U256::from(100));
+        assert_eq!(donate(&vm, &mut contract, donor(1), 100), U256::from(50));
+        assert_eq!(donate(&vm, &mut contract, donor(2), 100), U256::ZERO);
+        assert_eq!(contract.matching_pool(), U256::ZERO);
+        assert_eq!(
+            contract.cause(U256::ZERO).unwrap(),
+            (CHARITY, U256::from(300), U256::from(150), U256::from(450))
+        );
+    }
+
+    #[test]
+    fn test_only_beneficiary_withdraws() {
+        let (vm, mut contract) = setup();
+        donate(&vm, &mut contract, donor(0), 80);
+        vm.set_sender(donor(0));
+        assert!(matches!(
+            contract.withdraw(U256::ZERO),
+            Err(CharityMatchingError::NotBeneficiary(_))
+        ));
+
+        vm.set_sender(CHARITY);
+        assert_eq!(contract.withdraw(U256::ZERO).unwrap(), U256::from(80));
+        assert_eq!(vm.balance(CHARITY), U256::from(80));
+        assert!(matches!(
+            contract.withdraw(U256::ZERO),
+            Err(CharityMatchingError::NothingToWithdraw(_))
+        ));
+        assert_eq!(contract.cause(U256::ZERO).unwrap().1, U256::from(80));
+    }
+
+    #[test]
+    fn test_leaderboard_sorted_and_capped() {
+        let (vm, mut contract) = setup();
+        for i in 0..12u8 {
+            donate(&vm, &mut contract, donor(i), 10 + u64::from(i));
+        }
+        let board = contract.top_donors(U256::ZERO).unwrap();
+        assert_eq!(board.len(), MAX_LEADERBOARD);
+        assert_eq!(board[0], (donor(11), U256::from(21)));
+        assert_eq!(board[9], (donor(2), U256::from(12)));
+
+        // A repeat donation moves an existing entry; a dropped donor can climb back on.
+        donate(&vm, &mut contract, donor(5), 10);
+        donate(&vm, &mut contract, donor(0), 20);
+        let board = contract.top_donors(U256::ZERO).unwrap();
+        assert_eq!(board[0], (donor(0), U256::from(30)));
+        assert_eq!(board[1], (donor(5), U256::from(25)));
+        assert_eq!(board.len(), MAX_LEADERBOARD);
+        assert!(!board.iter().any(|(who, _)| *who == donor(2)));
+        assert_eq!(contract.donation_of(U256::ZERO, donor(2)), U256::from(12));
+    }
+
+    #[test]
+    fn test_rejects_invalid_calls() {
+        let (vm, mut contract) = setup();
+        assert!(matches!(
+            contract.register_cause(Address::ZERO),
+            Err(CharityMatchingError::InvalidBeneficiary(_))
+        ));
+        assert!(matches!(
+            contract.donate(U256::ZERO),
+            Err(CharityMatchingError::ZeroAmount(_))
+        ));
+        vm.set_value(U256::from(1));
+        assert!(matches!(
+            contract.donate(U256::from(1)),
+            Err(CharityMatchingError::UnknownCause(_))
+        ));
+        vm.set_sender(SPONSOR);
+        assert!(matches!(
+            contract.register_cause(CHARITY),
+            Err(CharityMatchingError::NotOwner(_))
+        ));
+    }
+}
@@ -0,0 +1,527 @@
+//!
+//! Stylus Bounty Board
+//!
+//! Posters fund tasks with ETH and a deadline. A worker claims an open task,
+//! does the work off-chain and submits a hash of the proof (a commit, a file,
+//! a URL) before the deadline. The poster then approves, paying the bounty,
+//! or disputes, handing the decision to the arbiter set at deployment.
+//!
+//! Every task is a small state machine:
+//!
+//! ```text
+//! Open -> Claimed -> Submitted -> Paid
+//!  |        |            `-> Disputed -> Paid | Refunded
+//!  `--------`-> Refunded (open, or claimed and past the deadline)
+//! ```
+//!
+//! A poster who never reviews cannot stall the worker: once `REVIEW_PERIOD`
+//! has passed since the submission, the worker can collect the bounty alone.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, B256, U256, U8},
+    prelude::*,
+};
+
+/// Seconds a poster has to approve or dispute a submission.
+const REVIEW_PERIOD: u64 = 3 * 24 * 60 * 60;
+
+pub const STATUS_OPEN: u8 = 1;
+pub const STATUS_CLAIMED: u8 = 2;
+pub const STATUS_SUBMITTED: u8 = 3;
+pub const STATUS_DISPUTED: u8 = 4;
+pub const STATUS_PAID: u8 = 5;
+pub const STATUS_REFUNDED: u8 = 6;
+
+sol! {
+    event TaskPosted(uint256 indexed taskId, address indexed poster, uint256 bounty, uint256 deadline);
+    event TaskClaimed(uint256 indexed taskId, address indexed worker);
+    event ProofSubmitted(uint256 indexed taskId, bytes32 proofHash);
+    event TaskDisputed(uint256 indexed taskId);
+    event BountyPaid(uint256 indexed taskId, address indexed worker, uint256 amount);
+    event BountyRefunded(uint256 indexed taskId, address indexed poster, uint256 amount);
+
+    #[derive(Debug)]
+    error UnknownTask(uint256 taskId);
+    #[derive(Debug)]
+    error ZeroBounty();
+    #[derive(Debug)]
+    error InvalidDeadline(uint256 deadline);
+    #[derive(Debug)]
+    error WrongStatus(uint8 status);
+    #[derive(Debug)]
+    error NotPoster(address caller);
+    #[derive(Debug)]
+    error NotWorker(address caller);
+    #[derive(Debug)]
+    error NotArbiter(address caller);
+    #[derive(Debug)]
+    error DeadlinePassed(uint256 deadline);
+    #[derive(Debug)]
+    error DeadlineNotReached(uint256 deadline);
+    #[derive(Debug)]
+    error ReviewPending(uint256 until);
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum BountyBoardError {
+    UnknownTask(UnknownTask),
+    ZeroBounty(ZeroBounty),
+    InvalidDeadline(InvalidDeadline),
+    WrongStatus(WrongStatus),
+    NotPoster(NotPoster),
+    NotWorker(NotWorker),
+    NotArbiter(NotArbiter),
+    DeadlinePassed(DeadlinePassed),
+    DeadlineNotReached(DeadlineNotReached),
+    ReviewPending(ReviewPending),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    pub struct Task {
+        address poster;
+        address worker;
+        uint256 bounty;
+        uint256 deadline;
+        bytes32 proof_hash;
+        uint256 submitted_at;
+        /// One of the `STATUS_*` constants.
+        uint8 status;
+    }
+
+    #[entrypoint]
+    pub struct BountyBoard {
+        /// Settles disputed tasks.
+        address arbiter;
+        /// Tasks are numbered from 0 in posting order.
+        uint256 task_count;
+        mapping(uint256 => Task) tasks;
+    }
+}
+
+#[public]
+impl BountyBoard {
+    #[constructor]
+    pub fn constructor(&mut self, arbiter: Address) {
+        self.arbiter.set(arbiter);
+    }
+
+    /// Posts a task funded with the attached ETH and returns its id.
+    #[payable]
+    pub fn post_task(&mut self, deadline: U256) -> Result<U256, BountyBoardError> {
+        let bounty = self.vm().msg_value();
+        if bounty.is_zero() {
+            return Err(BountyBoardError::ZeroBounty(ZeroBounty {}));
+        }
+        if deadline <= self.now() {
+            return Err(BountyBoardError::InvalidDeadline(InvalidDeadline {
+                deadline,
+            }));
+        }
+        let poster = self.vm().msg_sender();
+        let task_id = self.task_count.get();
+        self.task_count.set(task_id + U256::from(1));
+        let mut task = self.tasks.setter(task_id);
+        task.poster.set(poster);
+        task.bounty.set(bounty);
+        task.deadline.set(deadline);
+        task.status.set(U8::from(STATUS_OPEN));
+        log(
+            self.vm(),
+            TaskPosted {
+                taskId: task_id,
+                poster,
+                bounty,
+                deadline,
+            },
+        );
+        Ok(task_id)
+    }
+
+    /// Assigns an open task to the caller.
+    pub fn claim_task(&mut self, task_id: U256) -> Result<(), BountyBoardError> {
+        self.expect_status(task_id, STATUS_OPEN)?;
+        self.before_deadline(task_id)?;
+        let worker = self.vm().msg_sender();
+        let mut task = self.tasks.setter(task_id);
+        task.worker.set(worker);
+        task.status.set(U8::from(STATUS_CLAIMED));
+        log(
+            self.vm(),
+            TaskClaimed {
+                taskId: task_id,
+                worker,
+            },
+        );
+        Ok(())
+    }
+
+    /// Records the hash of the worker's proof of completion.
+    pub fn submit_proof(
+        &mut self,
+        task_id: U256,
+        proof_hash: B256,
+    ) -> Result<(), BountyBoardError> {
+        self.expect_status(task_id, STATUS_CLAIMED)?;
+        self.only_worker(task_id)?;
+        self.before_deadline(task_id)?;
+        let now = self.now();
+        let mut task = self.tasks.setter(task_id);
+        task.proof_hash.set(proof_hash);
+        task.submitted_at.set(now);
+        task.status.set(U8::from(STATUS_SUBMITTED));
+        log(
+            self.vm(),
+            ProofSubmitted {
+                taskId: task_id,
+                proofHash: proof_hash,
+            },
+        );
+        Ok(())
+    }
+
+    /// Accepts the submission and pays the worker. Poster only.
+    pub fn approve(&mut self, task_id: U256) -> Result<(), BountyBoardError> {
+        self.expect_status(task_id, STATUS_SUBMITTED)?;
+        self.only_poster(task_id)?;
+        self.pay_worker(task_id)
+    }
+
+    /// Rejects the submission, leaving the outcome to the arbiter. Poster only.
+    pub fn dispute(&mut self, task_id: U256) -> Result<(), BountyBoardError> {
+        self.expect_status(task_id, STATUS_SUBMITTED)?;
+        self.only_poster(task_id)?;
+        self.tasks
+            .setter(task_id)
+            .status
+            .set(U8::from(STATUS_DISPUTED));
+        log(self.vm(), TaskDisputed { taskId: task_id });
+        Ok(())
+    }
+
+    /// Pays the worker once the poster has let the review period lapse.
+    pub fn claim_payment(&mut self, task_id: U256) -> Result<(), BountyBoardError> {
+        self.expect_status(task_id, STATUS_SUBMITTED)?;
+        self.only_worker(task_id)?;
+        let until = self.tasks.getter(task_id).submitted_at.get() + U256::from(REVIEW_PERIOD);
+        if self.now() < until {
+            return Err(BountyBoardError::ReviewPending(ReviewPending { until }));
+        }
+        self.pay_worker(task_id)
+    }
+
+    /// Settles a disputed task in favour of the worker or the poster. Arbiter only.
+    pub fn resolve_dispute(
+        &mut self,
+        task_id: U256,
+        pay_worker: bool,
+    ) -> Result<(), BountyBoardError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.arbiter.get() {
+            return Err(BountyBoardError::NotArbiter(NotArbiter { caller }));
+        }
+        self.expect_status(task_id, STATUS_DISPUTED)?;
+        if pay_worker {
+            self.pay_worker(task_id)
+        } else {
+            self.refund_poster(task_id)
+        }
+    }
+
+    /// Takes back the bounty of a task nobody claimed, or whose worker missed the deadline.
+    pub fn cancel(&mut self, task_id: U256) -> Result<(), BountyBoardError> {
+        let status = self.status_of(task_id)?;
+        self.only_poster(task_id)?;
+        match status {
+            STATUS_OPEN => {}
+            STATUS_CLAIMED => {
+                let deadline = self.tasks.getter(task_id).deadline.get();
+                if self.now() < deadline {
+                    return Err(BountyBoardError::DeadlineNotReached(DeadlineNotReached {
+                        deadline,
+                    }));
+                }
+            }
+            _ => return Err(BountyBoardError::WrongStatus(WrongStatus { status })),
+        }
+        self.refund_poster(task_id)
+    }
+
+    pub fn arbiter(&self) -> Address {
+        self.arbiter.get()
+    }
+
+    pub fn task_count(&self) -> U256 {
+        self.task_count.get()
+    }
+
+    /// Returns `(poster, worker, bounty, deadline, status, proof_hash)`.
+    pub fn task(
+        &self,
+        task_id: U256,
+    ) -> Result<(Address, Address, U256, U256, u8, B256), BountyBoardError> {
+        let status = self.status_of(task_id)?;
+        let task = self.tasks.getter(task_id);
+        Ok((
+            task.poster.get(),
+            task.worker.get(),
+            task.bounty.get(),
+            task.deadline.get(),
+            status,
+            task.proof_hash.get(),
+        ))
+    }
+}
+
+impl BountyBoard {
+    fn now(&self) -> U256 {
+        U256::from(self.vm().block_timestamp())
+    }
+
+    fn status_of(&self, task_id: U256) -> Result<u8, BountyBoardError> {
+        if task_id >= self.task_count.get() {
+            return Err(BountyBoardError::UnknownTask(UnknownTask {
+                taskId: task_id,
+            }));
+        }
+        Ok(self.tasks.getter(task_id).status.get().to())
+    }
+
+    fn expect_status(&self, task_id: U256, expected: u8) -> Result<(), BountyBoardError> {
+        let status = self.status_of(task_id)?;
+        if status != expected {
+            return Err(BountyBoardError::WrongStatus(WrongStatus { status }));
+        }
+        Ok(())
+    }
+
+    fn before_deadline(&self, task_id: U256) -> Result<(), BountyBoardError> {
+        let deadline = self.tasks.getter(task_id).deadline.get();
+        if self.now() >= deadline {
+            return Err(BountyBoardError::DeadlinePassed(DeadlinePassed {
+                deadline,
+            }));
+        }
+        Ok(())
+    }
+
+    fn only_poster(&self, task_id: U256) -> Result<(), BountyBoardError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.tasks.getter(task_id).poster.get() {
+            return Err(BountyBoardError::NotPoster(NotPoster { caller }));
+        }
+        Ok(())
+    }
+
+    fn only_worker(&self, task_id: U256) -> Result<(), BountyBoardError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.tasks.getter(task_id).worker.get() {
+            return Err(BountyBoardError::NotWorker(NotWorker { caller }));
+        }
+        Ok(())
+    }
+
+    fn pay_worker(&mut self, task_id: U256) -> Result<(), BountyBoardError> {
+        let worker = self.tasks.getter(task_id).worker.get();
+        let amount = self.settle(task_id, STATUS_PAID, worker)?;
+        log(
+            self.vm(),
+            BountyPaid {
+                taskId: task_id,
+                worker,
+                amount,
+            },
+        );
+        Ok(())
+    }
+
+    fn refund_poster(&mut self, task_id: U256) -> Result<(), BountyBoardError> {
+        let poster = self.tasks.getter(task_id).poster.get();
+        let amount = self.settle(task_id, STATUS_REFUNDED, poster)?;
+        log(
+            self.vm(),
+            BountyRefunded {
+                taskId: task_id,
+                poster,
+                amount,
+            },
+        );
+        Ok(())
+    }
+
+    /// Moves the task to its final `status` and sends the bounty to `to`.
+    fn settle(&mut self, task_id: U256, status: u8, to: Address) -> Result<U256, BountyBoardError> {
+        let mut task = self.tasks.setter(task_id);
+        let amount = task.bounty.get();
+        // The final status is written first, so a reentrant call finds nothing to settle.
+        task.status.set(U8::from(status));
+        self.vm()
+            .transfer_eth(to, amount)
+            .map_err(|_| BountyBoardError::TransferFailed(TransferFailed { to, amount }))?;
+        Ok(amount)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const ARBITER: Address = Address::new([0x01; 20]);
+    const POSTER: Address = Address::new([0x02; 20]);
+    const WORKER: Address = Address::new([0x03; 20]);
+    const START: u64 = 1_000;
+    const DEADLINE: u64 = START + 500;
+    const BOUNTY: u64 = 700;
+    const PROOF: B256 = B256::new([0x77; 32]);
+
+    /// Task 0, funded with `BOUNTY` wei and due at `DEADLINE`.
+    fn setup() -> (TestVM, BountyBoard) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(START);
+        let mut contract = BountyBoard::from(&vm);
+        contract.constructor(ARBITER);
+        vm.set_sender(POSTER);
+        vm.set_value(U256::from(BOUNTY));
+        contract.post_task(U256::from(DEADLINE)).unwrap();
+        vm.set_value(U256::ZERO);
+        vm.set_balance(vm.contract_address(), U256::from(BOUNTY));
+        (vm, contract)
+    }
+
+    fn claim_and_submit(vm: &TestVM, contract: &mut BountyBoard) {
+        vm.set_sender(WORKER);
+        contract.claim_task(U256::ZERO).unwrap();
+        contract.submit_proof(U256::ZERO, PROOF).unwrap();
+    }
+
+    fn status(contract: &BountyBoard) -> u8 {
+        contract.task(U256::ZERO).unwrap().4
+    }
+
+    #[test]
+    fn test_approved_submission_pays_worker() {
+        let (vm, mut contract) = setup();
+        claim_and_submit(&vm, &mut contract);
+        assert_eq!(
+            contract.task(U256::ZERO).unwrap(),
+            (
+                POSTER,
+                WORKER,
+                U256::from(BOUNTY),
+                U256::from(DEADLINE),
+                STATUS_SUBMITTED,
+                PROOF
+            )
+        );
+        assert!(matches!(
+            contract.approve(U256::ZERO),
+            Err(BountyBoardError::NotPoster(_))
+        ));
+
+        vm.set_sender(POSTER);
+        contract.approve(U256::ZERO).unwrap();
+        assert_eq!(status(&contract), STATUS_PAID);
+        assert_eq!(vm.balance(WORKER), U256::from(BOUNTY));
+        assert!(matches!(
+            contract.approve(U256::ZERO),
+            Err(BountyBoardError::WrongStatus(_))
+        ));
+    }
+
+    #[test]
+    fn test_arbiter_settles_disputes() {
+        let (vm, mut contract) = setup();
+        claim_and_submit(&vm, &mut contract);
+        vm.set_sender(POSTER);
+        contract.dispute(U256::ZERO).unwrap();
+        assert!(matches!(
+            contract.resolve_dispute(U256::ZERO, false),
+            Err(BountyBoardError::NotArbiter(_))
+        ));
+
+        vm.set_sender(ARBITER);
+        contract.resolve_dispute(U256::ZERO, false).unwrap();
+        assert_eq!(status(&contract), STATUS_REFUNDED);
+        assert_eq!(vm.balance(POSTER), U256::from(BOUNTY));
+    }
+
+    #[test]
+    fn test_worker_collects_after_review_period() {
+        let (vm, mut contract) = setup();
+        claim_and_submit(&vm, &mut contract);
+        assert!(matches!(
+            contract.claim_payment(U256::ZERO),
+            Err(BountyBoardError::ReviewPending(_))
+        ));
+        vm.set_block_timestamp(START + REVIEW_PERIOD);
+        contract.claim_payment(U256::ZERO).unwrap();
+        assert_eq!(vm.balance(WORKER), U256::from(BOUNTY));
+    }
+
+    #[test]
+    fn test_cancel_open_or_expired_tasks() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(WORKER);
+        contract.claim_task(U256::ZERO).unwrap();
+        vm.set_sender(POSTER);
+        assert!(matches!(
+            contract.cancel(U256::ZERO),
+            Err(BountyBoardError::DeadlineNotReached(_))
+        ));
+
+        vm.set_block_timestamp(DEADLINE);
+        vm.set_sender(WORKER);
+        assert!(matches!(
+            contract.submit_proof(U256::ZERO, PROOF),
+            Err(BountyBoardError::DeadlinePassed(_))
+        ));
+        vm.set_sender(POSTER);
+        contract.cancel(U256::ZERO).unwrap();
+        assert_eq!(status(&contract), STATUS_REFUNDED);
+        assert_eq!(vm.balance(POSTER), U256::from(BOUNTY));
+    }
+
+    #[test]
+    fn test_rejects_invalid_tasks_and_actors() {
+        let (vm, mut contract) = setup();
+        assert!(matches!(
+            contract.post_task(U256::from(DEADLINE)),
+            Err(BountyBoardError::ZeroBounty(_))
+        ));
+        vm.set_value(U256::from(1));
+        assert!(matches!(
+            contract.post_task(U256::from(START)),
+            Err(BountyBoardError::InvalidDeadline(_))
+        ));
+        vm.set_value(U256::ZERO);
+        assert!(matches!(
+            contract.claim_task(U256::from(1)),
+            Err(BountyBoardError::UnknownTask(_))
+        ));
+
+        vm.set_sender(WORKER);
+        contract.claim_task(U256::ZERO).unwrap();
+        vm.set_sender(ARBITER);
+        assert!(matches!(
+            contract.submit_proof(U256::ZERO, PROOF),
+            Err(BountyBoardError::NotWorker(_))
+        ));
+        assert!(matches!(
+            contract.claim_task(U256::ZERO),
+            Err(BountyBoardError::WrongStatus(_))
+        ));
+    }
+}
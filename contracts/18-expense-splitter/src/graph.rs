@@ -0,0 +1,66 @@
+//! Debt simplification.
+//!
+//! A group's debts form a weighted directed graph: an edge `a -> b` of weight
+//! `w` means `a` owes `b` exactly `w`. Only each member's *net* position
+//! matters for settling up, so the graph can be replaced by any other graph
+//! with the same net positions. `simplify` builds one with at most `n - 1`
+//! edges: it walks creditors and debtors from largest to smallest and lets the
+//! current debtor pay the current creditor, settling at least one per step.
+
+use alloc::vec::Vec;
+use stylus_sdk::alloy_primitives::U256;
+
+/// An edge `(debtor, creditor, amount)` between member indices.
+pub type Edge = (usize, usize, U256);
+
+/// Net position of every member, as `(credit, debit)` with at most one side non-zero.
+pub fn net_positions(members: usize, edges: &[Edge]) -> Vec<(U256, U256)> {
+    let mut totals = vec![(U256::ZERO, U256::ZERO); members];
+    for &(debtor, creditor, amount) in edges {
+        totals[creditor].0 += amount;
+        totals[debtor].1 += amount;
+    }
+    totals
+        .into_iter()
+        .map(|(owed_to, owes)| {
+            if owed_to >= owes {
+                (owed_to - owes, U256::ZERO)
+            } else {
+                (U256::ZERO, owes - owed_to)
+            }
+        })
+        .collect()
+}
+
+/// Returns a graph with the same net positions as `edges` and at most `members - 1` edges.
+pub fn simplify(members: usize, edges: &[Edge]) -> Vec<Edge> {
+    let positions = net_positions(members, edges);
+    let mut creditors: Vec<(usize, U256)> = Vec::new();
+    let mut debtors: Vec<(usize, U256)> = Vec::new();
+    for (member, (credit, debit)) in positions.into_iter().enumerate() {
+        if !credit.is_zero() {
+            creditors.push((member, credit));
+        } else if !debit.is_zero() {
+            debtors.push((member, debit));
+        }
+    }
+    // Largest first; ties keep member order so the result is deterministic.
+    creditors.sort_by(|a, b| b.1.cmp(&a.1));
+    debtors.sort_by(|a, b| b.1.cmp(&a.1));
+
+    let mut simplified = Vec::new();
+    let (mut c, mut d) = (0, 0);
+    while c < creditors.len() && d < debtors.len() {
+        let amount = creditors[c].1.min(debtors[d].1);
+        simplified.push((debtors[d].0, creditors[c].0, amount));
+        creditors[c].1 -= amount;
+        debtors[d].1 -= amount;
+        if creditors[c].1.is_zero() {
+            c += 1;
+        }
+        if debtors[d].1.is_zero() {
+            d += 1;
+        }
+    }
+    simplified
+}
@@ -0,0 +1,434 @@
+//!
+//! Stylus Expense Splitter
+//!
+//! A Splitwise-style ledger for a fixed group. Members record expenses they
+//! paid for a subset of the group; each expense is split equally, adding a
+//! debt edge from every other participant to the payer. Debts between the same
+//! two members always net out, so at most one of `a -> b` and `b -> a` is set.
+//!
+//! Over time the debt graph gets tangled (Alice owes Bob, who owes Carol,
+//! who owes Alice...). `simplify()` rewrites it into at most `n - 1` edges
+//! with the same net position for every member; the algorithm lives in
+//! `graph.rs`.
+//!
+//! Debts are denominated in the group's settlement asset: ETH when the group
+//! was created with the zero token address, otherwise an ERC-20 that `settle`
+//! pulls from the debtor with `transferFrom`.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+mod graph;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use common::token::safe_transfer_from;
+use stylus_sdk::{
+    alloy_primitives::{Address, I256, U256},
+    prelude::*,
+};
+
+/// Largest group supported; `simplify` touches every ordered pair of members.
+const MAX_MEMBERS: usize = 16;
+
+sol! {
+    event ExpenseAdded(address indexed payer, uint256 amount, uint256 participants);
+    event DebtsSimplified(uint256 edges);
+    event Settled(address indexed debtor, address indexed creditor, uint256 amount);
+
+    #[derive(Debug)]
+    error InvalidGroup();
+    #[derive(Debug)]
+    error NotMember(address account);
+    #[derive(Debug)]
+    error InvalidExpense();
+    #[derive(Debug)]
+    error ExceedsDebt(uint256 owed, uint256 amount);
+    #[derive(Debug)]
+    error WrongPayment(uint256 expected, uint256 sent);
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum ExpenseSplitterError {
+    InvalidGroup(InvalidGroup),
+    NotMember(NotMember),
+    InvalidExpense(InvalidExpense),
+    ExceedsDebt(ExceedsDebt),
+    WrongPayment(WrongPayment),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct ExpenseSplitter {
+        address[] members;
+        mapping(address => bool) is_member;
+        /// Settlement token, or the zero address for ETH.
+        address token;
+        /// `debts[debtor][creditor]`.
+        mapping(address => mapping(address => uint256)) debts;
+    }
+}
+
+#[public]
+impl ExpenseSplitter {
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        members: Vec<Address>,
+        token: Address,
+    ) -> Result<(), ExpenseSplitterError> {
+        if members.len() < 2 || members.len() > MAX_MEMBERS {
+            return Err(ExpenseSplitterError::InvalidGroup(InvalidGroup {}));
+        }
+        for member in members {
+            if member.is_zero() || self.is_member.get(member) {
+                return Err(ExpenseSplitterError::InvalidGroup(InvalidGroup {}));
+            }
+            self.is_member.insert(member, true);
+            self.members.push(member);
+        }
+        self.token.set(token);
+        Ok(())
+    }
+
+    /// Records that the caller paid `amount` for `participants`, split equally between them.
+    ///
+    /// The caller may or may not be among the participants. Any remainder of
+    /// the division stays with the payer.
+    pub fn add_expense(
+        &mut self,
+        amount: U256,
+        participants: Vec<Address>,
+    ) -> Result<(), ExpenseSplitterError> {
+        let payer = self.vm().msg_sender();
+        self.only_member(payer)?;
+        if amount.is_zero() || participants.is_empty() {
+            return Err(ExpenseSplitterError::InvalidExpense(InvalidExpense {}));
+        }
+        for (i, participant) in participants.iter().enumerate() {
+            if !self.is_member.get(*participant) || participants[..i].contains(participant) {
+                return Err(ExpenseSplitterError::InvalidExpense(InvalidExpense {}));
+            }
+        }
+        let share = amount / U256::from(participants.len());
+        for participant in participants.iter().filter(|p| **p != payer) {
+            self.add_debt(*participant, payer, share);
+        }
+        log(
+            self.vm(),
+            ExpenseAdded {
+                payer,
+                amount,
+                participants: U256::from(participants.len()),
+            },
+        );
+        Ok(())
+    }
+
+    /// Replaces the debt graph with an equivalent one of at most `n - 1` edges. Members only.
+    pub fn simplify(&mut self) -> Result<U256, ExpenseSplitterError> {
+        self.only_member(self.vm().msg_sender())?;
+        let members = self.member_list();
+        let mut edges = Vec::new();
+        for (d, debtor) in members.iter().enumerate() {
+            for (c, creditor) in members.iter().enumerate() {
+                let amount = self.debts.getter(*debtor).get(*creditor);
+                if !amount.is_zero() {
+                    edges.push((d, c, amount));
+                    self.debts.setter(*debtor).delete(*creditor);
+                }
+            }
+        }
+        let simplified = graph::simplify(members.len(), &edges);
+        for &(d, c, amount) in &simplified {
+            self.debts.setter(members[d]).insert(members[c], amount);
+        }
+        let count = U256::from(simplified.len());
+        log(self.vm(), DebtsSimplified { edges: count });
+        Ok(count)
+    }
+
+    /// Pays `amount` of what the caller owes `creditor`.
+    ///
+    /// ETH groups must attach exactly `amount`; token groups must have approved this contract.
+    #[payable]
+    pub fn settle(&mut self, creditor: Address, amount: U256) -> Result<(), ExpenseSplitterError> {
+        let debtor = self.vm().msg_sender();
+        let owed = self.debts.getter(debtor).get(creditor);
+        if amount.is_zero() || amount > owed {
+            return Err(ExpenseSplitterError::ExceedsDebt(ExceedsDebt {
+                owed,
+                amount,
+            }));
+        }
+        let token = self.token.get();
+        let sent = self.vm().msg_value();
+        let expected = if token.is_zero() { amount } else { U256::ZERO };
+        if sent != expected {
+            return Err(ExpenseSplitterError::WrongPayment(WrongPayment {
+                expected,
+                sent,
+            }));
+        }
+
+        self.debts.setter(debtor).insert(creditor, owed - amount);
+        self.pay(token, debtor, creditor, amount)?;
+        log(
+            self.vm(),
+            Settled {
+                debtor,
+                creditor,
+                amount,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn members(&self) -> Vec<Address> {
+        self.member_list()
+    }
+
+    pub fn token(&self) -> Address {
+        self.token.get()
+    }
+
+    pub fn debt(&self, debtor: Address, creditor: Address) -> U256 {
+        self.debts.getter(debtor).get(creditor)
+    }
+
+    /// Every outstanding debt as `(debtor, creditor, amount)`.
+    pub fn all_debts(&self) -> Vec<(Address, Address, U256)> {
+        let members = self.member_list();
+        let mut debts = Vec::new();
+        for debtor in &members {
+            for creditor in &members {
+                let amount = self.debts.getter(*debtor).get(*creditor);
+                if !amount.is_zero() {
+                    debts.push((*debtor, *creditor, amount));
+                }
+            }
+        }
+        debts
+    }
+
+    /// What `member` is owed overall (positive) or owes overall (negative).
+    pub fn net_balance(&self, member: Address) -> I256 {
+        let mut net = I256::ZERO;
+        for other in self.member_list() {
+            let owed_to = I256::from_raw(self.debts.getter(other).get(member));
+            let owes = I256::from_raw(self.debts.getter(member).get(other));
+            net = net + owed_to - owes;
+        }
+        net
+    }
+}
+
+impl ExpenseSplitter {
+    fn only_member(&self, account: Address) -> Result<(), ExpenseSplitterError> {
+        if !self.is_member.get(account) {
+            return Err(ExpenseSplitterError::NotMember(NotMember { account }));
+        }
+        Ok(())
+    }
+
+    fn member_list(&self) -> Vec<Address> {
+        (0..self.members.len())
+            .filter_map(|i| self.members.get(i))
+            .collect()
+    }
+
+    /// Moves `amount` from `debtor` to `creditor` in the settlement asset.
+    fn pay(
+        &mut self,
+        token: Address,
+        debtor: Address,
+        creditor: Address,
+        amount: U256,
+    ) -> Result<(), ExpenseSplitterError> {
+        let failed = || {
+            ExpenseSplitterError::TransferFailed(TransferFailed {
+                to: creditor,
+                amount,
+            })
+        };
+        if token.is_zero() {
+            return self
+                .vm()
+                .transfer_eth(creditor, amount)
+                .map_err(|_| failed());
+        }
+        safe_transfer_from(self.vm(), token, debtor, creditor, amount).map_err(|_| failed())
+    }
+
+    /// Adds `amount` to what `debtor` owes `creditor`, first cancelling any debt the other way.
+    fn add_debt(&mut self, debtor: Address, creditor: Address, amount: U256) {
+        let reverse = self.debts.getter(creditor).get(debtor);
+        if reverse >= amount {
+            self.debts.setter(creditor).insert(debtor, reverse - amount);
+        } else {
+            self.debts.setter(creditor).delete(debtor);
+            let owed = self.debts.getter(debtor).get(creditor) + amount - reverse;
+            self.debts.setter(debtor).insert(creditor, owed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::SolCall;
+    use common::token::IERC20Transfers as IERC20;
+    use stylus_sdk::testing::*;
+
+    const ALICE: Address = Address::new([0x01; 20]);
+    const BOB: Address = Address::new([0x02; 20]);
+    const CAROL: Address = Address::new([0x03; 20]);
+    const DAVE: Address = Address::new([0x04; 20]);
+    const TOKEN: Address = Address::new([0xcc; 20]);
+
+    fn setup(token: Address) -> (TestVM, ExpenseSplitter) {
+        let vm = TestVM::default();
+        let mut contract = ExpenseSplitter::from(&vm);
+        contract
+            .constructor(vec![ALICE, BOB, CAROL, DAVE], token)
+            .unwrap();
+        (vm, contract)
+    }
+
+    fn expense(
+        vm: &TestVM,
+        contract: &mut ExpenseSplitter,
+        payer: Address,
+        amount: u64,
+        with: &[Address],
+    ) {
+        vm.set_sender(payer);
+        contract
+            .add_expense(U256::from(amount), with.to_vec())
+            .unwrap();
+    }
+
+    fn net(contract: &ExpenseSplitter, member: Address) -> I256 {
+        contract.net_balance(member)
+    }
+
+    #[test]
+    fn test_expenses_split_equally_and_net_out() {
+        let (vm, mut contract) = setup(Address::ZERO);
+        expense(&vm, &mut contract, ALICE, 90, &[ALICE, BOB, CAROL]);
+        assert_eq!(contract.debt(BOB, ALICE), U256::from(30));
+        assert_eq!(contract.debt(CAROL, ALICE), U256::from(30));
+
+        // Bob pays 100 for Alice alone: her 30 credit is cancelled first.
+        expense(&vm, &mut contract, BOB, 100, &[ALICE]);
+        assert_eq!(contract.debt(BOB, ALICE), U256::ZERO);
+        assert_eq!(contract.debt(ALICE, BOB), U256::from(70));
+        assert_eq!(net(&contract, ALICE), I256::try_from(-40).unwrap());
+    }
+
+    #[test]
+    fn test_simplify_preserves_net_positions() {
+        let (vm, mut contract) = setup(Address::ZERO);
+        // A cycle plus a chain: Bob -> Alice -> Carol -> Bob, Dave -> Carol.
+        expense(&vm, &mut contract, ALICE, 40, &[BOB]);
+        expense(&vm, &mut contract, CAROL, 30, &[ALICE]);
+        expense(&vm, &mut contract, BOB, 20, &[CAROL]);
+        expense(&vm, &mut contract, CAROL, 50, &[DAVE]);
+        let before: Vec<I256> = [ALICE, BOB, CAROL, DAVE]
+            .iter()
+            .map(|m| net(&contract, *m))
+            .collect();
+        assert_eq!(contract.all_debts().len(), 4);
+
+        assert_eq!(contract.simplify().unwrap(), U256::from(3));
+        let after: Vec<I256> = [ALICE, BOB, CAROL, DAVE]
+            .iter()
+            .map(|m| net(&contract, *m))
+            .collect();
+        assert_eq!(before, after);
+        assert_eq!(
+            contract.all_debts(),
+            vec![
+                (BOB, ALICE, U256::from(10)),
+                (BOB, CAROL, U256::from(10)),
+                (DAVE, CAROL, U256::from(50)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_settle_in_eth() {
+        let (vm, mut contract) = setup(Address::ZERO);
+        expense(&vm, &mut contract, ALICE, 50, &[BOB]);
+        vm.set_sender(BOB);
+        vm.set_value(U256::from(20));
+        assert!(matches!(
+            contract.settle(ALICE, U256::from(30)),
+            Err(ExpenseSplitterError::WrongPayment(_))
+        ));
+        vm.set_balance(vm.contract_address(), U256::from(20));
+        contract.settle(ALICE, U256::from(20)).unwrap();
+        assert_eq!(vm.balance(ALICE), U256::from(20));
+        assert_eq!(contract.debt(BOB, ALICE), U256::from(30));
+
+        vm.set_value(U256::from(31));
+        assert!(matches!(
+            contract.settle(ALICE, U256::from(31)),
+            Err(ExpenseSplitterError::ExceedsDebt(_))
+        ));
+    }
+
+    #[test]
+    fn test_settle_in_tokens() {
+        let (vm, mut contract) = setup(TOKEN);
+        expense(&vm, &mut contract, ALICE, 50, &[BOB]);
+        let transfer = |value: u64| IERC20::transferFromCall {
+            from: BOB,
+            to: ALICE,
+            value: U256::from(value),
+        };
+        let ok = IERC20::transferFromCall::abi_encode_returns(&(true,));
+        vm.mock_call(TOKEN, transfer(20).abi_encode(), Ok(ok));
+        vm.set_sender(BOB);
+        contract.settle(ALICE, U256::from(20)).unwrap();
+        assert_eq!(contract.debt(BOB, ALICE), U256::from(30));
+
+        vm.mock_call(TOKEN, transfer(30).abi_encode(), Err(b"allowance".to_vec()));
+        assert!(matches!(
+            contract.settle(ALICE, U256::from(30)),
+            Err(ExpenseSplitterError::TransferFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_invalid_groups_and_expenses() {
+        let vm = TestVM::default();
+        let mut contract = ExpenseSplitter::from(&vm);
+        assert!(matches!(
+            contract.constructor(vec![ALICE, ALICE], Address::ZERO),
+            Err(ExpenseSplitterError::InvalidGroup(_))
+        ));
+
+        let (vm, mut contract) = setup(Address::ZERO);
+        vm.set_sender(TOKEN);
+        assert!(matches!(
+            contract.add_expense(U256::from(10), vec![ALICE]),
+            Err(ExpenseSplitterError::NotMember(_))
+        ));
+        vm.set_sender(ALICE);
+        for participants in [vec![], vec![BOB, BOB], vec![TOKEN]] {
+            assert!(matches!(
+                contract.add_expense(U256::from(10), participants),
+                Err(ExpenseSplitterError::InvalidExpense(_))
+            ));
+        }
+    }
+}
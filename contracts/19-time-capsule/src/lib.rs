@@ -0,0 +1,357 @@
+//!
+//! Stylus Time Capsule
+//!
+//! Senders leave messages for a recipient that open at a chosen timestamp.
+//! A capsule starts as a draft the sender can still cancel; `seal()` locks
+//! it for good. Once the unlock time has passed anyone can call `unlock()`,
+//! which emits the payload in a `CapsuleUnlocked` event and makes it readable
+//! through `payload()`.
+//!
+//! Contract storage is public, so the time lock only controls what the
+//! contract *serves*. Store ciphertext (with the key released off-chain at the
+//! unlock time) or a hash to reveal against later, never plaintext secrets.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, U256, U8},
+    prelude::*,
+};
+
+/// Largest payload accepted, in bytes.
+const MAX_PAYLOAD_BYTES: usize = 1024;
+
+pub const STATUS_DRAFT: u8 = 1;
+pub const STATUS_SEALED: u8 = 2;
+pub const STATUS_UNLOCKED: u8 = 3;
+pub const STATUS_CANCELLED: u8 = 4;
+
+sol! {
+    event CapsuleCreated(uint256 indexed capsuleId, address indexed sender, address indexed recipient, uint256 unlockTime);
+    event CapsuleSealed(uint256 indexed capsuleId);
+    event CapsuleCancelled(uint256 indexed capsuleId);
+    event CapsuleUnlocked(uint256 indexed capsuleId, address indexed recipient, bytes payload);
+
+    #[derive(Debug)]
+    error UnknownCapsule(uint256 capsuleId);
+    #[derive(Debug)]
+    error InvalidUnlockTime(uint256 unlockTime);
+    #[derive(Debug)]
+    error InvalidPayload();
+    #[derive(Debug)]
+    error NotSender(address caller);
+    #[derive(Debug)]
+    error WrongStatus(uint8 status);
+    #[derive(Debug)]
+    error StillLocked(uint256 unlockTime);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum TimeCapsuleError {
+    UnknownCapsule(UnknownCapsule),
+    InvalidUnlockTime(InvalidUnlockTime),
+    InvalidPayload(InvalidPayload),
+    NotSender(NotSender),
+    WrongStatus(WrongStatus),
+    StillLocked(StillLocked),
+}
+
+sol_storage! {
+    pub struct Capsule {
+        address sender;
+        address recipient;
+        uint256 unlock_time;
+        bytes payload;
+        /// One of the `STATUS_*` constants.
+        uint8 status;
+    }
+
+    #[entrypoint]
+    pub struct TimeCapsule {
+        /// Capsules are numbered from 0 in creation order.
+        uint256 capsule_count;
+        mapping(uint256 => Capsule) capsules;
+        mapping(address => uint256[]) capsules_by_sender;
+    }
+}
+
+#[public]
+impl TimeCapsule {
+    /// Creates a draft capsule for `recipient` that opens at `unlock_time`, returning its id.
+    pub fn create_capsule(
+        &mut self,
+        recipient: Address,
+        payload: Bytes,
+        unlock_time: U256,
+    ) -> Result<U256, TimeCapsuleError> {
+        if unlock_time <= U256::from(self.vm().block_timestamp()) {
+            return Err(TimeCapsuleError::InvalidUnlockTime(InvalidUnlockTime {
+                unlockTime: unlock_time,
+            }));
+        }
+        if payload.is_empty() || payload.len() > MAX_PAYLOAD_BYTES {
+            return Err(TimeCapsuleError::InvalidPayload(InvalidPayload {}));
+        }
+        let sender = self.vm().msg_sender();
+        let capsule_id = self.capsule_count.get();
+        self.capsule_count.set(capsule_id + U256::from(1));
+        let mut capsule = self.capsules.setter(capsule_id);
+        capsule.sender.set(sender);
+        capsule.recipient.set(recipient);
+        capsule.unlock_time.set(unlock_time);
+        capsule.payload.set_bytes(&payload);
+        capsule.status.set(U8::from(STATUS_DRAFT));
+        self.capsules_by_sender.setter(sender).push(capsule_id);
+        log(
+            self.vm(),
+            CapsuleCreated {
+                capsuleId: capsule_id,
+                sender,
+                recipient,
+                unlockTime: unlock_time,
+            },
+        );
+        Ok(capsule_id)
+    }
+
+    /// Locks a draft so it can no longer be cancelled. Sender only.
+    pub fn seal(&mut self, capsule_id: U256) -> Result<(), TimeCapsuleError> {
+        self.sender_draft(capsule_id)?;
+        self.capsules
+            .setter(capsule_id)
+            .status
+            .set(U8::from(STATUS_SEALED));
+        log(
+            self.vm(),
+            CapsuleSealed {
+                capsuleId: capsule_id,
+            },
+        );
+        Ok(())
+    }
+
+    /// Withdraws a draft and erases its payload. Sender only.
+    pub fn cancel(&mut self, capsule_id: U256) -> Result<(), TimeCapsuleError> {
+        self.sender_draft(capsule_id)?;
+        let mut capsule = self.capsules.setter(capsule_id);
+        capsule.payload.erase();
+        capsule.status.set(U8::from(STATUS_CANCELLED));
+        log(
+            self.vm(),
+            CapsuleCancelled {
+                capsuleId: capsule_id,
+            },
+        );
+        Ok(())
+    }
+
+    /// Opens a sealed capsule whose unlock time has passed. Anyone may call this.
+    pub fn unlock(&mut self, capsule_id: U256) -> Result<Bytes, TimeCapsuleError> {
+        let status = self.status_of(capsule_id)?;
+        if status != STATUS_SEALED {
+            return Err(TimeCapsuleError::WrongStatus(WrongStatus { status }));
+        }
+        let now = U256::from(self.vm().block_timestamp());
+        let mut capsule = self.capsules.setter(capsule_id);
+        let unlock_time = capsule.unlock_time.get();
+        if now < unlock_time {
+            return Err(TimeCapsuleError::StillLocked(StillLocked {
+                unlockTime: unlock_time,
+            }));
+        }
+        capsule.status.set(U8::from(STATUS_UNLOCKED));
+        let recipient = capsule.recipient.get();
+        let payload = capsule.payload.get_bytes();
+        log(
+            self.vm(),
+            CapsuleUnlocked {
+                capsuleId: capsule_id,
+                recipient,
+                payload: payload.clone().into(),
+            },
+        );
+        Ok(payload.into())
+    }
+
+    /// The payload of an unlocked capsule.
+    pub fn payload(&self, capsule_id: U256) -> Result<Bytes, TimeCapsuleError> {
+        let status = self.status_of(capsule_id)?;
+        let capsule = self.capsules.getter(capsule_id);
+        if status != STATUS_UNLOCKED {
+            return Err(TimeCapsuleError::StillLocked(StillLocked {
+                unlockTime: capsule.unlock_time.get(),
+            }));
+        }
+        Ok(capsule.payload.get_bytes().into())
+    }
+
+    /// Returns `(sender, recipient, unlock_time, status)`.
+    pub fn capsule(
+        &self,
+        capsule_id: U256,
+    ) -> Result<(Address, Address, U256, u8), TimeCapsuleError> {
+        let status = self.status_of(capsule_id)?;
+        let capsule = self.capsules.getter(capsule_id);
+        Ok((
+            capsule.sender.get(),
+            capsule.recipient.get(),
+            capsule.unlock_time.get(),
+            status,
+        ))
+    }
+
+    /// Ids of every capsule `sender` created, oldest first.
+    pub fn capsules_of(&self, sender: Address) -> Vec<U256> {
+        let ids = self.capsules_by_sender.getter(sender);
+        (0..ids.len()).filter_map(|i| ids.get(i)).collect()
+    }
+
+    pub fn capsule_count(&self) -> U256 {
+        self.capsule_count.get()
+    }
+}
+
+impl TimeCapsule {
+    fn status_of(&self, capsule_id: U256) -> Result<u8, TimeCapsuleError> {
+        if capsule_id >= self.capsule_count.get() {
+            return Err(TimeCapsuleError::UnknownCapsule(UnknownCapsule {
+                capsuleId: capsule_id,
+            }));
+        }
+        Ok(self.capsules.getter(capsule_id).status.get().to())
+    }
+
+    /// Errors unless the caller created `capsule_id` and it is still a draft.
+    fn sender_draft(&self, capsule_id: U256) -> Result<(), TimeCapsuleError> {
+        let status = self.status_of(capsule_id)?;
+        let caller = self.vm().msg_sender();
+        if caller != self.capsules.getter(capsule_id).sender.get() {
+            return Err(TimeCapsuleError::NotSender(NotSender { caller }));
+        }
+        if status != STATUS_DRAFT {
+            return Err(TimeCapsuleError::WrongStatus(WrongStatus { status }));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::SolEvent;
+    use stylus_sdk::testing::*;
+
+    const SENDER: Address = Address::new([0x01; 20]);
+    const RECIPIENT: Address = Address::new([0x02; 20]);
+    const STRANGER: Address = Address::new([0x03; 20]);
+    const START: u64 = 1_000;
+    const UNLOCK: u64 = 5_000;
+
+    fn message() -> Bytes {
+        Bytes::from(b"ciphertext".to_vec())
+    }
+
+    /// Capsule 0, a draft from `SENDER` to `RECIPIENT` opening at `UNLOCK`.
+    fn setup() -> (TestVM, TimeCapsule) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(START);
+        let mut contract = TimeCapsule::from(&vm);
+        vm.set_sender(SENDER);
+        contract
+            .create_capsule(RECIPIENT, message(), U256::from(UNLOCK))
+            .unwrap();
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_sealed_capsule_opens_after_unlock_time() {
+        let (vm, mut contract) = setup();
+        contract.seal(U256::ZERO).unwrap();
+        vm.set_sender(STRANGER);
+        assert!(matches!(
+            contract.unlock(U256::ZERO),
+            Err(TimeCapsuleError::StillLocked(_))
+        ));
+        assert!(contract.payload(U256::ZERO).is_err());
+
+        vm.set_block_timestamp(UNLOCK);
+        assert_eq!(contract.unlock(U256::ZERO).unwrap(), message());
+        assert_eq!(contract.payload(U256::ZERO).unwrap(), message());
+        assert_eq!(
+            contract.capsule(U256::ZERO).unwrap(),
+            (SENDER, RECIPIENT, U256::from(UNLOCK), STATUS_UNLOCKED)
+        );
+        assert!(matches!(
+            contract.unlock(U256::ZERO),
+            Err(TimeCapsuleError::WrongStatus(_))
+        ));
+    }
+
+    #[test]
+    fn test_unlock_emits_payload() {
+        let (vm, mut contract) = setup();
+        contract.seal(U256::ZERO).unwrap();
+        vm.set_block_timestamp(UNLOCK);
+        contract.unlock(U256::ZERO).unwrap();
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = logs.last().unwrap();
+        assert_eq!(topics[0], CapsuleUnlocked::SIGNATURE_HASH);
+        let event = CapsuleUnlocked::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!(event.payload, message().0);
+        assert_eq!(event.recipient, RECIPIENT);
+    }
+
+    #[test]
+    fn test_drafts_cancel_but_sealed_capsules_do_not() {
+        let (vm, mut contract) = setup();
+        contract
+            .create_capsule(RECIPIENT, message(), U256::from(UNLOCK))
+            .unwrap();
+        contract.cancel(U256::ZERO).unwrap();
+        contract.seal(U256::from(1)).unwrap();
+        assert!(matches!(
+            contract.cancel(U256::from(1)),
+            Err(TimeCapsuleError::WrongStatus(_))
+        ));
+
+        vm.set_block_timestamp(UNLOCK);
+        assert!(contract.unlock(U256::ZERO).is_err());
+        assert_eq!(contract.capsule(U256::ZERO).unwrap().3, STATUS_CANCELLED);
+        assert_eq!(
+            contract.capsules_of(SENDER),
+            vec![U256::ZERO, U256::from(1)]
+        );
+        assert!(contract.capsules_of(RECIPIENT).is_empty());
+    }
+
+    #[test]
+    fn test_rejects_invalid_capsules_and_callers() {
+        let (vm, mut contract) = setup();
+        assert!(matches!(
+            contract.create_capsule(RECIPIENT, message(), U256::from(START)),
+            Err(TimeCapsuleError::InvalidUnlockTime(_))
+        ));
+        assert!(matches!(
+            contract.create_capsule(RECIPIENT, Bytes::from(Vec::new()), U256::from(UNLOCK)),
+            Err(TimeCapsuleError::InvalidPayload(_))
+        ));
+        vm.set_sender(STRANGER);
+        assert!(matches!(
+            contract.seal(U256::ZERO),
+            Err(TimeCapsuleError::NotSender(_))
+        ));
+        assert!(matches!(
+            contract.cancel(U256::from(7)),
+            Err(TimeCapsuleError::UnknownCapsule(_))
+        ));
+    }
+}
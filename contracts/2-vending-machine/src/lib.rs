@@ -1,88 +1,3176 @@
+//!
+//! Stylus Vending Machine
+//!
+//! Hands out one cupcake per call to `vend()`, with a per-user cooldown, and
+//! emits a `CupcakeVended` event for every cupcake handed out.
+//!
+//! The constructor takes the owner, the base cooldown and `max_users`, the
+//! most distinct users the machine will ever serve (zero for no limit). Stylus
+//! runs a `#[constructor]` exactly once, at deployment, and rejects any later
+//! call, so these settings cannot be re-initialized. Once `max_users` addresses
+//! have received a cupcake, newcomers get `MachineFull`; existing users carry
+//! on as before.
+//!
+//! The owner can tune the cooldown and pause or unpause vending. Owner-only
+//! functions go through the `only_owner` helper, the Stylus counterpart of a
+//! Solidity `onlyOwner` modifier. The pause switch is the shared `Pausable`
+//! component from `contracts/common`, embedded in the machine's storage.
+//!
+//! Cupcakes can also be bought with `buy_cupcake()`, which skips the cooldown
+//! but costs `price()` wei. Overpayment is refunded in the same call, and the
+//! owner collects the proceeds with `withdraw()`. Because the refund hands
+//! control to the buyer, `buy_cupcake` runs behind the shared `ReentrancyGuard`.
+//!
+//! By default cupcakes are tracked in the machine's own ledger. Once the owner
+//! points `set_cupcake_token` at a deployed CUPCAKE ERC-20
+//! (`contracts/9-erc20-token`) and makes the machine that token's owner, every
+//! cupcake is minted as one whole token to the buyer instead. The external
+//! call goes through `self.vm().call`, so it can be mocked in `TestVM`, and a
+//! revert in the token reverts the whole vend with `TokenMintFailed`.
+//!
+//! Free vends are also limited per day. Each user may vend `user_daily_quota`
+//! cupcakes in any rolling 24 hours, counted from a ring of their last
+//! `MAX_USER_DAILY_QUOTA` vend times, and the whole machine hands out at most
+//! `global_daily_cap` per UTC day, bucketed by `timestamp / 86400`. The owner
+//! can change both with `set_quotas`; zero lifts a limit. Bought cupcakes and
+//! referral bonuses count toward neither.
+//!
+//! The owner decides who may take free vends. Addresses on the blocklist are
+//! always refused with `Blocked`. In the default open mode everyone else may
+//! vend; with `set_allowlist_only(true)` only addresses on the allowlist may,
+//! and the rest get `NotAllowlisted`. The lists gate every free vend, including
+//! `vend_with_referral` and `vend_for`, but not purchases.
+//!
+//! Bots tend to vend through contracts, so the owner can treat contract
+//! callers apart from EOAs with `set_caller_policy`. A caller counts as a
+//! contract if it has code, or if it is not `tx_origin`, which also catches a
+//! contract calling from its constructor before its code is stored. Under
+//! `CallerPolicy::Throttle` contract callers wait `CONTRACT_COOLDOWN_FACTOR`
+//! base cooldowns between vends whatever their tier, and under
+//! `CallerPolicy::Block` they are refused with `ContractCaller`. Both are
+//! heuristics: an EOA can still run a bot. Blocking only applies to `vend()`
+//! and `vend_with_referral`. The throttled cooldown applies wherever a user's
+//! cooldown does, and `cooldown_of` and `time_until_next_vend` report it for
+//! any address with code; views cannot see `tx_origin`, so they miss a contract
+//! still in its constructor.
+//!
+//! Regulars earn VIP tiers from the cupcakes they have received over their
+//! lifetime, vended or bought: everyone starts at Bronze, reaches Silver after
+//! 10 cupcakes and Gold after 50. Silver users wait half the base cooldown and
+//! Gold users a quarter of it. A tier is never lost, and reaching a new one
+//! emits `TierUpgraded`. Tiers are stored as their `u8` discriminant in the
+//! user's record; `tier_of` returns the same value.
+//!
+//! A first-time user can name an existing customer with
+//! `vend_with_referral(referrer)`. The referrer receives `REFERRAL_BONUS`
+//! cupcakes on top of the user's own, and the referral is recorded once and
+//! for all. Since only new users can be referred and only by users who were
+//! customers before them, nobody can refer themselves and referrals can never
+//! form a cycle.
+//!
+//! Users can hand ledger cupcakes to each other with
+//! `transfer_cupcake(to, amount)`. A recipient who has never vended gets a
+//! user record and joins `user_index`, so transfers respect `max_users`.
+//! Zero-amount transfers are rejected, so they cannot index strangers.
+//! Received cupcakes do not count toward the recipient's lifetime total or
+//! tier. With a token configured, transfer the tokens on the ERC-20 instead.
+//!
+//! The ten biggest ledger balances are kept sorted in storage and served by
+//! `top_holders()`. Every credited cupcake may move its holder up the list,
+//! which costs a few extra storage writes per vend but spares front-ends from
+//! scanning every user. Cupcakes minted on the ERC-20 are not ranked. A holder
+//! who transfers cupcakes away moves down the list, but is only replaced by an
+//! unlisted user once that user's balance changes.
+//!
+//! Beside cupcakes, the owner can stock a catalog of other items with
+//! `add_item(name, price, stock, cooldown)` and adjust them with `update_item`.
+//! Items are numbered from zero in listing order. `vend_item(id)` takes the
+//! item's price, refunds any excess, draws one from its stock and credits the
+//! caller's per-item balance, with a cooldown per user and item. Catalog items
+//! are kept apart from cupcakes: they do not count toward tiers, quotas, the
+//! leaderboard or `total_vends`.
+//!
+//! Third parties can prepay cupcakes for someone else with
+//! `sponsor(beneficiary)`. The ETH sent buys whole credits at the current
+//! price, with the remainder refunded, and stays in escrow until the
+//! beneficiary redeems a credit with `vend_free()`. Credits are spent before
+//! anything else: with credits left, `vend_free` refunds any value sent,
+//! otherwise it falls back to an ordinary purchase. Sponsors are drawn on in
+//! the order they first funded the beneficiary, which `sponsors_of` lists
+//! with each sponsor once, and `refund_sponsorship` returns a sponsor's
+//! unused credits at the price paid, even while paused.
+//! Sponsored vends count as purchases, so cooldowns, quotas and the access
+//! lists do not apply, and `withdraw` never touches the escrow.
+//!
+//! The owner can point `set_raffle` at a `contracts/58-vend-raffle`
+//! deployment, after which every free vend also enters the user into that
+//! week's raffle draw. Entering is best effort: if the raffle reverts, the
+//! vend still goes through without a ticket.
+//!
+//! With `set_badges` pointing at a `contracts/59-achievement-badges`
+//! deployment, a user whose lifetime cupcakes reach 10, 50 or 100 is minted
+//! the matching soulbound badge in the same call. The badge is awarded once the
+//! cupcake has been credited, and a reverting badge contract reverts the whole
+//! vend with `BadgeMintFailed`.
+//!
+//! `machine_status()` bundles what an operator dashboard needs into one call:
+//! free vends left today, price, the pause flag, cupcakes handed out, unique
+//! users and the ETH waiting to be withdrawn.
+//!
+//! Failures revert with Solidity custom errors (`CooldownActive`,
+//! `MachinePaused`, `NotOwner`, ...), so ABI consumers can decode the reason.
+//!
+//! Every user has a `UserRecord` in a mapping, so lookups are O(1). Users are
+//! also appended to `user_index` the first time they vend, which keeps the set
+//! of users enumerable.
+//!
+//! Migration: earlier versions kept each user's balance and last vend time in
+//! two separate mappings, `cupcake_balances` and `last_vend_time`, with no
+//! index, so their users cannot be listed on chain. The storage layout is not
+//! compatible; deploy a fresh instance rather than upgrading one in place.
+//!
+//! Moving users to a new deployment goes through a snapshot API. The old
+//! machine pages its users out with `export_users(offset, limit)`, and the new
+//! owner replays the pages into `import_users`, which skips users already
+//! present so an interrupted run can resend a page. `close_import` ends
+//! importing for good, and `transfer_ownership` hands the machine over.
+//! Snapshots carry balances, last vend times and lifetime counts; tiers are
+//! re-derived from the counts. Referrals and quota history start afresh. A
+//! last vend time later than the importing block is rejected; a stamp from
+//! the future would keep its user in cooldown long after the move.
+//! `tools/migrate` drives the whole flow.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
 #![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
 #![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
 
 #[macro_use]
 extern crate alloc;
 
-use alloc::vec::Vec;
-use stylus_sdk::{alloy_primitives::{Address, U256}, prelude::*};
+#[cfg(test)]
+mod proptests;
+
+use alloc::{string::String, vec::Vec};
+use alloy_sol_types::{sol, SolCall};
+use common::{paging, Pausable, ReentrancyGuard, ReentrancyGuardReentrantCall};
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, U256, U64, U8},
+    prelude::*,
+};
+
+/// Price the machine starts with, 0.001 ETH; the owner can change it with `set_price`.
+const CUPCAKE_PRICE_WEI: u64 = 1_000_000_000_000_000;
+
+/// One whole CUPCAKE in the token's 18-decimal base units.
+const CUPCAKE_TOKEN_UNIT: u64 = 1_000_000_000_000_000_000;
+
+const DAY_SECONDS: u64 = 86_400;
+
+/// Free vends per user per 24 hours the machine starts with.
+const DEFAULT_USER_DAILY_QUOTA: u64 = 24;
+
+/// Highest per-user quota, and so the number of vend times kept per user.
+const MAX_USER_DAILY_QUOTA: u64 = 32;
+
+/// Free vends per day across all users the machine starts with.
+const DEFAULT_GLOBAL_DAILY_CAP: u64 = 10_000;
+
+/// Lifetime cupcakes needed to reach Silver.
+const SILVER_THRESHOLD: u64 = 10;
+
+/// Lifetime cupcakes needed to reach Gold.
+const GOLD_THRESHOLD: u64 = 50;
+
+/// Lifetime cupcake counts that earn an achievement badge (`contracts/59-achievement-badges`).
+const BADGE_MILESTONES: [u64; 3] = [10, 50, 100];
+
+/// How many times the base cooldown a throttled contract caller waits between vends.
+const CONTRACT_COOLDOWN_FACTOR: u64 = 4;
+
+/// Bonus cupcakes credited to a referrer for each new customer they bring in.
+const REFERRAL_BONUS: u64 = 1;
+
+/// Holders listed on the leaderboard.
+const MAX_TOP_HOLDERS: usize = 10;
+
+/// Longest catalog item name accepted, in bytes.
+const MAX_ITEM_NAME_BYTES: usize = 32;
+
+/// Most users `export_users` returns per call.
+const MAX_EXPORT_PAGE: u64 = 100;
+
+/// `(user, balance, last_vend_time, lifetime_cupcakes)`, as moved between deployments.
+pub type UserSnapshot = (Address, U256, U256, U256);
+
+/// `(stock, price, paused, total_vends, unique_users, treasury)`, as returned by `machine_status`.
+pub type MachineStatus = (U256, U256, bool, U256, U256, U256);
+
+/// `(name, price, stock, cooldown_seconds)`, as returned by `item`.
+pub type CatalogEntry = (String, U256, U256, u64);
+
+/// VIP tier, stored in `UserRecord` as its `u8` discriminant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Tier {
+    Bronze = 0,
+    Silver = 1,
+    Gold = 2,
+}
+
+impl Tier {
+    /// Tier earned by `lifetime` cupcakes.
+    fn for_lifetime(lifetime: U256) -> Self {
+        if lifetime >= U256::from(GOLD_THRESHOLD) {
+            Tier::Gold
+        } else if lifetime >= U256::from(SILVER_THRESHOLD) {
+            Tier::Silver
+        } else {
+            Tier::Bronze
+        }
+    }
+
+    /// Cooldown for this tier, given the machine's base cooldown.
+    fn cooldown(self, base: u64) -> u64 {
+        match self {
+            Tier::Bronze => base,
+            Tier::Silver => base / 2,
+            Tier::Gold => base / 4,
+        }
+    }
+}
+
+impl From<Tier> for u8 {
+    fn from(tier: Tier) -> Self {
+        tier as u8
+    }
+}
+
+impl From<u8> for Tier {
+    /// Only values written by `From<Tier>` are ever stored; anything else reads as Bronze,
+    /// so a corrupt value never grants perks.
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Tier::Silver,
+            2 => Tier::Gold,
+            _ => Tier::Bronze,
+        }
+    }
+}
+
+/// How free vends treat callers that look like contracts, stored as its `u8` discriminant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CallerPolicy {
+    /// Contract callers vend like anyone else.
+    Allow = 0,
+    /// Contract callers wait `CONTRACT_COOLDOWN_FACTOR` base cooldowns, whatever their tier.
+    Throttle = 1,
+    /// Contract callers are refused with `ContractCaller`.
+    Block = 2,
+}
+
+impl From<CallerPolicy> for u8 {
+    fn from(policy: CallerPolicy) -> Self {
+        policy as u8
+    }
+}
+
+impl TryFrom<u8> for CallerPolicy {
+    type Error = VendingMachineError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(CallerPolicy::Allow),
+            1 => Ok(CallerPolicy::Throttle),
+            2 => Ok(CallerPolicy::Block),
+            _ => Err(VendingMachineError::InvalidCallerPolicy(
+                InvalidCallerPolicy { policy: value },
+            )),
+        }
+    }
+}
+
+sol! {
+    /// The part of the CUPCAKE ERC-20 the machine calls into.
+    interface ICupcakeToken {
+        function mint(address to, uint256 value) external;
+    }
+
+    /// The weekly raffle (`contracts/58-vend-raffle`) free vends enter users into.
+    interface IVendRaffle {
+        function enter(address user) external;
+    }
+
+    /// The soulbound badges (`contracts/59-achievement-badges`) minted at cupcake milestones.
+    interface IAchievementBadges {
+        function award(address user, uint64 milestone) external returns (uint256);
+    }
+
+    event CupcakeVended(address indexed user, uint256 newBalance);
+    event CooldownUpdated(uint64 cooldownSeconds);
+    event PriceUpdated(uint256 price);
+    event Withdrawn(address indexed to, uint256 amount);
+    event CupcakeTokenUpdated(address token);
+    event RaffleUpdated(address raffle);
+    event BadgesUpdated(address badges);
+    event CallerPolicyUpdated(uint8 policy);
+    event CupcakeTransferred(address indexed from, address indexed to, uint256 amount);
+    event CupcakeMinted(address indexed user, address indexed token, uint256 amount);
+    event TierUpgraded(address indexed user, uint8 tier);
+    event QuotasUpdated(uint64 userDailyQuota, uint64 globalDailyCap);
+    event ReferralCredited(address indexed referrer, address indexed user, uint256 referrals);
+    event OwnershipTransferred(address indexed previousOwner, address indexed newOwner);
+    event UsersImported(uint256 count);
+    event ImportClosed();
+    event ItemListed(uint256 indexed id, string name, uint256 price, uint256 stock, uint64 cooldownSeconds);
+    event ItemUpdated(uint256 indexed id, uint256 price, uint256 stock, uint64 cooldownSeconds);
+    event AllowlistUpdated(address indexed user, bool allowed);
+    event BlocklistUpdated(address indexed user, bool blocked);
+    event AllowlistOnlyUpdated(bool allowlistOnly);
+    event ItemVended(address indexed user, uint256 indexed id, uint256 newBalance);
+    event Sponsored(address indexed sponsor, address indexed beneficiary, uint256 credits, uint256 amount);
+    event SponsoredVend(address indexed beneficiary, address indexed sponsor, uint256 creditsLeft);
+    event SponsorshipRefunded(address indexed sponsor, address indexed beneficiary, uint256 credits, uint256 amount);
+
+    #[derive(Debug)]
+    error CooldownActive(uint256 remaining);
+    #[derive(Debug)]
+    error MachinePaused();
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error InsufficientPayment(uint256 price, uint256 sent);
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+    #[derive(Debug)]
+    error TokenMintFailed(address token, bytes reason);
+    #[derive(Debug)]
+    error UserQuotaExceeded(uint256 resetsAt);
+    #[derive(Debug)]
+    error DailySupplyExhausted(uint256 resetsAt);
+    #[derive(Debug)]
+    error SelfReferral(address user);
+    #[derive(Debug)]
+    error NotACustomer(address referrer);
+    #[derive(Debug)]
+    error AlreadyCustomer(address user);
+    #[derive(Debug)]
+    error MachineFull(uint256 maxUsers);
+    #[derive(Debug)]
+    error QuotaTooHigh(uint256 max);
+    #[derive(Debug)]
+    error InvalidOwner(address owner);
+    #[derive(Debug)]
+    error ImportFinished();
+    #[derive(Debug)]
+    error InsufficientBalance(uint256 balance, uint256 amount);
+    #[derive(Debug)]
+    error ZeroTransfer();
+    #[derive(Debug)]
+    error InvalidRecipient(address to);
+    #[derive(Debug)]
+    error Blocked(address user);
+    #[derive(Debug)]
+    error NotAllowlisted(address user);
+    #[derive(Debug)]
+    error UnknownItem(uint256 id);
+    #[derive(Debug)]
+    error OutOfStock(uint256 id);
+    #[derive(Debug)]
+    error InvalidItemName();
+    #[derive(Debug)]
+    error NoSponsorship(address sponsor, address beneficiary);
+    #[derive(Debug)]
+    error SponsorshipUnavailable();
+    #[derive(Debug)]
+    error BadgeMintFailed(address badges, uint64 milestone, bytes reason);
+    #[derive(Debug)]
+    error ContractCaller(address caller);
+    #[derive(Debug)]
+    error InvalidCallerPolicy(uint8 policy);
+    #[derive(Debug)]
+    error InvalidSnapshot(address user, uint256 lastVendTime);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum VendingMachineError {
+    CooldownActive(CooldownActive),
+    MachinePaused(MachinePaused),
+    NotOwner(NotOwner),
+    InsufficientPayment(InsufficientPayment),
+    TransferFailed(TransferFailed),
+    TokenMintFailed(TokenMintFailed),
+    BadgeMintFailed(BadgeMintFailed),
+    ContractCaller(ContractCaller),
+    InvalidCallerPolicy(InvalidCallerPolicy),
+    ReentrantCall(ReentrancyGuardReentrantCall),
+    UserQuotaExceeded(UserQuotaExceeded),
+    DailySupplyExhausted(DailySupplyExhausted),
+    SelfReferral(SelfReferral),
+    NotACustomer(NotACustomer),
+    AlreadyCustomer(AlreadyCustomer),
+    MachineFull(MachineFull),
+    QuotaTooHigh(QuotaTooHigh),
+    InvalidOwner(InvalidOwner),
+    ImportFinished(ImportFinished),
+    InsufficientBalance(InsufficientBalance),
+    ZeroTransfer(ZeroTransfer),
+    InvalidRecipient(InvalidRecipient),
+    Blocked(Blocked),
+    NotAllowlisted(NotAllowlisted),
+    UnknownItem(UnknownItem),
+    OutOfStock(OutOfStock),
+    InvalidItemName(InvalidItemName),
+    NoSponsorship(NoSponsorship),
+    SponsorshipUnavailable(SponsorshipUnavailable),
+    InvalidSnapshot(InvalidSnapshot),
+}
+
+sol_storage! {
+    pub struct UserRecord {
+        uint256 balance;
+        uint256 last_vend_time;
+        /// Set once the user is in `user_index`.
+        bool indexed;
+        /// Cupcakes received over the user's lifetime, vended or bought.
+        uint256 lifetime_cupcakes;
+        /// `Tier` discriminant earned from `lifetime_cupcakes`.
+        uint8 tier;
+        /// Customer who referred this user, or zero.
+        address referrer;
+        /// New customers this user has referred.
+        uint256 referrals;
+        /// Times of the user's latest free vends, a ring of up to `MAX_USER_DAILY_QUOTA`.
+        uint64[] free_vend_times;
+        /// Ring slot holding the oldest time once the ring is full.
+        uint64 free_vend_cursor;
+        /// Catalog items held, by item id.
+        mapping(uint256 => uint256) item_balances;
+        /// When the user last vended each catalog item, by item id.
+        mapping(uint256 => uint256) item_last_vend;
+        /// Prepaid cupcakes sponsors have bought for this user and not yet used.
+        uint256 sponsored_credits;
+        /// Sponsors in the order they first funded this user, each listed once; `vend_free`
+        /// draws on the earliest first.
+        address[] sponsors;
+        /// First entry of `sponsors` that may still hold credits.
+        uint256 sponsor_cursor;
+        mapping(address => Sponsorship) sponsorships;
+    }
+
+    /// What one sponsor has prepaid for one beneficiary.
+    pub struct Sponsorship {
+        uint256 credits;
+        /// Wei held for `credits`, at the prices they were bought for.
+        uint256 escrow;
+        /// Set once the sponsor is in the beneficiary's `sponsors`, at `position`.
+        bool listed;
+        uint256 position;
+    }
+
+    pub struct CatalogItem {
+        string name;
+        uint256 price;
+        uint256 stock;
+        uint64 cooldown_seconds;
+    }
+
+    #[entrypoint]
+    pub struct VendingMachine {
+        mapping(address => UserRecord) users;
+        /// Every address that has vended at least once, in first-vend order.
+        address[] user_index;
+        address owner;
+        Pausable pausable;
+        ReentrancyGuard reentrancy;
+        uint64 cooldown_seconds;
+        uint256 price;
+        /// Lifetime ETH kept from sales, net of refunds; withdrawals do not reduce it.
+        uint256 total_collected;
+        /// CUPCAKE ERC-20 to mint into; zero keeps cupcakes in the internal ledger.
+        address cupcake_token;
+        /// Zero means no per-user limit.
+        uint64 user_daily_quota;
+        /// Zero means no global limit.
+        uint64 global_daily_cap;
+        /// Day bucket (`timestamp / 86400`) that `vended_today` counts.
+        uint64 current_day;
+        uint64 vended_today;
+        /// Most addresses `user_index` may hold; zero means no limit. Fixed at deployment.
+        uint64 max_users;
+        /// Set by `close_import`; `import_users` is refused afterwards.
+        bool import_closed;
+        /// Cupcakes handed out: vended, bought or credited as referral bonuses.
+        uint256 total_vends;
+        /// Biggest ledger balances first, at most `MAX_TOP_HOLDERS` entries.
+        address[] top_holders;
+        mapping(address => bool) allowlist;
+        mapping(address => bool) blocklist;
+        /// When set, only allowlisted addresses may take free vends.
+        bool allowlist_only;
+        /// Catalog items by id; every id below `item_count` is listed.
+        mapping(uint256 => CatalogItem) items;
+        uint256 item_count;
+        /// Wei held for unused sponsored credits; `withdraw` leaves it in place.
+        uint256 sponsor_escrow;
+        /// Raffle every free vend enters its user into; zero for none.
+        address raffle;
+        /// Badge contract awarding `BADGE_MILESTONES`; zero for none.
+        address badges;
+        /// `CallerPolicy` discriminant applied to contract callers of free vends.
+        uint8 caller_policy;
+    }
+}
+
+#[public]
+impl VendingMachine {
+    #[constructor]
+    pub fn constructor(&mut self, owner: Address, cooldown_seconds: u64, max_users: u64) {
+        self.owner.set(owner);
+        self.cooldown_seconds.set(U64::from(cooldown_seconds));
+        self.max_users.set(U64::from(max_users));
+        self.price.set(U256::from(CUPCAKE_PRICE_WEI));
+        self.user_daily_quota
+            .set(U64::from(DEFAULT_USER_DAILY_QUOTA));
+        self.global_daily_cap
+            .set(U64::from(DEFAULT_GLOBAL_DAILY_CAP));
+    }
+
+    pub fn vend(&mut self) -> Result<(), VendingMachineError> {
+        self.when_not_paused()?;
+        let caller = self.vm().msg_sender();
+        self.screen_caller(caller)?;
+        self.vend_to(caller)
+    }
+
+    /// Vends a first-time user their cupcake and credits `referrer`, an existing
+    /// customer, with `REFERRAL_BONUS` more.
+    ///
+    /// The bonus is credited like any other cupcake, so with a token configured a
+    /// failed mint for the referrer reverts the user's vend too.
+    pub fn vend_with_referral(&mut self, referrer: Address) -> Result<(), VendingMachineError> {
+        self.when_not_paused()?;
+        let caller = self.vm().msg_sender();
+        self.screen_caller(caller)?;
+        if referrer == caller {
+            return Err(VendingMachineError::SelfReferral(SelfReferral {
+                user: caller,
+            }));
+        }
+        if self.users.getter(caller).indexed.get() {
+            return Err(VendingMachineError::AlreadyCustomer(AlreadyCustomer {
+                user: caller,
+            }));
+        }
+        if !self.users.getter(referrer).indexed.get() {
+            return Err(VendingMachineError::NotACustomer(NotACustomer { referrer }));
+        }
+
+        self.vend_to(caller)?;
+        for _ in 0..REFERRAL_BONUS {
+            self.credit_cupcake(referrer)?;
+        }
+        self.users.setter(caller).referrer.set(referrer);
+        let mut record = self.users.setter(referrer);
+        let referrals = record.referrals.get() + U256::from(1);
+        record.referrals.set(referrals);
+        log(
+            self.vm(),
+            ReferralCredited {
+                referrer,
+                user: caller,
+                referrals,
+            },
+        );
+        Ok(())
+    }
+
+    /// Vends one cupcake to each of `users`, subject to their own cooldowns. Owner only.
+    ///
+    /// A user who is not eligible, because of the access lists, their cooldown or the
+    /// daily limits, is skipped without aborting the batch. Like Multicall3's
+    /// `aggregate3`, each entry of the result is `(success, error)`, where `error` is
+    /// the ABI-encoded custom error for a skipped user and empty on success.
+    ///
+    /// Eligibility is checked before anything is written. Once a user's cupcake is
+    /// being handed out, a failure such as `TokenMintFailed` reverts the whole batch,
+    /// so no user is ever left half-served.
+    pub fn vend_for(
+        &mut self,
+        users: Vec<Address>,
+    ) -> Result<Vec<(bool, Bytes)>, VendingMachineError> {
+        self.only_owner()?;
+        self.when_not_paused()?;
+        let mut results = Vec::with_capacity(users.len());
+        for user in users {
+            match self.check_vend(user) {
+                Ok(()) => {
+                    self.dispense(user)?;
+                    results.push((true, Bytes::from(Vec::new())));
+                }
+                Err(err) => results.push((false, Bytes::from(Vec::<u8>::from(err)))),
+            }
+        }
+        Ok(results)
+    }
+
+    /// Buys a cupcake for `price()` wei, refunding anything sent above the price.
+    #[payable]
+    pub fn buy_cupcake(&mut self) -> Result<(), VendingMachineError> {
+        self.reentrancy.enter()?;
+        let result = self.sell_cupcake();
+        self.reentrancy.exit();
+        result
+    }
+
+    /// Moves `amount` ledger cupcakes from the caller to `to`, indexing `to` if they are new.
+    pub fn transfer_cupcake(
+        &mut self,
+        to: Address,
+        amount: U256,
+    ) -> Result<(), VendingMachineError> {
+        self.when_not_paused()?;
+        let from = self.vm().msg_sender();
+        if to.is_zero() || to == from {
+            return Err(VendingMachineError::InvalidRecipient(InvalidRecipient { to }));
+        }
+        if amount.is_zero() {
+            return Err(VendingMachineError::ZeroTransfer(ZeroTransfer {}));
+        }
+        let balance = self.users.getter(from).balance.get();
+        if balance < amount {
+            return Err(VendingMachineError::InsufficientBalance(
+                InsufficientBalance { balance, amount },
+            ));
+        }
+        self.ensure_room(to)?;
+
+        self.users.setter(from).balance.set(balance - amount);
+        self.rank_holder(from, balance - amount);
+        let mut record = self.users.setter(to);
+        let received = record.balance.get() + amount;
+        record.balance.set(received);
+        if !record.indexed.get() {
+            record.indexed.set(true);
+            self.user_index.push(to);
+        }
+        self.rank_holder(to, received);
+        log(self.vm(), CupcakeTransferred { from, to, amount });
+        Ok(())
+    }
+
+    /// Buys one of catalog item `id` for its price, refunding anything sent above it.
+    #[payable]
+    pub fn vend_item(&mut self, id: U256) -> Result<(), VendingMachineError> {
+        self.reentrancy.enter()?;
+        let result = self.sell_item(id);
+        self.reentrancy.exit();
+        result
+    }
+
+    /// Prepays cupcakes for `beneficiary` at the current price and returns how many.
+    ///
+    /// The value sent buys `msg.value / price()` credits; the remainder is refunded.
+    #[payable]
+    pub fn sponsor(&mut self, beneficiary: Address) -> Result<U256, VendingMachineError> {
+        self.reentrancy.enter()?;
+        let result = self.fund_sponsorship(beneficiary);
+        self.reentrancy.exit();
+        result
+    }
+
+    /// Vends the caller a cupcake paid for by a sponsor, or by the caller when no sponsored
+    /// credits are left.
+    ///
+    /// Credits always come first: with credits available, any value sent is refunded in
+    /// full. Without them the call is a `buy_cupcake`.
+    #[payable]
+    pub fn vend_free(&mut self) -> Result<(), VendingMachineError> {
+        self.reentrancy.enter()?;
+        let result = if self.sponsored_credits(self.vm().msg_sender()).is_zero() {
+            self.sell_cupcake()
+        } else {
+            self.redeem_sponsorship()
+        };
+        self.reentrancy.exit();
+        result
+    }
+
+    /// Returns the caller's unused credits for `beneficiary` as the wei paid for them.
+    ///
+    /// Allowed while paused, so sponsors can always get their money back.
+    pub fn refund_sponsorship(
+        &mut self,
+        beneficiary: Address,
+    ) -> Result<U256, VendingMachineError> {
+        self.reentrancy.enter()?;
+        let result = self.cancel_sponsorship(beneficiary);
+        self.reentrancy.exit();
+        result
+    }
+
+    /// Cupcakes in the internal ledger; with a token configured, query the token instead.
+    pub fn balance_of(&self, user: Address) -> U256 {
+        self.users.getter(user).balance.get()
+    }
+
+    /// The user's VIP tier: 0 Bronze, 1 Silver, 2 Gold.
+    pub fn tier_of(&self, user: Address) -> u8 {
+        self.users.getter(user).tier.get().to()
+    }
+
+    /// Units of catalog item `id` that `user` holds.
+    pub fn item_balance_of(&self, user: Address, id: U256) -> U256 {
+        self.users.getter(user).item_balances.get(id)
+    }
+
+    /// Sponsored cupcakes `user` can still take with `vend_free`.
+    pub fn sponsored_credits(&self, user: Address) -> U256 {
+        self.users.getter(user).sponsored_credits.get()
+    }
+
+    /// Everyone who has sponsored `user`, in the order they first funded them.
+    pub fn sponsors_of(&self, user: Address) -> Vec<Address> {
+        let sponsors = &self.users.getter(user).sponsors;
+        (0..sponsors.len())
+            .filter_map(|index| sponsors.get(index))
+            .collect()
+    }
+
+    /// `(credits, escrow)` that `sponsor` has left prepaid for `beneficiary`.
+    pub fn sponsorship(&self, sponsor: Address, beneficiary: Address) -> (U256, U256) {
+        let record = self.users.getter(beneficiary);
+        let sponsorship = record.sponsorships.getter(sponsor);
+        (sponsorship.credits.get(), sponsorship.escrow.get())
+    }
+
+    pub fn item_count(&self) -> U256 {
+        self.item_count.get()
+    }
+
+    pub fn item(&self, id: U256) -> Result<CatalogEntry, VendingMachineError> {
+        self.ensure_item(id)?;
+        let item = self.items.getter(id);
+        Ok((
+            item.name.get_string(),
+            item.price.get(),
+            item.stock.get(),
+            item.cooldown_seconds.get().to(),
+        ))
+    }
+
+    pub fn lifetime_cupcakes(&self, user: Address) -> U256 {
+        self.users.getter(user).lifetime_cupcakes.get()
+    }
+
+    /// Seconds `user` must wait between vends at their current tier, or the contract cooldown
+    /// if `user` has code while contract callers are throttled.
+    pub fn cooldown_of(&self, user: Address) -> u64 {
+        if self.throttles_contracts() && self.vm().code_size(user) != 0 {
+            return self.contract_cooldown();
+        }
+        let tier = Tier::from(self.tier_of(user));
+        tier.cooldown(self.cooldown_seconds.get().to())
+    }
+
+    /// Timestamp of `user`'s last cooldown-bound vend, or zero if they never vended.
+    pub fn last_vend_time(&self, user: Address) -> U256 {
+        self.users.getter(user).last_vend_time.get()
+    }
+
+    /// Seconds until `user` may `vend()` again under `cooldown_of`; zero once the
+    /// cooldown has passed or if they never vended. A wait past `u64::MAX` seconds is
+    /// reported as ending then.
+    pub fn time_until_next_vend(&self, user: Address) -> U256 {
+        let last_time = self.last_vend_time(user);
+        if last_time.is_zero() {
+            return U256::ZERO;
+        }
+        let ready_at = last_time
+            .saturating_to::<u64>()
+            .saturating_add(self.cooldown_of(user));
+        U256::from(ready_at.saturating_sub(self.vm().block_timestamp()))
+    }
+
+    /// Customer who referred `user`, or zero.
+    pub fn referrer_of(&self, user: Address) -> Address {
+        self.users.getter(user).referrer.get()
+    }
+
+    pub fn referral_count(&self, user: Address) -> U256 {
+        self.users.getter(user).referrals.get()
+    }
+
+    /// Returns `(user_daily_quota, global_daily_cap)`; zero means unlimited.
+    pub fn daily_quotas(&self) -> (u64, u64) {
+        (
+            self.user_daily_quota.get().to(),
+            self.global_daily_cap.get().to(),
+        )
+    }
+
+    /// Free vends `user` has made in the last 24 hours.
+    pub fn quota_used(&self, user: Address) -> u64 {
+        self.recent_free_vends(user).len() as u64
+    }
+
+    /// Free vends across all users in the current day bucket.
+    pub fn vended_today(&self) -> u64 {
+        self.global_day().1
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    pub fn user_count(&self) -> U256 {
+        U256::from(self.user_index.len())
+    }
+
+    /// The biggest ledger balances and their holders, biggest first.
+    pub fn top_holders(&self) -> Vec<(Address, U256)> {
+        (0..self.top_holders.len())
+            .filter_map(|i| self.top_holders.get(i))
+            .map(|holder| (holder, self.users.getter(holder).balance.get()))
+            .collect()
+    }
+
+    pub fn total_vends(&self) -> U256 {
+        self.total_vends.get()
+    }
+
+    /// Everything the operator dashboard shows, in one call.
+    ///
+    /// `stock` is the free vends left in today's global cap, or `type(uint256).max`
+    /// without a cap; `treasury` is the ETH waiting for `withdraw`, which excludes
+    /// sponsorship escrow.
+    pub fn machine_status(&self) -> MachineStatus {
+        let cap = self.global_daily_cap.get().to::<u64>();
+        let stock = if cap == 0 {
+            U256::MAX
+        } else {
+            U256::from(cap.saturating_sub(self.global_day().1))
+        };
+        let treasury = self.treasury();
+        (
+            stock,
+            self.price.get(),
+            self.paused(),
+            self.total_vends.get(),
+            self.user_count(),
+            treasury,
+        )
+    }
+
+    /// `(user, balance, last_vend_time)` for every user, in first-vend order.
+    ///
+    /// Meant for `eth_call`: the cost grows with the user count, so large machines
+    /// should page through `export_users` instead.
+    pub fn get_all_users(&self) -> Vec<(Address, U256, U256)> {
+        (0..self.user_index.len())
+            .filter_map(|index| self.user_index.get(index))
+            .map(|user| {
+                let record = self.users.getter(user);
+                (user, record.balance.get(), record.last_vend_time.get())
+            })
+            .collect()
+    }
+
+    /// Up to `limit` users from position `offset` of the index, capped at `MAX_EXPORT_PAGE`.
+    pub fn export_users(&self, offset: U256, limit: U256) -> Vec<UserSnapshot> {
+        paging::page(self.user_index.len(), offset, limit, MAX_EXPORT_PAGE)
+            .filter_map(|index| self.user_index.get(index))
+            .map(|user| {
+                let record = self.users.getter(user);
+                (
+                    user,
+                    record.balance.get(),
+                    record.last_vend_time.get(),
+                    record.lifetime_cupcakes.get(),
+                )
+            })
+            .collect()
+    }
+
+    pub fn is_allowlisted(&self, user: Address) -> bool {
+        self.allowlist.get(user)
+    }
+
+    pub fn is_blocked(&self, user: Address) -> bool {
+        self.blocklist.get(user)
+    }
+
+    /// Whether free vends are limited to allowlisted addresses.
+    pub fn allowlist_only(&self) -> bool {
+        self.allowlist_only.get()
+    }
+
+    pub fn import_closed(&self) -> bool {
+        self.import_closed.get()
+    }
+
+    pub fn cooldown(&self) -> u64 {
+        self.cooldown_seconds.get().to()
+    }
+
+    /// Most distinct users the machine will serve; zero means no limit.
+    pub fn max_users(&self) -> u64 {
+        self.max_users.get().to()
+    }
+
+    pub fn paused(&self) -> bool {
+        self.pausable.paused()
+    }
+
+    pub fn price(&self) -> U256 {
+        self.price.get()
+    }
+
+    pub fn total_collected(&self) -> U256 {
+        self.total_collected.get()
+    }
+
+    pub fn cupcake_token(&self) -> Address {
+        self.cupcake_token.get()
+    }
+
+    /// Mints future cupcakes on `token`, or back to the internal ledger when zero.
+    pub fn set_cupcake_token(&mut self, token: Address) -> Result<(), VendingMachineError> {
+        self.only_owner()?;
+        self.cupcake_token.set(token);
+        log(self.vm(), CupcakeTokenUpdated { token });
+        Ok(())
+    }
+
+    pub fn raffle(&self) -> Address {
+        self.raffle.get()
+    }
+
+    /// Enters every future free vend into `raffle`, or stops entering when zero.
+    pub fn set_raffle(&mut self, raffle: Address) -> Result<(), VendingMachineError> {
+        self.only_owner()?;
+        self.raffle.set(raffle);
+        log(self.vm(), RaffleUpdated { raffle });
+        Ok(())
+    }
+
+    pub fn badges(&self) -> Address {
+        self.badges.get()
+    }
+
+    /// Awards milestone badges on `badges` from now on, or stops awarding when zero.
+    pub fn set_badges(&mut self, badges: Address) -> Result<(), VendingMachineError> {
+        self.only_owner()?;
+        self.badges.set(badges);
+        log(self.vm(), BadgesUpdated { badges });
+        Ok(())
+    }
+
+    /// `CallerPolicy` discriminant applied to contract callers of free vends.
+    pub fn caller_policy(&self) -> u8 {
+        self.caller_policy.get().to()
+    }
+
+    /// Sets how `vend()` and `vend_with_referral` treat contract callers.
+    pub fn set_caller_policy(&mut self, policy: u8) -> Result<(), VendingMachineError> {
+        self.only_owner()?;
+        CallerPolicy::try_from(policy)?;
+        self.caller_policy.set(U8::from(policy));
+        log(self.vm(), CallerPolicyUpdated { policy });
+        Ok(())
+    }
+
+    pub fn add_to_allowlist(&mut self, user: Address) -> Result<(), VendingMachineError> {
+        self.only_owner()?;
+        self.allowlist.insert(user, true);
+        log(
+            self.vm(),
+            AllowlistUpdated {
+                user,
+                allowed: true,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn remove_from_allowlist(&mut self, user: Address) -> Result<(), VendingMachineError> {
+        self.only_owner()?;
+        self.allowlist.delete(user);
+        log(
+            self.vm(),
+            AllowlistUpdated {
+                user,
+                allowed: false,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn add_to_blocklist(&mut self, user: Address) -> Result<(), VendingMachineError> {
+        self.only_owner()?;
+        self.blocklist.insert(user, true);
+        log(
+            self.vm(),
+            BlocklistUpdated {
+                user,
+                blocked: true,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn remove_from_blocklist(&mut self, user: Address) -> Result<(), VendingMachineError> {
+        self.only_owner()?;
+        self.blocklist.delete(user);
+        log(
+            self.vm(),
+            BlocklistUpdated {
+                user,
+                blocked: false,
+            },
+        );
+        Ok(())
+    }
+
+    /// Switches between open vending and allowlist-only vending.
+    pub fn set_allowlist_only(&mut self, allowlist_only: bool) -> Result<(), VendingMachineError> {
+        self.only_owner()?;
+        self.allowlist_only.set(allowlist_only);
+        log(
+            self.vm(),
+            AllowlistOnlyUpdated {
+                allowlistOnly: allowlist_only,
+            },
+        );
+        Ok(())
+    }
+
+    /// Lists a new catalog item and returns its id.
+    pub fn add_item(
+        &mut self,
+        name: String,
+        price: U256,
+        stock: U256,
+        cooldown_seconds: u64,
+    ) -> Result<U256, VendingMachineError> {
+        self.only_owner()?;
+        if name.is_empty() || name.len() > MAX_ITEM_NAME_BYTES {
+            return Err(VendingMachineError::InvalidItemName(InvalidItemName {}));
+        }
+        let id = self.item_count.get();
+        self.item_count.set(id + U256::from(1));
+        let mut item = self.items.setter(id);
+        item.name.set_str(&name);
+        item.price.set(price);
+        item.stock.set(stock);
+        item.cooldown_seconds.set(U64::from(cooldown_seconds));
+        log(
+            self.vm(),
+            ItemListed {
+                id,
+                name,
+                price,
+                stock,
+                cooldownSeconds: cooldown_seconds,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Replaces the price, stock and cooldown of catalog item `id`.
+    pub fn update_item(
+        &mut self,
+        id: U256,
+        price: U256,
+        stock: U256,
+        cooldown_seconds: u64,
+    ) -> Result<(), VendingMachineError> {
+        self.only_owner()?;
+        self.ensure_item(id)?;
+        let mut item = self.items.setter(id);
+        item.price.set(price);
+        item.stock.set(stock);
+        item.cooldown_seconds.set(U64::from(cooldown_seconds));
+        log(
+            self.vm(),
+            ItemUpdated {
+                id,
+                price,
+                stock,
+                cooldownSeconds: cooldown_seconds,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn set_price(&mut self, price: U256) -> Result<(), VendingMachineError> {
+        self.only_owner()?;
+        self.price.set(price);
+        log(self.vm(), PriceUpdated { price });
+        Ok(())
+    }
+
+    /// Sends the machine's ETH balance, less sponsorship escrow, to the owner.
+    pub fn withdraw(&mut self) -> Result<U256, VendingMachineError> {
+        self.only_owner()?;
+        let owner = self.owner.get();
+        let amount = self.treasury();
+        self.vm().transfer_eth(owner, amount).map_err(|_| {
+            VendingMachineError::TransferFailed(TransferFailed { to: owner, amount })
+        })?;
+        log(self.vm(), Withdrawn { to: owner, amount });
+        Ok(amount)
+    }
+
+    /// Sets the per-user and global daily limits on free vends; zero lifts a limit.
+    ///
+    /// The per-user quota may not exceed `MAX_USER_DAILY_QUOTA`.
+    pub fn set_quotas(
+        &mut self,
+        user_daily_quota: u64,
+        global_daily_cap: u64,
+    ) -> Result<(), VendingMachineError> {
+        self.only_owner()?;
+        if user_daily_quota > MAX_USER_DAILY_QUOTA {
+            return Err(VendingMachineError::QuotaTooHigh(QuotaTooHigh {
+                max: U256::from(MAX_USER_DAILY_QUOTA),
+            }));
+        }
+        self.user_daily_quota.set(U64::from(user_daily_quota));
+        self.global_daily_cap.set(U64::from(global_daily_cap));
+        log(
+            self.vm(),
+            QuotasUpdated {
+                userDailyQuota: user_daily_quota,
+                globalDailyCap: global_daily_cap,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn set_cooldown(&mut self, cooldown_seconds: u64) -> Result<(), VendingMachineError> {
+        self.only_owner()?;
+        self.cooldown_seconds.set(U64::from(cooldown_seconds));
+        log(
+            self.vm(),
+            CooldownUpdated {
+                cooldownSeconds: cooldown_seconds,
+            },
+        );
+        Ok(())
+    }
+
+    /// Adds users exported from another deployment and returns how many were new.
+    ///
+    /// Users this machine already has are skipped, so a page can safely be sent twice. A last
+    /// vend time in the future fails the page with `InvalidSnapshot`.
+    pub fn import_users(&mut self, users: Vec<UserSnapshot>) -> Result<U256, VendingMachineError> {
+        self.only_owner()?;
+        if self.import_closed.get() {
+            return Err(VendingMachineError::ImportFinished(ImportFinished {}));
+        }
+        let now = U256::from(self.vm().block_timestamp());
+        let mut imported = 0u64;
+        for (user, balance, last_vend_time, lifetime) in users {
+            if self.users.getter(user).indexed.get() {
+                continue;
+            }
+            if last_vend_time > now {
+                return Err(VendingMachineError::InvalidSnapshot(InvalidSnapshot {
+                    user,
+                    lastVendTime: last_vend_time,
+                }));
+            }
+            self.ensure_room(user)?;
+            let mut record = self.users.setter(user);
+            record.balance.set(balance);
+            record.last_vend_time.set(last_vend_time);
+            self.rank_holder(user, balance);
+            let mut record = self.users.setter(user);
+            record.lifetime_cupcakes.set(lifetime);
+            record
+                .tier
+                .set(U8::from(u8::from(Tier::for_lifetime(lifetime))));
+            record.indexed.set(true);
+            self.user_index.push(user);
+            imported += 1;
+        }
+        log(
+            self.vm(),
+            UsersImported {
+                count: U256::from(imported),
+            },
+        );
+        Ok(U256::from(imported))
+    }
+
+    /// Ends importing; `import_users` fails from now on.
+    pub fn close_import(&mut self) -> Result<(), VendingMachineError> {
+        self.only_owner()?;
+        self.import_closed.set(true);
+        log(self.vm(), ImportClosed {});
+        Ok(())
+    }
+
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), VendingMachineError> {
+        self.only_owner()?;
+        if new_owner.is_zero() {
+            return Err(VendingMachineError::InvalidOwner(InvalidOwner {
+                owner: new_owner,
+            }));
+        }
+        let previous_owner = self.owner.get();
+        self.owner.set(new_owner);
+        log(
+            self.vm(),
+            OwnershipTransferred {
+                previousOwner: previous_owner,
+                newOwner: new_owner,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn pause(&mut self) -> Result<(), VendingMachineError> {
+        self.only_owner()?;
+        self.pausable.pause();
+        Ok(())
+    }
+
+    pub fn unpause(&mut self) -> Result<(), VendingMachineError> {
+        self.only_owner()?;
+        self.pausable.unpause();
+        Ok(())
+    }
+}
+
+impl VendingMachine {
+    fn only_owner(&self) -> Result<(), VendingMachineError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(VendingMachineError::NotOwner(NotOwner { caller }));
+        }
+        Ok(())
+    }
+
+    fn when_not_paused(&self) -> Result<(), VendingMachineError> {
+        self.pausable
+            .when_not_paused()
+            .map_err(|_| VendingMachineError::MachinePaused(MachinePaused {}))
+    }
+
+    /// Takes payment for one cupcake; `buy_cupcake` runs it behind the reentrancy guard.
+    fn sell_cupcake(&mut self) -> Result<(), VendingMachineError> {
+        self.when_not_paused()?;
+        let caller = self.vm().msg_sender();
+        let sent = self.vm().msg_value();
+        let price = self.price.get();
+        if sent < price {
+            return Err(VendingMachineError::InsufficientPayment(
+                InsufficientPayment { price, sent },
+            ));
+        }
+        self.ensure_room(caller)?;
+
+        let collected = self.total_collected.get() + price;
+        self.total_collected.set(collected);
+        self.credit_cupcake(caller)?;
+
+        let refund = sent - price;
+        if !refund.is_zero() {
+            self.vm().transfer_eth(caller, refund).map_err(|_| {
+                VendingMachineError::TransferFailed(TransferFailed {
+                    to: caller,
+                    amount: refund,
+                })
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Sells one of catalog item `id`; `vend_item` runs it behind the reentrancy guard.
+    fn sell_item(&mut self, id: U256) -> Result<(), VendingMachineError> {
+        self.when_not_paused()?;
+        self.ensure_item(id)?;
+        let caller = self.vm().msg_sender();
+        let now = self.vm().block_timestamp();
+        let item = self.items.getter(id);
+        let (price, stock) = (item.price.get(), item.stock.get());
+        let cooldown = item.cooldown_seconds.get().to::<u64>();
+        if stock.is_zero() {
+            return Err(VendingMachineError::OutOfStock(OutOfStock { id }));
+        }
+        let last_time = self.users.getter(caller).item_last_vend.get(id);
+        let ready_at = last_time.saturating_to::<u64>().saturating_add(cooldown);
+        if last_time > U256::ZERO && now < ready_at {
+            return Err(VendingMachineError::CooldownActive(CooldownActive {
+                remaining: U256::from(ready_at - now),
+            }));
+        }
+        let sent = self.vm().msg_value();
+        if sent < price {
+            return Err(VendingMachineError::InsufficientPayment(
+                InsufficientPayment { price, sent },
+            ));
+        }
+
+        self.items.setter(id).stock.set(stock - U256::from(1));
+        let mut record = self.users.setter(caller);
+        record.item_last_vend.setter(id).set(U256::from(now));
+        let new_balance = record.item_balances.get(id) + U256::from(1);
+        record.item_balances.setter(id).set(new_balance);
+        let collected = self.total_collected.get() + price;
+        self.total_collected.set(collected);
+        log(
+            self.vm(),
+            ItemVended {
+                user: caller,
+                id,
+                newBalance: new_balance,
+            },
+        );
+
+        let refund = sent - price;
+        if !refund.is_zero() {
+            self.vm().transfer_eth(caller, refund).map_err(|_| {
+                VendingMachineError::TransferFailed(TransferFailed {
+                    to: caller,
+                    amount: refund,
+                })
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Buys credits for `beneficiary`; `sponsor` runs it behind the reentrancy guard.
+    fn fund_sponsorship(&mut self, beneficiary: Address) -> Result<U256, VendingMachineError> {
+        self.when_not_paused()?;
+        if beneficiary.is_zero() {
+            return Err(VendingMachineError::InvalidRecipient(InvalidRecipient {
+                to: beneficiary,
+            }));
+        }
+        let price = self.price.get();
+        if price.is_zero() {
+            return Err(VendingMachineError::SponsorshipUnavailable(
+                SponsorshipUnavailable {},
+            ));
+        }
+        let sponsor = self.vm().msg_sender();
+        let sent = self.vm().msg_value();
+        let credits = sent / price;
+        if credits.is_zero() {
+            return Err(VendingMachineError::InsufficientPayment(
+                InsufficientPayment { price, sent },
+            ));
+        }
+        let amount = credits * price;
+
+        let mut record = self.users.setter(beneficiary);
+        let total = record.sponsored_credits.get() + credits;
+        record.sponsored_credits.set(total);
+        let mut sponsorship = record.sponsorships.setter(sponsor);
+        let previous = sponsorship.credits.get();
+        sponsorship.credits.set(previous + credits);
+        let escrow = sponsorship.escrow.get() + amount;
+        sponsorship.escrow.set(escrow);
+        let (listed, position) = (sponsorship.listed.get(), sponsorship.position.get());
+        if !listed {
+            let position = U256::from(record.sponsors.len());
+            record.sponsors.push(sponsor);
+            let mut sponsorship = record.sponsorships.setter(sponsor);
+            sponsorship.listed.set(true);
+            sponsorship.position.set(position);
+        } else if previous.is_zero() && position < record.sponsor_cursor.get() {
+            // A returning sponsor keeps their first place, which the cursor may have passed.
+            record.sponsor_cursor.set(position);
+        }
+        let held = self.sponsor_escrow.get() + amount;
+        self.sponsor_escrow.set(held);
+        log(
+            self.vm(),
+            Sponsored {
+                sponsor,
+                beneficiary,
+                credits,
+                amount,
+            },
+        );
+
+        let refund = sent - amount;
+        if !refund.is_zero() {
+            self.vm().transfer_eth(sponsor, refund).map_err(|_| {
+                VendingMachineError::TransferFailed(TransferFailed {
+                    to: sponsor,
+                    amount: refund,
+                })
+            })?;
+        }
+        Ok(credits)
+    }
+
+    /// Spends one of the caller's sponsored credits, drawing on the earliest sponsor that
+    /// still has some; `vend_free` runs it behind the reentrancy guard.
+    fn redeem_sponsorship(&mut self) -> Result<(), VendingMachineError> {
+        self.when_not_paused()?;
+        let user = self.vm().msg_sender();
+        self.ensure_room(user)?;
+
+        let mut record = self.users.setter(user);
+        let mut cursor = record.sponsor_cursor.get();
+        // Entries behind the cursor have run dry; refunded sponsors are skipped here.
+        let (sponsor, credits) = loop {
+            let Some(sponsor) = record.sponsors.get(cursor) else {
+                return Err(VendingMachineError::NoSponsorship(NoSponsorship {
+                    sponsor: Address::ZERO,
+                    beneficiary: user,
+                }));
+            };
+            let credits = record.sponsorships.getter(sponsor).credits.get();
+            if !credits.is_zero() {
+                break (sponsor, credits);
+            }
+            cursor += U256::from(1);
+        };
+        let mut sponsorship = record.sponsorships.setter(sponsor);
+        let escrow = sponsorship.escrow.get();
+        let share = escrow / credits;
+        sponsorship.credits.set(credits - U256::from(1));
+        sponsorship.escrow.set(escrow - share);
+        if credits == U256::from(1) {
+            cursor += U256::from(1);
+        }
+        record.sponsor_cursor.set(cursor);
+        let credits_left = record.sponsored_credits.get() - U256::from(1);
+        record.sponsored_credits.set(credits_left);
+
+        let held = self.sponsor_escrow.get() - share;
+        self.sponsor_escrow.set(held);
+        let collected = self.total_collected.get() + share;
+        self.total_collected.set(collected);
+        self.credit_cupcake(user)?;
+        log(
+            self.vm(),
+            SponsoredVend {
+                beneficiary: user,
+                sponsor,
+                creditsLeft: credits_left,
+            },
+        );
+
+        let refund = self.vm().msg_value();
+        if !refund.is_zero() {
+            self.vm().transfer_eth(user, refund).map_err(|_| {
+                VendingMachineError::TransferFailed(TransferFailed {
+                    to: user,
+                    amount: refund,
+                })
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Pays back the caller's unused credits for `beneficiary`; `refund_sponsorship` runs it
+    /// behind the reentrancy guard.
+    fn cancel_sponsorship(&mut self, beneficiary: Address) -> Result<U256, VendingMachineError> {
+        let sponsor = self.vm().msg_sender();
+        let mut record = self.users.setter(beneficiary);
+        let mut sponsorship = record.sponsorships.setter(sponsor);
+        let (credits, amount) = (sponsorship.credits.get(), sponsorship.escrow.get());
+        if credits.is_zero() {
+            return Err(VendingMachineError::NoSponsorship(NoSponsorship {
+                sponsor,
+                beneficiary,
+            }));
+        }
+        sponsorship.credits.set(U256::ZERO);
+        sponsorship.escrow.set(U256::ZERO);
+        let total = record.sponsored_credits.get() - credits;
+        record.sponsored_credits.set(total);
+        let held = self.sponsor_escrow.get() - amount;
+        self.sponsor_escrow.set(held);
+        log(
+            self.vm(),
+            SponsorshipRefunded {
+                sponsor,
+                beneficiary,
+                credits,
+                amount,
+            },
+        );
+
+        self.vm().transfer_eth(sponsor, amount).map_err(|_| {
+            VendingMachineError::TransferFailed(TransferFailed {
+                to: sponsor,
+                amount,
+            })
+        })?;
+        Ok(amount)
+    }
+
+    /// ETH the owner may withdraw: the balance less what is held for sponsored credits.
+    fn treasury(&self) -> U256 {
+        let balance = self.vm().balance(self.vm().contract_address());
+        balance.saturating_sub(self.sponsor_escrow.get())
+    }
+
+    fn ensure_item(&self, id: U256) -> Result<(), VendingMachineError> {
+        if id >= self.item_count.get() {
+            return Err(VendingMachineError::UnknownItem(UnknownItem { id }));
+        }
+        Ok(())
+    }
+
+    /// Fails if `user` is blocked, or not allowlisted while the machine is allowlist-only.
+    fn ensure_access(&self, user: Address) -> Result<(), VendingMachineError> {
+        if self.blocklist.get(user) {
+            return Err(VendingMachineError::Blocked(Blocked { user }));
+        }
+        if self.allowlist_only.get() && !self.allowlist.get(user) {
+            return Err(VendingMachineError::NotAllowlisted(NotAllowlisted { user }));
+        }
+        Ok(())
+    }
+
+    /// True if `caller`, the current `msg_sender`, is a contract: it has code, or it is not
+    /// the transaction's origin, which also catches contracts still in their constructor.
+    fn is_contract_caller(&self, caller: Address) -> bool {
+        caller != self.vm().tx_origin() || self.vm().code_size(caller) != 0
+    }
+
+    fn throttles_contracts(&self) -> bool {
+        self.caller_policy() == u8::from(CallerPolicy::Throttle)
+    }
+
+    /// Cooldown of a throttled contract caller, whatever its tier, saturating so a huge base
+    /// cooldown cannot wrap around to a short one.
+    fn contract_cooldown(&self) -> u64 {
+        self.cooldown_seconds
+            .get()
+            .to::<u64>()
+            .saturating_mul(CONTRACT_COOLDOWN_FACTOR)
+    }
+
+    /// Applies the caller policy to `caller` taking a free vend for themselves.
+    fn screen_caller(&self, caller: Address) -> Result<(), VendingMachineError> {
+        let policy = CallerPolicy::try_from(self.caller_policy())?;
+        if policy == CallerPolicy::Allow || !self.is_contract_caller(caller) {
+            return Ok(());
+        }
+        if policy == CallerPolicy::Block {
+            return Err(VendingMachineError::ContractCaller(ContractCaller { caller }));
+        }
+        // `cooldown_of` already throttles callers with code; this catches the ones that only
+        // give themselves away by not being `tx_origin`.
+        let last_time = self.last_vend_time(caller);
+        if last_time.is_zero() {
+            return Ok(());
+        }
+        let ready_at = last_time
+            .saturating_to::<u64>()
+            .saturating_add(self.contract_cooldown());
+        let remaining = ready_at.saturating_sub(self.vm().block_timestamp());
+        if remaining != 0 {
+            return Err(VendingMachineError::CooldownActive(CooldownActive {
+                remaining: U256::from(remaining),
+            }));
+        }
+        Ok(())
+    }
+
+    /// Fails with `MachineFull` if `user` would be a new user beyond `max_users`.
+    fn ensure_room(&self, user: Address) -> Result<(), VendingMachineError> {
+        let max_users = self.max_users.get().to::<u64>();
+        if max_users != 0
+            && !self.users.getter(user).indexed.get()
+            && self.user_index.len() as u64 >= max_users
+        {
+            return Err(VendingMachineError::MachineFull(MachineFull {
+                maxUsers: U256::from(max_users),
+            }));
+        }
+        Ok(())
+    }
+
+    /// Times of `user`'s free vends in the last 24 hours, oldest first.
+    fn recent_free_vends(&self, user: Address) -> Vec<u64> {
+        let record = self.users.getter(user);
+        let times = &record.free_vend_times;
+        let cursor = record.free_vend_cursor.get().to::<usize>();
+        let now = self.vm().block_timestamp();
+        (cursor..times.len())
+            .chain(0..cursor)
+            .filter_map(|slot| times.get(slot))
+            .map(|time| time.to::<u64>())
+            .filter(|time| time + DAY_SECONDS > now)
+            .collect()
+    }
+
+    /// Adds a free vend at `time` to `user`'s ring, overwriting the oldest once it is full.
+    fn record_free_vend(&mut self, user: Address, time: u64) {
+        let mut record = self.users.setter(user);
+        if (record.free_vend_times.len() as u64) < MAX_USER_DAILY_QUOTA {
+            record.free_vend_times.push(U64::from(time));
+            return;
+        }
+        let cursor = record.free_vend_cursor.get().to::<usize>();
+        if let Some(mut slot) = record.free_vend_times.setter(cursor) {
+            slot.set(U64::from(time));
+        }
+        let next = (cursor + 1) % MAX_USER_DAILY_QUOTA as usize;
+        record.free_vend_cursor.set(U64::from(next));
+    }
+
+    /// Returns `(day, vended)` for the current day bucket.
+    fn global_day(&self) -> (u64, u64) {
+        let day = self.vm().block_timestamp() / DAY_SECONDS;
+        if self.current_day.get().to::<u64>() == day {
+            (day, self.vended_today.get().to())
+        } else {
+            (day, 0)
+        }
+    }
+
+    /// Vends one cupcake to `user` if the access lists admit them, their tier's cooldown has
+    /// passed and the daily limits allow.
+    fn vend_to(&mut self, user: Address) -> Result<(), VendingMachineError> {
+        self.check_vend(user)?;
+        self.dispense(user)
+    }
+
+    /// Fails if `user` may not take a free vend right now. Writes nothing.
+    fn check_vend(&self, user: Address) -> Result<(), VendingMachineError> {
+        self.ensure_access(user)?;
+        let remaining = self.time_until_next_vend(user);
+        if !remaining.is_zero() {
+            return Err(VendingMachineError::CooldownActive(CooldownActive { remaining }));
+        }
+
+        let recent = self.recent_free_vends(user);
+        let quota = self.user_daily_quota.get().to::<usize>();
+        if quota != 0 && recent.len() >= quota {
+            // A slot frees up when the vend `quota` places back leaves the window.
+            return Err(VendingMachineError::UserQuotaExceeded(UserQuotaExceeded {
+                resetsAt: U256::from(recent[recent.len() - quota] + DAY_SECONDS),
+            }));
+        }
+        let (day, vended) = self.global_day();
+        let cap = self.global_daily_cap.get().to::<u64>();
+        if cap != 0 && vended >= cap {
+            return Err(VendingMachineError::DailySupplyExhausted(
+                DailySupplyExhausted {
+                    resetsAt: U256::from((day + 1) * DAY_SECONDS),
+                },
+            ));
+        }
+        self.ensure_room(user)
+    }
+
+    /// Hands `user` a free vend that `check_vend` has already allowed.
+    ///
+    /// Any error here comes after writes, so callers must let it revert the transaction.
+    fn dispense(&mut self, user: Address) -> Result<(), VendingMachineError> {
+        let current_time = self.vm().block_timestamp();
+        // Stamp the cooldown before any external call, so the token or badge contract
+        // cannot re-enter a vend for this user.
+        self.users
+            .setter(user)
+            .last_vend_time
+            .set(U256::from(current_time));
+        self.credit_cupcake(user)?;
+
+        self.record_free_vend(user, current_time);
+        let (day, vended) = self.global_day();
+        self.current_day.set(U64::from(day));
+        self.vended_today.set(U64::from(vended + 1));
+        self.enter_raffle(user);
+        Ok(())
+    }
+
+    /// Gives `user` a ticket in the configured raffle, if any.
+    fn enter_raffle(&mut self, user: Address) {
+        let raffle = self.raffle.get();
+        if raffle.is_zero() {
+            return;
+        }
+        let call = IVendRaffle::enterCall { user };
+        // Best effort: a raffle that reverts costs the user a ticket, not their cupcake.
+        let _ = self
+            .vm()
+            .call(&calls::context::Call::new(), raffle, &call.abi_encode());
+    }
+
+    /// Moves `holder`, whose ledger balance is now `balance`, to its place on the leaderboard.
+    fn rank_holder(&mut self, holder: Address, balance: U256) {
+        let len = self.top_holders.len();
+        let mut slot = match (0..len).find(|&i| self.top_holders.get(i) == Some(holder)) {
+            Some(i) => i,
+            None if len < MAX_TOP_HOLDERS => {
+                self.top_holders.push(holder);
+                len
+            }
+            None => {
+                let last = self.top_holders.get(len - 1).expect("leaderboard is full");
+                if self.users.getter(last).balance.get() >= balance {
+                    return;
+                }
+                self.top_holders
+                    .setter(len - 1)
+                    .expect("in bounds")
+                    .set(holder);
+                len - 1
+            }
+        };
+        while slot > 0 {
+            let above = self.top_holders.get(slot - 1).expect("in bounds");
+            if self.users.getter(above).balance.get() >= balance {
+                break;
+            }
+            self.top_holders.setter(slot).expect("in bounds").set(above);
+            self.top_holders
+                .setter(slot - 1)
+                .expect("in bounds")
+                .set(holder);
+            slot -= 1;
+        }
+        // Transfers lower the sender's balance, so a holder may have to move down instead.
+        while slot + 1 < self.top_holders.len() {
+            let below = self.top_holders.get(slot + 1).expect("in bounds");
+            if self.users.getter(below).balance.get() <= balance {
+                break;
+            }
+            self.top_holders.setter(slot).expect("in bounds").set(below);
+            self.top_holders
+                .setter(slot + 1)
+                .expect("in bounds")
+                .set(holder);
+            slot += 1;
+        }
+    }
+
+    /// Gives `user` one cupcake, in the ledger or as a minted token, indexing first-time users
+    /// and upgrading their tier once they cross a threshold.
+    fn credit_cupcake(&mut self, user: Address) -> Result<(), VendingMachineError> {
+        let token = self.cupcake_token.get();
+        if token.is_zero() {
+            let mut record = self.users.setter(user);
+            let new_balance = record.balance.get() + U256::from(1);
+            record.balance.set(new_balance);
+            self.rank_holder(user, new_balance);
+            log(
+                self.vm(),
+                CupcakeVended {
+                    user,
+                    newBalance: new_balance,
+                },
+            );
+        } else {
+            self.mint_token(token, user)?;
+        }
+        let total_vends = self.total_vends.get() + U256::from(1);
+        self.total_vends.set(total_vends);
+
+        let mut record = self.users.setter(user);
+        if !record.indexed.get() {
+            record.indexed.set(true);
+            self.user_index.push(user);
+        }
+
+        let mut record = self.users.setter(user);
+        let lifetime = record.lifetime_cupcakes.get() + U256::from(1);
+        record.lifetime_cupcakes.set(lifetime);
+        let tier = Tier::for_lifetime(lifetime);
+        if tier > Tier::from(record.tier.get().to::<u8>()) {
+            record.tier.set(U8::from(u8::from(tier)));
+            log(
+                self.vm(),
+                TierUpgraded {
+                    user,
+                    tier: tier.into(),
+                },
+            );
+        }
+        // Last, once the cupcake is on the books, like any other external effect.
+        if let Some(&milestone) = BADGE_MILESTONES.iter().find(|&&m| U256::from(m) == lifetime) {
+            self.award_badge(user, milestone)?;
+        }
+        Ok(())
+    }
+
+    /// Mints `user` the badge for `milestone` on the configured badge contract, if any.
+    fn award_badge(&mut self, user: Address, milestone: u64) -> Result<(), VendingMachineError> {
+        let badges = self.badges.get();
+        if badges.is_zero() {
+            return Ok(());
+        }
+        let call = IAchievementBadges::awardCall { user, milestone };
+        self.vm()
+            .call(&calls::context::Call::new(), badges, &call.abi_encode())
+            .map_err(|err| {
+                let reason = match err {
+                    calls::errors::Error::Revert(data) => data.into(),
+                    _ => Default::default(),
+                };
+                VendingMachineError::BadgeMintFailed(BadgeMintFailed {
+                    badges,
+                    milestone,
+                    reason,
+                })
+            })?;
+        Ok(())
+    }
+
+    fn mint_token(&mut self, token: Address, user: Address) -> Result<(), VendingMachineError> {
+        let amount = U256::from(CUPCAKE_TOKEN_UNIT);
+        let call = ICupcakeToken::mintCall {
+            to: user,
+            value: amount,
+        };
+        self.vm()
+            .call(&calls::context::Call::new(), token, &call.abi_encode())
+            .map_err(|err| {
+                let reason = match err {
+                    calls::errors::Error::Revert(data) => data.into(),
+                    _ => Default::default(),
+                };
+                VendingMachineError::TokenMintFailed(TokenMintFailed { token, reason })
+            })?;
+        log(
+            self.vm(),
+            CupcakeMinted {
+                user,
+                token,
+                amount,
+            },
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::{SolError, SolEvent, SolValue};
+    use stylus_sdk::abi::Router;
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::new([0xee; 20]);
+    const VEND_COOLDOWN_SECONDS: u64 = 60;
+
+    fn setup() -> (TestVM, VendingMachine, Address) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(1_000_000);
+        let mut contract = VendingMachine::from(&vm);
+        contract.constructor(OWNER, VEND_COOLDOWN_SECONDS, 0);
+        let user = Address::from([0x01; 20]);
+        vm.set_sender(user);
+        (vm, contract, user)
+    }
+
+    /// Sends `value` with the next call. TestVM sets `msg.value` without adding it to the
+    /// contract's balance, so `pay` adds it, as a real transfer would.
+    fn pay(vm: &TestVM, value: U256) {
+        let balance = vm.balance(vm.contract_address());
+        vm.set_balance(vm.contract_address(), balance + value);
+        vm.set_value(value);
+    }
+
+    #[test]
+    fn test_initial_vend_succeeds() {
+        let (_vm, mut contract, user) = setup();
+        assert_eq!(contract.balance_of(user), U256::ZERO);
+        contract.vend().unwrap();
+        assert_eq!(contract.balance_of(user), U256::from(1));
+    }
+
+    #[test]
+    fn test_vend_fails_when_too_soon() {
+        let (vm, mut contract, user) = setup();
+        contract.vend().unwrap(); // Last vend time is now 1,000,000
+
+        vm.set_block_timestamp(vm.block_timestamp() + 30); // Timestamp is now 1,000,030
+        let result = contract.vend();
+        assert!(matches!(
+            result,
+            Err(VendingMachineError::CooldownActive(CooldownActive { remaining }))
+                if remaining == U256::from(30)
+        ));
+        assert_eq!(contract.balance_of(user), U256::from(1));
+    }
+
+    #[test]
+    fn test_vend_succeeds_after_cooldown() {
+        let (vm, mut contract, user) = setup();
+        contract.vend().unwrap();
+
+        vm.set_block_timestamp(vm.block_timestamp() + VEND_COOLDOWN_SECONDS + 1);
+        contract.vend().unwrap();
+        assert_eq!(contract.balance_of(user), U256::from(2));
+    }
+
+    #[test]
+    fn test_cooldown_views_count_down_to_next_vend() {
+        let (vm, mut contract, user) = setup();
+        let stranger = Address::from([0x77; 20]);
+        assert_eq!(contract.last_vend_time(stranger), U256::ZERO);
+        assert_eq!(contract.time_until_next_vend(stranger), U256::ZERO);
+
+        let start = vm.block_timestamp();
+        contract.vend().unwrap();
+        assert_eq!(contract.last_vend_time(user), U256::from(start));
+        assert_eq!(
+            contract.time_until_next_vend(user),
+            U256::from(VEND_COOLDOWN_SECONDS)
+        );
+
+        vm.set_block_timestamp(start + VEND_COOLDOWN_SECONDS - 1);
+        assert_eq!(contract.time_until_next_vend(user), U256::from(1));
+        // Exactly at the boundary the wait is over and `vend` agrees.
+        vm.set_block_timestamp(start + VEND_COOLDOWN_SECONDS);
+        assert_eq!(contract.time_until_next_vend(user), U256::ZERO);
+        contract.vend().unwrap();
+        assert_eq!(
+            contract.last_vend_time(user),
+            U256::from(start + VEND_COOLDOWN_SECONDS)
+        );
+    }
+
+    #[test]
+    fn test_cooldown_views_saturate_for_huge_values() {
+        let (vm, mut contract, user) = setup();
+        let now = vm.block_timestamp();
+        vm.set_sender(OWNER);
+        contract.set_cooldown(u64::MAX).unwrap();
+        vm.set_sender(user);
+        contract.vend().unwrap();
+        let remaining = U256::from(u64::MAX - now);
+        assert_eq!(contract.time_until_next_vend(user), remaining);
+        assert!(matches!(
+            contract.vend(),
+            Err(VendingMachineError::CooldownActive(CooldownActive { remaining: left }))
+                if left == remaining
+        ));
+
+        // A stamp beyond u64 reads as the last representable second, not a panic.
+        contract.users.setter(user).last_vend_time.set(U256::MAX);
+        assert_eq!(contract.time_until_next_vend(user), remaining);
+    }
+
+    #[test]
+    fn test_item_cooldown_saturates() {
+        let (vm, mut contract, user) = setup();
+        vm.set_sender(OWNER);
+        let id = contract
+            .add_item("gum".into(), U256::ZERO, U256::from(2), u64::MAX)
+            .unwrap();
+        vm.set_sender(user);
+        contract.vend_item(id).unwrap();
+        let remaining = U256::from(u64::MAX - vm.block_timestamp());
+        assert!(matches!(
+            contract.vend_item(id),
+            Err(VendingMachineError::CooldownActive(CooldownActive { remaining: left }))
+                if left == remaining
+        ));
+    }
+
+    #[test]
+    fn test_vend_emits_cupcake_vended() {
+        let (vm, mut contract, user) = setup();
+        contract.vend().unwrap();
+
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.len(), 1);
+        let (topics, data) = &logs[0];
+        assert_eq!(topics[0], CupcakeVended::SIGNATURE_HASH);
+        let event = CupcakeVended::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!(event.user, user);
+        assert_eq!(event.newBalance, U256::from(1));
+    }
+
+    #[test]
+    fn test_failed_vend_emits_nothing() {
+        let (vm, mut contract, _user) = setup();
+        contract.vend().unwrap();
+        contract.vend().unwrap_err();
+        assert_eq!(vm.get_emitted_logs().len(), 1);
+    }
+
+    #[test]
+    fn test_many_users_are_never_evicted() {
+        let (vm, mut contract, _user) = setup();
+        let users: Vec<Address> = (1..=50u8).map(|i| Address::from([i; 20])).collect();
+        for user in &users {
+            vm.set_sender(*user);
+            contract.vend().unwrap();
+        }
+
+        for user in &users {
+            assert_eq!(contract.balance_of(*user), U256::from(1));
+        }
+        assert_eq!(contract.user_index.len(), users.len());
+    }
+
+    #[test]
+    fn test_user_index_lists_each_user_once() {
+        let (vm, mut contract, user) = setup();
+        contract.vend().unwrap();
+        vm.set_block_timestamp(vm.block_timestamp() + VEND_COOLDOWN_SECONDS);
+        contract.vend().unwrap();
+
+        assert_eq!(contract.user_index.len(), 1);
+        assert_eq!(contract.user_index.get(0), Some(user));
+    }
+
+    #[test]
+    fn test_get_all_users_lists_balances_and_vend_times() {
+        let (vm, mut contract, user) = setup();
+        assert!(contract.get_all_users().is_empty());
+        contract.vend().unwrap();
+        let start = vm.block_timestamp();
+        let other = Address::from([0x02; 20]);
+        vm.set_sender(other);
+        vm.set_block_timestamp(start + 5);
+        contract.vend().unwrap();
+        vm.set_sender(user);
+        vm.set_block_timestamp(start + VEND_COOLDOWN_SECONDS);
+        contract.vend().unwrap();
+
+        assert_eq!(contract.user_count(), U256::from(2));
+        assert_eq!(
+            contract.get_all_users(),
+            vec![
+                (
+                    user,
+                    U256::from(2),
+                    U256::from(start + VEND_COOLDOWN_SECONDS)
+                ),
+                (other, U256::from(1), U256::from(start + 5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_top_holders_sorted_and_capped() {
+        let (vm, mut contract, _user) = setup();
+        vm.set_sender(OWNER);
+        let holder = |i: u8| Address::from([0x10 + i; 20]);
+        let snapshots = (0..12u8)
+            .map(|i| (holder(i), U256::from(10 + i), U256::ZERO, U256::ZERO))
+            .collect();
+        contract.import_users(snapshots).unwrap();
+        let board = contract.top_holders();
+        assert_eq!(board.len(), MAX_TOP_HOLDERS);
+        assert_eq!(board[0], (holder(11), U256::from(21)));
+        assert_eq!(board[9], (holder(2), U256::from(12)));
+
+        // Vending moves an existing holder up; a dropped holder can climb back on.
+        for (i, vends) in [(5u8, 6u64), (1, 12)] {
+            vm.set_sender(holder(i));
+            for _ in 0..vends {
+                vm.set_block_timestamp(vm.block_timestamp() + VEND_COOLDOWN_SECONDS);
+                contract.vend().unwrap();
+            }
+        }
+        let board = contract.top_holders();
+        assert_eq!(board[0], (holder(1), U256::from(23)));
+        assert_eq!(board[1], (holder(11), U256::from(21)));
+        assert_eq!(board[2], (holder(5), U256::from(21)));
+        assert_eq!(board[3], (holder(10), U256::from(20)));
+        assert_eq!(board.len(), MAX_TOP_HOLDERS);
+        assert!(!board.iter().any(|(who, _)| *who == holder(2)));
+    }
+
+    #[test]
+    fn test_transfer_cupcake_moves_ledger_balances() {
+        let (vm, mut contract, user) = setup();
+        let friend = Address::from([0x02; 20]);
+        for _ in 0..3 {
+            contract.vend().unwrap();
+            vm.set_block_timestamp(vm.block_timestamp() + VEND_COOLDOWN_SECONDS);
+        }
+        assert!(matches!(
+            contract.transfer_cupcake(friend, U256::from(4)),
+            Err(VendingMachineError::InsufficientBalance(InsufficientBalance { balance, .. }))
+                if balance == U256::from(3)
+        ));
+        for to in [Address::ZERO, user] {
+            assert!(matches!(
+                contract.transfer_cupcake(to, U256::from(1)),
+                Err(VendingMachineError::InvalidRecipient(_))
+            ));
+        }
+
+        contract.transfer_cupcake(friend, U256::from(2)).unwrap();
+        assert_eq!(contract.balance_of(user), U256::from(1));
+        assert_eq!(contract.balance_of(friend), U256::from(2));
+        // The recipient gets a record but no lifetime credit.
+        assert_eq!(contract.user_count(), U256::from(2));
+        assert_eq!(contract.lifetime_cupcakes(friend), U256::ZERO);
+        assert_eq!(
+            contract.top_holders(),
+            vec![(friend, U256::from(2)), (user, U256::from(1))]
+        );
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = logs.last().unwrap();
+        let event =
+            CupcakeTransferred::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!(
+            (event.from, event.to, event.amount),
+            (user, friend, U256::from(2))
+        );
+
+        vm.set_sender(friend);
+        contract.transfer_cupcake(user, U256::from(2)).unwrap();
+        assert_eq!(contract.user_count(), U256::from(2));
+        assert_eq!(
+            contract.top_holders(),
+            vec![(user, U256::from(3)), (friend, U256::ZERO)]
+        );
+    }
+
+    #[test]
+    fn test_zero_transfer_indexes_nobody() {
+        let (vm, mut contract, _user) = setup();
+        // A caller with no record sends nothing to a stranger.
+        vm.set_sender(Address::from([0x03; 20]));
+        assert!(matches!(
+            contract.transfer_cupcake(Address::from([0x04; 20]), U256::ZERO),
+            Err(VendingMachineError::ZeroTransfer(_))
+        ));
+        assert_eq!(contract.user_count(), U256::ZERO);
+        assert!(contract.top_holders().is_empty());
+    }
+
+    #[test]
+    fn test_constructor_sets_owner_and_default_cooldown() {
+        let (_vm, contract, _user) = setup();
+        assert_eq!(contract.owner(), OWNER);
+        assert_eq!(contract.cooldown(), VEND_COOLDOWN_SECONDS);
+        assert!(!contract.paused());
+    }
+
+    #[test]
+    fn test_constructor_runs_only_once() {
+        let vm = TestVM::default();
+        let mut contract = VendingMachine::from(&vm);
+        let init = (OWNER, 30u64, 2u64).abi_encode_params();
+        let deploy = <VendingMachine as Router<VendingMachine>>::constructor;
+        assert!(deploy(&mut contract, &init).unwrap().is_ok());
+        assert_eq!(contract.cooldown(), 30);
+        assert_eq!(contract.max_users(), 2);
+
+        let attacker = Address::from([0x66; 20]);
+        let reinit = (attacker, 0u64, 0u64).abi_encode_params();
+        assert!(deploy(&mut contract, &reinit).unwrap().is_err());
+        assert_eq!(contract.owner(), OWNER);
+        assert_eq!(contract.max_users(), 2);
+    }
+
+    #[test]
+    fn test_max_users_turns_away_newcomers() {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(1_000_000);
+        let mut contract = VendingMachine::from(&vm);
+        contract.constructor(OWNER, VEND_COOLDOWN_SECONDS, 2);
+        let users: Vec<Address> = (1..=3u8).map(|i| Address::from([i; 20])).collect();
+        for user in &users[..2] {
+            vm.set_sender(*user);
+            contract.vend().unwrap();
+        }
+
+        vm.set_sender(users[2]);
+        assert!(matches!(
+            contract.vend(),
+            Err(VendingMachineError::MachineFull(MachineFull { maxUsers }))
+                if maxUsers == U256::from(2)
+        ));
+        vm.set_value(U256::from(CUPCAKE_PRICE_WEI));
+        assert!(matches!(
+            contract.buy_cupcake(),
+            Err(VendingMachineError::MachineFull(_))
+        ));
+        assert_eq!(contract.total_collected(), U256::ZERO);
+
+        // Existing users are unaffected.
+        vm.set_sender(users[0]);
+        contract.buy_cupcake().unwrap();
+        assert_eq!(contract.balance_of(users[0]), U256::from(2));
+        assert_eq!(contract.user_index.len(), 2);
+    }
+
+    #[test]
+    fn test_owner_can_change_cooldown() {
+        let (vm, mut contract, user) = setup();
+        vm.set_sender(OWNER);
+        contract.set_cooldown(10).unwrap();
+        assert_eq!(contract.cooldown(), 10);
+
+        vm.set_sender(user);
+        contract.vend().unwrap();
+        vm.set_block_timestamp(vm.block_timestamp() + 10);
+        contract.vend().unwrap();
+        assert_eq!(contract.balance_of(user), U256::from(2));
+    }
 
-const VEND_COOLDOWN_SECONDS: u64 = 60;
+    #[test]
+    fn test_pause_blocks_vending_until_unpaused() {
+        let (vm, mut contract, user) = setup();
+        vm.set_sender(OWNER);
+        contract.pause().unwrap();
+        assert!(contract.paused());
 
-sol_storage! {
-    #[entrypoint]
-    pub struct VendingMachine {
-        mapping(address => uint256) cupcake_balances;
-        mapping(address => uint256) last_vend_time;
+        vm.set_sender(user);
+        assert!(matches!(
+            contract.vend(),
+            Err(VendingMachineError::MachinePaused(_))
+        ));
+
+        vm.set_sender(OWNER);
+        contract.unpause().unwrap();
+        vm.set_sender(user);
+        contract.vend().unwrap();
+        assert_eq!(contract.balance_of(user), U256::from(1));
     }
-}
 
-#[public]
-impl VendingMachine {
-    pub fn vend(&mut self) -> Result<(), Vec<u8>> {
-        let caller = self.vm().msg_sender();
-        let last_time = self.last_vend_time.get(caller);
-        let current_time = self.vm().block_timestamp();
+    #[test]
+    fn test_buy_cupcake_rejects_reentry() {
+        let (vm, mut contract, user) = setup();
+        vm.set_value(U256::from(CUPCAKE_PRICE_WEI));
+        // Stand in for a buyer whose refund callback calls back into `buy_cupcake`.
+        contract.reentrancy.enter().unwrap();
+        assert!(matches!(
+            contract.buy_cupcake(),
+            Err(VendingMachineError::ReentrantCall(_))
+        ));
+        contract.reentrancy.exit();
+        contract.buy_cupcake().unwrap();
+        assert!(!contract.reentrancy.entered());
+        assert_eq!(contract.balance_of(user), U256::from(1));
+    }
 
-        // This logic is now correct because our test setup will be more realistic.
-        if last_time > U256::ZERO && current_time < last_time.to::<u64>() + VEND_COOLDOWN_SECONDS {
-            return Err("Cooldown: Please wait before requesting another cupcake.".into());
-        }
+    #[test]
+    fn test_admin_functions_are_owner_only() {
+        let (_vm, mut contract, user) = setup();
+        assert!(matches!(
+            contract.set_cooldown(0),
+            Err(VendingMachineError::NotOwner(NotOwner { caller })) if caller == user
+        ));
+        assert!(contract.pause().is_err());
+        assert!(contract.unpause().is_err());
+        assert_eq!(contract.cooldown(), VEND_COOLDOWN_SECONDS);
+        assert!(!contract.paused());
+    }
 
-        self.last_vend_time.insert(caller, U256::from(current_time));
+    #[test]
+    fn test_errors_abi_encode_as_custom_errors() {
+        let (_vm, mut contract, _user) = setup();
+        contract.vend().unwrap();
+        let encoded: Vec<u8> = contract.vend().unwrap_err().into();
+        assert_eq!(encoded[..4], CooldownActive::SELECTOR);
+        let decoded = CooldownActive::abi_decode(&encoded, true).unwrap();
+        assert_eq!(decoded.remaining, U256::from(VEND_COOLDOWN_SECONDS));
+    }
 
-        let mut balance = self.cupcake_balances.setter(caller);
-        let new_balance = balance.get() + U256::from(1);
-        balance.set(new_balance);
+    #[test]
+    fn test_buy_cupcake_refunds_excess() {
+        let (vm, mut contract, user) = setup();
+        let price = U256::from(CUPCAKE_PRICE_WEI);
+        pay(&vm, price * U256::from(3));
+        contract.buy_cupcake().unwrap();
 
-        Ok(())
+        assert_eq!(contract.balance_of(user), U256::from(1));
+        assert_eq!(vm.balance(user), price * U256::from(2));
+        assert_eq!(contract.total_collected(), price);
+        assert_eq!(contract.user_index.len(), 1);
     }
 
-    pub fn balance_of(&self, user: Address) -> U256 {
-        self.cupcake_balances.get(user)
+    #[test]
+    fn test_machine_status_summarizes_the_machine() {
+        let (vm, mut contract, user) = setup();
+        let price = U256::from(CUPCAKE_PRICE_WEI);
+        let (stock, status_price, paused, total_vends, users, treasury) = contract.machine_status();
+        assert_eq!(stock, U256::from(DEFAULT_GLOBAL_DAILY_CAP));
+        assert_eq!((status_price, paused), (price, false));
+        assert_eq!(
+            (total_vends, users, treasury),
+            (U256::ZERO, U256::ZERO, U256::ZERO)
+        );
+
+        contract.vend().unwrap();
+        vm.set_sender(Address::from([0x02; 20]));
+        vm.set_balance(vm.contract_address(), price);
+        vm.set_value(price);
+        contract.buy_cupcake().unwrap();
+        vm.set_value(U256::ZERO);
+        vm.set_sender(user);
+        vm.set_block_timestamp(vm.block_timestamp() + VEND_COOLDOWN_SECONDS);
+        contract.vend().unwrap();
+
+        let (stock, _, _, total_vends, users, treasury) = contract.machine_status();
+        // Bought cupcakes do not draw on the free daily supply.
+        assert_eq!(stock, U256::from(DEFAULT_GLOBAL_DAILY_CAP - 2));
+        assert_eq!(
+            (total_vends, users, treasury),
+            (U256::from(3), U256::from(2), price)
+        );
+
+        vm.set_sender(OWNER);
+        contract.set_quotas(DEFAULT_USER_DAILY_QUOTA, 0).unwrap();
+        contract.pause().unwrap();
+        let (stock, _, paused, ..) = contract.machine_status();
+        assert_eq!((stock, paused), (U256::MAX, true));
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use stylus_sdk::testing::*;
+    #[test]
+    fn test_buy_cupcake_rejects_underpayment() {
+        let (vm, mut contract, user) = setup();
+        vm.set_value(U256::from(CUPCAKE_PRICE_WEI - 1));
+        assert!(matches!(
+            contract.buy_cupcake(),
+            Err(VendingMachineError::InsufficientPayment(InsufficientPayment { sent, .. }))
+                if sent == U256::from(CUPCAKE_PRICE_WEI - 1)
+        ));
+        assert_eq!(contract.balance_of(user), U256::ZERO);
+    }
+
+    #[test]
+    fn test_purchases_skip_cooldown_and_index_once() {
+        let (vm, mut contract, user) = setup();
+        contract.vend().unwrap();
+        vm.set_value(U256::from(CUPCAKE_PRICE_WEI));
+        contract.buy_cupcake().unwrap();
+        contract.buy_cupcake().unwrap();
+        assert_eq!(contract.balance_of(user), U256::from(3));
+        assert_eq!(contract.user_index.len(), 1);
+    }
+
+    #[test]
+    fn test_owner_withdraws_proceeds() {
+        let (vm, mut contract, _user) = setup();
+        vm.set_balance(vm.contract_address(), U256::from(5_000));
+        assert!(matches!(
+            contract.withdraw(),
+            Err(VendingMachineError::NotOwner(_))
+        ));
+
+        vm.set_sender(OWNER);
+        assert_eq!(contract.withdraw().unwrap(), U256::from(5_000));
+        assert_eq!(vm.balance(OWNER), U256::from(5_000));
+        assert_eq!(vm.balance(vm.contract_address()), U256::ZERO);
+
+        contract.set_price(U256::from(7)).unwrap();
+        assert_eq!(contract.price(), U256::from(7));
+    }
+
+    #[test]
+    fn test_vend_mints_token_when_configured() {
+        let (vm, mut contract, user) = setup();
+        let token = Address::from([0xcc; 20]);
+        vm.set_sender(OWNER);
+        contract.set_cupcake_token(token).unwrap();
 
-    fn setup() -> (TestVM, VendingMachine, Address) {
-        let vm = TestVM::default();
-        vm.set_block_timestamp(1_000_000); 
-        let contract = VendingMachine::from(&vm);
-        let user = Address::from([0x01; 20]);
         vm.set_sender(user);
-        (vm, contract, user)
+        contract.vend().unwrap();
+        assert_eq!(contract.balance_of(user), U256::ZERO);
+        assert_eq!(contract.user_index.len(), 1);
+
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = logs.last().unwrap();
+        let event = CupcakeMinted::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!((event.user, event.token), (user, token));
+        assert_eq!(event.amount, U256::from(CUPCAKE_TOKEN_UNIT));
     }
 
     #[test]
-    fn test_initial_vend_succeeds() {
+    fn test_token_revert_fails_the_vend() {
+        let (vm, mut contract, user) = setup();
+        let token = Address::from([0xcc; 20]);
+        vm.set_sender(OWNER);
+        contract.set_cupcake_token(token).unwrap();
+
+        let mint = ICupcakeToken::mintCall {
+            to: user,
+            value: U256::from(CUPCAKE_TOKEN_UNIT),
+        };
+        vm.mock_call(token, mint.abi_encode(), Err(b"not owner".to_vec()));
+        vm.set_sender(user);
+        assert!(matches!(
+            contract.vend(),
+            Err(VendingMachineError::TokenMintFailed(TokenMintFailed { reason, .. }))
+                if reason.as_ref() == b"not owner"
+        ));
+    }
+
+    #[test]
+    fn test_raffle_revert_does_not_fail_the_vend() {
+        let (vm, mut contract, user) = setup();
+        let raffle = Address::from([0xaa; 20]);
+        assert!(matches!(
+            contract.set_raffle(raffle),
+            Err(VendingMachineError::NotOwner(_))
+        ));
+        vm.set_sender(OWNER);
+        contract.set_raffle(raffle).unwrap();
+        assert_eq!(contract.raffle(), raffle);
+
+        let enter = IVendRaffle::enterCall { user };
+        vm.mock_call(raffle, enter.abi_encode(), Err(b"not machine".to_vec()));
+        vm.set_sender(user);
+        contract.vend().unwrap();
+        assert_eq!(contract.balance_of(user), U256::from(1));
+    }
+
+    #[test]
+    fn test_badge_revert_fails_the_milestone_vend() {
+        let (vm, mut contract, user) = setup();
+        let badges = Address::from([0xbb; 20]);
+        vm.set_sender(OWNER);
+        contract.set_badges(badges).unwrap();
+        assert_eq!(contract.badges(), badges);
+
+        vm.set_sender(user);
+        vm.set_value(U256::from(CUPCAKE_PRICE_WEI));
+        for _ in 0..9 {
+            contract.buy_cupcake().unwrap();
+        }
+        vm.set_value(U256::ZERO);
+        let award = IAchievementBadges::awardCall {
+            user,
+            milestone: 10,
+        };
+        vm.mock_call(badges, award.abi_encode(), Err(b"not machine".to_vec()));
+        // The error reverts the whole transaction on chain, which TestVM does not model.
+        assert!(matches!(
+            contract.vend(),
+            Err(VendingMachineError::BadgeMintFailed(BadgeMintFailed { milestone: 10, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_vend_for_aborts_on_failure_after_eligibility() {
+        let (vm, mut contract, user) = setup();
+        let token = Address::from([0xcc; 20]);
+        vm.set_sender(OWNER);
+        contract.set_cupcake_token(token).unwrap();
+        let mint = ICupcakeToken::mintCall {
+            to: user,
+            value: U256::from(CUPCAKE_TOKEN_UNIT),
+        };
+        vm.mock_call(token, mint.abi_encode(), Err(b"not owner".to_vec()));
+
+        // An eligible user whose mint fails takes the batch down rather than being skipped.
+        let other = Address::from([0x02; 20]);
+        assert!(matches!(
+            contract.vend_for(vec![other, user]),
+            Err(VendingMachineError::TokenMintFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_vend_for_reports_per_user_results() {
+        let (vm, mut contract, user) = setup();
+        contract.vend().unwrap();
+        let other = Address::from([0x02; 20]);
+
+        vm.set_sender(OWNER);
+        let results = contract.vend_for(vec![other, user, other]).unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results[0].0 && results[0].1.is_empty());
+        assert!(!results[1].0 && !results[2].0);
+        let decoded = CooldownActive::abi_decode(&results[1].1, true).unwrap();
+        assert_eq!(decoded.remaining, U256::from(VEND_COOLDOWN_SECONDS));
+
+        assert_eq!(contract.balance_of(other), U256::from(1));
+        assert_eq!(contract.balance_of(user), U256::from(1));
+    }
+
+    #[test]
+    fn test_vend_for_is_owner_only() {
         let (_vm, mut contract, user) = setup();
+        assert!(matches!(
+            contract.vend_for(vec![user]),
+            Err(VendingMachineError::NotOwner(_))
+        ));
         assert_eq!(contract.balance_of(user), U256::ZERO);
+    }
+
+    #[test]
+    fn test_purchases_upgrade_tier_once_per_threshold() {
+        let (vm, mut contract, user) = setup();
+        vm.set_value(U256::from(CUPCAKE_PRICE_WEI));
+        for _ in 1..SILVER_THRESHOLD {
+            contract.buy_cupcake().unwrap();
+        }
+        assert_eq!(contract.tier_of(user), u8::from(Tier::Bronze));
+        assert!(vm
+            .get_emitted_logs()
+            .iter()
+            .all(|(topics, _)| topics[0] != TierUpgraded::SIGNATURE_HASH));
+
+        contract.buy_cupcake().unwrap();
+        assert_eq!(contract.tier_of(user), u8::from(Tier::Silver));
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = logs.last().unwrap();
+        let event = TierUpgraded::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!((event.user, event.tier), (user, 1));
+
+        for _ in SILVER_THRESHOLD..GOLD_THRESHOLD {
+            contract.buy_cupcake().unwrap();
+        }
+        assert_eq!(contract.tier_of(user), u8::from(Tier::Gold));
+        assert_eq!(contract.lifetime_cupcakes(user), U256::from(GOLD_THRESHOLD));
+        let upgrades = vm
+            .get_emitted_logs()
+            .iter()
+            .filter(|(topics, _)| topics[0] == TierUpgraded::SIGNATURE_HASH)
+            .count();
+        assert_eq!(upgrades, 2);
+    }
+
+    #[test]
+    fn test_higher_tiers_wait_shorter_cooldowns() {
+        let (vm, mut contract, user) = setup();
+        assert_eq!(contract.cooldown_of(user), VEND_COOLDOWN_SECONDS);
+        contract
+            .users
+            .setter(user)
+            .tier
+            .set(U8::from(u8::from(Tier::Gold)));
+        assert_eq!(contract.cooldown_of(user), VEND_COOLDOWN_SECONDS / 4);
+
         contract.vend().unwrap();
-        assert_eq!(contract.balance_of(user), U256::from(1));
+        vm.set_block_timestamp(vm.block_timestamp() + VEND_COOLDOWN_SECONDS / 4 - 1);
+        assert!(matches!(
+            contract.vend(),
+            Err(VendingMachineError::CooldownActive(CooldownActive { remaining }))
+                if remaining == U256::from(1)
+        ));
+        vm.set_block_timestamp(vm.block_timestamp() + 1);
+        contract.vend().unwrap();
+        assert_eq!(contract.balance_of(user), U256::from(2));
     }
 
     #[test]
-    fn test_vend_fails_when_too_soon() {
+    fn test_tier_round_trips_through_u8() {
+        for tier in [Tier::Bronze, Tier::Silver, Tier::Gold] {
+            assert_eq!(Tier::from(u8::from(tier)), tier);
+        }
+        assert_eq!(Tier::from(3), Tier::Bronze);
+        assert_eq!(Tier::from(u8::MAX), Tier::Bronze);
+        assert_eq!(
+            Tier::for_lifetime(U256::from(SILVER_THRESHOLD - 1)),
+            Tier::Bronze
+        );
+        assert_eq!(Tier::for_lifetime(U256::from(GOLD_THRESHOLD)), Tier::Gold);
+    }
+
+    #[test]
+    fn test_referral_credits_referrer_once() {
+        let (vm, mut contract, referrer) = setup();
+        contract.vend().unwrap();
+        let newcomer = Address::from([0x02; 20]);
+        vm.set_sender(newcomer);
+        contract.vend_with_referral(referrer).unwrap();
+
+        assert_eq!(contract.balance_of(newcomer), U256::from(1));
+        assert_eq!(
+            contract.balance_of(referrer),
+            U256::from(1 + REFERRAL_BONUS)
+        );
+        assert_eq!(contract.referrer_of(newcomer), referrer);
+        assert_eq!(contract.referral_count(referrer), U256::from(1));
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = logs.last().unwrap();
+        let event = ReferralCredited::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!((event.referrer, event.user), (referrer, newcomer));
+
+        // Once a customer, the newcomer cannot be referred again, even after the cooldown.
+        vm.set_block_timestamp(vm.block_timestamp() + VEND_COOLDOWN_SECONDS);
+        assert!(matches!(
+            contract.vend_with_referral(referrer),
+            Err(VendingMachineError::AlreadyCustomer(_))
+        ));
+        assert_eq!(contract.referral_count(referrer), U256::from(1));
+    }
+
+    #[test]
+    fn test_referral_rejects_self_and_circular_referrals() {
+        let (vm, mut contract, first) = setup();
+        assert!(matches!(
+            contract.vend_with_referral(first),
+            Err(VendingMachineError::SelfReferral(_))
+        ));
+        // Nobody has vended yet, so there is no customer to refer anyone.
+        let second = Address::from([0x02; 20]);
+        assert!(matches!(
+            contract.vend_with_referral(second),
+            Err(VendingMachineError::NotACustomer(_))
+        ));
+
+        contract.vend().unwrap();
+        vm.set_sender(second);
+        contract.vend_with_referral(first).unwrap();
+        // `first` was a customer before `second`, so `second` can never refer `first` back.
+        vm.set_sender(first);
+        vm.set_block_timestamp(vm.block_timestamp() + VEND_COOLDOWN_SECONDS);
+        assert!(matches!(
+            contract.vend_with_referral(second),
+            Err(VendingMachineError::AlreadyCustomer(_))
+        ));
+        assert_eq!(contract.referrer_of(first), Address::ZERO);
+        assert_eq!(contract.referral_count(second), U256::ZERO);
+    }
+
+    #[test]
+    fn test_user_quota_rolls_over_24_hours() {
         let (vm, mut contract, user) = setup();
-        contract.vend().unwrap(); // Last vend time is now 1,000,000
+        vm.set_sender(OWNER);
+        contract.set_quotas(2, 0).unwrap();
+        vm.set_sender(user);
 
-        vm.set_block_timestamp(vm.block_timestamp() + 30); // Timestamp is now 1,000,030
-        let result = contract.vend();
-        assert!(result.is_err()); // This will now pass
+        let first = vm.block_timestamp();
+        contract.vend().unwrap();
+        let second = first + DAY_SECONDS / 2;
+        vm.set_block_timestamp(second);
+        contract.vend().unwrap();
+        assert_eq!(contract.quota_used(user), 2);
+        vm.set_block_timestamp(second + VEND_COOLDOWN_SECONDS);
+        assert!(matches!(
+            contract.vend(),
+            Err(VendingMachineError::UserQuotaExceeded(UserQuotaExceeded { resetsAt }))
+                if resetsAt == U256::from(first + DAY_SECONDS)
+        ));
+
+        // Only the first vend has left the window; the second still counts.
+        vm.set_block_timestamp(first + DAY_SECONDS);
+        assert_eq!(contract.quota_used(user), 1);
+        contract.vend().unwrap();
+        vm.set_block_timestamp(first + DAY_SECONDS + VEND_COOLDOWN_SECONDS);
+        assert!(matches!(
+            contract.vend(),
+            Err(VendingMachineError::UserQuotaExceeded(UserQuotaExceeded { resetsAt }))
+                if resetsAt == U256::from(second + DAY_SECONDS)
+        ));
+    }
+
+    #[test]
+    fn test_user_quota_ring_wraps() {
+        let (vm, mut contract, user) = setup();
+        vm.set_sender(OWNER);
+        contract.set_quotas(MAX_USER_DAILY_QUOTA, 0).unwrap();
+        assert!(matches!(
+            contract.set_quotas(MAX_USER_DAILY_QUOTA + 1, 0),
+            Err(VendingMachineError::QuotaTooHigh(_))
+        ));
+        vm.set_sender(user);
+
+        let start = vm.block_timestamp();
+        for _ in 0..MAX_USER_DAILY_QUOTA + 5 {
+            contract.vend().unwrap();
+            vm.set_block_timestamp(vm.block_timestamp() + 3_600);
+        }
+        // The ring keeps 32 vends, but only the 23 of the last 24 hours count.
+        assert_eq!(contract.quota_used(user), 23);
+        vm.set_block_timestamp(start + 60 * 3_600);
+        assert_eq!(contract.quota_used(user), 0);
+    }
+
+    #[test]
+    fn test_global_cap_resets_at_day_boundary() {
+        let (vm, mut contract, _user) = setup();
+        vm.set_sender(OWNER);
+        contract.set_quotas(0, 2).unwrap();
+        // One second before midnight UTC.
+        let day_end = (vm.block_timestamp() / DAY_SECONDS + 1) * DAY_SECONDS;
+        vm.set_block_timestamp(day_end - 1);
+
+        let users: Vec<Address> = (1..=3u8).map(|i| Address::from([i; 20])).collect();
+        let results = contract.vend_for(users.clone()).unwrap();
+        assert!(results[0].0 && results[1].0 && !results[2].0);
+        let decoded = DailySupplyExhausted::abi_decode(&results[2].1, true).unwrap();
+        assert_eq!(decoded.resetsAt, U256::from(day_end));
+        assert_eq!(contract.vended_today(), 2);
+
+        vm.set_block_timestamp(day_end);
+        assert_eq!(contract.vended_today(), 0);
+        vm.set_sender(users[2]);
+        contract.vend().unwrap();
+        assert_eq!(contract.vended_today(), 1);
+    }
+
+    #[test]
+    fn test_quotas_skip_purchases_and_are_owner_only() {
+        let (vm, mut contract, user) = setup();
+        assert_eq!(
+            contract.daily_quotas(),
+            (DEFAULT_USER_DAILY_QUOTA, DEFAULT_GLOBAL_DAILY_CAP)
+        );
+        assert!(matches!(
+            contract.set_quotas(1, 1),
+            Err(VendingMachineError::NotOwner(_))
+        ));
+
+        vm.set_sender(OWNER);
+        contract.set_quotas(1, 1).unwrap();
+        vm.set_sender(user);
+        contract.vend().unwrap();
+        vm.set_value(U256::from(CUPCAKE_PRICE_WEI));
+        contract.buy_cupcake().unwrap();
+        assert_eq!(contract.balance_of(user), U256::from(2));
+        assert_eq!((contract.quota_used(user), contract.vended_today()), (1, 1));
+    }
+
+    #[test]
+    fn test_export_and_import_users_between_deployments() {
+        let (vm, mut old, user) = setup();
+        let others: Vec<Address> = (2..=4u8).map(|i| Address::from([i; 20])).collect();
+        for _ in 0..SILVER_THRESHOLD {
+            old.vend().unwrap();
+            vm.set_block_timestamp(vm.block_timestamp() + VEND_COOLDOWN_SECONDS);
+        }
+        vm.set_sender(OWNER);
+        old.vend_for(others.clone()).unwrap();
+        assert_eq!(old.user_count(), U256::from(4));
+
+        let first_page = old.export_users(U256::ZERO, U256::from(2));
+        assert_eq!(
+            first_page[0],
+            (
+                user,
+                U256::from(SILVER_THRESHOLD),
+                U256::from(vm.block_timestamp() - VEND_COOLDOWN_SECONDS),
+                U256::from(SILVER_THRESHOLD)
+            )
+        );
+        let last_page = old.export_users(U256::from(2), U256::MAX);
+        assert_eq!(last_page.len(), 2);
+        assert!(old.export_users(U256::from(4), U256::from(2)).is_empty());
+
+        let new_vm = TestVM::default();
+        new_vm.set_block_timestamp(vm.block_timestamp());
+        let mut new = VendingMachine::from(&new_vm);
+        new.constructor(OWNER, VEND_COOLDOWN_SECONDS, 0);
+        new_vm.set_sender(OWNER);
+        let stamped = (Address::from([0x09; 20]), U256::ZERO, U256::MAX, U256::ZERO);
+        assert!(matches!(
+            new.import_users(vec![stamped]),
+            Err(VendingMachineError::InvalidSnapshot(InvalidSnapshot { user, .. }))
+                if user == stamped.0
+        ));
+        assert_eq!(new.import_users(first_page.clone()).unwrap(), U256::from(2));
+        // Resending a page after an interruption imports nothing twice.
+        assert_eq!(new.import_users(first_page).unwrap(), U256::ZERO);
+        assert_eq!(new.import_users(last_page).unwrap(), U256::from(2));
+        assert_eq!(
+            new.export_users(U256::ZERO, U256::from(10)),
+            old.export_users(U256::ZERO, U256::from(10))
+        );
+        assert_eq!(new.tier_of(user), Tier::Silver as u8);
+
+        new.close_import().unwrap();
+        assert!(matches!(
+            new.import_users(vec![]),
+            Err(VendingMachineError::ImportFinished(_))
+        ));
+    }
+
+    #[test]
+    fn test_vend_item_charges_stock_and_cooldown() {
+        let (vm, mut contract, user) = setup();
+        vm.set_sender(OWNER);
+        let price = U256::from(500);
+        let cola = contract
+            .add_item("cola".into(), price, U256::from(2), 30)
+            .unwrap();
+        let chips = contract
+            .add_item("chips".into(), U256::ZERO, U256::from(5), 0)
+            .unwrap();
+        assert_eq!((cola, chips), (U256::ZERO, U256::from(1)));
+        assert_eq!(contract.item_count(), U256::from(2));
+        assert_eq!(
+            contract.item(cola).unwrap(),
+            ("cola".into(), price, U256::from(2), 30)
+        );
+
+        vm.set_sender(user);
+        vm.set_value(price - U256::from(1));
+        assert!(matches!(
+            contract.vend_item(cola),
+            Err(VendingMachineError::InsufficientPayment(_))
+        ));
+        pay(&vm, price * U256::from(2));
+        contract.vend_item(cola).unwrap();
+        assert_eq!(vm.balance(user), price);
+        assert_eq!(contract.item_balance_of(user, cola), U256::from(1));
+        assert_eq!(contract.total_collected(), price);
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = logs.last().unwrap();
+        let event = ItemVended::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!((event.user, event.id), (user, cola));
+
+        // Each item keeps its own cooldown; other items and cupcakes are unaffected.
+        vm.set_value(price);
+        vm.set_block_timestamp(vm.block_timestamp() + 10);
+        assert!(matches!(
+            contract.vend_item(cola),
+            Err(VendingMachineError::CooldownActive(CooldownActive { remaining }))
+                if remaining == U256::from(20)
+        ));
+        vm.set_value(U256::ZERO);
+        contract.vend_item(chips).unwrap();
+        contract.vend_item(chips).unwrap();
+        contract.vend().unwrap();
+        assert_eq!(contract.item_balance_of(user, chips), U256::from(2));
         assert_eq!(contract.balance_of(user), U256::from(1));
+        assert_eq!(contract.lifetime_cupcakes(user), U256::from(1));
+
+        vm.set_block_timestamp(vm.block_timestamp() + 20);
+        vm.set_value(price);
+        contract.vend_item(cola).unwrap();
+        vm.set_sender(Address::from([0x02; 20]));
+        assert!(matches!(
+            contract.vend_item(cola),
+            Err(VendingMachineError::OutOfStock(OutOfStock { id })) if id == cola
+        ));
     }
 
     #[test]
-    fn test_vend_succeeds_after_cooldown() {
+    fn test_catalog_is_owner_managed() {
+        let (vm, mut contract, _user) = setup();
+        assert!(matches!(
+            contract.add_item("cola".into(), U256::ZERO, U256::from(1), 0),
+            Err(VendingMachineError::NotOwner(_))
+        ));
+        assert!(matches!(
+            contract.vend_item(U256::ZERO),
+            Err(VendingMachineError::UnknownItem(_))
+        ));
+
+        vm.set_sender(OWNER);
+        assert!(matches!(
+            contract.add_item(String::new(), U256::ZERO, U256::from(1), 0),
+            Err(VendingMachineError::InvalidItemName(_))
+        ));
+        let id = contract
+            .add_item("cola".into(), U256::ZERO, U256::ZERO, 0)
+            .unwrap();
+        assert!(matches!(
+            contract.update_item(id + U256::from(1), U256::ZERO, U256::ZERO, 0),
+            Err(VendingMachineError::UnknownItem(_))
+        ));
+        contract
+            .update_item(id, U256::from(7), U256::from(3), 60)
+            .unwrap();
+        assert_eq!(
+            contract.item(id).unwrap(),
+            ("cola".into(), U256::from(7), U256::from(3), 60)
+        );
+    }
+
+    #[test]
+    fn test_blocklist_refuses_vends_in_open_mode() {
+        let (vm, mut contract, user) = setup();
+        let other = Address::from([0x02; 20]);
+        assert!(matches!(
+            contract.add_to_blocklist(other),
+            Err(VendingMachineError::NotOwner(_))
+        ));
+
+        vm.set_sender(OWNER);
+        contract.add_to_blocklist(user).unwrap();
+        assert!(contract.is_blocked(user));
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = logs.last().unwrap();
+        let event = BlocklistUpdated::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!((event.user, event.blocked), (user, true));
+
+        let results = contract.vend_for(vec![user, other]).unwrap();
+        assert!(!results[0].0 && results[1].0);
+        let decoded = Blocked::abi_decode(&results[0].1, true).unwrap();
+        assert_eq!(decoded.user, user);
+
+        // Purchases are not gated.
+        vm.set_sender(user);
+        assert!(matches!(
+            contract.vend(),
+            Err(VendingMachineError::Blocked(Blocked { user: who })) if who == user
+        ));
+        vm.set_value(U256::from(CUPCAKE_PRICE_WEI));
+        contract.buy_cupcake().unwrap();
+        vm.set_value(U256::ZERO);
+
+        vm.set_sender(OWNER);
+        contract.remove_from_blocklist(user).unwrap();
+        vm.set_sender(user);
+        vm.set_block_timestamp(vm.block_timestamp() + VEND_COOLDOWN_SECONDS);
+        contract.vend().unwrap();
+        assert_eq!(contract.balance_of(user), U256::from(2));
+    }
+
+    #[test]
+    fn test_allowlist_only_mode_admits_listed_users() {
         let (vm, mut contract, user) = setup();
+        let other = Address::from([0x02; 20]);
+        vm.set_sender(OWNER);
+        contract.set_allowlist_only(true).unwrap();
+        contract.add_to_allowlist(user).unwrap();
+        contract.add_to_allowlist(other).unwrap();
+        contract.add_to_blocklist(other).unwrap();
+        assert!(contract.allowlist_only());
+
+        let newcomer = Address::from([0x03; 20]);
+        vm.set_sender(newcomer);
+        assert!(matches!(
+            contract.vend(),
+            Err(VendingMachineError::NotAllowlisted(NotAllowlisted { user: who }))
+                if who == newcomer
+        ));
+        // The blocklist wins over the allowlist.
+        vm.set_sender(other);
+        assert!(matches!(
+            contract.vend(),
+            Err(VendingMachineError::Blocked(_))
+        ));
+        vm.set_sender(user);
         contract.vend().unwrap();
 
-        vm.set_block_timestamp(vm.block_timestamp() + VEND_COOLDOWN_SECONDS + 1);
+        vm.set_sender(OWNER);
+        contract.remove_from_allowlist(user).unwrap();
+        assert!(!contract.is_allowlisted(user));
+        vm.set_sender(user);
+        vm.set_block_timestamp(vm.block_timestamp() + VEND_COOLDOWN_SECONDS);
+        assert!(matches!(
+            contract.vend(),
+            Err(VendingMachineError::NotAllowlisted(_))
+        ));
+
+        vm.set_sender(OWNER);
+        contract.set_allowlist_only(false).unwrap();
+        vm.set_sender(newcomer);
+        contract.vend().unwrap();
+        assert_eq!(contract.user_count(), U256::from(2));
+    }
+
+    #[test]
+    fn test_caller_policy_blocks_contract_callers() {
+        let (vm, mut contract, user) = setup();
+        let policy = u8::from(CallerPolicy::Block);
+        assert!(matches!(
+            contract.set_caller_policy(policy),
+            Err(VendingMachineError::NotOwner(_))
+        ));
+        vm.set_sender(OWNER);
+        assert!(matches!(
+            contract.set_caller_policy(3),
+            Err(VendingMachineError::InvalidCallerPolicy(_))
+        ));
+        contract.set_caller_policy(policy).unwrap();
+        assert_eq!(contract.caller_policy(), policy);
+
+        vm.set_sender(user);
+        vm.set_tx_origin(user);
+        contract.vend().unwrap();
+
+        // A deployed bot calling on its own transaction is caught by its code...
+        let bot = Address::from([0xb0; 20]);
+        vm.set_code(bot, vec![0x00]);
+        vm.set_sender(bot);
+        vm.set_tx_origin(bot);
+        assert!(matches!(
+            contract.vend(),
+            Err(VendingMachineError::ContractCaller(ContractCaller { caller })) if caller == bot
+        ));
+        // ...and one still in its constructor by not being the origin.
+        let fresh = Address::from([0xb1; 20]);
+        vm.set_sender(fresh);
+        vm.set_tx_origin(user);
+        assert!(matches!(
+            contract.vend_with_referral(user),
+            Err(VendingMachineError::ContractCaller(_))
+        ));
+    }
+
+    #[test]
+    fn test_caller_policy_throttles_contract_callers() {
+        let (vm, mut contract, user) = setup();
+        vm.set_sender(OWNER);
+        contract
+            .set_caller_policy(u8::from(CallerPolicy::Throttle))
+            .unwrap();
+
+        let bot = Address::from([0xb0; 20]);
+        vm.set_code(bot, vec![0x00]);
+        vm.set_tx_origin(bot);
+        vm.set_sender(bot);
+        contract.vend().unwrap();
+        vm.set_tx_origin(user);
+        vm.set_sender(user);
+        contract.vend().unwrap();
+
+        vm.set_block_timestamp(1_000_000 + VEND_COOLDOWN_SECONDS);
+        contract.vend().unwrap();
+        assert_eq!(contract.cooldown_of(user), VEND_COOLDOWN_SECONDS);
+        assert_eq!(
+            contract.cooldown_of(bot),
+            CONTRACT_COOLDOWN_FACTOR * VEND_COOLDOWN_SECONDS
+        );
+        let remaining = U256::from(3 * VEND_COOLDOWN_SECONDS);
+        assert_eq!(contract.time_until_next_vend(bot), remaining);
+        vm.set_tx_origin(bot);
+        vm.set_sender(bot);
+        assert!(matches!(
+            contract.vend(),
+            Err(VendingMachineError::CooldownActive(CooldownActive { remaining: left }))
+                if left == remaining
+        ));
+
+        vm.set_block_timestamp(1_000_000 + CONTRACT_COOLDOWN_FACTOR * VEND_COOLDOWN_SECONDS);
         contract.vend().unwrap();
+        assert_eq!(contract.balance_of(bot), U256::from(2));
+
+        // A base cooldown too big to multiply pins the throttle at the maximum.
+        vm.set_sender(OWNER);
+        contract.set_cooldown(u64::MAX / 2).unwrap();
+        assert_eq!(contract.cooldown_of(bot), u64::MAX);
+    }
+
+    #[test]
+    fn test_transfer_ownership() {
+        let (vm, mut contract, user) = setup();
+        assert!(matches!(
+            contract.transfer_ownership(user),
+            Err(VendingMachineError::NotOwner(_))
+        ));
+        assert!(matches!(
+            contract.import_users(vec![]),
+            Err(VendingMachineError::NotOwner(_))
+        ));
+
+        vm.set_sender(OWNER);
+        assert!(matches!(
+            contract.transfer_ownership(Address::ZERO),
+            Err(VendingMachineError::InvalidOwner(_))
+        ));
+        contract.transfer_ownership(user).unwrap();
+        assert_eq!(contract.owner(), user);
+        assert!(contract.pause().is_err());
+        vm.set_sender(user);
+        contract.pause().unwrap();
+    }
+
+    #[test]
+    fn test_sponsored_credits_come_before_payment() {
+        let (vm, mut contract, user) = setup();
+        let price = U256::from(CUPCAKE_PRICE_WEI);
+        let (first, second) = (Address::from([0x0a; 20]), Address::from([0x0b; 20]));
+        vm.set_sender(first);
+        pay(&vm, price * U256::from(2) + U256::from(5));
+        assert_eq!(contract.sponsor(user).unwrap(), U256::from(2));
+        assert_eq!(vm.balance(first), U256::from(5));
+        vm.set_sender(second);
+        pay(&vm, price);
+        assert_eq!(contract.sponsor(user).unwrap(), U256::from(1));
+        assert_eq!(contract.sponsored_credits(user), U256::from(3));
+
+        // Value sent alongside available credits goes straight back.
+        vm.set_sender(user);
+        pay(&vm, price);
+        contract.vend_free().unwrap();
+        assert_eq!(vm.balance(user), price);
+        assert_eq!(contract.sponsorship(first, user), (U256::from(1), price));
+        vm.set_value(U256::ZERO);
+        contract.vend_free().unwrap();
+        assert_eq!(contract.sponsorship(first, user), (U256::ZERO, U256::ZERO));
         assert_eq!(contract.balance_of(user), U256::from(2));
+
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = logs.last().unwrap();
+        let event = SponsoredVend::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!((event.sponsor, event.creditsLeft), (first, U256::from(1)));
+        assert_eq!(contract.total_collected(), price * U256::from(2));
+        assert_eq!(contract.machine_status().5, price * U256::from(2));
+
+        vm.set_sender(second);
+        assert_eq!(contract.refund_sponsorship(user).unwrap(), price);
+        assert_eq!(vm.balance(second), price);
+        assert!(matches!(
+            contract.refund_sponsorship(user),
+            Err(VendingMachineError::NoSponsorship(_))
+        ));
+
+        // Without credits, `vend_free` is an ordinary purchase.
+        vm.set_sender(user);
+        assert!(matches!(
+            contract.vend_free(),
+            Err(VendingMachineError::InsufficientPayment(_))
+        ));
+        vm.set_value(price);
+        contract.vend_free().unwrap();
+        assert_eq!(contract.balance_of(user), U256::from(3));
+        assert_eq!(contract.sponsored_credits(user), U256::ZERO);
+    }
+
+    #[test]
+    fn test_returning_sponsor_is_listed_once() {
+        let (vm, mut contract, user) = setup();
+        let price = U256::from(CUPCAKE_PRICE_WEI);
+        let (first, second) = (Address::from([0x0a; 20]), Address::from([0x0b; 20]));
+        vm.set_sender(first);
+        pay(&vm, price);
+        contract.sponsor(user).unwrap();
+        vm.set_sender(user);
+        vm.set_value(U256::ZERO);
+        contract.vend_free().unwrap();
+
+        // `first` ran dry, then funds again after `second`; they keep their first place.
+        for sponsor in [second, first] {
+            vm.set_sender(sponsor);
+            pay(&vm, price);
+            contract.sponsor(user).unwrap();
+        }
+        assert_eq!(contract.sponsors_of(user), vec![first, second]);
+        vm.set_sender(user);
+        vm.set_value(U256::ZERO);
+        contract.vend_free().unwrap();
+        assert_eq!(contract.sponsorship(first, user), (U256::ZERO, U256::ZERO));
+        assert_eq!(contract.sponsorship(second, user), (U256::from(1), price));
+    }
+
+    #[test]
+    fn test_withdraw_leaves_sponsorship_escrow() {
+        let (vm, mut contract, user) = setup();
+        let price = U256::from(CUPCAKE_PRICE_WEI);
+        let sponsor = Address::from([0x0a; 20]);
+        vm.set_sender(sponsor);
+        vm.set_value(price - U256::from(1));
+        assert!(matches!(
+            contract.sponsor(user),
+            Err(VendingMachineError::InsufficientPayment(_))
+        ));
+        vm.set_value(price);
+        assert!(matches!(
+            contract.sponsor(Address::ZERO),
+            Err(VendingMachineError::InvalidRecipient(_))
+        ));
+        vm.set_balance(vm.contract_address(), price + U256::from(7));
+        contract.sponsor(user).unwrap();
+
+        vm.set_sender(OWNER);
+        assert_eq!(contract.withdraw().unwrap(), U256::from(7));
+        assert_eq!(vm.balance(vm.contract_address()), price);
+        contract.pause().unwrap();
+        contract.set_price(U256::ZERO).unwrap();
+
+        // Refunds stay open while the machine is paused.
+        vm.set_sender(sponsor);
+        assert_eq!(contract.refund_sponsorship(user).unwrap(), price);
+        assert_eq!(vm.balance(vm.contract_address()), U256::ZERO);
+        vm.set_sender(OWNER);
+        contract.unpause().unwrap();
+        vm.set_sender(sponsor);
+        assert!(matches!(
+            contract.sponsor(user),
+            Err(VendingMachineError::SponsorshipUnavailable(_))
+        ));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,99 @@
+//! Property tests: random sequences of vends, purchases, referrals and waits
+//! must keep the ledger consistent after every step, whether each call
+//! succeeded or not.
+
+use super::*;
+use proptest::prelude::*;
+use stylus_sdk::testing::*;
+
+const OWNER: Address = Address::new([0xee; 20]);
+const COOLDOWN: u64 = 60;
+/// Operations draw their users from this many addresses.
+const USERS: u8 = 5;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Vend(u8),
+    VendWithReferral(u8, u8),
+    /// Buys as the user, paying one wei short when the flag is set.
+    Buy(u8, bool),
+    Wait(u64),
+}
+
+fn user(index: u8) -> Address {
+    Address::repeat_byte(index + 1)
+}
+
+fn arb_op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => (0..USERS).prop_map(Op::Vend),
+        1 => (0..USERS, 0..USERS).prop_map(|(u, r)| Op::VendWithReferral(u, r)),
+        1 => (0..USERS, any::<bool>()).prop_map(|(u, short)| Op::Buy(u, short)),
+        2 => (0..3 * COOLDOWN).prop_map(Op::Wait),
+    ]
+}
+
+fn setup() -> (TestVM, VendingMachine) {
+    let vm = TestVM::default();
+    vm.set_block_timestamp(1_000_000);
+    let mut contract = VendingMachine::from(&vm);
+    contract.constructor(OWNER, COOLDOWN, 0);
+    (vm, contract)
+}
+
+/// Ledger invariants that must hold after any operation.
+fn check_ledger(contract: &VendingMachine) -> Result<(), TestCaseError> {
+    let users: Vec<Address> = (0..USERS).map(user).collect();
+    let balances: U256 = users.iter().map(|&u| contract.balance_of(u)).sum();
+    let lifetime: U256 = users.iter().map(|&u| contract.lifetime_cupcakes(u)).sum();
+    prop_assert_eq!(balances, contract.total_vends());
+    prop_assert_eq!(lifetime, contract.total_vends());
+
+    let served = users
+        .iter()
+        .filter(|&&u| !contract.lifetime_cupcakes(u).is_zero())
+        .count();
+    prop_assert_eq!(contract.user_count(), U256::from(served));
+    for &u in &users {
+        let expected = Tier::for_lifetime(contract.lifetime_cupcakes(u));
+        prop_assert_eq!(contract.tier_of(u), u8::from(expected));
+    }
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn prop_balances_sum_to_total_vends(ops in prop::collection::vec(arb_op(), 1..40)) {
+        let (vm, mut contract) = setup();
+        let price = contract.price();
+        let mut last_free_vend = [None::<u64>; USERS as usize];
+        for op in ops {
+            match op {
+                Op::Vend(u) | Op::VendWithReferral(u, _) => {
+                    let now = vm.block_timestamp();
+                    let cooldown = contract.cooldown_of(user(u));
+                    vm.set_sender(user(u));
+                    let result = match op {
+                        Op::VendWithReferral(_, r) => contract.vend_with_referral(user(r)),
+                        _ => contract.vend(),
+                    };
+                    if result.is_ok() {
+                        // A free vend never lands inside the user's cooldown.
+                        if let Some(last) = last_free_vend[u as usize] {
+                            prop_assert!(now >= last + cooldown);
+                        }
+                        last_free_vend[u as usize] = Some(now);
+                    }
+                }
+                Op::Buy(u, short) => {
+                    vm.set_sender(user(u));
+                    vm.set_value(if short { price - U256::from(1) } else { price });
+                    prop_assert_eq!(contract.buy_cupcake().is_ok(), !short);
+                    vm.set_value(U256::ZERO);
+                }
+                Op::Wait(seconds) => vm.set_block_timestamp(vm.block_timestamp() + seconds),
+            }
+            check_ledger(&contract)?;
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//!
+//! Stylus Merkle Airdrop
+//!
+//! The owner publishes the root of a Merkle tree whose leaves are
+//! `keccak256(abi.encodePacked(account, amount))`. Each account then calls
+//! `claim(amount, proof)` with the sibling hashes from its leaf to the root;
+//! a valid proof credits `amount` to the caller's balance in the contract.
+//!
+//! Claims are recorded in a bitmap with one bit per leaf, addressed by the
+//! leaf hash: 256 leaves share a storage word, and a leaf carried over into a
+//! later root cannot be claimed twice.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use common::merkle;
+use stylus_sdk::{
+    alloy_primitives::{Address, B256, U256},
+    crypto::keccak,
+    prelude::*,
+};
+
+/// Deepest proof accepted; enough for 2^32 leaves.
+const MAX_PROOF_LENGTH: usize = 32;
+
+sol! {
+    event MerkleRootUpdated(bytes32 previousRoot, bytes32 newRoot);
+    event Claimed(address indexed account, uint256 amount);
+
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error AlreadyClaimed(address account, uint256 amount);
+    #[derive(Debug)]
+    error InvalidProof();
+    #[derive(Debug)]
+    error ProofTooLong(uint256 length);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum AirdropError {
+    NotOwner(NotOwner),
+    AlreadyClaimed(AlreadyClaimed),
+    InvalidProof(InvalidProof),
+    ProofTooLong(ProofTooLong),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct MerkleAirdrop {
+        address owner;
+        bytes32 merkle_root;
+        /// Bit `leaf & 0xff` of word `leaf >> 8` is set once `leaf` is claimed.
+        mapping(uint256 => uint256) claimed_bitmap;
+        mapping(address => uint256) balances;
+        uint256 total_claimed;
+    }
+}
+
+#[public]
+impl MerkleAirdrop {
+    #[constructor]
+    pub fn constructor(&mut self, owner: Address, merkle_root: B256) {
+        self.owner.set(owner);
+        self.merkle_root.set(merkle_root);
+    }
+
+    /// Replaces the root; leaves already claimed stay claimed.
+    pub fn set_merkle_root(&mut self, merkle_root: B256) -> Result<(), AirdropError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(AirdropError::NotOwner(NotOwner { caller }));
+        }
+        let previous_root = self.merkle_root.get();
+        self.merkle_root.set(merkle_root);
+        log(
+            self.vm(),
+            MerkleRootUpdated {
+                previousRoot: previous_root,
+                newRoot: merkle_root,
+            },
+        );
+        Ok(())
+    }
+
+    /// Credits `amount` to the caller if `proof` places their leaf under the current root.
+    pub fn claim(&mut self, amount: U256, proof: Vec<B256>) -> Result<(), AirdropError> {
+        if proof.len() > MAX_PROOF_LENGTH {
+            return Err(AirdropError::ProofTooLong(ProofTooLong {
+                length: U256::from(proof.len()),
+            }));
+        }
+        let account = self.vm().msg_sender();
+        let leaf = leaf(account, amount);
+        if self.leaf_claimed(leaf) {
+            return Err(AirdropError::AlreadyClaimed(AlreadyClaimed {
+                account,
+                amount,
+            }));
+        }
+        if !merkle::verify(&proof, self.merkle_root.get(), leaf) {
+            return Err(AirdropError::InvalidProof(InvalidProof {}));
+        }
+
+        let (word, mask) = bitmap_position(leaf);
+        let mut slot = self.claimed_bitmap.setter(word);
+        let bits = slot.get();
+        slot.set(bits | mask);
+        let mut balance = self.balances.setter(account);
+        let credited = balance.get() + amount;
+        balance.set(credited);
+        self.total_claimed.set(self.total_claimed.get() + amount);
+        log(self.vm(), Claimed { account, amount });
+        Ok(())
+    }
+
+    /// Whether the allocation of `amount` to `account` has been claimed.
+    pub fn is_claimed(&self, account: Address, amount: U256) -> bool {
+        self.leaf_claimed(leaf(account, amount))
+    }
+
+    pub fn merkle_root(&self) -> B256 {
+        self.merkle_root.get()
+    }
+
+    pub fn balance_of(&self, account: Address) -> U256 {
+        self.balances.get(account)
+    }
+
+    pub fn total_claimed(&self) -> U256 {
+        self.total_claimed.get()
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+}
+
+impl MerkleAirdrop {
+    fn leaf_claimed(&self, leaf: B256) -> bool {
+        let (word, mask) = bitmap_position(leaf);
+        !(self.claimed_bitmap.get(word) & mask).is_zero()
+    }
+}
+
+/// Leaf for `account` receiving `amount`: `keccak256(abi.encodePacked(account, amount))`.
+///
+/// The 52-byte preimage can never be mistaken for a 64-byte pair of nodes.
+fn leaf(account: Address, amount: U256) -> B256 {
+    let mut preimage = [0u8; 52];
+    preimage[..20].copy_from_slice(account.as_slice());
+    preimage[20..].copy_from_slice(&amount.to_be_bytes::<32>());
+    keccak(preimage)
+}
+
+/// The bitmap word holding `leaf` and the mask selecting its bit.
+fn bitmap_position(leaf: B256) -> (U256, U256) {
+    let word = U256::from_be_bytes(leaf.0) >> 8;
+    let mask = U256::from(1) << leaf[31];
+    (word, mask)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::SolEvent;
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::new([0xaa; 20]);
+    const ALICE: Address = Address::new([0x01; 20]);
+    const BOB: Address = Address::new([0x02; 20]);
+    const CAROL: Address = Address::new([0x03; 20]);
+
+    /// A three-leaf tree: `root = hash(hash(alice, bob), carol)`.
+    struct Tree {
+        leaves: [B256; 3],
+        inner: B256,
+        root: B256,
+    }
+
+    fn tree() -> Tree {
+        let leaves = [
+            leaf(ALICE, U256::from(100)),
+            leaf(BOB, U256::from(250)),
+            leaf(CAROL, U256::from(75)),
+        ];
+        let inner = merkle::hash_pair(leaves[0], leaves[1]);
+        let root = merkle::hash_pair(inner, leaves[2]);
+        Tree {
+            leaves,
+            inner,
+            root,
+        }
+    }
+
+    fn setup() -> (TestVM, MerkleAirdrop, Tree) {
+        let vm = TestVM::default();
+        let tree = tree();
+        let mut contract = MerkleAirdrop::from(&vm);
+        contract.constructor(OWNER, tree.root);
+        (vm, contract, tree)
+    }
+
+    #[test]
+    fn test_claim_credits_and_marks_leaf() {
+        let (vm, mut contract, tree) = setup();
+        vm.set_sender(ALICE);
+        contract
+            .claim(U256::from(100), vec![tree.leaves[1], tree.leaves[2]])
+            .unwrap();
+        vm.set_sender(CAROL);
+        contract.claim(U256::from(75), vec![tree.inner]).unwrap();
+
+        assert_eq!(contract.balance_of(ALICE), U256::from(100));
+        assert_eq!(contract.balance_of(CAROL), U256::from(75));
+        assert_eq!(contract.total_claimed(), U256::from(175));
+        assert!(contract.is_claimed(ALICE, U256::from(100)));
+        assert!(!contract.is_claimed(BOB, U256::from(250)));
+
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = logs.last().unwrap();
+        assert_eq!(topics[0], Claimed::SIGNATURE_HASH);
+        let event = Claimed::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!((event.account, event.amount), (CAROL, U256::from(75)));
+    }
+
+    #[test]
+    fn test_cannot_claim_twice() {
+        let (vm, mut contract, tree) = setup();
+        vm.set_sender(CAROL);
+        contract.claim(U256::from(75), vec![tree.inner]).unwrap();
+        assert!(matches!(
+            contract.claim(U256::from(75), vec![tree.inner]),
+            Err(AirdropError::AlreadyClaimed(_))
+        ));
+        assert_eq!(contract.balance_of(CAROL), U256::from(75));
+    }
+
+    #[test]
+    fn test_rejects_invalid_proofs() {
+        let (vm, mut contract, tree) = setup();
+        vm.set_sender(BOB);
+        let proof = vec![tree.leaves[0], tree.leaves[2]];
+        // A wrong amount or a wrong sibling leads to a different root.
+        assert!(matches!(
+            contract.claim(U256::from(251), proof.clone()),
+            Err(AirdropError::InvalidProof(_))
+        ));
+        assert!(contract
+            .claim(U256::from(250), vec![tree.leaves[2], tree.leaves[2]])
+            .is_err());
+        // Bob's proof does not work for anyone else.
+        vm.set_sender(CAROL);
+        assert!(contract.claim(U256::from(250), proof.clone()).is_err());
+        assert!(matches!(
+            contract.claim(U256::from(75), vec![B256::ZERO; MAX_PROOF_LENGTH + 1]),
+            Err(AirdropError::ProofTooLong(_))
+        ));
+
+        vm.set_sender(BOB);
+        contract.claim(U256::from(250), proof).unwrap();
+        assert_eq!(contract.total_claimed(), U256::from(250));
+    }
+
+    #[test]
+    fn test_owner_rotates_root() {
+        let (vm, mut contract, tree) = setup();
+        vm.set_sender(ALICE);
+        contract
+            .claim(U256::from(100), vec![tree.leaves[1], tree.leaves[2]])
+            .unwrap();
+        assert!(matches!(
+            contract.set_merkle_root(B256::ZERO),
+            Err(AirdropError::NotOwner(_))
+        ));
+
+        // The next round re-includes Alice's old allocation next to a new one for Bob.
+        let bob_leaf = leaf(BOB, U256::from(40));
+        let root = merkle::hash_pair(tree.leaves[0], bob_leaf);
+        vm.set_sender(OWNER);
+        contract.set_merkle_root(root).unwrap();
+        assert_eq!(contract.merkle_root(), root);
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.claim(U256::from(100), vec![bob_leaf]),
+            Err(AirdropError::AlreadyClaimed(_))
+        ));
+        vm.set_sender(BOB);
+        contract
+            .claim(U256::from(40), vec![tree.leaves[0]])
+            .unwrap();
+        assert!(contract.is_claimed(BOB, U256::from(40)));
+    }
+
+    #[test]
+    fn test_bitmap_packs_leaves_by_hash() {
+        let mut leaf = B256::repeat_byte(0x12);
+        let (word, mask) = bitmap_position(leaf);
+        assert_eq!(mask, U256::from(1) << 0x12);
+        leaf[31] = 0xff;
+        assert_eq!(bitmap_position(leaf), (word, U256::from(1) << 255));
+        assert!(merkle::verify(&[], leaf, leaf));
+    }
+}
@@ -0,0 +1,535 @@
+//!
+//! Stylus Allowlist Registrar
+//!
+//! Projects open allowlists that users join in one of two ways:
+//!
+//! * paying the current tier's fee, where each tier covers a number of paid
+//!   spots and later tiers usually cost more (see `common::fees`), or
+//! * presenting a Merkle proof that they are in the project's pre-approved
+//!   set, which is free and does not use up a paid spot (see `common::merkle`).
+//!
+//! When registration is over the project closes the list, withdraws the fees
+//! and exports the final entries page by page with `entries()`.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{string::ToString, vec::Vec};
+use alloy_sol_types::sol;
+use common::{fees, merkle, paging::page};
+use stylus_sdk::{
+    alloy_primitives::{Address, B256, U256},
+    crypto::keccak,
+    prelude::*,
+};
+
+/// Leaf for an allowlisted `account`: `keccak256(abi.encodePacked(account))`.
+fn leaf(account: Address) -> B256 {
+    keccak(account)
+}
+
+/// Largest page `entries` returns.
+const MAX_PAGE_SIZE: u64 = 100;
+/// Deepest proof accepted; enough for 2^32 pre-approved accounts.
+const MAX_PROOF_LENGTH: usize = 32;
+
+sol! {
+    event ListCreated(uint256 indexed listId, address indexed creator, bytes32 merkleRoot);
+    event Registered(uint256 indexed listId, address indexed account, uint256 fee, bool viaProof);
+    event ListFinalized(uint256 indexed listId, uint256 size);
+    event FeesWithdrawn(uint256 indexed listId, address indexed creator, uint256 amount);
+
+    #[derive(Debug)]
+    error UnknownList(uint256 listId);
+    #[derive(Debug)]
+    error InvalidSchedule(string reason);
+    #[derive(Debug)]
+    error NotCreator(address caller);
+    #[derive(Debug)]
+    error ListClosed(uint256 listId);
+    #[derive(Debug)]
+    error ListFull(uint256 listId);
+    #[derive(Debug)]
+    error AlreadyRegistered(uint256 listId, address account);
+    #[derive(Debug)]
+    error WrongFee(uint256 expected, uint256 sent);
+    #[derive(Debug)]
+    error InvalidProof();
+    #[derive(Debug)]
+    error NothingToWithdraw();
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum RegistrarError {
+    UnknownList(UnknownList),
+    InvalidSchedule(InvalidSchedule),
+    NotCreator(NotCreator),
+    ListClosed(ListClosed),
+    ListFull(ListFull),
+    AlreadyRegistered(AlreadyRegistered),
+    WrongFee(WrongFee),
+    InvalidProof(InvalidProof),
+    NothingToWithdraw(NothingToWithdraw),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    pub struct Allowlist {
+        address creator;
+        /// Root of the pre-approved set; zero if the list has none.
+        bytes32 merkle_root;
+        /// The fee schedule, as parallel `(cap, price)` arrays.
+        uint256[] tier_caps;
+        uint256[] tier_prices;
+        uint256 paid_count;
+        /// Fees received and not yet withdrawn.
+        uint256 collected;
+        bool closed;
+        /// Registered accounts in registration order.
+        address[] entries;
+        mapping(address => bool) registered;
+    }
+
+    #[entrypoint]
+    pub struct AllowlistRegistrar {
+        uint256 list_count;
+        mapping(uint256 => Allowlist) lists;
+    }
+}
+
+#[public]
+impl AllowlistRegistrar {
+    /// Opens a list owned by the caller and returns its id.
+    ///
+    /// `tier_caps` must be strictly increasing and match `tier_prices` in length. A list
+    /// needs paid tiers, a Merkle root, or both.
+    pub fn create_list(
+        &mut self,
+        merkle_root: B256,
+        tier_caps: Vec<U256>,
+        tier_prices: Vec<U256>,
+    ) -> Result<U256, RegistrarError> {
+        fees::validate(&tier_caps, &tier_prices).map_err(|err| {
+            let reason = match err {
+                fees::ScheduleError::LengthMismatch => "Caps and prices differ in length",
+                fees::ScheduleError::TooManyTiers => "Too many tiers",
+                fees::ScheduleError::CapsNotIncreasing => "Caps must increase",
+            };
+            RegistrarError::InvalidSchedule(InvalidSchedule {
+                reason: reason.to_string(),
+            })
+        })?;
+        if tier_caps.is_empty() && merkle_root.is_zero() {
+            return Err(RegistrarError::InvalidSchedule(InvalidSchedule {
+                reason: "No way to register".to_string(),
+            }));
+        }
+
+        let creator = self.vm().msg_sender();
+        let list_id = self.list_count.get();
+        self.list_count.set(list_id + U256::from(1));
+        let mut list = self.lists.setter(list_id);
+        list.creator.set(creator);
+        list.merkle_root.set(merkle_root);
+        for (cap, price) in tier_caps.into_iter().zip(tier_prices) {
+            list.tier_caps.push(cap);
+            list.tier_prices.push(price);
+        }
+        log(
+            self.vm(),
+            ListCreated {
+                listId: list_id,
+                creator,
+                merkleRoot: merkle_root,
+            },
+        );
+        Ok(list_id)
+    }
+
+    /// Takes the next paid spot; `msg.value` must equal `next_fee(list_id)` exactly.
+    #[payable]
+    pub fn register(&mut self, list_id: U256) -> Result<U256, RegistrarError> {
+        let account = self.vm().msg_sender();
+        self.check_open(list_id, account)?;
+        let fee = self.next_fee(list_id)?;
+        let sent = self.vm().msg_value();
+        if sent != fee {
+            return Err(RegistrarError::WrongFee(WrongFee {
+                expected: fee,
+                sent,
+            }));
+        }
+
+        let mut list = self.lists.setter(list_id);
+        let paid = list.paid_count.get();
+        list.paid_count.set(paid + U256::from(1));
+        let collected = list.collected.get();
+        list.collected.set(collected + fee);
+        self.add_entry(list_id, account, fee, false);
+        Ok(fee)
+    }
+
+    /// Registers the caller for free if `proof` places them in the pre-approved set.
+    pub fn register_with_proof(
+        &mut self,
+        list_id: U256,
+        proof: Vec<B256>,
+    ) -> Result<(), RegistrarError> {
+        let account = self.vm().msg_sender();
+        self.check_open(list_id, account)?;
+        let root = self.lists.getter(list_id).merkle_root.get();
+        if root.is_zero()
+            || proof.len() > MAX_PROOF_LENGTH
+            || !merkle::verify(&proof, root, leaf(account))
+        {
+            return Err(RegistrarError::InvalidProof(InvalidProof {}));
+        }
+        self.add_entry(list_id, account, U256::ZERO, true);
+        Ok(())
+    }
+
+    /// Ends registration for good. Only the list's creator may close it.
+    pub fn close(&mut self, list_id: U256) -> Result<(), RegistrarError> {
+        self.only_creator(list_id)?;
+        let mut list = self.lists.setter(list_id);
+        if list.closed.get() {
+            return Err(RegistrarError::ListClosed(ListClosed { listId: list_id }));
+        }
+        list.closed.set(true);
+        let size = U256::from(list.entries.len());
+        log(
+            self.vm(),
+            ListFinalized {
+                listId: list_id,
+                size,
+            },
+        );
+        Ok(())
+    }
+
+    /// Sends the fees collected so far to the list's creator.
+    pub fn withdraw_fees(&mut self, list_id: U256) -> Result<U256, RegistrarError> {
+        let creator = self.only_creator(list_id)?;
+        let mut list = self.lists.setter(list_id);
+        let amount = list.collected.get();
+        if amount.is_zero() {
+            return Err(RegistrarError::NothingToWithdraw(NothingToWithdraw {}));
+        }
+        list.collected.set(U256::ZERO);
+        self.vm().transfer_eth(creator, amount).map_err(|_| {
+            RegistrarError::TransferFailed(TransferFailed {
+                to: creator,
+                amount,
+            })
+        })?;
+        log(
+            self.vm(),
+            FeesWithdrawn {
+                listId: list_id,
+                creator,
+                amount,
+            },
+        );
+        Ok(amount)
+    }
+
+    /// Fee for the next paid spot.
+    pub fn next_fee(&self, list_id: U256) -> Result<U256, RegistrarError> {
+        let (caps, prices) = self.fee_schedule(list_id)?;
+        let paid = self.lists.getter(list_id).paid_count.get();
+        fees::price_for(&caps, &prices, paid)
+            .ok_or(RegistrarError::ListFull(ListFull { listId: list_id }))
+    }
+
+    /// Returns the schedule as `(caps, prices)`.
+    pub fn fee_schedule(&self, list_id: U256) -> Result<(Vec<U256>, Vec<U256>), RegistrarError> {
+        self.check_list(list_id)?;
+        let list = self.lists.getter(list_id);
+        let caps = (0..list.tier_caps.len()).filter_map(|i| list.tier_caps.get(i));
+        let prices = (0..list.tier_prices.len()).filter_map(|i| list.tier_prices.get(i));
+        Ok((caps.collect(), prices.collect()))
+    }
+
+    /// Returns `(creator, merkle_root, paid_count, entry_count, collected, closed)`.
+    pub fn list_info(
+        &self,
+        list_id: U256,
+    ) -> Result<(Address, B256, U256, U256, U256, bool), RegistrarError> {
+        self.check_list(list_id)?;
+        let list = self.lists.getter(list_id);
+        Ok((
+            list.creator.get(),
+            list.merkle_root.get(),
+            list.paid_count.get(),
+            U256::from(list.entries.len()),
+            list.collected.get(),
+            list.closed.get(),
+        ))
+    }
+
+    /// Returns up to `limit` registered accounts starting at `offset`, in registration order.
+    ///
+    /// A page holds at most `MAX_PAGE_SIZE` accounts, and an empty page means the whole list
+    /// has been read.
+    pub fn entries(
+        &self,
+        list_id: U256,
+        offset: U256,
+        limit: U256,
+    ) -> Result<Vec<Address>, RegistrarError> {
+        self.check_list(list_id)?;
+        let entries = &self.lists.getter(list_id).entries;
+        Ok(page(entries.len(), offset, limit, MAX_PAGE_SIZE)
+            .filter_map(|index| entries.get(index))
+            .collect())
+    }
+
+    pub fn is_registered(&self, list_id: U256, account: Address) -> bool {
+        self.lists.getter(list_id).registered.get(account)
+    }
+
+    pub fn list_count(&self) -> U256 {
+        self.list_count.get()
+    }
+}
+
+impl AllowlistRegistrar {
+    fn check_list(&self, list_id: U256) -> Result<(), RegistrarError> {
+        if list_id >= self.list_count.get() {
+            return Err(RegistrarError::UnknownList(UnknownList { listId: list_id }));
+        }
+        Ok(())
+    }
+
+    fn check_open(&self, list_id: U256, account: Address) -> Result<(), RegistrarError> {
+        self.check_list(list_id)?;
+        let list = self.lists.getter(list_id);
+        if list.closed.get() {
+            return Err(RegistrarError::ListClosed(ListClosed { listId: list_id }));
+        }
+        if list.registered.get(account) {
+            return Err(RegistrarError::AlreadyRegistered(AlreadyRegistered {
+                listId: list_id,
+                account,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Returns the caller if they created the list.
+    fn only_creator(&self, list_id: U256) -> Result<Address, RegistrarError> {
+        self.check_list(list_id)?;
+        let caller = self.vm().msg_sender();
+        if self.lists.getter(list_id).creator.get() != caller {
+            return Err(RegistrarError::NotCreator(NotCreator { caller }));
+        }
+        Ok(caller)
+    }
+
+    fn add_entry(&mut self, list_id: U256, account: Address, fee: U256, via_proof: bool) {
+        let mut list = self.lists.setter(list_id);
+        list.registered.setter(account).set(true);
+        list.entries.push(account);
+        log(
+            self.vm(),
+            Registered {
+                listId: list_id,
+                account,
+                fee,
+                viaProof: via_proof,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const PROJECT: Address = Address::new([0xaa; 20]);
+    const FRIEND: Address = Address::new([0xf1; 20]);
+    const PARTNER: Address = Address::new([0xf2; 20]);
+
+    fn user(n: u8) -> Address {
+        Address::from([n; 20])
+    }
+
+    /// Two spots at 10 wei, then one at 25 wei, plus FRIEND and PARTNER pre-approved.
+    fn setup() -> (TestVM, AllowlistRegistrar, U256) {
+        let vm = TestVM::default();
+        let mut contract = AllowlistRegistrar::from(&vm);
+        vm.set_sender(PROJECT);
+        let root = merkle::hash_pair(leaf(FRIEND), leaf(PARTNER));
+        let list_id = contract
+            .create_list(
+                root,
+                vec![U256::from(2), U256::from(3)],
+                vec![U256::from(10), U256::from(25)],
+            )
+            .unwrap();
+        (vm, contract, list_id)
+    }
+
+    fn pay(
+        vm: &TestVM,
+        contract: &mut AllowlistRegistrar,
+        list_id: U256,
+        who: Address,
+        amount: u64,
+    ) -> Result<U256, RegistrarError> {
+        vm.set_sender(who);
+        vm.set_value(U256::from(amount));
+        let result = contract.register(list_id);
+        if result.is_ok() {
+            let balance = vm.balance(vm.contract_address());
+            vm.set_balance(vm.contract_address(), balance + U256::from(amount));
+        }
+        vm.set_value(U256::ZERO);
+        result
+    }
+
+    #[test]
+    fn test_paid_registration_follows_tiers() {
+        let (vm, mut contract, list_id) = setup();
+        assert_eq!(contract.next_fee(list_id).unwrap(), U256::from(10));
+        pay(&vm, &mut contract, list_id, user(1), 10).unwrap();
+        assert!(matches!(
+            pay(&vm, &mut contract, list_id, user(1), 10),
+            Err(RegistrarError::AlreadyRegistered(_))
+        ));
+        pay(&vm, &mut contract, list_id, user(2), 10).unwrap();
+        assert!(matches!(
+            pay(&vm, &mut contract, list_id, user(3), 10),
+            Err(RegistrarError::WrongFee(WrongFee { expected, sent }))
+                if expected == U256::from(25) && sent == U256::from(10)
+        ));
+        pay(&vm, &mut contract, list_id, user(3), 25).unwrap();
+        assert!(matches!(
+            pay(&vm, &mut contract, list_id, user(4), 25),
+            Err(RegistrarError::ListFull(_))
+        ));
+
+        let (_, _, paid, size, collected, closed) = contract.list_info(list_id).unwrap();
+        assert_eq!(
+            (paid, size, collected),
+            (U256::from(3), U256::from(3), U256::from(45))
+        );
+        assert!(!closed);
+    }
+
+    #[test]
+    fn test_proof_registration_is_free_and_outside_tiers() {
+        let (vm, mut contract, list_id) = setup();
+        vm.set_sender(FRIEND);
+        contract
+            .register_with_proof(list_id, vec![leaf(PARTNER)])
+            .unwrap();
+        assert!(contract.is_registered(list_id, FRIEND));
+        assert!(matches!(
+            contract.register_with_proof(list_id, vec![leaf(PARTNER)]),
+            Err(RegistrarError::AlreadyRegistered(_))
+        ));
+        assert!(pay(&vm, &mut contract, list_id, FRIEND, 10).is_err());
+
+        vm.set_sender(user(5));
+        assert!(matches!(
+            contract.register_with_proof(list_id, vec![leaf(PARTNER)]),
+            Err(RegistrarError::InvalidProof(_))
+        ));
+        let (_, _, paid, size, _, _) = contract.list_info(list_id).unwrap();
+        assert_eq!((paid, size), (U256::ZERO, U256::from(1)));
+        assert_eq!(contract.next_fee(list_id).unwrap(), U256::from(10));
+    }
+
+    #[test]
+    fn test_close_and_export_pages() {
+        let (vm, mut contract, list_id) = setup();
+        pay(&vm, &mut contract, list_id, user(1), 10).unwrap();
+        vm.set_sender(PARTNER);
+        contract
+            .register_with_proof(list_id, vec![leaf(FRIEND)])
+            .unwrap();
+        pay(&vm, &mut contract, list_id, user(2), 10).unwrap();
+
+        assert!(matches!(
+            contract.close(list_id),
+            Err(RegistrarError::NotCreator(_))
+        ));
+        vm.set_sender(PROJECT);
+        contract.close(list_id).unwrap();
+        assert!(matches!(
+            pay(&vm, &mut contract, list_id, user(3), 25),
+            Err(RegistrarError::ListClosed(_))
+        ));
+
+        let page = |offset: u64, limit: u64| {
+            contract
+                .entries(list_id, U256::from(offset), U256::from(limit))
+                .unwrap()
+        };
+        assert_eq!(page(0, 2), vec![user(1), PARTNER]);
+        assert_eq!(page(2, 10), vec![user(2)]);
+        assert!(page(5, 10).is_empty());
+        assert_eq!(page(0, u64::MAX).len(), 3);
+    }
+
+    #[test]
+    fn test_creator_withdraws_fees() {
+        let (vm, mut contract, list_id) = setup();
+        pay(&vm, &mut contract, list_id, user(1), 10).unwrap();
+        pay(&vm, &mut contract, list_id, user(2), 10).unwrap();
+
+        assert!(matches!(
+            contract.withdraw_fees(list_id),
+            Err(RegistrarError::NotCreator(_))
+        ));
+        vm.set_sender(PROJECT);
+        assert_eq!(contract.withdraw_fees(list_id).unwrap(), U256::from(20));
+        assert_eq!(vm.balance(PROJECT), U256::from(20));
+        assert!(matches!(
+            contract.withdraw_fees(list_id),
+            Err(RegistrarError::NothingToWithdraw(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_bad_schedules() {
+        let (_vm, mut contract, _list_id) = setup();
+        let tiers = |caps: &[u64]| caps.iter().map(|&c| U256::from(c)).collect::<Vec<_>>();
+        let reason = |result: Result<U256, RegistrarError>| match result {
+            Err(RegistrarError::InvalidSchedule(e)) => e.reason,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(
+            reason(contract.create_list(B256::ZERO, tiers(&[1, 2]), tiers(&[1]))),
+            "Caps and prices differ in length"
+        );
+        assert_eq!(
+            reason(contract.create_list(B256::ZERO, tiers(&[2, 2]), tiers(&[1, 2]))),
+            "Caps must increase"
+        );
+        assert_eq!(
+            reason(contract.create_list(B256::ZERO, vec![], vec![])),
+            "No way to register"
+        );
+        assert!(contract
+            .create_list(B256::ZERO, tiers(&[1; 9]), tiers(&[1; 9]))
+            .is_err());
+        assert!(matches!(
+            contract.next_fee(U256::from(7)),
+            Err(RegistrarError::UnknownList(_))
+        ));
+        assert_eq!(
+            fees::price_for(&tiers(&[1]), &tiers(&[5]), U256::from(1)),
+            None
+        );
+    }
+}
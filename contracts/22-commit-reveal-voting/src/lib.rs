@@ -0,0 +1,383 @@
+//!
+//! Stylus Commit-Reveal Voting
+//!
+//! A single ballot run in two timed phases. During the commit window each
+//! voter submits only `keccak256(abi.encodePacked(choice, salt, voter))`, so
+//! nobody can see the running tally or copy another voter's commitment. During
+//! the reveal window voters publish `choice` and `salt`; a reveal that matches
+//! the commitment is counted. Once the reveal window closes the results are
+//! final.
+//!
+//! Phases follow `block.timestamp` alone: no transaction is needed to move
+//! from one to the next. Commitments that are never revealed are not counted.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, B256, U256},
+    prelude::*,
+};
+
+/// Most choices a ballot may offer.
+const MAX_CHOICES: u8 = 16;
+
+pub const PHASE_COMMIT: u8 = 1;
+pub const PHASE_REVEAL: u8 = 2;
+pub const PHASE_ENDED: u8 = 3;
+
+sol! {
+    event VoteCommitted(address indexed voter);
+    event VoteRevealed(address indexed voter, uint256 choice);
+
+    #[derive(Debug)]
+    error InvalidBallot();
+    #[derive(Debug)]
+    error WrongPhase(uint8 expected, uint8 current);
+    #[derive(Debug)]
+    error EmptyCommitment();
+    #[derive(Debug)]
+    error NoCommitment(address voter);
+    #[derive(Debug)]
+    error AlreadyRevealed(address voter);
+    #[derive(Debug)]
+    error CommitmentMismatch();
+    #[derive(Debug)]
+    error InvalidChoice(uint256 choice);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum VotingError {
+    InvalidBallot(InvalidBallot),
+    WrongPhase(WrongPhase),
+    EmptyCommitment(EmptyCommitment),
+    NoCommitment(NoCommitment),
+    AlreadyRevealed(AlreadyRevealed),
+    CommitmentMismatch(CommitmentMismatch),
+    InvalidChoice(InvalidChoice),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct CommitRevealVoting {
+        /// Commits are accepted before `commit_end`, reveals from then until `reveal_end`.
+        uint256 commit_end;
+        uint256 reveal_end;
+        /// One vote count per choice.
+        uint256[] tallies;
+        mapping(address => bytes32) commitments;
+        mapping(address => bool) revealed;
+        uint256 commit_count;
+        uint256 reveal_count;
+    }
+}
+
+#[public]
+impl CommitRevealVoting {
+    /// Opens a ballot with `choice_count` choices; the commit window starts now.
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        choice_count: u8,
+        commit_duration: u64,
+        reveal_duration: u64,
+    ) -> Result<(), VotingError> {
+        if !(2..=MAX_CHOICES).contains(&choice_count)
+            || commit_duration == 0
+            || reveal_duration == 0
+        {
+            return Err(VotingError::InvalidBallot(InvalidBallot {}));
+        }
+        let commit_end = U256::from(self.vm().block_timestamp()) + U256::from(commit_duration);
+        self.commit_end.set(commit_end);
+        self.reveal_end
+            .set(commit_end + U256::from(reveal_duration));
+        for _ in 0..choice_count {
+            self.tallies.push(U256::ZERO);
+        }
+        Ok(())
+    }
+
+    /// Records or replaces the caller's sealed vote.
+    pub fn commit(&mut self, hash: B256) -> Result<(), VotingError> {
+        self.require_phase(PHASE_COMMIT)?;
+        if hash.is_zero() {
+            return Err(VotingError::EmptyCommitment(EmptyCommitment {}));
+        }
+        let voter = self.vm().msg_sender();
+        if self.commitments.get(voter).is_zero() {
+            self.commit_count
+                .set(self.commit_count.get() + U256::from(1));
+        }
+        self.commitments.setter(voter).set(hash);
+        log(self.vm(), VoteCommitted { voter });
+        Ok(())
+    }
+
+    /// Opens the caller's commitment and counts the vote.
+    pub fn reveal(&mut self, choice: U256, salt: B256) -> Result<(), VotingError> {
+        self.require_phase(PHASE_REVEAL)?;
+        let voter = self.vm().msg_sender();
+        let commitment = self.commitments.get(voter);
+        if commitment.is_zero() {
+            return Err(VotingError::NoCommitment(NoCommitment { voter }));
+        }
+        if self.revealed.get(voter) {
+            return Err(VotingError::AlreadyRevealed(AlreadyRevealed { voter }));
+        }
+        if self.commitment_for(choice, salt, voter) != commitment {
+            return Err(VotingError::CommitmentMismatch(CommitmentMismatch {}));
+        }
+        // A choice that was out of range when committed can never be counted.
+        let index = usize::try_from(choice)
+            .ok()
+            .filter(|&index| index < self.tallies.len())
+            .ok_or(VotingError::InvalidChoice(InvalidChoice { choice }))?;
+
+        self.revealed.setter(voter).set(true);
+        let mut tally = self.tallies.setter(index).expect("index checked above");
+        let votes = tally.get() + U256::from(1);
+        tally.set(votes);
+        self.reveal_count
+            .set(self.reveal_count.get() + U256::from(1));
+        log(self.vm(), VoteRevealed { voter, choice });
+        Ok(())
+    }
+
+    /// The commitment `voter` should submit for `choice` and `salt`.
+    pub fn commitment_for(&self, choice: U256, salt: B256, voter: Address) -> B256 {
+        let mut preimage = Vec::with_capacity(84);
+        preimage.extend_from_slice(&choice.to_be_bytes::<32>());
+        preimage.extend_from_slice(salt.as_slice());
+        preimage.extend_from_slice(voter.as_slice());
+        self.vm().native_keccak256(&preimage)
+    }
+
+    /// One of the `PHASE_*` constants, derived from the block timestamp.
+    pub fn phase(&self) -> u8 {
+        let now = U256::from(self.vm().block_timestamp());
+        if now < self.commit_end.get() {
+            PHASE_COMMIT
+        } else if now < self.reveal_end.get() {
+            PHASE_REVEAL
+        } else {
+            PHASE_ENDED
+        }
+    }
+
+    /// Returns `(commit_end, reveal_end)` as timestamps.
+    pub fn schedule(&self) -> (U256, U256) {
+        (self.commit_end.get(), self.reveal_end.get())
+    }
+
+    /// Votes counted so far for each choice; only final once the ballot has ended.
+    pub fn tallies(&self) -> Vec<U256> {
+        (0..self.tallies.len())
+            .filter_map(|index| self.tallies.get(index))
+            .collect()
+    }
+
+    /// Returns `(commits, reveals)`.
+    pub fn turnout(&self) -> (U256, U256) {
+        (self.commit_count.get(), self.reveal_count.get())
+    }
+
+    /// Returns `(choice, votes, tied)` for the leading choice once the ballot has ended.
+    ///
+    /// On a tie the lowest-numbered leading choice is returned with `tied` set.
+    pub fn winner(&self) -> Result<(U256, U256, bool), VotingError> {
+        self.require_phase(PHASE_ENDED)?;
+        let (mut best, mut best_votes, mut tied) = (0, U256::ZERO, false);
+        for (index, votes) in self.tallies().into_iter().enumerate() {
+            if votes > best_votes {
+                (best, best_votes, tied) = (index, votes, false);
+            } else if votes == best_votes {
+                tied = true;
+            }
+        }
+        Ok((U256::from(best), best_votes, tied))
+    }
+}
+
+impl CommitRevealVoting {
+    fn require_phase(&self, expected: u8) -> Result<(), VotingError> {
+        let current = self.phase();
+        if current != expected {
+            return Err(VotingError::WrongPhase(WrongPhase { expected, current }));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const START: u64 = 1_000;
+    const ALICE: Address = Address::new([0x01; 20]);
+    const BOB: Address = Address::new([0x02; 20]);
+    const CAROL: Address = Address::new([0x03; 20]);
+
+    /// Three choices, 100 seconds to commit and 50 to reveal.
+    fn setup() -> (TestVM, CommitRevealVoting) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(START);
+        let mut contract = CommitRevealVoting::from(&vm);
+        contract.constructor(3, 100, 50).unwrap();
+        (vm, contract)
+    }
+
+    fn salt(voter: Address) -> B256 {
+        B256::left_padding_from(voter.as_slice())
+    }
+
+    fn commit(vm: &TestVM, contract: &mut CommitRevealVoting, voter: Address, choice: u64) {
+        vm.set_sender(voter);
+        let hash = contract.commitment_for(U256::from(choice), salt(voter), voter);
+        contract.commit(hash).unwrap();
+    }
+
+    fn reveal(
+        vm: &TestVM,
+        contract: &mut CommitRevealVoting,
+        voter: Address,
+        choice: u64,
+    ) -> Result<(), VotingError> {
+        vm.set_sender(voter);
+        contract.reveal(U256::from(choice), salt(voter))
+    }
+
+    #[test]
+    fn test_full_ballot() {
+        let (vm, mut contract) = setup();
+        commit(&vm, &mut contract, ALICE, 2);
+        commit(&vm, &mut contract, BOB, 2);
+        commit(&vm, &mut contract, CAROL, 0);
+        // Nothing is countable while the commit window is open.
+        assert!(matches!(
+            reveal(&vm, &mut contract, ALICE, 2),
+            Err(VotingError::WrongPhase(WrongPhase {
+                expected: 2,
+                current: 1
+            }))
+        ));
+
+        vm.set_block_timestamp(START + 100);
+        assert_eq!(contract.phase(), PHASE_REVEAL);
+        reveal(&vm, &mut contract, ALICE, 2).unwrap();
+        reveal(&vm, &mut contract, CAROL, 0).unwrap();
+        assert!(matches!(contract.winner(), Err(VotingError::WrongPhase(_))));
+
+        vm.set_block_timestamp(START + 150);
+        assert_eq!(contract.phase(), PHASE_ENDED);
+        // Bob never revealed, so his vote does not count.
+        assert!(reveal(&vm, &mut contract, BOB, 2).is_err());
+        assert_eq!(
+            contract.tallies(),
+            vec![U256::from(1), U256::ZERO, U256::from(1)]
+        );
+        assert_eq!(contract.turnout(), (U256::from(3), U256::from(2)));
+        assert_eq!(
+            contract.winner().unwrap(),
+            (U256::ZERO, U256::from(1), true)
+        );
+    }
+
+    #[test]
+    fn test_reveal_must_match_commitment() {
+        let (vm, mut contract) = setup();
+        commit(&vm, &mut contract, ALICE, 1);
+        vm.set_block_timestamp(START + 120);
+
+        assert!(matches!(
+            reveal(&vm, &mut contract, ALICE, 2),
+            Err(VotingError::CommitmentMismatch(_))
+        ));
+        vm.set_sender(ALICE);
+        assert!(contract.reveal(U256::from(1), B256::ZERO).is_err());
+        assert!(matches!(
+            reveal(&vm, &mut contract, BOB, 1),
+            Err(VotingError::NoCommitment(_))
+        ));
+
+        reveal(&vm, &mut contract, ALICE, 1).unwrap();
+        assert!(matches!(
+            reveal(&vm, &mut contract, ALICE, 1),
+            Err(VotingError::AlreadyRevealed(_))
+        ));
+        assert_eq!(contract.tallies()[1], U256::from(1));
+    }
+
+    #[test]
+    fn test_commitments_bind_the_voter() {
+        let (vm, mut contract) = setup();
+        commit(&vm, &mut contract, ALICE, 1);
+        // Bob copies Alice's commitment but cannot open it as his own.
+        vm.set_sender(BOB);
+        let copied = contract.commitment_for(U256::from(1), salt(ALICE), ALICE);
+        contract.commit(copied).unwrap();
+
+        vm.set_block_timestamp(START + 100);
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.reveal(U256::from(1), salt(ALICE)),
+            Err(VotingError::CommitmentMismatch(_))
+        ));
+        assert!(matches!(
+            contract.commit(copied),
+            Err(VotingError::WrongPhase(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_empty_commitment() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.commit(B256::ZERO),
+            Err(VotingError::EmptyCommitment(_))
+        ));
+        assert_eq!(contract.turnout(), (U256::ZERO, U256::ZERO));
+    }
+
+    #[test]
+    fn test_recommit_and_out_of_range_choice() {
+        let (vm, mut contract) = setup();
+        commit(&vm, &mut contract, ALICE, 7);
+        commit(&vm, &mut contract, ALICE, 0);
+        commit(&vm, &mut contract, BOB, 7);
+        assert_eq!(contract.turnout(), (U256::from(2), U256::ZERO));
+
+        vm.set_block_timestamp(START + 100);
+        assert!(reveal(&vm, &mut contract, ALICE, 7).is_err());
+        reveal(&vm, &mut contract, ALICE, 0).unwrap();
+        assert!(matches!(
+            reveal(&vm, &mut contract, BOB, 7),
+            Err(VotingError::InvalidChoice(_))
+        ));
+
+        vm.set_block_timestamp(START + 150);
+        assert_eq!(
+            contract.winner().unwrap(),
+            (U256::ZERO, U256::from(1), false)
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_ballots() {
+        let vm = TestVM::default();
+        let mut contract = CommitRevealVoting::from(&vm);
+        assert!(contract.constructor(1, 100, 50).is_err());
+        assert!(contract.constructor(MAX_CHOICES + 1, 100, 50).is_err());
+        assert!(contract.constructor(2, 0, 50).is_err());
+        assert!(contract.constructor(2, 100, 0).is_err());
+    }
+}
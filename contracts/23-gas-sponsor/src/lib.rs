@@ -0,0 +1,413 @@
+//!
+//! Stylus Gas Sponsor
+//!
+//! Subsidizes gas for a curated set of contract methods. The owner funds the
+//! sponsor with ETH and allowlists `(target, selector)` pairs; users then call
+//! those methods through `sponsored_call`, which forwards the call, measures
+//! the gas it used with `evm_gas_left` before and after, and refunds
+//! `gas * tx.gasprice` to the caller.
+//!
+//! Refunds are capped by a per-user daily budget in wei. Gas spent outside
+//! the measured window (the intrinsic transaction cost, calldata and the
+//! refund itself) is covered by a fixed estimate, `OVERHEAD_GAS`. On Arbitrum
+//! the L1 data fee is charged separately from L2 gas and is not reimbursed.
+//!
+//! Only externally owned accounts are refunded: a contract calling in would
+//! otherwise collect refunds for gas its own callers paid.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, FixedBytes, U256},
+    prelude::*,
+};
+
+/// Estimated gas a sponsored transaction spends outside the measured call.
+const OVERHEAD_GAS: u64 = 40_000;
+const SECONDS_PER_DAY: u64 = 86_400;
+
+sol! {
+    event MethodAllowed(address indexed target, bytes4 indexed selector, bool allowed);
+    event DailyBudgetUpdated(uint256 budget);
+    event Funded(address indexed from, uint256 amount);
+    event GasRefunded(address indexed user, address indexed target, uint256 gasUsed, uint256 refund);
+
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error NotEOA(address caller);
+    #[derive(Debug)]
+    error InvalidTarget(address target);
+    #[derive(Debug)]
+    error MethodNotAllowed(address target, bytes4 selector);
+    #[derive(Debug)]
+    error BudgetExhausted(address user);
+    #[derive(Debug)]
+    error CallFailed(address target, bytes returnData);
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum GasSponsorError {
+    NotOwner(NotOwner),
+    NotEOA(NotEOA),
+    InvalidTarget(InvalidTarget),
+    MethodNotAllowed(MethodNotAllowed),
+    BudgetExhausted(BudgetExhausted),
+    CallFailed(CallFailed),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct GasSponsor {
+        address owner;
+        /// Most each user can be refunded per UTC day, in wei.
+        uint256 daily_budget;
+        mapping(address => mapping(bytes4 => bool)) allowed_methods;
+        /// The day (`timestamp / 86400`) each user's `spent_today` belongs to.
+        mapping(address => uint256) budget_day;
+        mapping(address => uint256) spent_today;
+        uint256 total_refunded;
+    }
+}
+
+#[public]
+impl GasSponsor {
+    #[constructor]
+    pub fn constructor(&mut self, owner: Address, daily_budget: U256) {
+        self.owner.set(owner);
+        self.daily_budget.set(daily_budget);
+    }
+
+    /// Adds ETH to the refund pool; anyone may fund.
+    #[payable]
+    pub fn fund(&mut self) {
+        let from = self.vm().msg_sender();
+        let amount = self.vm().msg_value();
+        log(self.vm(), Funded { from, amount });
+    }
+
+    /// Calls `target` with `data` and refunds the caller's gas within their daily budget.
+    ///
+    /// Returns the target's return data. If the target reverts, so does the whole
+    /// call, and nothing is refunded.
+    pub fn sponsored_call(
+        &mut self,
+        target: Address,
+        data: Bytes,
+    ) -> Result<Bytes, GasSponsorError> {
+        let user = self.vm().msg_sender();
+        if user != self.vm().tx_origin() {
+            return Err(GasSponsorError::NotEOA(NotEOA { caller: user }));
+        }
+        let selector = data
+            .get(..4)
+            .map(FixedBytes::<4>::from_slice)
+            .unwrap_or_default();
+        if data.len() < 4 || !self.is_allowed(target, selector) {
+            return Err(GasSponsorError::MethodNotAllowed(MethodNotAllowed {
+                target,
+                selector,
+            }));
+        }
+        if self.remaining_budget(user).is_zero() {
+            return Err(GasSponsorError::BudgetExhausted(BudgetExhausted { user }));
+        }
+
+        let gas_before = self.vm().evm_gas_left();
+        let returned = self
+            .vm()
+            .call(&calls::context::Call::new(), target, &data)
+            .map_err(|err| {
+                let return_data = match err {
+                    calls::errors::Error::Revert(data) => data,
+                    _ => Vec::new(),
+                };
+                GasSponsorError::CallFailed(CallFailed {
+                    target,
+                    returnData: return_data.into(),
+                })
+            })?;
+        let gas_used = gas_before.saturating_sub(self.vm().evm_gas_left()) + OVERHEAD_GAS;
+
+        // The target cannot re-enter to spend this budget: only calls from EOAs are refunded.
+        let balance = self.vm().balance(self.vm().contract_address());
+        let refund = (U256::from(gas_used) * self.vm().tx_gas_price())
+            .min(self.remaining_budget(user))
+            .min(balance);
+        // Taken before stamping today, so a new day starts from zero.
+        let spent = self.spent_on_current_day(user);
+        let today = self.today();
+        self.budget_day.setter(user).set(today);
+        self.spent_today.setter(user).set(spent + refund);
+        self.total_refunded.set(self.total_refunded.get() + refund);
+        if !refund.is_zero() {
+            self.vm().transfer_eth(user, refund).map_err(|_| {
+                GasSponsorError::TransferFailed(TransferFailed {
+                    to: user,
+                    amount: refund,
+                })
+            })?;
+        }
+        log(
+            self.vm(),
+            GasRefunded {
+                user,
+                target,
+                gasUsed: U256::from(gas_used),
+                refund,
+            },
+        );
+        Ok(Bytes::from(returned))
+    }
+
+    /// Allows or disallows sponsoring calls to `selector` on `target`.
+    pub fn allow_method(
+        &mut self,
+        target: Address,
+        selector: FixedBytes<4>,
+        allowed: bool,
+    ) -> Result<(), GasSponsorError> {
+        self.only_owner()?;
+        // Calls back into the sponsor itself are never sponsored.
+        if target == self.vm().contract_address() || target.is_zero() {
+            return Err(GasSponsorError::InvalidTarget(InvalidTarget { target }));
+        }
+        self.allowed_methods
+            .setter(target)
+            .setter(selector)
+            .set(allowed);
+        log(
+            self.vm(),
+            MethodAllowed {
+                target,
+                selector,
+                allowed,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn set_daily_budget(&mut self, budget: U256) -> Result<(), GasSponsorError> {
+        self.only_owner()?;
+        self.daily_budget.set(budget);
+        log(self.vm(), DailyBudgetUpdated { budget });
+        Ok(())
+    }
+
+    /// Sends `amount` of the refund pool back to the owner.
+    pub fn withdraw(&mut self, amount: U256) -> Result<(), GasSponsorError> {
+        let owner = self.only_owner()?;
+        self.vm()
+            .transfer_eth(owner, amount)
+            .map_err(|_| GasSponsorError::TransferFailed(TransferFailed { to: owner, amount }))
+    }
+
+    /// What `user` can still be refunded today, in wei.
+    pub fn remaining_budget(&self, user: Address) -> U256 {
+        self.daily_budget
+            .get()
+            .saturating_sub(self.spent_on_current_day(user))
+    }
+
+    pub fn is_allowed(&self, target: Address, selector: FixedBytes<4>) -> bool {
+        self.allowed_methods.getter(target).get(selector)
+    }
+
+    pub fn daily_budget(&self) -> U256 {
+        self.daily_budget.get()
+    }
+
+    pub fn total_refunded(&self) -> U256 {
+        self.total_refunded.get()
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+}
+
+impl GasSponsor {
+    /// Returns the caller if they are the owner.
+    fn only_owner(&self) -> Result<Address, GasSponsorError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(GasSponsorError::NotOwner(NotOwner { caller }));
+        }
+        Ok(caller)
+    }
+
+    fn today(&self) -> U256 {
+        U256::from(self.vm().block_timestamp() / SECONDS_PER_DAY)
+    }
+
+    /// Refunds `user` has received today; an older `spent_today` counts as zero.
+    fn spent_on_current_day(&self, user: Address) -> U256 {
+        if self.budget_day.get(user) == self.today() {
+            self.spent_today.get(user)
+        } else {
+            U256::ZERO
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::SolEvent;
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::new([0xaa; 20]);
+    const USER: Address = Address::new([0x01; 20]);
+    const TARGET: Address = Address::new([0x7a; 20]);
+    const SELECTOR: [u8; 4] = [0xde, 0xad, 0xbe, 0xef];
+    /// What one call refunds at the test VM's fixed gas price of 1 wei.
+    const REFUND: u64 = OVERHEAD_GAS;
+
+    fn calldata(arg: u8) -> Vec<u8> {
+        let mut data = SELECTOR.to_vec();
+        data.extend_from_slice(&[arg; 32]);
+        data
+    }
+
+    /// A sponsor with a budget of one and a half refunds per day and 1 ETH in the pool.
+    fn setup() -> (TestVM, GasSponsor) {
+        let vm = TestVM::default();
+        let mut contract = GasSponsor::from(&vm);
+        contract.constructor(OWNER, U256::from(REFUND * 3 / 2));
+        vm.set_balance(vm.contract_address(), U256::from(10u64.pow(18)));
+        vm.set_sender(OWNER);
+        contract
+            .allow_method(TARGET, SELECTOR.into(), true)
+            .unwrap();
+        vm.set_sender(USER);
+        vm.mock_call(TARGET, calldata(1), Ok(vec![0x42; 32]));
+        vm.mock_call(TARGET, calldata(2), Ok(vec![]));
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_sponsored_call_forwards_and_refunds() {
+        let (vm, mut contract) = setup();
+        let returned = contract
+            .sponsored_call(TARGET, Bytes::from(calldata(1)))
+            .unwrap();
+        assert_eq!(returned.0, vec![0x42; 32]);
+        assert_eq!(vm.balance(USER), U256::from(REFUND));
+        assert_eq!(contract.total_refunded(), U256::from(REFUND));
+
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = logs.last().unwrap();
+        let event = GasRefunded::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!((event.user, event.target), (USER, TARGET));
+        assert_eq!(event.gasUsed, U256::from(OVERHEAD_GAS));
+    }
+
+    #[test]
+    fn test_daily_budget_caps_refunds() {
+        let (vm, mut contract) = setup();
+        vm.set_block_timestamp(SECONDS_PER_DAY * 10);
+        contract
+            .sponsored_call(TARGET, Bytes::from(calldata(1)))
+            .unwrap();
+        contract
+            .sponsored_call(TARGET, Bytes::from(calldata(2)))
+            .unwrap();
+        assert_eq!(vm.balance(USER), U256::from(REFUND * 3 / 2));
+        assert_eq!(contract.remaining_budget(USER), U256::ZERO);
+        assert!(matches!(
+            contract.sponsored_call(TARGET, Bytes::from(calldata(1))),
+            Err(GasSponsorError::BudgetExhausted(_))
+        ));
+
+        vm.set_block_timestamp(SECONDS_PER_DAY * 11);
+        assert_eq!(contract.remaining_budget(USER), U256::from(REFUND * 3 / 2));
+        contract
+            .sponsored_call(TARGET, Bytes::from(calldata(1)))
+            .unwrap();
+        assert_eq!(vm.balance(USER), U256::from(REFUND * 5 / 2));
+        assert_eq!(contract.remaining_budget(USER), U256::from(REFUND / 2));
+    }
+
+    #[test]
+    fn test_only_allowlisted_methods() {
+        let (vm, mut contract) = setup();
+        let mut other = calldata(1);
+        other[0] = 0x00;
+        for (target, data) in [
+            (TARGET, other),
+            (Address::from([0x7b; 20]), calldata(1)),
+            (TARGET, SELECTOR[..3].to_vec()),
+        ] {
+            assert!(matches!(
+                contract.sponsored_call(target, Bytes::from(data)),
+                Err(GasSponsorError::MethodNotAllowed(_))
+            ));
+        }
+
+        vm.set_sender(OWNER);
+        contract
+            .allow_method(TARGET, SELECTOR.into(), false)
+            .unwrap();
+        vm.set_sender(USER);
+        assert!(contract
+            .sponsored_call(TARGET, Bytes::from(calldata(1)))
+            .is_err());
+        assert_eq!(vm.balance(USER), U256::ZERO);
+    }
+
+    #[test]
+    fn test_rejects_contract_callers_and_failed_calls() {
+        let (vm, mut contract) = setup();
+        vm.set_tx_origin(Address::from([0x02; 20]));
+        assert!(matches!(
+            contract.sponsored_call(TARGET, Bytes::from(calldata(1))),
+            Err(GasSponsorError::NotEOA(_))
+        ));
+
+        vm.set_tx_origin(USER);
+        vm.mock_call(TARGET, calldata(3), Err(b"nope".to_vec()));
+        assert!(matches!(
+            contract.sponsored_call(TARGET, Bytes::from(calldata(3))),
+            Err(GasSponsorError::CallFailed(CallFailed { returnData, .. }))
+                if returnData.as_ref() == b"nope"
+        ));
+        assert_eq!(contract.total_refunded(), U256::ZERO);
+    }
+
+    #[test]
+    fn test_owner_configuration() {
+        let (vm, mut contract) = setup();
+        assert!(matches!(
+            contract.set_daily_budget(U256::ZERO),
+            Err(GasSponsorError::NotOwner(_))
+        ));
+        assert!(contract.withdraw(U256::from(1)).is_err());
+
+        vm.set_sender(OWNER);
+        assert!(matches!(
+            contract.allow_method(vm.contract_address(), SELECTOR.into(), true),
+            Err(GasSponsorError::InvalidTarget(_))
+        ));
+        contract.set_daily_budget(U256::ZERO).unwrap();
+        contract.withdraw(U256::from(500)).unwrap();
+        assert_eq!(vm.balance(OWNER), U256::from(500));
+
+        vm.set_sender(USER);
+        assert!(matches!(
+            contract.sponsored_call(TARGET, Bytes::from(calldata(1))),
+            Err(GasSponsorError::BudgetExhausted(_))
+        ));
+    }
+}
@@ -0,0 +1,440 @@
+//!
+//! Stylus English Auction
+//!
+//! A single item sold to the highest bidder. Bids are paid in ETH and held by
+//! the contract. When a bid is beaten, the outbid amount is not sent back
+//! straight away: it is credited to the bidder, who pulls it out with
+//! `withdraw()`. A bidder whose address cannot receive ETH therefore cannot
+//! block later bids, and no ETH leaves the contract in the middle of `bid()`.
+//!
+//! After the deadline anyone can call `end_auction()`, which marks the auction
+//! ended before sending the winning bid to the seller. If that transfer fails,
+//! the proceeds are credited to the seller to withdraw instead.
+//!
+//! Two storage components from `contracts/common` are embedded in the auction:
+//! `Pausable` lets the seller halt bidding in an emergency (the deadline keeps
+//! running, and withdrawals stay open), and `ReentrancyGuard` wraps
+//! `withdraw()` and `end_auction()`, the two functions that send ETH.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use common::{EnforcedPause, Pausable, ReentrancyGuard, ReentrancyGuardReentrantCall};
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    prelude::*,
+};
+
+/// Each bid must beat the current highest by at least this much, in basis points.
+const MIN_INCREMENT_BPS: u64 = 500;
+const BPS: u64 = 10_000;
+
+sol! {
+    event BidPlaced(address indexed bidder, uint256 amount);
+    event AuctionEnded(address indexed winner, uint256 amount);
+    event Withdrawn(address indexed account, uint256 amount);
+
+    #[derive(Debug)]
+    error InvalidAuction();
+    #[derive(Debug)]
+    error AuctionClosed(uint256 endTime);
+    #[derive(Debug)]
+    error AuctionStillOpen(uint256 endTime);
+    #[derive(Debug)]
+    error AlreadyEnded();
+    #[derive(Debug)]
+    error SellerCannotBid();
+    #[derive(Debug)]
+    error BidTooLow(uint256 minimum);
+    #[derive(Debug)]
+    error NothingToWithdraw();
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+    #[derive(Debug)]
+    error NotSeller(address caller);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum AuctionError {
+    InvalidAuction(InvalidAuction),
+    AuctionClosed(AuctionClosed),
+    AuctionStillOpen(AuctionStillOpen),
+    AlreadyEnded(AlreadyEnded),
+    SellerCannotBid(SellerCannotBid),
+    BidTooLow(BidTooLow),
+    NothingToWithdraw(NothingToWithdraw),
+    TransferFailed(TransferFailed),
+    NotSeller(NotSeller),
+    EnforcedPause(EnforcedPause),
+    ReentrantCall(ReentrancyGuardReentrantCall),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct EnglishAuction {
+        address seller;
+        /// Lowest acceptable first bid.
+        uint256 reserve_price;
+        uint256 end_time;
+        address highest_bidder;
+        uint256 highest_bid;
+        bool ended;
+        /// Outbid amounts (and undeliverable proceeds) waiting to be withdrawn.
+        mapping(address => uint256) pending_returns;
+        Pausable pausable;
+        ReentrancyGuard reentrancy;
+    }
+}
+
+#[public]
+impl EnglishAuction {
+    /// Opens the auction for `duration` seconds from now.
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        seller: Address,
+        reserve_price: U256,
+        duration: u64,
+    ) -> Result<(), AuctionError> {
+        if seller.is_zero() || reserve_price.is_zero() || duration == 0 {
+            return Err(AuctionError::InvalidAuction(InvalidAuction {}));
+        }
+        self.seller.set(seller);
+        self.reserve_price.set(reserve_price);
+        self.end_time
+            .set(U256::from(self.vm().block_timestamp() + duration));
+        Ok(())
+    }
+
+    /// Bids `msg.value`, which must be at least `min_next_bid()`.
+    #[payable]
+    pub fn bid(&mut self) -> Result<(), AuctionError> {
+        self.pausable.when_not_paused()?;
+        let end_time = self.end_time.get();
+        if U256::from(self.vm().block_timestamp()) >= end_time {
+            return Err(AuctionError::AuctionClosed(AuctionClosed {
+                endTime: end_time,
+            }));
+        }
+        let bidder = self.vm().msg_sender();
+        if bidder == self.seller.get() {
+            return Err(AuctionError::SellerCannotBid(SellerCannotBid {}));
+        }
+        let amount = self.vm().msg_value();
+        let minimum = self.min_next_bid();
+        if amount < minimum {
+            return Err(AuctionError::BidTooLow(BidTooLow { minimum }));
+        }
+
+        let previous_bidder = self.highest_bidder.get();
+        if !previous_bidder.is_zero() {
+            let outbid = self.highest_bid.get();
+            let mut pending = self.pending_returns.setter(previous_bidder);
+            let owed = pending.get() + outbid;
+            pending.set(owed);
+        }
+        self.highest_bidder.set(bidder);
+        self.highest_bid.set(amount);
+        log(self.vm(), BidPlaced { bidder, amount });
+        Ok(())
+    }
+
+    /// Sends the caller everything credited to them.
+    pub fn withdraw(&mut self) -> Result<U256, AuctionError> {
+        self.reentrancy.enter()?;
+        let result = self.withdraw_pending();
+        self.reentrancy.exit();
+        result
+    }
+
+    /// Closes the auction after the deadline and pays the seller.
+    pub fn end_auction(&mut self) -> Result<(), AuctionError> {
+        self.reentrancy.enter()?;
+        let result = self.settle();
+        self.reentrancy.exit();
+        result
+    }
+
+    /// Stops new bids until `unpause`. Seller only.
+    pub fn pause(&mut self) -> Result<(), AuctionError> {
+        self.only_seller()?;
+        self.pausable.pause();
+        Ok(())
+    }
+
+    pub fn unpause(&mut self) -> Result<(), AuctionError> {
+        self.only_seller()?;
+        self.pausable.unpause();
+        Ok(())
+    }
+
+    pub fn paused(&self) -> bool {
+        self.pausable.paused()
+    }
+
+    /// Smallest bid `bid()` would accept now.
+    pub fn min_next_bid(&self) -> U256 {
+        let highest = self.highest_bid.get();
+        if self.highest_bidder.get().is_zero() {
+            return self.reserve_price.get();
+        }
+        let increment =
+            (highest * U256::from(MIN_INCREMENT_BPS) / U256::from(BPS)).max(U256::from(1));
+        highest + increment
+    }
+
+    /// Returns `(bidder, amount)`; the zero address before the first bid.
+    pub fn highest_bid(&self) -> (Address, U256) {
+        (self.highest_bidder.get(), self.highest_bid.get())
+    }
+
+    pub fn pending_return(&self, account: Address) -> U256 {
+        self.pending_returns.get(account)
+    }
+
+    pub fn end_time(&self) -> U256 {
+        self.end_time.get()
+    }
+
+    pub fn ended(&self) -> bool {
+        self.ended.get()
+    }
+
+    pub fn seller(&self) -> Address {
+        self.seller.get()
+    }
+}
+
+impl EnglishAuction {
+    fn only_seller(&self) -> Result<(), AuctionError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.seller.get() {
+            return Err(AuctionError::NotSeller(NotSeller { caller }));
+        }
+        Ok(())
+    }
+
+    fn withdraw_pending(&mut self) -> Result<U256, AuctionError> {
+        let account = self.vm().msg_sender();
+        let amount = self.pending_returns.get(account);
+        if amount.is_zero() {
+            return Err(AuctionError::NothingToWithdraw(NothingToWithdraw {}));
+        }
+        // Clear the outbid refund first; re-entering from the transfer then finds it spent.
+        self.pending_returns.setter(account).set(U256::ZERO);
+        self.vm().transfer_eth(account, amount).map_err(|_| {
+            AuctionError::TransferFailed(TransferFailed {
+                to: account,
+                amount,
+            })
+        })?;
+        log(self.vm(), Withdrawn { account, amount });
+        Ok(amount)
+    }
+
+    fn settle(&mut self) -> Result<(), AuctionError> {
+        let end_time = self.end_time.get();
+        if U256::from(self.vm().block_timestamp()) < end_time {
+            return Err(AuctionError::AuctionStillOpen(AuctionStillOpen {
+                endTime: end_time,
+            }));
+        }
+        if self.ended.get() {
+            return Err(AuctionError::AlreadyEnded(AlreadyEnded {}));
+        }
+        self.ended.set(true);
+
+        let winner = self.highest_bidder.get();
+        let amount = self.highest_bid.get();
+        if !amount.is_zero() {
+            let seller = self.seller.get();
+            if self.vm().transfer_eth(seller, amount).is_err() {
+                let mut pending = self.pending_returns.setter(seller);
+                let owed = pending.get() + amount;
+                pending.set(owed);
+            }
+        }
+        log(self.vm(), AuctionEnded { winner, amount });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const SELLER: Address = Address::new([0x5e; 20]);
+    const ALICE: Address = Address::new([0x01; 20]);
+    const BOB: Address = Address::new([0x02; 20]);
+    const START: u64 = 1_000;
+    const DURATION: u64 = 3_600;
+
+    fn setup() -> (TestVM, EnglishAuction) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(START);
+        let mut contract = EnglishAuction::from(&vm);
+        contract
+            .constructor(SELLER, U256::from(1_000), DURATION)
+            .unwrap();
+        (vm, contract)
+    }
+
+    fn bid(
+        vm: &TestVM,
+        contract: &mut EnglishAuction,
+        bidder: Address,
+        amount: u64,
+    ) -> Result<(), AuctionError> {
+        vm.set_sender(bidder);
+        vm.set_value(U256::from(amount));
+        let result = contract.bid();
+        if result.is_ok() {
+            let balance = vm.balance(vm.contract_address());
+            vm.set_balance(vm.contract_address(), balance + U256::from(amount));
+        }
+        vm.set_value(U256::ZERO);
+        result
+    }
+
+    #[test]
+    fn test_outbid_bidder_withdraws() {
+        let (vm, mut contract) = setup();
+        bid(&vm, &mut contract, ALICE, 1_000).unwrap();
+        bid(&vm, &mut contract, BOB, 1_050).unwrap();
+        assert_eq!(contract.highest_bid(), (BOB, U256::from(1_050)));
+        assert_eq!(contract.pending_return(ALICE), U256::from(1_000));
+
+        vm.set_sender(ALICE);
+        assert_eq!(contract.withdraw().unwrap(), U256::from(1_000));
+        assert_eq!(vm.balance(ALICE), U256::from(1_000));
+        assert!(matches!(
+            contract.withdraw(),
+            Err(AuctionError::NothingToWithdraw(_))
+        ));
+        vm.set_sender(BOB);
+        assert!(contract.withdraw().is_err());
+    }
+
+    #[test]
+    fn test_minimum_bids() {
+        let (vm, mut contract) = setup();
+        assert!(matches!(
+            bid(&vm, &mut contract, ALICE, 999),
+            Err(AuctionError::BidTooLow(BidTooLow { minimum })) if minimum == U256::from(1_000)
+        ));
+        bid(&vm, &mut contract, ALICE, 2_000).unwrap();
+        assert_eq!(contract.min_next_bid(), U256::from(2_100));
+        assert!(bid(&vm, &mut contract, BOB, 2_099).is_err());
+        assert!(matches!(
+            bid(&vm, &mut contract, SELLER, 5_000),
+            Err(AuctionError::SellerCannotBid(_))
+        ));
+        // Raising your own bid frees the old one for withdrawal.
+        bid(&vm, &mut contract, ALICE, 2_100).unwrap();
+        assert_eq!(contract.pending_return(ALICE), U256::from(2_000));
+    }
+
+    #[test]
+    fn test_end_auction_pays_seller() {
+        let (vm, mut contract) = setup();
+        bid(&vm, &mut contract, ALICE, 1_000).unwrap();
+        bid(&vm, &mut contract, BOB, 1_500).unwrap();
+        assert!(matches!(
+            contract.end_auction(),
+            Err(AuctionError::AuctionStillOpen(_))
+        ));
+
+        vm.set_block_timestamp(START + DURATION);
+        assert!(matches!(
+            bid(&vm, &mut contract, ALICE, 5_000),
+            Err(AuctionError::AuctionClosed(_))
+        ));
+        contract.end_auction().unwrap();
+        assert!(contract.ended());
+        assert_eq!(vm.balance(SELLER), U256::from(1_500));
+        assert_eq!(vm.balance(vm.contract_address()), U256::from(1_000));
+        assert!(matches!(
+            contract.end_auction(),
+            Err(AuctionError::AlreadyEnded(_))
+        ));
+
+        // Losing bids stay withdrawable after the end.
+        vm.set_sender(ALICE);
+        contract.withdraw().unwrap();
+        assert_eq!(vm.balance(vm.contract_address()), U256::ZERO);
+    }
+
+    #[test]
+    fn test_end_without_bids() {
+        let (vm, mut contract) = setup();
+        vm.set_block_timestamp(START + DURATION + 1);
+        contract.end_auction().unwrap();
+        assert_eq!(contract.highest_bid(), (Address::ZERO, U256::ZERO));
+        assert_eq!(vm.balance(SELLER), U256::ZERO);
+    }
+
+    #[test]
+    fn test_rejects_invalid_auction() {
+        let vm = TestVM::default();
+        let mut contract = EnglishAuction::from(&vm);
+        assert!(contract
+            .constructor(Address::ZERO, U256::from(1), 1)
+            .is_err());
+        assert!(contract.constructor(SELLER, U256::ZERO, 1).is_err());
+        assert!(contract.constructor(SELLER, U256::from(1), 0).is_err());
+    }
+
+    #[test]
+    fn test_seller_pauses_bidding_but_not_withdrawals() {
+        let (vm, mut contract) = setup();
+        bid(&vm, &mut contract, ALICE, 1_000).unwrap();
+        bid(&vm, &mut contract, BOB, 1_100).unwrap();
+        vm.set_sender(ALICE);
+        assert!(matches!(contract.pause(), Err(AuctionError::NotSeller(_))));
+
+        vm.set_sender(SELLER);
+        contract.pause().unwrap();
+        assert!(contract.paused());
+        assert!(matches!(
+            bid(&vm, &mut contract, ALICE, 2_000),
+            Err(AuctionError::EnforcedPause(_))
+        ));
+        vm.set_sender(ALICE);
+        contract.withdraw().unwrap();
+
+        vm.set_sender(SELLER);
+        contract.unpause().unwrap();
+        bid(&vm, &mut contract, ALICE, 2_000).unwrap();
+    }
+
+    #[test]
+    fn test_payouts_reject_reentry() {
+        let (vm, mut contract) = setup();
+        bid(&vm, &mut contract, ALICE, 1_000).unwrap();
+        bid(&vm, &mut contract, BOB, 1_100).unwrap();
+        vm.set_block_timestamp(START + DURATION);
+        // Stand in for a recipient that calls back in while receiving ETH.
+        contract.reentrancy.enter().unwrap();
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.withdraw(),
+            Err(AuctionError::ReentrantCall(_))
+        ));
+        assert!(matches!(
+            contract.end_auction(),
+            Err(AuctionError::ReentrantCall(_))
+        ));
+        contract.reentrancy.exit();
+        contract.withdraw().unwrap();
+        contract.end_auction().unwrap();
+        assert!(!contract.reentrancy.entered());
+    }
+}
@@ -0,0 +1,667 @@
+//!
+//! Stylus Sensor Registry
+//!
+//! Weather stations and other IoT devices report readings through relayers.
+//! The owner registers each device with its signing key, the metric it
+//! measures and a plausible range. A relayer submits a batch of
+//! `(timestamp, value)` readings together with one EIP-191 signature from the
+//! device's key over the whole batch; the contract recovers the signer with
+//! the `ecrecover` precompile, so relayers cannot alter or invent readings.
+//!
+//! Each reading must be newer than the device's last one and not in the
+//! future, which also stops a batch being replayed. Values are decimal strings
+//! checked for plausibility with `rust_decimal` (see `plausibility`). Accepted
+//! readings are folded into per-device daily aggregates (count, sum, min, max)
+//! that can be queried long after the raw readings are gone from calldata.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+mod plausibility;
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use alloy_sol_types::sol;
+use common::{ecdsa, paging::page};
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, B256, I256, U256},
+    prelude::*,
+};
+
+/// Most readings accepted in one batch.
+const MAX_BATCH: usize = 48;
+/// Longest metric name, in bytes.
+const MAX_METRIC_BYTES: usize = 32;
+/// Largest page `reporting_days` returns.
+const MAX_PAGE_SIZE: u64 = 50;
+const SECONDS_PER_DAY: u64 = 86_400;
+
+sol! {
+    event DeviceRegistered(uint256 indexed deviceId, address signer, string metric);
+    event DeviceUpdated(uint256 indexed deviceId, address signer, bool active);
+    event ReadingsSubmitted(uint256 indexed deviceId, address indexed relayer, uint256 count, uint256 lastTimestamp);
+
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error UnknownDevice(uint256 deviceId);
+    #[derive(Debug)]
+    error InvalidDevice(string reason);
+    #[derive(Debug)]
+    error DeviceInactive(uint256 deviceId);
+    #[derive(Debug)]
+    error InvalidBatch(string reason);
+    #[derive(Debug)]
+    error InvalidSignature(string reason);
+    #[derive(Debug)]
+    error WrongSigner(uint256 deviceId, address signer);
+    #[derive(Debug)]
+    error StaleReading(uint256 index, uint256 timestamp);
+    #[derive(Debug)]
+    error ImplausibleReading(uint256 index, string reason);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum SensorRegistryError {
+    NotOwner(NotOwner),
+    UnknownDevice(UnknownDevice),
+    InvalidDevice(InvalidDevice),
+    DeviceInactive(DeviceInactive),
+    InvalidBatch(InvalidBatch),
+    InvalidSignature(InvalidSignature),
+    WrongSigner(WrongSigner),
+    StaleReading(StaleReading),
+    ImplausibleReading(ImplausibleReading),
+}
+
+impl SensorRegistryError {
+    fn device(reason: &str) -> Self {
+        SensorRegistryError::InvalidDevice(InvalidDevice {
+            reason: reason.to_string(),
+        })
+    }
+
+    fn batch(reason: &str) -> Self {
+        SensorRegistryError::InvalidBatch(InvalidBatch {
+            reason: reason.to_string(),
+        })
+    }
+
+    fn signature(reason: &str) -> Self {
+        SensorRegistryError::InvalidSignature(InvalidSignature {
+            reason: reason.to_string(),
+        })
+    }
+}
+
+sol_storage! {
+    /// Readings from one device on one UTC day, scaled by `10^READING_SCALE`.
+    pub struct DailyAggregate {
+        uint256 count;
+        int256 sum;
+        int256 min;
+        int256 max;
+    }
+
+    pub struct Device {
+        /// Key whose signature every batch from this device must carry.
+        address signer;
+        string metric;
+        /// Inclusive plausible range, kept as the decimal strings the owner supplied.
+        string min_value;
+        string max_value;
+        bool active;
+        /// Timestamp of the newest accepted reading.
+        uint256 last_timestamp;
+        /// Days (`timestamp / 86400`) with at least one reading, oldest first.
+        uint256[] days;
+        mapping(uint256 => DailyAggregate) daily;
+    }
+
+    #[entrypoint]
+    pub struct SensorRegistry {
+        address owner;
+        /// Devices are numbered from 0 in registration order.
+        uint256 device_count;
+        mapping(uint256 => Device) devices;
+    }
+}
+
+#[public]
+impl SensorRegistry {
+    #[constructor]
+    pub fn constructor(&mut self, owner: Address) {
+        self.owner.set(owner);
+    }
+
+    /// Registers a device that signs with `signer` and returns its id.
+    pub fn register_device(
+        &mut self,
+        signer: Address,
+        metric: String,
+        min_value: String,
+        max_value: String,
+    ) -> Result<U256, SensorRegistryError> {
+        self.only_owner()?;
+        if signer.is_zero() {
+            return Err(SensorRegistryError::device("Missing signer"));
+        }
+        if metric.is_empty() || metric.len() > MAX_METRIC_BYTES {
+            return Err(SensorRegistryError::device("Invalid metric"));
+        }
+        let min = plausibility::parse_bound(&min_value)
+            .ok_or_else(|| SensorRegistryError::device("Invalid minimum"))?;
+        let max = plausibility::parse_bound(&max_value)
+            .ok_or_else(|| SensorRegistryError::device("Invalid maximum"))?;
+        if min > max {
+            return Err(SensorRegistryError::device("Minimum exceeds maximum"));
+        }
+
+        let device_id = self.device_count.get();
+        self.device_count.set(device_id + U256::from(1));
+        let mut device = self.devices.setter(device_id);
+        device.signer.set(signer);
+        device.metric.set_str(&metric);
+        device.min_value.set_str(&min_value);
+        device.max_value.set_str(&max_value);
+        device.active.set(true);
+        log(
+            self.vm(),
+            DeviceRegistered {
+                deviceId: device_id,
+                signer,
+                metric,
+            },
+        );
+        Ok(device_id)
+    }
+
+    /// Rotates a device's key or switches it on or off.
+    pub fn update_device(
+        &mut self,
+        device_id: U256,
+        signer: Address,
+        active: bool,
+    ) -> Result<(), SensorRegistryError> {
+        self.only_owner()?;
+        self.check_device(device_id)?;
+        if signer.is_zero() {
+            return Err(SensorRegistryError::device("Missing signer"));
+        }
+        let mut device = self.devices.setter(device_id);
+        device.signer.set(signer);
+        device.active.set(active);
+        log(
+            self.vm(),
+            DeviceUpdated {
+                deviceId: device_id,
+                signer,
+                active,
+            },
+        );
+        Ok(())
+    }
+
+    /// Records a signed batch of readings for `device_id`; anyone may relay.
+    ///
+    /// `signature` is the device key's 65-byte `r || s || v` `personal_sign` signature
+    /// over `batch_hash(device_id, timestamps, values)`. Timestamps must be strictly
+    /// increasing, newer than the device's last reading and not in the future.
+    /// The whole batch is rejected if any reading is implausible.
+    pub fn submit_readings(
+        &mut self,
+        device_id: U256,
+        timestamps: Vec<u64>,
+        values: Vec<String>,
+        signature: Bytes,
+    ) -> Result<(), SensorRegistryError> {
+        self.check_device(device_id)?;
+        if !self.devices.getter(device_id).active.get() {
+            return Err(SensorRegistryError::DeviceInactive(DeviceInactive {
+                deviceId: device_id,
+            }));
+        }
+        if timestamps.is_empty() || timestamps.len() > MAX_BATCH {
+            return Err(SensorRegistryError::batch("Batch size out of range"));
+        }
+        if timestamps.len() != values.len() {
+            return Err(SensorRegistryError::batch(
+                "Timestamps and values differ in length",
+            ));
+        }
+
+        let hash = self.batch_hash(device_id, timestamps.clone(), values.clone());
+        let signer = ecdsa::recover(self.vm(), ecdsa::eip191_digest(self.vm(), hash), &signature)
+            .map_err(|err| SensorRegistryError::signature(err.reason()))?;
+        if signer != self.devices.getter(device_id).signer.get() {
+            return Err(SensorRegistryError::WrongSigner(WrongSigner {
+                deviceId: device_id,
+                signer,
+            }));
+        }
+
+        let device = self.devices.getter(device_id);
+        let min =
+            plausibility::parse_bound(&device.min_value.get_string()).expect("validated on write");
+        let max =
+            plausibility::parse_bound(&device.max_value.get_string()).expect("validated on write");
+        let mut previous = device.last_timestamp.get();
+        let now = U256::from(self.vm().block_timestamp());
+        let mut readings = Vec::with_capacity(values.len());
+        for (index, (&timestamp, value)) in timestamps.iter().zip(&values).enumerate() {
+            let time = U256::from(timestamp);
+            if time <= previous || time > now {
+                return Err(SensorRegistryError::StaleReading(StaleReading {
+                    index: U256::from(index),
+                    timestamp: time,
+                }));
+            }
+            previous = time;
+            let scaled = plausibility::check(value, min, max).map_err(|reason| {
+                SensorRegistryError::ImplausibleReading(ImplausibleReading {
+                    index: U256::from(index),
+                    reason: reason.to_string(),
+                })
+            })?;
+            readings.push((timestamp / SECONDS_PER_DAY, scaled));
+        }
+
+        let mut device = self.devices.setter(device_id);
+        device.last_timestamp.set(previous);
+        for (day, value) in readings {
+            let day = U256::from(day);
+            let mut aggregate = device.daily.setter(day);
+            let count = aggregate.count.get();
+            if count.is_zero() || value < aggregate.min.get() {
+                aggregate.min.set(value);
+            }
+            if count.is_zero() || value > aggregate.max.get() {
+                aggregate.max.set(value);
+            }
+            aggregate.count.set(count + U256::from(1));
+            let sum = aggregate.sum.get();
+            aggregate.sum.set(sum + value);
+            if count.is_zero() {
+                device.days.push(day);
+            }
+        }
+        let relayer = self.vm().msg_sender();
+        log(
+            self.vm(),
+            ReadingsSubmitted {
+                deviceId: device_id,
+                relayer,
+                count: U256::from(values.len()),
+                lastTimestamp: previous,
+            },
+        );
+        Ok(())
+    }
+
+    /// The hash a device signs for a batch: `keccak256(abi.encodePacked(address(this),
+    /// deviceId, timestamp_0, keccak256(value_0), timestamp_1, ...))` with each
+    /// timestamp as a `uint256`.
+    pub fn batch_hash(&self, device_id: U256, timestamps: Vec<u64>, values: Vec<String>) -> B256 {
+        let mut message = Vec::with_capacity(52 + 64 * timestamps.len());
+        message.extend_from_slice(self.vm().contract_address().as_slice());
+        message.extend_from_slice(&device_id.to_be_bytes::<32>());
+        for (timestamp, value) in timestamps.iter().zip(&values) {
+            message.extend_from_slice(&U256::from(*timestamp).to_be_bytes::<32>());
+            message.extend_from_slice(self.vm().native_keccak256(value.as_bytes()).as_slice());
+        }
+        self.vm().native_keccak256(&message)
+    }
+
+    /// Returns `(signer, metric, min_value, max_value, active, last_timestamp)`.
+    pub fn device(
+        &self,
+        device_id: U256,
+    ) -> Result<(Address, String, String, String, bool, U256), SensorRegistryError> {
+        self.check_device(device_id)?;
+        let device = self.devices.getter(device_id);
+        Ok((
+            device.signer.get(),
+            device.metric.get_string(),
+            device.min_value.get_string(),
+            device.max_value.get_string(),
+            device.active.get(),
+            device.last_timestamp.get(),
+        ))
+    }
+
+    pub fn device_count(&self) -> U256 {
+        self.device_count.get()
+    }
+
+    /// Returns up to `limit` days with readings starting at `offset`, oldest first.
+    ///
+    /// Each call returns at most `MAX_PAGE_SIZE` days, and none once `offset` passes the
+    /// device's last reporting day.
+    pub fn reporting_days(
+        &self,
+        device_id: U256,
+        offset: U256,
+        limit: U256,
+    ) -> Result<Vec<U256>, SensorRegistryError> {
+        self.check_device(device_id)?;
+        let days = &self.devices.getter(device_id).days;
+        Ok(page(days.len(), offset, limit, MAX_PAGE_SIZE)
+            .filter_map(|index| days.get(index))
+            .collect())
+    }
+
+    /// Returns `(count, sum, min, max, scale)` for one day; all zero if nothing was reported.
+    ///
+    /// `day` is `timestamp / 86400`, and values are integers scaled by `10^scale`.
+    pub fn daily_aggregate(
+        &self,
+        device_id: U256,
+        day: U256,
+    ) -> Result<(U256, I256, I256, I256, u8), SensorRegistryError> {
+        self.check_device(device_id)?;
+        let device = self.devices.getter(device_id);
+        let aggregate = device.daily.getter(day);
+        Ok((
+            aggregate.count.get(),
+            aggregate.sum.get(),
+            aggregate.min.get(),
+            aggregate.max.get(),
+            plausibility::READING_SCALE as u8,
+        ))
+    }
+
+    /// Returns `(average, scale)` for one day, truncated toward zero.
+    pub fn daily_average(
+        &self,
+        device_id: U256,
+        day: U256,
+    ) -> Result<(I256, u8), SensorRegistryError> {
+        let (count, sum, _, _, scale) = self.daily_aggregate(device_id, day)?;
+        if count.is_zero() {
+            return Ok((I256::ZERO, scale));
+        }
+        let count = I256::try_from(count).expect("count fits in int256");
+        Ok((sum / count, scale))
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+}
+
+impl SensorRegistry {
+    fn only_owner(&self) -> Result<(), SensorRegistryError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(SensorRegistryError::NotOwner(NotOwner { caller }));
+        }
+        Ok(())
+    }
+
+    fn check_device(&self, device_id: U256) -> Result<(), SensorRegistryError> {
+        if device_id >= self.device_count.get() {
+            return Err(SensorRegistryError::UnknownDevice(UnknownDevice {
+                deviceId: device_id,
+            }));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::{alloy_primitives::keccak256, testing::*};
+
+    const OWNER: Address = Address::new([0xaa; 20]);
+    const DEVICE_KEY: Address = Address::new([0xd1; 20]);
+    const RELAYER: Address = Address::new([0x01; 20]);
+    /// Noon on day 20000.
+    const NOW: u64 = 20_000 * SECONDS_PER_DAY + 43_200;
+
+    fn setup() -> (TestVM, SensorRegistry, U256) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(NOW);
+        let mut contract = SensorRegistry::from(&vm);
+        contract.constructor(OWNER);
+        vm.set_sender(OWNER);
+        let device_id = contract
+            .register_device(
+                DEVICE_KEY,
+                "temperature_c".to_string(),
+                "-60".to_string(),
+                "60".to_string(),
+            )
+            .unwrap();
+        vm.set_sender(RELAYER);
+        (vm, contract, device_id)
+    }
+
+    /// A well-formed signature with `s` below the malleability bound and `v = 27`.
+    fn signature(seed: u8) -> Vec<u8> {
+        let mut sig = vec![seed; 32];
+        sig.extend_from_slice(&[0x11; 32]);
+        sig.push(27);
+        sig
+    }
+
+    /// Signs `readings` as `signer` by mocking the precompile, then submits them.
+    fn submit(
+        vm: &TestVM,
+        contract: &mut SensorRegistry,
+        device_id: U256,
+        readings: &[(u64, &str)],
+        signer: Address,
+    ) -> Result<(), SensorRegistryError> {
+        let timestamps: Vec<u64> = readings.iter().map(|r| r.0).collect();
+        let values: Vec<String> = readings.iter().map(|r| r.1.to_string()).collect();
+        let sig = signature(readings.len() as u8);
+        let mut prefixed = b"\x19Ethereum Signed Message:\n32".to_vec();
+        prefixed.extend_from_slice(
+            contract
+                .batch_hash(device_id, timestamps.clone(), values.clone())
+                .as_slice(),
+        );
+        let mut input = keccak256(&prefixed).to_vec();
+        input.extend_from_slice(&U256::from(sig[64]).to_be_bytes::<32>());
+        input.extend_from_slice(&sig[..64]);
+        vm.mock_static_call(ecdsa::ECRECOVER, input, Ok(signer.into_word().to_vec()));
+        contract.submit_readings(device_id, timestamps, values, Bytes::from(sig))
+    }
+
+    fn scaled(micros: i64) -> I256 {
+        I256::try_from(micros).unwrap()
+    }
+
+    #[test]
+    fn test_batch_builds_daily_aggregates() {
+        let (vm, mut contract, device_id) = setup();
+        let yesterday = NOW - SECONDS_PER_DAY;
+        submit(
+            &vm,
+            &mut contract,
+            device_id,
+            &[
+                (yesterday, "12.5"),
+                (yesterday + 60, "-3.25"),
+                (NOW - 60, "20"),
+            ],
+            DEVICE_KEY,
+        )
+        .unwrap();
+        submit(
+            &vm,
+            &mut contract,
+            device_id,
+            &[(NOW, "21.000001")],
+            DEVICE_KEY,
+        )
+        .unwrap();
+
+        let (day_before, today) = (U256::from(19_999), U256::from(20_000));
+        assert_eq!(
+            contract
+                .reporting_days(device_id, U256::ZERO, U256::from(10))
+                .unwrap(),
+            vec![day_before, today]
+        );
+        assert_eq!(
+            contract.daily_aggregate(device_id, day_before).unwrap(),
+            (
+                U256::from(2),
+                scaled(9_250_000),
+                scaled(-3_250_000),
+                scaled(12_500_000),
+                6
+            )
+        );
+        assert_eq!(
+            contract.daily_average(device_id, today).unwrap(),
+            (scaled(20_500_000), 6)
+        );
+        assert_eq!(contract.device(device_id).unwrap().5, U256::from(NOW));
+        assert_eq!(
+            contract.daily_average(device_id, U256::from(1)).unwrap(),
+            (I256::ZERO, 6)
+        );
+    }
+
+    #[test]
+    fn test_rejects_wrong_signer_and_bad_signatures() {
+        let (vm, mut contract, device_id) = setup();
+        assert!(matches!(
+            submit(&vm, &mut contract, device_id, &[(NOW, "1")], RELAYER),
+            Err(SensorRegistryError::WrongSigner(WrongSigner { signer, .. })) if signer == RELAYER
+        ));
+        // Nothing mocked for this batch, so recovery yields no address.
+        let result = contract.submit_readings(
+            device_id,
+            vec![NOW],
+            vec!["2".to_string()],
+            Bytes::from(signature(9)),
+        );
+        assert!(matches!(
+            result,
+            Err(SensorRegistryError::InvalidSignature(_))
+        ));
+        assert!(contract
+            .submit_readings(
+                device_id,
+                vec![NOW],
+                vec!["2".to_string()],
+                Bytes::from(vec![0; 64])
+            )
+            .is_err());
+
+        // After key rotation only the new key is accepted.
+        vm.set_sender(OWNER);
+        contract.update_device(device_id, RELAYER, true).unwrap();
+        submit(&vm, &mut contract, device_id, &[(NOW, "1")], RELAYER).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_stale_future_and_implausible_readings() {
+        let (vm, mut contract, device_id) = setup();
+        submit(
+            &vm,
+            &mut contract,
+            device_id,
+            &[(NOW - 100, "1")],
+            DEVICE_KEY,
+        )
+        .unwrap();
+
+        let stale = submit(
+            &vm,
+            &mut contract,
+            device_id,
+            &[(NOW - 100, "1")],
+            DEVICE_KEY,
+        );
+        assert!(matches!(
+            stale,
+            Err(SensorRegistryError::StaleReading(StaleReading { index, .. })) if index.is_zero()
+        ));
+        assert!(submit(&vm, &mut contract, device_id, &[(NOW + 1, "1")], DEVICE_KEY).is_err());
+        assert!(submit(
+            &vm,
+            &mut contract,
+            device_id,
+            &[(NOW - 10, "1"), (NOW - 20, "1")],
+            DEVICE_KEY
+        )
+        .is_err());
+
+        for (value, reason) in [
+            ("61", "Above plausible range"),
+            ("-60.5", "Below plausible range"),
+            ("1.0000001", "Too many decimal places"),
+            ("warm", "Invalid decimal format"),
+        ] {
+            let result = submit(
+                &vm,
+                &mut contract,
+                device_id,
+                &[(NOW - 50, "1"), (NOW - 40, value)],
+                DEVICE_KEY,
+            );
+            assert!(matches!(
+                result,
+                Err(SensorRegistryError::ImplausibleReading(ImplausibleReading { index, reason: r }))
+                    if index == U256::from(1) && r == reason
+            ));
+        }
+        // Rejected batches leave the aggregates untouched.
+        let (count, ..) = contract
+            .daily_aggregate(device_id, U256::from(20_000))
+            .unwrap();
+        assert_eq!(count, U256::from(1));
+    }
+
+    #[test]
+    fn test_device_registration_rules() {
+        let (vm, mut contract, device_id) = setup();
+        assert!(matches!(
+            contract.register_device(
+                DEVICE_KEY,
+                "rh".to_string(),
+                "0".to_string(),
+                "100".to_string()
+            ),
+            Err(SensorRegistryError::NotOwner(_))
+        ));
+
+        vm.set_sender(OWNER);
+        for (metric, min, max, reason) in [
+            ("rh", "10", "0", "Minimum exceeds maximum"),
+            ("rh", "0.0000001", "1", "Invalid minimum"),
+            ("", "0", "1", "Invalid metric"),
+        ] {
+            assert!(matches!(
+                contract.register_device(DEVICE_KEY, metric.to_string(), min.to_string(), max.to_string()),
+                Err(SensorRegistryError::InvalidDevice(InvalidDevice { reason: r })) if r == reason
+            ));
+        }
+
+        contract
+            .update_device(device_id, DEVICE_KEY, false)
+            .unwrap();
+        vm.set_sender(RELAYER);
+        assert!(matches!(
+            submit(&vm, &mut contract, device_id, &[(NOW, "1")], DEVICE_KEY),
+            Err(SensorRegistryError::DeviceInactive(_))
+        ));
+        assert!(matches!(
+            contract.device(U256::from(5)),
+            Err(SensorRegistryError::UnknownDevice(_))
+        ));
+    }
+}
@@ -0,0 +1,44 @@
+//! Plausibility checks for sensor readings.
+//!
+//! Readings arrive as decimal strings and are parsed with `rust_decimal`, as in
+//! `onchain-data-validator`. A reading is plausible if it parses, lies within
+//! the device's inclusive `[min, max]` range and has at most
+//! `MAX_DECIMAL_PLACES` fractional digits. Plausible readings are converted to
+//! integers scaled by `10^READING_SCALE` for aggregation.
+
+use core::str::FromStr;
+use rust_decimal::Decimal;
+use stylus_sdk::alloy_primitives::I256;
+
+/// Finest resolution accepted from a sensor.
+pub const MAX_DECIMAL_PLACES: u32 = 6;
+/// Aggregates are stored as integers scaled by `10^READING_SCALE`.
+pub const READING_SCALE: usize = 6;
+
+/// Parses a bound supplied when a device is registered.
+pub fn parse_bound(value: &str) -> Option<Decimal> {
+    Decimal::from_str(value)
+        .ok()
+        .filter(|bound| bound.scale() <= MAX_DECIMAL_PLACES)
+}
+
+/// Checks `value` against `[min, max]` and returns it scaled by `10^READING_SCALE`.
+pub fn check(value: &str, min: Decimal, max: Decimal) -> Result<I256, &'static str> {
+    let reading = Decimal::from_str(value).map_err(|_| "Invalid decimal format")?;
+    if reading.scale() > MAX_DECIMAL_PLACES {
+        return Err("Too many decimal places");
+    }
+    if reading < min {
+        return Err("Below plausible range");
+    }
+    if reading > max {
+        return Err("Above plausible range");
+    }
+    Ok(to_scaled(reading))
+}
+
+/// Converts a reading with at most `READING_SCALE` fractional digits to a scaled integer.
+pub fn to_scaled(reading: Decimal) -> I256 {
+    let mantissa = I256::try_from(reading.mantissa()).expect("i128 fits in int256");
+    mantissa * I256::exp10(READING_SCALE - reading.scale() as usize)
+}
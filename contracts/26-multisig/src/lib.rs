@@ -0,0 +1,441 @@
+//!
+//! Stylus Multisig Wallet
+//!
+//! A wallet controlled by a fixed set of owners. Any owner can propose a
+//! transaction (`to`, `value`, `data`); it runs once `threshold` owners have
+//! confirmed it. Proposals are numbered by a nonce that only ever increases,
+//! so each confirmation applies to exactly one transaction and an executed
+//! transaction can never run again.
+//!
+//! `execute` marks the transaction executed before making the call, so a
+//! target that calls back into the wallet cannot execute it twice.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{string::ToString, vec::Vec};
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, U256},
+    prelude::*,
+};
+
+/// Largest owner set accepted.
+const MAX_OWNERS: usize = 20;
+
+sol! {
+    event Deposit(address indexed sender, uint256 amount);
+    event Submission(uint256 indexed txId, address indexed owner, address to, uint256 value);
+    event Confirmation(uint256 indexed txId, address indexed owner);
+    event Revocation(uint256 indexed txId, address indexed owner);
+    event Execution(uint256 indexed txId);
+
+    #[derive(Debug)]
+    error InvalidOwners(string reason);
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error UnknownTransaction(uint256 txId);
+    #[derive(Debug)]
+    error AlreadyExecuted(uint256 txId);
+    #[derive(Debug)]
+    error AlreadyConfirmed(uint256 txId, address owner);
+    #[derive(Debug)]
+    error NotConfirmed(uint256 txId, address owner);
+    #[derive(Debug)]
+    error ThresholdNotMet(uint256 txId, uint256 confirmations, uint256 threshold);
+    #[derive(Debug)]
+    error ExecutionFailed(uint256 txId, bytes returnData);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum MultisigError {
+    InvalidOwners(InvalidOwners),
+    NotOwner(NotOwner),
+    UnknownTransaction(UnknownTransaction),
+    AlreadyExecuted(AlreadyExecuted),
+    AlreadyConfirmed(AlreadyConfirmed),
+    NotConfirmed(NotConfirmed),
+    ThresholdNotMet(ThresholdNotMet),
+    ExecutionFailed(ExecutionFailed),
+}
+
+sol_storage! {
+    pub struct Transaction {
+        address to;
+        uint256 value;
+        bytes data;
+        bool executed;
+        uint256 confirmations;
+        mapping(address => bool) confirmed_by;
+    }
+
+    #[entrypoint]
+    pub struct Multisig {
+        address[] owners;
+        mapping(address => bool) is_owner;
+        uint256 threshold;
+        /// Transactions are numbered from 0; the count doubles as the next nonce.
+        uint256 transaction_count;
+        mapping(uint256 => Transaction) transactions;
+    }
+}
+
+#[public]
+impl Multisig {
+    /// Sets up the owners and how many of them must confirm each transaction.
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        owners: Vec<Address>,
+        threshold: U256,
+    ) -> Result<(), MultisigError> {
+        let invalid = |reason: &str| {
+            MultisigError::InvalidOwners(InvalidOwners {
+                reason: reason.to_string(),
+            })
+        };
+        if owners.is_empty() || owners.len() > MAX_OWNERS {
+            return Err(invalid("Owner count out of range"));
+        }
+        if threshold.is_zero() || threshold > U256::from(owners.len()) {
+            return Err(invalid("Threshold out of range"));
+        }
+        for owner in owners {
+            if owner.is_zero() {
+                return Err(invalid("Zero address owner"));
+            }
+            if self.is_owner.get(owner) {
+                return Err(invalid("Duplicate owner"));
+            }
+            self.is_owner.setter(owner).set(true);
+            self.owners.push(owner);
+        }
+        self.threshold.set(threshold);
+        Ok(())
+    }
+
+    /// Accepts plain ETH transfers.
+    #[receive]
+    #[payable]
+    pub fn receive(&mut self) -> Result<(), Vec<u8>> {
+        let sender = self.vm().msg_sender();
+        let amount = self.vm().msg_value();
+        log(self.vm(), Deposit { sender, amount });
+        Ok(())
+    }
+
+    /// Proposes a call and confirms it for the caller, returning its id.
+    pub fn submit_transaction(
+        &mut self,
+        to: Address,
+        value: U256,
+        data: Bytes,
+    ) -> Result<U256, MultisigError> {
+        let owner = self.only_owner()?;
+        let tx_id = self.transaction_count.get();
+        self.transaction_count.set(tx_id + U256::from(1));
+        let mut transaction = self.transactions.setter(tx_id);
+        transaction.to.set(to);
+        transaction.value.set(value);
+        transaction.data.set_bytes(&data);
+        log(
+            self.vm(),
+            Submission {
+                txId: tx_id,
+                owner,
+                to,
+                value,
+            },
+        );
+        self.confirm(tx_id)?;
+        Ok(tx_id)
+    }
+
+    pub fn confirm(&mut self, tx_id: U256) -> Result<(), MultisigError> {
+        let owner = self.only_owner()?;
+        self.check_pending(tx_id)?;
+        let mut transaction = self.transactions.setter(tx_id);
+        if transaction.confirmed_by.get(owner) {
+            return Err(MultisigError::AlreadyConfirmed(AlreadyConfirmed {
+                txId: tx_id,
+                owner,
+            }));
+        }
+        transaction.confirmed_by.setter(owner).set(true);
+        let confirmations = transaction.confirmations.get() + U256::from(1);
+        transaction.confirmations.set(confirmations);
+        log(self.vm(), Confirmation { txId: tx_id, owner });
+        Ok(())
+    }
+
+    /// Withdraws the caller's confirmation from a transaction that has not run yet.
+    pub fn revoke(&mut self, tx_id: U256) -> Result<(), MultisigError> {
+        let owner = self.only_owner()?;
+        self.check_pending(tx_id)?;
+        let mut transaction = self.transactions.setter(tx_id);
+        if !transaction.confirmed_by.get(owner) {
+            return Err(MultisigError::NotConfirmed(NotConfirmed {
+                txId: tx_id,
+                owner,
+            }));
+        }
+        transaction.confirmed_by.setter(owner).set(false);
+        let confirmations = transaction.confirmations.get() - U256::from(1);
+        transaction.confirmations.set(confirmations);
+        log(self.vm(), Revocation { txId: tx_id, owner });
+        Ok(())
+    }
+
+    /// Runs a confirmed transaction, returning the target's return data.
+    ///
+    /// If the call reverts, so does `execute`, and the transaction can be retried.
+    pub fn execute(&mut self, tx_id: U256) -> Result<Bytes, MultisigError> {
+        self.only_owner()?;
+        self.check_pending(tx_id)?;
+        let mut transaction = self.transactions.setter(tx_id);
+        let confirmations = transaction.confirmations.get();
+        let threshold = self.threshold.get();
+        if confirmations < threshold {
+            return Err(MultisigError::ThresholdNotMet(ThresholdNotMet {
+                txId: tx_id,
+                confirmations,
+                threshold,
+            }));
+        }
+        transaction.executed.set(true);
+        let (to, value, data) = (
+            transaction.to.get(),
+            transaction.value.get(),
+            transaction.data.get_bytes(),
+        );
+
+        let context = calls::context::Call::new().value(value);
+        match self.vm().call(&context, to, &data) {
+            Ok(returned) => {
+                log(self.vm(), Execution { txId: tx_id });
+                Ok(Bytes::from(returned))
+            }
+            Err(err) => {
+                let return_data = match err {
+                    calls::errors::Error::Revert(data) => data,
+                    _ => Vec::new(),
+                };
+                Err(MultisigError::ExecutionFailed(ExecutionFailed {
+                    txId: tx_id,
+                    returnData: return_data.into(),
+                }))
+            }
+        }
+    }
+
+    /// Returns `(to, value, data, executed, confirmations)`.
+    pub fn transaction(
+        &self,
+        tx_id: U256,
+    ) -> Result<(Address, U256, Bytes, bool, U256), MultisigError> {
+        self.check_transaction(tx_id)?;
+        let transaction = self.transactions.getter(tx_id);
+        Ok((
+            transaction.to.get(),
+            transaction.value.get(),
+            Bytes::from(transaction.data.get_bytes()),
+            transaction.executed.get(),
+            transaction.confirmations.get(),
+        ))
+    }
+
+    pub fn is_confirmed(&self, tx_id: U256, owner: Address) -> bool {
+        self.transactions.getter(tx_id).confirmed_by.get(owner)
+    }
+
+    pub fn owners(&self) -> Vec<Address> {
+        (0..self.owners.len())
+            .filter_map(|index| self.owners.get(index))
+            .collect()
+    }
+
+    pub fn threshold(&self) -> U256 {
+        self.threshold.get()
+    }
+
+    pub fn transaction_count(&self) -> U256 {
+        self.transaction_count.get()
+    }
+}
+
+impl Multisig {
+    /// Returns the caller if they are an owner.
+    fn only_owner(&self) -> Result<Address, MultisigError> {
+        let caller = self.vm().msg_sender();
+        if !self.is_owner.get(caller) {
+            return Err(MultisigError::NotOwner(NotOwner { caller }));
+        }
+        Ok(caller)
+    }
+
+    fn check_transaction(&self, tx_id: U256) -> Result<(), MultisigError> {
+        if tx_id >= self.transaction_count.get() {
+            return Err(MultisigError::UnknownTransaction(UnknownTransaction {
+                txId: tx_id,
+            }));
+        }
+        Ok(())
+    }
+
+    fn check_pending(&self, tx_id: U256) -> Result<(), MultisigError> {
+        self.check_transaction(tx_id)?;
+        if self.transactions.getter(tx_id).executed.get() {
+            return Err(MultisigError::AlreadyExecuted(AlreadyExecuted {
+                txId: tx_id,
+            }));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const ALICE: Address = Address::new([0x01; 20]);
+    const BOB: Address = Address::new([0x02; 20]);
+    const CAROL: Address = Address::new([0x03; 20]);
+    const TARGET: Address = Address::new([0x7a; 20]);
+
+    /// A 2-of-3 wallet with a pending call to `TARGET` submitted by Alice.
+    fn setup() -> (TestVM, Multisig, U256) {
+        let vm = TestVM::default();
+        let mut contract = Multisig::from(&vm);
+        contract
+            .constructor(vec![ALICE, BOB, CAROL], U256::from(2))
+            .unwrap();
+        vm.set_sender(ALICE);
+        let tx_id = contract
+            .submit_transaction(TARGET, U256::from(5), Bytes::from(vec![0xab, 0xcd]))
+            .unwrap();
+        (vm, contract, tx_id)
+    }
+
+    #[test]
+    fn test_executes_after_threshold() {
+        let (vm, mut contract, tx_id) = setup();
+        vm.mock_call(TARGET, vec![0xab, 0xcd], Ok(vec![0x01]));
+        assert!(matches!(
+            contract.execute(tx_id),
+            Err(MultisigError::ThresholdNotMet(_))
+        ));
+
+        vm.set_sender(BOB);
+        contract.confirm(tx_id).unwrap();
+        assert_eq!(contract.execute(tx_id).unwrap().0, vec![0x01]);
+        let (to, value, data, executed, confirmations) = contract.transaction(tx_id).unwrap();
+        assert_eq!(
+            (to, value, data.0),
+            (TARGET, U256::from(5), vec![0xab, 0xcd])
+        );
+        assert!(executed);
+        assert_eq!(confirmations, U256::from(2));
+
+        assert!(matches!(
+            contract.execute(tx_id),
+            Err(MultisigError::AlreadyExecuted(_))
+        ));
+        vm.set_sender(CAROL);
+        assert!(contract.confirm(tx_id).is_err());
+    }
+
+    #[test]
+    fn test_confirm_and_revoke() {
+        let (vm, mut contract, tx_id) = setup();
+        assert!(contract.is_confirmed(tx_id, ALICE));
+        assert!(matches!(
+            contract.confirm(tx_id),
+            Err(MultisigError::AlreadyConfirmed(_))
+        ));
+
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.revoke(tx_id),
+            Err(MultisigError::NotConfirmed(_))
+        ));
+        contract.confirm(tx_id).unwrap();
+        contract.revoke(tx_id).unwrap();
+        assert!(!contract.is_confirmed(tx_id, BOB));
+        assert!(matches!(
+            contract.execute(tx_id),
+            Err(MultisigError::ThresholdNotMet(ThresholdNotMet { confirmations, .. }))
+                if confirmations == U256::from(1)
+        ));
+    }
+
+    #[test]
+    fn test_failed_call_surfaces_return_data() {
+        let (vm, mut contract, tx_id) = setup();
+        vm.set_sender(CAROL);
+        contract.confirm(tx_id).unwrap();
+        vm.mock_call(TARGET, vec![0xab, 0xcd], Err(b"no".to_vec()));
+        assert!(matches!(
+            contract.execute(tx_id),
+            Err(MultisigError::ExecutionFailed(ExecutionFailed { txId, returnData }))
+                if txId == tx_id && returnData.as_ref() == b"no"
+        ));
+    }
+
+    #[test]
+    fn test_only_owners_act_and_nonces_increase() {
+        let (vm, mut contract, tx_id) = setup();
+        vm.set_sender(TARGET);
+        assert!(matches!(
+            contract.submit_transaction(TARGET, U256::ZERO, Bytes::from(vec![])),
+            Err(MultisigError::NotOwner(_))
+        ));
+        assert!(contract.confirm(tx_id).is_err());
+        assert!(contract.execute(tx_id).is_err());
+
+        vm.set_sender(BOB);
+        let next = contract
+            .submit_transaction(TARGET, U256::ZERO, Bytes::from(vec![]))
+            .unwrap();
+        assert_eq!(next, tx_id + U256::from(1));
+        assert_eq!(contract.transaction_count(), U256::from(2));
+        assert!(matches!(
+            contract.confirm(U256::from(2)),
+            Err(MultisigError::UnknownTransaction(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_invalid_owner_sets() {
+        let vm = TestVM::default();
+        let mut contract = Multisig::from(&vm);
+        let reason = |result: Result<(), MultisigError>| match result {
+            Err(MultisigError::InvalidOwners(e)) => e.reason,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(
+            reason(contract.constructor(vec![], U256::from(1))),
+            "Owner count out of range"
+        );
+        assert_eq!(
+            reason(contract.constructor(vec![ALICE, BOB], U256::from(3))),
+            "Threshold out of range"
+        );
+        assert_eq!(
+            reason(contract.constructor(vec![ALICE, Address::ZERO], U256::from(1))),
+            "Zero address owner"
+        );
+        let mut contract = Multisig::from(&TestVM::default());
+        assert_eq!(
+            reason(contract.constructor(vec![ALICE, ALICE], U256::from(1))),
+            "Duplicate owner"
+        );
+    }
+}
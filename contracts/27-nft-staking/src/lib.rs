@@ -0,0 +1,367 @@
+//!
+//! Stylus NFT Staking
+//!
+//! Holders of an ERC-721 collection (such as the `erc721-nft` example) stake
+//! tokens here to earn reward points. `stake` pulls the token into escrow with
+//! a cross-contract `transferFrom`, so the holder must first approve this
+//! contract. Every staked token earns `points_per_block` points for each block
+//! it stays staked; `unstake` sends the token back and `redeem` spends points.
+//!
+//! Points are settled lazily: a user's balance is brought up to date whenever
+//! their stake changes or they redeem, using the number of tokens they had
+//! staked since their last update.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall};
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    prelude::*,
+};
+
+sol! {
+    interface IERC721 {
+        function transferFrom(address from, address to, uint256 tokenId) external;
+    }
+
+    event Staked(address indexed user, uint256 indexed tokenId);
+    event Unstaked(address indexed user, uint256 indexed tokenId);
+    event Redeemed(address indexed user, uint256 amount);
+
+    #[derive(Debug)]
+    error InvalidConfig();
+    #[derive(Debug)]
+    error AlreadyStaked(uint256 tokenId);
+    #[derive(Debug)]
+    error NotStaker(uint256 tokenId, address caller);
+    #[derive(Debug)]
+    error TransferFailed(uint256 tokenId);
+    #[derive(Debug)]
+    error InsufficientPoints(uint256 available, uint256 requested);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum NftStakingError {
+    InvalidConfig(InvalidConfig),
+    AlreadyStaked(AlreadyStaked),
+    NotStaker(NotStaker),
+    TransferFailed(TransferFailed),
+    InsufficientPoints(InsufficientPoints),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct NftStaking {
+        address nft;
+        uint256 points_per_block;
+        /// Who staked each token; zero if it is not staked.
+        mapping(uint256 => address) staker_of;
+        /// Each staked token's position in its staker's `staked_tokens`.
+        mapping(uint256 => uint256) token_index;
+        mapping(address => uint256[]) staked_tokens;
+        /// Points settled up to `last_update_block`.
+        mapping(address => uint256) points;
+        mapping(address => uint256) last_update_block;
+        uint256 total_staked;
+    }
+}
+
+#[public]
+impl NftStaking {
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        nft: Address,
+        points_per_block: U256,
+    ) -> Result<(), NftStakingError> {
+        if nft.is_zero() || points_per_block.is_zero() {
+            return Err(NftStakingError::InvalidConfig(InvalidConfig {}));
+        }
+        self.nft.set(nft);
+        self.points_per_block.set(points_per_block);
+        Ok(())
+    }
+
+    /// Moves `token_id` from the caller into escrow; the caller must have approved this contract.
+    pub fn stake(&mut self, token_id: U256) -> Result<(), NftStakingError> {
+        if !self.staker_of.get(token_id).is_zero() {
+            return Err(NftStakingError::AlreadyStaked(AlreadyStaked {
+                tokenId: token_id,
+            }));
+        }
+        let user = self.vm().msg_sender();
+        let escrow = self.vm().contract_address();
+        self.transfer_nft(user, escrow, token_id)?;
+
+        self.settle(user);
+        let mut tokens = self.staked_tokens.setter(user);
+        let index = U256::from(tokens.len());
+        tokens.push(token_id);
+        self.token_index.setter(token_id).set(index);
+        self.staker_of.setter(token_id).set(user);
+        self.total_staked
+            .set(self.total_staked.get() + U256::from(1));
+        log(
+            self.vm(),
+            Staked {
+                user,
+                tokenId: token_id,
+            },
+        );
+        Ok(())
+    }
+
+    /// Returns `token_id` to the caller, who must be the one who staked it.
+    pub fn unstake(&mut self, token_id: U256) -> Result<(), NftStakingError> {
+        let user = self.vm().msg_sender();
+        if self.staker_of.get(token_id) != user {
+            return Err(NftStakingError::NotStaker(NotStaker {
+                tokenId: token_id,
+                caller: user,
+            }));
+        }
+        self.settle(user);
+        self.remove_stake(user, token_id);
+
+        let escrow = self.vm().contract_address();
+        self.transfer_nft(escrow, user, token_id)?;
+        log(
+            self.vm(),
+            Unstaked {
+                user,
+                tokenId: token_id,
+            },
+        );
+        Ok(())
+    }
+
+    /// Spends `amount` of the caller's points.
+    pub fn redeem(&mut self, amount: U256) -> Result<(), NftStakingError> {
+        let user = self.vm().msg_sender();
+        self.settle(user);
+        let available = self.points.get(user);
+        if amount > available {
+            return Err(NftStakingError::InsufficientPoints(InsufficientPoints {
+                available,
+                requested: amount,
+            }));
+        }
+        self.points.setter(user).set(available - amount);
+        log(self.vm(), Redeemed { user, amount });
+        Ok(())
+    }
+
+    /// The user's points, including those earned since their last update.
+    pub fn points_of(&self, user: Address) -> U256 {
+        self.points.get(user) + self.pending_points(user)
+    }
+
+    pub fn staked_tokens(&self, user: Address) -> Vec<U256> {
+        let tokens = self.staked_tokens.getter(user);
+        (0..tokens.len())
+            .filter_map(|index| tokens.get(index))
+            .collect()
+    }
+
+    pub fn staker_of(&self, token_id: U256) -> Address {
+        self.staker_of.get(token_id)
+    }
+
+    pub fn total_staked(&self) -> U256 {
+        self.total_staked.get()
+    }
+
+    pub fn points_per_block(&self) -> U256 {
+        self.points_per_block.get()
+    }
+
+    pub fn nft(&self) -> Address {
+        self.nft.get()
+    }
+}
+
+impl NftStaking {
+    /// Points earned since `last_update_block` by the tokens staked throughout.
+    fn pending_points(&self, user: Address) -> U256 {
+        let staked = U256::from(self.staked_tokens.getter(user).len());
+        let blocks = U256::from(self.vm().block_number()) - self.last_update_block.get(user);
+        staked * blocks * self.points_per_block.get()
+    }
+
+    fn settle(&mut self, user: Address) {
+        let points = self.points_of(user);
+        self.points.setter(user).set(points);
+        let block = U256::from(self.vm().block_number());
+        self.last_update_block.setter(user).set(block);
+    }
+
+    /// Clears the stake record, moving the user's last token into the freed slot.
+    fn remove_stake(&mut self, user: Address, token_id: U256) {
+        let index = self.token_index.get(token_id);
+        let mut tokens = self.staked_tokens.setter(user);
+        let last = tokens.len() - 1;
+        let moved = tokens.get(last).expect("staked list is non-empty");
+        if let Some(mut slot) = tokens.setter(index.to::<usize>()) {
+            slot.set(moved);
+        }
+        tokens.pop();
+        self.token_index.setter(moved).set(index);
+        self.token_index.delete(token_id);
+        self.staker_of.delete(token_id);
+        self.total_staked
+            .set(self.total_staked.get() - U256::from(1));
+    }
+
+    fn transfer_nft(
+        &mut self,
+        from: Address,
+        to: Address,
+        token_id: U256,
+    ) -> Result<(), NftStakingError> {
+        let call = IERC721::transferFromCall {
+            from,
+            to,
+            tokenId: token_id,
+        };
+        let nft = self.nft.get();
+        self.vm()
+            .call(&calls::context::Call::new(), nft, &call.abi_encode())
+            .map(|_| ())
+            .map_err(|_| NftStakingError::TransferFailed(TransferFailed { tokenId: token_id }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const NFT: Address = Address::new([0x11; 20]);
+    const ALICE: Address = Address::new([0x01; 20]);
+    const BOB: Address = Address::new([0x02; 20]);
+    const RATE: u64 = 10;
+
+    fn setup() -> (TestVM, NftStaking) {
+        let vm = TestVM::default();
+        vm.set_block_number(100);
+        let mut contract = NftStaking::from(&vm);
+        contract.constructor(NFT, U256::from(RATE)).unwrap();
+        vm.set_sender(ALICE);
+        (vm, contract)
+    }
+
+    fn transfer_call(from: Address, to: Address, token_id: u64) -> Vec<u8> {
+        IERC721::transferFromCall {
+            from,
+            to,
+            tokenId: U256::from(token_id),
+        }
+        .abi_encode()
+    }
+
+    #[test]
+    fn test_points_accrue_per_staked_token() {
+        let (vm, mut contract) = setup();
+        contract.stake(U256::from(1)).unwrap();
+        vm.set_block_number(105);
+        assert_eq!(contract.points_of(ALICE), U256::from(5 * RATE));
+
+        contract.stake(U256::from(2)).unwrap();
+        vm.set_block_number(110);
+        assert_eq!(contract.points_of(ALICE), U256::from(15 * RATE));
+        assert_eq!(
+            contract.staked_tokens(ALICE),
+            vec![U256::from(1), U256::from(2)]
+        );
+        assert_eq!(contract.staker_of(U256::from(2)), ALICE);
+        assert_eq!(contract.total_staked(), U256::from(2));
+    }
+
+    #[test]
+    fn test_unstake_returns_token_and_stops_accrual() {
+        let (vm, mut contract) = setup();
+        for token_id in 1..=3 {
+            contract.stake(U256::from(token_id)).unwrap();
+        }
+        vm.set_block_number(102);
+        contract.unstake(U256::from(1)).unwrap();
+        assert_eq!(
+            contract.staked_tokens(ALICE),
+            vec![U256::from(3), U256::from(2)]
+        );
+        assert_eq!(contract.staker_of(U256::from(1)), Address::ZERO);
+
+        vm.set_block_number(104);
+        // 3 tokens for 2 blocks, then 2 tokens for 2 blocks.
+        assert_eq!(contract.points_of(ALICE), U256::from(10 * RATE));
+        contract.unstake(U256::from(2)).unwrap();
+        contract.unstake(U256::from(3)).unwrap();
+        vm.set_block_number(200);
+        assert_eq!(contract.points_of(ALICE), U256::from(10 * RATE));
+        assert_eq!(contract.total_staked(), U256::ZERO);
+    }
+
+    #[test]
+    fn test_only_staker_can_unstake() {
+        let (vm, mut contract) = setup();
+        contract.stake(U256::from(7)).unwrap();
+        assert!(matches!(
+            contract.stake(U256::from(7)),
+            Err(NftStakingError::AlreadyStaked(_))
+        ));
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.unstake(U256::from(7)),
+            Err(NftStakingError::NotStaker(_))
+        ));
+        assert!(contract.unstake(U256::from(8)).is_err());
+    }
+
+    #[test]
+    fn test_redeem_spends_points() {
+        let (vm, mut contract) = setup();
+        contract.stake(U256::from(1)).unwrap();
+        vm.set_block_number(110);
+        contract.redeem(U256::from(60)).unwrap();
+        assert_eq!(contract.points_of(ALICE), U256::from(40));
+        assert!(matches!(
+            contract.redeem(U256::from(41)),
+            Err(NftStakingError::InsufficientPoints(InsufficientPoints { available, .. }))
+                if available == U256::from(40)
+        ));
+        vm.set_block_number(111);
+        contract.redeem(U256::from(50)).unwrap();
+        assert_eq!(contract.points_of(ALICE), U256::ZERO);
+    }
+
+    #[test]
+    fn test_failed_transfers_revert() {
+        let (vm, mut contract) = setup();
+        let escrow = vm.contract_address();
+        vm.mock_call(
+            NFT,
+            transfer_call(ALICE, escrow, 1),
+            Err(b"not approved".to_vec()),
+        );
+        assert!(matches!(
+            contract.stake(U256::from(1)),
+            Err(NftStakingError::TransferFailed(_))
+        ));
+        assert_eq!(contract.staker_of(U256::from(1)), Address::ZERO);
+
+        contract.stake(U256::from(2)).unwrap();
+        contract.stake(U256::from(3)).unwrap();
+        vm.mock_call(NFT, transfer_call(escrow, ALICE, 2), Err(vec![]));
+        assert!(matches!(
+            contract.unstake(U256::from(2)),
+            Err(NftStakingError::TransferFailed(_))
+        ));
+    }
+}
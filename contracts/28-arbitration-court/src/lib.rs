@@ -0,0 +1,855 @@
+//!
+//! Stylus Arbitration Court
+//!
+//! Jurors stake ETH to join the court's pool. A claimant opens a dispute
+//! against a respondent by paying the arbitration fee, and the court draws
+//! `JURY_SIZE` jurors from the pool with enough stake who are not parties to
+//! the dispute. Drawn jurors cannot withdraw any stake until the dispute is
+//! resolved.
+//!
+//! Each dispute then runs in two timed phases, as in `commit-reveal-voting`.
+//! During the commit window drawn jurors submit
+//! `keccak256(abi.encodePacked(disputeId, ruling, salt, juror))`. During the
+//! reveal window they open it. Once the reveal window closes anyone can call
+//! `resolve()`:
+//!
+//! - the ruling with the most revealed votes wins; a tie or no votes at all
+//!   gives `RULING_NONE`;
+//! - jurors who did not reveal, or who voted against the ruling, lose
+//!   `SLASH_BPS` of their stake;
+//! - the fee and the slashed stake are shared equally by the jurors who voted
+//!   with the ruling (all revealers when there is no ruling) and added to
+//!   their stake. If nobody revealed, they are refunded to the claimant.
+//!
+//! Jurors are drawn with a hash of block data, which a block producer can
+//! influence. Use a VRF for real stakes.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, B256, U256, U8},
+    prelude::*,
+};
+
+/// Jurors drawn per dispute; odd so that a full turnout cannot tie.
+pub const JURY_SIZE: usize = 3;
+/// Bounds the pool so drawing a jury stays within gas limits.
+const MAX_POOL_SIZE: usize = 64;
+/// Share of stake an incoherent juror loses, in basis points.
+const SLASH_BPS: u64 = 2_000;
+const BPS: u64 = 10_000;
+
+pub const RULING_NONE: u8 = 0;
+pub const RULING_CLAIMANT: u8 = 1;
+pub const RULING_RESPONDENT: u8 = 2;
+
+pub const PHASE_COMMIT: u8 = 1;
+pub const PHASE_REVEAL: u8 = 2;
+pub const PHASE_RESOLVABLE: u8 = 3;
+pub const PHASE_RESOLVED: u8 = 4;
+
+sol! {
+    event Staked(address indexed juror, uint256 amount);
+    event Withdrawn(address indexed juror, uint256 amount);
+    event DisputeCreated(uint256 indexed disputeId, address indexed claimant, address indexed respondent);
+    event JurorDrawn(uint256 indexed disputeId, address indexed juror);
+    event VoteCommitted(uint256 indexed disputeId, address indexed juror);
+    event VoteRevealed(uint256 indexed disputeId, address indexed juror, uint8 ruling);
+    event JurorSlashed(uint256 indexed disputeId, address indexed juror, uint256 amount);
+    event DisputeResolved(uint256 indexed disputeId, uint8 ruling, uint256 rewardPerJuror);
+
+    #[derive(Debug)]
+    error InvalidConfig();
+    #[derive(Debug)]
+    error ZeroAmount();
+    #[derive(Debug)]
+    error PoolFull();
+    #[derive(Debug)]
+    error InsufficientStake(uint256 available, uint256 requested);
+    #[derive(Debug)]
+    error StakeLocked(uint256 activeDisputes);
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+    #[derive(Debug)]
+    error InvalidParties();
+    #[derive(Debug)]
+    error WrongFee(uint256 expected, uint256 received);
+    #[derive(Debug)]
+    error NotEnoughJurors(uint256 eligible);
+    #[derive(Debug)]
+    error UnknownDispute(uint256 disputeId);
+    #[derive(Debug)]
+    error WrongPhase(uint8 expected, uint8 current);
+    #[derive(Debug)]
+    error NotJuror(uint256 disputeId, address account);
+    #[derive(Debug)]
+    error EmptyCommitment();
+    #[derive(Debug)]
+    error NoCommitment(address juror);
+    #[derive(Debug)]
+    error AlreadyRevealed(address juror);
+    #[derive(Debug)]
+    error CommitmentMismatch();
+    #[derive(Debug)]
+    error InvalidRuling(uint8 ruling);
+    #[derive(Debug)]
+    error NothingToRefund();
+}
+
+#[derive(SolidityError, Debug)]
+pub enum CourtError {
+    InvalidConfig(InvalidConfig),
+    ZeroAmount(ZeroAmount),
+    PoolFull(PoolFull),
+    InsufficientStake(InsufficientStake),
+    StakeLocked(StakeLocked),
+    TransferFailed(TransferFailed),
+    InvalidParties(InvalidParties),
+    WrongFee(WrongFee),
+    NotEnoughJurors(NotEnoughJurors),
+    UnknownDispute(UnknownDispute),
+    WrongPhase(WrongPhase),
+    NotJuror(NotJuror),
+    EmptyCommitment(EmptyCommitment),
+    NoCommitment(NoCommitment),
+    AlreadyRevealed(AlreadyRevealed),
+    CommitmentMismatch(CommitmentMismatch),
+    InvalidRuling(InvalidRuling),
+    NothingToRefund(NothingToRefund),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct ArbitrationCourt {
+        /// Stake a juror needs to be drawn.
+        uint256 min_stake;
+        uint256 arbitration_fee;
+        uint256 commit_duration;
+        uint256 reveal_duration;
+        mapping(address => uint256) stakes;
+        /// Unresolved disputes each juror was drawn for.
+        mapping(address => uint256) active_disputes;
+        address[] pool;
+        /// Each juror's position in `pool`, plus one; zero if not in the pool.
+        mapping(address => uint256) pool_position;
+        mapping(uint256 => Dispute) disputes;
+        uint256 dispute_count;
+        /// Fees owed back to claimants whose disputes nobody voted on.
+        mapping(address => uint256) refunds;
+    }
+
+    pub struct Dispute {
+        address claimant;
+        address respondent;
+        uint256 fee;
+        /// Commits are accepted before `commit_end`, reveals from then until `reveal_end`.
+        uint256 commit_end;
+        uint256 reveal_end;
+        address[] jurors;
+        mapping(address => bool) drawn;
+        mapping(address => bytes32) commitments;
+        /// Each juror's revealed ruling; `RULING_NONE` until revealed.
+        mapping(address => uint8) votes;
+        uint256 claimant_votes;
+        uint256 respondent_votes;
+        uint8 ruling;
+        bool resolved;
+    }
+}
+
+#[public]
+impl ArbitrationCourt {
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        min_stake: U256,
+        arbitration_fee: U256,
+        commit_duration: u64,
+        reveal_duration: u64,
+    ) -> Result<(), CourtError> {
+        if min_stake.is_zero() || commit_duration == 0 || reveal_duration == 0 {
+            return Err(CourtError::InvalidConfig(InvalidConfig {}));
+        }
+        self.min_stake.set(min_stake);
+        self.arbitration_fee.set(arbitration_fee);
+        self.commit_duration.set(U256::from(commit_duration));
+        self.reveal_duration.set(U256::from(reveal_duration));
+        Ok(())
+    }
+
+    /// Adds `msg.value` to the caller's stake, joining the pool if needed.
+    #[payable]
+    pub fn stake(&mut self) -> Result<(), CourtError> {
+        let amount = self.vm().msg_value();
+        if amount.is_zero() {
+            return Err(CourtError::ZeroAmount(ZeroAmount {}));
+        }
+        let juror = self.vm().msg_sender();
+        if self.pool_position.get(juror).is_zero() {
+            if self.pool.len() >= MAX_POOL_SIZE {
+                return Err(CourtError::PoolFull(PoolFull {}));
+            }
+            self.pool.push(juror);
+            self.pool_position
+                .setter(juror)
+                .set(U256::from(self.pool.len()));
+        }
+        let staked = self.stakes.get(juror) + amount;
+        self.stakes.setter(juror).set(staked);
+        log(self.vm(), Staked { juror, amount });
+        Ok(())
+    }
+
+    /// Returns `amount` of the caller's stake; not allowed while drawn for an unresolved dispute.
+    pub fn withdraw(&mut self, amount: U256) -> Result<(), CourtError> {
+        if amount.is_zero() {
+            return Err(CourtError::ZeroAmount(ZeroAmount {}));
+        }
+        let juror = self.vm().msg_sender();
+        let active = self.active_disputes.get(juror);
+        if !active.is_zero() {
+            return Err(CourtError::StakeLocked(StakeLocked {
+                activeDisputes: active,
+            }));
+        }
+        let available = self.stakes.get(juror);
+        if amount > available {
+            return Err(CourtError::InsufficientStake(InsufficientStake {
+                available,
+                requested: amount,
+            }));
+        }
+        self.stakes.setter(juror).set(available - amount);
+        if self.vm().transfer_eth(juror, amount).is_err() {
+            return Err(CourtError::TransferFailed(TransferFailed {
+                to: juror,
+                amount,
+            }));
+        }
+        if available == amount {
+            self.leave_pool(juror);
+        }
+        log(self.vm(), Withdrawn { juror, amount });
+        Ok(())
+    }
+
+    /// Opens a dispute against `respondent` and draws its jury; `msg.value` must equal the fee.
+    #[payable]
+    pub fn create_dispute(&mut self, respondent: Address) -> Result<U256, CourtError> {
+        let claimant = self.vm().msg_sender();
+        if respondent.is_zero() || respondent == claimant {
+            return Err(CourtError::InvalidParties(InvalidParties {}));
+        }
+        let fee = self.arbitration_fee.get();
+        let received = self.vm().msg_value();
+        if received != fee {
+            return Err(CourtError::WrongFee(WrongFee {
+                expected: fee,
+                received,
+            }));
+        }
+
+        let dispute_id = self.dispute_count.get();
+        let jurors = self.draw_jurors(dispute_id, claimant, respondent)?;
+        let commit_end = U256::from(self.vm().block_timestamp()) + self.commit_duration.get();
+        let reveal_end = commit_end + self.reveal_duration.get();
+
+        let mut dispute = self.disputes.setter(dispute_id);
+        dispute.claimant.set(claimant);
+        dispute.respondent.set(respondent);
+        dispute.fee.set(fee);
+        dispute.commit_end.set(commit_end);
+        dispute.reveal_end.set(reveal_end);
+        for &juror in &jurors {
+            dispute.jurors.push(juror);
+            dispute.drawn.setter(juror).set(true);
+        }
+        for &juror in &jurors {
+            let active = self.active_disputes.get(juror) + U256::from(1);
+            self.active_disputes.setter(juror).set(active);
+        }
+        self.dispute_count.set(dispute_id + U256::from(1));
+
+        log(
+            self.vm(),
+            DisputeCreated {
+                disputeId: dispute_id,
+                claimant,
+                respondent,
+            },
+        );
+        for juror in jurors {
+            log(
+                self.vm(),
+                JurorDrawn {
+                    disputeId: dispute_id,
+                    juror,
+                },
+            );
+        }
+        Ok(dispute_id)
+    }
+
+    /// Records or replaces the caller's sealed vote on a dispute they were drawn for.
+    pub fn commit_vote(&mut self, dispute_id: U256, commitment: B256) -> Result<(), CourtError> {
+        self.require_phase(dispute_id, PHASE_COMMIT)?;
+        if commitment.is_zero() {
+            return Err(CourtError::EmptyCommitment(EmptyCommitment {}));
+        }
+        let juror = self.require_juror(dispute_id)?;
+        self.disputes
+            .setter(dispute_id)
+            .commitments
+            .setter(juror)
+            .set(commitment);
+        log(
+            self.vm(),
+            VoteCommitted {
+                disputeId: dispute_id,
+                juror,
+            },
+        );
+        Ok(())
+    }
+
+    /// Opens the caller's commitment and counts the vote.
+    pub fn reveal_vote(
+        &mut self,
+        dispute_id: U256,
+        ruling: u8,
+        salt: B256,
+    ) -> Result<(), CourtError> {
+        self.require_phase(dispute_id, PHASE_REVEAL)?;
+        let juror = self.require_juror(dispute_id)?;
+        let dispute = self.disputes.getter(dispute_id);
+        let commitment = dispute.commitments.get(juror);
+        if commitment.is_zero() {
+            return Err(CourtError::NoCommitment(NoCommitment { juror }));
+        }
+        if dispute.votes.get(juror) != U8::from(RULING_NONE) {
+            return Err(CourtError::AlreadyRevealed(AlreadyRevealed { juror }));
+        }
+        if self.commitment_for(dispute_id, ruling, salt, juror) != commitment {
+            return Err(CourtError::CommitmentMismatch(CommitmentMismatch {}));
+        }
+        // A ruling that was out of range when committed can never be counted.
+        if ruling != RULING_CLAIMANT && ruling != RULING_RESPONDENT {
+            return Err(CourtError::InvalidRuling(InvalidRuling { ruling }));
+        }
+
+        let mut dispute = self.disputes.setter(dispute_id);
+        dispute.votes.setter(juror).set(U8::from(ruling));
+        if ruling == RULING_CLAIMANT {
+            let votes = dispute.claimant_votes.get() + U256::from(1);
+            dispute.claimant_votes.set(votes);
+        } else {
+            let votes = dispute.respondent_votes.get() + U256::from(1);
+            dispute.respondent_votes.set(votes);
+        }
+        log(
+            self.vm(),
+            VoteRevealed {
+                disputeId: dispute_id,
+                juror,
+                ruling,
+            },
+        );
+        Ok(())
+    }
+
+    /// Settles a dispute after its reveal window: slashes incoherent jurors and rewards the rest.
+    ///
+    /// Anyone may call this. Returns the ruling.
+    pub fn resolve(&mut self, dispute_id: U256) -> Result<u8, CourtError> {
+        self.require_phase(dispute_id, PHASE_RESOLVABLE)?;
+        let dispute = self.disputes.getter(dispute_id);
+        let claimant_votes = dispute.claimant_votes.get();
+        let respondent_votes = dispute.respondent_votes.get();
+        let ruling = if claimant_votes > respondent_votes {
+            RULING_CLAIMANT
+        } else if respondent_votes > claimant_votes {
+            RULING_RESPONDENT
+        } else {
+            RULING_NONE
+        };
+        let claimant = dispute.claimant.get();
+        let mut reward_pool = dispute.fee.get();
+        let jurors: Vec<(Address, u8)> = (0..dispute.jurors.len())
+            .filter_map(|index| dispute.jurors.get(index))
+            .map(|juror| (juror, dispute.votes.get(juror).to::<u8>()))
+            .collect();
+
+        let mut dispute = self.disputes.setter(dispute_id);
+        dispute.ruling.set(U8::from(ruling));
+        dispute.resolved.set(true);
+
+        let mut coherent = Vec::with_capacity(jurors.len());
+        for (juror, vote) in jurors {
+            let active = self.active_disputes.get(juror) - U256::from(1);
+            self.active_disputes.setter(juror).set(active);
+            let voted_with_ruling = if ruling == RULING_NONE {
+                vote != RULING_NONE
+            } else {
+                vote == ruling
+            };
+            if voted_with_ruling {
+                coherent.push(juror);
+                continue;
+            }
+            let staked = self.stakes.get(juror);
+            let slashed = staked * U256::from(SLASH_BPS) / U256::from(BPS);
+            self.stakes.setter(juror).set(staked - slashed);
+            reward_pool += slashed;
+            log(
+                self.vm(),
+                JurorSlashed {
+                    disputeId: dispute_id,
+                    juror,
+                    amount: slashed,
+                },
+            );
+        }
+
+        let reward = if coherent.is_empty() {
+            let refund = self.refunds.get(claimant) + reward_pool;
+            self.refunds.setter(claimant).set(refund);
+            U256::ZERO
+        } else {
+            let reward = reward_pool / U256::from(coherent.len());
+            let remainder = reward_pool - reward * U256::from(coherent.len());
+            for (index, &juror) in coherent.iter().enumerate() {
+                // The first coherent juror also takes any rounding remainder.
+                let extra = if index == 0 { remainder } else { U256::ZERO };
+                let staked = self.stakes.get(juror) + reward + extra;
+                self.stakes.setter(juror).set(staked);
+            }
+            reward
+        };
+        log(
+            self.vm(),
+            DisputeResolved {
+                disputeId: dispute_id,
+                ruling,
+                rewardPerJuror: reward,
+            },
+        );
+        Ok(ruling)
+    }
+
+    /// Sends the caller fees refunded from disputes nobody voted on.
+    pub fn claim_refund(&mut self) -> Result<U256, CourtError> {
+        let claimant = self.vm().msg_sender();
+        let amount = self.refunds.get(claimant);
+        if amount.is_zero() {
+            return Err(CourtError::NothingToRefund(NothingToRefund {}));
+        }
+        self.refunds.setter(claimant).set(U256::ZERO);
+        if self.vm().transfer_eth(claimant, amount).is_err() {
+            return Err(CourtError::TransferFailed(TransferFailed {
+                to: claimant,
+                amount,
+            }));
+        }
+        Ok(amount)
+    }
+
+    /// The commitment `juror` should submit for `ruling` and `salt` on a dispute.
+    pub fn commitment_for(&self, dispute_id: U256, ruling: u8, salt: B256, juror: Address) -> B256 {
+        let mut preimage = Vec::with_capacity(85);
+        preimage.extend_from_slice(&dispute_id.to_be_bytes::<32>());
+        preimage.push(ruling);
+        preimage.extend_from_slice(salt.as_slice());
+        preimage.extend_from_slice(juror.as_slice());
+        self.vm().native_keccak256(&preimage)
+    }
+
+    /// One of the `PHASE_*` constants, derived from the block timestamp.
+    pub fn phase(&self, dispute_id: U256) -> Result<u8, CourtError> {
+        if dispute_id >= self.dispute_count.get() {
+            return Err(CourtError::UnknownDispute(UnknownDispute {
+                disputeId: dispute_id,
+            }));
+        }
+        let dispute = self.disputes.getter(dispute_id);
+        let now = U256::from(self.vm().block_timestamp());
+        Ok(if dispute.resolved.get() {
+            PHASE_RESOLVED
+        } else if now < dispute.commit_end.get() {
+            PHASE_COMMIT
+        } else if now < dispute.reveal_end.get() {
+            PHASE_REVEAL
+        } else {
+            PHASE_RESOLVABLE
+        })
+    }
+
+    /// Returns `(claimant, respondent, commit_end, reveal_end, ruling, resolved)`.
+    pub fn dispute(&self, dispute_id: U256) -> (Address, Address, U256, U256, u8, bool) {
+        let dispute = self.disputes.getter(dispute_id);
+        (
+            dispute.claimant.get(),
+            dispute.respondent.get(),
+            dispute.commit_end.get(),
+            dispute.reveal_end.get(),
+            dispute.ruling.get().to::<u8>(),
+            dispute.resolved.get(),
+        )
+    }
+
+    pub fn jurors(&self, dispute_id: U256) -> Vec<Address> {
+        let jurors = &self.disputes.getter(dispute_id).jurors;
+        (0..jurors.len())
+            .filter_map(|index| jurors.get(index))
+            .collect()
+    }
+
+    /// Returns `(claimant_votes, respondent_votes)` revealed so far.
+    pub fn tally(&self, dispute_id: U256) -> (U256, U256) {
+        let dispute = self.disputes.getter(dispute_id);
+        (dispute.claimant_votes.get(), dispute.respondent_votes.get())
+    }
+
+    pub fn stake_of(&self, juror: Address) -> U256 {
+        self.stakes.get(juror)
+    }
+
+    pub fn active_disputes(&self, juror: Address) -> U256 {
+        self.active_disputes.get(juror)
+    }
+
+    pub fn pool(&self) -> Vec<Address> {
+        (0..self.pool.len())
+            .filter_map(|index| self.pool.get(index))
+            .collect()
+    }
+
+    pub fn refund_of(&self, claimant: Address) -> U256 {
+        self.refunds.get(claimant)
+    }
+
+    pub fn dispute_count(&self) -> U256 {
+        self.dispute_count.get()
+    }
+
+    pub fn min_stake(&self) -> U256 {
+        self.min_stake.get()
+    }
+
+    pub fn arbitration_fee(&self) -> U256 {
+        self.arbitration_fee.get()
+    }
+}
+
+impl ArbitrationCourt {
+    fn require_phase(&self, dispute_id: U256, expected: u8) -> Result<(), CourtError> {
+        let current = self.phase(dispute_id)?;
+        if current != expected {
+            return Err(CourtError::WrongPhase(WrongPhase { expected, current }));
+        }
+        Ok(())
+    }
+
+    fn require_juror(&self, dispute_id: U256) -> Result<Address, CourtError> {
+        let account = self.vm().msg_sender();
+        if !self.disputes.getter(dispute_id).drawn.get(account) {
+            return Err(CourtError::NotJuror(NotJuror {
+                disputeId: dispute_id,
+                account,
+            }));
+        }
+        Ok(account)
+    }
+
+    /// Picks `JURY_SIZE` distinct eligible jurors with a partial Fisher-Yates shuffle.
+    fn draw_jurors(
+        &self,
+        dispute_id: U256,
+        claimant: Address,
+        respondent: Address,
+    ) -> Result<Vec<Address>, CourtError> {
+        let min_stake = self.min_stake.get();
+        let mut candidates: Vec<Address> = (0..self.pool.len())
+            .filter_map(|index| self.pool.get(index))
+            .filter(|&juror| {
+                juror != claimant && juror != respondent && self.stakes.get(juror) >= min_stake
+            })
+            .collect();
+        if candidates.len() < JURY_SIZE {
+            return Err(CourtError::NotEnoughJurors(NotEnoughJurors {
+                eligible: U256::from(candidates.len()),
+            }));
+        }
+        for slot in 0..JURY_SIZE {
+            let seed = self.random_seed(dispute_id, slot);
+            let remaining = U256::from(candidates.len() - slot);
+            let pick = slot + (U256::from_be_bytes(seed.0) % remaining).to::<usize>();
+            candidates.swap(slot, pick);
+        }
+        candidates.truncate(JURY_SIZE);
+        Ok(candidates)
+    }
+
+    fn random_seed(&self, dispute_id: U256, slot: usize) -> B256 {
+        let mut preimage = Vec::with_capacity(128);
+        preimage.extend_from_slice(&dispute_id.to_be_bytes::<32>());
+        preimage.extend_from_slice(&U256::from(slot).to_be_bytes::<32>());
+        preimage.extend_from_slice(&U256::from(self.vm().block_number()).to_be_bytes::<32>());
+        preimage.extend_from_slice(&U256::from(self.vm().block_timestamp()).to_be_bytes::<32>());
+        self.vm().native_keccak256(&preimage)
+    }
+
+    /// Removes `juror` from the pool, moving the last member into the freed slot.
+    fn leave_pool(&mut self, juror: Address) {
+        let position = self.pool_position.get(juror);
+        if position.is_zero() {
+            return;
+        }
+        let index = position.to::<usize>() - 1;
+        let last = self.pool.len() - 1;
+        let moved = self.pool.get(last).expect("pool is non-empty");
+        if let Some(mut slot) = self.pool.setter(index) {
+            slot.set(moved);
+        }
+        self.pool.pop();
+        self.pool_position.setter(moved).set(position);
+        self.pool_position.delete(juror);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const CLAIMANT: Address = Address::new([0xc1; 20]);
+    const RESPONDENT: Address = Address::new([0xd2; 20]);
+    const JURORS: [Address; 4] = [
+        Address::new([0x01; 20]),
+        Address::new([0x02; 20]),
+        Address::new([0x03; 20]),
+        Address::new([0x04; 20]),
+    ];
+    const STAKE: u64 = 10_000;
+    const FEE: u64 = 300;
+    const START: u64 = 1_000;
+    const COMMIT: u64 = 100;
+    const REVEAL: u64 = 100;
+
+    fn send(vm: &TestVM, from: Address, amount: u64) {
+        vm.set_sender(from);
+        vm.set_value(U256::from(amount));
+        let balance = vm.balance(vm.contract_address());
+        vm.set_balance(vm.contract_address(), balance + U256::from(amount));
+    }
+
+    fn setup() -> (TestVM, ArbitrationCourt) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(START);
+        let mut contract = ArbitrationCourt::from(&vm);
+        contract
+            .constructor(U256::from(STAKE), U256::from(FEE), COMMIT, REVEAL)
+            .unwrap();
+        for juror in JURORS {
+            send(&vm, juror, STAKE);
+            contract.stake().unwrap();
+        }
+        vm.set_value(U256::ZERO);
+        (vm, contract)
+    }
+
+    fn open_dispute(vm: &TestVM, contract: &mut ArbitrationCourt) -> (U256, Vec<Address>) {
+        send(vm, CLAIMANT, FEE);
+        let dispute_id = contract.create_dispute(RESPONDENT).unwrap();
+        vm.set_value(U256::ZERO);
+        (dispute_id, contract.jurors(dispute_id))
+    }
+
+    fn commit(vm: &TestVM, contract: &mut ArbitrationCourt, id: U256, juror: Address, ruling: u8) {
+        vm.set_sender(juror);
+        let salt = B256::repeat_byte(juror[0]);
+        let commitment = contract.commitment_for(id, ruling, salt, juror);
+        contract.commit_vote(id, commitment).unwrap();
+    }
+
+    fn reveal(
+        vm: &TestVM,
+        contract: &mut ArbitrationCourt,
+        id: U256,
+        juror: Address,
+        ruling: u8,
+    ) -> Result<(), CourtError> {
+        vm.set_sender(juror);
+        contract.reveal_vote(id, ruling, B256::repeat_byte(juror[0]))
+    }
+
+    #[test]
+    fn test_draws_distinct_eligible_jurors() {
+        let (vm, mut contract) = setup();
+        // A party to the dispute is never drawn, even with enough stake.
+        send(&vm, CLAIMANT, STAKE);
+        contract.stake().unwrap();
+        let (dispute_id, jurors) = open_dispute(&vm, &mut contract);
+        assert_eq!(jurors.len(), JURY_SIZE);
+        for (index, juror) in jurors.iter().enumerate() {
+            assert!(JURORS.contains(juror));
+            assert!(!jurors[index + 1..].contains(juror));
+            assert_eq!(contract.active_disputes(*juror), U256::from(1));
+        }
+        assert_eq!(contract.phase(dispute_id).unwrap(), PHASE_COMMIT);
+
+        send(&vm, CLAIMANT, FEE - 1);
+        assert!(matches!(
+            contract.create_dispute(RESPONDENT),
+            Err(CourtError::WrongFee(_))
+        ));
+        send(&vm, CLAIMANT, FEE);
+        assert!(matches!(
+            contract.create_dispute(CLAIMANT),
+            Err(CourtError::InvalidParties(_))
+        ));
+    }
+
+    #[test]
+    fn test_not_enough_jurors() {
+        let (vm, mut contract) = setup();
+        // Stake below the minimum does not count.
+        vm.set_sender(JURORS[0]);
+        contract.withdraw(U256::from(1)).unwrap();
+        vm.set_sender(JURORS[1]);
+        contract.withdraw(U256::from(STAKE)).unwrap();
+        assert_eq!(contract.pool().len(), 3);
+
+        send(&vm, CLAIMANT, FEE);
+        assert!(matches!(
+            contract.create_dispute(RESPONDENT),
+            Err(CourtError::NotEnoughJurors(NotEnoughJurors { eligible }))
+                if eligible == U256::from(2)
+        ));
+    }
+
+    #[test]
+    fn test_majority_rewarded_minority_slashed() {
+        let (vm, mut contract) = setup();
+        let (id, jurors) = open_dispute(&vm, &mut contract);
+        commit(&vm, &mut contract, id, jurors[0], RULING_CLAIMANT);
+        commit(&vm, &mut contract, id, jurors[1], RULING_CLAIMANT);
+        commit(&vm, &mut contract, id, jurors[2], RULING_RESPONDENT);
+
+        vm.set_sender(jurors[0]);
+        assert!(matches!(
+            contract.withdraw(U256::from(1)),
+            Err(CourtError::StakeLocked(_))
+        ));
+
+        vm.set_block_timestamp(START + COMMIT);
+        for (index, ruling) in [RULING_CLAIMANT, RULING_CLAIMANT, RULING_RESPONDENT]
+            .into_iter()
+            .enumerate()
+        {
+            reveal(&vm, &mut contract, id, jurors[index], ruling).unwrap();
+        }
+        assert_eq!(contract.tally(id), (U256::from(2), U256::from(1)));
+        assert!(matches!(
+            contract.resolve(id),
+            Err(CourtError::WrongPhase(_))
+        ));
+
+        vm.set_block_timestamp(START + COMMIT + REVEAL);
+        assert_eq!(contract.resolve(id).unwrap(), RULING_CLAIMANT);
+        assert_eq!(contract.phase(id).unwrap(), PHASE_RESOLVED);
+        // The slashed 2_000 plus the 300 fee is shared by the two coherent jurors.
+        assert_eq!(contract.stake_of(jurors[0]), U256::from(STAKE + 1_150));
+        assert_eq!(contract.stake_of(jurors[1]), U256::from(STAKE + 1_150));
+        assert_eq!(contract.stake_of(jurors[2]), U256::from(STAKE - 2_000));
+        assert!(contract.resolve(id).is_err());
+
+        vm.set_sender(jurors[2]);
+        contract.withdraw(U256::from(STAKE - 2_000)).unwrap();
+        assert_eq!(vm.balance(jurors[2]), U256::from(STAKE - 2_000));
+        assert!(!contract.pool().contains(&jurors[2]));
+    }
+
+    #[test]
+    fn test_reveal_checks() {
+        let (vm, mut contract) = setup();
+        let (id, jurors) = open_dispute(&vm, &mut contract);
+        let outsider = *JURORS.iter().find(|j| !jurors.contains(j)).unwrap();
+        vm.set_sender(outsider);
+        assert!(matches!(
+            contract.commit_vote(id, B256::repeat_byte(1)),
+            Err(CourtError::NotJuror(_))
+        ));
+        commit(&vm, &mut contract, id, jurors[0], RULING_RESPONDENT);
+        assert!(matches!(
+            reveal(&vm, &mut contract, id, jurors[0], RULING_RESPONDENT),
+            Err(CourtError::WrongPhase(_))
+        ));
+
+        vm.set_block_timestamp(START + COMMIT);
+        assert!(matches!(
+            reveal(&vm, &mut contract, id, jurors[0], RULING_CLAIMANT),
+            Err(CourtError::CommitmentMismatch(_))
+        ));
+        assert!(matches!(
+            reveal(&vm, &mut contract, id, jurors[1], RULING_CLAIMANT),
+            Err(CourtError::NoCommitment(_))
+        ));
+        reveal(&vm, &mut contract, id, jurors[0], RULING_RESPONDENT).unwrap();
+        assert!(matches!(
+            reveal(&vm, &mut contract, id, jurors[0], RULING_RESPONDENT),
+            Err(CourtError::AlreadyRevealed(_))
+        ));
+        assert!(matches!(
+            contract.phase(U256::from(7)),
+            Err(CourtError::UnknownDispute(_))
+        ));
+    }
+
+    #[test]
+    fn test_no_votes_refunds_claimant() {
+        let (vm, mut contract) = setup();
+        let (id, jurors) = open_dispute(&vm, &mut contract);
+        vm.set_block_timestamp(START + COMMIT + REVEAL);
+        assert_eq!(contract.resolve(id).unwrap(), RULING_NONE);
+        for juror in &jurors {
+            assert_eq!(contract.stake_of(*juror), U256::from(STAKE - 2_000));
+            assert_eq!(contract.active_disputes(*juror), U256::ZERO);
+        }
+
+        vm.set_sender(CLAIMANT);
+        assert_eq!(contract.claim_refund().unwrap(), U256::from(FEE + 6_000));
+        assert_eq!(vm.balance(CLAIMANT), U256::from(FEE + 6_000));
+        assert!(matches!(
+            contract.claim_refund(),
+            Err(CourtError::NothingToRefund(_))
+        ));
+    }
+
+    #[test]
+    fn test_failed_transfer_surfaces_error() {
+        let (vm, mut contract) = setup();
+        let (id, jurors) = open_dispute(&vm, &mut contract);
+        vm.set_block_timestamp(START + COMMIT + REVEAL);
+        contract.resolve(id).unwrap();
+        vm.set_balance(vm.contract_address(), U256::ZERO);
+
+        vm.set_sender(jurors[0]);
+        assert!(matches!(
+            contract.withdraw(U256::from(1)),
+            Err(CourtError::TransferFailed(TransferFailed { to, .. })) if to == jurors[0]
+        ));
+        vm.set_sender(CLAIMANT);
+        assert!(matches!(
+            contract.claim_refund(),
+            Err(CourtError::TransferFailed(TransferFailed { to, .. })) if to == CLAIMANT
+        ));
+    }
+}
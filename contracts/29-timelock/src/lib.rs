@@ -0,0 +1,455 @@
+//!
+//! Stylus Timelock Controller
+//!
+//! An admin queues calls (`target`, `value`, `data`, `eta`) that can only run
+//! once `eta` has passed. Each operation is identified by
+//! `keccak256(abi.encode(target, value, data, eta))`, so anyone can check
+//! which call an id stands for before it executes. The delay between queueing
+//! and `eta` must be at least `delay`, giving everyone time to react to a
+//! queued change. Once ready, anyone may execute the operation within
+//! `GRACE_PERIOD`; after that it is stale. The admin can cancel an operation
+//! at any point before it runs.
+//!
+//! `set_delay` and `set_admin` can only be called by the timelock itself, so
+//! changing them has to go through the queue like any other operation.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolValue};
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{self, Address, B256, U256, U8},
+    prelude::*,
+};
+
+/// Longest delay that can be configured.
+const MAX_DELAY: u64 = 30 * 24 * 60 * 60;
+/// How long after `eta` an operation can still be executed.
+pub const GRACE_PERIOD: u64 = 14 * 24 * 60 * 60;
+
+pub const STATE_UNSET: u8 = 0;
+pub const STATE_QUEUED: u8 = 1;
+pub const STATE_EXECUTED: u8 = 2;
+pub const STATE_CANCELLED: u8 = 3;
+
+sol! {
+    event Deposit(address indexed sender, uint256 amount);
+    event OperationQueued(bytes32 indexed id, address indexed target, uint256 value, bytes data, uint256 eta);
+    event OperationExecuted(bytes32 indexed id);
+    event OperationCancelled(bytes32 indexed id);
+    event DelayChanged(uint256 delay);
+    event AdminChanged(address indexed admin);
+
+    #[derive(Debug)]
+    error InvalidConfig();
+    #[derive(Debug)]
+    error NotAdmin(address caller);
+    #[derive(Debug)]
+    error OnlyTimelock(address caller);
+    #[derive(Debug)]
+    error EtaTooEarly(uint256 eta, uint256 earliest);
+    #[derive(Debug)]
+    error OperationExists(bytes32 id);
+    #[derive(Debug)]
+    error NotQueued(bytes32 id, uint8 state);
+    #[derive(Debug)]
+    error NotReady(bytes32 id, uint256 eta);
+    #[derive(Debug)]
+    error Expired(bytes32 id, uint256 expiredAt);
+    #[derive(Debug)]
+    error ExecutionFailed(bytes32 id, bytes returnData);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum TimelockError {
+    InvalidConfig(InvalidConfig),
+    NotAdmin(NotAdmin),
+    OnlyTimelock(OnlyTimelock),
+    EtaTooEarly(EtaTooEarly),
+    OperationExists(OperationExists),
+    NotQueued(NotQueued),
+    NotReady(NotReady),
+    Expired(Expired),
+    ExecutionFailed(ExecutionFailed),
+}
+
+sol_storage! {
+    pub struct Operation {
+        address target;
+        uint256 value;
+        bytes data;
+        uint256 eta;
+        /// One of the `STATE_*` constants.
+        uint8 state;
+    }
+
+    #[entrypoint]
+    pub struct Timelock {
+        address admin;
+        /// Minimum time between queueing an operation and its `eta`, in seconds.
+        uint256 delay;
+        mapping(bytes32 => Operation) operations;
+    }
+}
+
+#[public]
+impl Timelock {
+    #[constructor]
+    pub fn constructor(&mut self, admin: Address, delay: u64) -> Result<(), TimelockError> {
+        if admin.is_zero() || delay > MAX_DELAY {
+            return Err(TimelockError::InvalidConfig(InvalidConfig {}));
+        }
+        self.admin.set(admin);
+        self.delay.set(U256::from(delay));
+        Ok(())
+    }
+
+    /// Accepts plain ETH transfers, which queued operations can forward.
+    #[receive]
+    #[payable]
+    pub fn receive(&mut self) -> Result<(), Vec<u8>> {
+        let sender = self.vm().msg_sender();
+        let amount = self.vm().msg_value();
+        log(self.vm(), Deposit { sender, amount });
+        Ok(())
+    }
+
+    /// Queues a call to run no earlier than `eta`, returning its id.
+    pub fn queue(
+        &mut self,
+        target: Address,
+        value: U256,
+        data: Bytes,
+        eta: U256,
+    ) -> Result<B256, TimelockError> {
+        self.only_admin()?;
+        let earliest = U256::from(self.vm().block_timestamp()) + self.delay.get();
+        if eta < earliest {
+            return Err(TimelockError::EtaTooEarly(EtaTooEarly { eta, earliest }));
+        }
+        let id = self.hash_operation(target, value, data.clone(), eta);
+        if self.state(id) != STATE_UNSET {
+            return Err(TimelockError::OperationExists(OperationExists { id }));
+        }
+        let mut operation = self.operations.setter(id);
+        operation.target.set(target);
+        operation.value.set(value);
+        operation.data.set_bytes(&data);
+        operation.eta.set(eta);
+        operation.state.set(U8::from(STATE_QUEUED));
+        log(
+            self.vm(),
+            OperationQueued {
+                id,
+                target,
+                value,
+                data: data.0.into(),
+                eta,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Runs a queued operation whose `eta` has passed, returning the target's return data.
+    ///
+    /// Anyone may call this. If the call reverts, so does `execute`, and the operation stays queued.
+    pub fn execute(&mut self, id: B256) -> Result<Bytes, TimelockError> {
+        self.require_queued(id)?;
+        let now = U256::from(self.vm().block_timestamp());
+        let mut operation = self.operations.setter(id);
+        let eta = operation.eta.get();
+        if now < eta {
+            return Err(TimelockError::NotReady(NotReady { id, eta }));
+        }
+        let expired_at = eta + U256::from(GRACE_PERIOD);
+        if now >= expired_at {
+            return Err(TimelockError::Expired(Expired {
+                id,
+                expiredAt: expired_at,
+            }));
+        }
+        // Mark it executed first so the target cannot run it again by calling back.
+        operation.state.set(U8::from(STATE_EXECUTED));
+        let (target, value, data) = (
+            operation.target.get(),
+            operation.value.get(),
+            operation.data.get_bytes(),
+        );
+
+        let context = calls::context::Call::new().value(value);
+        match self.vm().call(&context, target, &data) {
+            Ok(returned) => {
+                log(self.vm(), OperationExecuted { id });
+                Ok(Bytes::from(returned))
+            }
+            Err(err) => {
+                let return_data = match err {
+                    calls::errors::Error::Revert(data) => data,
+                    _ => Vec::new(),
+                };
+                Err(TimelockError::ExecutionFailed(ExecutionFailed {
+                    id,
+                    returnData: return_data.into(),
+                }))
+            }
+        }
+    }
+
+    /// Cancels a queued operation.
+    pub fn cancel(&mut self, id: B256) -> Result<(), TimelockError> {
+        self.only_admin()?;
+        self.require_queued(id)?;
+        self.operations
+            .setter(id)
+            .state
+            .set(U8::from(STATE_CANCELLED));
+        log(self.vm(), OperationCancelled { id });
+        Ok(())
+    }
+
+    /// Changes the minimum delay; must be called through a queued operation.
+    pub fn set_delay(&mut self, delay: u64) -> Result<(), TimelockError> {
+        self.only_self()?;
+        if delay > MAX_DELAY {
+            return Err(TimelockError::InvalidConfig(InvalidConfig {}));
+        }
+        self.delay.set(U256::from(delay));
+        log(
+            self.vm(),
+            DelayChanged {
+                delay: U256::from(delay),
+            },
+        );
+        Ok(())
+    }
+
+    /// Hands the admin role to `admin`; must be called through a queued operation.
+    pub fn set_admin(&mut self, admin: Address) -> Result<(), TimelockError> {
+        self.only_self()?;
+        if admin.is_zero() {
+            return Err(TimelockError::InvalidConfig(InvalidConfig {}));
+        }
+        self.admin.set(admin);
+        log(self.vm(), AdminChanged { admin });
+        Ok(())
+    }
+
+    /// The id `queue` assigns: `keccak256(abi.encode(target, value, data, eta))`.
+    pub fn hash_operation(&self, target: Address, value: U256, data: Bytes, eta: U256) -> B256 {
+        let encoded = (target, value, alloy_primitives::Bytes::from(data.0), eta).abi_encode();
+        self.vm().native_keccak256(&encoded)
+    }
+
+    /// Returns `(target, value, data, eta, state)`.
+    pub fn operation(&self, id: B256) -> (Address, U256, Bytes, U256, u8) {
+        let operation = self.operations.getter(id);
+        (
+            operation.target.get(),
+            operation.value.get(),
+            Bytes::from(operation.data.get_bytes()),
+            operation.eta.get(),
+            operation.state.get().to(),
+        )
+    }
+
+    /// One of the `STATE_*` constants.
+    pub fn state(&self, id: B256) -> u8 {
+        self.operations.getter(id).state.get().to()
+    }
+
+    pub fn admin(&self) -> Address {
+        self.admin.get()
+    }
+
+    pub fn delay(&self) -> U256 {
+        self.delay.get()
+    }
+}
+
+impl Timelock {
+    fn only_admin(&self) -> Result<(), TimelockError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.admin.get() {
+            return Err(TimelockError::NotAdmin(NotAdmin { caller }));
+        }
+        Ok(())
+    }
+
+    fn only_self(&self) -> Result<(), TimelockError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.vm().contract_address() {
+            return Err(TimelockError::OnlyTimelock(OnlyTimelock { caller }));
+        }
+        Ok(())
+    }
+
+    fn require_queued(&self, id: B256) -> Result<(), TimelockError> {
+        let state = self.state(id);
+        if state != STATE_QUEUED {
+            return Err(TimelockError::NotQueued(NotQueued { id, state }));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const ADMIN: Address = Address::new([0xad; 20]);
+    const ALICE: Address = Address::new([0x01; 20]);
+    const TARGET: Address = Address::new([0x7a; 20]);
+    const START: u64 = 1_000;
+    const DELAY: u64 = 2 * 24 * 60 * 60;
+
+    fn setup() -> (TestVM, Timelock) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(START);
+        let mut contract = Timelock::from(&vm);
+        contract.constructor(ADMIN, DELAY).unwrap();
+        vm.set_sender(ADMIN);
+        (vm, contract)
+    }
+
+    fn queue(contract: &mut Timelock, eta: u64) -> B256 {
+        contract
+            .queue(
+                TARGET,
+                U256::from(5),
+                Bytes::from(vec![0xab, 0xcd]),
+                U256::from(eta),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_executes_after_eta() {
+        let (vm, mut contract) = setup();
+        let eta = START + DELAY;
+        let id = queue(&mut contract, eta);
+        assert_eq!(contract.state(id), STATE_QUEUED);
+        assert_eq!(
+            id,
+            contract.hash_operation(
+                TARGET,
+                U256::from(5),
+                Bytes::from(vec![0xab, 0xcd]),
+                U256::from(eta)
+            )
+        );
+
+        vm.set_sender(ALICE);
+        vm.set_block_timestamp(eta - 1);
+        assert!(matches!(
+            contract.execute(id),
+            Err(TimelockError::NotReady(_))
+        ));
+        vm.set_block_timestamp(eta);
+        vm.mock_call(TARGET, vec![0xab, 0xcd], Ok(vec![0x01]));
+        assert_eq!(contract.execute(id).unwrap().0, vec![0x01]);
+        assert_eq!(contract.state(id), STATE_EXECUTED);
+        assert!(matches!(
+            contract.execute(id),
+            Err(TimelockError::NotQueued(NotQueued { state, .. })) if state == STATE_EXECUTED
+        ));
+    }
+
+    #[test]
+    fn test_queue_checks() {
+        let (vm, mut contract) = setup();
+        assert!(matches!(
+            contract.queue(
+                TARGET,
+                U256::ZERO,
+                Bytes::from(vec![]),
+                U256::from(START + DELAY - 1)
+            ),
+            Err(TimelockError::EtaTooEarly(_))
+        ));
+        queue(&mut contract, START + DELAY);
+        assert!(matches!(
+            contract.queue(
+                TARGET,
+                U256::from(5),
+                Bytes::from(vec![0xab, 0xcd]),
+                U256::from(START + DELAY)
+            ),
+            Err(TimelockError::OperationExists(_))
+        ));
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.queue(
+                TARGET,
+                U256::ZERO,
+                Bytes::from(vec![]),
+                U256::from(START + DELAY)
+            ),
+            Err(TimelockError::NotAdmin(_))
+        ));
+    }
+
+    #[test]
+    fn test_cancel_and_expiry() {
+        let (vm, mut contract) = setup();
+        let eta = START + DELAY;
+        let cancelled = queue(&mut contract, eta);
+        let stale = queue(&mut contract, eta + 1);
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.cancel(cancelled),
+            Err(TimelockError::NotAdmin(_))
+        ));
+        vm.set_sender(ADMIN);
+        contract.cancel(cancelled).unwrap();
+        assert_eq!(contract.state(cancelled), STATE_CANCELLED);
+
+        vm.set_block_timestamp(eta + 1 + GRACE_PERIOD);
+        assert!(matches!(
+            contract.execute(cancelled),
+            Err(TimelockError::NotQueued(_))
+        ));
+        assert!(matches!(
+            contract.execute(stale),
+            Err(TimelockError::Expired(_))
+        ));
+    }
+
+    #[test]
+    fn test_failed_call_surfaces_return_data() {
+        let (vm, mut contract) = setup();
+        let id = queue(&mut contract, START + DELAY);
+        vm.set_block_timestamp(START + DELAY);
+        vm.mock_call(TARGET, vec![0xab, 0xcd], Err(b"no".to_vec()));
+        assert!(matches!(
+            contract.execute(id),
+            Err(TimelockError::ExecutionFailed(ExecutionFailed { returnData, .. }))
+                if returnData.as_ref() == b"no"
+        ));
+    }
+
+    #[test]
+    fn test_settings_change_only_through_timelock() {
+        let (vm, mut contract) = setup();
+        assert!(matches!(
+            contract.set_delay(0),
+            Err(TimelockError::OnlyTimelock(_))
+        ));
+        assert!(contract.set_admin(ALICE).is_err());
+
+        vm.set_sender(vm.contract_address());
+        contract.set_delay(DELAY * 2).unwrap();
+        assert!(contract.set_delay(MAX_DELAY + 1).is_err());
+        contract.set_admin(ALICE).unwrap();
+        assert_eq!(contract.delay(), U256::from(DELAY * 2));
+        assert_eq!(contract.admin(), ALICE);
+    }
+}
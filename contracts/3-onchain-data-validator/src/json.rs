@@ -0,0 +1,162 @@
+//! Minimal JSON reader for flat objects whose values are all strings:
+//!
+//! ```text
+//! object := '{' (pair (',' pair)*)? '}'
+//! pair   := string ':' string
+//! ```
+//!
+//! That is all `submit_json` accepts, so a hand-written reader keeps the
+//! module far smaller than a general-purpose JSON crate would. Standard string
+//! escapes are decoded; numbers, nesting, arrays and literals are rejected.
+
+use alloc::{string::String, vec::Vec};
+
+/// Where and why a payload failed to parse.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub position: usize,
+    pub reason: &'static str,
+}
+
+/// Parses a flat object of string values into `(key, value)` pairs, in order.
+pub fn parse_flat_object(input: &str) -> Result<Vec<(String, String)>, ParseError> {
+    let mut reader = Reader {
+        bytes: input.as_bytes(),
+        pos: 0,
+    };
+    let mut pairs = Vec::new();
+    reader.skip_whitespace();
+    reader.expect(b'{', "Expected '{'")?;
+    reader.skip_whitespace();
+    if reader.peek() == Some(b'}') {
+        reader.pos += 1;
+    } else {
+        loop {
+            let key = reader.string()?;
+            reader.skip_whitespace();
+            reader.expect(b':', "Expected ':'")?;
+            reader.skip_whitespace();
+            let value = reader.string()?;
+            pairs.push((key, value));
+            reader.skip_whitespace();
+            match reader.next() {
+                Some(b',') => reader.skip_whitespace(),
+                Some(b'}') => break,
+                _ => return Err(reader.error_before("Expected ',' or '}'")),
+            }
+        }
+    }
+    reader.skip_whitespace();
+    if reader.peek().is_some() {
+        return Err(reader.error("Trailing characters"));
+    }
+    Ok(pairs)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn error(&self, reason: &'static str) -> ParseError {
+        ParseError {
+            position: self.pos,
+            reason,
+        }
+    }
+
+    /// An error pointing at the byte just consumed.
+    fn error_before(&self, reason: &'static str) -> ParseError {
+        ParseError {
+            position: self.pos.saturating_sub(1),
+            reason,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8, reason: &'static str) -> Result<(), ParseError> {
+        if self.peek() != Some(byte) {
+            return Err(self.error(reason));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        self.expect(b'"', "Expected string")?;
+        let mut out = Vec::new();
+        loop {
+            match self.next() {
+                None => return Err(self.error("Unterminated string")),
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    let unescaped = match self.next() {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => self.unicode_escape()?,
+                        _ => return Err(self.error_before("Invalid escape")),
+                    };
+                    let mut buf = [0u8; 4];
+                    out.extend_from_slice(unescaped.encode_utf8(&mut buf).as_bytes());
+                }
+                Some(byte) if byte < 0x20 => {
+                    return Err(self.error_before("Control character in string"))
+                }
+                Some(byte) => out.push(byte),
+            }
+        }
+        // The input is a `&str` and escapes decode to whole chars, so this is valid UTF-8.
+        Ok(String::from_utf8(out).expect("valid UTF-8"))
+    }
+
+    /// Decodes the `XXXX` of `\uXXXX`. Surrogate pairs are not supported.
+    fn unicode_escape(&mut self) -> Result<char, ParseError> {
+        let code = self
+            .bytes
+            .get(self.pos..self.pos + 4)
+            .filter(|digits| digits.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|digits| core::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| self.error("Invalid unicode escape"))?;
+        let c = char::from_u32(code).ok_or_else(|| self.error("Invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(c)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_json_reader_decodes_escapes() {
+        let pairs = parse_flat_object(r#"{"a\"b": "A\n\\"}"#).unwrap();
+        assert_eq!(pairs, vec![("a\"b".to_string(), "A\n\\".to_string())]);
+        assert_eq!(parse_flat_object("{}").unwrap(), vec![]);
+        assert!(parse_flat_object(r#"{"a": "\u+041"}"#).is_err());
+        assert!(parse_flat_object(r#"{"a": "\x"}"#).is_err());
+        assert!(parse_flat_object("{\"a\": \"unterminated}").is_err());
+    }
+}
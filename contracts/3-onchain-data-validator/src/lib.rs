@@ -4,56 +4,1118 @@
 #[macro_use]
 extern crate alloc;
 
-use alloc::{string::{String, ToString}, vec::Vec};
+mod json;
+#[cfg(test)]
+mod proptests;
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use alloy_sol_types::sol;
+use common::ecdsa;
 use core::str::FromStr;
 use rust_decimal::Decimal;
-use stylus_sdk::{alloy_primitives::Address, prelude::*};
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{aliases::U80, Address, B256, I256, U256, U64, U8},
+    prelude::*,
+};
+
+/// The most fractional digits `rust_decimal` can represent.
+const MAX_SCALE: u8 = 28;
+/// Aggregates are stored as integers scaled by `10^AGGREGATE_SCALE`.
+const AGGREGATE_SCALE: u8 = 18;
+/// Longest payload `submit_json` accepts, in bytes.
+const MAX_JSON_BYTES: usize = 256;
+/// Longest ticker symbol accepted in a JSON payload.
+const MAX_SYMBOL_BYTES: usize = 10;
+/// Longest CSV batch `submit_batch` accepts, in bytes.
+const MAX_BATCH_BYTES: usize = 4096;
+/// Most rows a single CSV batch may contain.
+const MAX_BATCH_ROWS: usize = 64;
+/// Longest feed description `set_feed_metadata` accepts, in bytes.
+const MAX_DESCRIPTION_BYTES: usize = 64;
+/// Longest unit `set_feed_metadata` accepts, in bytes.
+const MAX_UNIT_BYTES: usize = 16;
+/// Longest key `register_*_field` accepts, in bytes.
+const MAX_FIELD_KEY_BYTES: usize = 32;
+/// Most keys the field registry may hold.
+const MAX_FIELDS: usize = 32;
+/// Aggregated submissions needed before outliers are rejected.
+const MIN_ANOMALY_SAMPLES: u64 = 5;
+
+pub const FIELD_DECIMAL: u8 = 0;
+pub const FIELD_TICKER: u8 = 1;
+pub const FIELD_U64: u8 = 2;
+
+/// `(field_type, min, max, limit)`, as returned by `field_rule`. Decimal fields carry their
+/// bounds and `limit` decimal places, ticker fields only their `limit` length, and u64
+/// fields their bounds in decimal with a `limit` of zero.
+pub type FieldRule = (u8, String, String, u8);
+
+sol! {
+    event ValidationConfigUpdated(string minValue, string maxValue, uint8 maxDecimalPlaces);
+    event OracleUpdated(address indexed oracle, bool trusted);
+    event DataSubmitted(address indexed sender, string value);
+    event SignedDataSubmitted(address indexed oracle, address indexed relayer, string value);
+    event FeedMetadataUpdated(string description, uint8 decimals, string unit);
+    event AnomalyThresholdUpdated(uint8 maxZScore);
+    event AnomalyDetected(address indexed sender, string value, int256 mean, int256 stdDev);
+    event StrictModeUpdated(bool strict);
+    event FieldRegistered(string key, uint8 fieldType);
+    event FieldSubmitted(address indexed sender, string key, string value);
+
+    #[derive(Debug)]
+    error InvalidDecimal(string reason);
+    #[derive(Debug)]
+    error SubmissionNotFound(address user, uint256 index);
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error InvalidConfig(string reason);
+    #[derive(Debug)]
+    error InvalidJson(uint256 position, string reason);
+    #[derive(Debug)]
+    error InvalidField(string field, string reason);
+    #[derive(Debug)]
+    error InvalidSignature(string reason);
+    #[derive(Debug)]
+    error UntrustedSigner(address signer);
+    #[derive(Debug)]
+    error InvalidBatch(string reason);
+    #[derive(Debug)]
+    error RoundNotFound(uint256 roundId);
+    #[derive(Debug)]
+    error AnomalousValue(int256 mean, int256 stdDev);
+    #[derive(Debug)]
+    error UnknownField(string key);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum DataValidatorError {
+    InvalidDecimal(InvalidDecimal),
+    SubmissionNotFound(SubmissionNotFound),
+    NotOwner(NotOwner),
+    InvalidConfig(InvalidConfig),
+    InvalidJson(InvalidJson),
+    InvalidField(InvalidField),
+    InvalidSignature(InvalidSignature),
+    UntrustedSigner(UntrustedSigner),
+    InvalidBatch(InvalidBatch),
+    RoundNotFound(RoundNotFound),
+    AnomalousValue(AnomalousValue),
+    UnknownField(UnknownField),
+}
+
+impl DataValidatorError {
+    fn invalid(reason: &str) -> Self {
+        DataValidatorError::InvalidDecimal(InvalidDecimal {
+            reason: reason.to_string(),
+        })
+    }
+
+    fn config(reason: &str) -> Self {
+        DataValidatorError::InvalidConfig(InvalidConfig {
+            reason: reason.to_string(),
+        })
+    }
+
+    fn field(field: &str, reason: &str) -> Self {
+        DataValidatorError::InvalidField(InvalidField {
+            field: field.to_string(),
+            reason: reason.to_string(),
+        })
+    }
+
+    fn signature(reason: &str) -> Self {
+        DataValidatorError::InvalidSignature(InvalidSignature {
+            reason: reason.to_string(),
+        })
+    }
+
+    fn batch(reason: &str) -> Self {
+        DataValidatorError::InvalidBatch(InvalidBatch {
+            reason: reason.to_string(),
+        })
+    }
+}
 
 sol_storage! {
+    /// The fields of a `{"price": ..., "symbol": ...}` payload.
+    pub struct PriceQuote {
+        string price;
+        string symbol;
+    }
+
+    /// One aggregated submission.
+    pub struct Round {
+        /// Scaled by `10^AGGREGATE_SCALE`.
+        int256 answer;
+        address submitter;
+        uint64 updated_at;
+    }
+
+    /// A `submit_field` key: its validation rule and last accepted value.
+    pub struct Field {
+        bool registered;
+        /// One of the `FIELD_*` constants.
+        uint8 field_type;
+        /// Inclusive bounds of a decimal field, as the decimal strings the owner supplied.
+        string min_value;
+        string max_value;
+        uint8 max_decimal_places;
+        /// Longest value of a ticker field.
+        uint8 max_length;
+        /// Inclusive bounds of a u64 field.
+        uint64 min_u64;
+        uint64 max_u64;
+        string value;
+        address submitter;
+        uint64 updated_at;
+    }
+
     #[entrypoint]
     pub struct DataValidator {
-        /// Stores the last valid decimal submitted by each user.
-        mapping(address => string) last_valid_submissions;
+        address owner;
+        /// Inclusive bounds, kept as the decimal strings the owner supplied.
+        string min_value;
+        string max_value;
+        uint8 max_decimal_places;
+        /// Every valid decimal submitted by each user, oldest first.
+        mapping(address => string[]) submissions;
+        /// Aggregates over all valid submissions, scaled by `10^AGGREGATE_SCALE`.
+        uint256 aggregate_count;
+        int256 aggregate_sum;
+        int256 aggregate_min;
+        int256 aggregate_max;
+        /// Last valid JSON payload submitted by each user.
+        mapping(address => PriceQuote) json_submissions;
+        /// Oracles whose signed values anyone may relay with `submit_signed_data`.
+        mapping(address => bool) trusted_oracles;
+        /// Per-oracle counter bound into each signed message, so every signature is accepted
+        /// once and oracles never invalidate each other's pending signatures.
+        mapping(address => uint256) signed_nonces;
+        /// Every aggregated submission by round id; ids start at 1 and the latest is
+        /// `aggregate_count`.
+        mapping(uint256 => Round) rounds;
+        /// What the feed measures, as set by the owner with `set_feed_metadata`.
+        string description;
+        uint8 decimals;
+        string unit;
+        /// Welford running mean and sum of squared deviations over all aggregated
+        /// submissions, scaled by `10^AGGREGATE_SCALE`.
+        int256 stats_mean;
+        int256 stats_m2;
+        /// Values more than this many standard deviations from the mean are rejected;
+        /// zero turns detection off.
+        uint8 max_z_score;
+        /// Rejects digit-grouped and scientific values instead of normalizing them.
+        bool strict_mode;
+        /// Registered `submit_field` keys and their rules, in registration order.
+        mapping(string => Field) fields;
+        string[] field_keys;
     }
 }
 
 #[public]
 impl DataValidator {
+    /// Sets the owner and the initial bounds, checked like `set_validation_config`.
+    ///
+    /// Stylus runs the constructor once, at deployment; any later call reverts.
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        owner: Address,
+        min_value: String,
+        max_value: String,
+        max_decimal_places: u8,
+    ) -> Result<(), DataValidatorError> {
+        self.owner.set(owner);
+        self.write_config(min_value, max_value, max_decimal_places)
+    }
+
     /// Submits a string, validates it as a decimal, and stores it for the caller.
-    pub fn submit_data(&mut self, value: String) -> Result<(), Vec<u8>> {
-        match Decimal::from_str(&value) {
-            Ok(decimal) => {
-                if decimal > Decimal::new(1_000_000_000, 0) {
-                    return Err("Decimal value too large".to_string().into_bytes());
-                }
-                if decimal < Decimal::new(-1_000_000_000, 0) {
-                    return Err("Decimal value too small".to_string().into_bytes());
-                }
+    ///
+    /// Outside strict mode, `1_000_000.25`, `1,000,000.25` and `1.5e6` are accepted and
+    /// stored in plain notation.
+    pub fn submit_data(&mut self, value: String) -> Result<(), DataValidatorError> {
+        let (value, decimal) = self
+            .validate_decimal(&value)
+            .map_err(DataValidatorError::invalid)?;
+        self.check_anomaly(to_scaled(decimal))?;
+        let caller = self.vm().msg_sender();
+        self.store_submission(caller, value, decimal);
+        Ok(())
+    }
+
+    /// Submits one decimal per line and stores every valid row, returning
+    /// `(stored, failedRows)` with the 0-based indices of the rows that failed validation.
+    ///
+    /// Invalid rows are skipped rather than reverting the batch. Lines may end in `\n` or
+    /// `\r\n`, and a single trailing newline is ignored. The batch is capped at
+    /// `MAX_BATCH_BYTES` and `MAX_BATCH_ROWS` so its gas cost stays bounded. Rows rejected as
+    /// anomalies also count as failed, and since the batch does not revert, each of them is
+    /// logged as `AnomalyDetected`.
+    pub fn submit_batch(&mut self, csv: String) -> Result<(U256, Vec<U256>), DataValidatorError> {
+        if csv.is_empty() {
+            return Err(DataValidatorError::batch("Empty batch"));
+        }
+        if csv.len() > MAX_BATCH_BYTES {
+            return Err(DataValidatorError::batch("Batch too long"));
+        }
+        let body = csv.strip_suffix('\n').unwrap_or(&csv);
+        // Count first so an oversized batch stores nothing.
+        if body.split('\n').count() > MAX_BATCH_ROWS {
+            return Err(DataValidatorError::batch("Too many rows"));
+        }
+
+        let caller = self.vm().msg_sender();
+        let mut stored = 0;
+        let mut failed = Vec::new();
+        for (index, row) in body.split('\n').enumerate() {
+            let row = row.strip_suffix('\r').unwrap_or(row);
+            let Ok((value, decimal)) = self.validate_decimal(row) else {
+                failed.push(U256::from(index));
+                continue;
+            };
+            if let Err(DataValidatorError::AnomalousValue(anomaly)) =
+                self.check_anomaly(to_scaled(decimal))
+            {
+                log(
+                    self.vm(),
+                    AnomalyDetected {
+                        sender: caller,
+                        value: row.to_string(),
+                        mean: anomaly.mean,
+                        stdDev: anomaly.stdDev,
+                    },
+                );
+                failed.push(U256::from(index));
+                continue;
             }
-            Err(_) => {
-                return Err("Invalid decimal format".to_string().into_bytes());
+            self.store_submission(caller, value, decimal);
+            stored += 1;
+        }
+        Ok((U256::from(stored), failed))
+    }
+
+    /// Submits a JSON object such as `{"price": "1.23", "symbol": "ETH"}` and stores its fields.
+    ///
+    /// Both fields are required and no others are allowed. `price` passes the same checks
+    /// as `submit_data`; `symbol` must be 1 to 10 uppercase letters or digits.
+    pub fn submit_json(&mut self, payload: String) -> Result<(), DataValidatorError> {
+        if payload.len() > MAX_JSON_BYTES {
+            return Err(DataValidatorError::InvalidJson(InvalidJson {
+                position: U256::from(MAX_JSON_BYTES),
+                reason: "Payload too long".to_string(),
+            }));
+        }
+        let pairs = json::parse_flat_object(&payload).map_err(|err| {
+            DataValidatorError::InvalidJson(InvalidJson {
+                position: U256::from(err.position),
+                reason: err.reason.to_string(),
+            })
+        })?;
+
+        let (mut price, mut symbol) = (None, None);
+        for (key, value) in pairs {
+            let slot = match key.as_str() {
+                "price" => &mut price,
+                "symbol" => &mut symbol,
+                _ => return Err(DataValidatorError::field(&key, "Unknown field")),
+            };
+            if slot.replace(value).is_some() {
+                return Err(DataValidatorError::field(&key, "Duplicate field"));
             }
         }
+        let price = price.ok_or_else(|| DataValidatorError::field("price", "Missing field"))?;
+        let symbol = symbol.ok_or_else(|| DataValidatorError::field("symbol", "Missing field"))?;
+        let (price, _) = self
+            .validate_decimal(&price)
+            .map_err(|reason| DataValidatorError::field("price", reason))?;
+        if !valid_symbol(&symbol, MAX_SYMBOL_BYTES) {
+            return Err(DataValidatorError::field("symbol", "Invalid symbol"));
+        }
 
         let caller = self.vm().msg_sender();
-        self.last_valid_submissions.setter(caller).set_str(&value);
+        let mut quote = self.json_submissions.setter(caller);
+        quote.price.set_str(&price);
+        quote.symbol.set_str(&symbol);
+        Ok(())
+    }
+
+    /// Returns the `(price, symbol)` of a user's last valid JSON payload; empty if none.
+    pub fn get_json_submission(&self, user: Address) -> (String, String) {
+        let quote = self.json_submissions.getter(user);
+        (quote.price.get_string(), quote.symbol.get_string())
+    }
+
+    /// Stores `value` on behalf of the trusted `oracle` that signed it; anyone may relay.
+    ///
+    /// `signature` is the 65-byte `r || s || v` produced by `personal_sign` over the
+    /// 32 bytes returned by `signed_message_hash(oracle, value)`. The value is recorded in
+    /// the oracle's history, not the relayer's, and that oracle's nonce moves on so the
+    /// signature cannot be replayed. The signature covers `value` as sent; it is stored and
+    /// logged normalized.
+    pub fn submit_signed_data(
+        &mut self,
+        oracle: Address,
+        value: String,
+        signature: Bytes,
+    ) -> Result<(), DataValidatorError> {
+        if !self.trusted_oracles.get(oracle) {
+            return Err(DataValidatorError::UntrustedSigner(UntrustedSigner {
+                signer: oracle,
+            }));
+        }
+        let hash = self.signed_message_hash(oracle, value.clone());
+        let signer = ecdsa::recover(self.vm(), ecdsa::eip191_digest(self.vm(), hash), &signature)
+            .map_err(|err| DataValidatorError::signature(err.reason()))?;
+        if signer != oracle {
+            return Err(DataValidatorError::signature("Not signed by the oracle"));
+        }
+        let (value, decimal) = self
+            .validate_decimal(&value)
+            .map_err(DataValidatorError::invalid)?;
+        self.check_anomaly(to_scaled(decimal))?;
+
+        let nonce = self.signed_nonces.get(oracle);
+        self.signed_nonces.setter(oracle).set(nonce + U256::from(1));
+        self.submissions.setter(oracle).grow().set_str(&value);
+        self.record_aggregate(oracle, to_scaled(decimal));
+        let relayer = self.vm().msg_sender();
+        log(
+            self.vm(),
+            SignedDataSubmitted {
+                oracle,
+                relayer,
+                value,
+            },
+        );
+        Ok(())
+    }
+
+    /// The hash `oracle` signs for `value`:
+    /// `keccak256(abi.encodePacked(chainId, address(this), oracle, signed_nonce(oracle), value))`.
+    pub fn signed_message_hash(&self, oracle: Address, value: String) -> B256 {
+        let mut message = Vec::with_capacity(32 + 20 + 20 + 32 + value.len());
+        message.extend_from_slice(&U256::from(self.vm().chain_id()).to_be_bytes::<32>());
+        message.extend_from_slice(self.vm().contract_address().as_slice());
+        message.extend_from_slice(oracle.as_slice());
+        message.extend_from_slice(&self.signed_nonces.get(oracle).to_be_bytes::<32>());
+        message.extend_from_slice(value.as_bytes());
+        self.vm().native_keccak256(&message)
+    }
+
+    /// Nonce `oracle`'s next signed submission must be signed over.
+    pub fn signed_nonce(&self, oracle: Address) -> U256 {
+        self.signed_nonces.get(oracle)
+    }
+
+    /// Adds or removes an oracle whose signatures `submit_signed_data` accepts.
+    pub fn set_oracle(&mut self, oracle: Address, trusted: bool) -> Result<(), DataValidatorError> {
+        self.only_owner()?;
+        self.trusted_oracles.setter(oracle).set(trusted);
+        log(self.vm(), OracleUpdated { oracle, trusted });
         Ok(())
     }
 
-    /// Retrieves the last valid submission for a specific user.
+    pub fn is_oracle(&self, account: Address) -> bool {
+        self.trusted_oracles.get(account)
+    }
+
+    /// Retrieves the last valid submission for a specific user, or an empty string if none.
     pub fn get_last_submission(&self, user: Address) -> String {
-        self.last_valid_submissions.getter(user).get_string()
+        let history = self.submissions.getter(user);
+        match history.len().checked_sub(1) {
+            Some(last) => history
+                .getter(last)
+                .map(|s| s.get_string())
+                .unwrap_or_default(),
+            None => String::new(),
+        }
+    }
+
+    /// Retrieves a user's `index`-th valid submission, counting from 0.
+    pub fn get_submission(&self, user: Address, index: U256) -> Result<String, DataValidatorError> {
+        let not_found =
+            || DataValidatorError::SubmissionNotFound(SubmissionNotFound { user, index });
+        let index = usize::try_from(index).map_err(|_| not_found())?;
+        self.submissions
+            .getter(user)
+            .getter(index)
+            .map(|s| s.get_string())
+            .ok_or_else(not_found)
+    }
+
+    /// Number of valid submissions a user has made.
+    pub fn submission_count(&self, user: Address) -> U256 {
+        U256::from(self.submissions.getter(user).len())
+    }
+
+    /// Replaces the inclusive value bounds and the allowed number of decimal places.
+    pub fn set_validation_config(
+        &mut self,
+        min_value: String,
+        max_value: String,
+        max_decimal_places: u8,
+    ) -> Result<(), DataValidatorError> {
+        self.only_owner()?;
+        self.write_config(min_value, max_value, max_decimal_places)
+    }
+
+    /// Returns `(min_value, max_value, max_decimal_places)`.
+    pub fn get_validation_config(&self) -> (String, String, u8) {
+        (
+            self.min_value.get_string(),
+            self.max_value.get_string(),
+            self.max_decimal_places.get().to(),
+        )
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    /// Describes what the feed measures, e.g. `("ETH / USD", 2, "USD")`. Only the owner may
+    /// call this.
+    ///
+    /// `decimals` is the precision the measured quantity is quoted in, for display; it does
+    /// not change validation or the `10^AGGREGATE_SCALE` scaling of aggregates and rounds.
+    pub fn set_feed_metadata(
+        &mut self,
+        description: String,
+        decimals: u8,
+        unit: String,
+    ) -> Result<(), DataValidatorError> {
+        self.only_owner()?;
+        if description.len() > MAX_DESCRIPTION_BYTES {
+            return Err(DataValidatorError::config("Description too long"));
+        }
+        if unit.len() > MAX_UNIT_BYTES {
+            return Err(DataValidatorError::config("Unit too long"));
+        }
+        if decimals > MAX_SCALE {
+            return Err(DataValidatorError::config("Too many decimals"));
+        }
+        self.description.set_str(&description);
+        self.decimals.set(U8::from(decimals));
+        self.unit.set_str(&unit);
+        log(
+            self.vm(),
+            FeedMetadataUpdated {
+                description,
+                decimals,
+                unit,
+            },
+        );
+        Ok(())
+    }
+
+    /// Returns `(description, decimals, unit)`; empty until the owner sets them.
+    pub fn feed_metadata(&self) -> (String, u8, String) {
+        (
+            self.description.get_string(),
+            self.decimals.get().to(),
+            self.unit.get_string(),
+        )
+    }
+
+    /// Number of valid submissions across all users.
+    pub fn aggregate_count(&self) -> U256 {
+        self.aggregate_count.get()
+    }
+
+    /// Returns `(sum, scale)`: the sum of all valid submissions is `sum / 10^scale`.
+    pub fn aggregate_sum(&self) -> (I256, u8) {
+        (self.aggregate_sum.get(), AGGREGATE_SCALE)
+    }
+
+    /// Returns `(min, scale)`; `(0, scale)` before the first submission.
+    pub fn aggregate_min(&self) -> (I256, u8) {
+        (self.aggregate_min.get(), AGGREGATE_SCALE)
+    }
+
+    /// Returns `(max, scale)`; `(0, scale)` before the first submission.
+    pub fn aggregate_max(&self) -> (I256, u8) {
+        (self.aggregate_max.get(), AGGREGATE_SCALE)
+    }
+
+    /// Returns `(average, scale)`, truncated toward zero; `(0, scale)` before the first submission.
+    pub fn running_average(&self) -> (I256, u8) {
+        let count = self.aggregate_count.get();
+        if count.is_zero() {
+            return (I256::ZERO, AGGREGATE_SCALE);
+        }
+        let count = I256::try_from(count).expect("count fits in int256");
+        (self.aggregate_sum.get() / count, AGGREGATE_SCALE)
+    }
+
+    /// Returns `(stdDev, scale)`: the population standard deviation of all valid submissions.
+    pub fn running_std_dev(&self) -> (I256, u8) {
+        (self.std_dev(), AGGREGATE_SCALE)
+    }
+
+    /// Rejects submissions more than `max_z_score` standard deviations from the running
+    /// mean, once `MIN_ANOMALY_SAMPLES` values have been aggregated. Zero turns it off.
+    pub fn set_anomaly_threshold(&mut self, max_z_score: u8) -> Result<(), DataValidatorError> {
+        self.only_owner()?;
+        self.max_z_score.set(U8::from(max_z_score));
+        log(
+            self.vm(),
+            AnomalyThresholdUpdated {
+                maxZScore: max_z_score,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn anomaly_threshold(&self) -> u8 {
+        self.max_z_score.get().to()
+    }
+
+    /// In strict mode only plain decimals are accepted; digit-grouped and scientific values
+    /// are rejected rather than normalized. Only the owner may call this.
+    pub fn set_strict_mode(&mut self, strict: bool) -> Result<(), DataValidatorError> {
+        self.only_owner()?;
+        self.strict_mode.set(strict);
+        log(self.vm(), StrictModeUpdated { strict });
+        Ok(())
+    }
+
+    pub fn strict_mode(&self) -> bool {
+        self.strict_mode.get()
+    }
+
+    /// Registers `key` as a decimal field within `[min_value, max_value]` with at most
+    /// `max_decimal_places`. Only the owner may call this.
+    ///
+    /// Registering an existing key replaces its rule and clears its last value.
+    pub fn register_decimal_field(
+        &mut self,
+        key: String,
+        min_value: String,
+        max_value: String,
+        max_decimal_places: u8,
+    ) -> Result<(), DataValidatorError> {
+        self.only_owner()?;
+        let min = Decimal::from_str(&min_value)
+            .map_err(|_| DataValidatorError::config("Invalid minimum"))?;
+        let max = Decimal::from_str(&max_value)
+            .map_err(|_| DataValidatorError::config("Invalid maximum"))?;
+        if min > max {
+            return Err(DataValidatorError::config("Minimum exceeds maximum"));
+        }
+        if max_decimal_places > MAX_SCALE {
+            return Err(DataValidatorError::config("Too many decimal places"));
+        }
+        self.register_field(&key, FIELD_DECIMAL)?;
+        let mut field = self.fields.setter(key);
+        field.min_value.set_str(&min_value);
+        field.max_value.set_str(&max_value);
+        field.max_decimal_places.set(U8::from(max_decimal_places));
+        Ok(())
+    }
+
+    /// Registers `key` as a ticker field: 1 to `max_length` uppercase letters or digits.
+    /// Only the owner may call this.
+    pub fn register_ticker_field(
+        &mut self,
+        key: String,
+        max_length: u8,
+    ) -> Result<(), DataValidatorError> {
+        self.only_owner()?;
+        if max_length == 0 || usize::from(max_length) > MAX_SYMBOL_BYTES {
+            return Err(DataValidatorError::config("Invalid ticker length"));
+        }
+        self.register_field(&key, FIELD_TICKER)?;
+        let mut field = self.fields.setter(key);
+        field.max_length.set(U8::from(max_length));
+        Ok(())
+    }
+
+    /// Registers `key` as an unsigned integer field within `[min, max]`, such as a Unix
+    /// timestamp. Only the owner may call this.
+    pub fn register_u64_field(
+        &mut self,
+        key: String,
+        min: u64,
+        max: u64,
+    ) -> Result<(), DataValidatorError> {
+        self.only_owner()?;
+        if min > max {
+            return Err(DataValidatorError::config("Minimum exceeds maximum"));
+        }
+        self.register_field(&key, FIELD_U64)?;
+        let mut field = self.fields.setter(key);
+        field.min_u64.set(U64::from(min));
+        field.max_u64.set(U64::from(max));
+        Ok(())
+    }
+
+    /// Validates `value` against the rule registered for `key` and stores it as the key's
+    /// last value. Decimal fields follow the strict-mode setting like `submit_data`.
+    pub fn submit_field(&mut self, key: String, value: String) -> Result<(), DataValidatorError> {
+        let field = self.fields.getter(key.clone());
+        if !field.registered.get() {
+            return Err(DataValidatorError::UnknownField(UnknownField { key }));
+        }
+        let invalid = |reason| DataValidatorError::field(&key, reason);
+        let value = match field.field_type.get().to::<u8>() {
+            FIELD_DECIMAL => {
+                let parse = |bound: String| Decimal::from_str(&bound).expect("validated on write");
+                let min = parse(field.min_value.get_string());
+                let max = parse(field.max_value.get_string());
+                let places = field.max_decimal_places.get().to::<u8>();
+                self.check_decimal(&value, min, max, places)
+                    .map_err(invalid)?
+                    .0
+            }
+            FIELD_TICKER => {
+                if !valid_symbol(&value, field.max_length.get().to::<usize>()) {
+                    return Err(invalid("Invalid ticker"));
+                }
+                value
+            }
+            _ => {
+                if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(invalid("Invalid integer"));
+                }
+                let number = value
+                    .parse::<u64>()
+                    .map_err(|_| invalid("Integer too large"))?;
+                if number > field.max_u64.get().to::<u64>() {
+                    return Err(invalid("Integer too large"));
+                }
+                if number < field.min_u64.get().to::<u64>() {
+                    return Err(invalid("Integer too small"));
+                }
+                value
+            }
+        };
+
+        let sender = self.vm().msg_sender();
+        let updated_at = self.vm().block_timestamp();
+        let mut field = self.fields.setter(key.clone());
+        field.value.set_str(&value);
+        field.submitter.set(sender);
+        field.updated_at.set(U64::from(updated_at));
+        log(self.vm(), FieldSubmitted { sender, key, value });
+        Ok(())
+    }
+
+    /// Registered field keys, in registration order.
+    pub fn field_keys(&self) -> Vec<String> {
+        (0..self.field_keys.len())
+            .filter_map(|index| self.field_keys.getter(index))
+            .map(|key| key.get_string())
+            .collect()
+    }
+
+    /// The rule registered for `key`; see `FieldRule`.
+    pub fn field_rule(&self, key: String) -> Result<FieldRule, DataValidatorError> {
+        let field = self.fields.getter(key.clone());
+        if !field.registered.get() {
+            return Err(DataValidatorError::UnknownField(UnknownField { key }));
+        }
+        let field_type = field.field_type.get().to::<u8>();
+        Ok(match field_type {
+            FIELD_DECIMAL => (
+                field_type,
+                field.min_value.get_string(),
+                field.max_value.get_string(),
+                field.max_decimal_places.get().to(),
+            ),
+            FIELD_TICKER => (
+                field_type,
+                String::new(),
+                String::new(),
+                field.max_length.get().to(),
+            ),
+            _ => (
+                field_type,
+                field.min_u64.get().to_string(),
+                field.max_u64.get().to_string(),
+                0,
+            ),
+        })
+    }
+
+    /// `(value, submitter, updated_at)` of the last accepted value for `key`; empty until
+    /// the first one.
+    pub fn field_value(&self, key: String) -> Result<(String, Address, u64), DataValidatorError> {
+        let field = self.fields.getter(key.clone());
+        if !field.registered.get() {
+            return Err(DataValidatorError::UnknownField(UnknownField { key }));
+        }
+        Ok((
+            field.value.get_string(),
+            field.submitter.get(),
+            field.updated_at.get().to(),
+        ))
+    }
+
+    /// `(roundId, answer, startedAt, updatedAt, answeredInRound)` for a past round, as
+    /// `AggregatorV3Interface.getRoundData` returns it.
+    ///
+    /// Every value that enters the aggregates is a round, numbered from 1. Answers are scaled
+    /// by `10^AGGREGATE_SCALE`; a round starts and is answered in the same block.
+    pub fn get_round_data(
+        &self,
+        round_id: U80,
+    ) -> Result<(U80, I256, U256, U256, U80), DataValidatorError> {
+        let (answer, _, updated_at) = self.round(U256::from(round_id))?;
+        let updated_at = U256::from(updated_at);
+        Ok((round_id, answer, updated_at, updated_at, round_id))
+    }
+
+    /// `getRoundData` for the most recent round.
+    pub fn latest_round_data(&self) -> Result<(U80, I256, U256, U256, U80), DataValidatorError> {
+        let latest = U80::saturating_from(self.aggregate_count.get());
+        self.get_round_data(latest)
+    }
+
+    /// The user, or the oracle for a signed submission, whose value a round recorded.
+    pub fn round_submitter(&self, round_id: U80) -> Result<Address, DataValidatorError> {
+        Ok(self.round(U256::from(round_id))?.1)
+    }
+}
+
+impl DataValidator {
+    fn only_owner(&self) -> Result<(), DataValidatorError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(DataValidatorError::NotOwner(NotOwner { caller }));
+        }
+        Ok(())
+    }
+
+    /// Validates and stores the bounds, then logs `ValidationConfigUpdated`.
+    fn write_config(
+        &mut self,
+        min_value: String,
+        max_value: String,
+        max_decimal_places: u8,
+    ) -> Result<(), DataValidatorError> {
+        let min = Decimal::from_str(&min_value)
+            .map_err(|_| DataValidatorError::config("Invalid minimum"))?;
+        let max = Decimal::from_str(&max_value)
+            .map_err(|_| DataValidatorError::config("Invalid maximum"))?;
+        if min > max {
+            return Err(DataValidatorError::config("Minimum exceeds maximum"));
+        }
+        if max_decimal_places > MAX_SCALE {
+            return Err(DataValidatorError::config("Too many decimal places"));
+        }
+
+        self.min_value.set_str(&min_value);
+        self.max_value.set_str(&max_value);
+        self.max_decimal_places.set(U8::from(max_decimal_places));
+        log(
+            self.vm(),
+            ValidationConfigUpdated {
+                minValue: min_value,
+                maxValue: max_value,
+                maxDecimalPlaces: max_decimal_places,
+            },
+        );
+        Ok(())
+    }
+
+    /// The configured `(min, max)`, which `write_config` checked parse.
+    fn bounds(&self) -> (Decimal, Decimal) {
+        let parse = |value: String| Decimal::from_str(&value).expect("validated on write");
+        (
+            parse(self.min_value.get_string()),
+            parse(self.max_value.get_string()),
+        )
+    }
+
+    /// Parses `value` and checks it against the configured bounds and precision, returning
+    /// the string to store with the decimal.
+    fn validate_decimal(&self, value: &str) -> Result<(String, Decimal), &'static str> {
+        let (min, max) = self.bounds();
+        let max_decimal_places = self.max_decimal_places.get().to::<u8>();
+        self.check_decimal(value, min, max, max_decimal_places)
+    }
+
+    /// Parses `value` and checks it against `[min, max]` and `max_decimal_places`.
+    ///
+    /// Plain values are stored as submitted. Digit-grouped and scientific ones are rewritten
+    /// in plain notation, or rejected in strict mode.
+    fn check_decimal(
+        &self,
+        value: &str,
+        min: Decimal,
+        max: Decimal,
+        max_decimal_places: u8,
+    ) -> Result<(String, Decimal), &'static str> {
+        let value = match normalize(value)? {
+            None => value.to_string(),
+            Some(_) if self.strict_mode.get() && value.contains(['e', 'E']) => {
+                return Err("Scientific notation not allowed");
+            }
+            Some(_) if self.strict_mode.get() => return Err("Digit separators not allowed"),
+            Some(normalized) => normalized,
+        };
+        let decimal = Decimal::from_str(&value).map_err(|_| "Invalid decimal format")?;
+        if decimal > max {
+            return Err("Decimal value too large");
+        }
+        if decimal < min {
+            return Err("Decimal value too small");
+        }
+        if decimal.scale() > u32::from(max_decimal_places) {
+            return Err("Too many decimal places");
+        }
+        Ok((value, decimal))
+    }
+
+    /// Checks `key`, then marks it registered as `field_type` with its last value cleared;
+    /// the caller fills in the rule.
+    fn register_field(&mut self, key: &str, field_type: u8) -> Result<(), DataValidatorError> {
+        let valid_key = !key.is_empty()
+            && key.len() <= MAX_FIELD_KEY_BYTES
+            && key
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_');
+        if !valid_key {
+            return Err(DataValidatorError::config("Invalid field key"));
+        }
+        if !self.fields.getter(key.to_string()).registered.get() {
+            if self.field_keys.len() >= MAX_FIELDS {
+                return Err(DataValidatorError::config("Too many fields"));
+            }
+            self.field_keys.grow().set_str(key);
+        }
+        log(
+            self.vm(),
+            FieldRegistered {
+                key: key.to_string(),
+                fieldType: field_type,
+            },
+        );
+
+        let mut field = self.fields.setter(key.to_string());
+        field.registered.set(true);
+        field.field_type.set(U8::from(field_type));
+        field.value.set_str("");
+        field.submitter.set(Address::ZERO);
+        field.updated_at.set(U64::ZERO);
+        Ok(())
+    }
+
+    /// Population standard deviation of the aggregated values, scaled by `10^AGGREGATE_SCALE`.
+    fn std_dev(&self) -> I256 {
+        let count = self.aggregate_count.get();
+        if count.is_zero() {
+            return I256::ZERO;
+        }
+        let variance = self.stats_m2.get().unsigned_abs() / count;
+        // `variance` carries one factor of the scale; the root needs two.
+        let scale = U256::from(10u64).pow(U256::from(AGGREGATE_SCALE));
+        let root = isqrt(variance.saturating_mul(scale));
+        I256::try_from(root).expect("square root fits in int256")
+    }
+
+    /// Fails with `AnomalousValue` if `value` lies more than `max_z_score` standard
+    /// deviations from the mean.
+    ///
+    /// Compares squares, `(value - mean)^2 > k^2 * variance`, so no square root is taken
+    /// on the accepting path. Products saturate rather than overflow for huge values.
+    fn check_anomaly(&self, value: I256) -> Result<(), DataValidatorError> {
+        let k = self.max_z_score.get().to::<u64>();
+        let count = self.aggregate_count.get();
+        if k == 0 || count < U256::from(MIN_ANOMALY_SAMPLES) {
+            return Ok(());
+        }
+        let mean = self.stats_mean.get();
+        let deviation = value - mean;
+        let scale = I256::exp10(AGGREGATE_SCALE as usize);
+        let deviation_squared = deviation.saturating_mul(deviation) / scale;
+        let variance = self.stats_m2.get() / I256::try_from(count).expect("count fits in int256");
+        let limit = variance.saturating_mul(I256::try_from(k * k).expect("k is a u8"));
+        if deviation_squared > limit {
+            return Err(DataValidatorError::AnomalousValue(AnomalousValue {
+                mean,
+                stdDev: self.std_dev(),
+            }));
+        }
+        Ok(())
+    }
+
+    /// Appends a validated value to `user`'s history and the aggregates, then logs it.
+    fn store_submission(&mut self, user: Address, value: String, decimal: Decimal) {
+        self.submissions.setter(user).grow().set_str(&value);
+        self.record_aggregate(user, to_scaled(decimal));
+        log(
+            self.vm(),
+            DataSubmitted {
+                sender: user,
+                value,
+            },
+        );
+    }
+
+    /// Adds `value` to the aggregates and records it as the next round.
+    fn record_aggregate(&mut self, submitter: Address, value: I256) {
+        let count = self.aggregate_count.get();
+        if count.is_zero() || value < self.aggregate_min.get() {
+            self.aggregate_min.set(value);
+        }
+        if count.is_zero() || value > self.aggregate_max.get() {
+            self.aggregate_max.set(value);
+        }
+        let round_id = count + U256::from(1);
+        self.aggregate_count.set(round_id);
+        self.aggregate_sum.set(self.aggregate_sum.get() + value);
+
+        // Welford's update keeps the mean and variance numerically stable in fixed point.
+        let mean = self.stats_mean.get();
+        let delta = value - mean;
+        let new_mean = mean + delta / I256::try_from(round_id).expect("count fits in int256");
+        let product = delta.saturating_mul(value - new_mean);
+        let m2 = self.stats_m2.get() + product / I256::exp10(AGGREGATE_SCALE as usize);
+        self.stats_mean.set(new_mean);
+        self.stats_m2.set(m2);
+
+        let updated_at = self.vm().block_timestamp();
+        let mut round = self.rounds.setter(round_id);
+        round.answer.set(value);
+        round.submitter.set(submitter);
+        round.updated_at.set(U64::from(updated_at));
+    }
+
+    /// The stored round, or `RoundNotFound` for an id that was never recorded.
+    fn round(&self, round_id: U256) -> Result<(I256, Address, u64), DataValidatorError> {
+        if round_id.is_zero() || round_id > self.aggregate_count.get() {
+            return Err(DataValidatorError::RoundNotFound(RoundNotFound {
+                roundId: round_id,
+            }));
+        }
+        let round = self.rounds.getter(round_id);
+        Ok((
+            round.answer.get(),
+            round.submitter.get(),
+            round.updated_at.get().to::<u64>(),
+        ))
+    }
+}
+
+/// Converts `decimal` to an integer scaled by `10^AGGREGATE_SCALE`, truncating extra digits.
+fn to_scaled(decimal: Decimal) -> I256 {
+    let mantissa = I256::try_from(decimal.mantissa()).expect("i128 fits in int256");
+    let scale = decimal.scale() as usize;
+    let target = AGGREGATE_SCALE as usize;
+    if scale <= target {
+        mantissa * I256::exp10(target - scale)
+    } else {
+        mantissa / I256::exp10(scale - target)
+    }
+}
+
+/// Whether `symbol` is 1 to `max_length` uppercase ASCII letters or digits.
+fn valid_symbol(symbol: &str, max_length: usize) -> bool {
+    !symbol.is_empty()
+        && symbol.len() <= max_length
+        && symbol
+            .bytes()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+}
+
+/// Rewrites a digit-grouped (`1_000_000.25`, `1,000,000.25`) or scientific (`1.5e6`) value
+/// in plain notation. Returns `None` for a value that uses neither.
+///
+/// A comma always separates thousands, never decimals, so `1,50` is rejected rather than
+/// read as one and a half.
+fn normalize(value: &str) -> Result<Option<String>, &'static str> {
+    const INVALID: &str = "Invalid decimal format";
+    let (mantissa, exponent) = match value.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (value, None),
+    };
+    let grouped = mantissa.contains(['_', ',']);
+    if !grouped && exponent.is_none() {
+        return Ok(None);
+    }
+    let mantissa = if grouped {
+        ungroup(mantissa).ok_or(INVALID)?
+    } else {
+        mantissa.to_string()
+    };
+    let decimal = match exponent {
+        Some(exponent) => Decimal::from_scientific(&format!("{mantissa}e{exponent}")),
+        None => Decimal::from_str(&mantissa),
+    }
+    .map_err(|_| INVALID)?;
+    Ok(Some(decimal.to_string()))
+}
+
+/// Strips digit separators from a mantissa: `_` between any two digits, or `,` between
+/// the thousands groups of the integer part. The two may not be mixed.
+fn ungroup(mantissa: &str) -> Option<String> {
+    let (sign, unsigned) = mantissa.split_at(usize::from(mantissa.starts_with(['-', '+'])));
+    let (integer, fraction) = match unsigned.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (unsigned, None),
+    };
+    let digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    let separator = if integer.contains(',') { ',' } else { '_' };
+    let groups: Vec<&str> = integer.split(separator).collect();
+    let thousands = separator == '_'
+        || (groups[0].len() <= 3 && groups[1..].iter().all(|group| group.len() == 3));
+    if !thousands || !groups.iter().all(|group| digits(group)) {
+        return None;
+    }
+
+    let mut plain = String::from(sign);
+    plain.push_str(&groups.concat());
+    if let Some(fraction) = fraction {
+        if !fraction.split('_').all(digits) {
+            return None;
+        }
+        plain.push('.');
+        plain.push_str(&fraction.replace('_', ""));
+    }
+    Some(plain)
+}
+
+/// Integer square root, rounded down, by Newton's method.
+fn isqrt(n: U256) -> U256 {
+    if n.is_zero() {
+        return n;
+    }
+    // Start from a power of two at or above the root so the iterates only decrease.
+    let mut x = U256::ONE << n.bit_len().div_ceil(2);
+    loop {
+        let y = (x + n / x) >> 1;
+        if y >= x {
+            return x;
+        }
+        x = y;
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use stylus_sdk::testing::*;
+    use alloy_sol_types::{sol_data, SolError, SolEvent, SolType};
+    use stylus_sdk::{abi::Router, alloy_primitives::keccak256, testing::*};
+
+    const OWNER: Address = Address::new([0xaa; 20]);
 
     fn setup() -> (TestVM, DataValidator, Address) {
         let vm = TestVM::default();
-        let contract = DataValidator::from(&vm);
+        let mut contract = DataValidator::from(&vm);
+        contract
+            .constructor(
+                OWNER,
+                "-1000000000".to_string(),
+                "1000000000".to_string(),
+                28,
+            )
+            .unwrap();
         let user = Address::from([0x01; 20]);
         vm.set_sender(user);
         (vm, contract, user)
@@ -61,11 +1123,16 @@ mod test {
 
     #[test]
     fn test_submit_valid_decimal() {
-        let (_vm, mut contract, user) = setup();
+        let (vm, mut contract, user) = setup();
         let valid_decimal = "12345.6789".to_string();
 
         contract.submit_data(valid_decimal.clone()).unwrap();
         assert_eq!(contract.get_last_submission(user), valid_decimal);
+
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = logs.last().unwrap();
+        let event = DataSubmitted::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!((event.sender, event.value), (user, valid_decimal));
     }
 
     #[test]
@@ -74,7 +1141,705 @@ mod test {
         let invalid_string = "this-is-not-a-decimal".to_string();
 
         let result = contract.submit_data(invalid_string);
-        assert!(result.is_err());
+        assert!(matches!(
+            result,
+            Err(DataValidatorError::InvalidDecimal(InvalidDecimal { reason })) if reason == "Invalid decimal format"
+        ));
         assert_eq!(contract.get_last_submission(user), "");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_rejects_out_of_range_with_decodable_reason() {
+        let (_vm, mut contract, _user) = setup();
+        let err = contract
+            .submit_data("1000000000.01".to_string())
+            .unwrap_err();
+        let encoded: Vec<u8> = err.into();
+        assert_eq!(encoded[..4], InvalidDecimal::SELECTOR);
+        let decoded = InvalidDecimal::abi_decode(&encoded, true).unwrap();
+        assert_eq!(decoded.reason, "Decimal value too large");
+
+        assert!(contract.submit_data("-1000000000.01".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_history_is_kept_per_sender() {
+        let (vm, mut contract, alice) = setup();
+        let bob = Address::from([0x02; 20]);
+        contract.submit_data("1.5".to_string()).unwrap();
+        contract.submit_data("2.5".to_string()).unwrap();
+        assert!(contract.submit_data("nope".to_string()).is_err());
+        vm.set_sender(bob);
+        contract.submit_data("-7".to_string()).unwrap();
+
+        assert_eq!(contract.submission_count(alice), U256::from(2));
+        assert_eq!(contract.get_submission(alice, U256::ZERO).unwrap(), "1.5");
+        assert_eq!(
+            contract.get_submission(alice, U256::from(1)).unwrap(),
+            "2.5"
+        );
+        assert_eq!(contract.get_last_submission(alice), "2.5");
+        assert_eq!(contract.submission_count(bob), U256::from(1));
+        assert_eq!(contract.get_last_submission(bob), "-7");
+    }
+
+    #[test]
+    fn test_owner_sets_feed_metadata() {
+        let (vm, mut contract, _user) = setup();
+        assert_eq!(contract.feed_metadata(), (String::new(), 0, String::new()));
+        let set = |contract: &mut DataValidator, description: &str, decimals, unit: &str| {
+            contract.set_feed_metadata(description.to_string(), decimals, unit.to_string())
+        };
+        assert!(matches!(
+            set(&mut contract, "ETH / USD", 2, "USD"),
+            Err(DataValidatorError::NotOwner(_))
+        ));
+
+        vm.set_sender(OWNER);
+        set(&mut contract, "ETH / USD", 2, "USD").unwrap();
+        assert_eq!(
+            contract.feed_metadata(),
+            ("ETH / USD".to_string(), 2, "USD".to_string())
+        );
+        let logs = vm.get_emitted_logs();
+        assert_eq!(
+            logs.last().unwrap().0[0],
+            FeedMetadataUpdated::SIGNATURE_HASH
+        );
+
+        let long = "x".repeat(MAX_DESCRIPTION_BYTES + 1);
+        for (description, decimals, unit) in [
+            (long.as_str(), 2, "USD"),
+            ("ETH / USD", MAX_SCALE + 1, "USD"),
+            ("ETH / USD", 2, "US dollars per ether"),
+        ] {
+            assert!(matches!(
+                set(&mut contract, description, decimals, unit),
+                Err(DataValidatorError::InvalidConfig(_))
+            ));
+        }
+        assert_eq!(contract.feed_metadata().1, 2);
+    }
+
+    #[test]
+    fn test_missing_submission_index() {
+        let (_vm, mut contract, user) = setup();
+        assert!(matches!(
+            contract.get_submission(user, U256::ZERO),
+            Err(DataValidatorError::SubmissionNotFound(_))
+        ));
+        contract.submit_data("3".to_string()).unwrap();
+        assert!(contract.get_submission(user, U256::from(1)).is_err());
+        assert!(contract.get_submission(user, U256::MAX).is_err());
+    }
+
+    #[test]
+    fn test_owner_configures_bounds_and_precision() {
+        let (vm, mut contract, _user) = setup();
+        assert_eq!(
+            contract.get_validation_config(),
+            ("-1000000000".to_string(), "1000000000".to_string(), 28)
+        );
+        assert!(matches!(
+            contract.set_validation_config("0".to_string(), "100".to_string(), 2),
+            Err(DataValidatorError::NotOwner(_))
+        ));
+
+        vm.set_sender(OWNER);
+        contract
+            .set_validation_config("0".to_string(), "100".to_string(), 2)
+            .unwrap();
+        contract.submit_data("99.99".to_string()).unwrap();
+        contract.submit_data("100.00".to_string()).unwrap();
+        for (value, reason) in [
+            ("100.01", "Decimal value too large"),
+            ("-0.5", "Decimal value too small"),
+            ("1.234", "Too many decimal places"),
+        ] {
+            assert!(matches!(
+                contract.submit_data(value.to_string()),
+                Err(DataValidatorError::InvalidDecimal(InvalidDecimal { reason: r })) if r == reason
+            ));
+        }
+    }
+
+    #[test]
+    fn test_rejects_inconsistent_config() {
+        let (vm, mut contract, _user) = setup();
+        vm.set_sender(OWNER);
+        assert!(matches!(
+            contract.set_validation_config("5".to_string(), "1".to_string(), 2),
+            Err(DataValidatorError::InvalidConfig(_))
+        ));
+        assert!(contract
+            .set_validation_config("x".to_string(), "1".to_string(), 2)
+            .is_err());
+        assert!(contract
+            .set_validation_config("0".to_string(), "1".to_string(), 29)
+            .is_err());
+        assert_eq!(contract.get_validation_config().2, 28);
+    }
+
+    /// ABI-encoded constructor arguments, as the deployment transaction carries them.
+    fn deploy_args(owner: Address, min: &str, max: &str, places: u8) -> Vec<u8> {
+        type Args = (
+            sol_data::Address,
+            sol_data::String,
+            sol_data::String,
+            sol_data::Uint<8>,
+        );
+        Args::abi_encode_params(&(owner, min.to_string(), max.to_string(), places))
+    }
+
+    #[test]
+    fn test_constructor_validates_bounds_and_runs_once() {
+        let vm = TestVM::default();
+        let mut contract = DataValidator::from(&vm);
+        assert!(matches!(
+            contract.constructor(OWNER, "5".to_string(), "1".to_string(), 2),
+            Err(DataValidatorError::InvalidConfig(_))
+        ));
+
+        let deploy = <DataValidator as Router<DataValidator>>::constructor;
+        let init = deploy_args(OWNER, "0", "100", 2);
+        assert!(deploy(&mut contract, &init).unwrap().is_ok());
+        assert_eq!(
+            contract.get_validation_config(),
+            ("0".to_string(), "100".to_string(), 2)
+        );
+
+        let attacker = Address::from([0x66; 20]);
+        let reinit = deploy_args(attacker, "-1", "1", 0);
+        assert!(deploy(&mut contract, &reinit).unwrap().is_err());
+        assert_eq!(contract.owner(), OWNER);
+        assert_eq!(contract.get_validation_config().2, 2);
+    }
+
+    #[test]
+    fn test_aggregates_in_fixed_point() {
+        let (vm, mut contract, _user) = setup();
+        let scaled = |units: i64, micros: i64| {
+            I256::try_from(units * 1_000_000 + micros).unwrap() * I256::exp10(12)
+        };
+        assert_eq!(contract.running_average(), (I256::ZERO, 18));
+
+        contract.submit_data("2.5".to_string()).unwrap();
+        vm.set_sender(Address::from([0x02; 20]));
+        contract.submit_data("-1.25".to_string()).unwrap();
+        contract.submit_data("10".to_string()).unwrap();
+        assert!(contract.submit_data("bad".to_string()).is_err());
+
+        assert_eq!(contract.aggregate_count(), U256::from(3));
+        assert_eq!(contract.aggregate_sum(), (scaled(11, 250_000), 18));
+        assert_eq!(contract.aggregate_min(), (scaled(-1, -250_000), 18));
+        assert_eq!(contract.aggregate_max(), (scaled(10, 0), 18));
+        assert_eq!(contract.running_average(), (scaled(3, 750_000), 18));
+    }
+
+    #[test]
+    fn test_round_history_by_round_id() {
+        let (vm, mut contract, user) = setup();
+        assert!(matches!(
+            contract.latest_round_data(),
+            Err(DataValidatorError::RoundNotFound(RoundNotFound { roundId })) if roundId.is_zero()
+        ));
+
+        vm.set_block_timestamp(100);
+        contract.submit_data("2.5".to_string()).unwrap();
+        let other = Address::from([0x02; 20]);
+        vm.set_sender(other);
+        vm.set_block_timestamp(200);
+        contract.submit_batch("-1\nbad\n7".to_string()).unwrap();
+
+        let two_and_a_half = I256::try_from(25).unwrap() * I256::exp10(17);
+        let first = U80::from(1);
+        assert_eq!(
+            contract.get_round_data(first).unwrap(),
+            (
+                first,
+                two_and_a_half,
+                U256::from(100),
+                U256::from(100),
+                first
+            )
+        );
+        assert_eq!(contract.round_submitter(first).unwrap(), user);
+        let (round_id, answer, _, updated_at, _) = contract.latest_round_data().unwrap();
+        assert_eq!(round_id, U80::from(3));
+        assert_eq!(answer, I256::try_from(7).unwrap() * I256::exp10(18));
+        assert_eq!(updated_at, U256::from(200));
+        assert_eq!(contract.round_submitter(U80::from(2)).unwrap(), other);
+
+        for missing in [0u64, 4] {
+            assert!(matches!(
+                contract.get_round_data(U80::from(missing)),
+                Err(DataValidatorError::RoundNotFound(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_running_std_dev() {
+        let (_vm, mut contract, _user) = setup();
+        assert_eq!(contract.running_std_dev(), (I256::ZERO, 18));
+        for value in ["2", "4", "4", "4", "5", "5", "7", "9"] {
+            contract.submit_data(value.to_string()).unwrap();
+        }
+        // Mean 5, population standard deviation 2, up to fixed-point truncation.
+        let (std_dev, scale) = contract.running_std_dev();
+        let two = I256::try_from(2).unwrap() * I256::exp10(18);
+        assert_eq!(scale, 18);
+        assert!((std_dev - two).abs() < I256::exp10(6), "{std_dev}");
+        assert_eq!(isqrt(U256::from(99)), U256::from(9));
+        assert_eq!(isqrt(U256::MAX), U256::from(u128::MAX));
+    }
+
+    #[test]
+    fn test_anomalies_are_rejected_by_z_score() {
+        let (vm, mut contract, user) = setup();
+        assert!(matches!(
+            contract.set_anomaly_threshold(2),
+            Err(DataValidatorError::NotOwner(_))
+        ));
+        vm.set_sender(OWNER);
+        contract.set_anomaly_threshold(2).unwrap();
+        assert_eq!(contract.anomaly_threshold(), 2);
+        vm.set_sender(user);
+
+        // Nothing is rejected until enough samples exist.
+        for value in ["9", "11", "9", "11"] {
+            contract.submit_data(value.to_string()).unwrap();
+        }
+        contract.submit_data("10".to_string()).unwrap();
+        let err = contract.submit_data("14".to_string()).unwrap_err();
+        let DataValidatorError::AnomalousValue(anomaly) = err else {
+            panic!("unexpected {err:?}");
+        };
+        assert_eq!(anomaly.mean, I256::try_from(10).unwrap() * I256::exp10(18));
+        contract.submit_data("11.5".to_string()).unwrap();
+        assert_eq!(contract.aggregate_count(), U256::from(6));
+
+        let (stored, failed) = contract.submit_batch("-50\n10".to_string()).unwrap();
+        assert_eq!((stored, failed), (U256::from(1), vec![U256::ZERO]));
+        let logs = vm.get_emitted_logs();
+        let anomaly = logs
+            .iter()
+            .find(|(topics, _)| topics[0] == AnomalyDetected::SIGNATURE_HASH)
+            .unwrap();
+        let event = AnomalyDetected::decode_raw_log(anomaly.0.iter().copied(), &anomaly.1, true)
+            .unwrap();
+        assert_eq!((event.sender, event.value.as_str()), (user, "-50"));
+
+        vm.set_sender(OWNER);
+        contract.set_anomaly_threshold(0).unwrap();
+        contract.submit_data("-50".to_string()).unwrap();
+    }
+
+    #[test]
+    fn test_scaling_truncates_beyond_aggregate_scale() {
+        let tiny = Decimal::from_str("0.0000000000000000019").unwrap();
+        assert_eq!(to_scaled(tiny), I256::ONE);
+        assert_eq!(to_scaled(-tiny), I256::MINUS_ONE);
+        assert_eq!(
+            to_scaled(Decimal::from_str("-3").unwrap()),
+            I256::try_from(-3).unwrap() * I256::exp10(18)
+        );
+    }
+
+    #[test]
+    fn test_submit_batch_reports_failed_rows() {
+        let (vm, mut contract, user) = setup();
+        let csv = "1.5\r\nabc\n-2.25\n\n1000000000.01\n3\n".to_string();
+        let (stored, failed) = contract.submit_batch(csv).unwrap();
+        assert_eq!(stored, U256::from(3));
+        assert_eq!(failed, vec![U256::from(1), U256::from(3), U256::from(4)]);
+
+        assert_eq!(contract.submission_count(user), U256::from(3));
+        assert_eq!(contract.get_submission(user, U256::ZERO).unwrap(), "1.5");
+        assert_eq!(contract.get_last_submission(user), "3");
+        assert_eq!(contract.aggregate_count(), U256::from(3));
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.len(), 4);
+        assert_eq!(logs[3].0[0], DataSubmitted::SIGNATURE_HASH);
+    }
+
+    #[test]
+    fn test_submit_batch_limits() {
+        let (_vm, mut contract, user) = setup();
+        let reason = |result: Result<(U256, Vec<U256>), DataValidatorError>| match result {
+            Err(DataValidatorError::InvalidBatch(InvalidBatch { reason })) => reason,
+            other => panic!("unexpected {other:?}"),
+        };
+        assert_eq!(reason(contract.submit_batch(String::new())), "Empty batch");
+        let too_many = "1\n".repeat(MAX_BATCH_ROWS + 1);
+        assert_eq!(reason(contract.submit_batch(too_many)), "Too many rows");
+        let too_long = "1".repeat(MAX_BATCH_BYTES + 1);
+        assert_eq!(reason(contract.submit_batch(too_long)), "Batch too long");
+        assert_eq!(contract.submission_count(user), U256::ZERO);
+
+        let full = "1\n".repeat(MAX_BATCH_ROWS);
+        let (stored, failed) = contract.submit_batch(full).unwrap();
+        assert_eq!((stored, failed.len()), (U256::from(MAX_BATCH_ROWS), 0));
+    }
+
+    #[test]
+    fn test_number_formats_in_both_modes() {
+        let (vm, mut contract, user) = setup();
+        const INVALID: &str = "Invalid decimal format";
+        const SEPARATORS: &str = "Digit separators not allowed";
+        const SCIENTIFIC: &str = "Scientific notation not allowed";
+        // (submitted, lenient result, strict result); `Ok` holds the stored string.
+        let cases = [
+            ("1500000.25", Ok("1500000.25"), Ok("1500000.25")),
+            ("1_500_000.25", Ok("1500000.25"), Err(SEPARATORS)),
+            ("1,500,000.25", Ok("1500000.25"), Err(SEPARATORS)),
+            ("-1_000.000_5", Ok("-1000.0005"), Err(SEPARATORS)),
+            ("1.5e6", Ok("1500000"), Err(SCIENTIFIC)),
+            ("1.5E6", Ok("1500000"), Err(SCIENTIFIC)),
+            ("-2.5e-3", Ok("-0.0025"), Err(SCIENTIFIC)),
+            ("1_500e+3", Ok("1500000"), Err(SCIENTIFIC)),
+            ("1__000", Err(INVALID), Err(INVALID)),
+            ("_1000", Err(INVALID), Err(INVALID)),
+            ("1,50", Err(INVALID), Err(INVALID)),
+            ("1,000_000", Err(INVALID), Err(INVALID)),
+            ("1e", Err(INVALID), Err(INVALID)),
+            ("1.5e6.1", Err(INVALID), Err(INVALID)),
+            ("1e10", Err("Decimal value too large"), Err(SCIENTIFIC)),
+        ];
+        let check = |contract: &mut DataValidator, value: &str, expected: Result<&str, &str>| {
+            match (contract.submit_data(value.to_string()), expected) {
+                (Ok(()), Ok(stored)) => assert_eq!(contract.get_last_submission(user), stored),
+                (Err(DataValidatorError::InvalidDecimal(err)), Err(reason)) => {
+                    assert_eq!(err.reason, reason, "{value}")
+                }
+                (result, _) => panic!("{value}: unexpected {result:?}"),
+            }
+        };
+        assert!(!contract.strict_mode());
+        for (value, lenient, _) in cases {
+            check(&mut contract, value, lenient);
+        }
+        let (stored, failed) = contract.submit_batch("1e3\n2,000\n".to_string()).unwrap();
+        assert_eq!((stored, failed), (U256::from(2), vec![]));
+        assert_eq!(contract.get_last_submission(user), "2000");
+
+        vm.set_sender(OWNER);
+        contract.set_strict_mode(true).unwrap();
+        assert!(contract.strict_mode());
+        vm.set_sender(user);
+        for (value, _, strict) in cases {
+            check(&mut contract, value, strict);
+        }
+    }
+
+    #[test]
+    fn test_submit_field_validates_per_key() {
+        let (vm, mut contract, user) = setup();
+        vm.set_sender(OWNER);
+        contract
+            .register_decimal_field("price".to_string(), "0".to_string(), "100000".to_string(), 2)
+            .unwrap();
+        contract.register_ticker_field("ticker".to_string(), 8).unwrap();
+        contract
+            .register_u64_field("timestamp".to_string(), 1_600_000_000, u64::MAX)
+            .unwrap();
+        assert_eq!(contract.field_keys(), ["price", "ticker", "timestamp"]);
+        assert_eq!(
+            contract.field_rule("timestamp".to_string()).unwrap(),
+            (FIELD_U64, "1600000000".to_string(), u64::MAX.to_string(), 0)
+        );
+
+        vm.set_sender(user);
+        vm.set_block_timestamp(1_700_000_000);
+        let cases = [
+            ("price", "3012.55", Ok("3012.55")),
+            ("price", "1_250.5", Ok("1250.5")),
+            ("price", "100000.01", Err("Decimal value too large")),
+            ("price", "1.234", Err("Too many decimal places")),
+            ("price", "-1", Err("Decimal value too small")),
+            ("ticker", "ETH2", Ok("ETH2")),
+            ("ticker", "eth", Err("Invalid ticker")),
+            ("ticker", "TOOLONGER", Err("Invalid ticker")),
+            ("timestamp", "1700000000", Ok("1700000000")),
+            ("timestamp", "1599999999", Err("Integer too small")),
+            ("timestamp", "18446744073709551616", Err("Integer too large")),
+            ("timestamp", "+1700000000", Err("Invalid integer")),
+        ];
+        for (key, value, expected) in cases {
+            let result = contract.submit_field(key.to_string(), value.to_string());
+            match expected {
+                Ok(stored) => {
+                    result.unwrap();
+                    let last = contract.field_value(key.to_string()).unwrap();
+                    assert_eq!(last, (stored.to_string(), user, 1_700_000_000));
+                }
+                Err(reason) => assert!(
+                    matches!(&result, Err(DataValidatorError::InvalidField(err))
+                        if err.field == key && err.reason == reason),
+                    "{key}={value}: {result:?}"
+                ),
+            }
+        }
+        // A rejected value leaves the last accepted one in place.
+        assert_eq!(contract.field_value("price".to_string()).unwrap().0, "1250.5");
+        assert!(matches!(
+            contract.submit_field("volume".to_string(), "1".to_string()),
+            Err(DataValidatorError::UnknownField(_))
+        ));
+        // Fields are kept apart from the plain submissions and their aggregates.
+        assert_eq!(contract.aggregate_count(), U256::ZERO);
+    }
+
+    #[test]
+    fn test_field_registration_rules() {
+        let (vm, mut contract, user) = setup();
+        assert!(matches!(
+            contract.register_ticker_field("ticker".to_string(), 8),
+            Err(DataValidatorError::NotOwner(_))
+        ));
+        vm.set_sender(OWNER);
+        let reason = |result: Result<(), DataValidatorError>| match result {
+            Err(DataValidatorError::InvalidConfig(InvalidConfig { reason })) => reason,
+            other => panic!("unexpected {other:?}"),
+        };
+        let ticker = |contract: &mut DataValidator, key: &str, max_length| {
+            contract.register_ticker_field(key.to_string(), max_length)
+        };
+        assert_eq!(reason(ticker(&mut contract, "Ticker", 8)), "Invalid field key");
+        assert_eq!(reason(ticker(&mut contract, "", 8)), "Invalid field key");
+        assert_eq!(reason(ticker(&mut contract, "ticker", 0)), "Invalid ticker length");
+        assert_eq!(
+            reason(contract.register_u64_field("ts".to_string(), 2, 1)),
+            "Minimum exceeds maximum"
+        );
+        assert_eq!(
+            reason(contract.register_decimal_field(
+                "price".to_string(),
+                "1".to_string(),
+                "x".to_string(),
+                2
+            )),
+            "Invalid maximum"
+        );
+
+        // Re-registering a key replaces its rule and clears its value, but keeps its place.
+        ticker(&mut contract, "asset", 8).unwrap();
+        contract.submit_field("asset".to_string(), "BTC".to_string()).unwrap();
+        contract.register_u64_field("asset".to_string(), 0, 10).unwrap();
+        assert_eq!(contract.field_keys(), ["asset"]);
+        assert_eq!(
+            contract.field_value("asset".to_string()).unwrap(),
+            (String::new(), Address::ZERO, 0)
+        );
+        vm.set_sender(user);
+        assert!(contract.submit_field("asset".to_string(), "BTC".to_string()).is_err());
+        contract.submit_field("asset".to_string(), "7".to_string()).unwrap();
+
+        vm.set_sender(OWNER);
+        for index in 1..MAX_FIELDS {
+            ticker(&mut contract, &format!("key_{index}"), 4).unwrap();
+        }
+        assert_eq!(reason(ticker(&mut contract, "one_more", 4)), "Too many fields");
+    }
+
+    #[test]
+    fn test_submit_json_stores_fields() {
+        let (_vm, mut contract, user) = setup();
+        contract
+            .submit_json(r#" { "symbol": "ETH", "price": "3012.55" } "#.to_string())
+            .unwrap();
+        assert_eq!(
+            contract.get_json_submission(user),
+            ("3012.55".to_string(), "ETH".to_string())
+        );
+        // JSON payloads do not count as plain submissions.
+        assert_eq!(contract.submission_count(user), U256::ZERO);
+    }
+
+    #[test]
+    fn test_submit_json_field_errors() {
+        let (_vm, mut contract, user) = setup();
+        let cases = [
+            (
+                r#"{"price": "abc", "symbol": "ETH"}"#,
+                "price",
+                "Invalid decimal format",
+            ),
+            (
+                r#"{"price": "1", "symbol": "eth"}"#,
+                "symbol",
+                "Invalid symbol",
+            ),
+            (r#"{"price": "1"}"#, "symbol", "Missing field"),
+            (
+                r#"{"price": "1", "price": "2", "symbol": "ETH"}"#,
+                "price",
+                "Duplicate field",
+            ),
+            (
+                r#"{"price": "1", "symbol": "ETH", "venue": "x"}"#,
+                "venue",
+                "Unknown field",
+            ),
+        ];
+        for (payload, field, reason) in cases {
+            let err = contract.submit_json(payload.to_string()).unwrap_err();
+            assert!(
+                matches!(&err, DataValidatorError::InvalidField(e) if e.field == field && e.reason == reason),
+                "{payload}: {err:?}"
+            );
+        }
+        assert_eq!(contract.get_json_submission(user).0, "");
+    }
+
+    #[test]
+    fn test_submit_json_rejects_malformed_payloads() {
+        let (_vm, mut contract, _user) = setup();
+        let mut position = |payload: &str| match contract.submit_json(payload.to_string()) {
+            Err(DataValidatorError::InvalidJson(e)) => e.position.to::<usize>(),
+            other => panic!("{payload}: {other:?}"),
+        };
+        assert_eq!(position(r#"["price"]"#), 0);
+        assert_eq!(position(r#"{"price": 1.5}"#), 10);
+        assert_eq!(position(r#"{"price": "1" "symbol": "A"}"#), 14);
+        assert_eq!(position(r#"{"price": "1"} x"#), 15);
+        assert_eq!(position(&"a".repeat(MAX_JSON_BYTES + 1)), MAX_JSON_BYTES);
+    }
+
+    const ORACLE: Address = Address::new([0x0c; 20]);
+
+    /// A well-formed signature with `s` below the malleability bound and `v = 28`.
+    fn signature(seed: u8) -> Vec<u8> {
+        let mut sig = vec![seed; 32];
+        sig.extend_from_slice(&[0x11; 32]);
+        sig.push(28);
+        sig
+    }
+
+    /// Makes the ecrecover precompile return `signer` for `sig` over `value` at `oracle`'s
+    /// current nonce.
+    fn mock_recover(
+        vm: &TestVM,
+        contract: &DataValidator,
+        oracle: Address,
+        value: &str,
+        sig: &[u8],
+        signer: Address,
+    ) {
+        let hash = contract.signed_message_hash(oracle, value.to_string());
+        let mut prefixed = b"\x19Ethereum Signed Message:\n32".to_vec();
+        prefixed.extend_from_slice(hash.as_slice());
+        let mut input = keccak256(&prefixed).to_vec();
+        input.extend_from_slice(&U256::from(sig[64]).to_be_bytes::<32>());
+        input.extend_from_slice(&sig[..64]);
+        vm.mock_static_call(ecdsa::ECRECOVER, input, Ok(signer.into_word().to_vec()));
+    }
+
+    #[test]
+    fn test_signed_submission_is_stored_for_oracle() {
+        let (vm, mut contract, relayer) = setup();
+        vm.set_sender(OWNER);
+        contract.set_oracle(ORACLE, true).unwrap();
+        vm.set_sender(relayer);
+
+        let sig = signature(0x22);
+        mock_recover(&vm, &contract, ORACLE, "1850.25", &sig, ORACLE);
+        contract
+            .submit_signed_data(ORACLE, "1850.25".to_string(), Bytes::from(sig.clone()))
+            .unwrap();
+        assert_eq!(contract.get_last_submission(ORACLE), "1850.25");
+        assert_eq!(contract.submission_count(relayer), U256::ZERO);
+        assert_eq!(contract.aggregate_count(), U256::from(1));
+        assert_eq!(contract.signed_nonce(ORACLE), U256::from(1));
+
+        // The nonce changed the digest, so replaying the signature recovers nobody.
+        assert!(matches!(
+            contract.submit_signed_data(ORACLE, "1850.25".to_string(), Bytes::from(sig)),
+            Err(DataValidatorError::InvalidSignature(_))
+        ));
+        assert_eq!(contract.submission_count(ORACLE), U256::from(1));
+    }
+
+    #[test]
+    fn test_oracles_keep_separate_nonces() {
+        let (vm, mut contract, _relayer) = setup();
+        let second = Address::new([0x0d; 20]);
+        vm.set_sender(OWNER);
+        contract.set_oracle(ORACLE, true).unwrap();
+        contract.set_oracle(second, true).unwrap();
+
+        // Both oracles sign at nonce zero; landing one leaves the other's signature valid.
+        let (first_sig, second_sig) = (signature(0x66), signature(0x77));
+        mock_recover(&vm, &contract, ORACLE, "10", &first_sig, ORACLE);
+        mock_recover(&vm, &contract, second, "11", &second_sig, second);
+        contract
+            .submit_signed_data(ORACLE, "10".to_string(), Bytes::from(first_sig))
+            .unwrap();
+        contract
+            .submit_signed_data(second, "11".to_string(), Bytes::from(second_sig.clone()))
+            .unwrap();
+        assert_eq!(contract.signed_nonce(ORACLE), U256::from(1));
+        assert_eq!(contract.signed_nonce(second), U256::from(1));
+
+        // A signature from one oracle cannot be relayed in another's name.
+        mock_recover(&vm, &contract, ORACLE, "12", &second_sig, second);
+        assert!(matches!(
+            contract.submit_signed_data(ORACLE, "12".to_string(), Bytes::from(second_sig)),
+            Err(DataValidatorError::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn test_signed_submission_requires_trusted_oracle() {
+        let (vm, mut contract, _relayer) = setup();
+        let sig = signature(0x33);
+        mock_recover(&vm, &contract, ORACLE, "7", &sig, ORACLE);
+        assert!(matches!(
+            contract.submit_signed_data(ORACLE, "7".to_string(), Bytes::from(sig.clone())),
+            Err(DataValidatorError::UntrustedSigner(UntrustedSigner { signer })) if signer == ORACLE
+        ));
+        assert!(matches!(
+            contract.set_oracle(ORACLE, true),
+            Err(DataValidatorError::NotOwner(_))
+        ));
+
+        vm.set_sender(OWNER);
+        contract.set_oracle(ORACLE, true).unwrap();
+        assert!(contract.is_oracle(ORACLE));
+        contract.set_oracle(ORACLE, false).unwrap();
+        assert!(contract
+            .submit_signed_data(ORACLE, "7".to_string(), Bytes::from(sig))
+            .is_err());
+        assert_eq!(contract.signed_nonce(ORACLE), U256::ZERO);
+    }
+
+    #[test]
+    fn test_signed_submission_rejects_bad_signatures_and_values() {
+        let (vm, mut contract, _relayer) = setup();
+        vm.set_sender(OWNER);
+        contract.set_oracle(ORACLE, true).unwrap();
+
+        let mut wrong_v = signature(0x44);
+        wrong_v[64] = 29;
+        let mut high_s = signature(0x44);
+        high_s[32..64].copy_from_slice(&[0xff; 32]);
+        for (sig, reason) in [
+            (vec![0u8; 64], "Signature must be 65 bytes"),
+            (wrong_v, "Invalid recovery id"),
+            (high_s, "Malleable signature"),
+        ] {
+            assert!(matches!(
+                contract.submit_signed_data(ORACLE, "1".to_string(), Bytes::from(sig)),
+                Err(DataValidatorError::InvalidSignature(InvalidSignature { reason: r })) if r == reason
+            ));
+        }
+
+        let sig = signature(0x55);
+        mock_recover(&vm, &contract, ORACLE, "1.5x", &sig, ORACLE);
+        assert!(matches!(
+            contract.submit_signed_data(ORACLE, "1.5x".to_string(), Bytes::from(sig)),
+            Err(DataValidatorError::InvalidDecimal(_))
+        ));
+        assert_eq!(contract.get_last_submission(ORACLE), "");
+    }
+}
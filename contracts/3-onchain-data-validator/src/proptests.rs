@@ -0,0 +1,114 @@
+//! Property tests: whatever strings are submitted in strict mode, only values
+//! that parse and satisfy the configured bounds and precision are ever stored,
+//! and the aggregates always describe exactly the stored values.
+
+use super::*;
+use proptest::prelude::*;
+use stylus_sdk::testing::*;
+
+const OWNER: Address = Address::new([0xaa; 20]);
+const MAX_DECIMAL_PLACES: u8 = 4;
+/// Submitters are drawn from this many addresses.
+const USERS: u8 = 3;
+
+fn user(index: u8) -> Address {
+    Address::repeat_byte(index + 1)
+}
+
+/// Mostly decimal-looking strings, some out of bounds or too precise, plus arbitrary printable text.
+fn arb_value() -> impl Strategy<Value = String> {
+    prop_oneof![
+        4 => "-?[0-9]{1,12}(\\.[0-9]{0,8})?",
+        1 => "[-+0-9.eE ]{0,12}",
+        1 => "\\PC{0,24}",
+    ]
+}
+
+/// A value that is one CSV row: non-empty, since a trailing empty row is dropped.
+fn arb_row() -> impl Strategy<Value = String> {
+    arb_value().prop_filter("one non-empty line", |v| {
+        !v.is_empty() && !v.contains(['\n', '\r'])
+    })
+}
+
+fn setup() -> (TestVM, DataValidator) {
+    let vm = TestVM::default();
+    let mut contract = DataValidator::from(&vm);
+    contract
+        .constructor(
+            OWNER,
+            "-1000000".to_string(),
+            "1000000".to_string(),
+            MAX_DECIMAL_PLACES,
+        )
+        .unwrap();
+    vm.set_sender(OWNER);
+    contract.set_strict_mode(true).unwrap();
+    (vm, contract)
+}
+
+/// Whether `value` satisfies the bounds and precision `setup` configures. Strict mode
+/// refuses the digit separators `Decimal::from_str` would skip.
+fn acceptable(value: &str) -> bool {
+    !value.contains('_')
+        && Decimal::from_str(value).is_ok_and(|decimal| {
+            decimal.abs() <= Decimal::from(1_000_000)
+                && decimal.scale() <= u32::from(MAX_DECIMAL_PLACES)
+        })
+}
+
+/// Every stored value is acceptable, and the aggregates match the stored values.
+fn check_storage(contract: &DataValidator) -> Result<(), TestCaseError> {
+    let mut stored = Vec::new();
+    for u in 0..USERS {
+        for index in 0..contract.submission_count(user(u)).to::<u64>() {
+            let value = contract.get_submission(user(u), U256::from(index)).unwrap();
+            prop_assert!(acceptable(&value), "stored unacceptable {:?}", value);
+            stored.push(to_scaled(Decimal::from_str(&value).unwrap()));
+        }
+    }
+    prop_assert_eq!(contract.aggregate_count(), U256::from(stored.len()));
+    let sum = stored.iter().fold(I256::ZERO, |sum, &value| sum + value);
+    prop_assert_eq!(contract.aggregate_sum().0, sum);
+    if let (Some(min), Some(max)) = (stored.iter().min(), stored.iter().max()) {
+        prop_assert_eq!(contract.aggregate_min().0, *min);
+        prop_assert_eq!(contract.aggregate_max().0, *max);
+    }
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn prop_only_valid_decimals_are_stored(
+        submissions in prop::collection::vec((0..USERS, arb_value()), 1..30),
+    ) {
+        let (vm, mut contract) = setup();
+        for (u, value) in submissions {
+            vm.set_sender(user(u));
+            let before = contract.submission_count(user(u));
+            let result = contract.submit_data(value.clone());
+            prop_assert_eq!(result.is_ok(), acceptable(&value), "value {:?}", value);
+            let expected = if result.is_ok() { before + U256::from(1) } else { before };
+            prop_assert_eq!(contract.submission_count(user(u)), expected);
+            check_storage(&contract)?;
+        }
+    }
+
+    #[test]
+    fn prop_batches_store_exactly_the_valid_rows(
+        rows in prop::collection::vec(arb_row(), 1..20),
+    ) {
+        let (vm, mut contract) = setup();
+        vm.set_sender(user(0));
+        let (stored, failed) = contract.submit_batch(rows.join("\n")).unwrap();
+        let expected_failed: Vec<U256> = rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| !acceptable(row))
+            .map(|(index, _)| U256::from(index))
+            .collect();
+        prop_assert_eq!(stored, U256::from(rows.len() - expected_failed.len()));
+        prop_assert_eq!(failed, expected_failed);
+        check_storage(&contract)?;
+    }
+}
@@ -0,0 +1,343 @@
+//!
+//! Stylus Payment Splitter
+//!
+//! Splits incoming ETH between payees in proportion to their shares. Each
+//! deposit made with `deposit(payees, shares)` carries its own split; plain
+//! transfers to the contract are split with the default payees and shares set
+//! at deployment. Amounts are credited to payees as deposits arrive and each
+//! payee pulls what they are owed with `release(account)`, so one payee that
+//! cannot receive ETH never holds up the others.
+//!
+//! Each payee is credited `amount * share / totalShares`, rounded down. The
+//! wei lost to rounding goes to the first payee in the list, so every deposit
+//! is credited in full and nothing is stranded in the contract.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{string::ToString, vec::Vec};
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    prelude::*,
+};
+
+/// Most payees a single split may name.
+const MAX_PAYEES: usize = 20;
+/// Keeps `amount * share` well clear of overflow for any realistic amount.
+const MAX_TOTAL_SHARES: u64 = u64::MAX;
+
+sol! {
+    event PaymentReceived(address indexed from, uint256 amount, uint256 payees);
+    event PaymentReleased(address indexed to, uint256 amount);
+
+    #[derive(Debug)]
+    error InvalidSplit(string reason);
+    #[derive(Debug)]
+    error ZeroDeposit();
+    #[derive(Debug)]
+    error NothingDue(address account);
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum SplitterError {
+    InvalidSplit(InvalidSplit),
+    ZeroDeposit(ZeroDeposit),
+    NothingDue(NothingDue),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct PaymentSplitter {
+        /// Split applied to plain ETH transfers.
+        address[] default_payees;
+        uint256[] default_shares;
+        /// Credited to each payee and not yet released.
+        mapping(address => uint256) pending;
+        mapping(address => uint256) released;
+        uint256 total_received;
+        uint256 total_released;
+    }
+}
+
+#[public]
+impl PaymentSplitter {
+    /// Sets the split used for plain ETH transfers.
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        payees: Vec<Address>,
+        shares: Vec<U256>,
+    ) -> Result<(), SplitterError> {
+        validate_split(&payees, &shares)?;
+        for (payee, share) in payees.into_iter().zip(shares) {
+            self.default_payees.push(payee);
+            self.default_shares.push(share);
+        }
+        Ok(())
+    }
+
+    /// Splits plain ETH transfers with the default payees and shares.
+    #[receive]
+    #[payable]
+    pub fn receive(&mut self) -> Result<(), Vec<u8>> {
+        let (payees, shares) = self.default_split();
+        self.credit(&payees, &shares)?;
+        Ok(())
+    }
+
+    /// Splits `msg.value` between `payees` in proportion to `shares`.
+    #[payable]
+    pub fn deposit(
+        &mut self,
+        payees: Vec<Address>,
+        shares: Vec<U256>,
+    ) -> Result<(), SplitterError> {
+        validate_split(&payees, &shares)?;
+        self.credit(&payees, &shares)
+    }
+
+    /// Sends `account` everything credited to it. Anyone may call this.
+    pub fn release(&mut self, account: Address) -> Result<U256, SplitterError> {
+        let amount = self.pending.get(account);
+        if amount.is_zero() {
+            return Err(SplitterError::NothingDue(NothingDue { account }));
+        }
+        // Clear the credit before sending so a re-entrant call finds nothing to take.
+        self.pending.setter(account).set(U256::ZERO);
+        if self.vm().transfer_eth(account, amount).is_err() {
+            return Err(SplitterError::TransferFailed(TransferFailed {
+                to: account,
+                amount,
+            }));
+        }
+        let released = self.released.get(account) + amount;
+        self.released.setter(account).set(released);
+        self.total_released.set(self.total_released.get() + amount);
+        log(
+            self.vm(),
+            PaymentReleased {
+                to: account,
+                amount,
+            },
+        );
+        Ok(amount)
+    }
+
+    /// Amounts credited to `account` and not yet released.
+    pub fn pending(&self, account: Address) -> U256 {
+        self.pending.get(account)
+    }
+
+    pub fn released(&self, account: Address) -> U256 {
+        self.released.get(account)
+    }
+
+    pub fn total_received(&self) -> U256 {
+        self.total_received.get()
+    }
+
+    pub fn total_released(&self) -> U256 {
+        self.total_released.get()
+    }
+
+    /// Returns `(payees, shares)` applied to plain ETH transfers.
+    pub fn default_split(&self) -> (Vec<Address>, Vec<U256>) {
+        let payees = (0..self.default_payees.len())
+            .filter_map(|index| self.default_payees.get(index))
+            .collect();
+        let shares = (0..self.default_shares.len())
+            .filter_map(|index| self.default_shares.get(index))
+            .collect();
+        (payees, shares)
+    }
+}
+
+impl PaymentSplitter {
+    /// Credits `msg.value` to `payees`; the rounding remainder goes to the first payee.
+    fn credit(&mut self, payees: &[Address], shares: &[U256]) -> Result<(), SplitterError> {
+        let amount = self.vm().msg_value();
+        if amount.is_zero() {
+            return Err(SplitterError::ZeroDeposit(ZeroDeposit {}));
+        }
+        let total_shares = shares.iter().fold(U256::ZERO, |sum, &share| sum + share);
+        let portions: Vec<U256> = shares
+            .iter()
+            .map(|&share| amount * share / total_shares)
+            .collect();
+        let remainder = amount - portions.iter().fold(U256::ZERO, |sum, &part| sum + part);
+        for (index, (&payee, portion)) in payees.iter().zip(portions).enumerate() {
+            let extra = if index == 0 { remainder } else { U256::ZERO };
+            let owed = self.pending.get(payee) + portion + extra;
+            self.pending.setter(payee).set(owed);
+        }
+        self.total_received.set(self.total_received.get() + amount);
+        log(
+            self.vm(),
+            PaymentReceived {
+                from: self.vm().msg_sender(),
+                amount,
+                payees: U256::from(payees.len()),
+            },
+        );
+        Ok(())
+    }
+}
+
+fn validate_split(payees: &[Address], shares: &[U256]) -> Result<(), SplitterError> {
+    let invalid = |reason: &str| {
+        SplitterError::InvalidSplit(InvalidSplit {
+            reason: reason.to_string(),
+        })
+    };
+    if payees.is_empty() || payees.len() > MAX_PAYEES {
+        return Err(invalid("Payee count out of range"));
+    }
+    if payees.len() != shares.len() {
+        return Err(invalid("Payees and shares length mismatch"));
+    }
+    for (index, payee) in payees.iter().enumerate() {
+        if payee.is_zero() {
+            return Err(invalid("Zero address payee"));
+        }
+        if payees[..index].contains(payee) {
+            return Err(invalid("Duplicate payee"));
+        }
+    }
+    if shares.iter().any(|share| share.is_zero()) {
+        return Err(invalid("Zero share"));
+    }
+    let total_shares = shares
+        .iter()
+        .try_fold(U256::ZERO, |sum, &share| sum.checked_add(share));
+    if total_shares.is_none_or(|total| total > U256::from(MAX_TOTAL_SHARES)) {
+        return Err(invalid("Total shares too large"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const ALICE: Address = Address::new([0x01; 20]);
+    const BOB: Address = Address::new([0x02; 20]);
+    const CAROL: Address = Address::new([0x03; 20]);
+    const DEPOSITOR: Address = Address::new([0xde; 20]);
+
+    /// Defaults to a 1:3 split between Alice and Bob.
+    fn setup() -> (TestVM, PaymentSplitter) {
+        let vm = TestVM::default();
+        let mut contract = PaymentSplitter::from(&vm);
+        contract
+            .constructor(vec![ALICE, BOB], vec![U256::from(1), U256::from(3)])
+            .unwrap();
+        (vm, contract)
+    }
+
+    fn fund(vm: &TestVM, amount: u64) {
+        vm.set_sender(DEPOSITOR);
+        vm.set_value(U256::from(amount));
+        let balance = vm.balance(vm.contract_address());
+        vm.set_balance(vm.contract_address(), balance + U256::from(amount));
+    }
+
+    #[test]
+    fn test_receive_uses_default_split() {
+        let (vm, mut contract) = setup();
+        fund(&vm, 1_000);
+        contract.receive().unwrap();
+        assert_eq!(contract.pending(ALICE), U256::from(250));
+        assert_eq!(contract.pending(BOB), U256::from(750));
+        assert_eq!(contract.total_received(), U256::from(1_000));
+    }
+
+    #[test]
+    fn test_tagged_deposit_rounds_to_first_payee() {
+        let (vm, mut contract) = setup();
+        fund(&vm, 100);
+        contract
+            .deposit(vec![CAROL, BOB, ALICE], vec![U256::from(1); 3])
+            .unwrap();
+        // 100 / 3 = 33 each, with the leftover wei credited to Carol.
+        assert_eq!(contract.pending(CAROL), U256::from(34));
+        assert_eq!(contract.pending(BOB), U256::from(33));
+        assert_eq!(contract.pending(ALICE), U256::from(33));
+
+        vm.set_value(U256::ZERO);
+        assert!(matches!(
+            contract.deposit(vec![ALICE], vec![U256::from(1)]),
+            Err(SplitterError::ZeroDeposit(_))
+        ));
+    }
+
+    #[test]
+    fn test_release_pays_each_payee_once() {
+        let (vm, mut contract) = setup();
+        fund(&vm, 1_000);
+        contract.receive().unwrap();
+        fund(&vm, 400);
+        contract.deposit(vec![ALICE], vec![U256::from(7)]).unwrap();
+
+        // Anyone can trigger a release; the funds go to the payee.
+        vm.set_sender(CAROL);
+        assert_eq!(contract.release(ALICE).unwrap(), U256::from(650));
+        assert_eq!(vm.balance(ALICE), U256::from(650));
+        assert_eq!(contract.released(ALICE), U256::from(650));
+        assert!(matches!(
+            contract.release(ALICE),
+            Err(SplitterError::NothingDue(_))
+        ));
+        contract.release(BOB).unwrap();
+        assert_eq!(contract.total_released(), U256::from(1_400));
+        assert_eq!(vm.balance(vm.contract_address()), U256::ZERO);
+    }
+
+    #[test]
+    fn test_rejects_invalid_splits() {
+        let (vm, mut contract) = setup();
+        fund(&vm, 10);
+        let cases = [
+            (vec![], vec![]),
+            (vec![ALICE, BOB], vec![U256::from(1)]),
+            (vec![Address::ZERO], vec![U256::from(1)]),
+            (vec![ALICE, ALICE], vec![U256::from(1); 2]),
+            (vec![ALICE], vec![U256::ZERO]),
+            (vec![ALICE, BOB], vec![U256::from(u64::MAX), U256::from(1)]),
+            (
+                vec![ALICE; MAX_PAYEES + 1],
+                vec![U256::from(1); MAX_PAYEES + 1],
+            ),
+        ];
+        for (payees, shares) in cases {
+            assert!(matches!(
+                contract.deposit(payees, shares),
+                Err(SplitterError::InvalidSplit(_))
+            ));
+        }
+        let mut fresh = PaymentSplitter::from(&vm);
+        assert!(fresh.constructor(vec![], vec![]).is_err());
+    }
+
+    #[test]
+    fn test_failed_release_surfaces_error() {
+        let (vm, mut contract) = setup();
+        fund(&vm, 1_000);
+        contract.receive().unwrap();
+        vm.set_balance(vm.contract_address(), U256::ZERO);
+        assert!(matches!(
+            contract.release(BOB),
+            Err(SplitterError::TransferFailed(TransferFailed { to, amount }))
+                if to == BOB && amount == U256::from(750)
+        ));
+    }
+}
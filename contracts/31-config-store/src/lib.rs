@@ -0,0 +1,403 @@
+//!
+//! Stylus Config Store
+//!
+//! A shared key-value store for runtime parameters. Anyone can create a
+//! namespace; its id is `keccak256(abi.encodePacked(owner, name))`, so two
+//! owners can both have a namespace called `"vending-machine"` without
+//! clashing. Within a namespace, `bytes32` keys map to arbitrary `bytes`
+//! values, which the owner and the writers the owner allows can set.
+//!
+//! Every write bumps the key's version and is recorded with its writer and
+//! timestamp. The last `HISTORY_SIZE` writes of each key are kept in a ring
+//! buffer, and the current value is simply the newest of them. Setting an empty
+//! value is how a key is cleared.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, B256, U256},
+    prelude::*,
+};
+
+/// Writes kept per key, including the current value.
+pub const HISTORY_SIZE: u64 = 8;
+const MAX_NAME_LENGTH: usize = 64;
+const MAX_VALUE_SIZE: usize = 1024;
+/// Most keys `get_many` reads in one call.
+const MAX_BATCH_SIZE: usize = 50;
+
+sol! {
+    event NamespaceCreated(bytes32 indexed namespace, address indexed owner, string name);
+    event WriterUpdated(bytes32 indexed namespace, address indexed writer, bool allowed);
+    event ValueSet(bytes32 indexed namespace, bytes32 indexed key, uint256 version, address writer);
+
+    #[derive(Debug)]
+    error InvalidName();
+    #[derive(Debug)]
+    error NamespaceExists(bytes32 namespace);
+    #[derive(Debug)]
+    error UnknownNamespace(bytes32 namespace);
+    #[derive(Debug)]
+    error NotNamespaceOwner(bytes32 namespace, address caller);
+    #[derive(Debug)]
+    error NotWriter(bytes32 namespace, address caller);
+    #[derive(Debug)]
+    error ValueTooLarge(uint256 size, uint256 max);
+    #[derive(Debug)]
+    error BatchTooLarge(uint256 size, uint256 max);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum ConfigStoreError {
+    InvalidName(InvalidName),
+    NamespaceExists(NamespaceExists),
+    UnknownNamespace(UnknownNamespace),
+    NotNamespaceOwner(NotNamespaceOwner),
+    NotWriter(NotWriter),
+    ValueTooLarge(ValueTooLarge),
+    BatchTooLarge(BatchTooLarge),
+}
+
+sol_storage! {
+    pub struct Change {
+        bytes value;
+        address writer;
+        uint256 timestamp;
+    }
+
+    pub struct Entry {
+        /// Number of writes so far; zero if the key was never set.
+        uint256 version;
+        /// Write `v` (counting from 1) lives at `(v - 1) % HISTORY_SIZE`.
+        mapping(uint256 => Change) history;
+    }
+
+    pub struct Namespace {
+        address owner;
+        mapping(address => bool) writers;
+        mapping(bytes32 => Entry) entries;
+    }
+
+    #[entrypoint]
+    pub struct ConfigStore {
+        mapping(bytes32 => Namespace) namespaces;
+    }
+}
+
+#[public]
+impl ConfigStore {
+    /// Creates the caller's namespace called `name`, returning its id.
+    pub fn create_namespace(&mut self, name: String) -> Result<B256, ConfigStoreError> {
+        if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+            return Err(ConfigStoreError::InvalidName(InvalidName {}));
+        }
+        let owner = self.vm().msg_sender();
+        let namespace = self.namespace_id(owner, name.clone());
+        if !self.namespaces.getter(namespace).owner.get().is_zero() {
+            return Err(ConfigStoreError::NamespaceExists(NamespaceExists {
+                namespace,
+            }));
+        }
+        self.namespaces.setter(namespace).owner.set(owner);
+        log(
+            self.vm(),
+            NamespaceCreated {
+                namespace,
+                owner,
+                name,
+            },
+        );
+        Ok(namespace)
+    }
+
+    /// Allows or disallows `writer` to set values in a namespace. Namespace owner only.
+    pub fn set_writer(
+        &mut self,
+        namespace: B256,
+        writer: Address,
+        allowed: bool,
+    ) -> Result<(), ConfigStoreError> {
+        let caller = self.vm().msg_sender();
+        if self.namespace_owner(namespace)? != caller {
+            return Err(ConfigStoreError::NotNamespaceOwner(NotNamespaceOwner {
+                namespace,
+                caller,
+            }));
+        }
+        self.namespaces
+            .setter(namespace)
+            .writers
+            .setter(writer)
+            .set(allowed);
+        log(
+            self.vm(),
+            WriterUpdated {
+                namespace,
+                writer,
+                allowed,
+            },
+        );
+        Ok(())
+    }
+
+    /// Sets `key` to `value`, returning the key's new version.
+    pub fn set(
+        &mut self,
+        namespace: B256,
+        key: B256,
+        value: Bytes,
+    ) -> Result<U256, ConfigStoreError> {
+        let writer = self.vm().msg_sender();
+        if !self.is_writer(namespace, writer)? {
+            return Err(ConfigStoreError::NotWriter(NotWriter {
+                namespace,
+                caller: writer,
+            }));
+        }
+        if value.len() > MAX_VALUE_SIZE {
+            return Err(ConfigStoreError::ValueTooLarge(ValueTooLarge {
+                size: U256::from(value.len()),
+                max: U256::from(MAX_VALUE_SIZE),
+            }));
+        }
+        let timestamp = U256::from(self.vm().block_timestamp());
+        let mut ns = self.namespaces.setter(namespace);
+        let mut entry = ns.entries.setter(key);
+        let version = entry.version.get() + U256::from(1);
+        entry.version.set(version);
+        let mut change = entry.history.setter(history_slot(version));
+        change.value.set_bytes(&value);
+        change.writer.set(writer);
+        change.timestamp.set(timestamp);
+        log(
+            self.vm(),
+            ValueSet {
+                namespace,
+                key,
+                version,
+                writer,
+            },
+        );
+        Ok(version)
+    }
+
+    /// Returns `(value, version)`; an empty value and version 0 if the key was never set.
+    pub fn get(&self, namespace: B256, key: B256) -> (Bytes, U256) {
+        let ns = self.namespaces.getter(namespace);
+        let entry = ns.entries.getter(key);
+        let version = entry.version.get();
+        if version.is_zero() {
+            return (Bytes::from(Vec::new()), version);
+        }
+        let value = entry
+            .history
+            .getter(history_slot(version))
+            .value
+            .get_bytes();
+        (Bytes::from(value), version)
+    }
+
+    /// Current values of `keys`, in order.
+    pub fn get_many(
+        &self,
+        namespace: B256,
+        keys: Vec<B256>,
+    ) -> Result<Vec<Bytes>, ConfigStoreError> {
+        if keys.len() > MAX_BATCH_SIZE {
+            return Err(ConfigStoreError::BatchTooLarge(BatchTooLarge {
+                size: U256::from(keys.len()),
+                max: U256::from(MAX_BATCH_SIZE),
+            }));
+        }
+        Ok(keys
+            .into_iter()
+            .map(|key| self.get(namespace, key).0)
+            .collect())
+    }
+
+    /// The key's last `HISTORY_SIZE` writes as `(value, writer, timestamp)`, newest first.
+    pub fn history(&self, namespace: B256, key: B256) -> Vec<(Bytes, Address, U256)> {
+        let ns = self.namespaces.getter(namespace);
+        let entry = ns.entries.getter(key);
+        let version = entry.version.get();
+        let kept = version.min(U256::from(HISTORY_SIZE)).to::<u64>();
+        (0..kept)
+            .map(|age| {
+                let change = entry
+                    .history
+                    .getter(history_slot(version - U256::from(age)));
+                (
+                    Bytes::from(change.value.get_bytes()),
+                    change.writer.get(),
+                    change.timestamp.get(),
+                )
+            })
+            .collect()
+    }
+
+    /// The id `create_namespace` gives `owner`'s namespace called `name`.
+    pub fn namespace_id(&self, owner: Address, name: String) -> B256 {
+        let mut preimage = Vec::with_capacity(20 + name.len());
+        preimage.extend_from_slice(owner.as_slice());
+        preimage.extend_from_slice(name.as_bytes());
+        self.vm().native_keccak256(&preimage)
+    }
+
+    pub fn namespace_owner(&self, namespace: B256) -> Result<Address, ConfigStoreError> {
+        let owner = self.namespaces.getter(namespace).owner.get();
+        if owner.is_zero() {
+            return Err(ConfigStoreError::UnknownNamespace(UnknownNamespace {
+                namespace,
+            }));
+        }
+        Ok(owner)
+    }
+
+    /// Whether `account` may set values in the namespace; always true for its owner.
+    pub fn is_writer(&self, namespace: B256, account: Address) -> Result<bool, ConfigStoreError> {
+        let owner = self.namespace_owner(namespace)?;
+        Ok(account == owner || self.namespaces.getter(namespace).writers.get(account))
+    }
+}
+
+/// Ring buffer slot holding write number `version`.
+fn history_slot(version: U256) -> U256 {
+    (version - U256::from(1)) % U256::from(HISTORY_SIZE)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::new([0x0a; 20]);
+    const WRITER: Address = Address::new([0x0b; 20]);
+    const OTHER: Address = Address::new([0x0c; 20]);
+    const KEY: B256 = B256::repeat_byte(0x11);
+
+    fn setup() -> (TestVM, ConfigStore, B256) {
+        let vm = TestVM::default();
+        let mut contract = ConfigStore::from(&vm);
+        vm.set_sender(OWNER);
+        let namespace = contract.create_namespace("vending".into()).unwrap();
+        (vm, contract, namespace)
+    }
+
+    #[test]
+    fn test_namespaces_are_per_owner() {
+        let (vm, mut contract, namespace) = setup();
+        assert_eq!(namespace, contract.namespace_id(OWNER, "vending".into()));
+        assert!(matches!(
+            contract.create_namespace("vending".into()),
+            Err(ConfigStoreError::NamespaceExists(_))
+        ));
+        assert!(contract.create_namespace(String::new()).is_err());
+
+        vm.set_sender(OTHER);
+        let theirs = contract.create_namespace("vending".into()).unwrap();
+        assert_ne!(theirs, namespace);
+        assert_eq!(contract.namespace_owner(theirs).unwrap(), OTHER);
+        assert!(matches!(
+            contract.namespace_owner(B256::ZERO),
+            Err(ConfigStoreError::UnknownNamespace(_))
+        ));
+    }
+
+    #[test]
+    fn test_writers_need_permission() {
+        let (vm, mut contract, namespace) = setup();
+        vm.set_sender(WRITER);
+        assert!(matches!(
+            contract.set(namespace, KEY, Bytes::from(vec![1])),
+            Err(ConfigStoreError::NotWriter(_))
+        ));
+        assert!(matches!(
+            contract.set_writer(namespace, WRITER, true),
+            Err(ConfigStoreError::NotNamespaceOwner(_))
+        ));
+
+        vm.set_sender(OWNER);
+        contract.set_writer(namespace, WRITER, true).unwrap();
+        vm.set_sender(WRITER);
+        assert_eq!(
+            contract.set(namespace, KEY, Bytes::from(vec![1])).unwrap(),
+            U256::from(1)
+        );
+        assert_eq!(
+            contract.get(namespace, KEY),
+            (Bytes::from(vec![1]), U256::from(1))
+        );
+
+        vm.set_sender(OWNER);
+        contract.set_writer(namespace, WRITER, false).unwrap();
+        vm.set_sender(WRITER);
+        assert!(contract.set(namespace, KEY, Bytes::from(vec![2])).is_err());
+        assert!(matches!(
+            contract.set(namespace, KEY, Bytes::from(vec![0; MAX_VALUE_SIZE + 1])),
+            Err(ConfigStoreError::NotWriter(_))
+        ));
+    }
+
+    #[test]
+    fn test_history_keeps_last_writes() {
+        let (vm, mut contract, namespace) = setup();
+        for version in 1..=10u8 {
+            vm.set_block_timestamp(u64::from(version) * 100);
+            contract
+                .set(namespace, KEY, Bytes::from(vec![version]))
+                .unwrap();
+        }
+        assert_eq!(
+            contract.get(namespace, KEY),
+            (Bytes::from(vec![10]), U256::from(10))
+        );
+
+        let history = contract.history(namespace, KEY);
+        assert_eq!(history.len(), HISTORY_SIZE as usize);
+        assert_eq!(
+            history[0],
+            (Bytes::from(vec![10]), OWNER, U256::from(1_000))
+        );
+        assert_eq!(history[7].0, Bytes::from(vec![3]));
+        assert!(contract.history(namespace, B256::ZERO).is_empty());
+    }
+
+    #[test]
+    fn test_batch_reads() {
+        let (_vm, mut contract, namespace) = setup();
+        let other_key = B256::repeat_byte(0x22);
+        contract
+            .set(namespace, KEY, Bytes::from(vec![0xaa]))
+            .unwrap();
+        contract
+            .set(namespace, other_key, Bytes::from(vec![0xbb, 0xcc]))
+            .unwrap();
+        assert_eq!(
+            contract
+                .get_many(namespace, vec![other_key, B256::ZERO, KEY])
+                .unwrap(),
+            vec![
+                Bytes::from(vec![0xbb, 0xcc]),
+                Bytes::from(Vec::new()),
+                Bytes::from(vec![0xaa])
+            ]
+        );
+        assert!(matches!(
+            contract.get_many(namespace, vec![KEY; MAX_BATCH_SIZE + 1]),
+            Err(ConfigStoreError::BatchTooLarge(_))
+        ));
+        assert!(matches!(
+            contract.set(namespace, KEY, Bytes::from(vec![0; MAX_VALUE_SIZE + 1])),
+            Err(ConfigStoreError::ValueTooLarge(_))
+        ));
+    }
+}
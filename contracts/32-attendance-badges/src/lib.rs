@@ -0,0 +1,544 @@
+//!
+//! Stylus Attendance Badges
+//!
+//! Proof-of-attendance badges in the style of POAP. An organizer creates an
+//! event with a claim window and an optional cap on the number of badges.
+//! Attendees claim one badge per event in either of two ways, chosen by the
+//! organizer when creating the event:
+//!
+//! - secret: the organizer shares a code at the venue and stores only
+//!   `keccak256(code)`. Anyone who learns the code can claim, and a pending
+//!   claim reveals it to the mempool, so this suits casual events;
+//! - signature: the organizer's signer signs `claim_hash(eventId, attendee)`
+//!   for each attendee, which can only be used by that attendee.
+//!
+//! Badges are numbered tokens with an owner, like the `erc721-nft` example,
+//! but there is no way to transfer them: `locked` is always true, as in
+//! ERC-5192.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use alloy_sol_types::sol;
+use common::{ecdsa, paging::page};
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, B256, U256},
+    prelude::*,
+};
+
+const MAX_NAME_LENGTH: usize = 64;
+/// Largest page `attendees` returns.
+const MAX_PAGE_SIZE: u64 = 100;
+
+sol! {
+    event EventCreated(uint256 indexed eventId, address indexed organizer, string name);
+    event BadgeClaimed(uint256 indexed eventId, address indexed attendee, uint256 indexed tokenId);
+
+    #[derive(Debug)]
+    error InvalidEvent(string reason);
+    #[derive(Debug)]
+    error UnknownEvent(uint256 eventId);
+    #[derive(Debug)]
+    error ClaimWindowClosed(uint256 start, uint256 end);
+    #[derive(Debug)]
+    error AlreadyClaimed(uint256 eventId, address attendee);
+    #[derive(Debug)]
+    error SoldOut(uint256 eventId);
+    #[derive(Debug)]
+    error ClaimMethodDisabled(uint256 eventId);
+    #[derive(Debug)]
+    error WrongSecret();
+    #[derive(Debug)]
+    error InvalidSignature(string reason);
+    #[derive(Debug)]
+    error UntrustedSigner(address signer);
+    #[derive(Debug)]
+    error UnknownBadge(uint256 tokenId);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum BadgeError {
+    InvalidEvent(InvalidEvent),
+    UnknownEvent(UnknownEvent),
+    ClaimWindowClosed(ClaimWindowClosed),
+    AlreadyClaimed(AlreadyClaimed),
+    SoldOut(SoldOut),
+    ClaimMethodDisabled(ClaimMethodDisabled),
+    WrongSecret(WrongSecret),
+    InvalidSignature(InvalidSignature),
+    UntrustedSigner(UntrustedSigner),
+    UnknownBadge(UnknownBadge),
+}
+
+impl BadgeError {
+    fn invalid(reason: &str) -> Self {
+        BadgeError::InvalidEvent(InvalidEvent {
+            reason: reason.to_string(),
+        })
+    }
+
+    fn signature(reason: &str) -> Self {
+        BadgeError::InvalidSignature(InvalidSignature {
+            reason: reason.to_string(),
+        })
+    }
+}
+
+sol_storage! {
+    pub struct Event {
+        address organizer;
+        string name;
+        /// Claims are accepted from `start` until just before `end`.
+        uint256 start;
+        uint256 end;
+        /// `keccak256(code)` for secret claims; zero if disabled.
+        bytes32 secret_hash;
+        /// Address whose signatures authorize claims; zero if disabled.
+        address signer;
+        /// Most badges the event will issue; zero for no limit.
+        uint256 max_supply;
+        address[] attendees;
+        /// Each attendee's badge; zero if they have not claimed.
+        mapping(address => uint256) badge_of;
+    }
+
+    #[entrypoint]
+    pub struct AttendanceBadges {
+        mapping(uint256 => Event) events;
+        uint256 event_count;
+        /// Badges are numbered from 1.
+        uint256 badge_count;
+        mapping(uint256 => address) owners;
+        mapping(uint256 => uint256) badge_events;
+        mapping(address => uint256) balances;
+    }
+}
+
+#[public]
+impl AttendanceBadges {
+    /// Creates an event claimable between `start` and `end`, returning its id.
+    ///
+    /// At least one of `secret_hash` and `signer` must be set.
+    pub fn create_event(
+        &mut self,
+        name: String,
+        start: u64,
+        end: u64,
+        secret_hash: B256,
+        signer: Address,
+        max_supply: U256,
+    ) -> Result<U256, BadgeError> {
+        if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+            return Err(BadgeError::invalid("Name length out of range"));
+        }
+        if start >= end || end <= self.vm().block_timestamp() {
+            return Err(BadgeError::invalid("Invalid claim window"));
+        }
+        if secret_hash.is_zero() && signer.is_zero() {
+            return Err(BadgeError::invalid("No claim method"));
+        }
+        let organizer = self.vm().msg_sender();
+        let event_id = self.event_count.get();
+        self.event_count.set(event_id + U256::from(1));
+        let mut event = self.events.setter(event_id);
+        event.organizer.set(organizer);
+        event.name.set_str(&name);
+        event.start.set(U256::from(start));
+        event.end.set(U256::from(end));
+        event.secret_hash.set(secret_hash);
+        event.signer.set(signer);
+        event.max_supply.set(max_supply);
+        log(
+            self.vm(),
+            EventCreated {
+                eventId: event_id,
+                organizer,
+                name,
+            },
+        );
+        Ok(event_id)
+    }
+
+    /// Claims the caller's badge with the code shared by the organizer.
+    pub fn claim_with_secret(&mut self, event_id: U256, code: String) -> Result<U256, BadgeError> {
+        self.check_event(event_id)?;
+        let secret_hash = self.events.getter(event_id).secret_hash.get();
+        if secret_hash.is_zero() {
+            return Err(BadgeError::ClaimMethodDisabled(ClaimMethodDisabled {
+                eventId: event_id,
+            }));
+        }
+        if self.vm().native_keccak256(code.as_bytes()) != secret_hash {
+            return Err(BadgeError::WrongSecret(WrongSecret {}));
+        }
+        let attendee = self.vm().msg_sender();
+        self.mint(event_id, attendee)
+    }
+
+    /// Claims the caller's badge with the event signer's signature over `claim_hash`.
+    pub fn claim_with_signature(
+        &mut self,
+        event_id: U256,
+        signature: Bytes,
+    ) -> Result<U256, BadgeError> {
+        self.check_event(event_id)?;
+        let expected = self.events.getter(event_id).signer.get();
+        if expected.is_zero() {
+            return Err(BadgeError::ClaimMethodDisabled(ClaimMethodDisabled {
+                eventId: event_id,
+            }));
+        }
+        let attendee = self.vm().msg_sender();
+        let digest = ecdsa::eip191_digest(self.vm(), self.claim_hash(event_id, attendee));
+        let signer = ecdsa::recover(self.vm(), digest, &signature)
+            .map_err(|err| BadgeError::signature(err.reason()))?;
+        if signer != expected {
+            return Err(BadgeError::UntrustedSigner(UntrustedSigner { signer }));
+        }
+        self.mint(event_id, attendee)
+    }
+
+    /// The hash the event signer signs to let `attendee` claim:
+    /// `keccak256(abi.encodePacked(address(this), eventId, attendee))`.
+    pub fn claim_hash(&self, event_id: U256, attendee: Address) -> B256 {
+        let mut message = Vec::with_capacity(20 + 32 + 20);
+        message.extend_from_slice(self.vm().contract_address().as_slice());
+        message.extend_from_slice(&event_id.to_be_bytes::<32>());
+        message.extend_from_slice(attendee.as_slice());
+        self.vm().native_keccak256(&message)
+    }
+
+    /// Returns `(organizer, name, start, end, max_supply, claimed)`.
+    pub fn event_info(
+        &self,
+        event_id: U256,
+    ) -> Result<(Address, String, U256, U256, U256, U256), BadgeError> {
+        self.check_event(event_id)?;
+        let event = self.events.getter(event_id);
+        Ok((
+            event.organizer.get(),
+            event.name.get_string(),
+            event.start.get(),
+            event.end.get(),
+            event.max_supply.get(),
+            U256::from(event.attendees.len()),
+        ))
+    }
+
+    /// A page of an event's attendees in claim order.
+    ///
+    /// Pages are capped at `MAX_PAGE_SIZE` attendees; an offset beyond the last claim gives
+    /// an empty page.
+    pub fn attendees(
+        &self,
+        event_id: U256,
+        offset: U256,
+        limit: U256,
+    ) -> Result<Vec<Address>, BadgeError> {
+        self.check_event(event_id)?;
+        let attendees = &self.events.getter(event_id).attendees;
+        Ok(page(attendees.len(), offset, limit, MAX_PAGE_SIZE)
+            .filter_map(|index| attendees.get(index))
+            .collect())
+    }
+
+    /// The badge `attendee` holds for an event; zero if they have not claimed.
+    pub fn badge_of(&self, event_id: U256, attendee: Address) -> U256 {
+        self.events.getter(event_id).badge_of.get(attendee)
+    }
+
+    pub fn owner_of(&self, token_id: U256) -> Result<Address, BadgeError> {
+        let owner = self.owners.get(token_id);
+        if owner.is_zero() {
+            return Err(BadgeError::UnknownBadge(UnknownBadge { tokenId: token_id }));
+        }
+        Ok(owner)
+    }
+
+    /// The event a badge was issued for.
+    pub fn event_of(&self, token_id: U256) -> Result<U256, BadgeError> {
+        self.owner_of(token_id)?;
+        Ok(self.badge_events.get(token_id))
+    }
+
+    pub fn balance_of(&self, owner: Address) -> U256 {
+        self.balances.get(owner)
+    }
+
+    /// Always true: badges cannot be transferred (ERC-5192).
+    pub fn locked(&self, token_id: U256) -> Result<bool, BadgeError> {
+        self.owner_of(token_id)?;
+        Ok(true)
+    }
+
+    pub fn event_count(&self) -> U256 {
+        self.event_count.get()
+    }
+
+    pub fn total_supply(&self) -> U256 {
+        self.badge_count.get()
+    }
+}
+
+impl AttendanceBadges {
+    fn check_event(&self, event_id: U256) -> Result<(), BadgeError> {
+        if event_id >= self.event_count.get() {
+            return Err(BadgeError::UnknownEvent(UnknownEvent { eventId: event_id }));
+        }
+        Ok(())
+    }
+
+    /// Issues `attendee` a badge for the event after checking the window, cap and duplicates.
+    fn mint(&mut self, event_id: U256, attendee: Address) -> Result<U256, BadgeError> {
+        let now = U256::from(self.vm().block_timestamp());
+        let event = self.events.getter(event_id);
+        let (start, end) = (event.start.get(), event.end.get());
+        if now < start || now >= end {
+            return Err(BadgeError::ClaimWindowClosed(ClaimWindowClosed {
+                start,
+                end,
+            }));
+        }
+        if !event.badge_of.get(attendee).is_zero() {
+            return Err(BadgeError::AlreadyClaimed(AlreadyClaimed {
+                eventId: event_id,
+                attendee,
+            }));
+        }
+        let max_supply = event.max_supply.get();
+        if !max_supply.is_zero() && U256::from(event.attendees.len()) >= max_supply {
+            return Err(BadgeError::SoldOut(SoldOut { eventId: event_id }));
+        }
+
+        let token_id = self.badge_count.get() + U256::from(1);
+        self.badge_count.set(token_id);
+        self.owners.setter(token_id).set(attendee);
+        self.badge_events.setter(token_id).set(event_id);
+        let balance = self.balances.get(attendee) + U256::from(1);
+        self.balances.setter(attendee).set(balance);
+        let mut event = self.events.setter(event_id);
+        event.attendees.push(attendee);
+        event.badge_of.setter(attendee).set(token_id);
+        log(
+            self.vm(),
+            BadgeClaimed {
+                eventId: event_id,
+                attendee,
+                tokenId: token_id,
+            },
+        );
+        Ok(token_id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::{alloy_primitives::keccak256, testing::*};
+
+    const ORGANIZER: Address = Address::new([0x0a; 20]);
+    const SIGNER: Address = Address::new([0x5e; 20]);
+    const ALICE: Address = Address::new([0x01; 20]);
+    const BOB: Address = Address::new([0x02; 20]);
+    const START: u64 = 1_000;
+    const END: u64 = 2_000;
+    const CODE: &str = "stylus-meetup";
+
+    fn setup() -> (TestVM, AttendanceBadges, U256) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(START);
+        let mut contract = AttendanceBadges::from(&vm);
+        vm.set_sender(ORGANIZER);
+        let event_id = contract
+            .create_event(
+                "Meetup".to_string(),
+                START,
+                END,
+                keccak256(CODE),
+                SIGNER,
+                U256::from(2),
+            )
+            .unwrap();
+        (vm, contract, event_id)
+    }
+
+    /// A well-formed signature with `s` below the malleability bound and `v = 28`.
+    fn signature(seed: u8) -> Vec<u8> {
+        let mut sig = vec![seed; 32];
+        sig.extend_from_slice(&[0x11; 32]);
+        sig.push(28);
+        sig
+    }
+
+    /// Makes the ecrecover precompile return `signer` for `sig` over `attendee`'s claim hash.
+    fn mock_recover(
+        vm: &TestVM,
+        contract: &AttendanceBadges,
+        event_id: U256,
+        attendee: Address,
+        sig: &[u8],
+        signer: Address,
+    ) {
+        let mut prefixed = b"\x19Ethereum Signed Message:\n32".to_vec();
+        prefixed.extend_from_slice(contract.claim_hash(event_id, attendee).as_slice());
+        let mut input = keccak256(&prefixed).to_vec();
+        input.extend_from_slice(&U256::from(sig[64]).to_be_bytes::<32>());
+        input.extend_from_slice(&sig[..64]);
+        vm.mock_static_call(ecdsa::ECRECOVER, input, Ok(signer.into_word().to_vec()));
+    }
+
+    #[test]
+    fn test_claim_with_secret() {
+        let (vm, mut contract, event_id) = setup();
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.claim_with_secret(event_id, "guess".to_string()),
+            Err(BadgeError::WrongSecret(_))
+        ));
+        let token_id = contract
+            .claim_with_secret(event_id, CODE.to_string())
+            .unwrap();
+        assert_eq!(contract.owner_of(token_id).unwrap(), ALICE);
+        assert_eq!(contract.event_of(token_id).unwrap(), event_id);
+        assert_eq!(contract.badge_of(event_id, ALICE), token_id);
+        assert_eq!(contract.balance_of(ALICE), U256::from(1));
+        assert!(contract.locked(token_id).unwrap());
+        assert!(matches!(
+            contract.claim_with_secret(event_id, CODE.to_string()),
+            Err(BadgeError::AlreadyClaimed(_))
+        ));
+    }
+
+    #[test]
+    fn test_claim_with_signature() {
+        let (vm, mut contract, event_id) = setup();
+        let sig = signature(0x22);
+        mock_recover(&vm, &contract, event_id, BOB, &sig, SIGNER);
+
+        // A signature for Bob is useless to anyone else.
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.claim_with_signature(event_id, Bytes::from(sig.clone())),
+            Err(BadgeError::InvalidSignature(_))
+        ));
+        vm.set_sender(BOB);
+        contract
+            .claim_with_signature(event_id, Bytes::from(sig))
+            .unwrap();
+        assert!(!contract.badge_of(event_id, BOB).is_zero());
+
+        let other = signature(0x33);
+        mock_recover(&vm, &contract, event_id, ALICE, &other, ALICE);
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.claim_with_signature(event_id, Bytes::from(other)),
+            Err(BadgeError::UntrustedSigner(_))
+        ));
+    }
+
+    #[test]
+    fn test_window_and_supply() {
+        let (vm, mut contract, event_id) = setup();
+        for attendee in [ALICE, BOB] {
+            vm.set_sender(attendee);
+            contract
+                .claim_with_secret(event_id, CODE.to_string())
+                .unwrap();
+        }
+        vm.set_sender(ORGANIZER);
+        assert!(matches!(
+            contract.claim_with_secret(event_id, CODE.to_string()),
+            Err(BadgeError::SoldOut(_))
+        ));
+        let (organizer, name, _, _, max_supply, claimed) = contract.event_info(event_id).unwrap();
+        assert_eq!((organizer, name.as_str()), (ORGANIZER, "Meetup"));
+        assert_eq!((max_supply, claimed), (U256::from(2), U256::from(2)));
+
+        let open = contract
+            .create_event(
+                "Open".to_string(),
+                START + 10,
+                END,
+                keccak256(CODE),
+                Address::ZERO,
+                U256::ZERO,
+            )
+            .unwrap();
+        assert!(matches!(
+            contract.claim_with_secret(open, CODE.to_string()),
+            Err(BadgeError::ClaimWindowClosed(_))
+        ));
+        assert!(matches!(
+            contract.claim_with_signature(open, Bytes::from(signature(1))),
+            Err(BadgeError::ClaimMethodDisabled(_))
+        ));
+        vm.set_block_timestamp(END);
+        assert!(contract.claim_with_secret(open, CODE.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_attendee_pagination() {
+        let (vm, mut contract, _) = setup();
+        vm.set_sender(ORGANIZER);
+        let event_id = contract
+            .create_event(
+                "Big".to_string(),
+                START,
+                END,
+                keccak256(CODE),
+                Address::ZERO,
+                U256::ZERO,
+            )
+            .unwrap();
+        let attendees: Vec<Address> = (1..=5).map(Address::with_last_byte).collect();
+        for &attendee in &attendees {
+            vm.set_sender(attendee);
+            contract
+                .claim_with_secret(event_id, CODE.to_string())
+                .unwrap();
+        }
+        let page = |offset: u64, limit: u64| {
+            contract
+                .attendees(event_id, U256::from(offset), U256::from(limit))
+                .unwrap()
+        };
+        assert_eq!(page(0, 2), attendees[..2]);
+        assert_eq!(page(3, 10), attendees[3..]);
+        assert!(page(9, 2).is_empty());
+        assert_eq!(contract.total_supply(), U256::from(5));
+        assert!(matches!(
+            contract.attendees(U256::from(9), U256::ZERO, U256::from(1)),
+            Err(BadgeError::UnknownEvent(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_invalid_events() {
+        let (_vm, mut contract, _) = setup();
+        let create = |contract: &mut AttendanceBadges, name: &str, start, end, secret, signer| {
+            contract.create_event(name.to_string(), start, end, secret, signer, U256::ZERO)
+        };
+        let secret = keccak256(CODE);
+        assert!(create(&mut contract, "", START, END, secret, SIGNER).is_err());
+        assert!(create(&mut contract, "x", END, START, secret, SIGNER).is_err());
+        assert!(create(&mut contract, "x", 0, START, secret, SIGNER).is_err());
+        assert!(matches!(
+            create(&mut contract, "x", START, END, B256::ZERO, Address::ZERO),
+            Err(BadgeError::InvalidEvent(_))
+        ));
+        assert!(matches!(
+            contract.owner_of(U256::from(1)),
+            Err(BadgeError::UnknownBadge(_))
+        ));
+    }
+}
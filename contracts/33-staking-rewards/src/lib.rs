@@ -0,0 +1,442 @@
+//!
+//! Stylus Staking Rewards
+//!
+//! Stake the `CUPCAKE` ERC-20 from the `erc20-token` example and earn reward
+//! tokens every second, using the accumulator from Synthetix's
+//! `StakingRewards`. The owner funds a reward period with
+//! `notify_reward_amount`, which pulls the rewards in and spreads them evenly
+//! over `duration` seconds. Each second's rewards are shared by stakers in
+//! proportion to their stake.
+//!
+//! Rather than touching every staker each second, the contract keeps
+//! `reward_per_token_stored`: the rewards one staked token has earned since
+//! deployment, in WAD fixed point (`1e18` = 1.0). A staker's earnings are
+//! `balance * (reward_per_token - paid)`, where `paid` is the value the
+//! accumulator had when the staker was last settled. Every stake, unstake
+//! and claim settles the caller first.
+//!
+//! Tokens move with cross-contract `transferFrom` and `transfer` calls; the
+//! staking and reward tokens may be the same contract.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloy_sol_types::sol;
+use common::token::{safe_transfer, safe_transfer_from};
+use stylus_sdk::{
+    alloy_primitives::{uint, Address, U256},
+    prelude::*,
+};
+
+/// `1.0` in WAD fixed point.
+const WAD: U256 = uint!(1_000_000_000_000_000_000_U256);
+
+sol! {
+    event Staked(address indexed user, uint256 amount);
+    event Unstaked(address indexed user, uint256 amount);
+    event RewardPaid(address indexed user, uint256 reward);
+    event RewardAdded(uint256 reward, uint256 duration);
+
+    #[derive(Debug)]
+    error InvalidConfig();
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error ZeroAmount();
+    #[derive(Debug)]
+    error InsufficientStake(uint256 available, uint256 requested);
+    #[derive(Debug)]
+    error RewardRateTooLow();
+    #[derive(Debug)]
+    error NothingToClaim();
+    #[derive(Debug)]
+    error TransferFailed(address token, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum StakingError {
+    InvalidConfig(InvalidConfig),
+    NotOwner(NotOwner),
+    ZeroAmount(ZeroAmount),
+    InsufficientStake(InsufficientStake),
+    RewardRateTooLow(RewardRateTooLow),
+    NothingToClaim(NothingToClaim),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct StakingRewards {
+        address owner;
+        address staking_token;
+        address reward_token;
+        /// Reward tokens paid out per second while the period runs.
+        uint256 reward_rate;
+        uint256 period_finish;
+        uint256 last_update_time;
+        /// Rewards earned per staked token so far, in WAD.
+        uint256 reward_per_token_stored;
+        /// `reward_per_token_stored` when each user was last settled.
+        mapping(address => uint256) user_reward_per_token_paid;
+        /// Rewards settled to each user and not yet claimed.
+        mapping(address => uint256) rewards;
+        mapping(address => uint256) balances;
+        uint256 total_staked;
+    }
+}
+
+#[public]
+impl StakingRewards {
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        owner: Address,
+        staking_token: Address,
+        reward_token: Address,
+    ) -> Result<(), StakingError> {
+        if owner.is_zero() || staking_token.is_zero() || reward_token.is_zero() {
+            return Err(StakingError::InvalidConfig(InvalidConfig {}));
+        }
+        self.owner.set(owner);
+        self.staking_token.set(staking_token);
+        self.reward_token.set(reward_token);
+        Ok(())
+    }
+
+    /// Pulls `amount` staking tokens from the caller, who must have approved this contract.
+    pub fn stake(&mut self, amount: U256) -> Result<(), StakingError> {
+        if amount.is_zero() {
+            return Err(StakingError::ZeroAmount(ZeroAmount {}));
+        }
+        let user = self.vm().msg_sender();
+        self.update_reward(user);
+        let token = self.staking_token.get();
+        let escrow = self.vm().contract_address();
+        self.pull(token, user, escrow, amount)?;
+        let balance = self.balances.get(user) + amount;
+        self.balances.setter(user).set(balance);
+        self.total_staked.set(self.total_staked.get() + amount);
+        log(self.vm(), Staked { user, amount });
+        Ok(())
+    }
+
+    /// Returns `amount` of the caller's staked tokens; rewards stay claimable.
+    pub fn unstake(&mut self, amount: U256) -> Result<(), StakingError> {
+        if amount.is_zero() {
+            return Err(StakingError::ZeroAmount(ZeroAmount {}));
+        }
+        let user = self.vm().msg_sender();
+        self.update_reward(user);
+        let available = self.balances.get(user);
+        if amount > available {
+            return Err(StakingError::InsufficientStake(InsufficientStake {
+                available,
+                requested: amount,
+            }));
+        }
+        self.balances.setter(user).set(available - amount);
+        self.total_staked.set(self.total_staked.get() - amount);
+        let token = self.staking_token.get();
+        self.push(token, user, amount)?;
+        log(self.vm(), Unstaked { user, amount });
+        Ok(())
+    }
+
+    /// Sends the caller all rewards earned so far.
+    pub fn claim(&mut self) -> Result<U256, StakingError> {
+        let user = self.vm().msg_sender();
+        self.update_reward(user);
+        let reward = self.rewards.get(user);
+        if reward.is_zero() {
+            return Err(StakingError::NothingToClaim(NothingToClaim {}));
+        }
+        self.rewards.setter(user).set(U256::ZERO);
+        let token = self.reward_token.get();
+        self.push(token, user, reward)?;
+        log(self.vm(), RewardPaid { user, reward });
+        Ok(reward)
+    }
+
+    /// Pulls `reward` tokens from the owner and pays them out over the next `duration` seconds.
+    ///
+    /// Rewards left over from a running period are rolled into the new one.
+    pub fn notify_reward_amount(
+        &mut self,
+        reward: U256,
+        duration: u64,
+    ) -> Result<(), StakingError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(StakingError::NotOwner(NotOwner { caller }));
+        }
+        if duration == 0 {
+            return Err(StakingError::InvalidConfig(InvalidConfig {}));
+        }
+        self.update_reward(Address::ZERO);
+
+        let now = U256::from(self.vm().block_timestamp());
+        let duration = U256::from(duration);
+        let period_finish = self.period_finish.get();
+        let leftover = if now < period_finish {
+            (period_finish - now) * self.reward_rate.get()
+        } else {
+            U256::ZERO
+        };
+        let rate = (reward + leftover) / duration;
+        if rate.is_zero() {
+            return Err(StakingError::RewardRateTooLow(RewardRateTooLow {}));
+        }
+        let token = self.reward_token.get();
+        let escrow = self.vm().contract_address();
+        self.pull(token, caller, escrow, reward)?;
+
+        self.reward_rate.set(rate);
+        self.last_update_time.set(now);
+        self.period_finish.set(now + duration);
+        log(self.vm(), RewardAdded { reward, duration });
+        Ok(())
+    }
+
+    /// Rewards earned per staked token so far, in WAD.
+    pub fn reward_per_token(&self) -> U256 {
+        let stored = self.reward_per_token_stored.get();
+        let total = self.total_staked.get();
+        if total.is_zero() {
+            return stored;
+        }
+        let elapsed = self.last_time_reward_applicable() - self.last_update_time.get();
+        stored + elapsed * self.reward_rate.get() * WAD / total
+    }
+
+    /// Rewards `account` could claim now.
+    pub fn earned(&self, account: Address) -> U256 {
+        let accrued = self.reward_per_token() - self.user_reward_per_token_paid.get(account);
+        self.balances.get(account) * accrued / WAD + self.rewards.get(account)
+    }
+
+    /// The current time, capped at the end of the reward period.
+    pub fn last_time_reward_applicable(&self) -> U256 {
+        U256::from(self.vm().block_timestamp()).min(self.period_finish.get())
+    }
+
+    pub fn balance_of(&self, account: Address) -> U256 {
+        self.balances.get(account)
+    }
+
+    pub fn total_staked(&self) -> U256 {
+        self.total_staked.get()
+    }
+
+    pub fn reward_rate(&self) -> U256 {
+        self.reward_rate.get()
+    }
+
+    pub fn period_finish(&self) -> U256 {
+        self.period_finish.get()
+    }
+}
+
+impl StakingRewards {
+    /// Brings the accumulator up to date and settles `account`'s rewards; zero settles nobody.
+    fn update_reward(&mut self, account: Address) {
+        let reward_per_token = self.reward_per_token();
+        self.reward_per_token_stored.set(reward_per_token);
+        let now = self.last_time_reward_applicable();
+        self.last_update_time.set(now);
+        if !account.is_zero() {
+            let earned = self.earned(account);
+            self.rewards.setter(account).set(earned);
+            self.user_reward_per_token_paid
+                .setter(account)
+                .set(reward_per_token);
+        }
+    }
+
+    fn pull(
+        &self,
+        token: Address,
+        from: Address,
+        to: Address,
+        amount: U256,
+    ) -> Result<(), StakingError> {
+        safe_transfer_from(self.vm(), token, from, to, amount)
+            .map_err(|_| StakingError::TransferFailed(TransferFailed { token, amount }))
+    }
+
+    fn push(&self, token: Address, to: Address, amount: U256) -> Result<(), StakingError> {
+        safe_transfer(self.vm(), token, to, amount)
+            .map_err(|_| StakingError::TransferFailed(TransferFailed { token, amount }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::SolCall;
+    use common::token::IERC20Transfers as IERC20;
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::new([0x0a; 20]);
+    const CUPCAKE: Address = Address::new([0xcc; 20]);
+    const ALICE: Address = Address::new([0x01; 20]);
+    const BOB: Address = Address::new([0x02; 20]);
+    const START: u64 = 1_000;
+    const DURATION: u64 = 100;
+
+    /// Stakes and pays rewards in CUPCAKE; unmocked token calls succeed.
+    fn setup() -> (TestVM, StakingRewards) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(START);
+        let mut contract = StakingRewards::from(&vm);
+        contract.constructor(OWNER, CUPCAKE, CUPCAKE).unwrap();
+        (vm, contract)
+    }
+
+    fn fund(vm: &TestVM, contract: &mut StakingRewards, reward: u64) {
+        vm.set_sender(OWNER);
+        contract
+            .notify_reward_amount(U256::from(reward), DURATION)
+            .unwrap();
+    }
+
+    fn stake(vm: &TestVM, contract: &mut StakingRewards, user: Address, amount: u64) {
+        vm.set_sender(user);
+        contract.stake(U256::from(amount)).unwrap();
+    }
+
+    #[test]
+    fn test_single_staker_earns_full_rate() {
+        let (vm, mut contract) = setup();
+        stake(&vm, &mut contract, ALICE, 500);
+        fund(&vm, &mut contract, 1_000);
+        assert_eq!(contract.reward_rate(), U256::from(10));
+
+        vm.set_block_timestamp(START + 30);
+        assert_eq!(contract.earned(ALICE), U256::from(300));
+        vm.set_sender(ALICE);
+        assert_eq!(contract.claim().unwrap(), U256::from(300));
+        assert_eq!(contract.earned(ALICE), U256::ZERO);
+        assert!(matches!(
+            contract.claim(),
+            Err(StakingError::NothingToClaim(_))
+        ));
+
+        // Nothing accrues after the period ends.
+        vm.set_block_timestamp(START + DURATION + 50);
+        assert_eq!(contract.earned(ALICE), U256::from(700));
+    }
+
+    #[test]
+    fn test_rewards_split_by_stake_and_time() {
+        let (vm, mut contract) = setup();
+        fund(&vm, &mut contract, 1_000);
+        stake(&vm, &mut contract, ALICE, 100);
+        vm.set_block_timestamp(START + 20);
+        stake(&vm, &mut contract, BOB, 300);
+        vm.set_block_timestamp(START + 60);
+
+        // Alice alone for 20s (200), then a quarter of 40s (100); Bob gets the rest.
+        assert_eq!(contract.earned(ALICE), U256::from(300));
+        assert_eq!(contract.earned(BOB), U256::from(300));
+
+        vm.set_sender(ALICE);
+        contract.unstake(U256::from(100)).unwrap();
+        vm.set_block_timestamp(START + DURATION);
+        assert_eq!(contract.earned(ALICE), U256::from(300));
+        // Bob alone earns 400 over his 300 tokens; the accumulator rounds down.
+        assert_eq!(contract.earned(BOB), U256::from(699));
+        assert_eq!(contract.total_staked(), U256::from(300));
+    }
+
+    #[test]
+    fn test_notify_rolls_over_leftover() {
+        let (vm, mut contract) = setup();
+        stake(&vm, &mut contract, ALICE, 1);
+        fund(&vm, &mut contract, 1_000);
+        vm.set_block_timestamp(START + 50);
+        // 500 left over plus 1_500 new over another 100s.
+        fund(&vm, &mut contract, 1_500);
+        assert_eq!(contract.reward_rate(), U256::from(20));
+        assert_eq!(contract.period_finish(), U256::from(START + 50 + DURATION));
+        vm.set_block_timestamp(START + 50 + DURATION);
+        assert_eq!(contract.earned(ALICE), U256::from(2_500));
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.notify_reward_amount(U256::from(1), DURATION),
+            Err(StakingError::NotOwner(_))
+        ));
+        vm.set_sender(OWNER);
+        assert!(matches!(
+            contract.notify_reward_amount(U256::from(DURATION - 1), DURATION),
+            Err(StakingError::RewardRateTooLow(_))
+        ));
+    }
+
+    #[test]
+    fn test_unstake_checks_and_failed_transfer() {
+        let (vm, mut contract) = setup();
+        stake(&vm, &mut contract, ALICE, 100);
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.unstake(U256::from(101)),
+            Err(StakingError::InsufficientStake(_))
+        ));
+        assert!(matches!(
+            contract.stake(U256::ZERO),
+            Err(StakingError::ZeroAmount(_))
+        ));
+
+        // A token that returns `false` is treated as a failure too.
+        let pull = IERC20::transferFromCall {
+            from: ALICE,
+            to: vm.contract_address(),
+            value: U256::from(5),
+        };
+        vm.mock_call(
+            CUPCAKE,
+            pull.abi_encode(),
+            Ok(U256::ZERO.to_be_bytes::<32>().to_vec()),
+        );
+        assert!(matches!(
+            contract.stake(U256::from(5)),
+            Err(StakingError::TransferFailed(_))
+        ));
+
+        let call = IERC20::transferCall {
+            to: ALICE,
+            value: U256::from(40),
+        };
+        vm.mock_call(CUPCAKE, call.abi_encode(), Err(b"paused".to_vec()));
+        assert!(matches!(
+            contract.unstake(U256::from(40)),
+            Err(StakingError::TransferFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_idle_time_and_failed_claim() {
+        let (vm, mut contract) = setup();
+        fund(&vm, &mut contract, 1_000);
+        // Rewards for seconds with nobody staked are never earned.
+        vm.set_block_timestamp(START + 40);
+        stake(&vm, &mut contract, ALICE, 10);
+        vm.set_block_timestamp(START + DURATION);
+        assert_eq!(contract.earned(ALICE), U256::from(600));
+
+        let call = IERC20::transferCall {
+            to: ALICE,
+            value: U256::from(600),
+        };
+        vm.mock_call(CUPCAKE, call.abi_encode(), Err(Vec::new()));
+        assert!(matches!(
+            contract.claim(),
+            Err(StakingError::TransferFailed(_))
+        ));
+    }
+}
@@ -0,0 +1,498 @@
+//!
+//! Stylus Daily Auction
+//!
+//! A Nouns-style auction house: one freshly minted NFT is auctioned at a time,
+//! and each auction starts as the previous one settles. The house must own
+//! the `erc721-nft` contract it mints from, since only the owner can mint.
+//!
+//! `settle_and_start_next()` is the heartbeat. Anyone may call it once the
+//! current auction has ended: it hands the NFT to the winner (or to the
+//! treasury if nobody bid), credits the winning bid to the treasury and then
+//! mints the next token and opens its auction for `duration` seconds.
+//!
+//! Bids work like the `english-auction` example: outbid amounts are credited
+//! to the bidder and pulled with `withdraw()`, and so are the treasury's
+//! proceeds. A bid in the last `TIME_BUFFER` seconds pushes the end back so
+//! that nobody can win by sniping.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall};
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    prelude::*,
+};
+
+/// Each bid must beat the current highest by at least this much, in basis points.
+const MIN_INCREMENT_BPS: u64 = 500;
+const BPS: u64 = 10_000;
+/// A bid this close to the end extends the auction to this long after the bid.
+pub const TIME_BUFFER: u64 = 5 * 60;
+
+sol! {
+    interface IMintableNft {
+        function mint(address to) external returns (uint256);
+        function transferFrom(address from, address to, uint256 tokenId) external;
+    }
+
+    event AuctionCreated(uint256 indexed tokenId, uint256 startTime, uint256 endTime);
+    event AuctionBid(uint256 indexed tokenId, address indexed bidder, uint256 amount, bool extended);
+    event AuctionSettled(uint256 indexed tokenId, address indexed winner, uint256 amount);
+    event Withdrawn(address indexed account, uint256 amount);
+
+    #[derive(Debug)]
+    error InvalidConfig();
+    #[derive(Debug)]
+    error NoAuction();
+    #[derive(Debug)]
+    error WrongToken(uint256 current, uint256 requested);
+    #[derive(Debug)]
+    error AuctionClosed(uint256 endTime);
+    #[derive(Debug)]
+    error AuctionStillOpen(uint256 endTime);
+    #[derive(Debug)]
+    error BidTooLow(uint256 minimum);
+    #[derive(Debug)]
+    error MintFailed();
+    #[derive(Debug)]
+    error NftTransferFailed(uint256 tokenId, address to);
+    #[derive(Debug)]
+    error NothingToWithdraw();
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum DailyAuctionError {
+    InvalidConfig(InvalidConfig),
+    NoAuction(NoAuction),
+    WrongToken(WrongToken),
+    AuctionClosed(AuctionClosed),
+    AuctionStillOpen(AuctionStillOpen),
+    BidTooLow(BidTooLow),
+    MintFailed(MintFailed),
+    NftTransferFailed(NftTransferFailed),
+    NothingToWithdraw(NothingToWithdraw),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct DailyAuction {
+        address nft;
+        /// Receives the winning bids, and the NFT when nobody bids.
+        address treasury;
+        /// Lowest acceptable first bid.
+        uint256 reserve_price;
+        uint256 duration;
+        /// Set once the first auction has started.
+        bool started;
+        uint256 token_id;
+        uint256 start_time;
+        uint256 end_time;
+        address highest_bidder;
+        uint256 highest_bid;
+        /// Outbid amounts and treasury proceeds waiting to be withdrawn.
+        mapping(address => uint256) pending_returns;
+    }
+}
+
+#[public]
+impl DailyAuction {
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        nft: Address,
+        treasury: Address,
+        reserve_price: U256,
+        duration: u64,
+    ) -> Result<(), DailyAuctionError> {
+        if nft.is_zero() || treasury.is_zero() || reserve_price.is_zero() || duration == 0 {
+            return Err(DailyAuctionError::InvalidConfig(InvalidConfig {}));
+        }
+        self.nft.set(nft);
+        self.treasury.set(treasury);
+        self.reserve_price.set(reserve_price);
+        self.duration.set(U256::from(duration));
+        Ok(())
+    }
+
+    /// Settles the ended auction, if any, then mints the next NFT and opens its auction.
+    ///
+    /// Anyone may call this. Returns the new token id.
+    pub fn settle_and_start_next(&mut self) -> Result<U256, DailyAuctionError> {
+        if self.started.get() {
+            self.settle()?;
+        }
+        self.start_next()
+    }
+
+    /// Bids `msg.value` on `token_id`, which must be the NFT currently up for auction.
+    #[payable]
+    pub fn bid(&mut self, token_id: U256) -> Result<(), DailyAuctionError> {
+        if !self.started.get() {
+            return Err(DailyAuctionError::NoAuction(NoAuction {}));
+        }
+        let current = self.token_id.get();
+        if token_id != current {
+            return Err(DailyAuctionError::WrongToken(WrongToken {
+                current,
+                requested: token_id,
+            }));
+        }
+        let now = U256::from(self.vm().block_timestamp());
+        let end_time = self.end_time.get();
+        if now >= end_time {
+            return Err(DailyAuctionError::AuctionClosed(AuctionClosed {
+                endTime: end_time,
+            }));
+        }
+        let amount = self.vm().msg_value();
+        let minimum = self.min_next_bid();
+        if amount < minimum {
+            return Err(DailyAuctionError::BidTooLow(BidTooLow { minimum }));
+        }
+
+        let previous_bidder = self.highest_bidder.get();
+        if !previous_bidder.is_zero() {
+            let outbid = self.highest_bid.get();
+            self.credit(previous_bidder, outbid);
+        }
+        let bidder = self.vm().msg_sender();
+        self.highest_bidder.set(bidder);
+        self.highest_bid.set(amount);
+
+        let buffer_end = now + U256::from(TIME_BUFFER);
+        let extended = buffer_end > end_time;
+        if extended {
+            self.end_time.set(buffer_end);
+        }
+        log(
+            self.vm(),
+            AuctionBid {
+                tokenId: token_id,
+                bidder,
+                amount,
+                extended,
+            },
+        );
+        Ok(())
+    }
+
+    /// Sends the caller everything credited to them.
+    pub fn withdraw(&mut self) -> Result<U256, DailyAuctionError> {
+        let account = self.vm().msg_sender();
+        let amount = self.pending_returns.get(account);
+        if amount.is_zero() {
+            return Err(DailyAuctionError::NothingToWithdraw(NothingToWithdraw {}));
+        }
+        // Checks-effects-interactions: a bidder calling back in during the transfer is owed zero.
+        self.pending_returns.setter(account).set(U256::ZERO);
+        if self.vm().transfer_eth(account, amount).is_err() {
+            return Err(DailyAuctionError::TransferFailed(TransferFailed {
+                to: account,
+                amount,
+            }));
+        }
+        log(self.vm(), Withdrawn { account, amount });
+        Ok(amount)
+    }
+
+    /// Smallest bid `bid()` would accept now.
+    pub fn min_next_bid(&self) -> U256 {
+        let highest = self.highest_bid.get();
+        if self.highest_bidder.get().is_zero() {
+            return self.reserve_price.get();
+        }
+        let increment =
+            (highest * U256::from(MIN_INCREMENT_BPS) / U256::from(BPS)).max(U256::from(1));
+        highest + increment
+    }
+
+    /// Returns `(token_id, highest_bid, start_time, end_time, highest_bidder)`.
+    pub fn auction(&self) -> Result<(U256, U256, U256, U256, Address), DailyAuctionError> {
+        if !self.started.get() {
+            return Err(DailyAuctionError::NoAuction(NoAuction {}));
+        }
+        Ok((
+            self.token_id.get(),
+            self.highest_bid.get(),
+            self.start_time.get(),
+            self.end_time.get(),
+            self.highest_bidder.get(),
+        ))
+    }
+
+    pub fn pending_return(&self, account: Address) -> U256 {
+        self.pending_returns.get(account)
+    }
+
+    pub fn nft(&self) -> Address {
+        self.nft.get()
+    }
+
+    pub fn treasury(&self) -> Address {
+        self.treasury.get()
+    }
+}
+
+impl DailyAuction {
+    /// Hands the current NFT to its winner, or the treasury, and credits the proceeds.
+    fn settle(&mut self) -> Result<(), DailyAuctionError> {
+        let end_time = self.end_time.get();
+        if U256::from(self.vm().block_timestamp()) < end_time {
+            return Err(DailyAuctionError::AuctionStillOpen(AuctionStillOpen {
+                endTime: end_time,
+            }));
+        }
+        let token_id = self.token_id.get();
+        let winner = self.highest_bidder.get();
+        let amount = self.highest_bid.get();
+        let treasury = self.treasury.get();
+        let recipient = if winner.is_zero() { treasury } else { winner };
+
+        let call = IMintableNft::transferFromCall {
+            from: self.vm().contract_address(),
+            to: recipient,
+            tokenId: token_id,
+        };
+        let nft = self.nft.get();
+        self.vm()
+            .call(&calls::context::Call::new(), nft, &call.abi_encode())
+            .map_err(|_| {
+                DailyAuctionError::NftTransferFailed(NftTransferFailed {
+                    tokenId: token_id,
+                    to: recipient,
+                })
+            })?;
+        if !amount.is_zero() {
+            self.credit(treasury, amount);
+        }
+        log(
+            self.vm(),
+            AuctionSettled {
+                tokenId: token_id,
+                winner,
+                amount,
+            },
+        );
+        Ok(())
+    }
+
+    /// Mints the next NFT to the house and opens its auction.
+    fn start_next(&mut self) -> Result<U256, DailyAuctionError> {
+        let call = IMintableNft::mintCall {
+            to: self.vm().contract_address(),
+        };
+        let nft = self.nft.get();
+        let returned = self
+            .vm()
+            .call(&calls::context::Call::new(), nft, &call.abi_encode())
+            .map_err(|_| DailyAuctionError::MintFailed(MintFailed {}))?;
+        let token_id = IMintableNft::mintCall::abi_decode_returns(&returned, true)
+            .map_err(|_| DailyAuctionError::MintFailed(MintFailed {}))?
+            ._0;
+
+        let start_time = U256::from(self.vm().block_timestamp());
+        let end_time = start_time + self.duration.get();
+        self.started.set(true);
+        self.token_id.set(token_id);
+        self.start_time.set(start_time);
+        self.end_time.set(end_time);
+        self.highest_bidder.set(Address::ZERO);
+        self.highest_bid.set(U256::ZERO);
+        log(
+            self.vm(),
+            AuctionCreated {
+                tokenId: token_id,
+                startTime: start_time,
+                endTime: end_time,
+            },
+        );
+        Ok(token_id)
+    }
+
+    fn credit(&mut self, account: Address, amount: U256) {
+        let mut pending = self.pending_returns.setter(account);
+        let owed = pending.get() + amount;
+        pending.set(owed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const NFT: Address = Address::new([0x0f; 20]);
+    const TREASURY: Address = Address::new([0x7e; 20]);
+    const ALICE: Address = Address::new([0x01; 20]);
+    const BOB: Address = Address::new([0x02; 20]);
+    const START: u64 = 1_000;
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn setup() -> (TestVM, DailyAuction) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(START);
+        let mut contract = DailyAuction::from(&vm);
+        contract
+            .constructor(NFT, TREASURY, U256::from(1_000), DAY)
+            .unwrap();
+        mock_mint(&vm, 0);
+        contract.settle_and_start_next().unwrap();
+        (vm, contract)
+    }
+
+    /// Makes the next `mint` call return `token_id`.
+    fn mock_mint(vm: &TestVM, token_id: u64) {
+        let call = IMintableNft::mintCall {
+            to: vm.contract_address(),
+        };
+        let returned = U256::from(token_id).to_be_bytes::<32>().to_vec();
+        vm.mock_call(NFT, call.abi_encode(), Ok(returned));
+    }
+
+    fn bid(
+        vm: &TestVM,
+        contract: &mut DailyAuction,
+        bidder: Address,
+        token_id: u64,
+        amount: u64,
+    ) -> Result<(), DailyAuctionError> {
+        vm.set_sender(bidder);
+        vm.set_value(U256::from(amount));
+        let result = contract.bid(U256::from(token_id));
+        if result.is_ok() {
+            let balance = vm.balance(vm.contract_address());
+            vm.set_balance(vm.contract_address(), balance + U256::from(amount));
+        }
+        vm.set_value(U256::ZERO);
+        result
+    }
+
+    #[test]
+    fn test_first_call_starts_auction() {
+        let (_vm, contract) = setup();
+        let (token_id, amount, start, end, bidder) = contract.auction().unwrap();
+        assert_eq!((token_id, amount), (U256::ZERO, U256::ZERO));
+        assert_eq!((start, end), (U256::from(START), U256::from(START + DAY)));
+        assert_eq!(bidder, Address::ZERO);
+
+        let vm = TestVM::default();
+        let mut fresh = DailyAuction::from(&vm);
+        fresh
+            .constructor(NFT, TREASURY, U256::from(1), DAY)
+            .unwrap();
+        assert!(matches!(
+            fresh.auction(),
+            Err(DailyAuctionError::NoAuction(_))
+        ));
+        // An unmocked call returns no data, which is not a valid token id.
+        assert!(matches!(
+            fresh.settle_and_start_next(),
+            Err(DailyAuctionError::MintFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_bids_credit_outbid_bidders() {
+        let (vm, mut contract) = setup();
+        assert!(matches!(
+            bid(&vm, &mut contract, ALICE, 0, 999),
+            Err(DailyAuctionError::BidTooLow(_))
+        ));
+        assert!(matches!(
+            bid(&vm, &mut contract, ALICE, 1, 1_000),
+            Err(DailyAuctionError::WrongToken(_))
+        ));
+        bid(&vm, &mut contract, ALICE, 0, 1_000).unwrap();
+        bid(&vm, &mut contract, BOB, 0, 1_050).unwrap();
+        assert_eq!(contract.pending_return(ALICE), U256::from(1_000));
+
+        vm.set_sender(ALICE);
+        assert_eq!(contract.withdraw().unwrap(), U256::from(1_000));
+        assert_eq!(vm.balance(ALICE), U256::from(1_000));
+    }
+
+    #[test]
+    fn test_late_bid_extends_auction() {
+        let (vm, mut contract) = setup();
+        vm.set_block_timestamp(START + DAY - 60);
+        bid(&vm, &mut contract, ALICE, 0, 1_000).unwrap();
+        let end = contract.auction().unwrap().3;
+        assert_eq!(end, U256::from(START + DAY - 60 + TIME_BUFFER));
+
+        vm.set_block_timestamp(START + DAY);
+        assert!(matches!(
+            contract.settle_and_start_next(),
+            Err(DailyAuctionError::AuctionStillOpen(_))
+        ));
+        bid(&vm, &mut contract, BOB, 0, 2_000).unwrap();
+    }
+
+    #[test]
+    fn test_settle_pays_treasury_and_starts_next() {
+        let (vm, mut contract) = setup();
+        bid(&vm, &mut contract, ALICE, 0, 1_500).unwrap();
+        vm.set_block_timestamp(START + DAY);
+        assert!(matches!(
+            bid(&vm, &mut contract, BOB, 0, 5_000),
+            Err(DailyAuctionError::AuctionClosed(_))
+        ));
+
+        mock_mint(&vm, 1);
+        assert_eq!(contract.settle_and_start_next().unwrap(), U256::from(1));
+        assert_eq!(contract.pending_return(TREASURY), U256::from(1_500));
+        let (token_id, amount, start, _, bidder) = contract.auction().unwrap();
+        assert_eq!((token_id, amount), (U256::from(1), U256::ZERO));
+        assert_eq!((start, bidder), (U256::from(START + DAY), Address::ZERO));
+
+        vm.set_sender(TREASURY);
+        contract.withdraw().unwrap();
+        assert_eq!(vm.balance(TREASURY), U256::from(1_500));
+    }
+
+    #[test]
+    fn test_failed_nft_transfer_blocks_settlement() {
+        let (vm, mut contract) = setup();
+        vm.set_block_timestamp(START + DAY);
+        // Nobody bid, so the NFT goes to the treasury.
+        let call = IMintableNft::transferFromCall {
+            from: vm.contract_address(),
+            to: TREASURY,
+            tokenId: U256::ZERO,
+        };
+        vm.mock_call(NFT, call.abi_encode(), Err(Vec::new()));
+        assert!(matches!(
+            contract.settle_and_start_next(),
+            Err(DailyAuctionError::NftTransferFailed(_))
+        ));
+        assert_eq!(contract.auction().unwrap().0, U256::ZERO);
+
+        vm.mock_call(NFT, call.abi_encode(), Ok(Vec::new()));
+        mock_mint(&vm, 1);
+        contract.settle_and_start_next().unwrap();
+        assert_eq!(contract.pending_return(TREASURY), U256::ZERO);
+    }
+
+    #[test]
+    fn test_failed_withdrawal_surfaces_error() {
+        let (vm, mut contract) = setup();
+        bid(&vm, &mut contract, ALICE, 0, 1_000).unwrap();
+        bid(&vm, &mut contract, BOB, 0, 1_050).unwrap();
+        vm.set_balance(vm.contract_address(), U256::ZERO);
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.withdraw(),
+            Err(DailyAuctionError::TransferFailed(TransferFailed { to, amount }))
+                if to == ALICE && amount == U256::from(1_000)
+        ));
+    }
+}
@@ -0,0 +1,472 @@
+//!
+//! Stylus Two-Phase Commit
+//!
+//! A coordinator that applies changes across several participant contracts
+//! so that either all of them happen or none do. Participants implement
+//! `prepare`/`commit`/`rollback`, as the `two-phase-participant` ledger does,
+//! and trust this coordinator to call them.
+//!
+//! A transaction moves through explicit steps, each driven by its initiator:
+//!
+//! 1. `begin(participants, accounts, deltas)` records one change per
+//!    participant and returns the transaction id.
+//! 2. `prepare(txId)` asks every participant to check and lock its change. If
+//!    one refuses, the participants already prepared are rolled back and the
+//!    transaction is aborted; `prepare` returns `false` instead of reverting so
+//!    the abort is recorded.
+//! 3. `commit(txId)` tells every participant to apply its change. Prepared
+//!    participants have promised that commit succeeds, so a failure here
+//!    reverts the whole call.
+//!
+//! `rollback(txId)` aborts a transaction that has not been committed,
+//! releasing the participants' locks if it was prepared.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{string::ToString, vec::Vec};
+use alloy_sol_types::{sol, SolCall};
+use stylus_sdk::{
+    alloy_primitives::{Address, I256, U256, U8},
+    prelude::*,
+};
+
+/// Most participants a single transaction may touch.
+const MAX_PARTICIPANTS: usize = 8;
+
+pub const STATE_NONE: u8 = 0;
+pub const STATE_ACTIVE: u8 = 1;
+pub const STATE_PREPARED: u8 = 2;
+pub const STATE_COMMITTED: u8 = 3;
+pub const STATE_ABORTED: u8 = 4;
+
+sol! {
+    /// What the coordinator calls on each participant.
+    interface ITwoPhaseParticipant {
+        function prepare(uint256 txId, address account, int256 delta) external;
+        function commit(uint256 txId) external;
+        function rollback(uint256 txId) external;
+    }
+
+    event TransactionBegun(uint256 indexed txId, address indexed initiator, uint256 participants);
+    event TransactionPrepared(uint256 indexed txId);
+    event TransactionCommitted(uint256 indexed txId);
+    /// `participant` is the one that refused to prepare, or zero if the initiator rolled back.
+    event TransactionAborted(uint256 indexed txId, address participant);
+
+    #[derive(Debug)]
+    error InvalidTransaction(string reason);
+    #[derive(Debug)]
+    error UnknownTransaction(uint256 txId);
+    #[derive(Debug)]
+    error NotInitiator(address caller);
+    #[derive(Debug)]
+    error WrongState(uint256 txId, uint8 state);
+    #[derive(Debug)]
+    error CommitFailed(uint256 txId, address participant);
+    #[derive(Debug)]
+    error RollbackFailed(uint256 txId, address participant);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum CoordinatorError {
+    InvalidTransaction(InvalidTransaction),
+    UnknownTransaction(UnknownTransaction),
+    NotInitiator(NotInitiator),
+    WrongState(WrongState),
+    CommitFailed(CommitFailed),
+    RollbackFailed(RollbackFailed),
+}
+
+sol_storage! {
+    /// One change per participant: add `deltas[i]` to `accounts[i]` on `participants[i]`.
+    pub struct Transaction {
+        address initiator;
+        uint8 state;
+        address[] participants;
+        address[] accounts;
+        int256[] deltas;
+    }
+
+    #[entrypoint]
+    pub struct TwoPhaseCoordinator {
+        mapping(uint256 => Transaction) transactions;
+        /// Ids start at 1.
+        uint256 transaction_count;
+    }
+}
+
+#[public]
+impl TwoPhaseCoordinator {
+    /// Records a transaction for the caller to drive and returns its id.
+    pub fn begin(
+        &mut self,
+        participants: Vec<Address>,
+        accounts: Vec<Address>,
+        deltas: Vec<I256>,
+    ) -> Result<U256, CoordinatorError> {
+        validate(&participants, &accounts, &deltas)?;
+        let tx_id = self.transaction_count.get() + U256::from(1);
+        self.transaction_count.set(tx_id);
+
+        let initiator = self.vm().msg_sender();
+        let count = participants.len();
+        let mut transaction = self.transactions.setter(tx_id);
+        transaction.initiator.set(initiator);
+        transaction.state.set(U8::from(STATE_ACTIVE));
+        for ((participant, account), delta) in participants.into_iter().zip(accounts).zip(deltas) {
+            transaction.participants.push(participant);
+            transaction.accounts.push(account);
+            transaction.deltas.push(delta);
+        }
+        log(
+            self.vm(),
+            TransactionBegun {
+                txId: tx_id,
+                initiator,
+                participants: U256::from(count),
+            },
+        );
+        Ok(tx_id)
+    }
+
+    /// Phase one. Returns `false` if a participant refused and the transaction was aborted.
+    pub fn prepare(&mut self, tx_id: U256) -> Result<bool, CoordinatorError> {
+        self.check(tx_id, &[STATE_ACTIVE])?;
+        let changes = self.changes(tx_id);
+        for (index, &(participant, account, delta)) in changes.iter().enumerate() {
+            let call = ITwoPhaseParticipant::prepareCall {
+                txId: tx_id,
+                account,
+                delta,
+            };
+            if self
+                .vm()
+                .call(
+                    &calls::context::Call::new(),
+                    participant,
+                    &call.abi_encode(),
+                )
+                .is_err()
+            {
+                self.rollback_participants(tx_id, &changes[..index])?;
+                self.set_state(tx_id, STATE_ABORTED);
+                log(
+                    self.vm(),
+                    TransactionAborted {
+                        txId: tx_id,
+                        participant,
+                    },
+                );
+                return Ok(false);
+            }
+        }
+        self.set_state(tx_id, STATE_PREPARED);
+        log(self.vm(), TransactionPrepared { txId: tx_id });
+        Ok(true)
+    }
+
+    /// Phase two: applies every prepared change.
+    pub fn commit(&mut self, tx_id: U256) -> Result<(), CoordinatorError> {
+        self.check(tx_id, &[STATE_PREPARED])?;
+        for (participant, _, _) in self.changes(tx_id) {
+            let call = ITwoPhaseParticipant::commitCall { txId: tx_id };
+            self.vm()
+                .call(
+                    &calls::context::Call::new(),
+                    participant,
+                    &call.abi_encode(),
+                )
+                .map_err(|_| {
+                    CoordinatorError::CommitFailed(CommitFailed {
+                        txId: tx_id,
+                        participant,
+                    })
+                })?;
+        }
+        self.set_state(tx_id, STATE_COMMITTED);
+        log(self.vm(), TransactionCommitted { txId: tx_id });
+        Ok(())
+    }
+
+    /// Aborts a transaction that has not been committed, unlocking prepared participants.
+    pub fn rollback(&mut self, tx_id: U256) -> Result<(), CoordinatorError> {
+        let state = self.check(tx_id, &[STATE_ACTIVE, STATE_PREPARED])?;
+        if state == STATE_PREPARED {
+            let changes = self.changes(tx_id);
+            self.rollback_participants(tx_id, &changes)?;
+        }
+        self.set_state(tx_id, STATE_ABORTED);
+        log(
+            self.vm(),
+            TransactionAborted {
+                txId: tx_id,
+                participant: Address::ZERO,
+            },
+        );
+        Ok(())
+    }
+
+    /// Returns `(initiator, state, participants)` for `tx_id`.
+    pub fn transaction(&self, tx_id: U256) -> (Address, u8, Vec<Address>) {
+        let transaction = self.transactions.getter(tx_id);
+        let participants = (0..transaction.participants.len())
+            .filter_map(|index| transaction.participants.get(index))
+            .collect();
+        (
+            transaction.initiator.get(),
+            transaction.state.get().to(),
+            participants,
+        )
+    }
+
+    pub fn state_of(&self, tx_id: U256) -> u8 {
+        self.transactions.getter(tx_id).state.get().to()
+    }
+
+    pub fn transaction_count(&self) -> U256 {
+        self.transaction_count.get()
+    }
+}
+
+impl TwoPhaseCoordinator {
+    /// Requires the caller to be the initiator and the state to be one of `allowed`.
+    fn check(&self, tx_id: U256, allowed: &[u8]) -> Result<u8, CoordinatorError> {
+        let transaction = self.transactions.getter(tx_id);
+        let state = transaction.state.get().to::<u8>();
+        if state == STATE_NONE {
+            return Err(CoordinatorError::UnknownTransaction(UnknownTransaction {
+                txId: tx_id,
+            }));
+        }
+        let caller = self.vm().msg_sender();
+        if caller != transaction.initiator.get() {
+            return Err(CoordinatorError::NotInitiator(NotInitiator { caller }));
+        }
+        if !allowed.contains(&state) {
+            return Err(CoordinatorError::WrongState(WrongState {
+                txId: tx_id,
+                state,
+            }));
+        }
+        Ok(state)
+    }
+
+    fn changes(&self, tx_id: U256) -> Vec<(Address, Address, I256)> {
+        let transaction = self.transactions.getter(tx_id);
+        (0..transaction.participants.len())
+            .filter_map(|index| {
+                Some((
+                    transaction.participants.get(index)?,
+                    transaction.accounts.get(index)?,
+                    transaction.deltas.get(index)?,
+                ))
+            })
+            .collect()
+    }
+
+    fn rollback_participants(
+        &mut self,
+        tx_id: U256,
+        changes: &[(Address, Address, I256)],
+    ) -> Result<(), CoordinatorError> {
+        for &(participant, _, _) in changes {
+            let call = ITwoPhaseParticipant::rollbackCall { txId: tx_id };
+            self.vm()
+                .call(
+                    &calls::context::Call::new(),
+                    participant,
+                    &call.abi_encode(),
+                )
+                .map_err(|_| {
+                    CoordinatorError::RollbackFailed(RollbackFailed {
+                        txId: tx_id,
+                        participant,
+                    })
+                })?;
+        }
+        Ok(())
+    }
+
+    fn set_state(&mut self, tx_id: U256, state: u8) {
+        self.transactions.setter(tx_id).state.set(U8::from(state));
+    }
+}
+
+fn validate(
+    participants: &[Address],
+    accounts: &[Address],
+    deltas: &[I256],
+) -> Result<(), CoordinatorError> {
+    let invalid = |reason: &str| {
+        CoordinatorError::InvalidTransaction(InvalidTransaction {
+            reason: reason.to_string(),
+        })
+    };
+    if participants.is_empty() || participants.len() > MAX_PARTICIPANTS {
+        return Err(invalid("Participant count out of range"));
+    }
+    if participants.len() != accounts.len() || participants.len() != deltas.len() {
+        return Err(invalid("Participants, accounts and deltas length mismatch"));
+    }
+    for (index, participant) in participants.iter().enumerate() {
+        if participant.is_zero() {
+            return Err(invalid("Zero address participant"));
+        }
+        // Participants key their pending change by transaction id, so each may appear once.
+        if participants[..index].contains(participant) {
+            return Err(invalid("Duplicate participant"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const INITIATOR: Address = Address::new([0x01; 20]);
+    const BANK_A: Address = Address::new([0xa0; 20]);
+    const BANK_B: Address = Address::new([0xb0; 20]);
+    const ALICE: Address = Address::new([0x0a; 20]);
+    const BOB: Address = Address::new([0x0b; 20]);
+
+    fn delta(value: i64) -> I256 {
+        I256::try_from(value).unwrap()
+    }
+
+    /// Begins a transfer of 30 from Alice on bank A to Bob on bank B.
+    fn setup() -> (TestVM, TwoPhaseCoordinator, U256) {
+        let vm = TestVM::default();
+        let mut contract = TwoPhaseCoordinator::from(&vm);
+        vm.set_sender(INITIATOR);
+        let tx_id = contract
+            .begin(
+                vec![BANK_A, BANK_B],
+                vec![ALICE, BOB],
+                vec![delta(-30), delta(30)],
+            )
+            .unwrap();
+        (vm, contract, tx_id)
+    }
+
+    fn prepare_call(tx_id: U256, account: Address, delta: I256) -> Vec<u8> {
+        ITwoPhaseParticipant::prepareCall {
+            txId: tx_id,
+            account,
+            delta,
+        }
+        .abi_encode()
+    }
+
+    #[test]
+    fn test_prepare_then_commit() {
+        let (_vm, mut contract, tx_id) = setup();
+        assert_eq!(tx_id, U256::from(1));
+        assert_eq!(contract.state_of(tx_id), STATE_ACTIVE);
+        assert!(matches!(
+            contract.commit(tx_id),
+            Err(CoordinatorError::WrongState(_))
+        ));
+
+        assert!(contract.prepare(tx_id).unwrap());
+        assert_eq!(contract.state_of(tx_id), STATE_PREPARED);
+        contract.commit(tx_id).unwrap();
+        let (initiator, state, participants) = contract.transaction(tx_id);
+        assert_eq!(initiator, INITIATOR);
+        assert_eq!(state, STATE_COMMITTED);
+        assert_eq!(participants, vec![BANK_A, BANK_B]);
+    }
+
+    #[test]
+    fn test_refused_prepare_rolls_back_and_aborts() {
+        let (vm, mut contract, tx_id) = setup();
+        vm.mock_call(
+            BANK_B,
+            prepare_call(tx_id, BOB, delta(30)),
+            Err(b"locked".to_vec()),
+        );
+        // Bank A prepared first, so it must be rolled back; make that fail to prove it is called.
+        let rollback = ITwoPhaseParticipant::rollbackCall { txId: tx_id };
+        vm.mock_call(BANK_A, rollback.abi_encode(), Err(Vec::new()));
+        assert!(matches!(
+            contract.prepare(tx_id),
+            Err(CoordinatorError::RollbackFailed(RollbackFailed { participant, .. }))
+                if participant == BANK_A
+        ));
+
+        vm.mock_call(BANK_A, rollback.abi_encode(), Ok(Vec::new()));
+        assert!(!contract.prepare(tx_id).unwrap());
+        assert_eq!(contract.state_of(tx_id), STATE_ABORTED);
+        assert!(matches!(
+            contract.commit(tx_id),
+            Err(CoordinatorError::WrongState(_))
+        ));
+    }
+
+    #[test]
+    fn test_failed_commit_reverts() {
+        let (vm, mut contract, tx_id) = setup();
+        contract.prepare(tx_id).unwrap();
+        let commit = ITwoPhaseParticipant::commitCall { txId: tx_id };
+        vm.mock_call(BANK_B, commit.abi_encode(), Err(Vec::new()));
+        assert!(matches!(
+            contract.commit(tx_id),
+            Err(CoordinatorError::CommitFailed(CommitFailed { participant, .. }))
+                if participant == BANK_B
+        ));
+        assert_eq!(contract.state_of(tx_id), STATE_PREPARED);
+    }
+
+    #[test]
+    fn test_rollback_is_initiator_only_and_final() {
+        let (vm, mut contract, tx_id) = setup();
+        contract.prepare(tx_id).unwrap();
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.rollback(tx_id),
+            Err(CoordinatorError::NotInitiator(_))
+        ));
+
+        vm.set_sender(INITIATOR);
+        contract.rollback(tx_id).unwrap();
+        assert_eq!(contract.state_of(tx_id), STATE_ABORTED);
+        assert!(matches!(
+            contract.rollback(tx_id),
+            Err(CoordinatorError::WrongState(_))
+        ));
+        assert!(matches!(
+            contract.prepare(U256::from(2)),
+            Err(CoordinatorError::UnknownTransaction(_))
+        ));
+    }
+
+    #[test]
+    fn test_begin_rejects_invalid_transactions() {
+        let (_vm, mut contract, _) = setup();
+        let cases = [
+            (vec![], vec![], vec![]),
+            (vec![BANK_A], vec![ALICE, BOB], vec![delta(1)]),
+            (vec![Address::ZERO], vec![ALICE], vec![delta(1)]),
+            (vec![BANK_A, BANK_A], vec![ALICE, BOB], vec![delta(1); 2]),
+            (
+                vec![BANK_A; MAX_PARTICIPANTS + 1],
+                vec![ALICE; MAX_PARTICIPANTS + 1],
+                vec![delta(1); MAX_PARTICIPANTS + 1],
+            ),
+        ];
+        for (participants, accounts, deltas) in cases {
+            assert!(matches!(
+                contract.begin(participants, accounts, deltas),
+                Err(CoordinatorError::InvalidTransaction(_))
+            ));
+        }
+        assert_eq!(contract.transaction_count(), U256::from(1));
+    }
+}
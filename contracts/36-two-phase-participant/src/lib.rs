@@ -0,0 +1,331 @@
+//!
+//! Stylus Two-Phase Participant
+//!
+//! A token ledger that takes part in transactions run by the
+//! `two-phase-commit` coordinator. The coordinator set at deployment is the
+//! only caller allowed to drive the protocol:
+//!
+//! - `prepare(txId, account, delta)` checks that the change can be applied and
+//!   locks `account` until the transaction finishes. A debit that the balance
+//!   cannot cover is refused here, so that `commit` never has to fail.
+//! - `commit(txId)` applies the prepared change and unlocks the account.
+//! - `rollback(txId)` discards the prepared change and unlocks the account.
+//!
+//! While an account is locked its holder cannot `transfer`, which keeps the
+//! balance checked in `prepare` intact until the coordinator decides.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, I256, U256},
+    prelude::*,
+};
+
+sol! {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+    event Prepared(uint256 indexed txId, address indexed account, int256 delta);
+    event Committed(uint256 indexed txId, address indexed account, int256 delta);
+    event RolledBack(uint256 indexed txId, address indexed account);
+
+    #[derive(Debug)]
+    error NotCoordinator(address caller);
+    #[derive(Debug)]
+    error TransactionExists(uint256 txId);
+    #[derive(Debug)]
+    error UnknownTransaction(uint256 txId);
+    #[derive(Debug)]
+    error AccountLocked(address account);
+    #[derive(Debug)]
+    error InsufficientBalance(address account, uint256 balance, uint256 needed);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum ParticipantError {
+    NotCoordinator(NotCoordinator),
+    TransactionExists(TransactionExists),
+    UnknownTransaction(UnknownTransaction),
+    AccountLocked(AccountLocked),
+    InsufficientBalance(InsufficientBalance),
+}
+
+sol_storage! {
+    /// A change prepared for one transaction and not yet committed or rolled back.
+    pub struct PendingChange {
+        address account;
+        int256 delta;
+        bool active;
+    }
+
+    #[entrypoint]
+    pub struct TwoPhaseParticipant {
+        address coordinator;
+        mapping(address => uint256) balances;
+        /// Set while a prepared transaction holds the account.
+        mapping(address => bool) locked;
+        mapping(uint256 => PendingChange) pending;
+    }
+}
+
+#[public]
+impl TwoPhaseParticipant {
+    /// Trusts `coordinator` to drive transactions and mints `supply` to `holder`.
+    #[constructor]
+    pub fn constructor(&mut self, coordinator: Address, holder: Address, supply: U256) {
+        self.coordinator.set(coordinator);
+        self.balances.setter(holder).set(supply);
+    }
+
+    /// Phase one: checks that `delta` can be applied to `account` and locks it. Coordinator only.
+    pub fn prepare(
+        &mut self,
+        tx_id: U256,
+        account: Address,
+        delta: I256,
+    ) -> Result<(), ParticipantError> {
+        self.only_coordinator()?;
+        if self.pending.getter(tx_id).active.get() {
+            return Err(ParticipantError::TransactionExists(TransactionExists {
+                txId: tx_id,
+            }));
+        }
+        if self.locked.get(account) {
+            return Err(ParticipantError::AccountLocked(AccountLocked { account }));
+        }
+        let balance = self.balances.get(account);
+        if delta.is_negative() && balance < delta.unsigned_abs() {
+            return Err(ParticipantError::InsufficientBalance(InsufficientBalance {
+                account,
+                balance,
+                needed: delta.unsigned_abs(),
+            }));
+        }
+
+        let mut change = self.pending.setter(tx_id);
+        change.account.set(account);
+        change.delta.set(delta);
+        change.active.set(true);
+        self.locked.setter(account).set(true);
+        log(
+            self.vm(),
+            Prepared {
+                txId: tx_id,
+                account,
+                delta,
+            },
+        );
+        Ok(())
+    }
+
+    /// Phase two: applies the change prepared for `tx_id`. Coordinator only.
+    pub fn commit(&mut self, tx_id: U256) -> Result<(), ParticipantError> {
+        self.only_coordinator()?;
+        let (account, delta) = self.take_pending(tx_id)?;
+        let balance = self.balances.get(account);
+        // `prepare` checked the debit and the lock kept the balance from moving since.
+        let updated = if delta.is_negative() {
+            balance - delta.unsigned_abs()
+        } else {
+            balance + delta.unsigned_abs()
+        };
+        self.balances.setter(account).set(updated);
+        log(
+            self.vm(),
+            Committed {
+                txId: tx_id,
+                account,
+                delta,
+            },
+        );
+        Ok(())
+    }
+
+    /// Discards the change prepared for `tx_id`. Coordinator only.
+    pub fn rollback(&mut self, tx_id: U256) -> Result<(), ParticipantError> {
+        self.only_coordinator()?;
+        let (account, _) = self.take_pending(tx_id)?;
+        log(
+            self.vm(),
+            RolledBack {
+                txId: tx_id,
+                account,
+            },
+        );
+        Ok(())
+    }
+
+    /// Moves `value` from the caller to `to`; refused while either account is locked.
+    pub fn transfer(&mut self, to: Address, value: U256) -> Result<(), ParticipantError> {
+        let from = self.vm().msg_sender();
+        for account in [from, to] {
+            if self.locked.get(account) {
+                return Err(ParticipantError::AccountLocked(AccountLocked { account }));
+            }
+        }
+        let balance = self.balances.get(from);
+        if balance < value {
+            return Err(ParticipantError::InsufficientBalance(InsufficientBalance {
+                account: from,
+                balance,
+                needed: value,
+            }));
+        }
+        self.balances.setter(from).set(balance - value);
+        let received = self.balances.get(to) + value;
+        self.balances.setter(to).set(received);
+        log(self.vm(), Transfer { from, to, value });
+        Ok(())
+    }
+
+    pub fn balance_of(&self, account: Address) -> U256 {
+        self.balances.get(account)
+    }
+
+    pub fn is_locked(&self, account: Address) -> bool {
+        self.locked.get(account)
+    }
+
+    /// Returns `(account, delta, active)` for `tx_id`.
+    pub fn pending(&self, tx_id: U256) -> (Address, I256, bool) {
+        let change = self.pending.getter(tx_id);
+        (
+            change.account.get(),
+            change.delta.get(),
+            change.active.get(),
+        )
+    }
+
+    pub fn coordinator(&self) -> Address {
+        self.coordinator.get()
+    }
+}
+
+impl TwoPhaseParticipant {
+    fn only_coordinator(&self) -> Result<(), ParticipantError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.coordinator.get() {
+            return Err(ParticipantError::NotCoordinator(NotCoordinator { caller }));
+        }
+        Ok(())
+    }
+
+    /// Clears the pending change for `tx_id` and unlocks its account.
+    fn take_pending(&mut self, tx_id: U256) -> Result<(Address, I256), ParticipantError> {
+        let mut change = self.pending.setter(tx_id);
+        if !change.active.get() {
+            return Err(ParticipantError::UnknownTransaction(UnknownTransaction {
+                txId: tx_id,
+            }));
+        }
+        let account = change.account.get();
+        let delta = change.delta.get();
+        change.active.set(false);
+        self.locked.setter(account).set(false);
+        Ok((account, delta))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const COORDINATOR: Address = Address::new([0xc0; 20]);
+    const ALICE: Address = Address::new([0x01; 20]);
+    const BOB: Address = Address::new([0x02; 20]);
+
+    fn setup() -> (TestVM, TwoPhaseParticipant) {
+        let vm = TestVM::default();
+        let mut contract = TwoPhaseParticipant::from(&vm);
+        contract.constructor(COORDINATOR, ALICE, U256::from(100));
+        vm.set_sender(COORDINATOR);
+        (vm, contract)
+    }
+
+    fn delta(value: i64) -> I256 {
+        I256::try_from(value).unwrap()
+    }
+
+    #[test]
+    fn test_prepare_then_commit_applies_the_change() {
+        let (_vm, mut contract) = setup();
+        let tx_id = U256::from(1);
+        contract.prepare(tx_id, ALICE, delta(-40)).unwrap();
+        assert!(contract.is_locked(ALICE));
+        assert_eq!(contract.balance_of(ALICE), U256::from(100));
+
+        contract.commit(tx_id).unwrap();
+        assert_eq!(contract.balance_of(ALICE), U256::from(60));
+        assert!(!contract.is_locked(ALICE));
+        assert!(!contract.pending(tx_id).2);
+        assert!(matches!(
+            contract.commit(tx_id),
+            Err(ParticipantError::UnknownTransaction(_))
+        ));
+    }
+
+    #[test]
+    fn test_rollback_discards_the_change() {
+        let (_vm, mut contract) = setup();
+        contract.prepare(U256::from(1), BOB, delta(25)).unwrap();
+        contract.rollback(U256::from(1)).unwrap();
+        assert_eq!(contract.balance_of(BOB), U256::ZERO);
+        assert!(!contract.is_locked(BOB));
+    }
+
+    #[test]
+    fn test_prepare_refuses_what_commit_could_not_apply() {
+        let (_vm, mut contract) = setup();
+        assert!(matches!(
+            contract.prepare(U256::from(1), ALICE, delta(-101)),
+            Err(ParticipantError::InsufficientBalance(_))
+        ));
+        contract.prepare(U256::from(1), ALICE, delta(-10)).unwrap();
+        assert!(matches!(
+            contract.prepare(U256::from(1), BOB, delta(10)),
+            Err(ParticipantError::TransactionExists(_))
+        ));
+        assert!(matches!(
+            contract.prepare(U256::from(2), ALICE, delta(5)),
+            Err(ParticipantError::AccountLocked(_))
+        ));
+    }
+
+    #[test]
+    fn test_locked_accounts_cannot_transfer() {
+        let (vm, mut contract) = setup();
+        contract.prepare(U256::from(1), ALICE, delta(-100)).unwrap();
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.transfer(BOB, U256::from(100)),
+            Err(ParticipantError::AccountLocked(_))
+        ));
+
+        vm.set_sender(COORDINATOR);
+        contract.rollback(U256::from(1)).unwrap();
+        vm.set_sender(ALICE);
+        contract.transfer(BOB, U256::from(100)).unwrap();
+        assert_eq!(contract.balance_of(BOB), U256::from(100));
+    }
+
+    #[test]
+    fn test_only_the_coordinator_drives_transactions() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.prepare(U256::from(1), ALICE, delta(1)),
+            Err(ParticipantError::NotCoordinator(_))
+        ));
+        assert!(matches!(
+            contract.rollback(U256::from(1)),
+            Err(ParticipantError::NotCoordinator(_))
+        ));
+    }
+}
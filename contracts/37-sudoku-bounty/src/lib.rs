@@ -0,0 +1,474 @@
+//!
+//! Stylus Sudoku Bounty
+//!
+//! Posers fund a bounty with `post_puzzle(givens)` and the first caller to
+//! submit a valid solution with `submit_solution(puzzleId, solution)` is paid
+//! the bounty on the spot.
+//!
+//! Grids are 81 bytes in row-major order. In `givens`, 0 marks an empty cell
+//! and 1-9 a given digit; a solution fills every cell with 1-9. Checking a
+//! solution means verifying every row, column and 3x3 box against a bitmask
+//! of seen digits, plus every given. That is cheap in Rust compiled to WASM,
+//! where the same nested loops over byte arrays cost far more gas in Solidity.
+//!
+//! A puzzle nobody has solved within `CLAIM_WINDOW` can be reclaimed by its
+//! poser, who cannot pull the bounty any earlier from under a solver.
+//!
+//! Submitted solutions are public in the mempool, so a watcher could copy one
+//! and outbid its gas. A production version would have solvers commit to a
+//! hash of their solution first and reveal it later.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{string::ToString, vec::Vec};
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, U256},
+    prelude::*,
+};
+
+/// Cells in a 9x9 grid.
+const GRID_CELLS: usize = 81;
+/// How long a puzzle stays open before its poser may reclaim the bounty.
+pub const CLAIM_WINDOW: u64 = 30 * 24 * 60 * 60;
+
+sol! {
+    event PuzzlePosted(uint256 indexed puzzleId, address indexed poser, uint256 bounty);
+    event PuzzleSolved(uint256 indexed puzzleId, address indexed solver, uint256 bounty);
+    event BountyReclaimed(uint256 indexed puzzleId, address indexed poser, uint256 bounty);
+
+    #[derive(Debug)]
+    error InvalidPuzzle(string reason);
+    #[derive(Debug)]
+    error InvalidSolution(string reason);
+    #[derive(Debug)]
+    error ZeroBounty();
+    #[derive(Debug)]
+    error UnknownPuzzle(uint256 puzzleId);
+    #[derive(Debug)]
+    error PuzzleClosed(uint256 puzzleId);
+    #[derive(Debug)]
+    error NotPoser(address caller);
+    #[derive(Debug)]
+    error ClaimWindowOpen(uint256 reclaimableAt);
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum SudokuError {
+    InvalidPuzzle(InvalidPuzzle),
+    InvalidSolution(InvalidSolution),
+    ZeroBounty(ZeroBounty),
+    UnknownPuzzle(UnknownPuzzle),
+    PuzzleClosed(PuzzleClosed),
+    NotPoser(NotPoser),
+    ClaimWindowOpen(ClaimWindowOpen),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    pub struct Puzzle {
+        address poser;
+        bytes givens;
+        uint256 bounty;
+        uint256 posted_at;
+        /// Zero until solved.
+        address solver;
+        /// Set once the bounty is paid out or reclaimed.
+        bool closed;
+    }
+
+    #[entrypoint]
+    pub struct SudokuBounty {
+        mapping(uint256 => Puzzle) puzzles;
+        /// Ids start at 1.
+        uint256 puzzle_count;
+    }
+}
+
+#[public]
+impl SudokuBounty {
+    /// Posts `givens` with `msg.value` as the bounty and returns the puzzle id.
+    #[payable]
+    pub fn post_puzzle(&mut self, givens: Bytes) -> Result<U256, SudokuError> {
+        let bounty = self.vm().msg_value();
+        if bounty.is_zero() {
+            return Err(SudokuError::ZeroBounty(ZeroBounty {}));
+        }
+        check_givens(&givens).map_err(|reason| {
+            SudokuError::InvalidPuzzle(InvalidPuzzle {
+                reason: reason.to_string(),
+            })
+        })?;
+
+        let puzzle_id = self.puzzle_count.get() + U256::from(1);
+        self.puzzle_count.set(puzzle_id);
+        let poser = self.vm().msg_sender();
+        let posted_at = U256::from(self.vm().block_timestamp());
+        let mut puzzle = self.puzzles.setter(puzzle_id);
+        puzzle.poser.set(poser);
+        puzzle.givens.set_bytes(&givens);
+        puzzle.bounty.set(bounty);
+        puzzle.posted_at.set(posted_at);
+        log(
+            self.vm(),
+            PuzzlePosted {
+                puzzleId: puzzle_id,
+                poser,
+                bounty,
+            },
+        );
+        Ok(puzzle_id)
+    }
+
+    /// Pays the bounty to the caller if `solution` solves the puzzle.
+    pub fn submit_solution(&mut self, puzzle_id: U256, solution: Bytes) -> Result<(), SudokuError> {
+        let puzzle = self.puzzles.getter(puzzle_id);
+        if puzzle.poser.get().is_zero() {
+            return Err(SudokuError::UnknownPuzzle(UnknownPuzzle {
+                puzzleId: puzzle_id,
+            }));
+        }
+        if puzzle.closed.get() {
+            return Err(SudokuError::PuzzleClosed(PuzzleClosed {
+                puzzleId: puzzle_id,
+            }));
+        }
+        check_solution(&puzzle.givens.get_bytes(), &solution).map_err(|reason| {
+            SudokuError::InvalidSolution(InvalidSolution {
+                reason: reason.to_string(),
+            })
+        })?;
+
+        let solver = self.vm().msg_sender();
+        let bounty = puzzle.bounty.get();
+        // Close the puzzle before paying so a re-entrant call finds nothing to claim.
+        let mut puzzle = self.puzzles.setter(puzzle_id);
+        puzzle.solver.set(solver);
+        puzzle.closed.set(true);
+        if self.vm().transfer_eth(solver, bounty).is_err() {
+            return Err(SudokuError::TransferFailed(TransferFailed {
+                to: solver,
+                amount: bounty,
+            }));
+        }
+        log(
+            self.vm(),
+            PuzzleSolved {
+                puzzleId: puzzle_id,
+                solver,
+                bounty,
+            },
+        );
+        Ok(())
+    }
+
+    /// Returns an unsolved puzzle's bounty to its poser once `CLAIM_WINDOW` has passed.
+    pub fn reclaim(&mut self, puzzle_id: U256) -> Result<(), SudokuError> {
+        let puzzle = self.puzzles.getter(puzzle_id);
+        let poser = puzzle.poser.get();
+        if poser.is_zero() {
+            return Err(SudokuError::UnknownPuzzle(UnknownPuzzle {
+                puzzleId: puzzle_id,
+            }));
+        }
+        let caller = self.vm().msg_sender();
+        if caller != poser {
+            return Err(SudokuError::NotPoser(NotPoser { caller }));
+        }
+        if puzzle.closed.get() {
+            return Err(SudokuError::PuzzleClosed(PuzzleClosed {
+                puzzleId: puzzle_id,
+            }));
+        }
+        let reclaimable_at = puzzle.posted_at.get() + U256::from(CLAIM_WINDOW);
+        if U256::from(self.vm().block_timestamp()) < reclaimable_at {
+            return Err(SudokuError::ClaimWindowOpen(ClaimWindowOpen {
+                reclaimableAt: reclaimable_at,
+            }));
+        }
+
+        let bounty = puzzle.bounty.get();
+        self.puzzles.setter(puzzle_id).closed.set(true);
+        if self.vm().transfer_eth(poser, bounty).is_err() {
+            return Err(SudokuError::TransferFailed(TransferFailed {
+                to: poser,
+                amount: bounty,
+            }));
+        }
+        log(
+            self.vm(),
+            BountyReclaimed {
+                puzzleId: puzzle_id,
+                poser,
+                bounty,
+            },
+        );
+        Ok(())
+    }
+
+    /// Checks `solution` against the puzzle without claiming anything.
+    pub fn is_valid_solution(&self, puzzle_id: U256, solution: Bytes) -> bool {
+        let givens = self.puzzles.getter(puzzle_id).givens.get_bytes();
+        check_solution(&givens, &solution).is_ok()
+    }
+
+    /// Returns `(poser, givens, bounty, solver, closed)` for `puzzle_id`.
+    pub fn puzzle(&self, puzzle_id: U256) -> (Address, Bytes, U256, Address, bool) {
+        let puzzle = self.puzzles.getter(puzzle_id);
+        (
+            puzzle.poser.get(),
+            puzzle.givens.get_bytes().into(),
+            puzzle.bounty.get(),
+            puzzle.solver.get(),
+            puzzle.closed.get(),
+        )
+    }
+
+    pub fn puzzle_count(&self) -> U256 {
+        self.puzzle_count.get()
+    }
+}
+
+/// Index of the 3x3 box holding `cell`.
+fn box_of(cell: usize) -> usize {
+    let (row, col) = (cell / 9, cell % 9);
+    (row / 3) * 3 + col / 3
+}
+
+/// Checks that no digit repeats in a row, column or box; 0 cells are skipped.
+fn check_no_repeats(grid: &[u8]) -> Result<(), &'static str> {
+    // Bit `d` of each mask is set once digit `d` has been seen in that unit.
+    let mut rows = [0u16; 9];
+    let mut cols = [0u16; 9];
+    let mut boxes = [0u16; 9];
+    for (cell, &digit) in grid.iter().enumerate() {
+        if digit == 0 {
+            continue;
+        }
+        let bit = 1u16 << digit;
+        let (row, col, square) = (cell / 9, cell % 9, box_of(cell));
+        if rows[row] & bit != 0 {
+            return Err("Digit repeats in a row");
+        }
+        if cols[col] & bit != 0 {
+            return Err("Digit repeats in a column");
+        }
+        if boxes[square] & bit != 0 {
+            return Err("Digit repeats in a box");
+        }
+        rows[row] |= bit;
+        cols[col] |= bit;
+        boxes[square] |= bit;
+    }
+    Ok(())
+}
+
+fn check_givens(givens: &[u8]) -> Result<(), &'static str> {
+    if givens.len() != GRID_CELLS {
+        return Err("Grid must have 81 cells");
+    }
+    if givens.iter().any(|&digit| digit > 9) {
+        return Err("Givens must be 0-9");
+    }
+    check_no_repeats(givens)
+}
+
+fn check_solution(givens: &[u8], solution: &[u8]) -> Result<(), &'static str> {
+    if solution.len() != GRID_CELLS {
+        return Err("Grid must have 81 cells");
+    }
+    if solution.iter().any(|&digit| !(1..=9).contains(&digit)) {
+        return Err("Every cell must be 1-9");
+    }
+    let matches_givens = givens
+        .iter()
+        .zip(solution)
+        .all(|(&given, &digit)| given == 0 || given == digit);
+    if !matches_givens {
+        return Err("Solution changes a given");
+    }
+    // With every cell filled and no repeats, each unit holds exactly the digits 1-9.
+    check_no_repeats(solution)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const POSER: Address = Address::new([0x01; 20]);
+    const SOLVER: Address = Address::new([0x02; 20]);
+    const BOUNTY: u64 = 1_000;
+
+    /// A valid completed grid: row `r` is 1-9 rotated left by `3r + r / 3`.
+    fn solved_grid() -> Vec<u8> {
+        (0..GRID_CELLS)
+            .map(|cell| {
+                let (row, col) = (cell / 9, cell % 9);
+                ((row * 3 + row / 3 + col) % 9 + 1) as u8
+            })
+            .collect()
+    }
+
+    /// The solved grid with every third cell blanked.
+    fn givens() -> Vec<u8> {
+        solved_grid()
+            .into_iter()
+            .enumerate()
+            .map(|(cell, digit)| if cell % 3 == 0 { 0 } else { digit })
+            .collect()
+    }
+
+    fn setup() -> (TestVM, SudokuBounty, U256) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(1_000_000);
+        let mut contract = SudokuBounty::from(&vm);
+        vm.set_sender(POSER);
+        vm.set_value(U256::from(BOUNTY));
+        vm.set_balance(vm.contract_address(), U256::from(BOUNTY));
+        let puzzle_id = contract.post_puzzle(givens().into()).unwrap();
+        vm.set_value(U256::ZERO);
+        (vm, contract, puzzle_id)
+    }
+
+    #[test]
+    fn test_first_valid_solution_claims_bounty() {
+        let (vm, mut contract, puzzle_id) = setup();
+        vm.set_sender(SOLVER);
+        assert!(contract.is_valid_solution(puzzle_id, solved_grid().into()));
+        contract
+            .submit_solution(puzzle_id, solved_grid().into())
+            .unwrap();
+        assert_eq!(vm.balance(SOLVER), U256::from(BOUNTY));
+        let (poser, _, bounty, solver, closed) = contract.puzzle(puzzle_id);
+        assert_eq!((poser, solver, closed), (POSER, SOLVER, true));
+        assert_eq!(bounty, U256::from(BOUNTY));
+
+        vm.set_sender(POSER);
+        assert!(matches!(
+            contract.submit_solution(puzzle_id, solved_grid().into()),
+            Err(SudokuError::PuzzleClosed(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_invalid_solutions() {
+        let (vm, mut contract, puzzle_id) = setup();
+        vm.set_sender(SOLVER);
+
+        let mut wrong_length = solved_grid();
+        wrong_length.pop();
+        let mut out_of_range = solved_grid();
+        out_of_range[0] = 10;
+        // Swapping two cells in a row keeps rows valid but breaks a given and the columns.
+        let mut swapped = solved_grid();
+        swapped.swap(0, 1);
+        // Swapping two whole columns within a band keeps every unit valid but breaks givens.
+        let mut changes_given = solved_grid();
+        for row in 0..9 {
+            changes_given.swap(row * 9 + 1, row * 9 + 2);
+        }
+        for solution in [wrong_length, out_of_range, swapped, changes_given] {
+            assert!(!contract.is_valid_solution(puzzle_id, solution.clone().into()));
+            assert!(matches!(
+                contract.submit_solution(puzzle_id, solution.into()),
+                Err(SudokuError::InvalidSolution(_))
+            ));
+        }
+        assert_eq!(vm.balance(SOLVER), U256::ZERO);
+    }
+
+    #[test]
+    fn test_check_no_repeats_catches_each_unit() {
+        let mut row = [0u8; GRID_CELLS];
+        row[0] = 5;
+        row[8] = 5;
+        let mut col = [0u8; GRID_CELLS];
+        col[0] = 5;
+        col[72] = 5;
+        let mut square = [0u8; GRID_CELLS];
+        square[0] = 5;
+        square[20] = 5;
+        assert_eq!(check_no_repeats(&row), Err("Digit repeats in a row"));
+        assert_eq!(check_no_repeats(&col), Err("Digit repeats in a column"));
+        assert_eq!(check_no_repeats(&square), Err("Digit repeats in a box"));
+        assert_eq!(check_no_repeats(&solved_grid()), Ok(()));
+    }
+
+    #[test]
+    fn test_post_puzzle_validates_givens_and_bounty() {
+        let (vm, mut contract, _) = setup();
+        assert!(matches!(
+            contract.post_puzzle(givens().into()),
+            Err(SudokuError::ZeroBounty(_))
+        ));
+
+        vm.set_value(U256::from(1));
+        let mut conflicting = givens();
+        conflicting[0] = conflicting[1];
+        let mut out_of_range = givens();
+        out_of_range[0] = 10;
+        for bad in [vec![0u8; 80], conflicting, out_of_range] {
+            assert!(matches!(
+                contract.post_puzzle(bad.into()),
+                Err(SudokuError::InvalidPuzzle(_))
+            ));
+        }
+        assert_eq!(contract.puzzle_count(), U256::from(1));
+    }
+
+    #[test]
+    fn test_poser_reclaims_after_claim_window() {
+        let (vm, mut contract, puzzle_id) = setup();
+        vm.set_sender(SOLVER);
+        assert!(matches!(
+            contract.reclaim(puzzle_id),
+            Err(SudokuError::NotPoser(_))
+        ));
+
+        vm.set_sender(POSER);
+        assert!(matches!(
+            contract.reclaim(puzzle_id),
+            Err(SudokuError::ClaimWindowOpen(_))
+        ));
+        vm.set_block_timestamp(1_000_000 + CLAIM_WINDOW);
+        contract.reclaim(puzzle_id).unwrap();
+        assert_eq!(vm.balance(POSER), U256::from(BOUNTY));
+
+        vm.set_sender(SOLVER);
+        assert!(matches!(
+            contract.submit_solution(puzzle_id, solved_grid().into()),
+            Err(SudokuError::PuzzleClosed(_))
+        ));
+    }
+
+    #[test]
+    fn test_failed_payouts_surface_errors() {
+        let (vm, mut contract, puzzle_id) = setup();
+        vm.set_balance(vm.contract_address(), U256::ZERO);
+        vm.set_sender(SOLVER);
+        assert!(matches!(
+            contract.submit_solution(puzzle_id, solved_grid().into()),
+            Err(SudokuError::TransferFailed(TransferFailed { to, .. })) if to == SOLVER
+        ));
+
+        // TestVM keeps the puzzle closed after the error, so reclaim from a fresh one.
+        let (vm, mut contract, puzzle_id) = setup();
+        vm.set_balance(vm.contract_address(), U256::ZERO);
+        vm.set_block_timestamp(1_000_000 + CLAIM_WINDOW);
+        vm.set_sender(POSER);
+        assert!(matches!(
+            contract.reclaim(puzzle_id),
+            Err(SudokuError::TransferFailed(TransferFailed { to, .. })) if to == POSER
+        ));
+    }
+}
@@ -0,0 +1,440 @@
+//!
+//! Stylus Rebasing Token
+//!
+//! An ERC-20-like token, "Rebasing Cupcake" (`rCUPCAKE`), whose balances grow
+//! on their own, in the style of Ampleforth and Lido's stETH. Holders own
+//! shares; a balance is `shares * shareIndex / 1e18`, and the owner raises
+//! `shareIndex` with `rebase(increaseBps)` to pay inflation to every holder at
+//! once without touching their storage.
+//!
+//! Transfers, approvals and `totalSupply` are all in token amounts, as
+//! integrators expect, and are converted to shares internally.
+//! `transfer_shares` moves an exact number of shares instead.
+//!
+//! The example exists to show the pitfalls rebasing tokens create for
+//! integrators, each covered by a test:
+//!
+//! - Balances change without any `Transfer` event, so indexers that replay
+//!   events get them wrong. A rebase only emits `Rebased`.
+//! - A contract that records "amount deposited" and pays it back later
+//!   leaves the rebase yield stranded; it should hold shares instead.
+//! - Amounts convert to shares and back rounding down, so sending `x` can
+//!   debit the sender `x` and credit the recipient only `x - 1`.
+//!
+//! `supportsInterface` reports the ERC-20 interface id, since every ERC-20
+//! function is present with its standard signature. An interface id only
+//! describes the ABI, though; it cannot warn callers about any of the above.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use alloy_sol_types::sol;
+use common::introspection::{ERC165_ID, ERC20_ID};
+use stylus_sdk::{
+    alloy_primitives::{uint, Address, FixedBytes, U256},
+    prelude::*,
+};
+
+/// `1.0` in WAD fixed point.
+const WAD: U256 = uint!(1_000_000_000_000_000_000_U256);
+const BPS: u64 = 10_000;
+/// Largest increase a single rebase may apply, 10%.
+const MAX_REBASE_BPS: u64 = 1_000;
+
+sol! {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+    event TransferShares(address indexed from, address indexed to, uint256 sharesValue);
+    event Approval(address indexed owner, address indexed spender, uint256 value);
+    event Rebased(uint256 oldShareIndex, uint256 newShareIndex, uint256 totalSupply);
+
+    #[derive(Debug)]
+    error ERC20InsufficientBalance(address sender, uint256 balance, uint256 needed);
+    #[derive(Debug)]
+    error ERC20InvalidReceiver(address receiver);
+    #[derive(Debug)]
+    error ERC20InsufficientAllowance(address spender, uint256 allowance, uint256 needed);
+    #[derive(Debug)]
+    error ERC20InvalidSpender(address spender);
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error RebaseTooLarge(uint256 increaseBps, uint256 maxBps);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum RebasingTokenError {
+    InsufficientBalance(ERC20InsufficientBalance),
+    InvalidReceiver(ERC20InvalidReceiver),
+    InsufficientAllowance(ERC20InsufficientAllowance),
+    InvalidSpender(ERC20InvalidSpender),
+    NotOwner(NotOwner),
+    RebaseTooLarge(RebaseTooLarge),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct RebasingToken {
+        address owner;
+        /// Tokens per share, in WAD. Starts at 1.0 and only grows.
+        uint256 share_index;
+        uint256 total_shares;
+        mapping(address => uint256) shares;
+        /// Allowances are in token amounts, like any ERC-20.
+        mapping(address => mapping(address => uint256)) allowances;
+    }
+}
+
+#[public]
+impl RebasingToken {
+    #[constructor]
+    pub fn constructor(&mut self, owner: Address) {
+        self.owner.set(owner);
+        self.share_index.set(WAD);
+    }
+
+    pub fn name(&self) -> String {
+        "Rebasing Cupcake".into()
+    }
+
+    pub fn symbol(&self) -> String {
+        "rCUPCAKE".into()
+    }
+
+    pub fn decimals(&self) -> u8 {
+        18
+    }
+
+    pub fn total_supply(&self) -> U256 {
+        self.amount_for_shares(self.total_shares.get())
+    }
+
+    /// Current balance; changes with every rebase.
+    pub fn balance_of(&self, account: Address) -> U256 {
+        self.amount_for_shares(self.shares.get(account))
+    }
+
+    /// Shares held by `account`; only transfers and mints change them.
+    pub fn shares_of(&self, account: Address) -> U256 {
+        self.shares.get(account)
+    }
+
+    pub fn total_shares(&self) -> U256 {
+        self.total_shares.get()
+    }
+
+    /// Tokens per share, in WAD.
+    pub fn share_index(&self) -> U256 {
+        self.share_index.get()
+    }
+
+    /// Shares that `amount` tokens convert to, rounded down.
+    pub fn shares_for_amount(&self, amount: U256) -> U256 {
+        amount * WAD / self.share_index.get()
+    }
+
+    /// Tokens that `shares` are worth, rounded down.
+    pub fn amount_for_shares(&self, shares: U256) -> U256 {
+        shares * self.share_index.get() / WAD
+    }
+
+    pub fn allowance(&self, owner: Address, spender: Address) -> U256 {
+        self.allowances.getter(owner).get(spender)
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    /// ERC-165: whether this token implements the interface `interface_id`.
+    pub fn supports_interface(&self, interface_id: FixedBytes<4>) -> bool {
+        [ERC165_ID, ERC20_ID].contains(&interface_id)
+    }
+
+    /// Sends `value` tokens' worth of shares; `to` may receive up to a wei less.
+    pub fn transfer(&mut self, to: Address, value: U256) -> Result<bool, RebasingTokenError> {
+        let from = self.vm().msg_sender();
+        let shares = self.shares_for_amount(value);
+        self.move_shares(from, to, shares, value)?;
+        Ok(true)
+    }
+
+    /// Sends exactly `shares` shares, whatever they are worth.
+    pub fn transfer_shares(
+        &mut self,
+        to: Address,
+        shares: U256,
+    ) -> Result<U256, RebasingTokenError> {
+        let from = self.vm().msg_sender();
+        let value = self.amount_for_shares(shares);
+        self.move_shares(from, to, shares, value)?;
+        Ok(value)
+    }
+
+    pub fn approve(&mut self, spender: Address, value: U256) -> Result<bool, RebasingTokenError> {
+        if spender.is_zero() {
+            return Err(RebasingTokenError::InvalidSpender(ERC20InvalidSpender {
+                spender,
+            }));
+        }
+        let owner = self.vm().msg_sender();
+        self.allowances.setter(owner).insert(spender, value);
+        log(
+            self.vm(),
+            Approval {
+                owner,
+                spender,
+                value,
+            },
+        );
+        Ok(true)
+    }
+
+    pub fn transfer_from(
+        &mut self,
+        from: Address,
+        to: Address,
+        value: U256,
+    ) -> Result<bool, RebasingTokenError> {
+        let spender = self.vm().msg_sender();
+        let allowance = self.allowances.getter(from).get(spender);
+        if allowance != U256::MAX {
+            if allowance < value {
+                return Err(RebasingTokenError::InsufficientAllowance(
+                    ERC20InsufficientAllowance {
+                        spender,
+                        allowance,
+                        needed: value,
+                    },
+                ));
+            }
+            self.allowances
+                .setter(from)
+                .insert(spender, allowance - value);
+        }
+        let shares = self.shares_for_amount(value);
+        self.move_shares(from, to, shares, value)?;
+        Ok(true)
+    }
+
+    /// Creates `value` tokens' worth of shares for `to`. Owner only.
+    pub fn mint(&mut self, to: Address, value: U256) -> Result<(), RebasingTokenError> {
+        self.only_owner()?;
+        if to.is_zero() {
+            return Err(RebasingTokenError::InvalidReceiver(ERC20InvalidReceiver {
+                receiver: to,
+            }));
+        }
+        let shares = self.shares_for_amount(value);
+        self.total_shares.set(self.total_shares.get() + shares);
+        let balance = self.shares.get(to) + shares;
+        self.shares.insert(to, balance);
+        log(
+            self.vm(),
+            Transfer {
+                from: Address::ZERO,
+                to,
+                value,
+            },
+        );
+        Ok(())
+    }
+
+    /// Grows every balance by `increase_bps` basis points at once. Owner only.
+    ///
+    /// Only `Rebased` is emitted: no holder sees a `Transfer` for the tokens they gain.
+    pub fn rebase(&mut self, increase_bps: u64) -> Result<U256, RebasingTokenError> {
+        self.only_owner()?;
+        if increase_bps > MAX_REBASE_BPS {
+            return Err(RebasingTokenError::RebaseTooLarge(RebaseTooLarge {
+                increaseBps: U256::from(increase_bps),
+                maxBps: U256::from(MAX_REBASE_BPS),
+            }));
+        }
+        let old_index = self.share_index.get();
+        let new_index = old_index * U256::from(BPS + increase_bps) / U256::from(BPS);
+        self.share_index.set(new_index);
+        log(
+            self.vm(),
+            Rebased {
+                oldShareIndex: old_index,
+                newShareIndex: new_index,
+                totalSupply: self.total_supply(),
+            },
+        );
+        Ok(new_index)
+    }
+}
+
+impl RebasingToken {
+    fn only_owner(&self) -> Result<(), RebasingTokenError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(RebasingTokenError::NotOwner(NotOwner { caller }));
+        }
+        Ok(())
+    }
+
+    /// Moves `shares` from `from` to `to`, logging `value` as the token amount.
+    fn move_shares(
+        &mut self,
+        from: Address,
+        to: Address,
+        shares: U256,
+        value: U256,
+    ) -> Result<(), RebasingTokenError> {
+        if to.is_zero() {
+            return Err(RebasingTokenError::InvalidReceiver(ERC20InvalidReceiver {
+                receiver: to,
+            }));
+        }
+        let from_shares = self.shares.get(from);
+        if from_shares < shares {
+            return Err(RebasingTokenError::InsufficientBalance(
+                ERC20InsufficientBalance {
+                    sender: from,
+                    balance: self.amount_for_shares(from_shares),
+                    needed: value,
+                },
+            ));
+        }
+        self.shares.insert(from, from_shares - shares);
+        // Read after the debit so a self-transfer leaves the shares unchanged.
+        let to_shares = self.shares.get(to);
+        self.shares.insert(to, to_shares + shares);
+        log(self.vm(), Transfer { from, to, value });
+        log(
+            self.vm(),
+            TransferShares {
+                from,
+                to,
+                sharesValue: shares,
+            },
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::SolEvent;
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::new([0x01; 20]);
+    const ALICE: Address = Address::new([0x02; 20]);
+    const BOB: Address = Address::new([0x03; 20]);
+    const VAULT: Address = Address::new([0x04; 20]);
+
+    /// Alice starts with 1,000 tokens at an index of 1.0.
+    fn setup() -> (TestVM, RebasingToken) {
+        let vm = TestVM::default();
+        let mut contract = RebasingToken::from(&vm);
+        contract.constructor(OWNER);
+        vm.set_sender(OWNER);
+        contract.mint(ALICE, U256::from(1_000)).unwrap();
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_rebase_grows_balances_without_transfer_events() {
+        let (vm, mut contract) = setup();
+        let events_before = vm.get_emitted_logs().len();
+        contract.rebase(500).unwrap();
+
+        assert_eq!(contract.balance_of(ALICE), U256::from(1_050));
+        assert_eq!(contract.total_supply(), U256::from(1_050));
+        assert_eq!(contract.shares_of(ALICE), U256::from(1_000));
+        // An indexer summing `Transfer` events still thinks Alice holds 1,000.
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.len(), events_before + 1);
+        assert_eq!(logs.last().unwrap().0[0], Rebased::SIGNATURE_HASH);
+    }
+
+    #[test]
+    fn test_transfers_move_shares_at_the_current_index() {
+        let (vm, mut contract) = setup();
+        contract.rebase(1_000).unwrap();
+        vm.set_sender(ALICE);
+        contract.transfer(BOB, U256::from(550)).unwrap();
+        assert_eq!(contract.shares_of(BOB), U256::from(500));
+        assert_eq!(contract.balance_of(BOB), U256::from(550));
+        assert_eq!(contract.balance_of(ALICE), U256::from(550));
+
+        assert_eq!(
+            contract.transfer_shares(BOB, U256::from(100)).unwrap(),
+            U256::from(110)
+        );
+        assert!(matches!(
+            contract.transfer_shares(BOB, U256::from(401)),
+            Err(RebasingTokenError::InsufficientBalance(_))
+        ));
+    }
+
+    #[test]
+    fn test_rounding_can_short_the_recipient() {
+        let (vm, mut contract) = setup();
+        // An index of 1.1 makes 10 tokens worth 9.09 shares, rounded down to 9.
+        contract.rebase(1_000).unwrap();
+        vm.set_sender(ALICE);
+        contract.transfer(BOB, U256::from(10)).unwrap();
+        assert_eq!(contract.balance_of(BOB), U256::from(9));
+        // Alice still loses the full 10, so the balances now add up to a wei less than the supply.
+        assert_eq!(contract.balance_of(ALICE), U256::from(1_090));
+        assert_eq!(contract.total_supply(), U256::from(1_100));
+    }
+
+    #[test]
+    fn test_vault_tracking_amounts_strands_yield() {
+        let (vm, mut contract) = setup();
+        // A naive vault records the amount Alice deposits and pays back exactly that.
+        vm.set_sender(ALICE);
+        contract.approve(VAULT, U256::MAX).unwrap();
+        vm.set_sender(VAULT);
+        contract
+            .transfer_from(ALICE, VAULT, U256::from(1_000))
+            .unwrap();
+        let recorded_deposit = U256::from(1_000);
+
+        vm.set_sender(OWNER);
+        contract.rebase(200).unwrap();
+        vm.set_sender(VAULT);
+        contract.transfer(ALICE, recorded_deposit).unwrap();
+        // The 2% yield stays in the vault, where tracking shares would have returned it, and
+        // rounding the 1,000 down to 980 shares costs Alice another wei.
+        assert_eq!(contract.balance_of(ALICE), U256::from(999));
+        assert_eq!(contract.balance_of(VAULT), U256::from(20));
+    }
+
+    #[test]
+    fn test_advertises_the_erc20_abi() {
+        let (_vm, contract) = setup();
+        assert!(contract.supports_interface(ERC165_ID));
+        assert!(contract.supports_interface(ERC20_ID));
+        assert!(!contract.supports_interface(FixedBytes::new([0xff; 4])));
+    }
+
+    #[test]
+    fn test_rebase_and_mint_are_owner_only_and_bounded() {
+        let (vm, mut contract) = setup();
+        assert!(matches!(
+            contract.rebase(MAX_REBASE_BPS + 1),
+            Err(RebasingTokenError::RebaseTooLarge(_))
+        ));
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.rebase(1),
+            Err(RebasingTokenError::NotOwner(_))
+        ));
+        assert!(matches!(
+            contract.mint(ALICE, U256::from(1)),
+            Err(RebasingTokenError::NotOwner(_))
+        ));
+        assert_eq!(contract.share_index(), WAD);
+    }
+}
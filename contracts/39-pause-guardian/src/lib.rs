@@ -0,0 +1,399 @@
+//!
+//! Stylus Pause Guardian
+//!
+//! One place to stop a whole fleet of contracts in an emergency. The admin
+//! registers contracts that expose the `IPausable` interface (`pause()`,
+//! `unpause()`, `paused()`), such as the vending machine or the English
+//! auction, and the guardian pauses or unpauses all of them, or a chosen few,
+//! in a single transaction through cross-contract calls.
+//!
+//! Each target must accept `pause()` and `unpause()` from the guardian, for
+//! example by making the guardian the vending machine's owner.
+//!
+//! Two roles gate the switches, so a widely held "big red button" does not
+//! also let its holders switch things back on:
+//!
+//! - `PAUSER_ROLE` may call `pause_all` and `pause`.
+//! - `UNPAUSER_ROLE` may call `unpause_all` and `unpause`.
+//!
+//! The admin passed to the constructor holds both roles and grants or revokes
+//! them. A target that reverts does not stop the others: every call reports a
+//! success flag per target and logs `TargetCallFailed` with the revert data.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall};
+use stylus_sdk::{
+    alloy_primitives::{b256, Address, B256, U256},
+    prelude::*,
+};
+
+/// `keccak256("PAUSER_ROLE")`, as in OpenZeppelin's presets.
+pub const PAUSER_ROLE: B256 =
+    b256!("65d7a28e3265b37a6474929f336521b332c1681b933f6cb9f3376673440d862a");
+/// `keccak256("UNPAUSER_ROLE")`.
+pub const UNPAUSER_ROLE: B256 =
+    b256!("427da25fe773164f88948d3e215c94b6554e2ed5e5f203a821c9f2f6131cf75a");
+/// Most contracts the guardian will manage, which bounds the gas of `pause_all`.
+const MAX_TARGETS: usize = 32;
+
+sol! {
+    /// What the guardian calls on each registered contract.
+    interface IPausable {
+        function pause() external;
+        function unpause() external;
+        function paused() external view returns (bool);
+    }
+
+    event TargetRegistered(address indexed target);
+    event TargetRemoved(address indexed target);
+    event RoleGranted(bytes32 indexed role, address indexed account);
+    event RoleRevoked(bytes32 indexed role, address indexed account);
+    event TargetPaused(address indexed target, address indexed by);
+    event TargetUnpaused(address indexed target, address indexed by);
+    event TargetCallFailed(address indexed target, bytes reason);
+
+    #[derive(Debug)]
+    error NotAdmin(address caller);
+    #[derive(Debug)]
+    error MissingRole(bytes32 role, address account);
+    #[derive(Debug)]
+    error AlreadyRegistered(address target);
+    #[derive(Debug)]
+    error NotRegistered(address target);
+    #[derive(Debug)]
+    error TooManyTargets(uint256 max);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum GuardianError {
+    NotAdmin(NotAdmin),
+    MissingRole(MissingRole),
+    AlreadyRegistered(AlreadyRegistered),
+    NotRegistered(NotRegistered),
+    TooManyTargets(TooManyTargets),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct PauseGuardian {
+        address admin;
+        mapping(bytes32 => mapping(address => bool)) role_members;
+        address[] targets;
+        /// Index of each target in `targets`, plus one; zero means not registered.
+        mapping(address => uint256) target_position;
+    }
+}
+
+#[public]
+impl PauseGuardian {
+    /// Makes `admin` the admin and gives it both roles.
+    #[constructor]
+    pub fn constructor(&mut self, admin: Address) {
+        self.admin.set(admin);
+        for role in [PAUSER_ROLE, UNPAUSER_ROLE] {
+            self.role_members.setter(role).insert(admin, true);
+        }
+    }
+
+    pub fn grant_role(&mut self, role: B256, account: Address) -> Result<(), GuardianError> {
+        self.only_admin()?;
+        self.role_members.setter(role).insert(account, true);
+        log(self.vm(), RoleGranted { role, account });
+        Ok(())
+    }
+
+    pub fn revoke_role(&mut self, role: B256, account: Address) -> Result<(), GuardianError> {
+        self.only_admin()?;
+        self.role_members.setter(role).insert(account, false);
+        log(self.vm(), RoleRevoked { role, account });
+        Ok(())
+    }
+
+    pub fn has_role(&self, role: B256, account: Address) -> bool {
+        self.role_members.getter(role).get(account)
+    }
+
+    /// Adds `target` to the fleet. Admin only.
+    pub fn register(&mut self, target: Address) -> Result<(), GuardianError> {
+        self.only_admin()?;
+        if !self.target_position.get(target).is_zero() {
+            return Err(GuardianError::AlreadyRegistered(AlreadyRegistered {
+                target,
+            }));
+        }
+        if self.targets.len() >= MAX_TARGETS {
+            return Err(GuardianError::TooManyTargets(TooManyTargets {
+                max: U256::from(MAX_TARGETS),
+            }));
+        }
+        self.targets.push(target);
+        self.target_position
+            .insert(target, U256::from(self.targets.len()));
+        log(self.vm(), TargetRegistered { target });
+        Ok(())
+    }
+
+    /// Drops `target` from the fleet. Admin only.
+    pub fn remove(&mut self, target: Address) -> Result<(), GuardianError> {
+        self.only_admin()?;
+        let position = self.target_position.get(target);
+        if position.is_zero() {
+            return Err(GuardianError::NotRegistered(NotRegistered { target }));
+        }
+        // Swap the last target into the freed slot so the list stays dense.
+        let index = position.to::<usize>() - 1;
+        let last_index = self.targets.len() - 1;
+        if index != last_index {
+            let last = self.targets.get(last_index).unwrap_or_default();
+            if let Some(mut slot) = self.targets.setter(index) {
+                slot.set(last);
+            }
+            self.target_position.insert(last, position);
+        }
+        self.targets.pop();
+        self.target_position.delete(target);
+        log(self.vm(), TargetRemoved { target });
+        Ok(())
+    }
+
+    /// Pauses every registered target and returns whether each call succeeded.
+    pub fn pause_all(&mut self) -> Result<Vec<bool>, GuardianError> {
+        self.only_role(PAUSER_ROLE)?;
+        let targets = self.targets();
+        Ok(self.switch(&targets, true))
+    }
+
+    /// Unpauses every registered target and returns whether each call succeeded.
+    pub fn unpause_all(&mut self) -> Result<Vec<bool>, GuardianError> {
+        self.only_role(UNPAUSER_ROLE)?;
+        let targets = self.targets();
+        Ok(self.switch(&targets, false))
+    }
+
+    /// Pauses the given registered targets.
+    pub fn pause(&mut self, targets: Vec<Address>) -> Result<Vec<bool>, GuardianError> {
+        self.only_role(PAUSER_ROLE)?;
+        self.only_registered(&targets)?;
+        Ok(self.switch(&targets, true))
+    }
+
+    /// Unpauses the given registered targets.
+    pub fn unpause(&mut self, targets: Vec<Address>) -> Result<Vec<bool>, GuardianError> {
+        self.only_role(UNPAUSER_ROLE)?;
+        self.only_registered(&targets)?;
+        Ok(self.switch(&targets, false))
+    }
+
+    pub fn targets(&self) -> Vec<Address> {
+        (0..self.targets.len())
+            .filter_map(|index| self.targets.get(index))
+            .collect()
+    }
+
+    /// Returns `(target, paused)` for every target; one that cannot be queried reads as unpaused.
+    pub fn status(&self) -> Vec<(Address, bool)> {
+        let call = IPausable::pausedCall {}.abi_encode();
+        self.targets()
+            .into_iter()
+            .map(|target| {
+                let paused = self
+                    .vm()
+                    .static_call(&calls::context::Call::new(), target, &call)
+                    .ok()
+                    .and_then(|returned| {
+                        IPausable::pausedCall::abi_decode_returns(&returned, true).ok()
+                    })
+                    .is_some_and(|decoded| decoded._0);
+                (target, paused)
+            })
+            .collect()
+    }
+
+    pub fn admin(&self) -> Address {
+        self.admin.get()
+    }
+}
+
+impl PauseGuardian {
+    fn only_admin(&self) -> Result<(), GuardianError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.admin.get() {
+            return Err(GuardianError::NotAdmin(NotAdmin { caller }));
+        }
+        Ok(())
+    }
+
+    fn only_role(&self, role: B256) -> Result<(), GuardianError> {
+        let account = self.vm().msg_sender();
+        if !self.has_role(role, account) {
+            return Err(GuardianError::MissingRole(MissingRole { role, account }));
+        }
+        Ok(())
+    }
+
+    fn only_registered(&self, targets: &[Address]) -> Result<(), GuardianError> {
+        match targets
+            .iter()
+            .find(|&&target| self.target_position.get(target).is_zero())
+        {
+            Some(&target) => Err(GuardianError::NotRegistered(NotRegistered { target })),
+            None => Ok(()),
+        }
+    }
+
+    /// Calls `pause()` or `unpause()` on each target, carrying on past failures.
+    fn switch(&mut self, targets: &[Address], pause: bool) -> Vec<bool> {
+        let by = self.vm().msg_sender();
+        let data = if pause {
+            IPausable::pauseCall {}.abi_encode()
+        } else {
+            IPausable::unpauseCall {}.abi_encode()
+        };
+        targets
+            .iter()
+            .map(|&target| {
+                match self.vm().call(&calls::context::Call::new(), target, &data) {
+                    Ok(_) if pause => log(self.vm(), TargetPaused { target, by }),
+                    Ok(_) => log(self.vm(), TargetUnpaused { target, by }),
+                    Err(err) => {
+                        let reason = match err {
+                            calls::errors::Error::Revert(data) => data.into(),
+                            _ => Default::default(),
+                        };
+                        log(self.vm(), TargetCallFailed { target, reason });
+                        return false;
+                    }
+                }
+                true
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::SolEvent;
+    use stylus_sdk::testing::*;
+
+    const ADMIN: Address = Address::new([0xad; 20]);
+    const ONCALL: Address = Address::new([0x01; 20]);
+    const VENDING: Address = Address::new([0xa1; 20]);
+    const AUCTION: Address = Address::new([0xa2; 20]);
+    const STAKING: Address = Address::new([0xa3; 20]);
+
+    fn setup() -> (TestVM, PauseGuardian) {
+        let vm = TestVM::default();
+        let mut contract = PauseGuardian::from(&vm);
+        contract.constructor(ADMIN);
+        vm.set_sender(ADMIN);
+        for target in [VENDING, AUCTION, STAKING] {
+            contract.register(target).unwrap();
+        }
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_pause_all_carries_on_past_failures() {
+        let (vm, mut contract) = setup();
+        let pause = IPausable::pauseCall {}.abi_encode();
+        vm.mock_call(AUCTION, pause, Err(b"not seller".to_vec()));
+        assert_eq!(contract.pause_all().unwrap(), vec![true, false, true]);
+
+        let logs = vm.get_emitted_logs();
+        let failed: Vec<_> = logs
+            .iter()
+            .filter(|(topics, _)| topics[0] == TargetCallFailed::SIGNATURE_HASH)
+            .collect();
+        assert_eq!(failed.len(), 1);
+        let event =
+            TargetCallFailed::decode_raw_log(failed[0].0.iter().copied(), &failed[0].1, true)
+                .unwrap();
+        assert_eq!(event.target, AUCTION);
+        assert_eq!(event.reason.as_ref(), b"not seller");
+        let paused = logs
+            .iter()
+            .filter(|(topics, _)| topics[0] == TargetPaused::SIGNATURE_HASH)
+            .count();
+        assert_eq!(paused, 2);
+    }
+
+    #[test]
+    fn test_roles_split_pausing_from_unpausing() {
+        let (vm, mut contract) = setup();
+        contract.grant_role(PAUSER_ROLE, ONCALL).unwrap();
+
+        vm.set_sender(ONCALL);
+        assert_eq!(contract.pause(vec![VENDING]).unwrap(), vec![true]);
+        assert!(matches!(
+            contract.unpause_all(),
+            Err(GuardianError::MissingRole(MissingRole { role, .. })) if role == UNPAUSER_ROLE
+        ));
+        assert!(matches!(
+            contract.grant_role(UNPAUSER_ROLE, ONCALL),
+            Err(GuardianError::NotAdmin(_))
+        ));
+
+        vm.set_sender(ADMIN);
+        contract.unpause(vec![VENDING]).unwrap();
+        contract.revoke_role(PAUSER_ROLE, ONCALL).unwrap();
+        vm.set_sender(ONCALL);
+        assert!(contract.pause_all().is_err());
+    }
+
+    #[test]
+    fn test_registry_is_admin_managed() {
+        let (vm, mut contract) = setup();
+        assert!(matches!(
+            contract.register(VENDING),
+            Err(GuardianError::AlreadyRegistered(_))
+        ));
+        contract.remove(VENDING).unwrap();
+        assert_eq!(contract.targets(), vec![STAKING, AUCTION]);
+        assert!(matches!(
+            contract.pause(vec![AUCTION, VENDING]),
+            Err(GuardianError::NotRegistered(NotRegistered { target })) if target == VENDING
+        ));
+        assert!(contract.remove(VENDING).is_err());
+
+        vm.set_sender(ONCALL);
+        assert!(matches!(
+            contract.register(VENDING),
+            Err(GuardianError::NotAdmin(_))
+        ));
+    }
+
+    #[test]
+    fn test_register_is_capped() {
+        let (_vm, mut contract) = setup();
+        for i in 3..MAX_TARGETS {
+            contract.register(Address::from([i as u8; 20])).unwrap();
+        }
+        assert!(matches!(
+            contract.register(Address::from([0xff; 20])),
+            Err(GuardianError::TooManyTargets(_))
+        ));
+    }
+
+    #[test]
+    fn test_status_queries_each_target() {
+        let (vm, contract) = setup();
+        let call = IPausable::pausedCall {}.abi_encode();
+        let returns = |paused: bool| IPausable::pausedCall::abi_encode_returns(&(paused,));
+        vm.mock_static_call(VENDING, call.clone(), Ok(returns(true)));
+        vm.mock_static_call(AUCTION, call.clone(), Ok(returns(false)));
+        vm.mock_static_call(STAKING, call, Err(Vec::new()));
+        assert_eq!(
+            contract.status(),
+            vec![(VENDING, true), (AUCTION, false), (STAKING, false)]
+        );
+    }
+}
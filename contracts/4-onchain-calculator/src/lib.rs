@@ -0,0 +1,99 @@
+//!
+//! Stylus On-chain Calculator
+//!
+//! Parses and evaluates arithmetic expression strings such as `"(2.5+3)*4"`
+//! with a recursive-descent parser, using `rust_decimal`'s checked arithmetic
+//! so overflow and division by zero revert instead of wrapping or panicking.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+// Allow `cargo stylus export-abi` to generate a main function.
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+mod parser;
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use stylus_sdk::{alloy_primitives::Address, prelude::*};
+
+sol_storage! {
+    #[entrypoint]
+    pub struct Calculator {
+        /// Result of the last expression each user had evaluated on-chain.
+        mapping(address => string) last_results;
+    }
+}
+
+#[public]
+impl Calculator {
+    /// Evaluates an expression without touching storage.
+    pub fn evaluate(&self, expression: String) -> Result<String, Vec<u8>> {
+        parser::evaluate(&expression)
+            .map(|value| value.to_string())
+            .map_err(|err| err.message().as_bytes().to_vec())
+    }
+
+    /// Evaluates an expression and stores the result for the caller.
+    pub fn calculate(&mut self, expression: String) -> Result<String, Vec<u8>> {
+        let result = self.evaluate(expression)?;
+        let caller = self.vm().msg_sender();
+        self.last_results.setter(caller).set_str(&result);
+        Ok(result)
+    }
+
+    /// Retrieves the last stored result for a specific user.
+    pub fn get_last_result(&self, user: Address) -> String {
+        self.last_results.getter(user).get_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    fn setup() -> (TestVM, Calculator, Address) {
+        let vm = TestVM::default();
+        let contract = Calculator::from(&vm);
+        let user = Address::from([0x01; 20]);
+        vm.set_sender(user);
+        (vm, contract, user)
+    }
+
+    #[test]
+    fn test_evaluate_does_not_store() {
+        let (_vm, contract, user) = setup();
+        assert_eq!(contract.evaluate("(2.5+3)*4".to_string()).unwrap(), "22");
+        assert_eq!(contract.get_last_result(user), "");
+    }
+
+    #[test]
+    fn test_calculate_stores_result_per_user() {
+        let (vm, mut contract, user) = setup();
+        assert_eq!(contract.calculate("1 / 8".to_string()).unwrap(), "0.125");
+        assert_eq!(contract.get_last_result(user), "0.125");
+
+        let other = Address::from([0x02; 20]);
+        vm.set_sender(other);
+        contract.calculate("-3 * (2 + 1)".to_string()).unwrap();
+        assert_eq!(contract.get_last_result(other), "-9");
+        assert_eq!(contract.get_last_result(user), "0.125");
+    }
+
+    #[test]
+    fn test_calculate_reverts_with_reason() {
+        let (_vm, mut contract, user) = setup();
+        let err = contract.calculate("10 / (5 - 5)".to_string()).unwrap_err();
+        assert_eq!(err, b"Division by zero".to_vec());
+
+        let err = contract.calculate("2 ** 3".to_string()).unwrap_err();
+        assert_eq!(err, b"Unexpected character".to_vec());
+        assert_eq!(contract.get_last_result(user), "");
+    }
+}
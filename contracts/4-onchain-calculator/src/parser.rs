@@ -0,0 +1,216 @@
+//! Recursive-descent evaluator for arithmetic expressions.
+//!
+//! ```text
+//! expr   := term (('+' | '-') term)*
+//! term   := factor (('*' | '/') factor)*
+//! factor := ('+' | '-') factor | number | '(' expr ')'
+//! ```
+//!
+//! Every operation goes through `rust_decimal`'s checked arithmetic, so overflow
+//! and division by zero surface as errors instead of panics.
+
+use core::str::FromStr;
+use rust_decimal::Decimal;
+
+/// Longest expression accepted, in bytes.
+pub const MAX_EXPRESSION_LEN: usize = 256;
+
+/// Deepest nesting of parentheses and unary signs. Keeps recursion well inside
+/// the 32 KiB stack the contract is linked with.
+pub const MAX_DEPTH: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EvalError {
+    Empty,
+    TooLong,
+    TooDeep,
+    UnexpectedChar(usize),
+    UnexpectedEnd,
+    InvalidNumber,
+    Overflow,
+    DivisionByZero,
+}
+
+impl EvalError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            EvalError::Empty => "Empty expression",
+            EvalError::TooLong => "Expression too long",
+            EvalError::TooDeep => "Expression nested too deeply",
+            EvalError::UnexpectedChar(_) => "Unexpected character",
+            EvalError::UnexpectedEnd => "Unexpected end of expression",
+            EvalError::InvalidNumber => "Invalid number",
+            EvalError::Overflow => "Arithmetic overflow",
+            EvalError::DivisionByZero => "Division by zero",
+        }
+    }
+}
+
+/// Parses and evaluates `input`.
+pub fn evaluate(input: &str) -> Result<Decimal, EvalError> {
+    if input.len() > MAX_EXPRESSION_LEN {
+        return Err(EvalError::TooLong);
+    }
+    let mut parser = Parser {
+        input: input.as_bytes(),
+        pos: 0,
+        depth: 0,
+    };
+    if parser.peek().is_none() {
+        return Err(EvalError::Empty);
+    }
+    let value = parser.expr()?;
+    match parser.peek() {
+        None => Ok(value.normalize()),
+        Some(_) => Err(EvalError::UnexpectedChar(parser.pos)),
+    }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    /// Next non-whitespace byte, without consuming it.
+    fn peek(&mut self) -> Option<u8> {
+        while self
+            .input
+            .get(self.pos)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            self.pos += 1;
+        }
+        self.input.get(self.pos).copied()
+    }
+
+    fn expr(&mut self) -> Result<Decimal, EvalError> {
+        let mut value = self.term()?;
+        while let Some(op @ (b'+' | b'-')) = self.peek() {
+            self.pos += 1;
+            let rhs = self.term()?;
+            value = match op {
+                b'+' => value.checked_add(rhs),
+                _ => value.checked_sub(rhs),
+            }
+            .ok_or(EvalError::Overflow)?;
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<Decimal, EvalError> {
+        let mut value = self.factor()?;
+        while let Some(op @ (b'*' | b'/')) = self.peek() {
+            self.pos += 1;
+            let rhs = self.factor()?;
+            value = match op {
+                b'*' => value.checked_mul(rhs).ok_or(EvalError::Overflow)?,
+                _ if rhs.is_zero() => return Err(EvalError::DivisionByZero),
+                _ => value.checked_div(rhs).ok_or(EvalError::Overflow)?,
+            };
+        }
+        Ok(value)
+    }
+
+    fn factor(&mut self) -> Result<Decimal, EvalError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(EvalError::TooDeep);
+        }
+        let value = match self.peek() {
+            None => Err(EvalError::UnexpectedEnd),
+            Some(b'+') => {
+                self.pos += 1;
+                self.factor()
+            }
+            Some(b'-') => {
+                self.pos += 1;
+                self.factor().map(|v| -v)
+            }
+            Some(b'(') => {
+                self.pos += 1;
+                let inner = self.expr()?;
+                match self.peek() {
+                    Some(b')') => {
+                        self.pos += 1;
+                        Ok(inner)
+                    }
+                    Some(_) => Err(EvalError::UnexpectedChar(self.pos)),
+                    None => Err(EvalError::UnexpectedEnd),
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == b'.' => self.number(),
+            Some(_) => Err(EvalError::UnexpectedChar(self.pos)),
+        };
+        self.depth -= 1;
+        value
+    }
+
+    fn number(&mut self) -> Result<Decimal, EvalError> {
+        let start = self.pos;
+        while self
+            .input
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_digit() || *c == b'.')
+        {
+            self.pos += 1;
+        }
+        // Only ASCII digits and dots were consumed, so this slice is valid UTF-8.
+        let text = core::str::from_utf8(&self.input[start..self.pos]).unwrap_or_default();
+        Decimal::from_str(text).map_err(|_| EvalError::InvalidNumber)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn eval(input: &str) -> Result<Decimal, EvalError> {
+        evaluate(input)
+    }
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn test_precedence_and_parentheses() {
+        assert_eq!(eval("(2.5+3)*4"), Ok(dec("22")));
+        assert_eq!(eval("2.5 + 3 * 4"), Ok(dec("14.5")));
+        assert_eq!(eval("10 - 4 - 3"), Ok(dec("3")));
+        assert_eq!(eval("1 / 4"), Ok(dec("0.25")));
+        assert_eq!(eval("-(2 - 5) * -2"), Ok(dec("-6")));
+    }
+
+    #[test]
+    fn test_rejects_malformed_input() {
+        assert_eq!(eval(""), Err(EvalError::Empty));
+        assert_eq!(eval("   "), Err(EvalError::Empty));
+        assert_eq!(eval("2 +"), Err(EvalError::UnexpectedEnd));
+        assert_eq!(eval("(1 + 2"), Err(EvalError::UnexpectedEnd));
+        assert_eq!(eval("1 + 2)"), Err(EvalError::UnexpectedChar(5)));
+        assert_eq!(eval("2 ^ 3"), Err(EvalError::UnexpectedChar(2)));
+        assert_eq!(eval("1.2.3"), Err(EvalError::InvalidNumber));
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        assert_eq!(eval("1 / (2 - 2)"), Err(EvalError::DivisionByZero));
+        let max = Decimal::MAX.to_string();
+        assert_eq!(eval(&format!("{max} * 2")), Err(EvalError::Overflow));
+        assert_eq!(eval(&format!("{max} + 1")), Err(EvalError::Overflow));
+    }
+
+    #[test]
+    fn test_limits() {
+        let deep = format!(
+            "{}1{}",
+            "(".repeat(MAX_DEPTH + 1),
+            ")".repeat(MAX_DEPTH + 1)
+        );
+        assert_eq!(eval(&deep), Err(EvalError::TooDeep));
+        let long = "1+".repeat(MAX_EXPRESSION_LEN / 2) + "1";
+        assert_eq!(eval(&long), Err(EvalError::TooLong));
+    }
+}
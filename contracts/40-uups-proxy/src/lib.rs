@@ -0,0 +1,193 @@
+//!
+//! Stylus UUPS Proxy
+//!
+//! The proxy half of an upgradeable contract (see `uups-counter` for the
+//! implementation half). Users always talk to the proxy's address. It keeps
+//! all state, and its `#[fallback]` forwards every call to the current
+//! implementation with `delegate_call`, so the implementation's code runs
+//! against the proxy's storage.
+//!
+//! The proxy has no public methods of its own, so none of its selectors can
+//! clash with the implementation's. That is the UUPS ("universal upgradeable
+//! proxy standard", ERC-1822) style: the upgrade function lives in the
+//! implementation, not in the proxy.
+//!
+//! The implementation address lives in the ERC-1967 slot
+//! `keccak256("eip1967.proxy.implementation") - 1` rather than slot 0. An
+//! implementation laid out by `sol_storage!` starts at slot 0, so its fields
+//! never overwrite the pointer. Explorers also read this slot to find the
+//! implementation.
+//!
+//! The constructor takes the first implementation and the calldata for its
+//! `initialize` function. It runs that call in the same transaction, so nobody
+//! can front-run the initialization.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{b256, Address, B256, U256},
+    prelude::*,
+    ArbResult,
+};
+
+/// `keccak256("eip1967.proxy.implementation") - 1`.
+pub const IMPLEMENTATION_SLOT: B256 =
+    b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+
+sol! {
+    event Upgraded(address indexed implementation);
+
+    #[derive(Debug)]
+    error InitializationFailed(bytes reason);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum ProxyError {
+    InitializationFailed(InitializationFailed),
+}
+
+sol_storage! {
+    /// Deliberately empty: all proxy state sits in the ERC-1967 slot.
+    #[entrypoint]
+    pub struct UupsProxy {}
+}
+
+#[public]
+impl UupsProxy {
+    /// Points the proxy at `implementation` and, if `data` is non-empty,
+    /// delegate-calls it (normally an `initialize(...)` call).
+    #[constructor]
+    #[payable]
+    pub fn constructor(&mut self, implementation: Address, data: Bytes) -> Result<(), ProxyError> {
+        self.set_implementation(implementation);
+        log(self.vm(), Upgraded { implementation });
+        if !data.is_empty() {
+            // SAFETY: the deployer chose the implementation.
+            unsafe {
+                self.vm()
+                    .delegate_call(&calls::context::Call::new(), implementation, &data)
+            }
+            .map_err(|err| {
+                ProxyError::InitializationFailed(InitializationFailed {
+                    reason: revert_data(err).into(),
+                })
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Forwards every call to the implementation and passes its result back.
+    #[fallback]
+    #[payable]
+    pub fn fallback(&mut self, calldata: &[u8]) -> ArbResult {
+        let implementation = self.implementation();
+        // SAFETY: the implementation is trusted with this contract's storage;
+        // only its own `upgrade_to` can change which one that is.
+        unsafe {
+            self.vm()
+                .delegate_call(&calls::context::Call::new(), implementation, calldata)
+        }
+        .map_err(revert_data)
+    }
+}
+
+impl UupsProxy {
+    pub fn implementation(&self) -> Address {
+        let word = self
+            .vm()
+            .storage_load_bytes32(U256::from_be_bytes(IMPLEMENTATION_SLOT.0));
+        Address::from_word(word)
+    }
+
+    fn set_implementation(&mut self, implementation: Address) {
+        // SAFETY: the ERC-1967 slot is outside every `sol_storage!` layout.
+        unsafe {
+            self.vm().storage_cache_bytes32(
+                U256::from_be_bytes(IMPLEMENTATION_SLOT.0),
+                implementation.into_word(),
+            );
+        }
+        self.vm().flush_cache(false);
+    }
+}
+
+/// The raw revert data, so callers see the implementation's own error.
+fn revert_data(err: calls::errors::Error) -> Vec<u8> {
+    match err {
+        calls::errors::Error::Revert(data) => data,
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::{SolCall, SolValue};
+    use stylus_sdk::testing::*;
+
+    const COUNTER_V1: Address = Address::new([0xc1; 20]);
+
+    sol! {
+        interface ICounter {
+            function initialize(address owner) external;
+            function increment() external returns (uint256);
+        }
+    }
+
+    #[test]
+    fn test_constructor_stores_implementation_in_erc1967_slot() {
+        let vm = TestVM::default();
+        let mut contract = UupsProxy::from(&vm);
+        contract
+            .constructor(COUNTER_V1, Bytes::from(Vec::new()))
+            .unwrap();
+
+        assert_eq!(contract.implementation(), COUNTER_V1);
+        // Slot 0, where the implementation's first field lives, stays untouched.
+        assert_eq!(vm.storage_load_bytes32(U256::ZERO), B256::ZERO);
+    }
+
+    #[test]
+    fn test_fallback_forwards_calldata_and_result() {
+        let vm = TestVM::default();
+        let mut contract = UupsProxy::from(&vm);
+        contract
+            .constructor(COUNTER_V1, Bytes::from(Vec::new()))
+            .unwrap();
+
+        let call = ICounter::incrementCall {}.abi_encode();
+        let result = U256::from(1).abi_encode();
+        vm.mock_delegate_call(COUNTER_V1, call.clone(), Ok(result.clone()));
+        assert_eq!(contract.fallback(&call).unwrap(), result);
+
+        vm.mock_delegate_call(COUNTER_V1, call.clone(), Err(b"paused".to_vec()));
+        assert_eq!(contract.fallback(&call).unwrap_err(), b"paused".to_vec());
+    }
+
+    #[test]
+    fn test_constructor_surfaces_initialize_failure() {
+        let vm = TestVM::default();
+        let mut contract = UupsProxy::from(&vm);
+        let init = ICounter::initializeCall {
+            owner: Address::new([0xad; 20]),
+        }
+        .abi_encode();
+        vm.mock_delegate_call(COUNTER_V1, init.clone(), Err(b"initialized".to_vec()));
+
+        match contract.constructor(COUNTER_V1, init.into()) {
+            Err(ProxyError::InitializationFailed(err)) => {
+                assert_eq!(err.reason.as_ref(), b"initialized")
+            }
+            other => panic!("expected InitializationFailed, got {other:?}"),
+        }
+    }
+}
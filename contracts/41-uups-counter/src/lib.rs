@@ -0,0 +1,248 @@
+//!
+//! Stylus UUPS Counter
+//!
+//! The implementation half of an upgradeable contract, deployed behind
+//! `uups-proxy`. Users call the proxy. The proxy delegate-calls this code,
+//! so `count`, `owner` and `initialized` below are stored in the proxy's
+//! storage. The copies in this contract's own storage are never used.
+//!
+//! Two things differ from an ordinary contract:
+//!
+//! - **No constructor state.** A constructor only writes the implementation's
+//!   own storage, which the proxy never sees. Setup happens in `initialize`,
+//!   which the proxy calls once on deployment, guarded by an `initialized`
+//!   flag. The constructor here does the opposite: it marks the
+//!   implementation itself as initialized, so nobody can initialize it
+//!   directly and claim ownership of it.
+//! - **The upgrade lives here.** `upgrade_to` is owner-only and rewrites the
+//!   proxy's ERC-1967 implementation slot. It first asks the new
+//!   implementation for `proxiableUUID()` and refuses anything that does not
+//!   return that slot. A contract without `upgrade_to` would leave the proxy
+//!   unable to upgrade ever again.
+//!
+//! To upgrade, deploy a new build (here, with `VERSION` bumped) and call
+//! `upgrade_to` through the proxy. A new version may append storage fields
+//! but must never reorder or remove existing ones, or it will read the old
+//! values from the wrong slots.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall};
+use stylus_sdk::{
+    alloy_primitives::{b256, Address, B256, U256},
+    prelude::*,
+};
+
+/// `keccak256("eip1967.proxy.implementation") - 1`, the slot `uups-proxy` reads.
+pub const IMPLEMENTATION_SLOT: B256 =
+    b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+/// Bump this in the next build to see the upgrade take effect.
+pub const VERSION: u64 = 1;
+
+sol! {
+    /// ERC-1822: what a UUPS implementation reports about itself.
+    interface IERC1822Proxiable {
+        function proxiableUUID() external view returns (bytes32);
+    }
+
+    event Initialized(address indexed owner);
+    event Upgraded(address indexed implementation);
+    event Incremented(uint256 count);
+
+    #[derive(Debug)]
+    error AlreadyInitialized();
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error NotUpgradeable(address implementation);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum CounterError {
+    AlreadyInitialized(AlreadyInitialized),
+    NotOwner(NotOwner),
+    NotUpgradeable(NotUpgradeable),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct UupsCounter {
+        bool initialized;
+        address owner;
+        uint256 count;
+    }
+}
+
+#[public]
+impl UupsCounter {
+    /// Locks the implementation's own storage so it can never be initialized.
+    #[constructor]
+    pub fn constructor(&mut self) {
+        self.initialized.set(true);
+    }
+
+    /// One-time setup, called by the proxy in its constructor.
+    pub fn initialize(&mut self, owner: Address) -> Result<(), CounterError> {
+        if self.initialized.get() {
+            return Err(CounterError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        self.initialized.set(true);
+        self.owner.set(owner);
+        log(self.vm(), Initialized { owner });
+        Ok(())
+    }
+
+    pub fn increment(&mut self) -> U256 {
+        let count = self.count.get() + U256::from(1);
+        self.count.set(count);
+        log(self.vm(), Incremented { count });
+        count
+    }
+
+    pub fn count(&self) -> U256 {
+        self.count.get()
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    pub fn version(&self) -> u64 {
+        VERSION
+    }
+
+    /// Points the proxy at `new_implementation`. Owner only; call it through the proxy.
+    pub fn upgrade_to(&mut self, new_implementation: Address) -> Result<(), CounterError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(CounterError::NotOwner(NotOwner { caller }));
+        }
+        let call = IERC1822Proxiable::proxiableUUIDCall {}.abi_encode();
+        let uuid = self
+            .vm()
+            .static_call(&calls::context::Call::new(), new_implementation, &call)
+            .ok()
+            .and_then(|returned| {
+                IERC1822Proxiable::proxiableUUIDCall::abi_decode_returns(&returned, true).ok()
+            })
+            .map(|decoded| decoded._0);
+        if uuid != Some(IMPLEMENTATION_SLOT) {
+            return Err(CounterError::NotUpgradeable(NotUpgradeable {
+                implementation: new_implementation,
+            }));
+        }
+        // SAFETY: the ERC-1967 slot is outside the `sol_storage!` layout above.
+        unsafe {
+            self.vm().storage_cache_bytes32(
+                U256::from_be_bytes(IMPLEMENTATION_SLOT.0),
+                new_implementation.into_word(),
+            );
+        }
+        self.vm().flush_cache(false);
+        log(
+            self.vm(),
+            Upgraded {
+                implementation: new_implementation,
+            },
+        );
+        Ok(())
+    }
+
+    /// The ERC-1967 slot this implementation upgrades, per ERC-1822.
+    #[selector(name = "proxiableUUID")]
+    pub fn proxiable_uuid(&self) -> B256 {
+        IMPLEMENTATION_SLOT
+    }
+
+    /// The current implementation, when called through the proxy.
+    pub fn implementation(&self) -> Address {
+        let word = self
+            .vm()
+            .storage_load_bytes32(U256::from_be_bytes(IMPLEMENTATION_SLOT.0));
+        Address::from_word(word)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::new([0xad; 20]);
+    const ATTACKER: Address = Address::new([0xee; 20]);
+    const COUNTER_V2: Address = Address::new([0xc2; 20]);
+
+    /// Storage as a fresh proxy sees it: the constructor never ran there.
+    fn behind_proxy() -> (TestVM, UupsCounter) {
+        let vm = TestVM::default();
+        let mut contract = UupsCounter::from(&vm);
+        contract.initialize(OWNER).unwrap();
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_implementation_cannot_be_initialized() {
+        let vm = TestVM::default();
+        let mut contract = UupsCounter::from(&vm);
+        contract.constructor();
+        vm.set_sender(ATTACKER);
+        assert!(matches!(
+            contract.initialize(ATTACKER),
+            Err(CounterError::AlreadyInitialized(_))
+        ));
+        assert_eq!(contract.owner(), Address::ZERO);
+    }
+
+    #[test]
+    fn test_initialize_runs_once() {
+        let (_vm, mut contract) = behind_proxy();
+        assert_eq!(contract.owner(), OWNER);
+        assert!(matches!(
+            contract.initialize(ATTACKER),
+            Err(CounterError::AlreadyInitialized(_))
+        ));
+        assert_eq!(contract.increment(), U256::from(1));
+        assert_eq!(contract.count(), U256::from(1));
+    }
+
+    #[test]
+    fn test_upgrade_to_writes_erc1967_slot() {
+        let (vm, mut contract) = behind_proxy();
+        contract.increment();
+        let call = IERC1822Proxiable::proxiableUUIDCall {}.abi_encode();
+        let returns =
+            IERC1822Proxiable::proxiableUUIDCall::abi_encode_returns(&(IMPLEMENTATION_SLOT,));
+        vm.mock_static_call(COUNTER_V2, call, Ok(returns));
+
+        vm.set_sender(OWNER);
+        contract.upgrade_to(COUNTER_V2).unwrap();
+        assert_eq!(contract.implementation(), COUNTER_V2);
+        // State written by the old version is still there for the new one.
+        assert_eq!(contract.count(), U256::from(1));
+    }
+
+    #[test]
+    fn test_upgrade_to_rejects_non_owner_and_non_uups_target() {
+        let (vm, mut contract) = behind_proxy();
+        vm.set_sender(ATTACKER);
+        assert!(matches!(
+            contract.upgrade_to(COUNTER_V2),
+            Err(CounterError::NotOwner(_))
+        ));
+
+        // Nothing answers `proxiableUUID()` at COUNTER_V2, e.g. a plain contract.
+        vm.set_sender(OWNER);
+        assert!(matches!(
+            contract.upgrade_to(COUNTER_V2),
+            Err(CounterError::NotUpgradeable(_))
+        ));
+        assert_eq!(contract.implementation(), Address::ZERO);
+    }
+}
@@ -0,0 +1,513 @@
+//!
+//! Stylus OTC Escrow
+//!
+//! A swap of two ERC-20 tokens between two named parties, agreed over the
+//! counter. The proposer records the terms with `propose`: they give
+//! `amount_a` of `token_a` to the counterparty, who gives `amount_b` of
+//! `token_b` in return, before `deadline`.
+//!
+//! Each party then calls `deposit` for their own leg, in either order, after
+//! approving this contract on their token. The deposit that completes the
+//! pair settles the deal in the same call: each party receives the other's
+//! leg. Neither party can take the other's tokens without giving up their
+//! own, because both transfers happen in one transaction or not at all.
+//!
+//! If the deadline passes before both legs are in, the deal can no longer
+//! settle and each party can `reclaim` whatever they deposited.
+//!
+//! Flags are written before every token call (checks-effects-interactions),
+//! so a token that calls back into the escrow finds the deal already updated.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloy_sol_types::sol;
+use common::token::{safe_transfer, safe_transfer_from};
+use stylus_sdk::{
+    alloy_primitives::{Address, U256, U64},
+    prelude::*,
+};
+
+/// `(party_a, party_b, token_a, amount_a, token_b, amount_b, deadline)`.
+pub type DealTerms = (Address, Address, Address, U256, Address, U256, u64);
+
+sol! {
+    event DealProposed(uint256 indexed id, address indexed partyA, address indexed partyB, uint64 deadline);
+    event Deposited(uint256 indexed id, address indexed party, address token, uint256 amount);
+    event Settled(uint256 indexed id);
+    event Reclaimed(uint256 indexed id, address indexed party, address token, uint256 amount);
+
+    #[derive(Debug)]
+    error InvalidTerms();
+    #[derive(Debug)]
+    error DealNotFound(uint256 id);
+    #[derive(Debug)]
+    error NotAParty(uint256 id, address caller);
+    #[derive(Debug)]
+    error AlreadyDeposited(uint256 id, address party);
+    #[derive(Debug)]
+    error AlreadySettled(uint256 id);
+    #[derive(Debug)]
+    error DeadlinePassed(uint256 id, uint64 deadline);
+    #[derive(Debug)]
+    error DeadlineNotReached(uint256 id, uint64 deadline);
+    #[derive(Debug)]
+    error NothingToReclaim(uint256 id, address party);
+    #[derive(Debug)]
+    error TransferFailed(address token, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum OtcEscrowError {
+    InvalidTerms(InvalidTerms),
+    DealNotFound(DealNotFound),
+    NotAParty(NotAParty),
+    AlreadyDeposited(AlreadyDeposited),
+    AlreadySettled(AlreadySettled),
+    DeadlinePassed(DeadlinePassed),
+    DeadlineNotReached(DeadlineNotReached),
+    NothingToReclaim(NothingToReclaim),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    /// Party A gives `amount_a` of `token_a`; party B gives `amount_b` of `token_b`.
+    pub struct Deal {
+        address party_a;
+        address party_b;
+        address token_a;
+        uint256 amount_a;
+        address token_b;
+        uint256 amount_b;
+        uint64 deadline;
+        /// Whether each leg is currently held by the escrow.
+        bool deposited_a;
+        bool deposited_b;
+        bool settled;
+    }
+
+    #[entrypoint]
+    pub struct OtcEscrow {
+        mapping(uint256 => Deal) deals;
+        uint256 deal_count;
+    }
+}
+
+#[public]
+impl OtcEscrow {
+    /// Records a swap between the caller (party A) and `counterparty` (party B); returns its id.
+    pub fn propose(
+        &mut self,
+        counterparty: Address,
+        token_a: Address,
+        amount_a: U256,
+        token_b: Address,
+        amount_b: U256,
+        deadline: u64,
+    ) -> Result<U256, OtcEscrowError> {
+        let proposer = self.vm().msg_sender();
+        if counterparty.is_zero()
+            || counterparty == proposer
+            || token_a.is_zero()
+            || token_b.is_zero()
+            || token_a == token_b
+            || amount_a.is_zero()
+            || amount_b.is_zero()
+            || deadline <= self.vm().block_timestamp()
+        {
+            return Err(OtcEscrowError::InvalidTerms(InvalidTerms {}));
+        }
+
+        let id = self.deal_count.get() + U256::from(1);
+        self.deal_count.set(id);
+        let mut deal = self.deals.setter(id);
+        deal.party_a.set(proposer);
+        deal.party_b.set(counterparty);
+        deal.token_a.set(token_a);
+        deal.amount_a.set(amount_a);
+        deal.token_b.set(token_b);
+        deal.amount_b.set(amount_b);
+        deal.deadline.set(U64::from(deadline));
+        log(
+            self.vm(),
+            DealProposed {
+                id,
+                partyA: proposer,
+                partyB: counterparty,
+                deadline,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Pulls the caller's leg of deal `id`, settling the deal if the other leg is already in.
+    pub fn deposit(&mut self, id: U256) -> Result<(), OtcEscrowError> {
+        let caller = self.vm().msg_sender();
+        let is_a = self.party_side(id, caller)?;
+        let deal = self.deals.getter(id);
+        if deal.settled.get() {
+            return Err(OtcEscrowError::AlreadySettled(AlreadySettled { id }));
+        }
+        let deadline = deal.deadline.get().to::<u64>();
+        if self.vm().block_timestamp() >= deadline {
+            return Err(OtcEscrowError::DeadlinePassed(DeadlinePassed {
+                id,
+                deadline,
+            }));
+        }
+        let (deposited, other_deposited) = if is_a {
+            (deal.deposited_a.get(), deal.deposited_b.get())
+        } else {
+            (deal.deposited_b.get(), deal.deposited_a.get())
+        };
+        if deposited {
+            return Err(OtcEscrowError::AlreadyDeposited(AlreadyDeposited {
+                id,
+                party: caller,
+            }));
+        }
+        let (token, amount) = self.leg(id, is_a);
+
+        self.set_deposited(id, is_a, true);
+        let escrow = self.vm().contract_address();
+        self.pull(token, caller, escrow, amount)?;
+        log(
+            self.vm(),
+            Deposited {
+                id,
+                party: caller,
+                token,
+                amount,
+            },
+        );
+
+        if other_deposited {
+            self.settle(id)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the caller's deposited leg once the deadline has passed without settlement.
+    pub fn reclaim(&mut self, id: U256) -> Result<(), OtcEscrowError> {
+        let caller = self.vm().msg_sender();
+        let is_a = self.party_side(id, caller)?;
+        let deal = self.deals.getter(id);
+        if deal.settled.get() {
+            return Err(OtcEscrowError::AlreadySettled(AlreadySettled { id }));
+        }
+        let deadline = deal.deadline.get().to::<u64>();
+        if self.vm().block_timestamp() < deadline {
+            return Err(OtcEscrowError::DeadlineNotReached(DeadlineNotReached {
+                id,
+                deadline,
+            }));
+        }
+        let deposited = if is_a {
+            deal.deposited_a.get()
+        } else {
+            deal.deposited_b.get()
+        };
+        if !deposited {
+            return Err(OtcEscrowError::NothingToReclaim(NothingToReclaim {
+                id,
+                party: caller,
+            }));
+        }
+        let (token, amount) = self.leg(id, is_a);
+
+        self.set_deposited(id, is_a, false);
+        self.push(token, caller, amount)?;
+        log(
+            self.vm(),
+            Reclaimed {
+                id,
+                party: caller,
+                token,
+                amount,
+            },
+        );
+        Ok(())
+    }
+
+    /// Returns the terms of deal `id` as a `DealTerms` tuple.
+    pub fn deal_terms(&self, id: U256) -> Result<DealTerms, OtcEscrowError> {
+        self.existing(id)?;
+        let deal = self.deals.getter(id);
+        Ok((
+            deal.party_a.get(),
+            deal.party_b.get(),
+            deal.token_a.get(),
+            deal.amount_a.get(),
+            deal.token_b.get(),
+            deal.amount_b.get(),
+            deal.deadline.get().to(),
+        ))
+    }
+
+    /// Returns `(deposited_a, deposited_b, settled)`.
+    pub fn deal_status(&self, id: U256) -> Result<(bool, bool, bool), OtcEscrowError> {
+        self.existing(id)?;
+        let deal = self.deals.getter(id);
+        Ok((
+            deal.deposited_a.get(),
+            deal.deposited_b.get(),
+            deal.settled.get(),
+        ))
+    }
+
+    pub fn deal_count(&self) -> U256 {
+        self.deal_count.get()
+    }
+}
+
+impl OtcEscrow {
+    fn existing(&self, id: U256) -> Result<(), OtcEscrowError> {
+        if id.is_zero() || id > self.deal_count.get() {
+            return Err(OtcEscrowError::DealNotFound(DealNotFound { id }));
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if `caller` is party A of deal `id`, `false` if party B.
+    fn party_side(&self, id: U256, caller: Address) -> Result<bool, OtcEscrowError> {
+        self.existing(id)?;
+        let deal = self.deals.getter(id);
+        if caller == deal.party_a.get() {
+            Ok(true)
+        } else if caller == deal.party_b.get() {
+            Ok(false)
+        } else {
+            Err(OtcEscrowError::NotAParty(NotAParty { id, caller }))
+        }
+    }
+
+    /// The `(token, amount)` party A (`is_a`) or party B owes.
+    fn leg(&self, id: U256, is_a: bool) -> (Address, U256) {
+        let deal = self.deals.getter(id);
+        if is_a {
+            (deal.token_a.get(), deal.amount_a.get())
+        } else {
+            (deal.token_b.get(), deal.amount_b.get())
+        }
+    }
+
+    fn set_deposited(&mut self, id: U256, is_a: bool, value: bool) {
+        let mut deal = self.deals.setter(id);
+        if is_a {
+            deal.deposited_a.set(value);
+        } else {
+            deal.deposited_b.set(value);
+        }
+    }
+
+    /// Sends each leg to the other party. Both transfers succeed or the whole call reverts.
+    fn settle(&mut self, id: U256) -> Result<(), OtcEscrowError> {
+        let deal = self.deals.getter(id);
+        let (party_a, party_b) = (deal.party_a.get(), deal.party_b.get());
+        let (token_a, amount_a) = self.leg(id, true);
+        let (token_b, amount_b) = self.leg(id, false);
+
+        let mut deal = self.deals.setter(id);
+        deal.settled.set(true);
+        deal.deposited_a.set(false);
+        deal.deposited_b.set(false);
+        self.push(token_a, party_b, amount_a)?;
+        self.push(token_b, party_a, amount_b)?;
+        log(self.vm(), Settled { id });
+        Ok(())
+    }
+
+    fn pull(
+        &self,
+        token: Address,
+        from: Address,
+        to: Address,
+        amount: U256,
+    ) -> Result<(), OtcEscrowError> {
+        safe_transfer_from(self.vm(), token, from, to, amount)
+            .map_err(|_| OtcEscrowError::TransferFailed(TransferFailed { token, amount }))
+    }
+
+    fn push(&self, token: Address, to: Address, amount: U256) -> Result<(), OtcEscrowError> {
+        safe_transfer(self.vm(), token, to, amount)
+            .map_err(|_| OtcEscrowError::TransferFailed(TransferFailed { token, amount }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::{SolCall, SolEvent};
+    use common::token::IERC20Transfers as IERC20;
+    use stylus_sdk::testing::*;
+
+    const ALICE: Address = Address::new([0x01; 20]);
+    const BOB: Address = Address::new([0x02; 20]);
+    const MALLORY: Address = Address::new([0x0e; 20]);
+    const USDC: Address = Address::new([0xa1; 20]);
+    const CUPCAKE: Address = Address::new([0xa2; 20]);
+    const START: u64 = 1_000;
+    const DEADLINE: u64 = START + 3_600;
+
+    /// Alice offers 500 USDC for Bob's 20 CUPCAKE; unmocked token calls succeed.
+    fn setup() -> (TestVM, OtcEscrow, U256) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(START);
+        let mut contract = OtcEscrow::from(&vm);
+        vm.set_sender(ALICE);
+        let id = contract
+            .propose(
+                BOB,
+                USDC,
+                U256::from(500),
+                CUPCAKE,
+                U256::from(20),
+                DEADLINE,
+            )
+            .unwrap();
+        (vm, contract, id)
+    }
+
+    #[test]
+    fn test_second_deposit_settles_both_legs() {
+        let (vm, mut contract, id) = setup();
+        vm.set_sender(BOB);
+        contract.deposit(id).unwrap();
+        assert_eq!(contract.deal_status(id).unwrap(), (false, true, false));
+
+        vm.set_sender(ALICE);
+        contract.deposit(id).unwrap();
+        assert_eq!(contract.deal_status(id).unwrap(), (false, false, true));
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.last().unwrap().0[0], Settled::SIGNATURE_HASH);
+
+        assert!(matches!(
+            contract.deposit(id),
+            Err(OtcEscrowError::AlreadySettled(_))
+        ));
+        vm.set_block_timestamp(DEADLINE);
+        assert!(matches!(
+            contract.reclaim(id),
+            Err(OtcEscrowError::AlreadySettled(_))
+        ));
+    }
+
+    #[test]
+    fn test_failed_settlement_transfer_surfaces_error() {
+        let (vm, mut contract, id) = setup();
+        vm.set_sender(ALICE);
+        contract.deposit(id).unwrap();
+
+        let pay_alice = IERC20::transferCall {
+            to: ALICE,
+            value: U256::from(20),
+        };
+        vm.mock_call(CUPCAKE, pay_alice.abi_encode(), Err(b"frozen".to_vec()));
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.deposit(id),
+            Err(OtcEscrowError::TransferFailed(TransferFailed { token, .. })) if token == CUPCAKE
+        ));
+    }
+
+    #[test]
+    fn test_deposit_checks_party_and_deadline() {
+        let (vm, mut contract, id) = setup();
+        vm.set_sender(MALLORY);
+        assert!(matches!(
+            contract.deposit(id),
+            Err(OtcEscrowError::NotAParty(_))
+        ));
+        assert!(matches!(
+            contract.deposit(U256::from(9)),
+            Err(OtcEscrowError::DealNotFound(_))
+        ));
+
+        vm.set_sender(ALICE);
+        contract.deposit(id).unwrap();
+        assert!(matches!(
+            contract.deposit(id),
+            Err(OtcEscrowError::AlreadyDeposited(_))
+        ));
+
+        // A failed pull surfaces the token that refused it.
+        let pull = IERC20::transferFromCall {
+            from: BOB,
+            to: vm.contract_address(),
+            value: U256::from(20),
+        };
+        vm.mock_call(CUPCAKE, pull.abi_encode(), Err(b"allowance".to_vec()));
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.deposit(id),
+            Err(OtcEscrowError::TransferFailed(TransferFailed { token, .. })) if token == CUPCAKE
+        ));
+
+        vm.set_block_timestamp(DEADLINE);
+        assert!(matches!(
+            contract.deposit(id),
+            Err(OtcEscrowError::DeadlinePassed(_))
+        ));
+    }
+
+    #[test]
+    fn test_reclaim_after_deadline() {
+        let (vm, mut contract, id) = setup();
+        vm.set_sender(ALICE);
+        contract.deposit(id).unwrap();
+        assert!(matches!(
+            contract.reclaim(id),
+            Err(OtcEscrowError::DeadlineNotReached(_))
+        ));
+
+        vm.set_block_timestamp(DEADLINE);
+        contract.reclaim(id).unwrap();
+        assert_eq!(contract.deal_status(id).unwrap(), (false, false, false));
+        assert!(matches!(
+            contract.reclaim(id),
+            Err(OtcEscrowError::NothingToReclaim(_))
+        ));
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.reclaim(id),
+            Err(OtcEscrowError::NothingToReclaim(_))
+        ));
+    }
+
+    #[test]
+    fn test_propose_rejects_bad_terms() {
+        let (vm, mut contract, id) = setup();
+        assert_eq!(
+            contract.deal_terms(id).unwrap(),
+            (
+                ALICE,
+                BOB,
+                USDC,
+                U256::from(500),
+                CUPCAKE,
+                U256::from(20),
+                DEADLINE
+            )
+        );
+        let one = U256::from(1);
+        for (counterparty, token_b, amount_b, deadline) in [
+            (ALICE, CUPCAKE, one, DEADLINE),
+            (Address::ZERO, CUPCAKE, one, DEADLINE),
+            (BOB, USDC, one, DEADLINE),
+            (BOB, CUPCAKE, U256::ZERO, DEADLINE),
+            (BOB, CUPCAKE, one, START),
+        ] {
+            vm.set_sender(ALICE);
+            assert!(matches!(
+                contract.propose(counterparty, USDC, one, token_b, amount_b, deadline),
+                Err(OtcEscrowError::InvalidTerms(_))
+            ));
+        }
+        assert_eq!(contract.deal_count(), U256::from(1));
+    }
+}
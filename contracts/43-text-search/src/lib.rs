@@ -0,0 +1,310 @@
+//!
+//! Stylus Text Search
+//!
+//! A full-text index kept entirely on-chain. `index_document` splits a text
+//! into terms and appends the new document id to each term's postings list,
+//! and `search` returns the ids of documents containing every query term:
+//!
+//! ```text
+//! "Stylus runs Rust on Arbitrum"  ->  stylus, runs, rust, arbitrum
+//! search("rust arbitrum")         ->  ids in both postings lists
+//! ```
+//!
+//! Terms are maximal runs of alphanumeric characters, lowercased. Terms
+//! shorter than `MIN_TERM_CHARS`, longer than `MAX_TERM_BYTES`, or in
+//! `STOP_WORDS` are dropped, and each term is indexed once per document.
+//! `tokenize` exposes the same rules so clients can preview them.
+//!
+//! Ids are assigned in increasing order, so every postings list is sorted.
+//! `search` walks the shortest list and binary-searches the others for each
+//! id. That costs `O(shortest * terms * log(longest))` storage reads rather
+//! than the length of every list.
+//!
+//! Tokenizing and intersecting in WASM is far cheaper than the equivalent
+//! Solidity loop over bytes. Storage writes still dominate, so documents and
+//! queries are capped.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    prelude::*,
+};
+
+const MAX_DOCUMENT_BYTES: usize = 2_048;
+/// Most distinct terms a document may add to the index.
+const MAX_TERMS_PER_DOCUMENT: usize = 128;
+const MAX_QUERY_TERMS: usize = 8;
+const MIN_TERM_CHARS: usize = 2;
+const MAX_TERM_BYTES: usize = 32;
+/// Terms too common to narrow a search down.
+const STOP_WORDS: [&str; 12] = [
+    "an", "and", "are", "as", "at", "for", "in", "is", "of", "on", "the", "to",
+];
+
+sol! {
+    event DocumentIndexed(uint256 indexed id, address indexed author, uint256 terms);
+
+    #[derive(Debug)]
+    error DocumentTooLong(uint256 maxBytes);
+    #[derive(Debug)]
+    error NoSearchableTerms();
+    #[derive(Debug)]
+    error TooManyTerms(uint256 max);
+    #[derive(Debug)]
+    error DocumentNotFound(uint256 id);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum TextSearchError {
+    DocumentTooLong(DocumentTooLong),
+    NoSearchableTerms(NoSearchableTerms),
+    TooManyTerms(TooManyTerms),
+    DocumentNotFound(DocumentNotFound),
+}
+
+sol_storage! {
+    pub struct Document {
+        address author;
+        string text;
+    }
+
+    #[entrypoint]
+    pub struct TextSearch {
+        /// Documents by id, starting at 1.
+        mapping(uint256 => Document) documents;
+        uint256 document_count;
+        /// Ids of the documents containing each term, in increasing order.
+        mapping(string => uint256[]) postings;
+    }
+}
+
+#[public]
+impl TextSearch {
+    /// Stores `text`, indexes its terms and returns the new document id.
+    pub fn index_document(&mut self, text: String) -> Result<U256, TextSearchError> {
+        if text.len() > MAX_DOCUMENT_BYTES {
+            return Err(TextSearchError::DocumentTooLong(DocumentTooLong {
+                maxBytes: U256::from(MAX_DOCUMENT_BYTES),
+            }));
+        }
+        let terms = terms_of(&text);
+        if terms.is_empty() {
+            return Err(TextSearchError::NoSearchableTerms(NoSearchableTerms {}));
+        }
+        if terms.len() > MAX_TERMS_PER_DOCUMENT {
+            return Err(TextSearchError::TooManyTerms(TooManyTerms {
+                max: U256::from(MAX_TERMS_PER_DOCUMENT),
+            }));
+        }
+
+        let id = self.document_count.get() + U256::from(1);
+        self.document_count.set(id);
+        let author = self.vm().msg_sender();
+        let mut document = self.documents.setter(id);
+        document.author.set(author);
+        document.text.set_str(&text);
+        for term in &terms {
+            self.postings.setter(term.clone()).push(id);
+        }
+        log(
+            self.vm(),
+            DocumentIndexed {
+                id,
+                author,
+                terms: U256::from(terms.len()),
+            },
+        );
+        Ok(id)
+    }
+
+    /// Ids of the documents containing every term of `query`, in increasing order.
+    pub fn search(&self, query: String) -> Result<Vec<U256>, TextSearchError> {
+        let mut terms = terms_of(&query);
+        if terms.is_empty() {
+            return Err(TextSearchError::NoSearchableTerms(NoSearchableTerms {}));
+        }
+        if terms.len() > MAX_QUERY_TERMS {
+            return Err(TextSearchError::TooManyTerms(TooManyTerms {
+                max: U256::from(MAX_QUERY_TERMS),
+            }));
+        }
+        terms.sort_by_key(|term| self.postings.getter(term.clone()).len());
+
+        let (shortest, others) = terms.split_first().expect("checked non-empty");
+        let candidates = self.postings.getter(shortest.clone());
+        let matches = (0..candidates.len())
+            .filter_map(|index| candidates.get(index))
+            .filter(|&id| others.iter().all(|term| self.contains(term, id)))
+            .collect();
+        Ok(matches)
+    }
+
+    /// Number of documents containing `term`, after normalizing it like a document term.
+    pub fn document_frequency(&self, term: String) -> U256 {
+        match terms_of(&term).as_slice() {
+            [normalized] => U256::from(self.postings.getter(normalized.clone()).len()),
+            _ => U256::ZERO,
+        }
+    }
+
+    /// The distinct terms `text` would be indexed under, in order of first appearance.
+    pub fn tokenize(&self, text: String) -> Vec<String> {
+        terms_of(&text)
+    }
+
+    /// Returns `(author, text)` of document `id`.
+    pub fn document(&self, id: U256) -> Result<(Address, String), TextSearchError> {
+        if id.is_zero() || id > self.document_count.get() {
+            return Err(TextSearchError::DocumentNotFound(DocumentNotFound { id }));
+        }
+        let document = self.documents.getter(id);
+        Ok((document.author.get(), document.text.get_string()))
+    }
+
+    pub fn document_count(&self) -> U256 {
+        self.document_count.get()
+    }
+}
+
+impl TextSearch {
+    /// Binary search of `term`'s postings, which are sorted because ids only grow.
+    fn contains(&self, term: &str, id: U256) -> bool {
+        let postings = self.postings.getter(String::from(term));
+        let (mut low, mut high) = (0, postings.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            match postings.get(mid) {
+                Some(found) if found == id => return true,
+                Some(found) if found < id => low = mid + 1,
+                _ => high = mid,
+            }
+        }
+        false
+    }
+}
+
+/// Splits `text` into distinct lowercase terms, dropping short, long and stop words.
+fn terms_of(text: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for word in text.split(|c: char| !c.is_alphanumeric()) {
+        let term = word.to_lowercase();
+        if term.chars().count() < MIN_TERM_CHARS
+            || term.len() > MAX_TERM_BYTES
+            || STOP_WORDS.contains(&term.as_str())
+            || terms.contains(&term)
+        {
+            continue;
+        }
+        terms.push(term);
+    }
+    terms
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const AUTHOR: Address = Address::new([0x01; 20]);
+
+    fn setup(documents: &[&str]) -> (TestVM, TextSearch) {
+        let vm = TestVM::default();
+        vm.set_sender(AUTHOR);
+        let mut contract = TextSearch::from(&vm);
+        for text in documents {
+            contract.index_document(text.to_string()).unwrap();
+        }
+        (vm, contract)
+    }
+
+    fn ids(values: &[u64]) -> Vec<U256> {
+        values.iter().map(|&id| U256::from(id)).collect()
+    }
+
+    #[test]
+    fn test_search_matches_every_query_term() {
+        let (_vm, contract) = setup(&[
+            "Stylus runs Rust on Arbitrum.",
+            "Solidity runs on Arbitrum, too!",
+            "Rust: fast, safe, WASM",
+        ]);
+        let search = |query: &str| contract.search(query.to_string()).unwrap();
+        assert_eq!(search("arbitrum"), ids(&[1, 2]));
+        assert_eq!(search("RUST"), ids(&[1, 3]));
+        assert_eq!(search("rust arbitrum"), ids(&[1]));
+        assert_eq!(search("arbitrum rust runs"), ids(&[1]));
+        assert_eq!(search("wasm solidity"), ids(&[]));
+        assert_eq!(search("cairo"), ids(&[]));
+    }
+
+    #[test]
+    fn test_tokenizer_normalizes_and_dedupes() {
+        let (_vm, contract) = setup(&["The cupcake, the CUPCAKE and a cupcake-shop at 9am"]);
+        assert_eq!(
+            contract.tokenize("The cupcake, the CUPCAKE and a cupcake-shop at 9am".into()),
+            vec!["cupcake", "shop", "9am"]
+        );
+        assert_eq!(contract.document_frequency("Cupcake".into()), U256::from(1));
+        assert_eq!(contract.document_frequency("the".into()), U256::ZERO);
+        assert_eq!(contract.search("café".into()).unwrap(), ids(&[]));
+
+        let long = "x".repeat(MAX_TERM_BYTES + 1);
+        assert!(contract.tokenize(long).is_empty());
+    }
+
+    #[test]
+    fn test_index_document_limits() {
+        let (_vm, mut contract) = setup(&[]);
+        assert!(matches!(
+            contract.index_document("a".repeat(MAX_DOCUMENT_BYTES + 1)),
+            Err(TextSearchError::DocumentTooLong(_))
+        ));
+        assert!(matches!(
+            contract.index_document("the a of !!".into()),
+            Err(TextSearchError::NoSearchableTerms(_))
+        ));
+        let many: Vec<String> = (0..=MAX_TERMS_PER_DOCUMENT)
+            .map(|i| format!("t{i}"))
+            .collect();
+        assert!(matches!(
+            contract.index_document(many.join(" ")),
+            Err(TextSearchError::TooManyTerms(_))
+        ));
+        assert_eq!(contract.document_count(), U256::ZERO);
+    }
+
+    #[test]
+    fn test_search_limits() {
+        let (_vm, contract) = setup(&["one two"]);
+        assert!(matches!(
+            contract.search("  ".into()),
+            Err(TextSearchError::NoSearchableTerms(_))
+        ));
+        assert!(matches!(
+            contract.search("aa bb cc dd ee ff gg hh ii".into()),
+            Err(TextSearchError::TooManyTerms(_))
+        ));
+    }
+
+    #[test]
+    fn test_document_lookup() {
+        let (_vm, contract) = setup(&["Cupcake recipes"]);
+        assert_eq!(
+            contract.document(U256::from(1)).unwrap(),
+            (AUTHOR, "Cupcake recipes".to_string())
+        );
+        assert!(matches!(
+            contract.document(U256::from(2)),
+            Err(TextSearchError::DocumentNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,613 @@
+//!
+//! Stylus Pawn Shop
+//!
+//! Borrowers lock an ERC-721 token here as collateral for a fixed-term ETH
+//! loan. `request_loan` pulls the token into escrow (the borrower must first
+//! approve this contract) and records the terms: principal, yearly interest
+//! rate in basis points, and duration. The amount owed is fixed up front as
+//! the principal plus simple interest over the full term, rounded up in the
+//! lender's favour, so repaying early costs the same as repaying on the last
+//! day.
+//!
+//! Any lender can take a request by calling `fund_loan` with exactly the
+//! principal, which is forwarded to the borrower and starts the clock. Until
+//! the due date the borrower can `repay`, which returns the token and credits
+//! the lender; once it has passed without repayment the lender can `seize`
+//! the token instead. A request nobody has funded can be cancelled by its
+//! borrower. Lenders collect repayments with `withdraw`, so a lender that
+//! cannot receive ETH never blocks a borrower from getting their token back.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall};
+use stylus_sdk::{
+    alloy_primitives::{Address, U256, U64, U8},
+    prelude::*,
+};
+
+pub const BPS: u64 = 10_000;
+pub const YEAR: u64 = 365 * 24 * 60 * 60;
+/// Highest yearly interest rate a request may ask for (500%).
+pub const MAX_APR_BPS: u64 = 50_000;
+pub const MIN_DURATION: u64 = 24 * 60 * 60;
+pub const MAX_DURATION: u64 = YEAR;
+
+pub const STATE_UNSET: u8 = 0;
+pub const STATE_REQUESTED: u8 = 1;
+pub const STATE_ACTIVE: u8 = 2;
+pub const STATE_REPAID: u8 = 3;
+pub const STATE_SEIZED: u8 = 4;
+pub const STATE_CANCELLED: u8 = 5;
+
+sol! {
+    interface IERC721 {
+        function transferFrom(address from, address to, uint256 tokenId) external;
+    }
+
+    event LoanRequested(uint256 indexed loanId, address indexed borrower, address indexed nft, uint256 tokenId, uint256 principal, uint256 repayment, uint256 duration);
+    event LoanFunded(uint256 indexed loanId, address indexed lender, uint256 dueAt);
+    event LoanRepaid(uint256 indexed loanId, uint256 repayment);
+    event CollateralSeized(uint256 indexed loanId, address indexed lender);
+    event LoanCancelled(uint256 indexed loanId);
+    event Withdrawn(address indexed account, uint256 amount);
+
+    #[derive(Debug)]
+    error InvalidTerms(string reason);
+    #[derive(Debug)]
+    error UnknownLoan(uint256 loanId);
+    #[derive(Debug)]
+    error WrongState(uint256 loanId, uint8 state);
+    #[derive(Debug)]
+    error NotBorrower(address caller);
+    #[derive(Debug)]
+    error NotLender(address caller);
+    #[derive(Debug)]
+    error WrongPayment(uint256 expected, uint256 sent);
+    #[derive(Debug)]
+    error LoanOverdue(uint256 loanId, uint256 dueAt);
+    #[derive(Debug)]
+    error LoanNotDue(uint256 loanId, uint256 dueAt);
+    #[derive(Debug)]
+    error NftTransferFailed(address nft, uint256 tokenId);
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+    #[derive(Debug)]
+    error NothingToWithdraw();
+}
+
+#[derive(SolidityError, Debug)]
+pub enum PawnShopError {
+    InvalidTerms(InvalidTerms),
+    UnknownLoan(UnknownLoan),
+    WrongState(WrongState),
+    NotBorrower(NotBorrower),
+    NotLender(NotLender),
+    WrongPayment(WrongPayment),
+    LoanOverdue(LoanOverdue),
+    LoanNotDue(LoanNotDue),
+    NftTransferFailed(NftTransferFailed),
+    TransferFailed(TransferFailed),
+    NothingToWithdraw(NothingToWithdraw),
+}
+
+impl PawnShopError {
+    fn terms(reason: &str) -> Self {
+        PawnShopError::InvalidTerms(InvalidTerms {
+            reason: reason.into(),
+        })
+    }
+}
+
+sol_storage! {
+    pub struct Loan {
+        address borrower;
+        /// Zero until the loan is funded.
+        address lender;
+        address nft;
+        uint256 token_id;
+        uint256 principal;
+        /// Principal plus interest for the full term.
+        uint256 repayment;
+        uint64 duration;
+        /// Zero until the loan is funded.
+        uint64 due_at;
+        /// One of the `STATE_*` constants.
+        uint8 state;
+    }
+
+    #[entrypoint]
+    pub struct PawnShop {
+        uint256 loan_count;
+        mapping(uint256 => Loan) loans;
+        /// Repayments waiting for their lender to withdraw them.
+        mapping(address => uint256) pending_withdrawals;
+    }
+}
+
+/// Principal plus simple interest at `apr_bps` over `duration` seconds, rounded up.
+pub fn repayment_for(principal: U256, apr_bps: u64, duration: u64) -> Option<U256> {
+    let scaled = principal.checked_mul(U256::from(apr_bps) * U256::from(duration))?;
+    let interest = scaled.div_ceil(U256::from(BPS) * U256::from(YEAR));
+    principal.checked_add(interest)
+}
+
+#[public]
+impl PawnShop {
+    /// Escrows `token_id` of `nft` and opens a loan request, returning its id.
+    pub fn request_loan(
+        &mut self,
+        nft: Address,
+        token_id: U256,
+        principal: U256,
+        apr_bps: u64,
+        duration: u64,
+    ) -> Result<U256, PawnShopError> {
+        if nft.is_zero() {
+            return Err(PawnShopError::terms("NFT address is zero"));
+        }
+        if principal.is_zero() {
+            return Err(PawnShopError::terms("Principal is zero"));
+        }
+        if apr_bps > MAX_APR_BPS {
+            return Err(PawnShopError::terms("Interest rate too high"));
+        }
+        if !(MIN_DURATION..=MAX_DURATION).contains(&duration) {
+            return Err(PawnShopError::terms("Duration out of range"));
+        }
+        let repayment = repayment_for(principal, apr_bps, duration)
+            .ok_or_else(|| PawnShopError::terms("Principal too large"))?;
+
+        let borrower = self.vm().msg_sender();
+        let escrow = self.vm().contract_address();
+        self.transfer_nft(nft, borrower, escrow, token_id)?;
+
+        let loan_id = self.loan_count.get();
+        self.loan_count.set(loan_id + U256::from(1));
+        let mut loan = self.loans.setter(loan_id);
+        loan.borrower.set(borrower);
+        loan.nft.set(nft);
+        loan.token_id.set(token_id);
+        loan.principal.set(principal);
+        loan.repayment.set(repayment);
+        loan.duration.set(U64::from(duration));
+        loan.state.set(U8::from(STATE_REQUESTED));
+        log(
+            self.vm(),
+            LoanRequested {
+                loanId: loan_id,
+                borrower,
+                nft,
+                tokenId: token_id,
+                principal,
+                repayment,
+                duration: U256::from(duration),
+            },
+        );
+        Ok(loan_id)
+    }
+
+    /// Returns the token of a request nobody has funded yet. Only its borrower may cancel.
+    pub fn cancel_request(&mut self, loan_id: U256) -> Result<(), PawnShopError> {
+        self.require_state(loan_id, STATE_REQUESTED)?;
+        let borrower = self.only_borrower(loan_id)?;
+        self.loans
+            .setter(loan_id)
+            .state
+            .set(U8::from(STATE_CANCELLED));
+        self.release_nft(loan_id, borrower)?;
+        log(self.vm(), LoanCancelled { loanId: loan_id });
+        Ok(())
+    }
+
+    /// Funds a request with exactly its principal, which goes straight to the borrower.
+    #[payable]
+    pub fn fund_loan(&mut self, loan_id: U256) -> Result<(), PawnShopError> {
+        self.require_state(loan_id, STATE_REQUESTED)?;
+        let loan = self.loans.getter(loan_id);
+        let (borrower, principal) = (loan.borrower.get(), loan.principal.get());
+        let sent = self.vm().msg_value();
+        if sent != principal {
+            return Err(PawnShopError::WrongPayment(WrongPayment {
+                expected: principal,
+                sent,
+            }));
+        }
+        let lender = self.vm().msg_sender();
+        let due_at = self.vm().block_timestamp() + loan.duration.get().to::<u64>();
+        let mut loan = self.loans.setter(loan_id);
+        loan.lender.set(lender);
+        loan.due_at.set(U64::from(due_at));
+        loan.state.set(U8::from(STATE_ACTIVE));
+        if self.vm().transfer_eth(borrower, principal).is_err() {
+            return Err(PawnShopError::TransferFailed(TransferFailed {
+                to: borrower,
+                amount: principal,
+            }));
+        }
+        log(
+            self.vm(),
+            LoanFunded {
+                loanId: loan_id,
+                lender,
+                dueAt: U256::from(due_at),
+            },
+        );
+        Ok(())
+    }
+
+    /// Pays back an active loan before it is due and returns the token to its borrower.
+    ///
+    /// Anyone may repay on the borrower's behalf; the token always goes to the borrower.
+    #[payable]
+    pub fn repay(&mut self, loan_id: U256) -> Result<(), PawnShopError> {
+        self.require_state(loan_id, STATE_ACTIVE)?;
+        let loan = self.loans.getter(loan_id);
+        let due_at = loan.due_at.get().to::<u64>();
+        if self.vm().block_timestamp() >= due_at {
+            return Err(PawnShopError::LoanOverdue(LoanOverdue {
+                loanId: loan_id,
+                dueAt: U256::from(due_at),
+            }));
+        }
+        let (borrower, lender, repayment) =
+            (loan.borrower.get(), loan.lender.get(), loan.repayment.get());
+        let sent = self.vm().msg_value();
+        if sent != repayment {
+            return Err(PawnShopError::WrongPayment(WrongPayment {
+                expected: repayment,
+                sent,
+            }));
+        }
+        self.loans.setter(loan_id).state.set(U8::from(STATE_REPAID));
+        let pending = self.pending_withdrawals.get(lender) + repayment;
+        self.pending_withdrawals.setter(lender).set(pending);
+        self.release_nft(loan_id, borrower)?;
+        log(
+            self.vm(),
+            LoanRepaid {
+                loanId: loan_id,
+                repayment,
+            },
+        );
+        Ok(())
+    }
+
+    /// Hands the token of an overdue, unpaid loan to its lender. Only the lender may seize.
+    pub fn seize(&mut self, loan_id: U256) -> Result<(), PawnShopError> {
+        self.require_state(loan_id, STATE_ACTIVE)?;
+        let loan = self.loans.getter(loan_id);
+        let lender = loan.lender.get();
+        let caller = self.vm().msg_sender();
+        if caller != lender {
+            return Err(PawnShopError::NotLender(NotLender { caller }));
+        }
+        let due_at = loan.due_at.get().to::<u64>();
+        if self.vm().block_timestamp() < due_at {
+            return Err(PawnShopError::LoanNotDue(LoanNotDue {
+                loanId: loan_id,
+                dueAt: U256::from(due_at),
+            }));
+        }
+        self.loans.setter(loan_id).state.set(U8::from(STATE_SEIZED));
+        self.release_nft(loan_id, lender)?;
+        log(
+            self.vm(),
+            CollateralSeized {
+                loanId: loan_id,
+                lender,
+            },
+        );
+        Ok(())
+    }
+
+    /// Sends the caller every repayment credited to them.
+    pub fn withdraw(&mut self) -> Result<U256, PawnShopError> {
+        let account = self.vm().msg_sender();
+        let amount = self.pending_withdrawals.get(account);
+        if amount.is_zero() {
+            return Err(PawnShopError::NothingToWithdraw(NothingToWithdraw {}));
+        }
+        // The repayment credit is gone before the ETH leaves, so a nested `withdraw` gets nothing.
+        self.pending_withdrawals.setter(account).set(U256::ZERO);
+        if self.vm().transfer_eth(account, amount).is_err() {
+            return Err(PawnShopError::TransferFailed(TransferFailed {
+                to: account,
+                amount,
+            }));
+        }
+        log(self.vm(), Withdrawn { account, amount });
+        Ok(amount)
+    }
+
+    /// `(borrower, lender, nft, tokenId, principal, repayment, dueAt, state)` for a loan.
+    #[allow(clippy::type_complexity)]
+    pub fn loan(
+        &self,
+        loan_id: U256,
+    ) -> Result<(Address, Address, Address, U256, U256, U256, U256, u8), PawnShopError> {
+        let state = self.state(loan_id);
+        if state == STATE_UNSET {
+            return Err(PawnShopError::UnknownLoan(UnknownLoan { loanId: loan_id }));
+        }
+        let loan = self.loans.getter(loan_id);
+        Ok((
+            loan.borrower.get(),
+            loan.lender.get(),
+            loan.nft.get(),
+            loan.token_id.get(),
+            loan.principal.get(),
+            loan.repayment.get(),
+            U256::from(loan.due_at.get()),
+            state,
+        ))
+    }
+
+    pub fn loan_count(&self) -> U256 {
+        self.loan_count.get()
+    }
+
+    pub fn pending_withdrawal(&self, account: Address) -> U256 {
+        self.pending_withdrawals.get(account)
+    }
+}
+
+impl PawnShop {
+    fn state(&self, loan_id: U256) -> u8 {
+        self.loans.getter(loan_id).state.get().to::<u8>()
+    }
+
+    fn require_state(&self, loan_id: U256, expected: u8) -> Result<(), PawnShopError> {
+        match self.state(loan_id) {
+            STATE_UNSET => Err(PawnShopError::UnknownLoan(UnknownLoan { loanId: loan_id })),
+            state if state != expected => Err(PawnShopError::WrongState(WrongState {
+                loanId: loan_id,
+                state,
+            })),
+            _ => Ok(()),
+        }
+    }
+
+    fn only_borrower(&self, loan_id: U256) -> Result<Address, PawnShopError> {
+        let borrower = self.loans.getter(loan_id).borrower.get();
+        let caller = self.vm().msg_sender();
+        if caller != borrower {
+            return Err(PawnShopError::NotBorrower(NotBorrower { caller }));
+        }
+        Ok(borrower)
+    }
+
+    /// Sends a loan's collateral out of escrow to `to`.
+    fn release_nft(&mut self, loan_id: U256, to: Address) -> Result<(), PawnShopError> {
+        let loan = self.loans.getter(loan_id);
+        let (nft, token_id) = (loan.nft.get(), loan.token_id.get());
+        let escrow = self.vm().contract_address();
+        self.transfer_nft(nft, escrow, to, token_id)
+    }
+
+    fn transfer_nft(
+        &mut self,
+        nft: Address,
+        from: Address,
+        to: Address,
+        token_id: U256,
+    ) -> Result<(), PawnShopError> {
+        let call = IERC721::transferFromCall {
+            from,
+            to,
+            tokenId: token_id,
+        };
+        self.vm()
+            .call(&calls::context::Call::new(), nft, &call.abi_encode())
+            .map(|_| ())
+            .map_err(|_| {
+                PawnShopError::NftTransferFailed(NftTransferFailed {
+                    nft,
+                    tokenId: token_id,
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const NFT: Address = Address::new([0x11; 20]);
+    const ALICE: Address = Address::new([0x01; 20]);
+    const BOB: Address = Address::new([0x02; 20]);
+    const START: u64 = 1_000;
+    const DAY: u64 = 24 * 60 * 60;
+    const TOKEN_ID: u64 = 7;
+    /// 1 ETH at 36.5% APR for 30 days.
+    const PRINCIPAL: u64 = 1_000_000_000_000_000_000;
+    const APR_BPS: u64 = 3_650;
+    const REPAYMENT: u64 = 1_030_000_000_000_000_000;
+
+    /// Alice has asked for a loan against token 7; unmocked NFT calls succeed.
+    fn setup() -> (TestVM, PawnShop, U256) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(START);
+        let mut contract = PawnShop::from(&vm);
+        vm.set_sender(ALICE);
+        let loan_id = contract
+            .request_loan(
+                NFT,
+                U256::from(TOKEN_ID),
+                U256::from(PRINCIPAL),
+                APR_BPS,
+                30 * DAY,
+            )
+            .unwrap();
+        (vm, contract, loan_id)
+    }
+
+    /// Calls `f` as `sender` with `value` attached, crediting the contract if it succeeds.
+    fn send<T>(
+        vm: &TestVM,
+        contract: &mut PawnShop,
+        sender: Address,
+        value: u64,
+        f: impl FnOnce(&mut PawnShop) -> Result<T, PawnShopError>,
+    ) -> Result<T, PawnShopError> {
+        vm.set_sender(sender);
+        vm.set_value(U256::from(value));
+        let balance = vm.balance(vm.contract_address());
+        vm.set_balance(vm.contract_address(), balance + U256::from(value));
+        let result = f(contract);
+        if result.is_err() {
+            vm.set_balance(vm.contract_address(), balance);
+        }
+        vm.set_value(U256::ZERO);
+        result
+    }
+
+    fn transfer_call(from: Address, to: Address) -> Vec<u8> {
+        IERC721::transferFromCall {
+            from,
+            to,
+            tokenId: U256::from(TOKEN_ID),
+        }
+        .abi_encode()
+    }
+
+    #[test]
+    fn test_repay_returns_token_and_credits_lender() {
+        let (vm, mut contract, loan_id) = setup();
+        let (borrower, _, nft, _, principal, repayment, due_at, state) =
+            contract.loan(loan_id).unwrap();
+        assert_eq!(
+            (borrower, nft, principal),
+            (ALICE, NFT, U256::from(PRINCIPAL))
+        );
+        assert_eq!(repayment, U256::from(REPAYMENT));
+        assert_eq!((due_at, state), (U256::ZERO, STATE_REQUESTED));
+
+        send(&vm, &mut contract, BOB, PRINCIPAL, |c| c.fund_loan(loan_id)).unwrap();
+        assert_eq!(vm.balance(ALICE), U256::from(PRINCIPAL));
+        let (_, lender, _, _, _, _, due_at, state) = contract.loan(loan_id).unwrap();
+        assert_eq!((lender, state), (BOB, STATE_ACTIVE));
+        assert_eq!(due_at, U256::from(START + 30 * DAY));
+
+        vm.set_block_timestamp(START + 29 * DAY);
+        assert!(matches!(
+            send(&vm, &mut contract, ALICE, PRINCIPAL, |c| c.repay(loan_id)),
+            Err(PawnShopError::WrongPayment(WrongPayment { expected, .. }))
+                if expected == U256::from(REPAYMENT)
+        ));
+        send(&vm, &mut contract, ALICE, REPAYMENT, |c| c.repay(loan_id)).unwrap();
+        assert_eq!(contract.loan(loan_id).unwrap().7, STATE_REPAID);
+        assert_eq!(contract.pending_withdrawal(BOB), U256::from(REPAYMENT));
+
+        vm.set_sender(BOB);
+        assert_eq!(contract.withdraw().unwrap(), U256::from(REPAYMENT));
+        assert_eq!(vm.balance(BOB), U256::from(REPAYMENT));
+        assert!(matches!(
+            contract.withdraw(),
+            Err(PawnShopError::NothingToWithdraw(_))
+        ));
+    }
+
+    #[test]
+    fn test_lender_seizes_overdue_collateral() {
+        let (vm, mut contract, loan_id) = setup();
+        send(&vm, &mut contract, BOB, PRINCIPAL, |c| c.fund_loan(loan_id)).unwrap();
+
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.seize(loan_id),
+            Err(PawnShopError::LoanNotDue(_))
+        ));
+        vm.set_block_timestamp(START + 30 * DAY);
+        assert!(matches!(
+            send(&vm, &mut contract, ALICE, REPAYMENT, |c| c.repay(loan_id)),
+            Err(PawnShopError::LoanOverdue(_))
+        ));
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.seize(loan_id),
+            Err(PawnShopError::NotLender(_))
+        ));
+
+        vm.set_sender(BOB);
+        contract.seize(loan_id).unwrap();
+        assert_eq!(contract.loan(loan_id).unwrap().7, STATE_SEIZED);
+        assert!(matches!(
+            contract.seize(loan_id),
+            Err(PawnShopError::WrongState(WrongState { state, .. })) if state == STATE_SEIZED
+        ));
+    }
+
+    #[test]
+    fn test_borrower_cancels_unfunded_request() {
+        let (vm, mut contract, loan_id) = setup();
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.cancel_request(loan_id),
+            Err(PawnShopError::NotBorrower(_))
+        ));
+
+        vm.set_sender(ALICE);
+        contract.cancel_request(loan_id).unwrap();
+        assert_eq!(contract.loan(loan_id).unwrap().7, STATE_CANCELLED);
+        assert!(matches!(
+            send(&vm, &mut contract, BOB, PRINCIPAL, |c| c.fund_loan(loan_id)),
+            Err(PawnShopError::WrongState(_))
+        ));
+        assert!(matches!(
+            contract.loan(U256::from(9)),
+            Err(PawnShopError::UnknownLoan(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_bad_terms() {
+        let (_vm, mut contract, _) = setup();
+        let principal = U256::from(PRINCIPAL);
+        let token_id = U256::from(TOKEN_ID);
+        for (nft, principal, apr_bps, duration) in [
+            (Address::ZERO, principal, APR_BPS, DAY),
+            (NFT, U256::ZERO, APR_BPS, DAY),
+            (NFT, principal, MAX_APR_BPS + 1, DAY),
+            (NFT, principal, APR_BPS, DAY - 1),
+            (NFT, principal, APR_BPS, MAX_DURATION + 1),
+            (NFT, U256::MAX, APR_BPS, DAY),
+        ] {
+            assert!(matches!(
+                contract.request_loan(nft, token_id, principal, apr_bps, duration),
+                Err(PawnShopError::InvalidTerms(_))
+            ));
+        }
+        assert_eq!(contract.loan_count(), U256::from(1));
+
+        // Interest rounds up, and a zero rate owes only the principal.
+        assert_eq!(repayment_for(U256::from(1), 1, DAY), Some(U256::from(2)));
+        assert_eq!(repayment_for(principal, 0, YEAR), Some(principal));
+    }
+
+    #[test]
+    fn test_failed_nft_transfers_surface_errors() {
+        let (vm, mut contract, loan_id) = setup();
+        let escrow = vm.contract_address();
+        vm.mock_call(NFT, transfer_call(BOB, escrow), Err(b"not owner".to_vec()));
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.request_loan(NFT, U256::from(TOKEN_ID), U256::from(1), 0, DAY),
+            Err(PawnShopError::NftTransferFailed(NftTransferFailed { nft, .. })) if nft == NFT
+        ));
+
+        vm.mock_call(NFT, transfer_call(escrow, ALICE), Err(Vec::new()));
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.cancel_request(loan_id),
+            Err(PawnShopError::NftTransferFailed(_))
+        ));
+    }
+}
@@ -0,0 +1,557 @@
+//!
+//! Stylus Threshold Approver
+//!
+//! A reusable k-of-n approval component, generalising the `multisig` example.
+//! A registered signer proposes an action (`target`, `data`); it can run once
+//! `threshold` distinct signers have approved it. Signers approve either by
+//! sending `approve` themselves, or off-chain by signing the action's
+//! `approval_hash`, in which case anyone can relay a batch of signatures to
+//! `approve_with_signatures` in a single transaction. Both kinds of approval
+//! count towards the same threshold.
+//!
+//! The approval hash covers the chain id and this contract's address as well
+//! as the action, so a signature cannot be replayed on another deployment or
+//! chain. Signatures are recovered with the `ecrecover` precompile over the
+//! EIP-191 `personal_sign` digest, and high-`s` (malleable) signatures are
+//! rejected.
+//!
+//! Approvals are counted against the current signer set when the action
+//! executes, so approvals from a signer who has since been removed no longer
+//! count. `set_signers` can only be called by the approver itself, so the
+//! signer set changes only through an approved action, like any other.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{string::ToString, vec::Vec};
+use alloy_sol_types::sol;
+use common::ecdsa;
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, B256, U256},
+    prelude::*,
+};
+
+/// Largest signer set accepted.
+const MAX_SIGNERS: usize = 20;
+
+sol! {
+    event ActionProposed(uint256 indexed actionId, address indexed proposer, address target, bytes data);
+    event ActionApproved(uint256 indexed actionId, address indexed signer);
+    event ActionExecuted(uint256 indexed actionId);
+    event SignersChanged(address[] signers, uint256 threshold);
+
+    #[derive(Debug)]
+    error InvalidSigners(string reason);
+    #[derive(Debug)]
+    error NotSigner(address account);
+    #[derive(Debug)]
+    error OnlySelf(address caller);
+    #[derive(Debug)]
+    error UnknownAction(uint256 actionId);
+    #[derive(Debug)]
+    error AlreadyExecuted(uint256 actionId);
+    #[derive(Debug)]
+    error AlreadyApproved(uint256 actionId, address signer);
+    #[derive(Debug)]
+    error InvalidSignature(uint256 index, string reason);
+    #[derive(Debug)]
+    error ThresholdNotMet(uint256 actionId, uint256 approvals, uint256 threshold);
+    #[derive(Debug)]
+    error ExecutionFailed(uint256 actionId, bytes returnData);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum ApproverError {
+    InvalidSigners(InvalidSigners),
+    NotSigner(NotSigner),
+    OnlySelf(OnlySelf),
+    UnknownAction(UnknownAction),
+    AlreadyExecuted(AlreadyExecuted),
+    AlreadyApproved(AlreadyApproved),
+    InvalidSignature(InvalidSignature),
+    ThresholdNotMet(ThresholdNotMet),
+    ExecutionFailed(ExecutionFailed),
+}
+
+impl ApproverError {
+    fn signature(index: usize, reason: &str) -> Self {
+        ApproverError::InvalidSignature(InvalidSignature {
+            index: U256::from(index),
+            reason: reason.to_string(),
+        })
+    }
+}
+
+sol_storage! {
+    pub struct Action {
+        address target;
+        bytes data;
+        bool executed;
+        mapping(address => bool) approved_by;
+    }
+
+    #[entrypoint]
+    pub struct ThresholdApprover {
+        address[] signers;
+        mapping(address => bool) is_signer;
+        uint256 threshold;
+        /// Actions are numbered from 0; the count doubles as the next id.
+        uint256 action_count;
+        mapping(uint256 => Action) actions;
+    }
+}
+
+#[public]
+impl ThresholdApprover {
+    /// Registers the signers and how many of them must approve each action.
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        signers: Vec<Address>,
+        threshold: U256,
+    ) -> Result<(), ApproverError> {
+        self.replace_signers(signers, threshold)
+    }
+
+    /// Stores a call for approval, returning its id. Only signers may propose.
+    pub fn propose(&mut self, target: Address, data: Bytes) -> Result<U256, ApproverError> {
+        let proposer = self.vm().msg_sender();
+        if !self.is_signer.get(proposer) {
+            return Err(ApproverError::NotSigner(NotSigner { account: proposer }));
+        }
+        let action_id = self.action_count.get();
+        self.action_count.set(action_id + U256::from(1));
+        let mut action = self.actions.setter(action_id);
+        action.target.set(target);
+        action.data.set_bytes(&data);
+        log(
+            self.vm(),
+            ActionProposed {
+                actionId: action_id,
+                proposer,
+                target,
+                data: data.0.into(),
+            },
+        );
+        Ok(action_id)
+    }
+
+    /// Records the caller's approval of a pending action.
+    pub fn approve(&mut self, action_id: U256) -> Result<(), ApproverError> {
+        self.check_pending(action_id)?;
+        let signer = self.vm().msg_sender();
+        if !self.is_signer.get(signer) {
+            return Err(ApproverError::NotSigner(NotSigner { account: signer }));
+        }
+        if self.actions.getter(action_id).approved_by.get(signer) {
+            return Err(ApproverError::AlreadyApproved(AlreadyApproved {
+                actionId: action_id,
+                signer,
+            }));
+        }
+        self.record_approval(action_id, signer);
+        Ok(())
+    }
+
+    /// Records approvals from 65-byte `personal_sign` signatures over `approval_hash(action_id)`.
+    ///
+    /// Anyone may relay signatures. Signers who have already approved are skipped, so a
+    /// batch can be resubmitted; the number of new approvals is returned.
+    pub fn approve_with_signatures(
+        &mut self,
+        action_id: U256,
+        signatures: Vec<Bytes>,
+    ) -> Result<U256, ApproverError> {
+        self.check_pending(action_id)?;
+        let digest = ecdsa::eip191_digest(self.vm(), self.approval_hash(action_id));
+        let mut recorded = 0;
+        for (index, signature) in signatures.iter().enumerate() {
+            let signer = self.recover_signer(digest, signature, index)?;
+            if !self.is_signer.get(signer) {
+                return Err(ApproverError::NotSigner(NotSigner { account: signer }));
+            }
+            if !self.actions.getter(action_id).approved_by.get(signer) {
+                self.record_approval(action_id, signer);
+                recorded += 1;
+            }
+        }
+        Ok(U256::from(recorded))
+    }
+
+    /// Runs an action once enough current signers have approved it, returning its return data.
+    ///
+    /// Anyone may execute. If the call reverts, so does `execute`.
+    pub fn execute(&mut self, action_id: U256) -> Result<Bytes, ApproverError> {
+        self.check_pending(action_id)?;
+        let approvals = self.approval_count(action_id)?;
+        let threshold = self.threshold.get();
+        if approvals < threshold {
+            return Err(ApproverError::ThresholdNotMet(ThresholdNotMet {
+                actionId: action_id,
+                approvals,
+                threshold,
+            }));
+        }
+        // Mark it executed first so the target cannot run it again by calling back.
+        let mut action = self.actions.setter(action_id);
+        action.executed.set(true);
+        let (target, data) = (action.target.get(), action.data.get_bytes());
+
+        match self.vm().call(&calls::context::Call::new(), target, &data) {
+            Ok(returned) => {
+                log(
+                    self.vm(),
+                    ActionExecuted {
+                        actionId: action_id,
+                    },
+                );
+                Ok(Bytes::from(returned))
+            }
+            Err(err) => {
+                let return_data = match err {
+                    calls::errors::Error::Revert(data) => data,
+                    _ => Vec::new(),
+                };
+                Err(ApproverError::ExecutionFailed(ExecutionFailed {
+                    actionId: action_id,
+                    returnData: return_data.into(),
+                }))
+            }
+        }
+    }
+
+    /// Replaces the signer set and threshold. Only callable through an approved action.
+    pub fn set_signers(
+        &mut self,
+        signers: Vec<Address>,
+        threshold: U256,
+    ) -> Result<(), ApproverError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.vm().contract_address() {
+            return Err(ApproverError::OnlySelf(OnlySelf { caller }));
+        }
+        self.replace_signers(signers, threshold)
+    }
+
+    /// The hash signers sign to approve an action:
+    /// `keccak256(abi.encodePacked(chainId, address(this), actionId, target, keccak256(data)))`.
+    pub fn approval_hash(&self, action_id: U256) -> B256 {
+        let action = self.actions.getter(action_id);
+        let data_hash = self.vm().native_keccak256(&action.data.get_bytes());
+        let mut message = Vec::with_capacity(32 + 20 + 32 + 20 + 32);
+        message.extend_from_slice(&U256::from(self.vm().chain_id()).to_be_bytes::<32>());
+        message.extend_from_slice(self.vm().contract_address().as_slice());
+        message.extend_from_slice(&action_id.to_be_bytes::<32>());
+        message.extend_from_slice(action.target.get().as_slice());
+        message.extend_from_slice(data_hash.as_slice());
+        self.vm().native_keccak256(&message)
+    }
+
+    /// Approvals on an action from signers who are currently registered.
+    pub fn approval_count(&self, action_id: U256) -> Result<U256, ApproverError> {
+        self.check_action(action_id)?;
+        let action = self.actions.getter(action_id);
+        let approvals = self
+            .signers()
+            .into_iter()
+            .filter(|&signer| action.approved_by.get(signer))
+            .count();
+        Ok(U256::from(approvals))
+    }
+
+    /// Returns `(target, data, executed)`.
+    pub fn action(&self, action_id: U256) -> Result<(Address, Bytes, bool), ApproverError> {
+        self.check_action(action_id)?;
+        let action = self.actions.getter(action_id);
+        Ok((
+            action.target.get(),
+            Bytes::from(action.data.get_bytes()),
+            action.executed.get(),
+        ))
+    }
+
+    pub fn has_approved(&self, action_id: U256, signer: Address) -> bool {
+        self.actions.getter(action_id).approved_by.get(signer)
+    }
+
+    pub fn signers(&self) -> Vec<Address> {
+        (0..self.signers.len())
+            .filter_map(|index| self.signers.get(index))
+            .collect()
+    }
+
+    pub fn threshold(&self) -> U256 {
+        self.threshold.get()
+    }
+
+    pub fn action_count(&self) -> U256 {
+        self.action_count.get()
+    }
+}
+
+impl ThresholdApprover {
+    fn replace_signers(
+        &mut self,
+        signers: Vec<Address>,
+        threshold: U256,
+    ) -> Result<(), ApproverError> {
+        let invalid = |reason: &str| {
+            ApproverError::InvalidSigners(InvalidSigners {
+                reason: reason.to_string(),
+            })
+        };
+        if signers.is_empty() || signers.len() > MAX_SIGNERS {
+            return Err(invalid("Signer count out of range"));
+        }
+        if threshold.is_zero() || threshold > U256::from(signers.len()) {
+            return Err(invalid("Threshold out of range"));
+        }
+        for old in self.signers() {
+            self.is_signer.setter(old).set(false);
+        }
+        self.signers.erase();
+        for &signer in &signers {
+            if signer.is_zero() {
+                return Err(invalid("Zero address signer"));
+            }
+            if self.is_signer.get(signer) {
+                return Err(invalid("Duplicate signer"));
+            }
+            self.is_signer.setter(signer).set(true);
+            self.signers.push(signer);
+        }
+        self.threshold.set(threshold);
+        log(self.vm(), SignersChanged { signers, threshold });
+        Ok(())
+    }
+
+    fn record_approval(&mut self, action_id: U256, signer: Address) {
+        self.actions
+            .setter(action_id)
+            .approved_by
+            .setter(signer)
+            .set(true);
+        log(
+            self.vm(),
+            ActionApproved {
+                actionId: action_id,
+                signer,
+            },
+        );
+    }
+
+    fn check_action(&self, action_id: U256) -> Result<(), ApproverError> {
+        if action_id >= self.action_count.get() {
+            return Err(ApproverError::UnknownAction(UnknownAction {
+                actionId: action_id,
+            }));
+        }
+        Ok(())
+    }
+
+    fn check_pending(&self, action_id: U256) -> Result<(), ApproverError> {
+        self.check_action(action_id)?;
+        if self.actions.getter(action_id).executed.get() {
+            return Err(ApproverError::AlreadyExecuted(AlreadyExecuted {
+                actionId: action_id,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Recovers the address that produced the `index`th signature over `digest` via the precompile.
+    fn recover_signer(
+        &self,
+        digest: B256,
+        signature: &[u8],
+        index: usize,
+    ) -> Result<Address, ApproverError> {
+        ecdsa::recover(self.vm(), digest, signature)
+            .map_err(|err| ApproverError::signature(index, err.reason()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::{alloy_primitives::keccak256, testing::*};
+
+    const ALICE: Address = Address::new([0x01; 20]);
+    const BOB: Address = Address::new([0x02; 20]);
+    const CAROL: Address = Address::new([0x03; 20]);
+    const MALLORY: Address = Address::new([0x0e; 20]);
+    const TARGET: Address = Address::new([0x7a; 20]);
+
+    /// A 2-of-3 approver with a pending call to `TARGET` proposed by Alice.
+    fn setup() -> (TestVM, ThresholdApprover, U256) {
+        let vm = TestVM::default();
+        let mut contract = ThresholdApprover::from(&vm);
+        contract
+            .constructor(vec![ALICE, BOB, CAROL], U256::from(2))
+            .unwrap();
+        vm.set_sender(ALICE);
+        let action_id = contract
+            .propose(TARGET, Bytes::from(vec![0xab, 0xcd]))
+            .unwrap();
+        (vm, contract, action_id)
+    }
+
+    /// A well-formed signature with `s` below the malleability bound and `v = 28`.
+    fn signature(seed: u8) -> Bytes {
+        let mut sig = vec![seed; 32];
+        sig.extend_from_slice(&[0x11; 32]);
+        sig.push(28);
+        Bytes::from(sig)
+    }
+
+    /// Makes the ecrecover precompile return `signer` for `sig` over the action's approval hash.
+    fn mock_recover(
+        vm: &TestVM,
+        contract: &ThresholdApprover,
+        action_id: U256,
+        sig: &[u8],
+        signer: Address,
+    ) {
+        let mut prefixed = b"\x19Ethereum Signed Message:\n32".to_vec();
+        prefixed.extend_from_slice(contract.approval_hash(action_id).as_slice());
+        let mut input = keccak256(&prefixed).to_vec();
+        input.extend_from_slice(&U256::from(sig[64]).to_be_bytes::<32>());
+        input.extend_from_slice(&sig[..64]);
+        vm.mock_static_call(ecdsa::ECRECOVER, input, Ok(signer.into_word().to_vec()));
+    }
+
+    #[test]
+    fn test_executes_after_k_transaction_approvals() {
+        let (vm, mut contract, action_id) = setup();
+        contract.approve(action_id).unwrap();
+        assert!(matches!(
+            contract.approve(action_id),
+            Err(ApproverError::AlreadyApproved(_))
+        ));
+        assert!(matches!(
+            contract.execute(action_id),
+            Err(ApproverError::ThresholdNotMet(ThresholdNotMet { approvals, .. }))
+                if approvals == U256::from(1)
+        ));
+
+        vm.set_sender(BOB);
+        contract.approve(action_id).unwrap();
+        assert_eq!(contract.approval_count(action_id).unwrap(), U256::from(2));
+        vm.mock_call(TARGET, vec![0xab, 0xcd], Ok(vec![0x01]));
+        // Anyone may execute once the threshold is met.
+        vm.set_sender(MALLORY);
+        assert_eq!(contract.execute(action_id).unwrap().0, vec![0x01]);
+        assert!(contract.action(action_id).unwrap().2);
+        assert!(matches!(
+            contract.execute(action_id),
+            Err(ApproverError::AlreadyExecuted(_))
+        ));
+    }
+
+    #[test]
+    fn test_relayed_signatures_count_toward_threshold() {
+        let (vm, mut contract, action_id) = setup();
+        contract.approve(action_id).unwrap();
+        let (alice_sig, carol_sig) = (signature(0x0a), signature(0x0c));
+        mock_recover(&vm, &contract, action_id, &alice_sig, ALICE);
+        mock_recover(&vm, &contract, action_id, &carol_sig, CAROL);
+
+        // Alice already approved by transaction, so only Carol's signature is new.
+        vm.set_sender(MALLORY);
+        let recorded = contract
+            .approve_with_signatures(action_id, vec![alice_sig, carol_sig.clone()])
+            .unwrap();
+        assert_eq!(recorded, U256::from(1));
+        assert!(contract.has_approved(action_id, CAROL));
+        assert_eq!(
+            contract
+                .approve_with_signatures(action_id, vec![carol_sig])
+                .unwrap(),
+            U256::ZERO
+        );
+
+        vm.mock_call(TARGET, vec![0xab, 0xcd], Ok(Vec::new()));
+        contract.execute(action_id).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_bad_signatures() {
+        let (vm, mut contract, action_id) = setup();
+        let mallory_sig = signature(0x0e);
+        mock_recover(&vm, &contract, action_id, &mallory_sig, MALLORY);
+        assert!(matches!(
+            contract.approve_with_signatures(action_id, vec![mallory_sig]),
+            Err(ApproverError::NotSigner(NotSigner { account })) if account == MALLORY
+        ));
+
+        let mut malleable = signature(0x0a).0;
+        malleable[32..64].copy_from_slice(&[0xff; 32]);
+        let short = Bytes::from(vec![0u8; 64]);
+        // An unmocked recovery returns nothing, which is not a signer.
+        for sig in [signature(0x0b), Bytes::from(malleable), short] {
+            assert!(matches!(
+                contract.approve_with_signatures(action_id, vec![sig]),
+                Err(ApproverError::InvalidSignature(_))
+            ));
+        }
+        assert_eq!(contract.approval_count(action_id).unwrap(), U256::ZERO);
+        assert!(matches!(
+            contract.approval_count(U256::from(9)),
+            Err(ApproverError::UnknownAction(_))
+        ));
+    }
+
+    #[test]
+    fn test_signer_changes_only_through_self_and_drop_stale_approvals() {
+        let (vm, mut contract, action_id) = setup();
+        contract.approve(action_id).unwrap();
+        vm.set_sender(BOB);
+        contract.approve(action_id).unwrap();
+        assert!(matches!(
+            contract.set_signers(vec![ALICE], U256::from(1)),
+            Err(ApproverError::OnlySelf(_))
+        ));
+
+        vm.set_sender(vm.contract_address());
+        contract
+            .set_signers(vec![ALICE, CAROL], U256::from(2))
+            .unwrap();
+        assert_eq!(contract.signers(), vec![ALICE, CAROL]);
+        // Bob's approval no longer counts now that he is not a signer.
+        assert_eq!(contract.approval_count(action_id).unwrap(), U256::from(1));
+        assert!(matches!(
+            contract.execute(action_id),
+            Err(ApproverError::ThresholdNotMet(_))
+        ));
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.propose(TARGET, Bytes::from(Vec::new())),
+            Err(ApproverError::NotSigner(_))
+        ));
+    }
+
+    #[test]
+    fn test_constructor_validates_signers() {
+        let vm = TestVM::default();
+        let mut contract = ThresholdApprover::from(&vm);
+        for (signers, threshold) in [
+            (vec![], 1),
+            (vec![ALICE, BOB], 3),
+            (vec![ALICE, BOB], 0),
+            (vec![ALICE, ALICE], 1),
+            (vec![ALICE, Address::ZERO], 1),
+            (vec![ALICE; MAX_SIGNERS + 1], 1),
+        ] {
+            assert!(matches!(
+                contract.constructor(signers, U256::from(threshold)),
+                Err(ApproverError::InvalidSigners(_))
+            ));
+        }
+    }
+}
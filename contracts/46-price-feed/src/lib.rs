@@ -0,0 +1,384 @@
+//!
+//! Stylus Price Feed Consumer
+//!
+//! Reads prices from a Chainlink-style aggregator, so Stylus contracts can use
+//! the same oracle infrastructure Solidity contracts do. The feed is declared
+//! with its Solidity `AggregatorV3Interface` and called with a static call to
+//! a configurable address:
+//!
+//! ```solidity
+//! function latestRoundData() external view returns (
+//!     uint80 roundId, int256 answer, uint256 startedAt, uint256 updatedAt, uint80 answeredInRound
+//! );
+//! ```
+//!
+//! `refresh` reads the latest round and accepts it only if the answer is
+//! positive, the round is complete, and it was updated no more than
+//! `max_staleness` seconds ago. An accepted answer newer than the stored one
+//! becomes the last good price, which `last_price` serves to consumers that
+//! would rather use a slightly old price than revert when the feed stalls.
+//! Only the owner can point the contract at a different feed, which clears
+//! the stored price since the new feed may use different units.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall};
+use stylus_sdk::{
+    alloy_primitives::{Address, I256, U256},
+    prelude::*,
+};
+
+sol! {
+    /// The subset of Chainlink's `AggregatorV3Interface` the consumer reads.
+    interface AggregatorV3Interface {
+        function decimals() external view returns (uint8);
+        function latestRoundData() external view returns (uint80 roundId, int256 answer, uint256 startedAt, uint256 updatedAt, uint80 answeredInRound);
+    }
+
+    event PriceUpdated(uint256 indexed roundId, int256 answer, uint256 updatedAt);
+    event FeedChanged(address indexed feed, uint256 maxStaleness);
+
+    #[derive(Debug)]
+    error InvalidConfig();
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error FeedUnavailable(address feed);
+    #[derive(Debug)]
+    error InvalidAnswer(int256 answer);
+    #[derive(Debug)]
+    error IncompleteRound(uint256 roundId, uint256 answeredInRound);
+    #[derive(Debug)]
+    error StalePrice(uint256 updatedAt, uint256 maxStaleness);
+    #[derive(Debug)]
+    error NoPrice();
+}
+
+#[derive(SolidityError, Debug)]
+pub enum PriceFeedError {
+    InvalidConfig(InvalidConfig),
+    NotOwner(NotOwner),
+    FeedUnavailable(FeedUnavailable),
+    InvalidAnswer(InvalidAnswer),
+    IncompleteRound(IncompleteRound),
+    StalePrice(StalePrice),
+    NoPrice(NoPrice),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct PriceFeedConsumer {
+        address owner;
+        address feed;
+        /// Oldest `updatedAt` accepted, in seconds before the current block.
+        uint256 max_staleness;
+        /// Last good price; zero `last_updated_at` means none has been stored.
+        int256 last_price;
+        uint256 last_round_id;
+        uint256 last_updated_at;
+    }
+}
+
+#[public]
+impl PriceFeedConsumer {
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        owner: Address,
+        feed: Address,
+        max_staleness: U256,
+    ) -> Result<(), PriceFeedError> {
+        if owner.is_zero() {
+            return Err(PriceFeedError::InvalidConfig(InvalidConfig {}));
+        }
+        self.owner.set(owner);
+        self.configure(feed, max_staleness)
+    }
+
+    /// Reads the feed and stores its answer if it passes every check, returning the last good price.
+    ///
+    /// Anyone may refresh. A valid answer that is not newer than the stored one leaves it unchanged.
+    pub fn refresh(&mut self) -> Result<I256, PriceFeedError> {
+        let round = self.read_feed()?;
+        let (round_id, answered_in) =
+            (U256::from(round.roundId), U256::from(round.answeredInRound));
+        if round.answer <= I256::ZERO {
+            return Err(PriceFeedError::InvalidAnswer(InvalidAnswer {
+                answer: round.answer,
+            }));
+        }
+        if round.updatedAt.is_zero() || answered_in < round_id {
+            return Err(PriceFeedError::IncompleteRound(IncompleteRound {
+                roundId: round_id,
+                answeredInRound: answered_in,
+            }));
+        }
+        let now = U256::from(self.vm().block_timestamp());
+        let max_staleness = self.max_staleness.get();
+        // A feed reporting a time ahead of the block is as untrustworthy as a stale one.
+        if round.updatedAt > now || now - round.updatedAt > max_staleness {
+            return Err(PriceFeedError::StalePrice(StalePrice {
+                updatedAt: round.updatedAt,
+                maxStaleness: max_staleness,
+            }));
+        }
+
+        if round.updatedAt > self.last_updated_at.get() {
+            self.last_price.set(round.answer);
+            self.last_round_id.set(round_id);
+            self.last_updated_at.set(round.updatedAt);
+            log(
+                self.vm(),
+                PriceUpdated {
+                    roundId: round_id,
+                    answer: round.answer,
+                    updatedAt: round.updatedAt,
+                },
+            );
+        }
+        Ok(self.last_price.get())
+    }
+
+    /// Returns `(price, roundId, updatedAt)` for the last good price.
+    pub fn last_price(&self) -> Result<(I256, U256, U256), PriceFeedError> {
+        let updated_at = self.last_updated_at.get();
+        if updated_at.is_zero() {
+            return Err(PriceFeedError::NoPrice(NoPrice {}));
+        }
+        Ok((self.last_price.get(), self.last_round_id.get(), updated_at))
+    }
+
+    /// Decimals of the feed's answers, read from the feed itself.
+    pub fn decimals(&self) -> Result<u8, PriceFeedError> {
+        let feed = self.feed.get();
+        let unavailable = || PriceFeedError::FeedUnavailable(FeedUnavailable { feed });
+        let call = AggregatorV3Interface::decimalsCall {};
+        let returned = self
+            .vm()
+            .static_call(&calls::context::Call::new(), feed, &call.abi_encode())
+            .map_err(|_| unavailable())?;
+        let decimals = AggregatorV3Interface::decimalsCall::abi_decode_returns(&returned, true)
+            .map_err(|_| unavailable())?
+            ._0;
+        Ok(decimals)
+    }
+
+    /// Points the consumer at a new feed and clears the stored price. Only the owner may call this.
+    pub fn set_feed(&mut self, feed: Address, max_staleness: U256) -> Result<(), PriceFeedError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(PriceFeedError::NotOwner(NotOwner { caller }));
+        }
+        self.last_price.set(I256::ZERO);
+        self.last_round_id.set(U256::ZERO);
+        self.last_updated_at.set(U256::ZERO);
+        self.configure(feed, max_staleness)
+    }
+
+    pub fn feed(&self) -> Address {
+        self.feed.get()
+    }
+
+    pub fn max_staleness(&self) -> U256 {
+        self.max_staleness.get()
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+}
+
+impl PriceFeedConsumer {
+    fn configure(&mut self, feed: Address, max_staleness: U256) -> Result<(), PriceFeedError> {
+        if feed.is_zero() || max_staleness.is_zero() {
+            return Err(PriceFeedError::InvalidConfig(InvalidConfig {}));
+        }
+        self.feed.set(feed);
+        self.max_staleness.set(max_staleness);
+        log(
+            self.vm(),
+            FeedChanged {
+                feed,
+                maxStaleness: max_staleness,
+            },
+        );
+        Ok(())
+    }
+
+    fn read_feed(&self) -> Result<AggregatorV3Interface::latestRoundDataReturn, PriceFeedError> {
+        let feed = self.feed.get();
+        let unavailable = || PriceFeedError::FeedUnavailable(FeedUnavailable { feed });
+        let call = AggregatorV3Interface::latestRoundDataCall {};
+        let returned = self
+            .vm()
+            .static_call(&calls::context::Call::new(), feed, &call.abi_encode())
+            .map_err(|_| unavailable())?;
+        AggregatorV3Interface::latestRoundDataCall::abi_decode_returns(&returned, true)
+            .map_err(|_| unavailable())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::SolEvent;
+    use stylus_sdk::{alloy_primitives::aliases::U80, testing::*};
+
+    const OWNER: Address = Address::new([0x01; 20]);
+    const ALICE: Address = Address::new([0x02; 20]);
+    const FEED: Address = Address::new([0xfe; 20]);
+    const OTHER_FEED: Address = Address::new([0xef; 20]);
+    const NOW: u64 = 10_000;
+    const STALENESS: u64 = 3_600;
+    /// $3,000 with the 8 decimals USD feeds use.
+    const PRICE: i64 = 300_000_000_000;
+
+    fn setup() -> (TestVM, PriceFeedConsumer) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(NOW);
+        let mut contract = PriceFeedConsumer::from(&vm);
+        contract
+            .constructor(OWNER, FEED, U256::from(STALENESS))
+            .unwrap();
+        (vm, contract)
+    }
+
+    /// Makes `feed` report `answer` for `round_id`, answered in `answered_in` at `updated_at`.
+    fn report(
+        vm: &TestVM,
+        feed: Address,
+        round_id: u64,
+        answer: i64,
+        updated_at: u64,
+        answered_in: u64,
+    ) {
+        let call = AggregatorV3Interface::latestRoundDataCall {};
+        let returned = AggregatorV3Interface::latestRoundDataCall::abi_encode_returns(&(
+            U80::from(round_id),
+            I256::try_from(answer).unwrap(),
+            U256::from(updated_at),
+            U256::from(updated_at),
+            U80::from(answered_in),
+        ));
+        vm.mock_static_call(feed, call.abi_encode(), Ok(returned));
+    }
+
+    #[test]
+    fn test_refresh_stores_fresh_answer() {
+        let (vm, mut contract) = setup();
+        assert!(matches!(
+            contract.last_price(),
+            Err(PriceFeedError::NoPrice(_))
+        ));
+        report(&vm, FEED, 7, PRICE, NOW - 60, 7);
+        let price = I256::try_from(PRICE).unwrap();
+        assert_eq!(contract.refresh().unwrap(), price);
+        assert_eq!(
+            contract.last_price().unwrap(),
+            (price, U256::from(7), U256::from(NOW - 60))
+        );
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.last().unwrap().0[0], PriceUpdated::SIGNATURE_HASH);
+
+        // An older round is valid but does not replace the newer stored price.
+        report(&vm, FEED, 6, PRICE - 1, NOW - 120, 6);
+        assert_eq!(contract.refresh().unwrap(), price);
+        assert_eq!(contract.last_price().unwrap().1, U256::from(7));
+    }
+
+    #[test]
+    fn test_rejects_stale_and_invalid_rounds() {
+        let (vm, mut contract) = setup();
+        report(&vm, FEED, 7, 3_000, NOW - STALENESS - 1, 7);
+        assert!(matches!(
+            contract.refresh(),
+            Err(PriceFeedError::StalePrice(StalePrice { updatedAt, .. }))
+                if updatedAt == U256::from(NOW - STALENESS - 1)
+        ));
+        report(&vm, FEED, 7, 3_000, NOW + 1, 7);
+        assert!(matches!(
+            contract.refresh(),
+            Err(PriceFeedError::StalePrice(_))
+        ));
+        report(&vm, FEED, 7, 0, NOW, 7);
+        assert!(matches!(
+            contract.refresh(),
+            Err(PriceFeedError::InvalidAnswer(_))
+        ));
+        report(&vm, FEED, 7, -5, NOW, 7);
+        assert!(matches!(
+            contract.refresh(),
+            Err(PriceFeedError::InvalidAnswer(_))
+        ));
+        report(&vm, FEED, 7, 3_000, NOW, 6);
+        assert!(matches!(
+            contract.refresh(),
+            Err(PriceFeedError::IncompleteRound(_))
+        ));
+        report(&vm, FEED, 7, 3_000, 0, 7);
+        assert!(matches!(
+            contract.refresh(),
+            Err(PriceFeedError::IncompleteRound(_))
+        ));
+
+        // Exactly `max_staleness` old is still accepted.
+        report(&vm, FEED, 7, 3_000, NOW - STALENESS, 7);
+        contract.refresh().unwrap();
+    }
+
+    #[test]
+    fn test_unavailable_feed() {
+        let (vm, mut contract) = setup();
+        // An unmocked call returns no data, which does not decode as a round.
+        assert!(matches!(
+            contract.refresh(),
+            Err(PriceFeedError::FeedUnavailable(FeedUnavailable { feed })) if feed == FEED
+        ));
+        let call = AggregatorV3Interface::decimalsCall {};
+        vm.mock_static_call(FEED, call.abi_encode(), Err(b"down".to_vec()));
+        assert!(matches!(
+            contract.decimals(),
+            Err(PriceFeedError::FeedUnavailable(_))
+        ));
+        let returned = AggregatorV3Interface::decimalsCall::abi_encode_returns(&(8u8,));
+        vm.mock_static_call(FEED, call.abi_encode(), Ok(returned));
+        assert_eq!(contract.decimals().unwrap(), 8);
+    }
+
+    #[test]
+    fn test_owner_switches_feed_and_clears_price() {
+        let (vm, mut contract) = setup();
+        report(&vm, FEED, 7, 3_000, NOW, 7);
+        contract.refresh().unwrap();
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.set_feed(OTHER_FEED, U256::from(60)),
+            Err(PriceFeedError::NotOwner(_))
+        ));
+        vm.set_sender(OWNER);
+        assert!(matches!(
+            contract.set_feed(OTHER_FEED, U256::ZERO),
+            Err(PriceFeedError::InvalidConfig(_))
+        ));
+        contract.set_feed(OTHER_FEED, U256::from(60)).unwrap();
+        assert_eq!(contract.feed(), OTHER_FEED);
+        assert!(matches!(
+            contract.last_price(),
+            Err(PriceFeedError::NoPrice(_))
+        ));
+
+        report(&vm, OTHER_FEED, 1, 42, NOW - 61, 1);
+        assert!(matches!(
+            contract.refresh(),
+            Err(PriceFeedError::StalePrice(_))
+        ));
+    }
+}
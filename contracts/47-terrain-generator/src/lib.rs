@@ -0,0 +1,416 @@
+//!
+//! Stylus Terrain Generator
+//!
+//! Generates deterministic tile maps for game worlds from a seed. Each tile's
+//! elevation comes from fractal value noise (see `noise`), computed in integer
+//! fixed point so the same seed always produces the same world on every node,
+//! and is bucketed into a tile type from deep water up to snow.
+//!
+//! `create_world` registers a seed and map size. Any tile, or any rectangle
+//! of up to `MAX_REGION_TILES` tiles, can then be computed on demand through
+//! the `tile_at` and `region` views, which cost the caller nothing when called
+//! off-chain. Games that read the same area repeatedly on-chain can instead
+//! `generate_chunk` once, which stores a `CHUNK_SIZE` x `CHUNK_SIZE` block of
+//! tiles so later `chunk` reads are a storage load rather than a recomputation.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+pub mod noise;
+
+use alloc::{string::ToString, vec::Vec};
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, U256, U32, U64},
+    prelude::*,
+};
+
+/// Side of a stored chunk, in tiles. World sizes are whole numbers of chunks.
+pub const CHUNK_SIZE: u32 = 16;
+/// Largest world side, in tiles.
+pub const MAX_WORLD_SIZE: u32 = 1024;
+/// Most tiles a single `region` query may return.
+pub const MAX_REGION_TILES: u32 = 1024;
+/// Octaves of noise summed per tile.
+const OCTAVES: u32 = 4;
+/// log2 of the tiles spanned by one cell of the coarsest noise octave.
+const FEATURE_SHIFT: u32 = 4;
+
+pub const TILE_DEEP_WATER: u8 = 0;
+pub const TILE_WATER: u8 = 1;
+pub const TILE_SAND: u8 = 2;
+pub const TILE_GRASS: u8 = 3;
+pub const TILE_FOREST: u8 = 4;
+pub const TILE_MOUNTAIN: u8 = 5;
+pub const TILE_SNOW: u8 = 6;
+
+/// Upper elevation bound of each tile type below snow, as a fraction of `noise::ONE`.
+const TILE_THRESHOLDS: [(i64, u8); 6] = [
+    (30, TILE_DEEP_WATER),
+    (40, TILE_WATER),
+    (44, TILE_SAND),
+    (60, TILE_GRASS),
+    (72, TILE_FOREST),
+    (84, TILE_MOUNTAIN),
+];
+
+sol! {
+    event WorldCreated(uint256 indexed worldId, address indexed creator, uint64 seed, uint32 width, uint32 height);
+    event ChunkGenerated(uint256 indexed worldId, uint32 chunkX, uint32 chunkY);
+
+    #[derive(Debug)]
+    error InvalidWorld(string reason);
+    #[derive(Debug)]
+    error UnknownWorld(uint256 worldId);
+    #[derive(Debug)]
+    error OutOfBounds(uint32 x, uint32 y);
+    #[derive(Debug)]
+    error RegionTooLarge(uint256 tiles, uint256 maxTiles);
+    #[derive(Debug)]
+    error ChunkAlreadyGenerated(uint256 worldId, uint32 chunkX, uint32 chunkY);
+    #[derive(Debug)]
+    error ChunkNotGenerated(uint256 worldId, uint32 chunkX, uint32 chunkY);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum TerrainError {
+    InvalidWorld(InvalidWorld),
+    UnknownWorld(UnknownWorld),
+    OutOfBounds(OutOfBounds),
+    RegionTooLarge(RegionTooLarge),
+    ChunkAlreadyGenerated(ChunkAlreadyGenerated),
+    ChunkNotGenerated(ChunkNotGenerated),
+}
+
+sol_storage! {
+    pub struct World {
+        address creator;
+        uint64 seed;
+        uint32 width;
+        uint32 height;
+        /// Generated chunks by `chunkY * (width / CHUNK_SIZE) + chunkX`, one byte per tile.
+        mapping(uint256 => bytes) chunks;
+    }
+
+    #[entrypoint]
+    pub struct TerrainGenerator {
+        uint256 world_count;
+        mapping(uint256 => World) worlds;
+    }
+}
+
+/// Elevation of tile `(x, y)` in a world seeded with `seed`, in `[0, noise::ONE)`.
+pub fn elevation(seed: u64, x: u32, y: u32) -> i64 {
+    let to_fixed = |coordinate: u32| (i64::from(coordinate) * noise::ONE) >> FEATURE_SHIFT;
+    noise::fbm(seed, to_fixed(x), to_fixed(y), OCTAVES)
+}
+
+/// The tile type for an elevation.
+pub fn classify(elevation: i64) -> u8 {
+    TILE_THRESHOLDS
+        .iter()
+        .find(|&&(percent, _)| elevation * 100 < percent * noise::ONE)
+        .map_or(TILE_SNOW, |&(_, tile)| tile)
+}
+
+#[public]
+impl TerrainGenerator {
+    /// Registers a `width` x `height` world generated from `seed`, returning its id.
+    pub fn create_world(
+        &mut self,
+        seed: u64,
+        width: u32,
+        height: u32,
+    ) -> Result<U256, TerrainError> {
+        let invalid = |reason: &str| {
+            TerrainError::InvalidWorld(InvalidWorld {
+                reason: reason.to_string(),
+            })
+        };
+        for side in [width, height] {
+            if side == 0 || side > MAX_WORLD_SIZE {
+                return Err(invalid("Size out of range"));
+            }
+            if side % CHUNK_SIZE != 0 {
+                return Err(invalid("Size must be a multiple of the chunk size"));
+            }
+        }
+        let creator = self.vm().msg_sender();
+        let world_id = self.world_count.get();
+        self.world_count.set(world_id + U256::from(1));
+        let mut world = self.worlds.setter(world_id);
+        world.creator.set(creator);
+        world.seed.set(U64::from(seed));
+        world.width.set(U32::from(width));
+        world.height.set(U32::from(height));
+        log(
+            self.vm(),
+            WorldCreated {
+                worldId: world_id,
+                creator,
+                seed,
+                width,
+                height,
+            },
+        );
+        Ok(world_id)
+    }
+
+    /// Returns `(creator, seed, width, height)`.
+    pub fn world(&self, world_id: U256) -> Result<(Address, u64, u32, u32), TerrainError> {
+        let (seed, width, height) = self.world_params(world_id)?;
+        let creator = self.worlds.getter(world_id).creator.get();
+        Ok((creator, seed, width, height))
+    }
+
+    /// Elevation of a tile, scaled to `0..=65535`.
+    pub fn elevation_at(&self, world_id: U256, x: u32, y: u32) -> Result<u16, TerrainError> {
+        let seed = self.checked_seed(world_id, x, y)?;
+        Ok(elevation(seed, x, y) as u16)
+    }
+
+    /// The `TILE_*` type of a tile.
+    pub fn tile_at(&self, world_id: U256, x: u32, y: u32) -> Result<u8, TerrainError> {
+        let seed = self.checked_seed(world_id, x, y)?;
+        Ok(classify(elevation(seed, x, y)))
+    }
+
+    /// Computes the `width` x `height` tiles from `(x, y)`, row by row, one byte per tile.
+    pub fn region(
+        &self,
+        world_id: U256,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<Bytes, TerrainError> {
+        let tiles = u64::from(width) * u64::from(height);
+        if tiles > u64::from(MAX_REGION_TILES) {
+            return Err(TerrainError::RegionTooLarge(RegionTooLarge {
+                tiles: U256::from(tiles),
+                maxTiles: U256::from(MAX_REGION_TILES),
+            }));
+        }
+        if width == 0 || height == 0 {
+            return Ok(Bytes::from(Vec::new()));
+        }
+        let seed = self.checked_seed(world_id, x, y)?;
+        let (x_end, y_end) = (x + width - 1, y + height - 1);
+        self.checked_seed(world_id, x_end, y_end)?;
+        Ok(Bytes::from(generate(seed, x, y, width, height)))
+    }
+
+    /// Generates and stores a chunk, returning its tiles. Anyone may generate any chunk once.
+    pub fn generate_chunk(
+        &mut self,
+        world_id: U256,
+        chunk_x: u32,
+        chunk_y: u32,
+    ) -> Result<Bytes, TerrainError> {
+        let key = self.chunk_key(world_id, chunk_x, chunk_y)?;
+        if !self.worlds.getter(world_id).chunks.getter(key).is_empty() {
+            return Err(TerrainError::ChunkAlreadyGenerated(ChunkAlreadyGenerated {
+                worldId: world_id,
+                chunkX: chunk_x,
+                chunkY: chunk_y,
+            }));
+        }
+        let seed = self.world_params(world_id)?.0;
+        let (x, y) = (chunk_x * CHUNK_SIZE, chunk_y * CHUNK_SIZE);
+        let tiles = generate(seed, x, y, CHUNK_SIZE, CHUNK_SIZE);
+        self.worlds
+            .setter(world_id)
+            .chunks
+            .setter(key)
+            .set_bytes(&tiles);
+        log(
+            self.vm(),
+            ChunkGenerated {
+                worldId: world_id,
+                chunkX: chunk_x,
+                chunkY: chunk_y,
+            },
+        );
+        Ok(Bytes::from(tiles))
+    }
+
+    /// The stored tiles of a generated chunk, row by row.
+    pub fn chunk(&self, world_id: U256, chunk_x: u32, chunk_y: u32) -> Result<Bytes, TerrainError> {
+        let key = self.chunk_key(world_id, chunk_x, chunk_y)?;
+        let tiles = self.worlds.getter(world_id).chunks.getter(key).get_bytes();
+        if tiles.is_empty() {
+            return Err(TerrainError::ChunkNotGenerated(ChunkNotGenerated {
+                worldId: world_id,
+                chunkX: chunk_x,
+                chunkY: chunk_y,
+            }));
+        }
+        Ok(Bytes::from(tiles))
+    }
+
+    pub fn world_count(&self) -> U256 {
+        self.world_count.get()
+    }
+}
+
+impl TerrainGenerator {
+    /// Returns `(seed, width, height)`.
+    fn world_params(&self, world_id: U256) -> Result<(u64, u32, u32), TerrainError> {
+        if world_id >= self.world_count.get() {
+            return Err(TerrainError::UnknownWorld(UnknownWorld {
+                worldId: world_id,
+            }));
+        }
+        let world = self.worlds.getter(world_id);
+        Ok((
+            world.seed.get().to::<u64>(),
+            world.width.get().to::<u32>(),
+            world.height.get().to::<u32>(),
+        ))
+    }
+
+    /// Returns the world's seed if `(x, y)` lies inside it.
+    fn checked_seed(&self, world_id: U256, x: u32, y: u32) -> Result<u64, TerrainError> {
+        let (seed, width, height) = self.world_params(world_id)?;
+        if x >= width || y >= height {
+            return Err(TerrainError::OutOfBounds(OutOfBounds { x, y }));
+        }
+        Ok(seed)
+    }
+
+    fn chunk_key(&self, world_id: U256, chunk_x: u32, chunk_y: u32) -> Result<U256, TerrainError> {
+        let (_, width, height) = self.world_params(world_id)?;
+        let (columns, rows) = (width / CHUNK_SIZE, height / CHUNK_SIZE);
+        if chunk_x >= columns || chunk_y >= rows {
+            return Err(TerrainError::OutOfBounds(OutOfBounds {
+                x: chunk_x * CHUNK_SIZE,
+                y: chunk_y * CHUNK_SIZE,
+            }));
+        }
+        Ok(U256::from(chunk_y) * U256::from(columns) + U256::from(chunk_x))
+    }
+}
+
+/// The tiles of a rectangle, row by row.
+fn generate(seed: u64, x: u32, y: u32, width: u32, height: u32) -> Vec<u8> {
+    (y..y + height)
+        .flat_map(|row| (x..x + width).map(move |column| classify(elevation(seed, column, row))))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const ALICE: Address = Address::new([0x01; 20]);
+    const SEED: u64 = 0x5eed;
+
+    /// A 64 x 32 world created by Alice.
+    fn setup() -> (TestVM, TerrainGenerator, U256) {
+        let vm = TestVM::default();
+        let mut contract = TerrainGenerator::from(&vm);
+        vm.set_sender(ALICE);
+        let world_id = contract.create_world(SEED, 64, 32).unwrap();
+        (vm, contract, world_id)
+    }
+
+    #[test]
+    fn test_region_matches_tiles_and_is_deterministic() {
+        let (_vm, mut contract, world_id) = setup();
+        assert_eq!(contract.world(world_id).unwrap(), (ALICE, SEED, 64, 32));
+        let region = contract.region(world_id, 10, 5, 8, 4).unwrap();
+        assert_eq!(region.len(), 32);
+        for (index, &tile) in region.iter().enumerate() {
+            let (x, y) = (10 + index as u32 % 8, 5 + index as u32 / 8);
+            assert_eq!(contract.tile_at(world_id, x, y).unwrap(), tile);
+            assert!(tile <= TILE_SNOW);
+        }
+
+        // The same seed always yields the same map; another seed does not.
+        let twin = contract.create_world(SEED, 64, 32).unwrap();
+        let other = contract.create_world(SEED + 1, 64, 32).unwrap();
+        let full = |id| contract.region(id, 0, 0, 32, 32).unwrap();
+        assert_eq!(full(world_id), full(twin));
+        assert_ne!(full(world_id), full(other));
+    }
+
+    #[test]
+    fn test_terrain_has_varied_tiles() {
+        let (_vm, contract, world_id) = setup();
+        let mut seen = [false; 7];
+        for y in 0..32 {
+            for &tile in contract.region(world_id, 0, y, 32, 1).unwrap().iter() {
+                seen[tile as usize] = true;
+            }
+        }
+        let kinds = seen.iter().filter(|&&seen| seen).count();
+        assert!(kinds >= 3, "only {kinds} tile types");
+        assert_eq!(classify(0), TILE_DEEP_WATER);
+        assert_eq!(classify(noise::ONE / 2), TILE_GRASS);
+        assert_eq!(classify(noise::ONE - 1), TILE_SNOW);
+    }
+
+    #[test]
+    fn test_chunks_are_stored_once() {
+        let (vm, mut contract, world_id) = setup();
+        assert!(matches!(
+            contract.chunk(world_id, 1, 1),
+            Err(TerrainError::ChunkNotGenerated(_))
+        ));
+        let tiles = contract.generate_chunk(world_id, 1, 1).unwrap();
+        assert_eq!(tiles.len(), (CHUNK_SIZE * CHUNK_SIZE) as usize);
+        assert_eq!(contract.chunk(world_id, 1, 1).unwrap(), tiles);
+        assert_eq!(
+            tiles,
+            contract
+                .region(world_id, CHUNK_SIZE, CHUNK_SIZE, CHUNK_SIZE, CHUNK_SIZE)
+                .unwrap()
+        );
+        assert!(matches!(
+            contract.generate_chunk(world_id, 1, 1),
+            Err(TerrainError::ChunkAlreadyGenerated(_))
+        ));
+        assert_eq!(vm.get_emitted_logs().len(), 2);
+        // 64 x 32 tiles is 4 x 2 chunks.
+        assert!(matches!(
+            contract.generate_chunk(world_id, 4, 0),
+            Err(TerrainError::OutOfBounds(_))
+        ));
+        contract.generate_chunk(world_id, 3, 1).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_bad_worlds_and_queries() {
+        let (_vm, mut contract, world_id) = setup();
+        for (width, height) in [(0, 16), (16, MAX_WORLD_SIZE + CHUNK_SIZE), (20, 16)] {
+            assert!(matches!(
+                contract.create_world(SEED, width, height),
+                Err(TerrainError::InvalidWorld(_))
+            ));
+        }
+        assert!(matches!(
+            contract.tile_at(world_id, 64, 0),
+            Err(TerrainError::OutOfBounds(_))
+        ));
+        assert!(matches!(
+            contract.elevation_at(U256::from(9), 0, 0),
+            Err(TerrainError::UnknownWorld(_))
+        ));
+        assert!(matches!(
+            contract.region(world_id, 60, 0, 8, 1),
+            Err(TerrainError::OutOfBounds(OutOfBounds { x: 67, y: 0 }))
+        ));
+        assert!(matches!(
+            contract.region(world_id, 0, 0, 64, 32),
+            Err(TerrainError::RegionTooLarge(_))
+        ));
+        assert!(contract.region(world_id, 0, 0, 0, 5).unwrap().is_empty());
+    }
+}
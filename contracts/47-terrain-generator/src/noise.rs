@@ -0,0 +1,92 @@
+//! Deterministic 2D value noise in integer fixed point.
+//!
+//! Every lattice point gets a pseudo-random height from a hash of the seed and
+//! its coordinates; points in between blend the four surrounding lattice
+//! values with a smoothstep curve. Several octaves at doubling frequency and
+//! halving weight are summed (fractal Brownian motion) for coastlines and
+//! ridges at more than one scale. Only integer arithmetic is used, so every
+//! node computes exactly the same map.
+
+/// Fixed-point one: values carry 16 fractional bits.
+pub const ONE: i64 = 1 << 16;
+
+/// Mixes a seed and lattice coordinates into 64 well-distributed bits (splitmix64 finaliser).
+pub fn hash(seed: u64, x: i64, y: i64) -> u64 {
+    let mut z = seed
+        ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// The random height at a lattice point, in `[0, ONE)`.
+fn lattice(seed: u64, x: i64, y: i64) -> i64 {
+    (hash(seed, x, y) >> 48) as i64
+}
+
+/// `3t² - 2t³` for `t` in `[0, ONE]`, easing the blend so cell edges do not show.
+fn smoothstep(t: i64) -> i64 {
+    t * t / ONE * (3 * ONE - 2 * t) / ONE
+}
+
+fn lerp(a: i64, b: i64, t: i64) -> i64 {
+    a + (b - a) * t / ONE
+}
+
+/// Value noise at the fixed-point point `(x, y)`, in `[0, ONE)`.
+pub fn value_noise(seed: u64, x: i64, y: i64) -> i64 {
+    let (ix, iy) = (x >> 16, y >> 16);
+    let tx = smoothstep(x & (ONE - 1));
+    let ty = smoothstep(y & (ONE - 1));
+    let top = lerp(lattice(seed, ix, iy), lattice(seed, ix + 1, iy), tx);
+    let bottom = lerp(lattice(seed, ix, iy + 1), lattice(seed, ix + 1, iy + 1), tx);
+    lerp(top, bottom, ty)
+}
+
+/// `octaves` layers of value noise, each at twice the frequency and half the weight
+/// of the last, normalised back to `[0, ONE)`.
+pub fn fbm(seed: u64, x: i64, y: i64, octaves: u32) -> i64 {
+    let (mut total, mut weights) = (0, 0);
+    let mut weight = 1 << octaves;
+    for octave in 0..octaves {
+        // A different seed per octave keeps the layers from lining up.
+        let layer_seed = seed.wrapping_add(u64::from(octave));
+        total += value_noise(layer_seed, x << octave, y << octave) * weight;
+        weights += weight;
+        weight /= 2;
+    }
+    total / weights
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_noise_is_deterministic_and_in_range() {
+        for (x, y) in [(0, 0), (ONE / 3, 5 * ONE), (123 * ONE + 7, 9 * ONE / 2)] {
+            let value = fbm(42, x, y, 4);
+            assert_eq!(value, fbm(42, x, y, 4));
+            assert!((0..ONE).contains(&value));
+        }
+        assert_ne!(hash(1, 2, 3), hash(2, 2, 3));
+        assert_ne!(hash(1, 2, 3), hash(1, 3, 2));
+    }
+
+    #[test]
+    fn test_noise_matches_lattice_and_is_continuous() {
+        // On a lattice point the noise is exactly the lattice value.
+        assert_eq!(value_noise(7, 3 * ONE, 4 * ONE), lattice(7, 3, 4));
+        assert_eq!(smoothstep(0), 0);
+        assert_eq!(smoothstep(ONE), ONE);
+
+        // Small steps move the value by a small amount, so terrain has no seams.
+        let step = ONE / 64;
+        for i in 0..256 {
+            let (x, y) = (i * step, 3 * ONE + i * step / 2);
+            let delta = (value_noise(7, x + step, y) - value_noise(7, x, y)).abs();
+            assert!(delta < ONE / 16, "jump of {delta} at {x}");
+        }
+    }
+}
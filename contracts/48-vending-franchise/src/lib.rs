@@ -0,0 +1,464 @@
+//!
+//! Stylus Vending Franchise
+//!
+//! A factory that opens `VendingMachine` franchises (`contracts/2-vending-machine`).
+//! The franchisor (the factory's owner) calls `open_franchise` with the
+//! machine's init code and the franchisee's settings; the factory deploys a
+//! fresh machine with CREATE2, runs its constructor with itself as owner, sets
+//! the price, and hands ownership to the franchisee, all in one transaction.
+//!
+//! Stylus constructors are an ordinary call that runs once, so deploying and
+//! constructing in separate transactions would let anyone construct the
+//! machine first. Doing both here closes that gap. The init code is supplied
+//! by the caller, so the factory also checks the deployed machine's code hash
+//! against the one the owner approved: every franchise runs the same program.
+//!
+//! Machines are recorded in a registry, per franchisee and overall, and
+//! `network_stats` / `franchisee_stats` add up users and sales across machines
+//! with static calls to each machine's `user_count` and `total_collected`.
+//! Those loops grow with the registry, so they are meant for off-chain reads.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall, SolValue};
+use stylus_sdk::{
+    abi::{Bytes, CONSTRUCTOR_SELECTOR},
+    alloy_primitives::{Address, B256, U256},
+    prelude::*,
+};
+
+sol! {
+    /// The parts of the vending machine the factory drives and reads.
+    interface IVendingMachine {
+        function setPrice(uint256 price) external;
+        function transferOwnership(address newOwner) external;
+        function userCount() external view returns (uint256);
+        function totalCollected() external view returns (uint256);
+    }
+
+    event FranchiseOpened(address indexed franchisee, address indexed machine, uint256 price);
+    event MachineCodeHashChanged(bytes32 codeHash);
+
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error InvalidConfig();
+    #[derive(Debug)]
+    error DeployFailed();
+    #[derive(Debug)]
+    error UnapprovedCode(address machine, bytes32 codeHash);
+    #[derive(Debug)]
+    error MachineCallFailed(address machine);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum VendingFranchiseError {
+    NotOwner(NotOwner),
+    InvalidConfig(InvalidConfig),
+    DeployFailed(DeployFailed),
+    UnapprovedCode(UnapprovedCode),
+    MachineCallFailed(MachineCallFailed),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct VendingFranchise {
+        address owner;
+        /// Runtime code hash every franchise machine must have.
+        bytes32 machine_code_hash;
+        address[] machines;
+        mapping(address => address[]) machines_of;
+        mapping(address => address) franchisee_of;
+    }
+}
+
+#[public]
+impl VendingFranchise {
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        owner: Address,
+        machine_code_hash: B256,
+    ) -> Result<(), VendingFranchiseError> {
+        if owner.is_zero() {
+            return Err(VendingFranchiseError::InvalidConfig(InvalidConfig {}));
+        }
+        self.owner.set(owner);
+        self.set_code_hash(machine_code_hash)
+    }
+
+    /// Deploys, configures and hands over a new machine to `franchisee`, returning its address.
+    ///
+    /// Only the owner may call this. The CREATE2 salt is derived from the franchisee and
+    /// their machine count, so each franchisee's machine addresses are predictable.
+    pub fn open_franchise(
+        &mut self,
+        franchisee: Address,
+        init_code: Bytes,
+        price: U256,
+        cooldown_seconds: u64,
+        max_users: u64,
+    ) -> Result<Address, VendingFranchiseError> {
+        self.only_owner()?;
+        if franchisee.is_zero() {
+            return Err(VendingFranchiseError::InvalidConfig(InvalidConfig {}));
+        }
+
+        let salt = self.salt_for(franchisee);
+        // SAFETY: the factory holds no state that a reentrant call could see half-updated;
+        // the registry is only written once the machine is fully set up.
+        let machine = unsafe { self.vm().deploy(&init_code, U256::ZERO, Some(salt)) }
+            .map_err(|_| VendingFranchiseError::DeployFailed(DeployFailed {}))?;
+        let code_hash = self.vm().code_hash(machine);
+        if code_hash != self.machine_code_hash.get() {
+            return Err(VendingFranchiseError::UnapprovedCode(UnapprovedCode {
+                machine,
+                codeHash: code_hash,
+            }));
+        }
+
+        let this = self.vm().contract_address();
+        let mut constructor = CONSTRUCTOR_SELECTOR.to_be_bytes().to_vec();
+        constructor.extend((this, cooldown_seconds, max_users).abi_encode_params());
+        self.call_machine(machine, &constructor)?;
+        self.call_machine(
+            machine,
+            &IVendingMachine::setPriceCall { price }.abi_encode(),
+        )?;
+        self.call_machine(
+            machine,
+            &IVendingMachine::transferOwnershipCall {
+                newOwner: franchisee,
+            }
+            .abi_encode(),
+        )?;
+
+        self.machines.push(machine);
+        self.machines_of.setter(franchisee).push(machine);
+        self.franchisee_of.setter(machine).set(franchisee);
+        log(
+            self.vm(),
+            FranchiseOpened {
+                franchisee,
+                machine,
+                price,
+            },
+        );
+        Ok(machine)
+    }
+
+    /// Changes the code hash new machines must have. Only the owner may call this;
+    /// machines already opened are unaffected.
+    pub fn set_machine_code_hash(&mut self, code_hash: B256) -> Result<(), VendingFranchiseError> {
+        self.only_owner()?;
+        self.set_code_hash(code_hash)
+    }
+
+    /// Returns `(users, collected)` summed over every machine in the network.
+    pub fn network_stats(&self) -> Result<(U256, U256), VendingFranchiseError> {
+        let machines: Vec<Address> = (0..self.machines.len())
+            .filter_map(|i| self.machines.get(i))
+            .collect();
+        self.aggregate(&machines)
+    }
+
+    /// Returns `(users, collected)` summed over one franchisee's machines.
+    pub fn franchisee_stats(
+        &self,
+        franchisee: Address,
+    ) -> Result<(U256, U256), VendingFranchiseError> {
+        self.aggregate(&self.machines_of(franchisee))
+    }
+
+    pub fn machine_count(&self) -> U256 {
+        U256::from(self.machines.len())
+    }
+
+    pub fn machine_at(&self, index: U256) -> Address {
+        self.machines.get(index).unwrap_or_default()
+    }
+
+    pub fn machines_of(&self, franchisee: Address) -> Vec<Address> {
+        let machines = self.machines_of.get(franchisee);
+        (0..machines.len())
+            .filter_map(|i| machines.get(i))
+            .collect()
+    }
+
+    /// The franchisee a machine was opened for, or zero if it is not part of the network.
+    pub fn franchisee_of(&self, machine: Address) -> Address {
+        self.franchisee_of.get(machine)
+    }
+
+    /// The address the franchisee's next machine will be deployed at, given its init code.
+    pub fn next_machine_address(&self, franchisee: Address, init_code: Bytes) -> Address {
+        let salt = self.salt_for(franchisee);
+        let code_hash = self.vm().native_keccak256(&init_code);
+        self.vm().contract_address().create2(salt, code_hash)
+    }
+
+    pub fn machine_code_hash(&self) -> B256 {
+        self.machine_code_hash.get()
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+}
+
+impl VendingFranchise {
+    fn only_owner(&self) -> Result<(), VendingFranchiseError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(VendingFranchiseError::NotOwner(NotOwner { caller }));
+        }
+        Ok(())
+    }
+
+    fn set_code_hash(&mut self, code_hash: B256) -> Result<(), VendingFranchiseError> {
+        if code_hash.is_zero() {
+            return Err(VendingFranchiseError::InvalidConfig(InvalidConfig {}));
+        }
+        self.machine_code_hash.set(code_hash);
+        log(
+            self.vm(),
+            MachineCodeHashChanged {
+                codeHash: code_hash,
+            },
+        );
+        Ok(())
+    }
+
+    /// `keccak256(abi.encodePacked(franchisee, machineCount))`.
+    fn salt_for(&self, franchisee: Address) -> B256 {
+        let count = U256::from(self.machines_of.get(franchisee).len());
+        let mut packed = franchisee.to_vec();
+        packed.extend_from_slice(&count.to_be_bytes::<32>());
+        self.vm().native_keccak256(&packed)
+    }
+
+    fn call_machine(&mut self, machine: Address, data: &[u8]) -> Result<(), VendingFranchiseError> {
+        self.vm()
+            .call(&calls::context::Call::new(), machine, data)
+            .map_err(|_| VendingFranchiseError::MachineCallFailed(MachineCallFailed { machine }))?;
+        Ok(())
+    }
+
+    /// Static-calls a machine view that returns a single `uint256`.
+    fn read_machine(
+        &self,
+        machine: Address,
+        call: impl SolCall,
+    ) -> Result<U256, VendingFranchiseError> {
+        let failed = || VendingFranchiseError::MachineCallFailed(MachineCallFailed { machine });
+        let returned = self
+            .vm()
+            .static_call(&calls::context::Call::new(), machine, &call.abi_encode())
+            .map_err(|_| failed())?;
+        U256::abi_decode(&returned, true).map_err(|_| failed())
+    }
+
+    fn aggregate(&self, machines: &[Address]) -> Result<(U256, U256), VendingFranchiseError> {
+        let (mut users, mut collected) = (U256::ZERO, U256::ZERO);
+        for &machine in machines {
+            users += self.read_machine(machine, IVendingMachine::userCountCall {})?;
+            collected += self.read_machine(machine, IVendingMachine::totalCollectedCall {})?;
+        }
+        Ok((users, collected))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::SolEvent;
+    use stylus_sdk::{alloy_primitives::keccak256, testing::*};
+
+    const FRANCHISOR: Address = Address::new([0x01; 20]);
+    const ALICE: Address = Address::new([0x02; 20]);
+    const BOB: Address = Address::new([0x03; 20]);
+    const MACHINE_A: Address = Address::new([0xa1; 20]);
+    const MACHINE_B: Address = Address::new([0xb1; 20]);
+    const RUNTIME: &[u8] = b"vending machine runtime";
+    const INIT_CODE: &[u8] = b"vending machine init code";
+    const PRICE: u64 = 2_000_000_000_000_000;
+
+    fn setup() -> (TestVM, VendingFranchise) {
+        let vm = TestVM::default();
+        vm.set_sender(FRANCHISOR);
+        let mut contract = VendingFranchise::from(&vm);
+        contract
+            .constructor(FRANCHISOR, keccak256(RUNTIME))
+            .unwrap();
+        (vm, contract)
+    }
+
+    /// Makes the next deployment for `franchisee` land at `machine` with `runtime` code.
+    fn expect_deploy(
+        vm: &TestVM,
+        contract: &VendingFranchise,
+        franchisee: Address,
+        machine: Address,
+        runtime: &[u8],
+    ) {
+        let salt = contract.salt_for(franchisee);
+        vm.mock_deploy(INIT_CODE.to_vec(), Some(salt), Ok(machine));
+        vm.set_code(machine, runtime.to_vec());
+    }
+
+    fn open(
+        contract: &mut VendingFranchise,
+        franchisee: Address,
+    ) -> Result<Address, VendingFranchiseError> {
+        contract.open_franchise(
+            franchisee,
+            INIT_CODE.to_vec().into(),
+            U256::from(PRICE),
+            60,
+            0,
+        )
+    }
+
+    fn mock_stats(vm: &TestVM, machine: Address, users: u64, collected: u64) {
+        let users_call = IVendingMachine::userCountCall {};
+        let collected_call = IVendingMachine::totalCollectedCall {};
+        vm.mock_static_call(
+            machine,
+            users_call.abi_encode(),
+            Ok(U256::from(users).abi_encode()),
+        );
+        vm.mock_static_call(
+            machine,
+            collected_call.abi_encode(),
+            Ok(U256::from(collected).abi_encode()),
+        );
+    }
+
+    #[test]
+    fn test_open_franchise_registers_machine() {
+        let (vm, mut contract) = setup();
+        expect_deploy(&vm, &contract, ALICE, MACHINE_A, RUNTIME);
+        assert_eq!(open(&mut contract, ALICE).unwrap(), MACHINE_A);
+        expect_deploy(&vm, &contract, ALICE, MACHINE_B, RUNTIME);
+        assert_eq!(open(&mut contract, ALICE).unwrap(), MACHINE_B);
+
+        assert_eq!(contract.machine_count(), U256::from(2));
+        assert_eq!(contract.machine_at(U256::from(1)), MACHINE_B);
+        assert_eq!(contract.machines_of(ALICE), vec![MACHINE_A, MACHINE_B]);
+        assert_eq!(contract.franchisee_of(MACHINE_A), ALICE);
+        assert!(contract.machines_of(BOB).is_empty());
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.last().unwrap().0[0], FranchiseOpened::SIGNATURE_HASH);
+        assert_eq!(logs.last().unwrap().0[2], MACHINE_B.into_word());
+    }
+
+    #[test]
+    fn test_salt_and_predicted_address_depend_on_count() {
+        let (vm, mut contract) = setup();
+        let first = contract.salt_for(ALICE);
+        assert_ne!(first, contract.salt_for(BOB));
+        let predicted = contract.next_machine_address(ALICE, INIT_CODE.to_vec().into());
+        assert_eq!(
+            predicted,
+            vm.contract_address().create2(first, keccak256(INIT_CODE))
+        );
+
+        expect_deploy(&vm, &contract, ALICE, MACHINE_A, RUNTIME);
+        open(&mut contract, ALICE).unwrap();
+        assert_ne!(contract.salt_for(ALICE), first);
+    }
+
+    #[test]
+    fn test_rejects_unapproved_code_and_failed_setup() {
+        let (vm, mut contract) = setup();
+        expect_deploy(&vm, &contract, ALICE, MACHINE_A, b"something else");
+        assert!(matches!(
+            open(&mut contract, ALICE),
+            Err(VendingFranchiseError::UnapprovedCode(UnapprovedCode { machine, .. }))
+                if machine == MACHINE_A
+        ));
+
+        expect_deploy(&vm, &contract, ALICE, MACHINE_A, RUNTIME);
+        let handover = IVendingMachine::transferOwnershipCall { newOwner: ALICE };
+        vm.mock_call(MACHINE_A, handover.abi_encode(), Err(b"NotOwner".to_vec()));
+        assert!(matches!(
+            open(&mut contract, ALICE),
+            Err(VendingFranchiseError::MachineCallFailed(MachineCallFailed { machine }))
+                if machine == MACHINE_A
+        ));
+
+        let salt = contract.salt_for(BOB);
+        vm.mock_deploy(INIT_CODE.to_vec(), Some(salt), Err(Vec::new()));
+        assert!(matches!(
+            open(&mut contract, BOB),
+            Err(VendingFranchiseError::DeployFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_only_owner_opens_and_configures() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            open(&mut contract, ALICE),
+            Err(VendingFranchiseError::NotOwner(_))
+        ));
+        assert!(matches!(
+            contract.set_machine_code_hash(B256::repeat_byte(7)),
+            Err(VendingFranchiseError::NotOwner(_))
+        ));
+
+        vm.set_sender(FRANCHISOR);
+        assert!(matches!(
+            open(&mut contract, Address::ZERO),
+            Err(VendingFranchiseError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            contract.set_machine_code_hash(B256::ZERO),
+            Err(VendingFranchiseError::InvalidConfig(_))
+        ));
+        contract
+            .set_machine_code_hash(B256::repeat_byte(7))
+            .unwrap();
+        assert_eq!(contract.machine_code_hash(), B256::repeat_byte(7));
+    }
+
+    #[test]
+    fn test_stats_aggregate_across_machines() {
+        let (vm, mut contract) = setup();
+        expect_deploy(&vm, &contract, ALICE, MACHINE_A, RUNTIME);
+        open(&mut contract, ALICE).unwrap();
+        expect_deploy(&vm, &contract, BOB, MACHINE_B, RUNTIME);
+        open(&mut contract, BOB).unwrap();
+
+        mock_stats(&vm, MACHINE_A, 3, 500);
+        mock_stats(&vm, MACHINE_B, 4, 250);
+        assert_eq!(
+            contract.network_stats().unwrap(),
+            (U256::from(7), U256::from(750))
+        );
+        assert_eq!(
+            contract.franchisee_stats(BOB).unwrap(),
+            (U256::from(4), U256::from(250))
+        );
+        assert_eq!(
+            contract.franchisee_stats(FRANCHISOR).unwrap(),
+            (U256::ZERO, U256::ZERO)
+        );
+
+        let down = IVendingMachine::totalCollectedCall {};
+        vm.mock_static_call(MACHINE_B, down.abi_encode(), Err(Vec::new()));
+        assert!(matches!(
+            contract.network_stats(),
+            Err(VendingFranchiseError::MachineCallFailed(MachineCallFailed { machine }))
+                if machine == MACHINE_B
+        ));
+    }
+}
@@ -0,0 +1,535 @@
+//!
+//! Stylus Payment Stream
+//!
+//! Money streaming: a sender locks ETH in a stream to a recipient, and the
+//! deposit vests linearly, second by second, between a start and a stop
+//! time. `create_stream` takes the deposit as `msg.value`; before the start
+//! nothing has vested and after the stop everything has.
+//!
+//! The recipient can `withdraw_from_stream` any part of what has vested at
+//! any time. Either party can `cancel_stream` while it is running: the
+//! recipient keeps everything vested up to that second and the sender gets
+//! the rest back. The caller's share is sent at once; the other party's is
+//! credited to them and collected with `withdraw`, so neither side can block
+//! a cancellation by refusing ETH.
+//!
+//! Vesting is computed as `deposit * elapsed / duration` in `U256`, which
+//! cannot overflow for any deposit that fits in the chain's ETH supply and
+//! rounds down, so the recipient can never take more than the deposit.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256, U64},
+    prelude::*,
+};
+
+sol! {
+    event StreamCreated(uint256 indexed streamId, address indexed sender, address indexed recipient, uint256 deposit, uint256 startTime, uint256 stopTime);
+    event WithdrawnFromStream(uint256 indexed streamId, address indexed recipient, uint256 amount);
+    event StreamCancelled(uint256 indexed streamId, uint256 senderBalance, uint256 recipientBalance);
+    event Withdrawn(address indexed account, uint256 amount);
+
+    #[derive(Debug)]
+    error InvalidStream(string reason);
+    #[derive(Debug)]
+    error UnknownStream(uint256 streamId);
+    #[derive(Debug)]
+    error StreamCancelledAlready(uint256 streamId);
+    #[derive(Debug)]
+    error NotRecipient(address caller);
+    #[derive(Debug)]
+    error NotParty(address caller);
+    #[derive(Debug)]
+    error InsufficientVested(uint256 available, uint256 requested);
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+    #[derive(Debug)]
+    error NothingToWithdraw();
+}
+
+#[derive(SolidityError, Debug)]
+pub enum PaymentStreamError {
+    InvalidStream(InvalidStream),
+    UnknownStream(UnknownStream),
+    StreamCancelledAlready(StreamCancelledAlready),
+    NotRecipient(NotRecipient),
+    NotParty(NotParty),
+    InsufficientVested(InsufficientVested),
+    TransferFailed(TransferFailed),
+    NothingToWithdraw(NothingToWithdraw),
+}
+
+impl PaymentStreamError {
+    fn invalid(reason: &str) -> Self {
+        PaymentStreamError::InvalidStream(InvalidStream {
+            reason: reason.into(),
+        })
+    }
+}
+
+sol_storage! {
+    pub struct Stream {
+        /// Zero for a stream id that was never created.
+        address sender;
+        address recipient;
+        uint256 deposit;
+        /// Total the recipient has taken out so far.
+        uint256 withdrawn;
+        uint64 start_time;
+        uint64 stop_time;
+        bool cancelled;
+    }
+
+    #[entrypoint]
+    pub struct PaymentStream {
+        uint256 stream_count;
+        mapping(uint256 => Stream) streams;
+        /// Shares of cancelled streams waiting for the non-cancelling party.
+        mapping(address => uint256) pending_withdrawals;
+    }
+}
+
+/// The part of `deposit` vested at `now` for a stream running from `start` to `stop`.
+pub fn vested_amount(deposit: U256, start: u64, stop: u64, now: u64) -> U256 {
+    if now <= start {
+        return U256::ZERO;
+    }
+    if now >= stop {
+        return deposit;
+    }
+    deposit * U256::from(now - start) / U256::from(stop - start)
+}
+
+#[public]
+impl PaymentStream {
+    /// Opens a stream of `msg.value` wei to `recipient` from `start_time` to `stop_time`,
+    /// returning its id.
+    #[payable]
+    pub fn create_stream(
+        &mut self,
+        recipient: Address,
+        start_time: u64,
+        stop_time: u64,
+    ) -> Result<U256, PaymentStreamError> {
+        let sender = self.vm().msg_sender();
+        let deposit = self.vm().msg_value();
+        if recipient.is_zero() || recipient == self.vm().contract_address() {
+            return Err(PaymentStreamError::invalid("Invalid recipient"));
+        }
+        if recipient == sender {
+            return Err(PaymentStreamError::invalid("Cannot stream to yourself"));
+        }
+        if deposit.is_zero() {
+            return Err(PaymentStreamError::invalid("Deposit is zero"));
+        }
+        if start_time < self.vm().block_timestamp() {
+            return Err(PaymentStreamError::invalid("Start time in the past"));
+        }
+        if stop_time <= start_time {
+            return Err(PaymentStreamError::invalid("Stop time not after start"));
+        }
+
+        let stream_id = self.stream_count.get();
+        self.stream_count.set(stream_id + U256::from(1));
+        let mut stream = self.streams.setter(stream_id);
+        stream.sender.set(sender);
+        stream.recipient.set(recipient);
+        stream.deposit.set(deposit);
+        stream.start_time.set(U64::from(start_time));
+        stream.stop_time.set(U64::from(stop_time));
+        log(
+            self.vm(),
+            StreamCreated {
+                streamId: stream_id,
+                sender,
+                recipient,
+                deposit,
+                startTime: U256::from(start_time),
+                stopTime: U256::from(stop_time),
+            },
+        );
+        Ok(stream_id)
+    }
+
+    /// Sends `amount` of the vested, unwithdrawn balance to the recipient. Only the recipient
+    /// may withdraw.
+    pub fn withdraw_from_stream(
+        &mut self,
+        stream_id: U256,
+        amount: U256,
+    ) -> Result<(), PaymentStreamError> {
+        self.require_running(stream_id)?;
+        let recipient = self.streams.getter(stream_id).recipient.get();
+        let caller = self.vm().msg_sender();
+        if caller != recipient {
+            return Err(PaymentStreamError::NotRecipient(NotRecipient { caller }));
+        }
+        let available = self.recipient_balance(stream_id);
+        if amount.is_zero() || amount > available {
+            return Err(PaymentStreamError::InsufficientVested(InsufficientVested {
+                available,
+                requested: amount,
+            }));
+        }
+
+        let withdrawn = self.streams.getter(stream_id).withdrawn.get() + amount;
+        self.streams.setter(stream_id).withdrawn.set(withdrawn);
+        self.send(recipient, amount)?;
+        log(
+            self.vm(),
+            WithdrawnFromStream {
+                streamId: stream_id,
+                recipient,
+                amount,
+            },
+        );
+        Ok(())
+    }
+
+    /// Ends a stream, splitting the deposit at the current second. Either party may cancel.
+    pub fn cancel_stream(&mut self, stream_id: U256) -> Result<(), PaymentStreamError> {
+        self.require_running(stream_id)?;
+        let stream = self.streams.getter(stream_id);
+        let (sender, recipient) = (stream.sender.get(), stream.recipient.get());
+        let caller = self.vm().msg_sender();
+        if caller != sender && caller != recipient {
+            return Err(PaymentStreamError::NotParty(NotParty { caller }));
+        }
+        let recipient_balance = self.recipient_balance(stream_id);
+        let sender_balance = self.sender_balance(stream_id);
+
+        self.streams.setter(stream_id).cancelled.set(true);
+        let (own, other, other_share) = if caller == sender {
+            (sender_balance, recipient, recipient_balance)
+        } else {
+            (recipient_balance, sender, sender_balance)
+        };
+        if !other_share.is_zero() {
+            let pending = self.pending_withdrawals.get(other) + other_share;
+            self.pending_withdrawals.setter(other).set(pending);
+        }
+        if !own.is_zero() {
+            self.send(caller, own)?;
+        }
+        log(
+            self.vm(),
+            StreamCancelled {
+                streamId: stream_id,
+                senderBalance: sender_balance,
+                recipientBalance: recipient_balance,
+            },
+        );
+        Ok(())
+    }
+
+    /// Sends the caller every share credited to them by a cancellation.
+    pub fn withdraw(&mut self) -> Result<U256, PaymentStreamError> {
+        let account = self.vm().msg_sender();
+        let amount = self.pending_withdrawals.get(account);
+        if amount.is_zero() {
+            return Err(PaymentStreamError::NothingToWithdraw(NothingToWithdraw {}));
+        }
+        // `send` hands control to `account`, so their credit must already read zero.
+        self.pending_withdrawals.setter(account).set(U256::ZERO);
+        self.send(account, amount)?;
+        log(self.vm(), Withdrawn { account, amount });
+        Ok(amount)
+    }
+
+    /// What `who` could take out of a running stream right now: the vested, unwithdrawn
+    /// amount for the recipient and the unvested remainder for the sender. Zero for
+    /// anyone else and for cancelled streams.
+    pub fn balance_of(&self, stream_id: U256, who: Address) -> Result<U256, PaymentStreamError> {
+        let stream = self.streams.getter(stream_id);
+        if stream.sender.get().is_zero() {
+            return Err(PaymentStreamError::UnknownStream(UnknownStream {
+                streamId: stream_id,
+            }));
+        }
+        if stream.cancelled.get() {
+            return Ok(U256::ZERO);
+        }
+        Ok(if who == stream.recipient.get() {
+            self.recipient_balance(stream_id)
+        } else if who == stream.sender.get() {
+            self.sender_balance(stream_id)
+        } else {
+            U256::ZERO
+        })
+    }
+
+    /// `(sender, recipient, deposit, startTime, stopTime, withdrawn, cancelled)` for a stream.
+    #[allow(clippy::type_complexity)]
+    pub fn stream(
+        &self,
+        stream_id: U256,
+    ) -> Result<(Address, Address, U256, u64, u64, U256, bool), PaymentStreamError> {
+        let stream = self.streams.getter(stream_id);
+        if stream.sender.get().is_zero() {
+            return Err(PaymentStreamError::UnknownStream(UnknownStream {
+                streamId: stream_id,
+            }));
+        }
+        Ok((
+            stream.sender.get(),
+            stream.recipient.get(),
+            stream.deposit.get(),
+            stream.start_time.get().to::<u64>(),
+            stream.stop_time.get().to::<u64>(),
+            stream.withdrawn.get(),
+            stream.cancelled.get(),
+        ))
+    }
+
+    pub fn stream_count(&self) -> U256 {
+        self.stream_count.get()
+    }
+
+    pub fn pending_withdrawal(&self, account: Address) -> U256 {
+        self.pending_withdrawals.get(account)
+    }
+}
+
+impl PaymentStream {
+    fn require_running(&self, stream_id: U256) -> Result<(), PaymentStreamError> {
+        let stream = self.streams.getter(stream_id);
+        if stream.sender.get().is_zero() {
+            return Err(PaymentStreamError::UnknownStream(UnknownStream {
+                streamId: stream_id,
+            }));
+        }
+        if stream.cancelled.get() {
+            return Err(PaymentStreamError::StreamCancelledAlready(
+                StreamCancelledAlready {
+                    streamId: stream_id,
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    fn vested(&self, stream_id: U256) -> U256 {
+        let stream = self.streams.getter(stream_id);
+        vested_amount(
+            stream.deposit.get(),
+            stream.start_time.get().to::<u64>(),
+            stream.stop_time.get().to::<u64>(),
+            self.vm().block_timestamp(),
+        )
+    }
+
+    fn recipient_balance(&self, stream_id: U256) -> U256 {
+        self.vested(stream_id) - self.streams.getter(stream_id).withdrawn.get()
+    }
+
+    fn sender_balance(&self, stream_id: U256) -> U256 {
+        self.streams.getter(stream_id).deposit.get() - self.vested(stream_id)
+    }
+
+    fn send(&mut self, to: Address, amount: U256) -> Result<(), PaymentStreamError> {
+        self.vm()
+            .transfer_eth(to, amount)
+            .map_err(|_| PaymentStreamError::TransferFailed(TransferFailed { to, amount }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const ALICE: Address = Address::new([0x01; 20]);
+    const BOB: Address = Address::new([0x02; 20]);
+    const CAROL: Address = Address::new([0x03; 20]);
+    const NOW: u64 = 1_000;
+    const START: u64 = 2_000;
+    const STOP: u64 = 3_000;
+    const DEPOSIT: u64 = 1_000_000;
+
+    /// Alice streams `DEPOSIT` wei to Bob from `START` to `STOP`.
+    fn setup() -> (TestVM, PaymentStream, U256) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(NOW);
+        let mut contract = PaymentStream::from(&vm);
+        let stream_id = create(&vm, &mut contract, BOB, START, STOP, DEPOSIT).unwrap();
+        (vm, contract, stream_id)
+    }
+
+    /// Opens a stream from Alice with `deposit` attached, crediting the contract if it succeeds.
+    fn create(
+        vm: &TestVM,
+        contract: &mut PaymentStream,
+        recipient: Address,
+        start: u64,
+        stop: u64,
+        deposit: u64,
+    ) -> Result<U256, PaymentStreamError> {
+        vm.set_sender(ALICE);
+        vm.set_value(U256::from(deposit));
+        let result = contract.create_stream(recipient, start, stop);
+        if result.is_ok() {
+            let balance = vm.balance(vm.contract_address());
+            vm.set_balance(vm.contract_address(), balance + U256::from(deposit));
+        }
+        vm.set_value(U256::ZERO);
+        result
+    }
+
+    #[test]
+    fn test_vesting_is_linear_and_clamped() {
+        let deposit = U256::from(DEPOSIT);
+        assert_eq!(vested_amount(deposit, START, STOP, NOW), U256::ZERO);
+        assert_eq!(vested_amount(deposit, START, STOP, START), U256::ZERO);
+        assert_eq!(
+            vested_amount(deposit, START, STOP, START + 250),
+            U256::from(DEPOSIT / 4)
+        );
+        assert_eq!(vested_amount(deposit, START, STOP, STOP), deposit);
+        assert_eq!(vested_amount(deposit, START, STOP, STOP + 1), deposit);
+        // Rounds down, so the recipient is never ahead of the schedule.
+        assert_eq!(vested_amount(U256::from(2), 0, 3, 1), U256::ZERO);
+    }
+
+    #[test]
+    fn test_recipient_withdraws_vested_portion() {
+        let (vm, mut contract, stream_id) = setup();
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.withdraw_from_stream(stream_id, U256::from(1)),
+            Err(PaymentStreamError::InsufficientVested(_))
+        ));
+
+        vm.set_block_timestamp(START + 400);
+        assert_eq!(
+            contract.balance_of(stream_id, BOB).unwrap(),
+            U256::from(400_000)
+        );
+        assert_eq!(
+            contract.balance_of(stream_id, ALICE).unwrap(),
+            U256::from(600_000)
+        );
+        contract
+            .withdraw_from_stream(stream_id, U256::from(300_000))
+            .unwrap();
+        assert_eq!(vm.balance(BOB), U256::from(300_000));
+        assert!(matches!(
+            contract.withdraw_from_stream(stream_id, U256::from(100_001)),
+            Err(PaymentStreamError::InsufficientVested(InsufficientVested { available, .. }))
+                if available == U256::from(100_000)
+        ));
+
+        vm.set_sender(CAROL);
+        assert!(matches!(
+            contract.withdraw_from_stream(stream_id, U256::from(1)),
+            Err(PaymentStreamError::NotRecipient(_))
+        ));
+
+        vm.set_block_timestamp(STOP + 10);
+        vm.set_sender(BOB);
+        contract
+            .withdraw_from_stream(stream_id, U256::from(700_000))
+            .unwrap();
+        assert_eq!(vm.balance(BOB), U256::from(DEPOSIT));
+        assert_eq!(contract.stream(stream_id).unwrap().5, U256::from(DEPOSIT));
+    }
+
+    #[test]
+    fn test_cancel_splits_deposit_fairly() {
+        let (vm, mut contract, stream_id) = setup();
+        vm.set_block_timestamp(START + 250);
+        vm.set_sender(BOB);
+        contract
+            .withdraw_from_stream(stream_id, U256::from(100_000))
+            .unwrap();
+
+        vm.set_block_timestamp(START + 400);
+        vm.set_sender(CAROL);
+        assert!(matches!(
+            contract.cancel_stream(stream_id),
+            Err(PaymentStreamError::NotParty(_))
+        ));
+        vm.set_sender(ALICE);
+        contract.cancel_stream(stream_id).unwrap();
+        // Alice gets the unvested 600,000 now; Bob's remaining 300,000 waits for him.
+        assert_eq!(vm.balance(ALICE), U256::from(600_000));
+        assert_eq!(contract.pending_withdrawal(BOB), U256::from(300_000));
+        assert_eq!(contract.balance_of(stream_id, BOB).unwrap(), U256::ZERO);
+        assert!(matches!(
+            contract.cancel_stream(stream_id),
+            Err(PaymentStreamError::StreamCancelledAlready(_))
+        ));
+
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.withdraw_from_stream(stream_id, U256::from(1)),
+            Err(PaymentStreamError::StreamCancelledAlready(_))
+        ));
+        assert_eq!(contract.withdraw().unwrap(), U256::from(300_000));
+        assert_eq!(vm.balance(BOB), U256::from(400_000));
+        assert_eq!(vm.balance(vm.contract_address()), U256::ZERO);
+        assert!(matches!(
+            contract.withdraw(),
+            Err(PaymentStreamError::NothingToWithdraw(_))
+        ));
+    }
+
+    #[test]
+    fn test_recipient_cancels_before_start() {
+        let (vm, mut contract, stream_id) = setup();
+        vm.set_sender(BOB);
+        contract.cancel_stream(stream_id).unwrap();
+        assert_eq!(vm.balance(BOB), U256::ZERO);
+        assert_eq!(contract.pending_withdrawal(BOB), U256::ZERO);
+        assert_eq!(contract.pending_withdrawal(ALICE), U256::from(DEPOSIT));
+        assert!(contract.stream(stream_id).unwrap().6);
+    }
+
+    #[test]
+    fn test_rejects_bad_streams() {
+        let (vm, mut contract, _) = setup();
+        let this = vm.contract_address();
+        for (recipient, start, stop, deposit) in [
+            (Address::ZERO, START, STOP, DEPOSIT),
+            (this, START, STOP, DEPOSIT),
+            (ALICE, START, STOP, DEPOSIT),
+            (BOB, START, STOP, 0),
+            (BOB, NOW - 1, STOP, DEPOSIT),
+            (BOB, START, START, DEPOSIT),
+        ] {
+            assert!(matches!(
+                create(&vm, &mut contract, recipient, start, stop, deposit),
+                Err(PaymentStreamError::InvalidStream(_))
+            ));
+        }
+        assert_eq!(contract.stream_count(), U256::from(1));
+        assert!(matches!(
+            contract.stream(U256::from(5)),
+            Err(PaymentStreamError::UnknownStream(_))
+        ));
+        assert!(matches!(
+            contract.cancel_stream(U256::from(5)),
+            Err(PaymentStreamError::UnknownStream(_))
+        ));
+    }
+
+    #[test]
+    fn test_failed_transfer_surfaces_error() {
+        let (vm, mut contract, stream_id) = setup();
+        vm.set_block_timestamp(STOP);
+        vm.set_balance(vm.contract_address(), U256::ZERO);
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.withdraw_from_stream(stream_id, U256::from(DEPOSIT)),
+            Err(PaymentStreamError::TransferFailed(TransferFailed { to, .. })) if to == BOB
+        ));
+    }
+}
@@ -0,0 +1,392 @@
+//!
+//! Stylus Workflow Engine
+//!
+//! The owner defines named states and the transitions allowed between them,
+//! each optionally gated behind a role. Items are created in the first state
+//! and move through the workflow one transition at a time, keeping a full
+//! history of who moved them, when, and from where to where.
+//!
+//! This is the skeleton of approval and escrow flows: e.g. `Draft -> Submitted`
+//! open to anyone, `Submitted -> Approved` restricted to an `APPROVER` role.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, B256, U256},
+    prelude::*,
+};
+
+/// Role that lets anyone take a transition.
+pub const OPEN_ROLE: B256 = B256::ZERO;
+
+sol! {
+    event StateAdded(uint256 indexed state, string name);
+    event TransitionSet(uint256 indexed from, uint256 indexed to, bytes32 role);
+    event TransitionRemoved(uint256 indexed from, uint256 indexed to);
+    event RoleGranted(bytes32 indexed role, address indexed account);
+    event RoleRevoked(bytes32 indexed role, address indexed account);
+    event ItemCreated(uint256 indexed item, address indexed creator);
+    event ItemTransitioned(uint256 indexed item, uint256 from, uint256 to, address indexed actor);
+}
+
+sol_storage! {
+    pub struct TransitionRule {
+        bool allowed;
+        bytes32 role;
+    }
+
+    pub struct HistoryEntry {
+        uint256 from;
+        uint256 to;
+        address actor;
+        uint256 timestamp;
+    }
+
+    pub struct Item {
+        /// Current state; zero for items that do not exist.
+        uint256 state;
+        HistoryEntry[] history;
+    }
+
+    #[entrypoint]
+    pub struct WorkflowEngine {
+        address owner;
+        /// States are numbered from 1 in the order they were added.
+        uint256 state_count;
+        mapping(uint256 => string) state_names;
+        mapping(uint256 => mapping(uint256 => TransitionRule)) transitions;
+        mapping(bytes32 => mapping(address => bool)) role_members;
+        /// Items are numbered from 1 in creation order.
+        uint256 item_count;
+        mapping(uint256 => Item) items;
+    }
+}
+
+#[public]
+impl WorkflowEngine {
+    #[constructor]
+    pub fn constructor(&mut self, owner: Address) {
+        self.owner.set(owner);
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    /// Adds a named state and returns its id. The first state is where items start.
+    pub fn add_state(&mut self, name: String) -> Result<U256, Vec<u8>> {
+        self.only_owner()?;
+        if name.is_empty() {
+            return Err("State name must not be empty".into());
+        }
+        let state = self.state_count.get() + U256::from(1);
+        self.state_count.set(state);
+        self.state_names.setter(state).set_str(&name);
+        log(self.vm(), StateAdded { state, name });
+        Ok(state)
+    }
+
+    /// Allows moving items from `from` to `to` for holders of `role` (or anyone, for `OPEN_ROLE`).
+    pub fn set_transition(&mut self, from: U256, to: U256, role: B256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if !self.state_exists(from) || !self.state_exists(to) {
+            return Err("Unknown state".into());
+        }
+        let mut targets = self.transitions.setter(from);
+        let mut rule = targets.setter(to);
+        rule.allowed.set(true);
+        rule.role.set(role);
+        log(self.vm(), TransitionSet { from, to, role });
+        Ok(())
+    }
+
+    pub fn remove_transition(&mut self, from: U256, to: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        let mut targets = self.transitions.setter(from);
+        let mut rule = targets.setter(to);
+        rule.allowed.set(false);
+        rule.role.set(OPEN_ROLE);
+        log(self.vm(), TransitionRemoved { from, to });
+        Ok(())
+    }
+
+    pub fn grant_role(&mut self, role: B256, account: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.role_members.setter(role).insert(account, true);
+        log(self.vm(), RoleGranted { role, account });
+        Ok(())
+    }
+
+    pub fn revoke_role(&mut self, role: B256, account: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.role_members.setter(role).insert(account, false);
+        log(self.vm(), RoleRevoked { role, account });
+        Ok(())
+    }
+
+    pub fn has_role(&self, role: B256, account: Address) -> bool {
+        role == OPEN_ROLE || self.role_members.getter(role).get(account)
+    }
+
+    /// Creates an item in the first state and returns its id.
+    pub fn create_item(&mut self) -> Result<U256, Vec<u8>> {
+        let initial = U256::from(1);
+        if !self.state_exists(initial) {
+            return Err("No states defined".into());
+        }
+        let item = self.item_count.get() + U256::from(1);
+        self.item_count.set(item);
+        self.items.setter(item).state.set(initial);
+        log(
+            self.vm(),
+            ItemCreated {
+                item,
+                creator: self.vm().msg_sender(),
+            },
+        );
+        Ok(item)
+    }
+
+    /// Moves an item to `to`, if a transition exists and the caller holds its role.
+    pub fn transition(&mut self, item: U256, to: U256) -> Result<(), Vec<u8>> {
+        let actor = self.vm().msg_sender();
+        let timestamp = U256::from(self.vm().block_timestamp());
+        let from = self.items.getter(item).state.get();
+        if from.is_zero() {
+            return Err("Unknown item".into());
+        }
+        let (allowed, role) = self.transition_rule(from, to);
+        if !allowed {
+            return Err("Transition not allowed".into());
+        }
+        if !self.has_role(role, actor) {
+            return Err("Missing role for transition".into());
+        }
+
+        let mut entry = self.items.setter(item);
+        entry.state.set(to);
+        let mut record = entry.history.grow();
+        record.from.set(from);
+        record.to.set(to);
+        record.actor.set(actor);
+        record.timestamp.set(timestamp);
+
+        log(
+            self.vm(),
+            ItemTransitioned {
+                item,
+                from,
+                to,
+                actor,
+            },
+        );
+        Ok(())
+    }
+
+    /// Whether `from -> to` is allowed, and the role it requires.
+    pub fn transition_rule(&self, from: U256, to: U256) -> (bool, B256) {
+        let targets = self.transitions.getter(from);
+        let rule = targets.getter(to);
+        (rule.allowed.get(), rule.role.get())
+    }
+
+    pub fn state_count(&self) -> U256 {
+        self.state_count.get()
+    }
+
+    pub fn state_name(&self, state: U256) -> String {
+        self.state_names.getter(state).get_string()
+    }
+
+    pub fn item_count(&self) -> U256 {
+        self.item_count.get()
+    }
+
+    pub fn state_of(&self, item: U256) -> U256 {
+        self.items.getter(item).state.get()
+    }
+
+    pub fn history_length(&self, item: U256) -> U256 {
+        U256::from(self.items.getter(item).history.len())
+    }
+
+    /// Returns `(from, to, actor, timestamp)` for one step of an item's history.
+    pub fn history_entry(
+        &self,
+        item: U256,
+        index: U256,
+    ) -> Result<(U256, U256, Address, U256), Vec<u8>> {
+        let item = self.items.getter(item);
+        let entry = item
+            .history
+            .getter(index)
+            .ok_or("History index out of bounds")?;
+        Ok((
+            entry.from.get(),
+            entry.to.get(),
+            entry.actor.get(),
+            entry.timestamp.get(),
+        ))
+    }
+}
+
+impl WorkflowEngine {
+    fn only_owner(&self) -> Result<(), Vec<u8>> {
+        if self.vm().msg_sender() != self.owner.get() {
+            return Err("Caller is not the owner".into());
+        }
+        Ok(())
+    }
+
+    fn state_exists(&self, state: U256) -> bool {
+        !state.is_zero() && state <= self.state_count.get()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::string::ToString;
+    use alloy_sol_types::SolEvent;
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::new([0x01; 20]);
+    const USER: Address = Address::new([0x02; 20]);
+    const APPROVER: Address = Address::new([0x03; 20]);
+    const APPROVER_ROLE: B256 = B256::new([0xaa; 32]);
+
+    const DRAFT: U256 = U256::from_limbs([1, 0, 0, 0]);
+    const SUBMITTED: U256 = U256::from_limbs([2, 0, 0, 0]);
+    const APPROVED: U256 = U256::from_limbs([3, 0, 0, 0]);
+
+    /// Draft -> Submitted (anyone), Submitted -> Approved (approvers only).
+    fn setup() -> (TestVM, WorkflowEngine) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(1_000);
+        let mut contract = WorkflowEngine::from(&vm);
+        contract.constructor(OWNER);
+
+        vm.set_sender(OWNER);
+        for name in ["Draft", "Submitted", "Approved"] {
+            contract.add_state(name.to_string()).unwrap();
+        }
+        contract
+            .set_transition(DRAFT, SUBMITTED, OPEN_ROLE)
+            .unwrap();
+        contract
+            .set_transition(SUBMITTED, APPROVED, APPROVER_ROLE)
+            .unwrap();
+        contract.grant_role(APPROVER_ROLE, APPROVER).unwrap();
+        vm.set_sender(USER);
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_setup_defines_states_and_rules() {
+        let (_vm, contract) = setup();
+        assert_eq!(contract.state_count(), U256::from(3));
+        assert_eq!(contract.state_name(SUBMITTED), "Submitted");
+        assert_eq!(
+            contract.transition_rule(DRAFT, SUBMITTED),
+            (true, OPEN_ROLE)
+        );
+        assert_eq!(
+            contract.transition_rule(SUBMITTED, APPROVED),
+            (true, APPROVER_ROLE)
+        );
+        assert_eq!(
+            contract.transition_rule(DRAFT, APPROVED),
+            (false, OPEN_ROLE)
+        );
+        assert!(contract.has_role(APPROVER_ROLE, APPROVER));
+        assert!(!contract.has_role(APPROVER_ROLE, USER));
+    }
+
+    #[test]
+    fn test_only_owner_configures_workflow() {
+        let (_vm, mut contract) = setup();
+        assert!(contract.add_state("Rejected".to_string()).is_err());
+        assert!(contract.set_transition(DRAFT, APPROVED, OPEN_ROLE).is_err());
+        assert!(contract.grant_role(APPROVER_ROLE, USER).is_err());
+        assert_eq!(contract.state_count(), U256::from(3));
+    }
+
+    #[test]
+    fn test_item_moves_through_workflow_with_history() {
+        let (vm, mut contract) = setup();
+        let item = contract.create_item().unwrap();
+        assert_eq!(contract.state_of(item), DRAFT);
+
+        contract.transition(item, SUBMITTED).unwrap();
+        vm.set_block_timestamp(2_000);
+        vm.set_sender(APPROVER);
+        contract.transition(item, APPROVED).unwrap();
+
+        assert_eq!(contract.state_of(item), APPROVED);
+        assert_eq!(contract.history_length(item), U256::from(2));
+        assert_eq!(
+            contract.history_entry(item, U256::ZERO).unwrap(),
+            (DRAFT, SUBMITTED, USER, U256::from(1_000))
+        );
+        assert_eq!(
+            contract.history_entry(item, U256::from(1)).unwrap(),
+            (SUBMITTED, APPROVED, APPROVER, U256::from(2_000))
+        );
+        assert!(contract.history_entry(item, U256::from(2)).is_err());
+    }
+
+    #[test]
+    fn test_transition_requires_rule_and_role() {
+        let (vm, mut contract) = setup();
+        let item = contract.create_item().unwrap();
+
+        assert!(contract.transition(item, APPROVED).is_err());
+        contract.transition(item, SUBMITTED).unwrap();
+        assert!(contract.transition(item, APPROVED).is_err());
+
+        vm.set_sender(OWNER);
+        contract.revoke_role(APPROVER_ROLE, APPROVER).unwrap();
+        vm.set_sender(APPROVER);
+        assert!(contract.transition(item, APPROVED).is_err());
+        assert_eq!(contract.state_of(item), SUBMITTED);
+    }
+
+    #[test]
+    fn test_removed_transition_is_rejected() {
+        let (vm, mut contract) = setup();
+        let item = contract.create_item().unwrap();
+        vm.set_sender(OWNER);
+        contract.remove_transition(DRAFT, SUBMITTED).unwrap();
+
+        vm.set_sender(USER);
+        assert!(contract.transition(item, SUBMITTED).is_err());
+        assert!(contract.transition(U256::from(99), SUBMITTED).is_err());
+    }
+
+    #[test]
+    fn test_create_item_requires_states() {
+        let vm = TestVM::default();
+        let mut contract = WorkflowEngine::from(&vm);
+        contract.constructor(OWNER);
+        assert!(contract.create_item().is_err());
+    }
+
+    #[test]
+    fn test_transition_emits_event() {
+        let (vm, mut contract) = setup();
+        let item = contract.create_item().unwrap();
+        contract.transition(item, SUBMITTED).unwrap();
+
+        let logs = vm.get_emitted_logs();
+        let (topics, _data) = logs.last().unwrap();
+        assert_eq!(topics[0], ItemTransitioned::SIGNATURE_HASH);
+    }
+}
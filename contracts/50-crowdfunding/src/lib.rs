@@ -0,0 +1,400 @@
+//!
+//! Stylus Crowdfunding
+//!
+//! A single all-or-nothing campaign, Kickstarter style. The constructor sets
+//! the creator, the funding goal in wei and the deadline. Until the deadline
+//! anyone can back the campaign with `contribute()`, and every backer's total
+//! is tracked.
+//!
+//! Once the deadline has passed the outcome is fixed. If the goal was met,
+//! the creator takes everything raised with `claim_funds()`; if it was not,
+//! each backer takes their own contribution back with `refund()`. Neither
+//! side ever depends on the other: funds are pulled, never pushed.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256, U64},
+    prelude::*,
+};
+
+sol! {
+    event Contributed(address indexed backer, uint256 amount, uint256 totalRaised);
+    event FundsClaimed(address indexed creator, uint256 amount);
+    event Refunded(address indexed backer, uint256 amount);
+
+    #[derive(Debug)]
+    error InvalidCampaign(string reason);
+    #[derive(Debug)]
+    error CampaignEnded(uint256 deadline);
+    #[derive(Debug)]
+    error CampaignActive(uint256 deadline);
+    #[derive(Debug)]
+    error ZeroContribution();
+    #[derive(Debug)]
+    error NotCreator(address caller);
+    #[derive(Debug)]
+    error GoalNotReached(uint256 raised, uint256 goal);
+    #[derive(Debug)]
+    error GoalReached(uint256 raised, uint256 goal);
+    #[derive(Debug)]
+    error AlreadyClaimed();
+    #[derive(Debug)]
+    error NothingToRefund(address backer);
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum CrowdfundingError {
+    InvalidCampaign(InvalidCampaign),
+    CampaignEnded(CampaignEnded),
+    CampaignActive(CampaignActive),
+    ZeroContribution(ZeroContribution),
+    NotCreator(NotCreator),
+    GoalNotReached(GoalNotReached),
+    GoalReached(GoalReached),
+    AlreadyClaimed(AlreadyClaimed),
+    NothingToRefund(NothingToRefund),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct Crowdfunding {
+        address creator;
+        uint256 goal;
+        uint64 deadline;
+        uint256 total_raised;
+        bool claimed;
+        mapping(address => uint256) contributions;
+    }
+}
+
+#[public]
+impl Crowdfunding {
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        creator: Address,
+        goal: U256,
+        deadline: u64,
+    ) -> Result<(), CrowdfundingError> {
+        let invalid = |reason: &str| {
+            CrowdfundingError::InvalidCampaign(InvalidCampaign {
+                reason: reason.into(),
+            })
+        };
+        if creator.is_zero() {
+            return Err(invalid("Creator is zero"));
+        }
+        if goal.is_zero() {
+            return Err(invalid("Goal is zero"));
+        }
+        if deadline <= self.vm().block_timestamp() {
+            return Err(invalid("Deadline not in the future"));
+        }
+        self.creator.set(creator);
+        self.goal.set(goal);
+        self.deadline.set(U64::from(deadline));
+        Ok(())
+    }
+
+    /// Backs the campaign with `msg.value` wei. Only possible before the deadline.
+    #[payable]
+    pub fn contribute(&mut self) -> Result<(), CrowdfundingError> {
+        let deadline = self.deadline();
+        if self.vm().block_timestamp() >= deadline {
+            return Err(CrowdfundingError::CampaignEnded(CampaignEnded {
+                deadline: U256::from(deadline),
+            }));
+        }
+        let amount = self.vm().msg_value();
+        if amount.is_zero() {
+            return Err(CrowdfundingError::ZeroContribution(ZeroContribution {}));
+        }
+        let backer = self.vm().msg_sender();
+        let contribution = self.contributions.get(backer) + amount;
+        self.contributions.setter(backer).set(contribution);
+        let total_raised = self.total_raised.get() + amount;
+        self.total_raised.set(total_raised);
+        log(
+            self.vm(),
+            Contributed {
+                backer,
+                amount,
+                totalRaised: total_raised,
+            },
+        );
+        Ok(())
+    }
+
+    /// Sends everything raised to the creator once a successful campaign has ended.
+    pub fn claim_funds(&mut self) -> Result<U256, CrowdfundingError> {
+        let creator = self.creator.get();
+        let caller = self.vm().msg_sender();
+        if caller != creator {
+            return Err(CrowdfundingError::NotCreator(NotCreator { caller }));
+        }
+        self.require_ended()?;
+        let (raised, goal) = (self.total_raised.get(), self.goal.get());
+        if raised < goal {
+            return Err(CrowdfundingError::GoalNotReached(GoalNotReached {
+                raised,
+                goal,
+            }));
+        }
+        if self.claimed.get() {
+            return Err(CrowdfundingError::AlreadyClaimed(AlreadyClaimed {}));
+        }
+        self.claimed.set(true);
+        self.send(creator, raised)?;
+        log(
+            self.vm(),
+            FundsClaimed {
+                creator,
+                amount: raised,
+            },
+        );
+        Ok(raised)
+    }
+
+    /// Returns the caller's whole contribution once a failed campaign has ended.
+    pub fn refund(&mut self) -> Result<U256, CrowdfundingError> {
+        self.require_ended()?;
+        let (raised, goal) = (self.total_raised.get(), self.goal.get());
+        if raised >= goal {
+            return Err(CrowdfundingError::GoalReached(GoalReached { raised, goal }));
+        }
+        let backer = self.vm().msg_sender();
+        let amount = self.contributions.get(backer);
+        if amount.is_zero() {
+            return Err(CrowdfundingError::NothingToRefund(NothingToRefund {
+                backer,
+            }));
+        }
+        // Zero the contribution before sending so a re-entrant call finds nothing to take.
+        self.contributions.setter(backer).set(U256::ZERO);
+        self.send(backer, amount)?;
+        log(self.vm(), Refunded { backer, amount });
+        Ok(amount)
+    }
+
+    /// True once the deadline has passed with the goal met.
+    pub fn succeeded(&self) -> bool {
+        self.vm().block_timestamp() >= self.deadline() && self.total_raised.get() >= self.goal.get()
+    }
+
+    pub fn contribution_of(&self, backer: Address) -> U256 {
+        self.contributions.get(backer)
+    }
+
+    pub fn total_raised(&self) -> U256 {
+        self.total_raised.get()
+    }
+
+    pub fn goal(&self) -> U256 {
+        self.goal.get()
+    }
+
+    pub fn deadline(&self) -> u64 {
+        self.deadline.get().to::<u64>()
+    }
+
+    pub fn creator(&self) -> Address {
+        self.creator.get()
+    }
+
+    pub fn claimed(&self) -> bool {
+        self.claimed.get()
+    }
+}
+
+impl Crowdfunding {
+    fn require_ended(&self) -> Result<(), CrowdfundingError> {
+        let deadline = self.deadline();
+        if self.vm().block_timestamp() < deadline {
+            return Err(CrowdfundingError::CampaignActive(CampaignActive {
+                deadline: U256::from(deadline),
+            }));
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, to: Address, amount: U256) -> Result<(), CrowdfundingError> {
+        self.vm()
+            .transfer_eth(to, amount)
+            .map_err(|_| CrowdfundingError::TransferFailed(TransferFailed { to, amount }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const CREATOR: Address = Address::new([0x01; 20]);
+    const ALICE: Address = Address::new([0x02; 20]);
+    const BOB: Address = Address::new([0x03; 20]);
+    const NOW: u64 = 1_000;
+    const DEADLINE: u64 = 2_000;
+    const GOAL: u64 = 10_000;
+
+    fn setup() -> (TestVM, Crowdfunding) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(NOW);
+        let mut contract = Crowdfunding::from(&vm);
+        contract
+            .constructor(CREATOR, U256::from(GOAL), DEADLINE)
+            .unwrap();
+        (vm, contract)
+    }
+
+    /// Contributes `value` as `backer`, crediting the contract if it succeeds.
+    fn contribute(
+        vm: &TestVM,
+        contract: &mut Crowdfunding,
+        backer: Address,
+        value: u64,
+    ) -> Result<(), CrowdfundingError> {
+        vm.set_sender(backer);
+        vm.set_value(U256::from(value));
+        let result = contract.contribute();
+        if result.is_ok() {
+            let balance = vm.balance(vm.contract_address());
+            vm.set_balance(vm.contract_address(), balance + U256::from(value));
+        }
+        vm.set_value(U256::ZERO);
+        result
+    }
+
+    #[test]
+    fn test_successful_campaign_pays_creator() {
+        let (vm, mut contract) = setup();
+        contribute(&vm, &mut contract, ALICE, 6_000).unwrap();
+        contribute(&vm, &mut contract, BOB, 3_000).unwrap();
+        contribute(&vm, &mut contract, ALICE, 1_000).unwrap();
+        assert_eq!(contract.contribution_of(ALICE), U256::from(7_000));
+        assert_eq!(contract.total_raised(), U256::from(GOAL));
+
+        vm.set_sender(CREATOR);
+        assert!(matches!(
+            contract.claim_funds(),
+            Err(CrowdfundingError::CampaignActive(_))
+        ));
+        vm.set_block_timestamp(DEADLINE);
+        assert!(contract.succeeded());
+        assert_eq!(contract.claim_funds().unwrap(), U256::from(GOAL));
+        assert_eq!(vm.balance(CREATOR), U256::from(GOAL));
+        assert!(contract.claimed());
+        assert!(matches!(
+            contract.claim_funds(),
+            Err(CrowdfundingError::AlreadyClaimed(_))
+        ));
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.refund(),
+            Err(CrowdfundingError::GoalReached(_))
+        ));
+    }
+
+    #[test]
+    fn test_failed_campaign_refunds_backers() {
+        let (vm, mut contract) = setup();
+        contribute(&vm, &mut contract, ALICE, 4_000).unwrap();
+        contribute(&vm, &mut contract, BOB, 2_000).unwrap();
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.refund(),
+            Err(CrowdfundingError::CampaignActive(_))
+        ));
+        vm.set_block_timestamp(DEADLINE + 1);
+        assert!(!contract.succeeded());
+        assert_eq!(contract.refund().unwrap(), U256::from(4_000));
+        assert_eq!(vm.balance(ALICE), U256::from(4_000));
+        assert!(matches!(
+            contract.refund(),
+            Err(CrowdfundingError::NothingToRefund(NothingToRefund { backer })) if backer == ALICE
+        ));
+
+        vm.set_sender(CREATOR);
+        assert!(matches!(
+            contract.claim_funds(),
+            Err(CrowdfundingError::GoalNotReached(GoalNotReached { raised, .. }))
+                if raised == U256::from(6_000)
+        ));
+        vm.set_sender(BOB);
+        contract.refund().unwrap();
+        assert_eq!(vm.balance(vm.contract_address()), U256::ZERO);
+    }
+
+    #[test]
+    fn test_contributions_close_at_deadline() {
+        let (vm, mut contract) = setup();
+        assert!(matches!(
+            contribute(&vm, &mut contract, ALICE, 0),
+            Err(CrowdfundingError::ZeroContribution(_))
+        ));
+        vm.set_block_timestamp(DEADLINE - 1);
+        contribute(&vm, &mut contract, ALICE, 1).unwrap();
+        vm.set_block_timestamp(DEADLINE);
+        assert!(matches!(
+            contribute(&vm, &mut contract, ALICE, GOAL),
+            Err(CrowdfundingError::CampaignEnded(_))
+        ));
+        assert_eq!(contract.total_raised(), U256::from(1));
+    }
+
+    #[test]
+    fn test_only_creator_claims() {
+        let (vm, mut contract) = setup();
+        contribute(&vm, &mut contract, ALICE, GOAL).unwrap();
+        vm.set_block_timestamp(DEADLINE);
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.claim_funds(),
+            Err(CrowdfundingError::NotCreator(NotCreator { caller })) if caller == ALICE
+        ));
+        assert!(!contract.claimed());
+    }
+
+    #[test]
+    fn test_rejects_bad_campaigns() {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(NOW);
+        for (creator, goal, deadline) in [
+            (Address::ZERO, GOAL, DEADLINE),
+            (CREATOR, 0, DEADLINE),
+            (CREATOR, GOAL, NOW),
+        ] {
+            let mut contract = Crowdfunding::from(&vm);
+            assert!(matches!(
+                contract.constructor(creator, U256::from(goal), deadline),
+                Err(CrowdfundingError::InvalidCampaign(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_failed_transfer_surfaces_error() {
+        let (vm, mut contract) = setup();
+        contribute(&vm, &mut contract, ALICE, 5).unwrap();
+        vm.set_block_timestamp(DEADLINE);
+        vm.set_balance(vm.contract_address(), U256::ZERO);
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.refund(),
+            Err(CrowdfundingError::TransferFailed(TransferFailed { to, .. })) if to == ALICE
+        ));
+    }
+}
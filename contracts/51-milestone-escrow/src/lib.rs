@@ -0,0 +1,609 @@
+//!
+//! Stylus Milestone Escrow
+//!
+//! Freelance work paid in milestones. The client opens a job with
+//! `create_job(freelancer, arbiter, amounts, review_period)` and sends the sum
+//! of the milestone amounts with it, so every milestone is funded up front and
+//! held by the contract until it is settled.
+//!
+//! The freelancer calls `deliver` for a milestone, which starts its review
+//! period. Within that period the client either `approve`s the milestone,
+//! paying the freelancer, or `dispute`s it. A client who does neither cannot
+//! hold the payment back: once the review period is over anyone can `release`
+//! the milestone to the freelancer. A disputed milestone waits for the
+//! arbiter, who splits it with `resolve(id, index, freelancer_amount)`; that
+//! much goes to the freelancer and the rest back to the client. Until a
+//! milestone is delivered the client may `cancel` it and take its amount back.
+//!
+//! Milestones are independent and may be delivered in any order. A
+//! milestone's state is written before any ETH moves, so a recipient that
+//! calls back into the escrow finds it already settled.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256, U64, U8},
+    prelude::*,
+};
+
+/// Most milestones a single job may have.
+const MAX_MILESTONES: usize = 20;
+
+pub const MILESTONE_FUNDED: u8 = 0;
+pub const MILESTONE_DELIVERED: u8 = 1;
+pub const MILESTONE_DISPUTED: u8 = 2;
+pub const MILESTONE_RELEASED: u8 = 3;
+pub const MILESTONE_RESOLVED: u8 = 4;
+pub const MILESTONE_CANCELLED: u8 = 5;
+
+/// `(client, freelancer, arbiter, review_period, milestone_count)`.
+pub type JobInfo = (Address, Address, Address, u64, U256);
+
+sol! {
+    event JobCreated(uint256 indexed id, address indexed client, address indexed freelancer, uint256 total);
+    event MilestoneDelivered(uint256 indexed id, uint256 index, uint64 reviewEndsAt);
+    event MilestoneReleased(uint256 indexed id, uint256 index, uint256 amount);
+    event MilestoneDisputed(uint256 indexed id, uint256 index);
+    event DisputeResolved(uint256 indexed id, uint256 index, uint256 freelancerAmount, uint256 clientAmount);
+    event MilestoneCancelled(uint256 indexed id, uint256 index, uint256 amount);
+
+    #[derive(Debug)]
+    error InvalidJob(string reason);
+    #[derive(Debug)]
+    error IncorrectFunding(uint256 expected, uint256 sent);
+    #[derive(Debug)]
+    error JobNotFound(uint256 id);
+    #[derive(Debug)]
+    error MilestoneNotFound(uint256 id, uint256 index);
+    #[derive(Debug)]
+    error NotAuthorized(address caller);
+    #[derive(Debug)]
+    error WrongState(uint256 id, uint256 index, uint8 state);
+    #[derive(Debug)]
+    error ReviewPeriodActive(uint64 endsAt);
+    #[derive(Debug)]
+    error ReviewPeriodOver(uint64 endedAt);
+    #[derive(Debug)]
+    error InvalidSplit(uint256 freelancerAmount, uint256 amount);
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum MilestoneEscrowError {
+    InvalidJob(InvalidJob),
+    IncorrectFunding(IncorrectFunding),
+    JobNotFound(JobNotFound),
+    MilestoneNotFound(MilestoneNotFound),
+    NotAuthorized(NotAuthorized),
+    WrongState(WrongState),
+    ReviewPeriodActive(ReviewPeriodActive),
+    ReviewPeriodOver(ReviewPeriodOver),
+    InvalidSplit(InvalidSplit),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    pub struct Milestone {
+        uint256 amount;
+        /// One of the `MILESTONE_*` constants.
+        uint8 state;
+        uint64 delivered_at;
+    }
+
+    pub struct Job {
+        address client;
+        address freelancer;
+        address arbiter;
+        /// Seconds the client has to approve or dispute a delivered milestone.
+        uint64 review_period;
+        Milestone[] milestones;
+    }
+
+    #[entrypoint]
+    pub struct MilestoneEscrow {
+        mapping(uint256 => Job) jobs;
+        uint256 job_count;
+    }
+}
+
+#[public]
+impl MilestoneEscrow {
+    /// Opens a job paying `amounts[i]` wei for milestone `i`; `msg.value` must be their sum.
+    #[payable]
+    pub fn create_job(
+        &mut self,
+        freelancer: Address,
+        arbiter: Address,
+        amounts: Vec<U256>,
+        review_period: u64,
+    ) -> Result<U256, MilestoneEscrowError> {
+        let invalid = |reason: &str| {
+            MilestoneEscrowError::InvalidJob(InvalidJob {
+                reason: reason.into(),
+            })
+        };
+        let client = self.vm().msg_sender();
+        if freelancer.is_zero() || freelancer == client {
+            return Err(invalid("Invalid freelancer"));
+        }
+        if arbiter.is_zero() || arbiter == client || arbiter == freelancer {
+            return Err(invalid("Invalid arbiter"));
+        }
+        if amounts.is_empty() || amounts.len() > MAX_MILESTONES {
+            return Err(invalid("Invalid milestone count"));
+        }
+        if amounts.iter().any(|amount| amount.is_zero()) {
+            return Err(invalid("Zero milestone amount"));
+        }
+        if review_period == 0 {
+            return Err(invalid("Zero review period"));
+        }
+        let total = amounts
+            .iter()
+            .try_fold(U256::ZERO, |sum, &amount| sum.checked_add(amount))
+            .ok_or_else(|| invalid("Total overflows"))?;
+        let sent = self.vm().msg_value();
+        if sent != total {
+            return Err(MilestoneEscrowError::IncorrectFunding(IncorrectFunding {
+                expected: total,
+                sent,
+            }));
+        }
+
+        let id = self.job_count.get() + U256::from(1);
+        self.job_count.set(id);
+        let mut job = self.jobs.setter(id);
+        job.client.set(client);
+        job.freelancer.set(freelancer);
+        job.arbiter.set(arbiter);
+        job.review_period.set(U64::from(review_period));
+        for amount in amounts {
+            job.milestones.grow().amount.set(amount);
+        }
+        log(
+            self.vm(),
+            JobCreated {
+                id,
+                client,
+                freelancer,
+                total,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Marks a funded milestone delivered and starts its review period. Freelancer only.
+    pub fn deliver(&mut self, id: U256, index: U256) -> Result<(), MilestoneEscrowError> {
+        let freelancer = self.jobs.getter(id).freelancer.get();
+        self.require_state(id, index, MILESTONE_FUNDED)?;
+        self.only(freelancer)?;
+        let now = self.vm().block_timestamp();
+        let mut job = self.jobs.setter(id);
+        let review_ends_at = now + job.review_period.get().to::<u64>();
+        let mut milestone = job.milestones.setter(index).expect("milestone exists");
+        milestone.state.set(U8::from(MILESTONE_DELIVERED));
+        milestone.delivered_at.set(U64::from(now));
+        log(
+            self.vm(),
+            MilestoneDelivered {
+                id,
+                index,
+                reviewEndsAt: review_ends_at,
+            },
+        );
+        Ok(())
+    }
+
+    /// Accepts a delivered milestone and pays the freelancer. Client only.
+    pub fn approve(&mut self, id: U256, index: U256) -> Result<(), MilestoneEscrowError> {
+        let client = self.jobs.getter(id).client.get();
+        self.require_state(id, index, MILESTONE_DELIVERED)?;
+        self.only(client)?;
+        self.release_to_freelancer(id, index)
+    }
+
+    /// Disputes a delivered milestone before its review period ends. Client only.
+    pub fn dispute(&mut self, id: U256, index: U256) -> Result<(), MilestoneEscrowError> {
+        let client = self.jobs.getter(id).client.get();
+        self.require_state(id, index, MILESTONE_DELIVERED)?;
+        self.only(client)?;
+        let ends_at = self.review_ends_at(id, index);
+        if self.vm().block_timestamp() >= ends_at {
+            return Err(MilestoneEscrowError::ReviewPeriodOver(ReviewPeriodOver {
+                endedAt: ends_at,
+            }));
+        }
+        self.set_state(id, index, MILESTONE_DISPUTED);
+        log(self.vm(), MilestoneDisputed { id, index });
+        Ok(())
+    }
+
+    /// Pays the freelancer for a delivered milestone the client let the review period lapse on.
+    ///
+    /// Anyone may call this, so the freelancer does not depend on the client to get paid.
+    pub fn release(&mut self, id: U256, index: U256) -> Result<(), MilestoneEscrowError> {
+        self.require_state(id, index, MILESTONE_DELIVERED)?;
+        let ends_at = self.review_ends_at(id, index);
+        if self.vm().block_timestamp() < ends_at {
+            return Err(MilestoneEscrowError::ReviewPeriodActive(
+                ReviewPeriodActive { endsAt: ends_at },
+            ));
+        }
+        self.release_to_freelancer(id, index)
+    }
+
+    /// Settles a disputed milestone: `freelancer_amount` to the freelancer, the rest to the
+    /// client. Arbiter only.
+    pub fn resolve(
+        &mut self,
+        id: U256,
+        index: U256,
+        freelancer_amount: U256,
+    ) -> Result<(), MilestoneEscrowError> {
+        let job = self.jobs.getter(id);
+        let (client, freelancer, arbiter) =
+            (job.client.get(), job.freelancer.get(), job.arbiter.get());
+        let amount = self.require_state(id, index, MILESTONE_DISPUTED)?;
+        self.only(arbiter)?;
+        if freelancer_amount > amount {
+            return Err(MilestoneEscrowError::InvalidSplit(InvalidSplit {
+                freelancerAmount: freelancer_amount,
+                amount,
+            }));
+        }
+        let client_amount = amount - freelancer_amount;
+
+        self.set_state(id, index, MILESTONE_RESOLVED);
+        self.send(freelancer, freelancer_amount)?;
+        self.send(client, client_amount)?;
+        log(
+            self.vm(),
+            DisputeResolved {
+                id,
+                index,
+                freelancerAmount: freelancer_amount,
+                clientAmount: client_amount,
+            },
+        );
+        Ok(())
+    }
+
+    /// Withdraws an undelivered milestone and refunds its amount. Client only.
+    pub fn cancel(&mut self, id: U256, index: U256) -> Result<(), MilestoneEscrowError> {
+        let client = self.jobs.getter(id).client.get();
+        let amount = self.require_state(id, index, MILESTONE_FUNDED)?;
+        self.only(client)?;
+        self.set_state(id, index, MILESTONE_CANCELLED);
+        self.send(client, amount)?;
+        log(self.vm(), MilestoneCancelled { id, index, amount });
+        Ok(())
+    }
+
+    /// Returns the job as a `JobInfo` tuple.
+    pub fn job(&self, id: U256) -> Result<JobInfo, MilestoneEscrowError> {
+        self.existing(id)?;
+        let job = self.jobs.getter(id);
+        Ok((
+            job.client.get(),
+            job.freelancer.get(),
+            job.arbiter.get(),
+            job.review_period.get().to(),
+            U256::from(job.milestones.len()),
+        ))
+    }
+
+    /// Returns `(amount, state, delivered_at)`; `delivered_at` is zero until delivery.
+    pub fn milestone(
+        &self,
+        id: U256,
+        index: U256,
+    ) -> Result<(U256, u8, u64), MilestoneEscrowError> {
+        self.existing(id)?;
+        let job = self.jobs.getter(id);
+        let milestone = job
+            .milestones
+            .getter(index)
+            .ok_or(MilestoneEscrowError::MilestoneNotFound(MilestoneNotFound {
+                id,
+                index,
+            }))?;
+        Ok((
+            milestone.amount.get(),
+            milestone.state.get().to(),
+            milestone.delivered_at.get().to(),
+        ))
+    }
+
+    pub fn job_count(&self) -> U256 {
+        self.job_count.get()
+    }
+}
+
+impl MilestoneEscrow {
+    fn existing(&self, id: U256) -> Result<(), MilestoneEscrowError> {
+        if id.is_zero() || id > self.job_count.get() {
+            return Err(MilestoneEscrowError::JobNotFound(JobNotFound { id }));
+        }
+        Ok(())
+    }
+
+    fn only(&self, account: Address) -> Result<(), MilestoneEscrowError> {
+        let caller = self.vm().msg_sender();
+        if caller != account {
+            return Err(MilestoneEscrowError::NotAuthorized(NotAuthorized { caller }));
+        }
+        Ok(())
+    }
+
+    /// Fails unless milestone `index` of job `id` exists and is in `expected`; returns its amount.
+    fn require_state(
+        &self,
+        id: U256,
+        index: U256,
+        expected: u8,
+    ) -> Result<U256, MilestoneEscrowError> {
+        let (amount, state, _) = self.milestone(id, index)?;
+        if state != expected {
+            return Err(MilestoneEscrowError::WrongState(WrongState {
+                id,
+                index,
+                state,
+            }));
+        }
+        Ok(amount)
+    }
+
+    fn review_ends_at(&self, id: U256, index: U256) -> u64 {
+        let job = self.jobs.getter(id);
+        let milestone = job.milestones.getter(index).expect("milestone exists");
+        milestone.delivered_at.get().to::<u64>() + job.review_period.get().to::<u64>()
+    }
+
+    fn set_state(&mut self, id: U256, index: U256, state: u8) {
+        let mut job = self.jobs.setter(id);
+        let mut milestone = job.milestones.setter(index).expect("milestone exists");
+        milestone.state.set(U8::from(state));
+    }
+
+    fn release_to_freelancer(
+        &mut self,
+        id: U256,
+        index: U256,
+    ) -> Result<(), MilestoneEscrowError> {
+        let freelancer = self.jobs.getter(id).freelancer.get();
+        let (amount, _, _) = self.milestone(id, index)?;
+        self.set_state(id, index, MILESTONE_RELEASED);
+        self.send(freelancer, amount)?;
+        log(self.vm(), MilestoneReleased { id, index, amount });
+        Ok(())
+    }
+
+    /// Sends `amount` wei to `to`; a zero amount is skipped.
+    fn send(&mut self, to: Address, amount: U256) -> Result<(), MilestoneEscrowError> {
+        if amount.is_zero() {
+            return Ok(());
+        }
+        self.vm()
+            .transfer_eth(to, amount)
+            .map_err(|_| MilestoneEscrowError::TransferFailed(TransferFailed { to, amount }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::SolEvent;
+    use stylus_sdk::testing::*;
+
+    const CLIENT: Address = Address::new([0x01; 20]);
+    const FREELANCER: Address = Address::new([0x02; 20]);
+    const ARBITER: Address = Address::new([0x03; 20]);
+    const STRANGER: Address = Address::new([0x0e; 20]);
+    const NOW: u64 = 1_000;
+    const REVIEW: u64 = 7 * 86_400;
+
+    /// A job with milestones of 1,000 and 3,000 wei, funded in full.
+    fn setup() -> (TestVM, MilestoneEscrow, U256) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(NOW);
+        let mut contract = MilestoneEscrow::from(&vm);
+        vm.set_sender(CLIENT);
+        vm.set_value(U256::from(4_000));
+        let id = contract
+            .create_job(
+                FREELANCER,
+                ARBITER,
+                vec![U256::from(1_000), U256::from(3_000)],
+                REVIEW,
+            )
+            .unwrap();
+        vm.set_value(U256::ZERO);
+        // The 4,000 wei deposit never lands in the test contract's balance, so put it
+        // there by hand for the milestone payouts to draw on.
+        vm.set_balance(vm.contract_address(), U256::from(4_000));
+        (vm, contract, id)
+    }
+
+    fn index(i: u64) -> U256 {
+        U256::from(i)
+    }
+
+    #[test]
+    fn test_approved_and_lapsed_milestones_pay_freelancer() {
+        let (vm, mut contract, id) = setup();
+        assert_eq!(
+            contract.job(id).unwrap(),
+            (CLIENT, FREELANCER, ARBITER, REVIEW, U256::from(2))
+        );
+
+        vm.set_sender(FREELANCER);
+        contract.deliver(id, index(0)).unwrap();
+        vm.set_sender(CLIENT);
+        contract.approve(id, index(0)).unwrap();
+        assert_eq!(vm.balance(FREELANCER), U256::from(1_000));
+        assert_eq!(contract.milestone(id, index(0)).unwrap().1, MILESTONE_RELEASED);
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = logs.last().unwrap();
+        let event = MilestoneReleased::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!((event.index, event.amount), (index(0), U256::from(1_000)));
+
+        // The client stays silent on the second milestone; anyone may release it afterwards.
+        vm.set_sender(FREELANCER);
+        contract.deliver(id, index(1)).unwrap();
+        assert_eq!(
+            contract.milestone(id, index(1)).unwrap(),
+            (U256::from(3_000), MILESTONE_DELIVERED, NOW)
+        );
+        vm.set_sender(STRANGER);
+        vm.set_block_timestamp(NOW + REVIEW - 1);
+        assert!(matches!(
+            contract.release(id, index(1)),
+            Err(MilestoneEscrowError::ReviewPeriodActive(ReviewPeriodActive { endsAt }))
+                if endsAt == NOW + REVIEW
+        ));
+        vm.set_block_timestamp(NOW + REVIEW);
+        contract.release(id, index(1)).unwrap();
+        assert_eq!(vm.balance(FREELANCER), U256::from(4_000));
+        assert_eq!(vm.balance(vm.contract_address()), U256::ZERO);
+
+        vm.set_sender(CLIENT);
+        assert!(matches!(
+            contract.dispute(id, index(1)),
+            Err(MilestoneEscrowError::WrongState(WrongState { state, .. }))
+                if state == MILESTONE_RELEASED
+        ));
+    }
+
+    #[test]
+    fn test_arbiter_splits_disputed_milestone() {
+        let (vm, mut contract, id) = setup();
+        vm.set_sender(FREELANCER);
+        contract.deliver(id, index(1)).unwrap();
+        vm.set_sender(CLIENT);
+        vm.set_block_timestamp(NOW + REVIEW - 1);
+        contract.dispute(id, index(1)).unwrap();
+
+        // A disputed milestone no longer lapses to the freelancer.
+        vm.set_block_timestamp(NOW + REVIEW);
+        assert!(matches!(
+            contract.release(id, index(1)),
+            Err(MilestoneEscrowError::WrongState(_))
+        ));
+        assert!(matches!(
+            contract.resolve(id, index(1), U256::from(1_000)),
+            Err(MilestoneEscrowError::NotAuthorized(NotAuthorized { caller })) if caller == CLIENT
+        ));
+        vm.set_sender(ARBITER);
+        assert!(matches!(
+            contract.resolve(id, index(1), U256::from(3_001)),
+            Err(MilestoneEscrowError::InvalidSplit(_))
+        ));
+        contract.resolve(id, index(1), U256::from(1_000)).unwrap();
+        assert_eq!(vm.balance(FREELANCER), U256::from(1_000));
+        assert_eq!(vm.balance(CLIENT), U256::from(2_000));
+        assert_eq!(contract.milestone(id, index(1)).unwrap().1, MILESTONE_RESOLVED);
+    }
+
+    #[test]
+    fn test_dispute_closes_with_review_period() {
+        let (vm, mut contract, id) = setup();
+        vm.set_sender(FREELANCER);
+        contract.deliver(id, index(0)).unwrap();
+        vm.set_sender(CLIENT);
+        vm.set_block_timestamp(NOW + REVIEW);
+        assert!(matches!(
+            contract.dispute(id, index(0)),
+            Err(MilestoneEscrowError::ReviewPeriodOver(ReviewPeriodOver { endedAt }))
+                if endedAt == NOW + REVIEW
+        ));
+    }
+
+    #[test]
+    fn test_client_cancels_undelivered_milestone() {
+        let (vm, mut contract, id) = setup();
+        vm.set_sender(FREELANCER);
+        assert!(matches!(
+            contract.cancel(id, index(1)),
+            Err(MilestoneEscrowError::NotAuthorized(_))
+        ));
+        contract.deliver(id, index(0)).unwrap();
+
+        vm.set_sender(CLIENT);
+        assert!(matches!(
+            contract.cancel(id, index(0)),
+            Err(MilestoneEscrowError::WrongState(_))
+        ));
+        contract.cancel(id, index(1)).unwrap();
+        assert_eq!(vm.balance(CLIENT), U256::from(3_000));
+        vm.set_sender(FREELANCER);
+        assert!(matches!(
+            contract.deliver(id, index(1)),
+            Err(MilestoneEscrowError::WrongState(WrongState { state, .. }))
+                if state == MILESTONE_CANCELLED
+        ));
+    }
+
+    #[test]
+    fn test_roles_are_enforced() {
+        let (vm, mut contract, id) = setup();
+        vm.set_sender(CLIENT);
+        assert!(matches!(
+            contract.deliver(id, index(0)),
+            Err(MilestoneEscrowError::NotAuthorized(_))
+        ));
+        vm.set_sender(FREELANCER);
+        contract.deliver(id, index(0)).unwrap();
+        for caller in [FREELANCER, ARBITER] {
+            vm.set_sender(caller);
+            assert!(contract.approve(id, index(0)).is_err());
+            assert!(contract.dispute(id, index(0)).is_err());
+        }
+        assert!(matches!(
+            contract.deliver(id, index(2)),
+            Err(MilestoneEscrowError::MilestoneNotFound(_))
+        ));
+        assert!(matches!(
+            contract.deliver(id + U256::from(1), index(0)),
+            Err(MilestoneEscrowError::JobNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_bad_jobs() {
+        let vm = TestVM::default();
+        let mut contract = MilestoneEscrow::from(&vm);
+        vm.set_sender(CLIENT);
+        let one = vec![U256::from(1)];
+        for (freelancer, arbiter, amounts, review) in [
+            (CLIENT, ARBITER, one.clone(), REVIEW),
+            (FREELANCER, FREELANCER, one.clone(), REVIEW),
+            (FREELANCER, ARBITER, vec![], REVIEW),
+            (FREELANCER, ARBITER, vec![U256::from(1); MAX_MILESTONES + 1], REVIEW),
+            (FREELANCER, ARBITER, vec![U256::ZERO], REVIEW),
+            (FREELANCER, ARBITER, one.clone(), 0),
+            (FREELANCER, ARBITER, vec![U256::MAX, U256::from(1)], REVIEW),
+        ] {
+            assert!(matches!(
+                contract.create_job(freelancer, arbiter, amounts, review),
+                Err(MilestoneEscrowError::InvalidJob(_))
+            ));
+        }
+
+        vm.set_value(U256::from(2));
+        assert!(matches!(
+            contract.create_job(FREELANCER, ARBITER, one, REVIEW),
+            Err(MilestoneEscrowError::IncorrectFunding(IncorrectFunding { expected, sent }))
+                if expected == U256::from(1) && sent == U256::from(2)
+        ));
+        assert_eq!(contract.job_count(), U256::ZERO);
+    }
+}
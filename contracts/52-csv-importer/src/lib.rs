@@ -0,0 +1,687 @@
+//!
+//! Stylus CSV Importer
+//!
+//! Loads CSV data into a typed on-chain table. The owner first declares the
+//! schema with `set_schema`: an ordered list of columns, each with a name, a
+//! type (`COLUMN_UINT`, `COLUMN_INT`, `COLUMN_BOOL` or `COLUMN_ADDRESS`) and,
+//! for numeric columns, an inclusive `[min, max]` range. The first column is
+//! the table's primary key and must be unique.
+//!
+//! A CSV file is usually too large for one transaction, so it is sent in
+//! chunks. `begin_import` opens an import, `append_chunk` parses every
+//! complete line in the chunk and keeps a trailing partial line for the next
+//! one, and `finish_import` parses whatever is left. Chunks may therefore
+//! split a row anywhere. The first line of each import is a header that must
+//! name the schema's columns in order; blank lines are skipped.
+//!
+//! Every row is checked against the schema before it is stored, and a chunk
+//! with a bad row reverts as a whole with `InvalidRow(line, column, reason)`,
+//! so the owner can fix the line and resend the same chunk. Valid rows are
+//! stored one 32-byte word per cell: numbers as their two's-complement value,
+//! booleans as 0 or 1 and addresses right-aligned. Rows can then be read by
+//! position with `row` and `cell`, or by primary key with `find_by_key`.
+//!
+//! Fields are split on commas and trimmed; quoted fields are not supported,
+//! which none of the column types need.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, I256, U256, U8},
+    prelude::*,
+};
+
+pub const COLUMN_UINT: u8 = 0;
+pub const COLUMN_INT: u8 = 1;
+pub const COLUMN_BOOL: u8 = 2;
+pub const COLUMN_ADDRESS: u8 = 3;
+
+/// Most columns a schema may declare.
+const MAX_COLUMNS: usize = 16;
+/// Longest column name accepted, in bytes.
+const MAX_NAME_BYTES: usize = 32;
+/// Largest chunk `append_chunk` accepts, in bytes.
+const MAX_CHUNK_BYTES: usize = 8192;
+/// Longest partial line carried over between chunks, in bytes.
+const MAX_LINE_BYTES: usize = 1024;
+
+/// `(name, column_type, min, max)`; `min` and `max` only apply to numeric columns.
+pub type ColumnSpec = (String, u8, I256, I256);
+
+sol! {
+    event SchemaSet(uint256 columns);
+    event ImportStarted();
+    event RowsImported(uint256 firstRow, uint256 count);
+    event ImportFinished(uint256 totalRows);
+
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error InvalidSchema(string reason);
+    #[derive(Debug)]
+    error SchemaLocked();
+    #[derive(Debug)]
+    error NoSchema();
+    #[derive(Debug)]
+    error NoImportInProgress();
+    #[derive(Debug)]
+    error ChunkTooLarge(uint256 size, uint256 max);
+    #[derive(Debug)]
+    error LineTooLong(uint256 line, uint256 max);
+    #[derive(Debug)]
+    error InvalidHeader(string reason);
+    #[derive(Debug)]
+    error InvalidRow(uint256 line, uint256 column, string reason);
+    #[derive(Debug)]
+    error DuplicateKey(uint256 line, uint256 key);
+    #[derive(Debug)]
+    error RowNotFound(uint256 row);
+    #[derive(Debug)]
+    error KeyNotFound(uint256 key);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum CsvImporterError {
+    NotOwner(NotOwner),
+    InvalidSchema(InvalidSchema),
+    SchemaLocked(SchemaLocked),
+    NoSchema(NoSchema),
+    NoImportInProgress(NoImportInProgress),
+    ChunkTooLarge(ChunkTooLarge),
+    LineTooLong(LineTooLong),
+    InvalidHeader(InvalidHeader),
+    InvalidRow(InvalidRow),
+    DuplicateKey(DuplicateKey),
+    RowNotFound(RowNotFound),
+    KeyNotFound(KeyNotFound),
+}
+
+impl CsvImporterError {
+    fn schema(reason: &str) -> Self {
+        CsvImporterError::InvalidSchema(InvalidSchema {
+            reason: reason.to_string(),
+        })
+    }
+
+    fn header(reason: &str) -> Self {
+        CsvImporterError::InvalidHeader(InvalidHeader {
+            reason: reason.to_string(),
+        })
+    }
+
+    fn row(line: usize, column: usize, reason: &str) -> Self {
+        CsvImporterError::InvalidRow(InvalidRow {
+            line: U256::from(line),
+            column: U256::from(column),
+            reason: reason.to_string(),
+        })
+    }
+}
+
+sol_storage! {
+    #[derive(Erase)]
+    pub struct Column {
+        string name;
+        /// One of the `COLUMN_*` constants.
+        uint8 column_type;
+        int256 min;
+        int256 max;
+    }
+
+    #[entrypoint]
+    pub struct CsvImporter {
+        address owner;
+        Column[] columns;
+        /// Stored rows, one word per cell, by position from 0.
+        mapping(uint256 => uint256[]) rows;
+        uint256 row_count;
+        /// Row position plus one for each primary key; zero means absent.
+        mapping(uint256 => uint256) key_index;
+        bool importing;
+        /// Set once the current import's header line has been checked.
+        bool header_seen;
+        /// Lines of the current import consumed so far, counting the header as line 0.
+        uint256 lines_read;
+        /// The partial line at the end of the last chunk.
+        string pending;
+    }
+}
+
+#[public]
+impl CsvImporter {
+    #[constructor]
+    pub fn constructor(&mut self, owner: Address) {
+        self.owner.set(owner);
+    }
+
+    /// Declares the table's columns. Only possible while the table is empty and no import
+    /// is in progress.
+    pub fn set_schema(&mut self, columns: Vec<ColumnSpec>) -> Result<(), CsvImporterError> {
+        self.only_owner()?;
+        if !self.row_count.get().is_zero() || self.importing.get() {
+            return Err(CsvImporterError::SchemaLocked(SchemaLocked {}));
+        }
+        if columns.is_empty() || columns.len() > MAX_COLUMNS {
+            return Err(CsvImporterError::schema("Invalid column count"));
+        }
+        for (index, (name, column_type, min, max)) in columns.iter().enumerate() {
+            let valid_name = !name.is_empty()
+                && name.len() <= MAX_NAME_BYTES
+                && name
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'_');
+            if !valid_name {
+                return Err(CsvImporterError::schema("Invalid column name"));
+            }
+            if columns[..index].iter().any(|column| column.0 == *name) {
+                return Err(CsvImporterError::schema("Duplicate column name"));
+            }
+            match *column_type {
+                COLUMN_UINT | COLUMN_INT if min > max => {
+                    return Err(CsvImporterError::schema("Minimum exceeds maximum"));
+                }
+                COLUMN_UINT if min.is_negative() => {
+                    return Err(CsvImporterError::schema("Negative minimum for uint"));
+                }
+                COLUMN_UINT | COLUMN_INT | COLUMN_BOOL | COLUMN_ADDRESS => {}
+                _ => return Err(CsvImporterError::schema("Unknown column type")),
+            }
+        }
+
+        self.columns.erase();
+        for (name, column_type, min, max) in &columns {
+            let mut column = self.columns.grow();
+            column.name.set_str(name);
+            column.column_type.set(U8::from(*column_type));
+            column.min.set(*min);
+            column.max.set(*max);
+        }
+        log(
+            self.vm(),
+            SchemaSet {
+                columns: U256::from(columns.len()),
+            },
+        );
+        Ok(())
+    }
+
+    /// Starts a new import, discarding any partial line left by an unfinished one.
+    pub fn begin_import(&mut self) -> Result<(), CsvImporterError> {
+        self.only_owner()?;
+        if self.columns.is_empty() {
+            return Err(CsvImporterError::NoSchema(NoSchema {}));
+        }
+        self.importing.set(true);
+        self.header_seen.set(false);
+        self.lines_read.set(U256::ZERO);
+        self.pending.set_str("");
+        log(self.vm(), ImportStarted {});
+        Ok(())
+    }
+
+    /// Parses every complete line of `chunk`, after the partial line carried over from the
+    /// previous chunk, and returns the number of rows stored.
+    pub fn append_chunk(&mut self, chunk: String) -> Result<U256, CsvImporterError> {
+        self.only_owner()?;
+        self.require_importing()?;
+        if chunk.len() > MAX_CHUNK_BYTES {
+            return Err(CsvImporterError::ChunkTooLarge(ChunkTooLarge {
+                size: U256::from(chunk.len()),
+                max: U256::from(MAX_CHUNK_BYTES),
+            }));
+        }
+        let mut data = self.pending.get_string();
+        data.push_str(&chunk);
+        let (complete, rest) = match data.rfind('\n') {
+            Some(end) => data.split_at(end + 1),
+            None => ("", data.as_str()),
+        };
+        if rest.len() > MAX_LINE_BYTES {
+            let line = self.lines_read.get() + U256::from(complete.lines().count());
+            return Err(CsvImporterError::LineTooLong(LineTooLong {
+                line,
+                max: U256::from(MAX_LINE_BYTES),
+            }));
+        }
+        let stored = self.consume(complete.lines())?;
+        self.pending.set_str(rest);
+        Ok(U256::from(stored))
+    }
+
+    /// Parses the final partial line, if any, and closes the import. Returns the total number
+    /// of rows in the table.
+    pub fn finish_import(&mut self) -> Result<U256, CsvImporterError> {
+        self.only_owner()?;
+        self.require_importing()?;
+        let rest = self.pending.get_string();
+        self.consume(rest.lines())?;
+        if !self.header_seen.get() {
+            return Err(CsvImporterError::header("Missing header"));
+        }
+        self.importing.set(false);
+        self.pending.set_str("");
+        let total_rows = self.row_count.get();
+        log(
+            self.vm(),
+            ImportFinished {
+                totalRows: total_rows,
+            },
+        );
+        Ok(total_rows)
+    }
+
+    /// The declared columns, in order.
+    pub fn schema(&self) -> Vec<ColumnSpec> {
+        (0..self.columns.len())
+            .filter_map(|index| self.columns.getter(index))
+            .map(|column| {
+                (
+                    column.name.get_string(),
+                    column.column_type.get().to(),
+                    column.min.get(),
+                    column.max.get(),
+                )
+            })
+            .collect()
+    }
+
+    pub fn row_count(&self) -> U256 {
+        self.row_count.get()
+    }
+
+    pub fn importing(&self) -> bool {
+        self.importing.get()
+    }
+
+    /// The cells of row `row`, one word per column.
+    pub fn row(&self, row: U256) -> Result<Vec<U256>, CsvImporterError> {
+        self.existing(row)?;
+        let cells = self.rows.getter(row);
+        Ok((0..cells.len()).filter_map(|index| cells.get(index)).collect())
+    }
+
+    pub fn cell(&self, row: U256, column: U256) -> Result<U256, CsvImporterError> {
+        self.existing(row)?;
+        self.rows
+            .getter(row)
+            .get(column)
+            .ok_or_else(|| CsvImporterError::schema("Unknown column"))
+    }
+
+    /// Position of the row whose primary key cell is `key`.
+    pub fn find_by_key(&self, key: U256) -> Result<U256, CsvImporterError> {
+        let position = self.key_index.get(key);
+        if position.is_zero() {
+            return Err(CsvImporterError::KeyNotFound(KeyNotFound { key }));
+        }
+        Ok(position - U256::from(1))
+    }
+}
+
+impl CsvImporter {
+    fn only_owner(&self) -> Result<(), CsvImporterError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(CsvImporterError::NotOwner(NotOwner { caller }));
+        }
+        Ok(())
+    }
+
+    fn require_importing(&self) -> Result<(), CsvImporterError> {
+        if !self.importing.get() {
+            return Err(CsvImporterError::NoImportInProgress(NoImportInProgress {}));
+        }
+        Ok(())
+    }
+
+    fn existing(&self, row: U256) -> Result<(), CsvImporterError> {
+        if row >= self.row_count.get() {
+            return Err(CsvImporterError::RowNotFound(RowNotFound { row }));
+        }
+        Ok(())
+    }
+
+    /// Checks the header and parses every row on `lines`, then stores the rows. Nothing is
+    /// written unless all of them are valid. Returns the number of rows stored.
+    fn consume<'a>(
+        &mut self,
+        lines: impl Iterator<Item = &'a str>,
+    ) -> Result<usize, CsvImporterError> {
+        let schema = self.schema();
+        let mut header_seen = self.header_seen.get();
+        let mut line_number = self.lines_read.get().to::<usize>();
+        let mut parsed: Vec<Vec<U256>> = Vec::new();
+        for line in lines {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            if line.trim().is_empty() {
+                line_number += 1;
+                continue;
+            }
+            if header_seen {
+                let cells = parse_row(&schema, line, line_number)?;
+                let key = cells[0];
+                let repeated = parsed.iter().any(|row| row[0] == key);
+                if repeated || !self.key_index.get(key).is_zero() {
+                    return Err(CsvImporterError::DuplicateKey(DuplicateKey {
+                        line: U256::from(line_number),
+                        key,
+                    }));
+                }
+                parsed.push(cells);
+            } else {
+                check_header(&schema, line)?;
+                header_seen = true;
+            }
+            line_number += 1;
+        }
+
+        self.header_seen.set(header_seen);
+        self.lines_read.set(U256::from(line_number));
+        let first_row = self.row_count.get();
+        let stored = parsed.len();
+        for (offset, cells) in parsed.into_iter().enumerate() {
+            let position = first_row + U256::from(offset);
+            self.key_index.insert(cells[0], position + U256::from(1));
+            let mut row = self.rows.setter(position);
+            for cell in cells {
+                row.push(cell);
+            }
+        }
+        if stored > 0 {
+            self.row_count.set(first_row + U256::from(stored));
+            log(
+                self.vm(),
+                RowsImported {
+                    firstRow: first_row,
+                    count: U256::from(stored),
+                },
+            );
+        }
+        Ok(stored)
+    }
+}
+
+fn check_header(schema: &[ColumnSpec], line: &str) -> Result<(), CsvImporterError> {
+    let names: Vec<&str> = line.split(',').map(str::trim).collect();
+    if names.len() != schema.len() {
+        return Err(CsvImporterError::header("Wrong column count"));
+    }
+    if names.iter().zip(schema).any(|(name, column)| *name != column.0) {
+        return Err(CsvImporterError::header("Column names do not match the schema"));
+    }
+    Ok(())
+}
+
+/// Parses one data line into a word per cell, checking every field against its column.
+fn parse_row(
+    schema: &[ColumnSpec],
+    line: &str,
+    line_number: usize,
+) -> Result<Vec<U256>, CsvImporterError> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    if fields.len() != schema.len() {
+        let column = fields.len().min(schema.len());
+        return Err(CsvImporterError::row(line_number, column, "Wrong column count"));
+    }
+    fields
+        .iter()
+        .zip(schema)
+        .enumerate()
+        .map(|(index, (field, column))| {
+            parse_cell(field, column).map_err(|reason| {
+                CsvImporterError::row(line_number, index, reason)
+            })
+        })
+        .collect()
+}
+
+fn parse_cell(field: &str, column: &ColumnSpec) -> Result<U256, &'static str> {
+    let (_, column_type, min, max) = column;
+    match *column_type {
+        COLUMN_BOOL => match field {
+            "true" => Ok(U256::from(1)),
+            "false" => Ok(U256::ZERO),
+            _ => Err("Expected true or false"),
+        },
+        COLUMN_ADDRESS => {
+            let hex = field.strip_prefix("0x").ok_or("Expected a 0x address")?;
+            if hex.len() != 40 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err("Expected a 0x address");
+            }
+            let address: Address = field.parse().map_err(|_| "Expected a 0x address")?;
+            Ok(U256::from_be_bytes(address.into_word().0))
+        }
+        _ => {
+            // Digits only: `from_dec_str` alone would also accept `+` and `_`.
+            let digits = field.strip_prefix('-').unwrap_or(field);
+            if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return Err("Expected an integer");
+            }
+            let value = I256::from_dec_str(field).map_err(|_| "Integer out of range")?;
+            if value < *min || value > *max {
+                return Err("Value outside the column range");
+            }
+            Ok(value.into_raw())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::SolEvent;
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::new([0xaa; 20]);
+    const ALICE: Address = Address::new([0x11; 20]);
+    const HEADER: &str = "id,owner,balance,delta,active\n";
+
+    fn int(value: i64) -> I256 {
+        I256::try_from(value).unwrap()
+    }
+
+    fn deploy() -> (TestVM, CsvImporter) {
+        let vm = TestVM::default();
+        let mut contract = CsvImporter::from(&vm);
+        contract.constructor(OWNER);
+        vm.set_sender(OWNER);
+        (vm, contract)
+    }
+
+    /// A table of accounts: uint id, address owner, uint balance up to 1,000,000, int delta
+    /// in [-100, 100] and a bool flag.
+    fn setup() -> (TestVM, CsvImporter) {
+        let (vm, mut contract) = deploy();
+        let column = |name: &str, column_type, min, max| (name.to_string(), column_type, min, max);
+        contract
+            .set_schema(vec![
+                column("id", COLUMN_UINT, I256::ZERO, I256::MAX),
+                column("owner", COLUMN_ADDRESS, I256::ZERO, I256::ZERO),
+                column("balance", COLUMN_UINT, I256::ZERO, int(1_000_000)),
+                column("delta", COLUMN_INT, int(-100), int(100)),
+                column("active", COLUMN_BOOL, I256::ZERO, I256::ZERO),
+            ])
+            .unwrap();
+        contract.begin_import().unwrap();
+        (vm, contract)
+    }
+
+    fn row(id: u64, balance: u64, delta: i64, active: bool) -> String {
+        format!("{id},{ALICE},{balance},{delta},{active}\n")
+    }
+
+    fn chunk(contract: &mut CsvImporter, data: &str) -> Result<U256, CsvImporterError> {
+        contract.append_chunk(data.to_string())
+    }
+
+    #[test]
+    fn test_rows_split_across_chunks_are_stored() {
+        let (vm, mut contract) = setup();
+        let csv = format!(
+            "{HEADER}{}\r\n{}{}",
+            row(7, 500, -3, true).trim_end(),
+            row(9, 0, 100, false),
+            row(12, 1_000_000, -100, true).trim_end(),
+        );
+        let (first, second) = csv.split_at(HEADER.len() + 20);
+        assert_eq!(chunk(&mut contract, first).unwrap(), U256::ZERO);
+        // The row cut in half by the chunk boundary is completed here; the last row has no
+        // newline and waits for `finish_import`.
+        assert_eq!(chunk(&mut contract, second).unwrap(), U256::from(2));
+        assert_eq!(contract.finish_import().unwrap(), U256::from(3));
+        assert!(!contract.importing());
+
+        assert_eq!(
+            contract.row(U256::ZERO).unwrap(),
+            vec![
+                U256::from(7),
+                U256::from_be_bytes(ALICE.into_word().0),
+                U256::from(500),
+                int(-3).into_raw(),
+                U256::from(1),
+            ]
+        );
+        let position = contract.find_by_key(U256::from(12)).unwrap();
+        assert_eq!(position, U256::from(2));
+        assert_eq!(
+            contract.cell(position, U256::from(2)).unwrap(),
+            U256::from(1_000_000)
+        );
+        assert!(matches!(
+            contract.find_by_key(U256::from(8)),
+            Err(CsvImporterError::KeyNotFound(_))
+        ));
+        assert!(matches!(
+            contract.row(U256::from(3)),
+            Err(CsvImporterError::RowNotFound(_))
+        ));
+
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = &logs[logs.len() - 2];
+        let event = RowsImported::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!((event.firstRow, event.count), (U256::from(2), U256::from(1)));
+    }
+
+    #[test]
+    fn test_bad_row_reverts_chunk_with_line_and_column() {
+        let (_vm, mut contract) = setup();
+        chunk(&mut contract, HEADER).unwrap();
+        let cases = [
+            (format!("1,{ALICE},5,0,yes\n"), 4, "Expected true or false"),
+            (format!("1,{ALICE},1000001,0,true\n"), 2, "Value outside the column range"),
+            (format!("1,{ALICE},5,-101,true\n"), 3, "Value outside the column range"),
+            (format!("-1,{ALICE},5,0,true\n"), 0, "Value outside the column range"),
+            (format!("1,{ALICE},1_000,0,true\n"), 2, "Expected an integer"),
+            ("1,0x1234,5,0,true\n".to_string(), 1, "Expected a 0x address"),
+            (format!("1,{ALICE},5\n"), 3, "Wrong column count"),
+        ];
+        for (line, column, reason) in cases {
+            let data = format!("{}{line}", row(1, 1, 1, true));
+            let err = chunk(&mut contract, &data).unwrap_err();
+            assert!(
+                matches!(&err, CsvImporterError::InvalidRow(e)
+                    if e.line == U256::from(2)
+                        && e.column == U256::from(column)
+                        && e.reason == reason),
+                "{line}: {err:?}"
+            );
+        }
+        // Nothing from the failed chunks was kept, so the corrected chunk goes through.
+        assert_eq!(contract.row_count(), U256::ZERO);
+        let data = format!("{}{}", row(1, 1, 1, true), row(2, 2, 2, false));
+        assert_eq!(chunk(&mut contract, &data).unwrap(), U256::from(2));
+    }
+
+    #[test]
+    fn test_duplicate_keys_are_rejected() {
+        let (_vm, mut contract) = setup();
+        let data = format!("{HEADER}{}\n{}", row(4, 1, 1, true), row(4, 2, 2, true));
+        assert!(matches!(
+            chunk(&mut contract, &data),
+            Err(CsvImporterError::DuplicateKey(DuplicateKey { line, key }))
+                if line == U256::from(3) && key == U256::from(4)
+        ));
+        assert_eq!(contract.row_count(), U256::ZERO);
+    }
+
+    #[test]
+    fn test_header_must_match_schema() {
+        let (_vm, mut contract) = setup();
+        assert!(matches!(
+            chunk(&mut contract, "id,owner,balance,active,delta\n"),
+            Err(CsvImporterError::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            contract.finish_import(),
+            Err(CsvImporterError::InvalidHeader(InvalidHeader { reason }))
+                if reason == "Missing header"
+        ));
+        chunk(&mut contract, " id , owner,balance,delta,active\r\n").unwrap();
+        assert_eq!(contract.finish_import().unwrap(), U256::ZERO);
+    }
+
+    #[test]
+    fn test_chunk_limits() {
+        let (_vm, mut contract) = setup();
+        assert!(matches!(
+            chunk(&mut contract, &"1".repeat(MAX_CHUNK_BYTES + 1)),
+            Err(CsvImporterError::ChunkTooLarge(_))
+        ));
+        assert!(matches!(
+            chunk(&mut contract, &format!("{HEADER}{}", "1".repeat(MAX_LINE_BYTES + 1))),
+            Err(CsvImporterError::LineTooLong(LineTooLong { line, .. })) if line == U256::from(1)
+        ));
+        contract.finish_import().unwrap_err();
+        assert!(contract.importing());
+    }
+
+    #[test]
+    fn test_schema_rules() {
+        let spec = |name: &str, column_type| {
+            vec![(name.to_string(), column_type, I256::ZERO, I256::ZERO)]
+        };
+        for columns in [
+            vec![],
+            spec("bad name", COLUMN_UINT),
+            spec("id", 9),
+            vec![(String::from("id"), COLUMN_INT, int(1), int(0))],
+            vec![(String::from("id"), COLUMN_UINT, int(-1), int(0))],
+            [spec("id", COLUMN_UINT), spec("id", COLUMN_BOOL)].concat(),
+        ] {
+            let (_vm, mut contract) = deploy();
+            assert!(matches!(
+                contract.set_schema(columns),
+                Err(CsvImporterError::InvalidSchema(_))
+            ));
+        }
+
+        let (vm, mut contract) = setup();
+
+        // The schema is fixed while importing and once the table has rows.
+        assert!(matches!(
+            contract.set_schema(spec("id", COLUMN_UINT)),
+            Err(CsvImporterError::SchemaLocked(_))
+        ));
+        chunk(&mut contract, &format!("{HEADER}{}", row(1, 1, 1, true))).unwrap();
+        contract.finish_import().unwrap();
+        assert!(contract.set_schema(spec("id", COLUMN_UINT)).is_err());
+        assert_eq!(contract.schema().len(), 5);
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.begin_import(),
+            Err(CsvImporterError::NotOwner(_))
+        ));
+    }
+}
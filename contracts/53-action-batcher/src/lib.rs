@@ -0,0 +1,495 @@
+//!
+//! Stylus Action Batcher
+//!
+//! Users queue ETH transfers instead of sending them directly, and keepers
+//! execute the queue in batches. `queue_transfer(recipient, tip)` takes the
+//! amount to send plus a tip for whoever executes it, at least the `min_tip`
+//! fixed at deployment. Any account may then call `execute_batch()`, which
+//! works through up to `MAX_BATCH_SIZE` queued actions in order and pays the
+//! caller the tips of every action it processed.
+//!
+//! Only one batch runs per block: a second `execute_batch` in the same block
+//! reverts with `BatchAlreadyExecuted`, so keepers race for the block rather
+//! than splitting the queue into tiny, tip-draining batches. The batch size
+//! cap keeps the loop's gas cost bounded however long the queue grows; a long
+//! queue simply takes several blocks to drain.
+//!
+//! A recipient that rejects its transfer cannot stall the queue. The action
+//! is marked failed, the keeper still earns its tip, and the amount is
+//! credited to the sender, who takes it back with `claim_refund`. Until its
+//! batch runs, a sender may `cancel` an action and recover amount and tip.
+//! Each action's state is written before its ETH moves, and the block is
+//! claimed before the first transfer, so a recipient that calls back into the
+//! batcher cannot run the same actions twice.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+extern crate alloc;
+
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256, U64, U8},
+    prelude::*,
+};
+
+/// Most queued actions a single batch processes.
+const MAX_BATCH_SIZE: u64 = 25;
+
+pub const ACTION_PENDING: u8 = 0;
+pub const ACTION_EXECUTED: u8 = 1;
+pub const ACTION_FAILED: u8 = 2;
+pub const ACTION_CANCELLED: u8 = 3;
+
+/// `(sender, recipient, amount, tip, status)`.
+pub type ActionInfo = (Address, Address, U256, U256, u8);
+
+sol! {
+    event ActionQueued(uint256 indexed id, address indexed sender, address indexed recipient, uint256 amount, uint256 tip);
+    event ActionExecuted(uint256 indexed id, bool success);
+    event ActionCancelled(uint256 indexed id);
+    event BatchExecuted(address indexed keeper, uint64 blockNumber, uint256 processed, uint256 reward);
+    event RefundClaimed(address indexed sender, uint256 amount);
+
+    #[derive(Debug)]
+    error InvalidAction(string reason);
+    #[derive(Debug)]
+    error TipTooLow(uint256 tip, uint256 minTip);
+    #[derive(Debug)]
+    error ActionNotFound(uint256 id);
+    #[derive(Debug)]
+    error NotActionSender(address caller);
+    #[derive(Debug)]
+    error ActionNotPending(uint256 id, uint8 status);
+    #[derive(Debug)]
+    error BatchAlreadyExecuted(uint64 blockNumber);
+    #[derive(Debug)]
+    error NothingToExecute();
+    #[derive(Debug)]
+    error NothingToClaim();
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum ActionBatcherError {
+    InvalidAction(InvalidAction),
+    TipTooLow(TipTooLow),
+    ActionNotFound(ActionNotFound),
+    NotActionSender(NotActionSender),
+    ActionNotPending(ActionNotPending),
+    BatchAlreadyExecuted(BatchAlreadyExecuted),
+    NothingToExecute(NothingToExecute),
+    NothingToClaim(NothingToClaim),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    pub struct Action {
+        address sender;
+        address recipient;
+        uint256 amount;
+        /// Paid to the keeper whose batch processes the action.
+        uint256 tip;
+        /// One of the `ACTION_*` constants.
+        uint8 status;
+    }
+
+    #[entrypoint]
+    pub struct ActionBatcher {
+        /// Queued actions by id; ids start at 1 and the latest is `action_count`.
+        mapping(uint256 => Action) actions;
+        uint256 action_count;
+        /// Every action up to this id has been processed or cancelled.
+        uint256 processed_through;
+        /// Block of the last batch; one batch runs per block.
+        uint64 last_batch_block;
+        uint256 min_tip;
+        /// Amounts of failed transfers, waiting for `claim_refund`.
+        mapping(address => uint256) refunds;
+    }
+}
+
+#[public]
+impl ActionBatcher {
+    #[constructor]
+    pub fn constructor(&mut self, min_tip: U256) {
+        self.min_tip.set(min_tip);
+    }
+
+    /// Queues a transfer of `msg.value - tip` wei to `recipient` and returns its id.
+    #[payable]
+    pub fn queue_transfer(
+        &mut self,
+        recipient: Address,
+        tip: U256,
+    ) -> Result<U256, ActionBatcherError> {
+        let sender = self.vm().msg_sender();
+        if recipient.is_zero() {
+            return Err(ActionBatcherError::InvalidAction(InvalidAction {
+                reason: "Invalid recipient".into(),
+            }));
+        }
+        let min_tip = self.min_tip.get();
+        if tip < min_tip {
+            return Err(ActionBatcherError::TipTooLow(TipTooLow {
+                tip,
+                minTip: min_tip,
+            }));
+        }
+        let value = self.vm().msg_value();
+        if value <= tip {
+            return Err(ActionBatcherError::InvalidAction(InvalidAction {
+                reason: "Nothing to transfer".into(),
+            }));
+        }
+        let amount = value - tip;
+
+        let id = self.action_count.get() + U256::from(1);
+        self.action_count.set(id);
+        let mut action = self.actions.setter(id);
+        action.sender.set(sender);
+        action.recipient.set(recipient);
+        action.amount.set(amount);
+        action.tip.set(tip);
+        log(
+            self.vm(),
+            ActionQueued {
+                id,
+                sender,
+                recipient,
+                amount,
+                tip,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Withdraws a pending action, returning its amount and tip to the sender.
+    pub fn cancel(&mut self, id: U256) -> Result<(), ActionBatcherError> {
+        let status = self.status(id)?;
+        let caller = self.vm().msg_sender();
+        let mut action = self.actions.setter(id);
+        if action.sender.get() != caller {
+            return Err(ActionBatcherError::NotActionSender(NotActionSender {
+                caller,
+            }));
+        }
+        if status != ACTION_PENDING {
+            return Err(ActionBatcherError::ActionNotPending(ActionNotPending {
+                id,
+                status,
+            }));
+        }
+        action.status.set(U8::from(ACTION_CANCELLED));
+        let amount = action.amount.get() + action.tip.get();
+        log(self.vm(), ActionCancelled { id });
+        self.send(caller, amount)
+    }
+
+    /// Processes up to `MAX_BATCH_SIZE` queued actions in order and pays the caller their
+    /// tips, returning the reward. Runs at most once per block.
+    ///
+    /// Cancelled actions are skipped but count toward the batch size, so a queue full of
+    /// cancellations still drains in bounded steps.
+    pub fn execute_batch(&mut self) -> Result<U256, ActionBatcherError> {
+        let block_number = self.vm().block_number();
+        let last_batch = self.last_batch_block.get().to::<u64>();
+        if last_batch != 0 && last_batch >= block_number {
+            return Err(ActionBatcherError::BatchAlreadyExecuted(
+                BatchAlreadyExecuted {
+                    blockNumber: block_number,
+                },
+            ));
+        }
+        let first = self.processed_through.get() + U256::from(1);
+        let last = self
+            .action_count
+            .get()
+            .min(first + U256::from(MAX_BATCH_SIZE - 1));
+        if first > last {
+            return Err(ActionBatcherError::NothingToExecute(NothingToExecute {}));
+        }
+        // Claim the block and the range before any ETH moves.
+        self.last_batch_block.set(U64::from(block_number));
+        self.processed_through.set(last);
+
+        let mut reward = U256::ZERO;
+        let mut id = first;
+        while id <= last {
+            if self.status(id)? == ACTION_PENDING {
+                reward += self.run(id);
+            }
+            id += U256::from(1);
+        }
+        let keeper = self.vm().msg_sender();
+        log(
+            self.vm(),
+            BatchExecuted {
+                keeper,
+                blockNumber: block_number,
+                processed: last + U256::from(1) - first,
+                reward,
+            },
+        );
+        if !reward.is_zero() {
+            self.send(keeper, reward)?;
+        }
+        Ok(reward)
+    }
+
+    /// Pays out the caller's failed transfers.
+    pub fn claim_refund(&mut self) -> Result<U256, ActionBatcherError> {
+        let caller = self.vm().msg_sender();
+        let amount = self.refunds.get(caller);
+        if amount.is_zero() {
+            return Err(ActionBatcherError::NothingToClaim(NothingToClaim {}));
+        }
+        self.refunds.insert(caller, U256::ZERO);
+        log(
+            self.vm(),
+            RefundClaimed {
+                sender: caller,
+                amount,
+            },
+        );
+        self.send(caller, amount)?;
+        Ok(amount)
+    }
+
+    pub fn action(&self, id: U256) -> Result<ActionInfo, ActionBatcherError> {
+        let status = self.status(id)?;
+        let action = self.actions.getter(id);
+        Ok((
+            action.sender.get(),
+            action.recipient.get(),
+            action.amount.get(),
+            action.tip.get(),
+            status,
+        ))
+    }
+
+    /// Actions queued but not yet reached by a batch, cancelled ones included.
+    pub fn pending_count(&self) -> U256 {
+        self.action_count.get() - self.processed_through.get()
+    }
+
+    pub fn action_count(&self) -> U256 {
+        self.action_count.get()
+    }
+
+    pub fn last_batch_block(&self) -> u64 {
+        self.last_batch_block.get().to()
+    }
+
+    pub fn min_tip(&self) -> U256 {
+        self.min_tip.get()
+    }
+
+    pub fn refund_of(&self, sender: Address) -> U256 {
+        self.refunds.get(sender)
+    }
+}
+
+impl ActionBatcher {
+    fn status(&self, id: U256) -> Result<u8, ActionBatcherError> {
+        if id.is_zero() || id > self.action_count.get() {
+            return Err(ActionBatcherError::ActionNotFound(ActionNotFound { id }));
+        }
+        Ok(self.actions.getter(id).status.get().to())
+    }
+
+    /// Sends a pending action's transfer, crediting the sender on failure; returns its tip.
+    fn run(&mut self, id: U256) -> U256 {
+        let action = self.actions.getter(id);
+        let (sender, recipient) = (action.sender.get(), action.recipient.get());
+        let (amount, tip) = (action.amount.get(), action.tip.get());
+        self.actions
+            .setter(id)
+            .status
+            .set(U8::from(ACTION_EXECUTED));
+        let success = self.vm().transfer_eth(recipient, amount).is_ok();
+        if !success {
+            self.actions.setter(id).status.set(U8::from(ACTION_FAILED));
+            let refund = self.refunds.get(sender) + amount;
+            self.refunds.insert(sender, refund);
+        }
+        log(self.vm(), ActionExecuted { id, success });
+        tip
+    }
+
+    fn send(&mut self, to: Address, amount: U256) -> Result<(), ActionBatcherError> {
+        self.vm()
+            .transfer_eth(to, amount)
+            .map_err(|_| ActionBatcherError::TransferFailed(TransferFailed { to, amount }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::SolEvent;
+    use stylus_sdk::testing::*;
+
+    const MIN_TIP: u64 = 100;
+    const ALICE: Address = Address::new([0x11; 20]);
+    const BOB: Address = Address::new([0x22; 20]);
+    const KEEPER: Address = Address::new([0x33; 20]);
+
+    fn setup() -> (TestVM, ActionBatcher) {
+        let vm = TestVM::default();
+        vm.set_block_number(10);
+        let mut contract = ActionBatcher::from(&vm);
+        contract.constructor(U256::from(MIN_TIP));
+        (vm, contract)
+    }
+
+    /// Queues `amount` to `recipient` from `sender` with a `tip`. The attached value is only
+    /// mocked, so the helper also adds it to the contract's balance for `execute_batch`
+    /// to spend.
+    fn queue(
+        vm: &TestVM,
+        contract: &mut ActionBatcher,
+        sender: Address,
+        recipient: Address,
+        amount: u64,
+        tip: u64,
+    ) -> U256 {
+        let value = U256::from(amount + tip);
+        vm.set_sender(sender);
+        vm.set_value(value);
+        let balance = vm.balance(vm.contract_address());
+        vm.set_balance(vm.contract_address(), balance + value);
+        let id = contract.queue_transfer(recipient, U256::from(tip)).unwrap();
+        vm.set_value(U256::ZERO);
+        id
+    }
+
+    #[test]
+    fn test_keeper_executes_batch_and_earns_tips() {
+        let (vm, mut contract) = setup();
+        let first = queue(&vm, &mut contract, ALICE, BOB, 1_000, MIN_TIP);
+        let second = queue(&vm, &mut contract, BOB, ALICE, 500, 250);
+        assert_eq!((first, second), (U256::from(1), U256::from(2)));
+        assert_eq!(contract.pending_count(), U256::from(2));
+
+        vm.set_sender(KEEPER);
+        assert_eq!(contract.execute_batch().unwrap(), U256::from(MIN_TIP + 250));
+        assert_eq!(vm.balance(KEEPER), U256::from(MIN_TIP + 250));
+        assert_eq!(vm.balance(BOB), U256::from(1_000));
+        assert_eq!(vm.balance(ALICE), U256::from(500));
+        assert_eq!(vm.balance(vm.contract_address()), U256::ZERO);
+        assert_eq!(contract.action(first).unwrap().4, ACTION_EXECUTED);
+        assert_eq!(contract.pending_count(), U256::ZERO);
+
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = logs.last().unwrap();
+        let event = BatchExecuted::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!(event.keeper, KEEPER);
+        assert_eq!((event.blockNumber, event.processed), (10, U256::from(2)));
+    }
+
+    #[test]
+    fn test_one_batch_per_block() {
+        let (vm, mut contract) = setup();
+        queue(&vm, &mut contract, ALICE, BOB, 1_000, MIN_TIP);
+        vm.set_sender(KEEPER);
+        contract.execute_batch().unwrap();
+
+        queue(&vm, &mut contract, ALICE, BOB, 1_000, MIN_TIP);
+        vm.set_sender(KEEPER);
+        assert!(matches!(
+            contract.execute_batch(),
+            Err(ActionBatcherError::BatchAlreadyExecuted(BatchAlreadyExecuted { blockNumber }))
+                if blockNumber == 10
+        ));
+        vm.set_block_number(11);
+        contract.execute_batch().unwrap();
+        vm.set_block_number(12);
+        assert!(matches!(
+            contract.execute_batch(),
+            Err(ActionBatcherError::NothingToExecute(_))
+        ));
+        assert_eq!(contract.last_batch_block(), 11);
+    }
+
+    #[test]
+    fn test_batches_are_bounded() {
+        let (vm, mut contract) = setup();
+        let queued = MAX_BATCH_SIZE + 3;
+        for _ in 0..queued {
+            queue(&vm, &mut contract, ALICE, BOB, 10, MIN_TIP);
+        }
+        vm.set_sender(KEEPER);
+        let reward = contract.execute_batch().unwrap();
+        assert_eq!(reward, U256::from(MAX_BATCH_SIZE * MIN_TIP));
+        assert_eq!(contract.pending_count(), U256::from(3));
+        let next = U256::from(MAX_BATCH_SIZE + 1);
+        assert_eq!(contract.action(next).unwrap().4, ACTION_PENDING);
+
+        vm.set_block_number(11);
+        assert_eq!(contract.execute_batch().unwrap(), U256::from(3 * MIN_TIP));
+        assert_eq!(vm.balance(BOB), U256::from(queued * 10));
+    }
+
+    #[test]
+    fn test_cancelled_actions_are_refunded_and_skipped() {
+        let (vm, mut contract) = setup();
+        let first = queue(&vm, &mut contract, ALICE, BOB, 1_000, MIN_TIP);
+        let second = queue(&vm, &mut contract, ALICE, BOB, 2_000, MIN_TIP);
+
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.cancel(first),
+            Err(ActionBatcherError::NotActionSender(_))
+        ));
+        vm.set_sender(ALICE);
+        contract.cancel(first).unwrap();
+        assert_eq!(vm.balance(ALICE), U256::from(1_000 + MIN_TIP));
+        assert!(matches!(
+            contract.cancel(first),
+            Err(ActionBatcherError::ActionNotPending(ActionNotPending { status, .. }))
+                if status == ACTION_CANCELLED
+        ));
+
+        vm.set_sender(KEEPER);
+        assert_eq!(contract.execute_batch().unwrap(), U256::from(MIN_TIP));
+        assert_eq!(vm.balance(BOB), U256::from(2_000));
+        assert_eq!(contract.action(first).unwrap().4, ACTION_CANCELLED);
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.cancel(second),
+            Err(ActionBatcherError::ActionNotPending(_))
+        ));
+        assert!(matches!(
+            contract.cancel(U256::from(3)),
+            Err(ActionBatcherError::ActionNotFound(_))
+        ));
+        assert!(matches!(
+            contract.claim_refund(),
+            Err(ActionBatcherError::NothingToClaim(_))
+        ));
+    }
+
+    #[test]
+    fn test_queue_validates_tip_and_value() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(ALICE);
+        vm.set_value(U256::from(1_000));
+        assert!(matches!(
+            contract.queue_transfer(BOB, U256::from(MIN_TIP - 1)),
+            Err(ActionBatcherError::TipTooLow(_))
+        ));
+        assert!(matches!(
+            contract.queue_transfer(Address::ZERO, U256::from(MIN_TIP)),
+            Err(ActionBatcherError::InvalidAction(_))
+        ));
+        assert!(matches!(
+            contract.queue_transfer(BOB, U256::from(1_000)),
+            Err(ActionBatcherError::InvalidAction(_))
+        ));
+        assert_eq!(contract.action_count(), U256::ZERO);
+    }
+}
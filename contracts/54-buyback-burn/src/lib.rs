@@ -0,0 +1,411 @@
+//!
+//! Stylus Buyback & Burn Treasury
+//!
+//! A treasury that collects a project's ETH revenue, periodically buys the
+//! project token with it and burns what it bought. Revenue arrives as plain
+//! ETH transfers to the contract; the owner then calls `buyback(min_out)`,
+//! which chains three contracts together:
+//!
+//! 1. this treasury sends ETH to an AMM pool's `swapExactEthForTokens`,
+//! 2. the pool sends project tokens back to the treasury,
+//! 3. the treasury transfers those tokens to the burn address `0x…dEaD`.
+//!
+//! This repo has no AMM example, so the pool is any contract exposing the
+//! `IEthTokenPool` interface below; the token can be the `9-erc20-token`
+//! Cupcake, which has no `burn` of its own and is burned by sending it to an
+//! address nobody holds the key for.
+//!
+//! Buybacks are rate limited twice over: at most `max_per_buyback` wei is
+//! spent per call, and consecutive buybacks are at least `min_interval`
+//! seconds apart. Spreading the purchases out keeps each swap small relative
+//! to the pool, so the treasury does not move the price against itself, and
+//! `min_out` bounds the slippage of every swap. The rate limit is recorded
+//! before any external call, so a pool that calls back into the treasury
+//! cannot trigger a second buyback.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall};
+use common::token::safe_transfer;
+use stylus_sdk::{
+    alloy_primitives::{address, Address, U256, U64},
+    prelude::*,
+};
+
+/// Tokens sent here are gone for good; the Cupcake ERC-20 has no `burn`.
+pub const BURN_ADDRESS: Address = address!("000000000000000000000000000000000000dEaD");
+
+sol! {
+    interface IEthTokenPool {
+        function swapExactEthForTokens(uint256 minOut) external payable returns (uint256);
+    }
+
+    event RevenueReceived(address indexed from, uint256 amount);
+    event BuybackExecuted(uint256 spent, uint256 burned, uint64 timestamp);
+    event LimitsUpdated(uint256 maxPerBuyback, uint64 minInterval);
+
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error InvalidConfig();
+    #[derive(Debug)]
+    error BuybackTooSoon(uint64 nextAt);
+    #[derive(Debug)]
+    error NothingToSpend();
+    #[derive(Debug)]
+    error SwapFailed(bytes reason);
+    #[derive(Debug)]
+    error SlippageExceeded(uint256 received, uint256 minimum);
+    #[derive(Debug)]
+    error BurnFailed(uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum BuybackTreasuryError {
+    NotOwner(NotOwner),
+    InvalidConfig(InvalidConfig),
+    BuybackTooSoon(BuybackTooSoon),
+    NothingToSpend(NothingToSpend),
+    SwapFailed(SwapFailed),
+    SlippageExceeded(SlippageExceeded),
+    BurnFailed(BurnFailed),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct BuybackTreasury {
+        address owner;
+        /// AMM pool swapping ETH for `token`.
+        address pool;
+        /// Project token bought and burned.
+        address token;
+        /// Most wei a single buyback spends.
+        uint256 max_per_buyback;
+        /// Minimum seconds between two buybacks.
+        uint64 min_interval;
+        /// Timestamp of the last buyback; zero before the first one.
+        uint64 last_buyback_at;
+        uint256 total_revenue;
+        uint256 total_spent;
+        uint256 total_burned;
+    }
+}
+
+#[public]
+impl BuybackTreasury {
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        owner: Address,
+        pool: Address,
+        token: Address,
+        max_per_buyback: U256,
+        min_interval: u64,
+    ) -> Result<(), BuybackTreasuryError> {
+        if owner.is_zero() || pool.is_zero() || token.is_zero() || max_per_buyback.is_zero() {
+            return Err(BuybackTreasuryError::InvalidConfig(InvalidConfig {}));
+        }
+        self.owner.set(owner);
+        self.pool.set(pool);
+        self.token.set(token);
+        self.max_per_buyback.set(max_per_buyback);
+        self.min_interval.set(U64::from(min_interval));
+        Ok(())
+    }
+
+    /// Accepts revenue sent as a plain ETH transfer.
+    #[receive]
+    #[payable]
+    pub fn receive(&mut self) -> Result<(), Vec<u8>> {
+        let amount = self.vm().msg_value();
+        self.total_revenue.set(self.total_revenue.get() + amount);
+        log(
+            self.vm(),
+            RevenueReceived {
+                from: self.vm().msg_sender(),
+                amount,
+            },
+        );
+        Ok(())
+    }
+
+    /// Swaps up to `max_per_buyback` wei for tokens and burns them, returning the amount burned.
+    /// Reverts if the pool returns fewer than `min_tokens_out`.
+    pub fn buyback(&mut self, min_tokens_out: U256) -> Result<U256, BuybackTreasuryError> {
+        self.only_owner()?;
+        let now = self.vm().block_timestamp();
+        let next_at = self.next_buyback_at();
+        if now < next_at {
+            return Err(BuybackTreasuryError::BuybackTooSoon(BuybackTooSoon {
+                nextAt: next_at,
+            }));
+        }
+        let balance = self.vm().balance(self.vm().contract_address());
+        let amount = balance.min(self.max_per_buyback.get());
+        if amount.is_zero() {
+            return Err(BuybackTreasuryError::NothingToSpend(NothingToSpend {}));
+        }
+        self.last_buyback_at.set(U64::from(now));
+
+        let received = self.swap(amount)?;
+        if received < min_tokens_out {
+            return Err(BuybackTreasuryError::SlippageExceeded(SlippageExceeded {
+                received,
+                minimum: min_tokens_out,
+            }));
+        }
+        self.burn(received)?;
+
+        self.total_spent.set(self.total_spent.get() + amount);
+        self.total_burned.set(self.total_burned.get() + received);
+        log(
+            self.vm(),
+            BuybackExecuted {
+                spent: amount,
+                burned: received,
+                timestamp: now,
+            },
+        );
+        Ok(received)
+    }
+
+    /// Changes the per-buyback cap and the minimum interval between buybacks.
+    pub fn set_limits(
+        &mut self,
+        max_per_buyback: U256,
+        min_interval: u64,
+    ) -> Result<(), BuybackTreasuryError> {
+        self.only_owner()?;
+        if max_per_buyback.is_zero() {
+            return Err(BuybackTreasuryError::InvalidConfig(InvalidConfig {}));
+        }
+        self.max_per_buyback.set(max_per_buyback);
+        self.min_interval.set(U64::from(min_interval));
+        log(
+            self.vm(),
+            LimitsUpdated {
+                maxPerBuyback: max_per_buyback,
+                minInterval: min_interval,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    pub fn pool(&self) -> Address {
+        self.pool.get()
+    }
+
+    pub fn token(&self) -> Address {
+        self.token.get()
+    }
+
+    /// `(max_per_buyback, min_interval)`.
+    pub fn limits(&self) -> (U256, u64) {
+        (
+            self.max_per_buyback.get(),
+            self.min_interval.get().to::<u64>(),
+        )
+    }
+
+    /// Earliest timestamp at which the next buyback may run.
+    pub fn next_buyback_at(&self) -> u64 {
+        let last = self.last_buyback_at.get().to::<u64>();
+        if last == 0 {
+            return 0;
+        }
+        last.saturating_add(self.min_interval.get().to::<u64>())
+    }
+
+    pub fn total_revenue(&self) -> U256 {
+        self.total_revenue.get()
+    }
+
+    pub fn total_spent(&self) -> U256 {
+        self.total_spent.get()
+    }
+
+    pub fn total_burned(&self) -> U256 {
+        self.total_burned.get()
+    }
+}
+
+impl BuybackTreasury {
+    fn only_owner(&self) -> Result<(), BuybackTreasuryError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(BuybackTreasuryError::NotOwner(NotOwner { caller }));
+        }
+        Ok(())
+    }
+
+    /// Sends `amount` wei to the pool and returns the tokens it reports sending back.
+    fn swap(&mut self, amount: U256) -> Result<U256, BuybackTreasuryError> {
+        let pool = self.pool.get();
+        // Slippage is checked by the caller so a short swap gets a descriptive error.
+        let call = IEthTokenPool::swapExactEthForTokensCall { minOut: U256::ZERO };
+        let context = calls::context::Call::new().value(amount);
+        let returned = self
+            .vm()
+            .call(&context, pool, &call.abi_encode())
+            .map_err(|err| {
+                let reason = match err {
+                    calls::errors::Error::Revert(data) => data,
+                    _ => Vec::new(),
+                };
+                BuybackTreasuryError::SwapFailed(SwapFailed {
+                    reason: reason.into(),
+                })
+            })?;
+        IEthTokenPool::swapExactEthForTokensCall::abi_decode_returns(&returned, true)
+            .map(|ret| ret._0)
+            .map_err(|_| {
+                BuybackTreasuryError::SwapFailed(SwapFailed {
+                    reason: Vec::new().into(),
+                })
+            })
+    }
+
+    /// Sends `amount` tokens to the burn address.
+    fn burn(&mut self, amount: U256) -> Result<(), BuybackTreasuryError> {
+        safe_transfer(self.vm(), self.token.get(), BURN_ADDRESS, amount)
+            .map_err(|_| BuybackTreasuryError::BurnFailed(BurnFailed { amount }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use common::token::IERC20Transfers as IERC20;
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::new([0x01; 20]);
+    const PAYER: Address = Address::new([0x02; 20]);
+    const POOL: Address = Address::new([0xaa; 20]);
+    const TOKEN: Address = Address::new([0xcc; 20]);
+    const HOUR: u64 = 3600;
+
+    fn setup() -> (TestVM, BuybackTreasury) {
+        let vm = TestVM::default();
+        let mut contract = BuybackTreasury::from(&vm);
+        contract
+            .constructor(OWNER, POOL, TOKEN, U256::from(100), HOUR)
+            .unwrap();
+        vm.set_block_timestamp(10_000);
+        (vm, contract)
+    }
+
+    fn mock_swap(vm: &TestVM, tokens_out: u64) {
+        let call = IEthTokenPool::swapExactEthForTokensCall { minOut: U256::ZERO };
+        let ret = IEthTokenPool::swapExactEthForTokensCall::abi_encode_returns(&(U256::from(
+            tokens_out,
+        ),));
+        vm.mock_call(POOL, call.abi_encode(), Ok(ret));
+    }
+
+    fn mock_burn(vm: &TestVM, amount: u64, result: Result<Vec<u8>, Vec<u8>>) {
+        let call = IERC20::transferCall {
+            to: BURN_ADDRESS,
+            value: U256::from(amount),
+        };
+        vm.mock_call(TOKEN, call.abi_encode(), result);
+    }
+
+    #[test]
+    fn test_buyback_swaps_and_burns() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(PAYER);
+        vm.set_value(U256::from(60));
+        contract.receive().unwrap();
+        assert_eq!(contract.total_revenue(), U256::from(60));
+
+        vm.set_balance(vm.contract_address(), U256::from(60));
+        mock_swap(&vm, 500);
+        mock_burn(&vm, 500, Ok(IERC20::transferCall::abi_encode_returns(&(true,))));
+        vm.set_sender(OWNER);
+        vm.set_value(U256::ZERO);
+        assert_eq!(contract.buyback(U256::from(400)).unwrap(), U256::from(500));
+        assert_eq!(contract.total_spent(), U256::from(60));
+        assert_eq!(contract.total_burned(), U256::from(500));
+        assert_eq!(contract.next_buyback_at(), 10_000 + HOUR);
+    }
+
+    #[test]
+    fn test_buyback_is_capped_and_rate_limited() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(OWNER);
+        assert!(matches!(
+            contract.buyback(U256::ZERO),
+            Err(BuybackTreasuryError::NothingToSpend(_))
+        ));
+
+        vm.set_balance(vm.contract_address(), U256::from(1_000));
+        mock_swap(&vm, 50);
+        mock_burn(&vm, 50, Ok(Vec::new()));
+        contract.buyback(U256::ZERO).unwrap();
+        // TestVM does not move the ETH sent with the swap, so the cap is what limits the spend.
+        assert_eq!(contract.total_spent(), U256::from(100));
+
+        vm.set_block_timestamp(10_000 + HOUR - 1);
+        assert!(matches!(
+            contract.buyback(U256::ZERO),
+            Err(BuybackTreasuryError::BuybackTooSoon(_))
+        ));
+        vm.set_block_timestamp(10_000 + HOUR);
+        contract.buyback(U256::ZERO).unwrap();
+        assert_eq!(contract.total_spent(), U256::from(200));
+
+        contract.set_limits(U256::from(300), 0).unwrap();
+        assert_eq!(contract.limits(), (U256::from(300), 0));
+        contract.buyback(U256::ZERO).unwrap();
+        assert_eq!(contract.total_spent(), U256::from(500));
+
+        vm.set_sender(PAYER);
+        assert!(matches!(
+            contract.buyback(U256::ZERO),
+            Err(BuybackTreasuryError::NotOwner(_))
+        ));
+    }
+
+    #[test]
+    fn test_buyback_failures() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(OWNER);
+        vm.set_balance(vm.contract_address(), U256::from(100));
+        let swap = IEthTokenPool::swapExactEthForTokensCall { minOut: U256::ZERO };
+        vm.mock_call(POOL, swap.abi_encode(), Err(b"no liquidity".to_vec()));
+        assert!(matches!(
+            contract.buyback(U256::ZERO),
+            Err(BuybackTreasuryError::SwapFailed(_))
+        ));
+
+        let (vm, mut contract) = setup();
+        vm.set_sender(OWNER);
+        vm.set_balance(vm.contract_address(), U256::from(100));
+        mock_swap(&vm, 50);
+        assert!(matches!(
+            contract.buyback(U256::from(51)),
+            Err(BuybackTreasuryError::SlippageExceeded(_))
+        ));
+
+        let (vm, mut contract) = setup();
+        vm.set_sender(OWNER);
+        vm.set_balance(vm.contract_address(), U256::from(100));
+        mock_swap(&vm, 50);
+        mock_burn(&vm, 50, Ok(IERC20::transferCall::abi_encode_returns(&(false,))));
+        assert!(matches!(
+            contract.buyback(U256::ZERO),
+            Err(BuybackTreasuryError::BurnFailed(_))
+        ));
+        assert_eq!(contract.total_burned(), U256::ZERO);
+    }
+}
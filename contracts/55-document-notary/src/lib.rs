@@ -0,0 +1,387 @@
+//!
+//! Stylus Document Notary
+//!
+//! Notarizes documents by their hash. `notarize(content_hash)` opens a new
+//! document owned by the caller, with the hash as its first version and the
+//! block timestamp as proof of when it existed. The document itself never
+//! touches the chain; only its keccak or SHA-256 digest does.
+//!
+//! Documents change over time, so their owner can `append_version` with the
+//! hash of each revision. Every version stores a link hash,
+//! `keccak256(previous_link ++ content_hash ++ timestamp)`, starting from a
+//! zero link for the first version. The latest link therefore commits to the
+//! whole history in order, just as a block hash commits to the chain before
+//! it.
+//!
+//! Anyone can audit a document: `history(id)` lists every version, and
+//! `verify_history(id, hashes)` checks a claimed list of content hashes
+//! against the record, recomputing the link chain as it goes. `find(hash)`
+//! tells which document and version a hash was notarized as. A hash can be
+//! notarized only once across all documents, so the first notarization of a
+//! file stands as its timestamp.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, B256, U256, U64},
+    prelude::*,
+};
+
+/// Most versions one document can hold, keeping `history` and `verify_history` bounded.
+const MAX_VERSIONS: usize = 64;
+
+/// `(content_hash, timestamp, link)` of one version.
+pub type VersionInfo = (B256, u64, B256);
+
+sol! {
+    event DocumentNotarized(uint256 indexed id, address indexed owner, bytes32 contentHash, bytes32 link);
+    event VersionAppended(uint256 indexed id, uint64 version, bytes32 contentHash, bytes32 link);
+
+    #[derive(Debug)]
+    error InvalidHash();
+    #[derive(Debug)]
+    error AlreadyNotarized(bytes32 contentHash, uint256 documentId);
+    #[derive(Debug)]
+    error DocumentNotFound(uint256 id);
+    #[derive(Debug)]
+    error NotDocumentOwner(address caller);
+    #[derive(Debug)]
+    error VersionNotFound(uint256 id, uint64 version);
+    #[derive(Debug)]
+    error TooManyVersions(uint256 id);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum DocumentNotaryError {
+    InvalidHash(InvalidHash),
+    AlreadyNotarized(AlreadyNotarized),
+    DocumentNotFound(DocumentNotFound),
+    NotDocumentOwner(NotDocumentOwner),
+    VersionNotFound(VersionNotFound),
+    TooManyVersions(TooManyVersions),
+}
+
+sol_storage! {
+    pub struct Version {
+        bytes32 content_hash;
+        uint64 timestamp;
+        /// Hash of the previous version's link, this content hash and the timestamp.
+        bytes32 link;
+    }
+
+    pub struct Document {
+        address owner;
+        /// Oldest first; version numbers count from 1.
+        Version[] versions;
+    }
+
+    /// Where a content hash was notarized.
+    pub struct Notarization {
+        uint256 document;
+        uint64 version;
+    }
+
+    #[entrypoint]
+    pub struct DocumentNotary {
+        /// Documents by id; ids start at 1 and the latest is `document_count`.
+        mapping(uint256 => Document) documents;
+        uint256 document_count;
+        mapping(bytes32 => Notarization) notarizations;
+    }
+}
+
+#[public]
+impl DocumentNotary {
+    /// Opens a document owned by the caller with `content_hash` as version 1, returning its id.
+    pub fn notarize(&mut self, content_hash: B256) -> Result<U256, DocumentNotaryError> {
+        self.ensure_new(content_hash)?;
+        let owner = self.vm().msg_sender();
+        let id = self.document_count.get() + U256::from(1);
+        self.document_count.set(id);
+        self.documents.setter(id).owner.set(owner);
+        let link = self.push_version(id, content_hash);
+        log(
+            self.vm(),
+            DocumentNotarized {
+                id,
+                owner,
+                contentHash: content_hash,
+                link,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Records `content_hash` as the next version of document `id`, returning the version
+    /// number. Only the document's owner may append.
+    pub fn append_version(
+        &mut self,
+        id: U256,
+        content_hash: B256,
+    ) -> Result<u64, DocumentNotaryError> {
+        let caller = self.vm().msg_sender();
+        if self.owner_of(id)? != caller {
+            return Err(DocumentNotaryError::NotDocumentOwner(NotDocumentOwner {
+                caller,
+            }));
+        }
+        if self.documents.getter(id).versions.len() >= MAX_VERSIONS {
+            return Err(DocumentNotaryError::TooManyVersions(TooManyVersions { id }));
+        }
+        self.ensure_new(content_hash)?;
+        let link = self.push_version(id, content_hash);
+        let version = self.documents.getter(id).versions.len() as u64;
+        log(
+            self.vm(),
+            VersionAppended {
+                id,
+                version,
+                contentHash: content_hash,
+                link,
+            },
+        );
+        Ok(version)
+    }
+
+    pub fn owner_of(&self, id: U256) -> Result<Address, DocumentNotaryError> {
+        self.ensure_document(id)?;
+        Ok(self.documents.getter(id).owner.get())
+    }
+
+    pub fn version_count(&self, id: U256) -> Result<u64, DocumentNotaryError> {
+        self.ensure_document(id)?;
+        Ok(self.documents.getter(id).versions.len() as u64)
+    }
+
+    /// Link of the latest version, committing to the document's whole history.
+    pub fn head(&self, id: U256) -> Result<B256, DocumentNotaryError> {
+        let count = self.version_count(id)?;
+        Ok(self.version(id, count)?.2)
+    }
+
+    pub fn version(&self, id: U256, version: u64) -> Result<VersionInfo, DocumentNotaryError> {
+        self.ensure_document(id)?;
+        let document = self.documents.getter(id);
+        let entry = version
+            .checked_sub(1)
+            .and_then(|index| document.versions.getter(index as usize))
+            .ok_or(DocumentNotaryError::VersionNotFound(VersionNotFound {
+                id,
+                version,
+            }))?;
+        Ok((
+            entry.content_hash.get(),
+            entry.timestamp.get().to(),
+            entry.link.get(),
+        ))
+    }
+
+    /// Every version of document `id`, oldest first.
+    pub fn history(&self, id: U256) -> Result<Vec<VersionInfo>, DocumentNotaryError> {
+        let count = self.version_count(id)?;
+        (1..=count).map(|version| self.version(id, version)).collect()
+    }
+
+    /// `(document, version)` that `content_hash` was notarized as, or zeros if it never was.
+    pub fn find(&self, content_hash: B256) -> (U256, u64) {
+        let notarization = self.notarizations.getter(content_hash);
+        (
+            notarization.document.get(),
+            notarization.version.get().to(),
+        )
+    }
+
+    /// Whether `content_hashes` is exactly the version history of document `id`, in order,
+    /// with every stored link matching the chain recomputed from them.
+    pub fn verify_history(
+        &self,
+        id: U256,
+        content_hashes: Vec<B256>,
+    ) -> Result<bool, DocumentNotaryError> {
+        let history = self.history(id)?;
+        if history.len() != content_hashes.len() {
+            return Ok(false);
+        }
+        let mut link = B256::ZERO;
+        for (entry, claimed) in history.into_iter().zip(content_hashes) {
+            let (stored_hash, timestamp, stored_link) = entry;
+            link = self.link(link, claimed, timestamp);
+            if stored_hash != claimed || stored_link != link {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    pub fn document_count(&self) -> U256 {
+        self.document_count.get()
+    }
+}
+
+impl DocumentNotary {
+    fn ensure_document(&self, id: U256) -> Result<(), DocumentNotaryError> {
+        if id.is_zero() || id > self.document_count.get() {
+            return Err(DocumentNotaryError::DocumentNotFound(DocumentNotFound { id }));
+        }
+        Ok(())
+    }
+
+    fn ensure_new(&self, content_hash: B256) -> Result<(), DocumentNotaryError> {
+        if content_hash.is_zero() {
+            return Err(DocumentNotaryError::InvalidHash(InvalidHash {}));
+        }
+        let existing = self.notarizations.getter(content_hash).document.get();
+        if !existing.is_zero() {
+            return Err(DocumentNotaryError::AlreadyNotarized(AlreadyNotarized {
+                contentHash: content_hash,
+                documentId: existing,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Appends a version stamped with the current block time and returns its link.
+    fn push_version(&mut self, id: U256, content_hash: B256) -> B256 {
+        let timestamp = self.vm().block_timestamp();
+        let document = self.documents.getter(id);
+        let count = document.versions.len();
+        let previous = match count.checked_sub(1).and_then(|i| document.versions.getter(i)) {
+            Some(entry) => entry.link.get(),
+            None => B256::ZERO,
+        };
+        let link = self.link(previous, content_hash, timestamp);
+
+        let mut document = self.documents.setter(id);
+        let mut entry = document.versions.grow();
+        entry.content_hash.set(content_hash);
+        entry.timestamp.set(U64::from(timestamp));
+        entry.link.set(link);
+
+        let mut notarization = self.notarizations.setter(content_hash);
+        notarization.document.set(id);
+        notarization.version.set(U64::from(count + 1));
+        link
+    }
+
+    /// `keccak256(previous ++ content_hash ++ timestamp)`, with the timestamp as 8 big-endian
+    /// bytes.
+    fn link(&self, previous: B256, content_hash: B256, timestamp: u64) -> B256 {
+        let mut preimage = Vec::with_capacity(72);
+        preimage.extend_from_slice(previous.as_slice());
+        preimage.extend_from_slice(content_hash.as_slice());
+        preimage.extend_from_slice(&timestamp.to_be_bytes());
+        self.vm().native_keccak256(&preimage)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::{alloy_primitives::keccak256, testing::*};
+
+    const ALICE: Address = Address::new([0x11; 20]);
+    const BOB: Address = Address::new([0x22; 20]);
+    const START: u64 = 1_700_000_000;
+
+    fn setup() -> (TestVM, DocumentNotary) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(START);
+        vm.set_sender(ALICE);
+        let contract = DocumentNotary::from(&vm);
+        (vm, contract)
+    }
+
+    fn doc(text: &str) -> B256 {
+        keccak256(text.as_bytes())
+    }
+
+    #[test]
+    fn test_versions_form_a_hash_linked_chain() {
+        let (vm, mut contract) = setup();
+        let id = contract.notarize(doc("draft")).unwrap();
+        assert_eq!(id, U256::from(1));
+        vm.set_block_timestamp(START + 100);
+        assert_eq!(contract.append_version(id, doc("final")).unwrap(), 2);
+
+        let mut genesis = [0u8; 72];
+        genesis[32..64].copy_from_slice(doc("draft").as_slice());
+        genesis[64..].copy_from_slice(&START.to_be_bytes());
+        let first = keccak256(genesis);
+        let mut second = [0u8; 72];
+        second[..32].copy_from_slice(first.as_slice());
+        second[32..64].copy_from_slice(doc("final").as_slice());
+        second[64..].copy_from_slice(&(START + 100).to_be_bytes());
+        assert_eq!(
+            contract.history(id).unwrap(),
+            vec![
+                (doc("draft"), START, first),
+                (doc("final"), START + 100, keccak256(second)),
+            ]
+        );
+        assert_eq!(contract.head(id).unwrap(), keccak256(second));
+        assert_eq!(contract.find(doc("final")), (id, 2));
+        assert_eq!(contract.find(doc("unknown")), (U256::ZERO, 0));
+    }
+
+    #[test]
+    fn test_anyone_can_verify_history() {
+        let (vm, mut contract) = setup();
+        let id = contract.notarize(doc("v1")).unwrap();
+        contract.append_version(id, doc("v2")).unwrap();
+        contract.append_version(id, doc("v3")).unwrap();
+
+        vm.set_sender(BOB);
+        let claimed = vec![doc("v1"), doc("v2"), doc("v3")];
+        assert!(contract.verify_history(id, claimed).unwrap());
+        assert!(!contract
+            .verify_history(id, vec![doc("v1"), doc("v3"), doc("v2")])
+            .unwrap());
+        assert!(!contract
+            .verify_history(id, vec![doc("v1"), doc("v2")])
+            .unwrap());
+        assert!(matches!(
+            contract.verify_history(U256::from(2), vec![]),
+            Err(DocumentNotaryError::DocumentNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_invalid_notarizations() {
+        let (vm, mut contract) = setup();
+        assert!(matches!(
+            contract.notarize(B256::ZERO),
+            Err(DocumentNotaryError::InvalidHash(_))
+        ));
+        let id = contract.notarize(doc("contract")).unwrap();
+
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.notarize(doc("contract")),
+            Err(DocumentNotaryError::AlreadyNotarized(_))
+        ));
+        assert!(matches!(
+            contract.append_version(id, doc("forged")),
+            Err(DocumentNotaryError::NotDocumentOwner(_))
+        ));
+        assert!(matches!(
+            contract.version(id, 0),
+            Err(DocumentNotaryError::VersionNotFound(_))
+        ));
+
+        vm.set_sender(ALICE);
+        for i in 1..MAX_VERSIONS {
+            contract.append_version(id, doc(&i.to_string())).unwrap();
+        }
+        assert!(matches!(
+            contract.append_version(id, doc("one too many")),
+            Err(DocumentNotaryError::TooManyVersions(_))
+        ));
+    }
+}
@@ -0,0 +1,739 @@
+//!
+//! Stylus ERC-1155 Cupcake Flavors
+//!
+//! One contract, many tokens: every cupcake flavor is an ERC-1155 token id
+//! with its own metadata URI, price, supply cap and purchase cooldown.
+//!
+//! ```solidity
+//! function balanceOf(address account, uint256 id) external view returns (uint256);
+//! function balanceOfBatch(address[] accounts, uint256[] ids) external view returns (uint256[]);
+//! function setApprovalForAll(address operator, bool approved) external;
+//! function safeTransferFrom(address from, address to, uint256 id, uint256 value, bytes data) external;
+//! function safeBatchTransferFrom(address from, address to, uint256[] ids, uint256[] values, bytes data) external;
+//! function uri(uint256 id) external view returns (string memory);
+//! ```
+//!
+//! The owner lists flavors with `add_flavor`, which assigns sequential ids
+//! starting at 0. Anyone then mints cupcakes of a flavor with
+//! `buy(id, amount)`, paying exactly `price * amount` wei, as long as the
+//! flavor's `max_supply` is not exhausted and the buyer's cooldown for that
+//! flavor has passed. Free flavors (price zero) are limited by the cooldown
+//! alone. The owner collects sales with `withdraw`.
+//!
+//! Balances live in a nested `mapping(uint256 => mapping(address => uint256))`,
+//! token id first, exactly as in OpenZeppelin's ERC1155. Transfers move any mix
+//! of ids in one call and emit `TransferSingle` or `TransferBatch`. As the
+//! standard requires, every transfer and mint to a contract calls its
+//! `onERC1155Received` or `onERC1155BatchReceived` and reverts unless it
+//! returns that function's selector. Failures revert with the ERC-6093
+//! custom errors.
+//!
+//! `supportsInterface` answers ERC-165 queries for ERC-165, ERC-1155 and the
+//! ERC-1155 Metadata URI extension, with the ids from `common::introspection`.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use alloy_sol_types::{sol, SolCall};
+use common::introspection::{ERC1155_ID, ERC1155_METADATA_URI_ID, ERC165_ID};
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, FixedBytes, U256, U64},
+    prelude::*,
+};
+
+/// `(name, price, max_supply, minted, cooldown_seconds)`, as returned by `flavor`.
+pub type FlavorInfo = (String, U256, U256, U256, u64);
+
+sol! {
+    interface IERC1155Receiver {
+        function onERC1155Received(address operator, address from, uint256 id, uint256 value, bytes data) external returns (bytes4);
+        function onERC1155BatchReceived(address operator, address from, uint256[] ids, uint256[] values, bytes data) external returns (bytes4);
+    }
+
+    event TransferSingle(address indexed operator, address indexed from, address indexed to, uint256 id, uint256 value);
+    event TransferBatch(address indexed operator, address indexed from, address indexed to, uint256[] ids, uint256[] values);
+    event ApprovalForAll(address indexed account, address indexed operator, bool approved);
+    event URI(string value, uint256 indexed id);
+    event FlavorAdded(uint256 indexed id, string name, uint256 price, uint256 maxSupply, uint64 cooldownSeconds);
+
+    #[derive(Debug)]
+    error ERC1155InsufficientBalance(address sender, uint256 balance, uint256 needed, uint256 tokenId);
+    #[derive(Debug)]
+    error ERC1155InvalidSender(address sender);
+    #[derive(Debug)]
+    error ERC1155InvalidReceiver(address receiver);
+    #[derive(Debug)]
+    error ERC1155MissingApprovalForAll(address operator, address owner);
+    #[derive(Debug)]
+    error ERC1155InvalidOperator(address operator);
+    #[derive(Debug)]
+    error ERC1155InvalidArrayLength(uint256 idsLength, uint256 valuesLength);
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error InvalidFlavor(string reason);
+    #[derive(Debug)]
+    error UnknownFlavor(uint256 id);
+    #[derive(Debug)]
+    error SoldOut(uint256 id, uint256 remaining);
+    #[derive(Debug)]
+    error CooldownActive(uint256 id, uint256 remaining);
+    #[derive(Debug)]
+    error WrongPayment(uint256 expected, uint256 sent);
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum Erc1155Error {
+    InsufficientBalance(ERC1155InsufficientBalance),
+    InvalidSender(ERC1155InvalidSender),
+    InvalidReceiver(ERC1155InvalidReceiver),
+    MissingApprovalForAll(ERC1155MissingApprovalForAll),
+    InvalidOperator(ERC1155InvalidOperator),
+    InvalidArrayLength(ERC1155InvalidArrayLength),
+    NotOwner(NotOwner),
+    InvalidFlavor(InvalidFlavor),
+    UnknownFlavor(UnknownFlavor),
+    SoldOut(SoldOut),
+    CooldownActive(CooldownActive),
+    WrongPayment(WrongPayment),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    pub struct Flavor {
+        string name;
+        /// Metadata URI returned by `uri(id)`.
+        string uri;
+        /// Wei per cupcake; zero makes the flavor free.
+        uint256 price;
+        uint256 max_supply;
+        uint256 minted;
+        /// Seconds an account waits between two purchases of this flavor.
+        uint64 cooldown_seconds;
+        mapping(address => uint64) last_bought;
+    }
+
+    #[entrypoint]
+    pub struct CupcakeFlavors {
+        /// Contract owner, who lists flavors and collects sales.
+        address owner;
+        /// Flavors by token id; ids run from 0 to `flavor_count - 1`.
+        mapping(uint256 => Flavor) flavors;
+        uint256 flavor_count;
+        /// Balances by token id, then account.
+        mapping(uint256 => mapping(address => uint256)) balances;
+        mapping(address => mapping(address => bool)) operator_approvals;
+    }
+}
+
+#[public]
+impl CupcakeFlavors {
+    #[constructor]
+    pub fn constructor(&mut self, owner: Address) {
+        self.owner.set(owner);
+    }
+
+    /// Metadata URI of flavor `id`.
+    pub fn uri(&self, id: U256) -> Result<String, Erc1155Error> {
+        self.ensure_flavor(id)?;
+        Ok(self.flavors.getter(id).uri.get_string())
+    }
+
+    pub fn balance_of(&self, account: Address, id: U256) -> U256 {
+        self.balances.getter(id).get(account)
+    }
+
+    /// `balance_of(accounts[i], ids[i])` for every `i`.
+    pub fn balance_of_batch(
+        &self,
+        accounts: Vec<Address>,
+        ids: Vec<U256>,
+    ) -> Result<Vec<U256>, Erc1155Error> {
+        if accounts.len() != ids.len() {
+            return Err(Erc1155Error::InvalidArrayLength(
+                ERC1155InvalidArrayLength {
+                    idsLength: U256::from(ids.len()),
+                    valuesLength: U256::from(accounts.len()),
+                },
+            ));
+        }
+        Ok(accounts
+            .into_iter()
+            .zip(ids)
+            .map(|(account, id)| self.balance_of(account, id))
+            .collect())
+    }
+
+    pub fn set_approval_for_all(
+        &mut self,
+        operator: Address,
+        approved: bool,
+    ) -> Result<(), Erc1155Error> {
+        if operator.is_zero() {
+            return Err(Erc1155Error::InvalidOperator(ERC1155InvalidOperator {
+                operator,
+            }));
+        }
+        let account = self.vm().msg_sender();
+        self.operator_approvals
+            .setter(account)
+            .insert(operator, approved);
+        log(
+            self.vm(),
+            ApprovalForAll {
+                account,
+                operator,
+                approved,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn is_approved_for_all(&self, account: Address, operator: Address) -> bool {
+        self.operator_approvals.getter(account).get(operator)
+    }
+
+    /// Moves `value` cupcakes of flavor `id`; `from` or one of its operators may call this.
+    pub fn safe_transfer_from(
+        &mut self,
+        from: Address,
+        to: Address,
+        id: U256,
+        value: U256,
+        data: Bytes,
+    ) -> Result<(), Erc1155Error> {
+        self.transfer_with_check(from, to, vec![id], vec![value], data)
+    }
+
+    /// Moves `values[i]` cupcakes of flavor `ids[i]` for every `i` in one call.
+    pub fn safe_batch_transfer_from(
+        &mut self,
+        from: Address,
+        to: Address,
+        ids: Vec<U256>,
+        values: Vec<U256>,
+        data: Bytes,
+    ) -> Result<(), Erc1155Error> {
+        self.transfer_with_check(from, to, ids, values, data)
+    }
+
+    /// ERC-165: whether this contract implements the interface `interface_id`.
+    pub fn supports_interface(&self, interface_id: FixedBytes<4>) -> bool {
+        [ERC165_ID, ERC1155_ID, ERC1155_METADATA_URI_ID].contains(&interface_id)
+    }
+
+    /// Mints `amount` cupcakes of flavor `id` to the caller for exactly `price * amount` wei.
+    #[payable]
+    pub fn buy(&mut self, id: U256, amount: U256) -> Result<(), Erc1155Error> {
+        self.ensure_flavor(id)?;
+        if amount.is_zero() {
+            return Err(Erc1155Error::InvalidFlavor(InvalidFlavor {
+                reason: "Nothing to buy".into(),
+            }));
+        }
+        let buyer = self.vm().msg_sender();
+        let now = self.vm().block_timestamp();
+        let flavor = self.flavors.getter(id);
+        let last = flavor.last_bought.get(buyer).to::<u64>();
+        let ready_at = last + flavor.cooldown_seconds.get().to::<u64>();
+        if last != 0 && now < ready_at {
+            return Err(Erc1155Error::CooldownActive(CooldownActive {
+                id,
+                remaining: U256::from(ready_at - now),
+            }));
+        }
+        let remaining = flavor.max_supply.get() - flavor.minted.get();
+        if amount > remaining {
+            return Err(Erc1155Error::SoldOut(SoldOut { id, remaining }));
+        }
+        let expected = flavor.price.get().saturating_mul(amount);
+        let sent = self.vm().msg_value();
+        if sent != expected {
+            return Err(Erc1155Error::WrongPayment(WrongPayment { expected, sent }));
+        }
+
+        let mut flavor = self.flavors.setter(id);
+        let minted = flavor.minted.get() + amount;
+        flavor.minted.set(minted);
+        flavor.last_bought.insert(buyer, U64::from(now));
+        let (ids, values) = (vec![id], vec![amount]);
+        self.update(Address::ZERO, buyer, &ids, &values)?;
+        self.check_received(Address::ZERO, buyer, ids, values, Bytes::from(Vec::new()))
+    }
+
+    /// Lists a new flavor and returns its token id. Only the owner may add flavors.
+    pub fn add_flavor(
+        &mut self,
+        name: String,
+        uri: String,
+        price: U256,
+        max_supply: U256,
+        cooldown_seconds: u64,
+    ) -> Result<U256, Erc1155Error> {
+        self.only_owner()?;
+        if name.is_empty() {
+            return Err(Erc1155Error::InvalidFlavor(InvalidFlavor {
+                reason: "Empty name".into(),
+            }));
+        }
+        if max_supply.is_zero() {
+            return Err(Erc1155Error::InvalidFlavor(InvalidFlavor {
+                reason: "Zero supply".into(),
+            }));
+        }
+        let id = self.flavor_count.get();
+        self.flavor_count.set(id + U256::from(1));
+        let mut flavor = self.flavors.setter(id);
+        flavor.name.set_str(&name);
+        flavor.uri.set_str(&uri);
+        flavor.price.set(price);
+        flavor.max_supply.set(max_supply);
+        flavor.cooldown_seconds.set(U64::from(cooldown_seconds));
+        log(
+            self.vm(),
+            FlavorAdded {
+                id,
+                name,
+                price,
+                maxSupply: max_supply,
+                cooldownSeconds: cooldown_seconds,
+            },
+        );
+        log(self.vm(), URI { value: uri, id });
+        Ok(id)
+    }
+
+    /// Replaces the metadata URI of flavor `id`.
+    pub fn set_uri(&mut self, id: U256, uri: String) -> Result<(), Erc1155Error> {
+        self.only_owner()?;
+        self.ensure_flavor(id)?;
+        self.flavors.setter(id).uri.set_str(&uri);
+        log(self.vm(), URI { value: uri, id });
+        Ok(())
+    }
+
+    /// Sends the contract's sales proceeds to the owner and returns the amount.
+    pub fn withdraw(&mut self) -> Result<U256, Erc1155Error> {
+        self.only_owner()?;
+        let owner = self.owner.get();
+        let amount = self.vm().balance(self.vm().contract_address());
+        self.vm()
+            .transfer_eth(owner, amount)
+            .map_err(|_| Erc1155Error::TransferFailed(TransferFailed { to: owner, amount }))?;
+        Ok(amount)
+    }
+
+    pub fn flavor(&self, id: U256) -> Result<FlavorInfo, Erc1155Error> {
+        self.ensure_flavor(id)?;
+        let flavor = self.flavors.getter(id);
+        Ok((
+            flavor.name.get_string(),
+            flavor.price.get(),
+            flavor.max_supply.get(),
+            flavor.minted.get(),
+            flavor.cooldown_seconds.get().to(),
+        ))
+    }
+
+    pub fn flavor_count(&self) -> U256 {
+        self.flavor_count.get()
+    }
+
+    /// Cupcakes of flavor `id` minted so far.
+    pub fn total_supply(&self, id: U256) -> U256 {
+        self.flavors.getter(id).minted.get()
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+}
+
+impl CupcakeFlavors {
+    fn only_owner(&self) -> Result<(), Erc1155Error> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(Erc1155Error::NotOwner(NotOwner { caller }));
+        }
+        Ok(())
+    }
+
+    fn ensure_flavor(&self, id: U256) -> Result<(), Erc1155Error> {
+        if id >= self.flavor_count.get() {
+            return Err(Erc1155Error::UnknownFlavor(UnknownFlavor { id }));
+        }
+        Ok(())
+    }
+
+    /// Shared body of the two `safe*TransferFrom` entry points.
+    fn transfer_with_check(
+        &mut self,
+        from: Address,
+        to: Address,
+        ids: Vec<U256>,
+        values: Vec<U256>,
+        data: Bytes,
+    ) -> Result<(), Erc1155Error> {
+        let caller = self.vm().msg_sender();
+        if caller != from && !self.is_approved_for_all(from, caller) {
+            return Err(Erc1155Error::MissingApprovalForAll(
+                ERC1155MissingApprovalForAll {
+                    operator: caller,
+                    owner: from,
+                },
+            ));
+        }
+        if from.is_zero() {
+            return Err(Erc1155Error::InvalidSender(ERC1155InvalidSender {
+                sender: from,
+            }));
+        }
+        if to.is_zero() {
+            return Err(Erc1155Error::InvalidReceiver(ERC1155InvalidReceiver {
+                receiver: to,
+            }));
+        }
+        self.update(from, to, &ids, &values)?;
+        self.check_received(from, to, ids, values, data)
+    }
+
+    /// Moves balances from `from` to `to`, where a zero `from` mints, and emits
+    /// `TransferSingle` for one id or `TransferBatch` for several.
+    fn update(
+        &mut self,
+        from: Address,
+        to: Address,
+        ids: &[U256],
+        values: &[U256],
+    ) -> Result<(), Erc1155Error> {
+        if ids.len() != values.len() {
+            return Err(Erc1155Error::InvalidArrayLength(
+                ERC1155InvalidArrayLength {
+                    idsLength: U256::from(ids.len()),
+                    valuesLength: U256::from(values.len()),
+                },
+            ));
+        }
+        for (&id, &value) in ids.iter().zip(values) {
+            if !from.is_zero() {
+                let balance = self.balance_of(from, id);
+                if balance < value {
+                    return Err(Erc1155Error::InsufficientBalance(
+                        ERC1155InsufficientBalance {
+                            sender: from,
+                            balance,
+                            needed: value,
+                            tokenId: id,
+                        },
+                    ));
+                }
+                self.balances.setter(id).insert(from, balance - value);
+            }
+            let balance = self.balance_of(to, id);
+            self.balances.setter(id).insert(to, balance + value);
+        }
+
+        let operator = self.vm().msg_sender();
+        if ids.len() == 1 {
+            log(
+                self.vm(),
+                TransferSingle {
+                    operator,
+                    from,
+                    to,
+                    id: ids[0],
+                    value: values[0],
+                },
+            );
+        } else {
+            log(
+                self.vm(),
+                TransferBatch {
+                    operator,
+                    from,
+                    to,
+                    ids: ids.to_vec(),
+                    values: values.to_vec(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Accepts accounts without code; a contract must answer `onERC1155Received` (one id) or
+    /// `onERC1155BatchReceived` (several) with that function's selector.
+    fn check_received(
+        &mut self,
+        from: Address,
+        to: Address,
+        ids: Vec<U256>,
+        values: Vec<U256>,
+        data: Bytes,
+    ) -> Result<(), Erc1155Error> {
+        if self.vm().code_size(to) == 0 {
+            return Ok(());
+        }
+        let operator = self.vm().msg_sender();
+        let (calldata, expected) = if ids.len() == 1 {
+            let call = IERC1155Receiver::onERC1155ReceivedCall {
+                operator,
+                from,
+                id: ids[0],
+                value: values[0],
+                data: data.0.into(),
+            };
+            (
+                call.abi_encode(),
+                IERC1155Receiver::onERC1155ReceivedCall::SELECTOR,
+            )
+        } else {
+            let call = IERC1155Receiver::onERC1155BatchReceivedCall {
+                operator,
+                from,
+                ids,
+                values,
+                data: data.0.into(),
+            };
+            (
+                call.abi_encode(),
+                IERC1155Receiver::onERC1155BatchReceivedCall::SELECTOR,
+            )
+        };
+        // Both callbacks return a single `bytes4`, so either decoder reads the answer.
+        let accepted = self
+            .vm()
+            .call(&calls::context::Call::new(), to, &calldata)
+            .ok()
+            .and_then(|returned| {
+                IERC1155Receiver::onERC1155ReceivedCall::abi_decode_returns(&returned, true).ok()
+            })
+            .is_some_and(|decoded| decoded._0 == expected);
+        if !accepted {
+            return Err(Erc1155Error::InvalidReceiver(ERC1155InvalidReceiver {
+                receiver: to,
+            }));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::SolEvent;
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::new([0x01; 20]);
+    const ALICE: Address = Address::new([0x02; 20]);
+    const BOB: Address = Address::new([0x03; 20]);
+    const PRICE: u64 = 1_000;
+    const COOLDOWN: u64 = 60;
+
+    /// Flavor 0 is "Vanilla" at `PRICE` wei, flavor 1 is free "Lemon"; both capped at 10.
+    fn setup() -> (TestVM, CupcakeFlavors) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(1_000_000);
+        let mut contract = CupcakeFlavors::from(&vm);
+        contract.constructor(OWNER);
+        vm.set_sender(OWNER);
+        for (name, price) in [("Vanilla", PRICE), ("Lemon", 0)] {
+            let id = contract.flavor_count();
+            contract
+                .add_flavor(
+                    name.into(),
+                    format!("ipfs://cupcakes/{id}"),
+                    U256::from(price),
+                    U256::from(10),
+                    COOLDOWN,
+                )
+                .unwrap();
+        }
+        (vm, contract)
+    }
+
+    fn buy(vm: &TestVM, contract: &mut CupcakeFlavors, buyer: Address, id: u64, amount: u64) {
+        vm.set_sender(buyer);
+        let price = contract.flavor(U256::from(id)).unwrap().1;
+        vm.set_value(price * U256::from(amount));
+        contract.buy(U256::from(id), U256::from(amount)).unwrap();
+        vm.set_value(U256::ZERO);
+    }
+
+    fn none() -> Bytes {
+        Bytes::from(Vec::new())
+    }
+
+    #[test]
+    fn test_flavors_have_their_own_uri_and_supply() {
+        let (vm, mut contract) = setup();
+        assert_eq!(contract.flavor_count(), U256::from(2));
+        assert_eq!(contract.uri(U256::from(1)).unwrap(), "ipfs://cupcakes/1");
+        contract
+            .set_uri(U256::from(1), "ipfs://lemon".into())
+            .unwrap();
+        assert_eq!(contract.uri(U256::from(1)).unwrap(), "ipfs://lemon");
+        assert!(matches!(
+            contract.uri(U256::from(2)),
+            Err(Erc1155Error::UnknownFlavor(_))
+        ));
+
+        buy(&vm, &mut contract, ALICE, 0, 3);
+        assert_eq!(contract.total_supply(U256::ZERO), U256::from(3));
+        assert_eq!(contract.total_supply(U256::from(1)), U256::ZERO);
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.set_uri(U256::ZERO, "ipfs://mine".into()),
+            Err(Erc1155Error::NotOwner(_))
+        ));
+    }
+
+    #[test]
+    fn test_buy_enforces_price_supply_and_cooldown() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(ALICE);
+        vm.set_value(U256::from(PRICE));
+        assert!(matches!(
+            contract.buy(U256::ZERO, U256::from(2)),
+            Err(Erc1155Error::WrongPayment(_))
+        ));
+        buy(&vm, &mut contract, ALICE, 0, 2);
+        assert_eq!(contract.balance_of(ALICE, U256::ZERO), U256::from(2));
+
+        // Cooldowns are per flavor: Lemon is still available.
+        buy(&vm, &mut contract, ALICE, 1, 1);
+        vm.set_block_timestamp(1_000_000 + COOLDOWN - 1);
+        assert!(matches!(
+            contract.buy(U256::from(1), U256::from(1)),
+            Err(Erc1155Error::CooldownActive(CooldownActive { remaining, .. }))
+                if remaining == U256::from(1)
+        ));
+        vm.set_block_timestamp(1_000_000 + COOLDOWN);
+        assert!(matches!(
+            contract.buy(U256::from(1), U256::from(10)),
+            Err(Erc1155Error::SoldOut(SoldOut { remaining, .. })) if remaining == U256::from(9)
+        ));
+        buy(&vm, &mut contract, ALICE, 1, 9);
+        assert_eq!(contract.flavor(U256::from(1)).unwrap().3, U256::from(10));
+
+        vm.set_sender(OWNER);
+        vm.set_balance(vm.contract_address(), U256::from(2 * PRICE));
+        assert_eq!(contract.withdraw().unwrap(), U256::from(2 * PRICE));
+        assert_eq!(vm.balance(OWNER), U256::from(2 * PRICE));
+    }
+
+    #[test]
+    fn test_balance_of_batch() {
+        let (vm, mut contract) = setup();
+        buy(&vm, &mut contract, ALICE, 0, 2);
+        buy(&vm, &mut contract, BOB, 1, 5);
+        let ids = vec![U256::ZERO, U256::from(1), U256::from(1)];
+        assert_eq!(
+            contract
+                .balance_of_batch(vec![ALICE, BOB, ALICE], ids)
+                .unwrap(),
+            vec![U256::from(2), U256::from(5), U256::ZERO]
+        );
+        assert!(matches!(
+            contract.balance_of_batch(vec![ALICE], vec![]),
+            Err(Erc1155Error::InvalidArrayLength(_))
+        ));
+    }
+
+    #[test]
+    fn test_transfers_need_owner_or_operator() {
+        let (vm, mut contract) = setup();
+        buy(&vm, &mut contract, ALICE, 0, 3);
+        buy(&vm, &mut contract, ALICE, 1, 4);
+
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.safe_transfer_from(ALICE, BOB, U256::ZERO, U256::from(1), none()),
+            Err(Erc1155Error::MissingApprovalForAll(_))
+        ));
+        vm.set_sender(ALICE);
+        contract.set_approval_for_all(BOB, true).unwrap();
+        vm.set_sender(BOB);
+        contract
+            .safe_batch_transfer_from(
+                ALICE,
+                BOB,
+                vec![U256::ZERO, U256::from(1)],
+                vec![U256::from(1), U256::from(4)],
+                none(),
+            )
+            .unwrap();
+        assert_eq!(contract.balance_of(ALICE, U256::ZERO), U256::from(2));
+        assert_eq!(contract.balance_of(BOB, U256::from(1)), U256::from(4));
+
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = logs.last().unwrap();
+        let event = TransferBatch::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!((event.operator, event.from, event.to), (BOB, ALICE, BOB));
+        assert_eq!(event.values, vec![U256::from(1), U256::from(4)]);
+
+        assert!(matches!(
+            contract.safe_transfer_from(ALICE, BOB, U256::ZERO, U256::from(3), none()),
+            Err(Erc1155Error::InsufficientBalance(_))
+        ));
+        assert!(matches!(
+            contract.safe_transfer_from(ALICE, Address::ZERO, U256::ZERO, U256::from(1), none()),
+            Err(Erc1155Error::InvalidReceiver(_))
+        ));
+    }
+
+    #[test]
+    fn test_contract_receivers_must_accept() {
+        let (vm, mut contract) = setup();
+        buy(&vm, &mut contract, ALICE, 0, 2);
+        let shop = Address::new([0x0c; 20]);
+        vm.set_code(shop, vec![0x00]);
+
+        let call = IERC1155Receiver::onERC1155ReceivedCall {
+            operator: ALICE,
+            from: ALICE,
+            id: U256::ZERO,
+            value: U256::from(1),
+            data: b"stock".to_vec().into(),
+        };
+        let accept = IERC1155Receiver::onERC1155ReceivedCall::abi_encode_returns(&(FixedBytes::new(
+            IERC1155Receiver::onERC1155ReceivedCall::SELECTOR,
+        ),));
+        vm.mock_call(shop, call.abi_encode(), Ok(accept));
+        vm.set_sender(ALICE);
+        contract
+            .safe_transfer_from(ALICE, shop, U256::ZERO, U256::from(1), b"stock".to_vec().into())
+            .unwrap();
+        assert_eq!(contract.balance_of(shop, U256::ZERO), U256::from(1));
+
+        // The batch callback is not mocked, so the shop refuses batches.
+        let result = contract.safe_batch_transfer_from(
+            ALICE,
+            shop,
+            vec![U256::ZERO, U256::ZERO],
+            vec![U256::ZERO, U256::from(1)],
+            b"stock".to_vec().into(),
+        );
+        assert!(matches!(
+            result,
+            Err(Erc1155Error::InvalidReceiver(ERC1155InvalidReceiver { receiver })) if receiver == shop
+        ));
+    }
+
+    #[test]
+    fn test_supports_erc165_erc1155_and_metadata_uri() {
+        let (_vm, contract) = setup();
+        for id in [ERC165_ID, ERC1155_ID, ERC1155_METADATA_URI_ID] {
+            assert!(contract.supports_interface(id));
+        }
+        assert!(!contract.supports_interface(FixedBytes::new([0xff; 4])));
+    }
+}
@@ -0,0 +1,366 @@
+//!
+//! Stylus Savings Vault
+//!
+//! A self-custody vault that slows down withdrawals. Anyone can `deposit` ETH
+//! at any time, but each account withdraws at most `daily_limit` wei per 24
+//! hours. If the account's key is stolen, the thief can only drain one day's
+//! allowance while the owner notices and reacts, the same idea as the daily
+//! limit on a bank card.
+//!
+//! The limit is the shared `RateLimiter` component from `contracts/common`,
+//! embedded in every account's storage. A window opens on the first
+//! withdrawal and lasts `DAY_SECONDS`; once it ends, the next withdrawal opens
+//! a fresh one with the full allowance.
+//!
+//! Savings that must leave faster take the emergency exit instead:
+//! `request_exit()` starts a timelock of `exit_delay` seconds, after which
+//! `exit()` withdraws the whole balance regardless of the daily limit. The
+//! request is public on-chain as an `ExitRequested` event, so a watcher or the
+//! rightful owner has the whole delay to notice, and `cancel_exit()` stops it.
+//!
+//! All payouts are pull payments: funds only ever move to the account that
+//! asks for them, and balances are updated before any ETH is sent.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+extern crate alloc;
+
+use alloy_sol_types::sol;
+use common::{RateLimitExceeded, RateLimiter};
+use stylus_sdk::{
+    alloy_primitives::{Address, U256, U64},
+    prelude::*,
+};
+
+/// Length of a withdrawal window.
+pub const DAY_SECONDS: u64 = 86_400;
+
+sol! {
+    event Deposited(address indexed account, uint256 amount);
+    event Withdrawn(address indexed account, uint256 amount);
+    event ExitRequested(address indexed account, uint64 unlocksAt);
+    event ExitCancelled(address indexed account);
+    event Exited(address indexed account, uint256 amount);
+
+    #[derive(Debug)]
+    error InvalidConfig();
+    #[derive(Debug)]
+    error InvalidAmount();
+    #[derive(Debug)]
+    error InsufficientBalance(uint256 balance, uint256 requested);
+    #[derive(Debug)]
+    error ExitAlreadyRequested(uint64 unlocksAt);
+    #[derive(Debug)]
+    error ExitNotRequested();
+    #[derive(Debug)]
+    error ExitLocked(uint64 unlocksAt);
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum SavingsVaultError {
+    InvalidConfig(InvalidConfig),
+    InvalidAmount(InvalidAmount),
+    InsufficientBalance(InsufficientBalance),
+    RateLimitExceeded(RateLimitExceeded),
+    ExitAlreadyRequested(ExitAlreadyRequested),
+    ExitNotRequested(ExitNotRequested),
+    ExitLocked(ExitLocked),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    pub struct Account {
+        uint256 balance;
+        /// Daily withdrawal allowance, configured on the first deposit.
+        RateLimiter limiter;
+        /// When a requested emergency exit unlocks; zero if none is pending.
+        uint64 exit_unlocks_at;
+    }
+
+    #[entrypoint]
+    pub struct SavingsVault {
+        /// Most wei an account may withdraw per `DAY_SECONDS`.
+        uint256 daily_limit;
+        /// Seconds between `request_exit` and `exit`.
+        uint64 exit_delay;
+        mapping(address => Account) accounts;
+    }
+}
+
+#[public]
+impl SavingsVault {
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        daily_limit: U256,
+        exit_delay: u64,
+    ) -> Result<(), SavingsVaultError> {
+        if daily_limit.is_zero() || exit_delay == 0 {
+            return Err(SavingsVaultError::InvalidConfig(InvalidConfig {}));
+        }
+        self.daily_limit.set(daily_limit);
+        self.exit_delay.set(U64::from(exit_delay));
+        Ok(())
+    }
+
+    /// Adds `msg.value` to the caller's savings.
+    #[payable]
+    pub fn deposit(&mut self) -> Result<(), SavingsVaultError> {
+        let amount = self.vm().msg_value();
+        if amount.is_zero() {
+            return Err(SavingsVaultError::InvalidAmount(InvalidAmount {}));
+        }
+        let account = self.vm().msg_sender();
+        let daily_limit = self.daily_limit.get();
+        let mut record = self.accounts.setter(account);
+        if record.limiter.window_seconds() == 0 {
+            record.limiter.configure(daily_limit, DAY_SECONDS);
+        }
+        let balance = record.balance.get() + amount;
+        record.balance.set(balance);
+        log(self.vm(), Deposited { account, amount });
+        Ok(())
+    }
+
+    /// Sends `amount` of the caller's savings back to them, within today's allowance.
+    pub fn withdraw(&mut self, amount: U256) -> Result<(), SavingsVaultError> {
+        if amount.is_zero() {
+            return Err(SavingsVaultError::InvalidAmount(InvalidAmount {}));
+        }
+        let account = self.vm().msg_sender();
+        let mut record = self.accounts.setter(account);
+        let balance = record.balance.get();
+        if amount > balance {
+            return Err(SavingsVaultError::InsufficientBalance(
+                InsufficientBalance {
+                    balance,
+                    requested: amount,
+                },
+            ));
+        }
+        record
+            .limiter
+            .consume(amount)
+            .map_err(SavingsVaultError::RateLimitExceeded)?;
+        record.balance.set(balance - amount);
+        log(self.vm(), Withdrawn { account, amount });
+        self.send(account, amount)
+    }
+
+    /// Starts the timelock for withdrawing the caller's whole balance, returning when it
+    /// unlocks.
+    pub fn request_exit(&mut self) -> Result<u64, SavingsVaultError> {
+        let account = self.vm().msg_sender();
+        let pending = self.exit_unlocks_at(account);
+        if pending != 0 {
+            return Err(SavingsVaultError::ExitAlreadyRequested(
+                ExitAlreadyRequested { unlocksAt: pending },
+            ));
+        }
+        let delay = self.exit_delay.get().to::<u64>();
+        let unlocks_at = self.vm().block_timestamp() + delay;
+        self.accounts
+            .setter(account)
+            .exit_unlocks_at
+            .set(U64::from(unlocks_at));
+        log(
+            self.vm(),
+            ExitRequested {
+                account,
+                unlocksAt: unlocks_at,
+            },
+        );
+        Ok(unlocks_at)
+    }
+
+    pub fn cancel_exit(&mut self) -> Result<(), SavingsVaultError> {
+        let account = self.vm().msg_sender();
+        self.pending_exit(account)?;
+        self.accounts
+            .setter(account)
+            .exit_unlocks_at
+            .set(U64::ZERO);
+        log(self.vm(), ExitCancelled { account });
+        Ok(())
+    }
+
+    /// Withdraws the caller's whole balance once their exit timelock has passed, ignoring the
+    /// daily limit.
+    pub fn exit(&mut self) -> Result<U256, SavingsVaultError> {
+        let account = self.vm().msg_sender();
+        let unlocks_at = self.pending_exit(account)?;
+        if self.vm().block_timestamp() < unlocks_at {
+            return Err(SavingsVaultError::ExitLocked(ExitLocked {
+                unlocksAt: unlocks_at,
+            }));
+        }
+        let mut record = self.accounts.setter(account);
+        let amount = record.balance.get();
+        record.balance.set(U256::ZERO);
+        record.exit_unlocks_at.set(U64::ZERO);
+        log(self.vm(), Exited { account, amount });
+        self.send(account, amount)?;
+        Ok(amount)
+    }
+
+    pub fn balance_of(&self, account: Address) -> U256 {
+        self.accounts.getter(account).balance.get()
+    }
+
+    /// Wei `account` could withdraw right now: the rest of today's allowance, capped by
+    /// their balance.
+    pub fn available_to_withdraw(&self, account: Address) -> U256 {
+        let record = self.accounts.getter(account);
+        record.limiter.available().min(record.balance.get())
+    }
+
+    /// When `account`'s full daily allowance is available again; zero if it already is.
+    pub fn allowance_resets_at(&self, account: Address) -> u64 {
+        self.accounts.getter(account).limiter.resets_at()
+    }
+
+    /// When `account`'s emergency exit unlocks; zero if none is pending.
+    pub fn exit_unlocks_at(&self, account: Address) -> u64 {
+        self.accounts.getter(account).exit_unlocks_at.get().to()
+    }
+
+    pub fn daily_limit(&self) -> U256 {
+        self.daily_limit.get()
+    }
+
+    pub fn exit_delay(&self) -> u64 {
+        self.exit_delay.get().to()
+    }
+}
+
+impl SavingsVault {
+    /// Unlock time of `account`'s pending exit, or `ExitNotRequested`.
+    fn pending_exit(&self, account: Address) -> Result<u64, SavingsVaultError> {
+        let unlocks_at = self.exit_unlocks_at(account);
+        if unlocks_at == 0 {
+            return Err(SavingsVaultError::ExitNotRequested(ExitNotRequested {}));
+        }
+        Ok(unlocks_at)
+    }
+
+    fn send(&mut self, to: Address, amount: U256) -> Result<(), SavingsVaultError> {
+        self.vm()
+            .transfer_eth(to, amount)
+            .map_err(|_| SavingsVaultError::TransferFailed(TransferFailed { to, amount }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const ALICE: Address = Address::new([0x11; 20]);
+    const BOB: Address = Address::new([0x22; 20]);
+    const LIMIT: u64 = 1_000;
+    const EXIT_DELAY: u64 = 3 * DAY_SECONDS;
+    const START: u64 = 1_700_000_000;
+
+    /// Alice has 5,000 wei saved.
+    fn setup() -> (TestVM, SavingsVault) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(START);
+        let mut contract = SavingsVault::from(&vm);
+        contract
+            .constructor(U256::from(LIMIT), EXIT_DELAY)
+            .unwrap();
+        vm.set_sender(ALICE);
+        vm.set_value(U256::from(5_000));
+        contract.deposit().unwrap();
+        vm.set_value(U256::ZERO);
+        vm.set_balance(vm.contract_address(), U256::from(5_000));
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_withdrawals_limited_per_day() {
+        let (vm, mut contract) = setup();
+        assert_eq!(contract.available_to_withdraw(ALICE), U256::from(LIMIT));
+        contract.withdraw(U256::from(600)).unwrap();
+        contract.withdraw(U256::from(400)).unwrap();
+        assert_eq!(vm.balance(ALICE), U256::from(LIMIT));
+        assert_eq!(contract.available_to_withdraw(ALICE), U256::ZERO);
+        assert_eq!(contract.allowance_resets_at(ALICE), START + DAY_SECONDS);
+
+        vm.set_block_timestamp(START + DAY_SECONDS - 1);
+        assert!(matches!(
+            contract.withdraw(U256::from(1)),
+            Err(SavingsVaultError::RateLimitExceeded(RateLimitExceeded { resetsAt, .. }))
+                if resetsAt == START + DAY_SECONDS
+        ));
+        vm.set_block_timestamp(START + DAY_SECONDS);
+        contract.withdraw(U256::from(LIMIT)).unwrap();
+        assert_eq!(contract.balance_of(ALICE), U256::from(3_000));
+    }
+
+    #[test]
+    fn test_withdraw_rejects_bad_amounts() {
+        let (vm, mut contract) = setup();
+        assert!(matches!(
+            contract.withdraw(U256::ZERO),
+            Err(SavingsVaultError::InvalidAmount(_))
+        ));
+        assert!(matches!(
+            contract.withdraw(U256::from(LIMIT + 1)),
+            Err(SavingsVaultError::RateLimitExceeded(_))
+        ));
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.withdraw(U256::from(1)),
+            Err(SavingsVaultError::InsufficientBalance(_))
+        ));
+        assert_eq!(contract.available_to_withdraw(BOB), U256::ZERO);
+    }
+
+    #[test]
+    fn test_emergency_exit_after_timelock() {
+        let (vm, mut contract) = setup();
+        assert!(matches!(
+            contract.exit(),
+            Err(SavingsVaultError::ExitNotRequested(_))
+        ));
+        let unlocks_at = contract.request_exit().unwrap();
+        assert_eq!(unlocks_at, START + EXIT_DELAY);
+        assert!(matches!(
+            contract.request_exit(),
+            Err(SavingsVaultError::ExitAlreadyRequested(_))
+        ));
+
+        vm.set_block_timestamp(unlocks_at - 1);
+        assert!(matches!(
+            contract.exit(),
+            Err(SavingsVaultError::ExitLocked(_))
+        ));
+        vm.set_block_timestamp(unlocks_at);
+        assert_eq!(contract.exit().unwrap(), U256::from(5_000));
+        assert_eq!(vm.balance(ALICE), U256::from(5_000));
+        assert_eq!(contract.balance_of(ALICE), U256::ZERO);
+        assert_eq!(contract.exit_unlocks_at(ALICE), 0);
+    }
+
+    #[test]
+    fn test_cancelled_exit_does_not_unlock() {
+        let (vm, mut contract) = setup();
+        contract.request_exit().unwrap();
+        contract.cancel_exit().unwrap();
+        vm.set_block_timestamp(START + EXIT_DELAY);
+        assert!(matches!(
+            contract.exit(),
+            Err(SavingsVaultError::ExitNotRequested(_))
+        ));
+        assert!(matches!(
+            contract.cancel_exit(),
+            Err(SavingsVaultError::ExitNotRequested(_))
+        ));
+    }
+}
@@ -0,0 +1,363 @@
+//!
+//! Stylus Vend Raffle
+//!
+//! A weekly raffle for vending machine customers. The machine
+//! (`contracts/2-vending-machine`, once its owner calls `set_raffle`) calls
+//! `enter(user)` for every free vend, so each cupcake is one ticket in the
+//! current week's draw. Only the machine fixed at deployment may enter users.
+//!
+//! Time is cut into epochs of `EPOCH_SECONDS` (one week) counted from
+//! deployment. Once an epoch has ended, anyone can call `draw_winner()`,
+//! which draws the oldest epoch not yet drawn and pays the winner the whole
+//! pot: every wei the raffle holds that is not owed to an earlier winner. The
+//! pot is funded by plain ETH transfers, from the machine's owner or anyone
+//! else. An epoch without entrants is drawn with no winner and its pot simply
+//! carries over. A winner whose address rejects the payment keeps the prize
+//! as a credit and pulls it later with `claim()`.
+//!
+//! Randomness: the winning index is `keccak256(seed ++ epoch ++ block number
+//! ++ timestamp) % entries`, where `seed` is a stored hash that every entry
+//! mixes its user and timestamp into. That is NOT secure randomness. Every
+//! input is public, so a block producer can choose the timestamp or withhold
+//! the draw, the caller of `draw_winner()` can pick the block they draw in,
+//! and the last entrant can compute the outcome before deciding to enter. It
+//! is fine for prizes worth less than manipulating a block; anything larger
+//! needs a VRF oracle or a commit-reveal scheme (see `22-commit-reveal-voting`).
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, B256, U256, U64},
+    prelude::*,
+};
+
+/// Length of one raffle epoch.
+pub const EPOCH_SECONDS: u64 = 7 * 86_400;
+
+/// `(winner, prize, entries, drawn)`, as returned by `draw_result`.
+pub type DrawResult = (Address, U256, U256, bool);
+
+sol! {
+    event Entered(uint256 indexed epoch, address indexed user);
+    event PotFunded(address indexed from, uint256 amount);
+    event WinnerDrawn(uint256 indexed epoch, address indexed winner, uint256 prize, uint256 entries);
+    event PrizeCredited(address indexed winner, uint256 amount);
+    event PrizeClaimed(address indexed winner, uint256 amount);
+
+    #[derive(Debug)]
+    error NotMachine(address caller);
+    #[derive(Debug)]
+    error EpochNotOver(uint256 epoch, uint64 endsAt);
+    #[derive(Debug)]
+    error NothingToClaim();
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum VendRaffleError {
+    NotMachine(NotMachine),
+    EpochNotOver(EpochNotOver),
+    NothingToClaim(NothingToClaim),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    pub struct Epoch {
+        /// One entry per vend, holding the user.
+        address[] entrants;
+        address winner;
+        uint256 prize;
+        bool drawn;
+    }
+
+    #[entrypoint]
+    pub struct VendRaffle {
+        /// Vending machine allowed to call `enter`.
+        address machine;
+        /// Start of epoch 0.
+        uint64 start_time;
+        mapping(uint256 => Epoch) epochs;
+        /// Oldest epoch `draw_winner` has not drawn yet.
+        uint256 next_draw;
+        /// Running hash of every entry, mixed into each draw.
+        bytes32 seed;
+        /// Prizes whose payout failed, waiting for `claim`.
+        mapping(address => uint256) unclaimed;
+        /// Sum of `unclaimed`; never part of the pot.
+        uint256 owed;
+    }
+}
+
+#[public]
+impl VendRaffle {
+    #[constructor]
+    pub fn constructor(&mut self, machine: Address) {
+        self.machine.set(machine);
+        let now = self.vm().block_timestamp();
+        self.start_time.set(U64::from(now));
+    }
+
+    /// Adds raffle funds sent as a plain ETH transfer to the pot.
+    #[receive]
+    #[payable]
+    pub fn receive(&mut self) -> Result<(), Vec<u8>> {
+        log(
+            self.vm(),
+            PotFunded {
+                from: self.vm().msg_sender(),
+                amount: self.vm().msg_value(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Gives `user` one ticket in the current epoch. Only the vending machine may call this.
+    pub fn enter(&mut self, user: Address) -> Result<(), VendRaffleError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.machine.get() {
+            return Err(VendRaffleError::NotMachine(NotMachine { caller }));
+        }
+        let epoch = self.current_epoch();
+        self.epochs.setter(epoch).entrants.push(user);
+
+        let mut preimage = Vec::with_capacity(60);
+        preimage.extend_from_slice(self.seed.get().as_slice());
+        preimage.extend_from_slice(user.as_slice());
+        preimage.extend_from_slice(&self.vm().block_timestamp().to_be_bytes());
+        let seed = self.vm().native_keccak256(&preimage);
+        self.seed.set(seed);
+        log(self.vm(), Entered { epoch, user });
+        Ok(())
+    }
+
+    /// Draws the oldest finished epoch not yet drawn and pays its winner the pot, returning
+    /// the winner, or zero if the epoch had no entrants. Anyone may call this.
+    pub fn draw_winner(&mut self) -> Result<Address, VendRaffleError> {
+        let epoch = self.next_draw.get();
+        let ends_at = self.epoch_end(epoch);
+        if self.vm().block_timestamp() < ends_at {
+            return Err(VendRaffleError::EpochNotOver(EpochNotOver {
+                epoch,
+                endsAt: ends_at,
+            }));
+        }
+        let entries = self.epochs.getter(epoch).entrants.len();
+        let (winner, prize) = if entries == 0 {
+            (Address::ZERO, U256::ZERO)
+        } else {
+            let seed = self.draw_seed(epoch);
+            let index = (U256::from_be_bytes(seed.0) % U256::from(entries)).to::<usize>();
+            let winner = self
+                .epochs
+                .getter(epoch)
+                .entrants
+                .get(index)
+                .expect("index is below len");
+            (winner, self.pot())
+        };
+
+        // Record the draw before any ETH moves.
+        self.next_draw.set(epoch + U256::from(1));
+        let mut record = self.epochs.setter(epoch);
+        record.drawn.set(true);
+        record.winner.set(winner);
+        record.prize.set(prize);
+        log(
+            self.vm(),
+            WinnerDrawn {
+                epoch,
+                winner,
+                prize,
+                entries: U256::from(entries),
+            },
+        );
+        if !prize.is_zero() && self.vm().transfer_eth(winner, prize).is_err() {
+            let credit = self.unclaimed.get(winner) + prize;
+            self.unclaimed.insert(winner, credit);
+            self.owed.set(self.owed.get() + prize);
+            log(
+                self.vm(),
+                PrizeCredited {
+                    winner,
+                    amount: prize,
+                },
+            );
+        }
+        Ok(winner)
+    }
+
+    /// Pays out the caller's prizes that could not be sent when they were drawn.
+    pub fn claim(&mut self) -> Result<U256, VendRaffleError> {
+        let caller = self.vm().msg_sender();
+        let amount = self.unclaimed.get(caller);
+        if amount.is_zero() {
+            return Err(VendRaffleError::NothingToClaim(NothingToClaim {}));
+        }
+        self.unclaimed.insert(caller, U256::ZERO);
+        self.owed.set(self.owed.get() - amount);
+        log(
+            self.vm(),
+            PrizeClaimed {
+                winner: caller,
+                amount,
+            },
+        );
+        self.vm()
+            .transfer_eth(caller, amount)
+            .map_err(|_| VendRaffleError::TransferFailed(TransferFailed { to: caller, amount }))?;
+        Ok(amount)
+    }
+
+    pub fn current_epoch(&self) -> U256 {
+        let elapsed = self.vm().block_timestamp() - self.start_time.get().to::<u64>();
+        U256::from(elapsed / EPOCH_SECONDS)
+    }
+
+    /// Timestamp at which `epoch` ends and can be drawn.
+    pub fn epoch_end(&self, epoch: U256) -> u64 {
+        let start = self.start_time.get().to::<u64>();
+        let epochs = epoch.saturating_add(U256::from(1)).saturating_to::<u64>();
+        start.saturating_add(epochs.saturating_mul(EPOCH_SECONDS))
+    }
+
+    /// Oldest epoch waiting for `draw_winner`.
+    pub fn next_draw(&self) -> U256 {
+        self.next_draw.get()
+    }
+
+    /// Tickets in `epoch` so far.
+    pub fn entries(&self, epoch: U256) -> U256 {
+        U256::from(self.epochs.getter(epoch).entrants.len())
+    }
+
+    pub fn draw_result(&self, epoch: U256) -> DrawResult {
+        let record = self.epochs.getter(epoch);
+        (
+            record.winner.get(),
+            record.prize.get(),
+            U256::from(record.entrants.len()),
+            record.drawn.get(),
+        )
+    }
+
+    /// What the next winner would receive: the balance not owed to earlier winners.
+    pub fn pot(&self) -> U256 {
+        let balance = self.vm().balance(self.vm().contract_address());
+        balance.saturating_sub(self.owed.get())
+    }
+
+    pub fn unclaimed_of(&self, winner: Address) -> U256 {
+        self.unclaimed.get(winner)
+    }
+
+    pub fn machine(&self) -> Address {
+        self.machine.get()
+    }
+}
+
+impl VendRaffle {
+    /// `keccak256(seed ++ epoch ++ block number ++ timestamp)`; see the module docs for why
+    /// this is only pseudo-random.
+    fn draw_seed(&self, epoch: U256) -> B256 {
+        let mut preimage = Vec::with_capacity(80);
+        preimage.extend_from_slice(self.seed.get().as_slice());
+        preimage.extend_from_slice(&epoch.to_be_bytes::<32>());
+        preimage.extend_from_slice(&self.vm().block_number().to_be_bytes());
+        preimage.extend_from_slice(&self.vm().block_timestamp().to_be_bytes());
+        self.vm().native_keccak256(&preimage)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const MACHINE: Address = Address::new([0xee; 20]);
+    const ALICE: Address = Address::new([0x11; 20]);
+    const BOB: Address = Address::new([0x22; 20]);
+    const START: u64 = 1_700_000_000;
+
+    fn setup() -> (TestVM, VendRaffle) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(START);
+        let mut contract = VendRaffle::from(&vm);
+        contract.constructor(MACHINE);
+        vm.set_sender(MACHINE);
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_entries_are_grouped_by_week() {
+        let (vm, mut contract) = setup();
+        contract.enter(ALICE).unwrap();
+        contract.enter(BOB).unwrap();
+        vm.set_block_timestamp(START + EPOCH_SECONDS - 1);
+        contract.enter(ALICE).unwrap();
+        assert_eq!(contract.entries(U256::ZERO), U256::from(3));
+
+        vm.set_block_timestamp(START + EPOCH_SECONDS);
+        assert_eq!(contract.current_epoch(), U256::from(1));
+        contract.enter(BOB).unwrap();
+        assert_eq!(contract.entries(U256::from(1)), U256::from(1));
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.enter(ALICE),
+            Err(VendRaffleError::NotMachine(_))
+        ));
+    }
+
+    #[test]
+    fn test_draw_pays_an_entrant_the_pot() {
+        let (vm, mut contract) = setup();
+        contract.enter(ALICE).unwrap();
+        contract.enter(BOB).unwrap();
+        vm.set_balance(vm.contract_address(), U256::from(900));
+        assert!(matches!(
+            contract.draw_winner(),
+            Err(VendRaffleError::EpochNotOver(EpochNotOver { endsAt, .. }))
+                if endsAt == START + EPOCH_SECONDS
+        ));
+
+        vm.set_block_timestamp(START + EPOCH_SECONDS);
+        let winner = contract.draw_winner().unwrap();
+        assert!(winner == ALICE || winner == BOB);
+        assert_eq!(vm.balance(winner), U256::from(900));
+        assert_eq!(
+            contract.draw_result(U256::ZERO),
+            (winner, U256::from(900), U256::from(2), true)
+        );
+        assert_eq!(contract.next_draw(), U256::from(1));
+        // Epoch 1 is still running.
+        assert!(contract.draw_winner().is_err());
+        // Payouts that go through leave nothing to claim.
+        vm.set_sender(winner);
+        assert!(matches!(
+            contract.claim(),
+            Err(VendRaffleError::NothingToClaim(_))
+        ));
+    }
+
+    #[test]
+    fn test_empty_epoch_carries_the_pot_over() {
+        let (vm, mut contract) = setup();
+        vm.set_balance(vm.contract_address(), U256::from(500));
+        vm.set_block_timestamp(START + EPOCH_SECONDS);
+        contract.enter(BOB).unwrap();
+
+        assert_eq!(contract.draw_winner().unwrap(), Address::ZERO);
+        assert_eq!(contract.pot(), U256::from(500));
+        vm.set_block_timestamp(START + 2 * EPOCH_SECONDS);
+        assert_eq!(contract.draw_winner().unwrap(), BOB);
+        assert_eq!(vm.balance(BOB), U256::from(500));
+    }
+}
@@ -0,0 +1,222 @@
+//!
+//! Stylus Achievement Badges
+//!
+//! Soulbound badges for vending machine milestones. A customer whose lifetime
+//! cupcake count reaches 10, 50 or 100 earns the matching badge, minted by a
+//! cross-contract call from `contracts/2-vending-machine` once its owner
+//! points `set_badges` at this contract. Only the machine fixed at deployment
+//! may award badges, and `badges_of(user)` lists the milestones a user has
+//! reached.
+//!
+//! Badges are numbered tokens with an owner, like the `attendance-badges`
+//! example, and just as non-transferable: there is no transfer function and
+//! `locked` is always true, as in ERC-5192. A badge records the achievement
+//! of one address, so it cannot be sold or moved to another.
+//!
+//! `award` is idempotent: awarding a badge the user already holds returns the
+//! existing token id without minting. The machine can therefore retry a vend
+//! whose award went through but which failed later on.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256, U64},
+    prelude::*,
+};
+
+/// Lifetime cupcake counts that earn a badge, in ascending order.
+pub const MILESTONES: [u64; 3] = [10, 50, 100];
+
+sol! {
+    event BadgeAwarded(address indexed user, uint64 indexed milestone, uint256 indexed tokenId);
+    /// ERC-5192: emitted once per badge, since every badge is locked from the start.
+    event Locked(uint256 tokenId);
+
+    #[derive(Debug)]
+    error NotMachine(address caller);
+    #[derive(Debug)]
+    error UnknownMilestone(uint64 milestone);
+    #[derive(Debug)]
+    error UnknownBadge(uint256 tokenId);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum AchievementBadgesError {
+    NotMachine(NotMachine),
+    UnknownMilestone(UnknownMilestone),
+    UnknownBadge(UnknownBadge),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct AchievementBadges {
+        /// Vending machine allowed to call `award`.
+        address machine;
+        /// Badges minted; token ids run from 1 to `badge_count`.
+        uint256 badge_count;
+        mapping(uint256 => address) owners;
+        mapping(uint256 => uint64) milestone_of;
+        /// Token id of each user's badge per milestone; zero if not earned.
+        mapping(address => mapping(uint64 => uint256)) badge_ids;
+        /// Milestones each user has reached, in the order they were awarded.
+        mapping(address => uint64[]) earned;
+    }
+}
+
+#[public]
+impl AchievementBadges {
+    #[constructor]
+    pub fn constructor(&mut self, machine: Address) {
+        self.machine.set(machine);
+    }
+
+    /// Mints `user` the badge for `milestone`, or returns the one they already hold. Only
+    /// the vending machine may call this.
+    pub fn award(&mut self, user: Address, milestone: u64) -> Result<U256, AchievementBadgesError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.machine.get() {
+            return Err(AchievementBadgesError::NotMachine(NotMachine { caller }));
+        }
+        if !MILESTONES.contains(&milestone) {
+            return Err(AchievementBadgesError::UnknownMilestone(UnknownMilestone {
+                milestone,
+            }));
+        }
+        let existing = self.badge_of(user, milestone);
+        if !existing.is_zero() {
+            return Ok(existing);
+        }
+
+        let token_id = self.badge_count.get() + U256::from(1);
+        self.badge_count.set(token_id);
+        self.owners.insert(token_id, user);
+        self.milestone_of.insert(token_id, U64::from(milestone));
+        self.badge_ids
+            .setter(user)
+            .insert(U64::from(milestone), token_id);
+        self.earned.setter(user).push(U64::from(milestone));
+        log(
+            self.vm(),
+            BadgeAwarded {
+                user,
+                milestone,
+                tokenId: token_id,
+            },
+        );
+        log(self.vm(), Locked { tokenId: token_id });
+        Ok(token_id)
+    }
+
+    /// Milestones `user` has reached, in the order the badges were awarded.
+    pub fn badges_of(&self, user: Address) -> Vec<u64> {
+        let earned = self.earned.getter(user);
+        (0..earned.len())
+            .filter_map(|index| earned.get(index))
+            .map(|milestone| milestone.to())
+            .collect()
+    }
+
+    /// The token id of `user`'s badge for `milestone`; zero if they have not earned it.
+    pub fn badge_of(&self, user: Address, milestone: u64) -> U256 {
+        self.badge_ids.getter(user).get(U64::from(milestone))
+    }
+
+    pub fn owner_of(&self, token_id: U256) -> Result<Address, AchievementBadgesError> {
+        let owner = self.owners.get(token_id);
+        if owner.is_zero() {
+            return Err(AchievementBadgesError::UnknownBadge(UnknownBadge {
+                tokenId: token_id,
+            }));
+        }
+        Ok(owner)
+    }
+
+    /// The milestone a badge was awarded for.
+    pub fn milestone_of(&self, token_id: U256) -> Result<u64, AchievementBadgesError> {
+        self.owner_of(token_id)?;
+        Ok(self.milestone_of.get(token_id).to())
+    }
+
+    pub fn balance_of(&self, owner: Address) -> U256 {
+        U256::from(self.earned.getter(owner).len())
+    }
+
+    /// Always true: badges cannot be transferred (ERC-5192).
+    pub fn locked(&self, token_id: U256) -> Result<bool, AchievementBadgesError> {
+        self.owner_of(token_id)?;
+        Ok(true)
+    }
+
+    pub fn milestones(&self) -> Vec<u64> {
+        MILESTONES.to_vec()
+    }
+
+    pub fn machine(&self) -> Address {
+        self.machine.get()
+    }
+
+    pub fn total_supply(&self) -> U256 {
+        self.badge_count.get()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const MACHINE: Address = Address::new([0xee; 20]);
+    const ALICE: Address = Address::new([0x11; 20]);
+    const BOB: Address = Address::new([0x22; 20]);
+
+    fn setup() -> (TestVM, AchievementBadges) {
+        let vm = TestVM::default();
+        let mut contract = AchievementBadges::from(&vm);
+        contract.constructor(MACHINE);
+        vm.set_sender(MACHINE);
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_machine_awards_milestone_badges() {
+        let (_vm, mut contract) = setup();
+        assert_eq!(contract.award(ALICE, 10).unwrap(), U256::from(1));
+        assert_eq!(contract.award(BOB, 10).unwrap(), U256::from(2));
+        assert_eq!(contract.award(ALICE, 50).unwrap(), U256::from(3));
+
+        assert_eq!(contract.badges_of(ALICE), vec![10, 50]);
+        assert_eq!(contract.badges_of(BOB), vec![10]);
+        assert_eq!(contract.badge_of(ALICE, 50), U256::from(3));
+        assert_eq!(contract.badge_of(ALICE, 100), U256::ZERO);
+        assert_eq!(contract.owner_of(U256::from(2)).unwrap(), BOB);
+        assert_eq!(contract.milestone_of(U256::from(3)).unwrap(), 50);
+        assert!(contract.locked(U256::from(1)).unwrap());
+        assert!(contract.locked(U256::from(4)).is_err());
+    }
+
+    #[test]
+    fn test_award_is_idempotent_and_machine_only() {
+        let (vm, mut contract) = setup();
+        let token_id = contract.award(ALICE, 100).unwrap();
+        assert_eq!(contract.award(ALICE, 100).unwrap(), token_id);
+        assert_eq!(contract.total_supply(), U256::from(1));
+        assert_eq!(contract.balance_of(ALICE), U256::from(1));
+        assert!(matches!(
+            contract.award(ALICE, 25),
+            Err(AchievementBadgesError::UnknownMilestone(_))
+        ));
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.award(ALICE, 10),
+            Err(AchievementBadgesError::NotMachine(_))
+        ));
+    }
+}
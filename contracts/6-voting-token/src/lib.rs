@@ -0,0 +1,349 @@
+//!
+//! Stylus Voting Token
+//!
+//! A token whose balances are checkpointed per block, so governance can ask
+//! what an account's voting power was at any past block:
+//!
+//! ```solidity
+//! function getPastVotes(address account, uint256 blockNumber) external view returns (uint256);
+//! function getPastTotalSupply(uint256 blockNumber) external view returns (uint256);
+//! ```
+//!
+//! Every balance change appends a `(block, votes)` checkpoint, or overwrites
+//! the last one when it is from the same block. Checkpoints are ordered by
+//! block, so a lookup is a binary search over the account's history. Each
+//! checkpoint packs a `uint64` block number and a `uint192` amount into a
+//! single storage slot, which caps the total supply at `2^192 - 1`.
+//!
+//! Voting power equals the holder's own balance; there is no delegation.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{aliases::U192, Address, U256, U64},
+    prelude::*,
+    storage::StorageVec,
+};
+
+sol! {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+}
+
+sol_storage! {
+    pub struct Checkpoint {
+        uint64 block_number;
+        uint192 votes;
+    }
+
+    #[entrypoint]
+    pub struct VotingToken {
+        address owner;
+        uint256 total_supply;
+        mapping(address => uint256) balances;
+        mapping(address => Checkpoint[]) account_checkpoints;
+        Checkpoint[] total_supply_checkpoints;
+    }
+}
+
+#[public]
+impl VotingToken {
+    #[constructor]
+    pub fn constructor(&mut self, owner: Address) {
+        self.owner.set(owner);
+    }
+
+    pub fn name(&self) -> String {
+        "Cupcake Votes".into()
+    }
+
+    pub fn symbol(&self) -> String {
+        "vCUPCAKE".into()
+    }
+
+    pub fn decimals(&self) -> u8 {
+        18
+    }
+
+    pub fn total_supply(&self) -> U256 {
+        self.total_supply.get()
+    }
+
+    pub fn balance_of(&self, account: Address) -> U256 {
+        self.balances.get(account)
+    }
+
+    pub fn transfer(&mut self, to: Address, value: U256) -> Result<bool, Vec<u8>> {
+        let from = self.vm().msg_sender();
+        if to.is_zero() {
+            return Err("Transfer to the zero address".into());
+        }
+        let from_balance = self.balances.get(from);
+        if from_balance < value {
+            return Err("Transfer amount exceeds balance".into());
+        }
+        self.set_balance(from, from_balance - value);
+        // Read after the debit so a self-transfer leaves the balance unchanged.
+        let to_balance = self.balances.get(to);
+        self.set_balance(to, to_balance + value);
+        log(self.vm(), Transfer { from, to, value });
+        Ok(true)
+    }
+
+    /// Mints new tokens. Only the owner may mint.
+    pub fn mint(&mut self, to: Address, value: U256) -> Result<(), Vec<u8>> {
+        if self.vm().msg_sender() != self.owner.get() {
+            return Err("Caller is not the owner".into());
+        }
+        if to.is_zero() {
+            return Err("Mint to the zero address".into());
+        }
+        let supply = self
+            .total_supply
+            .get()
+            .checked_add(value)
+            .filter(|supply| *supply <= U256::from(U192::MAX))
+            .ok_or("Total supply exceeds 2^192 - 1")?;
+        self.total_supply.set(supply);
+        let block = self.vm().block_number();
+        push_checkpoint(&mut self.total_supply_checkpoints, block, supply);
+
+        let to_balance = self.balances.get(to);
+        self.set_balance(to, to_balance + value);
+        log(
+            self.vm(),
+            Transfer {
+                from: Address::ZERO,
+                to,
+                value,
+            },
+        );
+        Ok(())
+    }
+
+    /// Current voting power of `account`.
+    pub fn get_votes(&self, account: Address) -> U256 {
+        self.balances.get(account)
+    }
+
+    /// Voting power of `account` at the end of `block_number`, which must be in the past.
+    pub fn get_past_votes(&self, account: Address, block_number: U256) -> Result<U256, Vec<u8>> {
+        let block = self.past_block(block_number)?;
+        Ok(lookup(&self.account_checkpoints.getter(account), block))
+    }
+
+    /// Total supply at the end of `block_number`, which must be in the past.
+    pub fn get_past_total_supply(&self, block_number: U256) -> Result<U256, Vec<u8>> {
+        let block = self.past_block(block_number)?;
+        Ok(lookup(&self.total_supply_checkpoints, block))
+    }
+
+    pub fn num_checkpoints(&self, account: Address) -> U256 {
+        U256::from(self.account_checkpoints.getter(account).len())
+    }
+
+    /// Returns `(block_number, votes)` of one of the account's checkpoints.
+    pub fn checkpoints(&self, account: Address, pos: U256) -> Result<(u64, U256), Vec<u8>> {
+        let checkpoints = self.account_checkpoints.getter(account);
+        let checkpoint = checkpoints
+            .getter(pos)
+            .ok_or("Checkpoint index out of bounds")?;
+        Ok((
+            checkpoint.block_number.get().to(),
+            U256::from(checkpoint.votes.get()),
+        ))
+    }
+}
+
+impl VotingToken {
+    fn set_balance(&mut self, account: Address, balance: U256) {
+        self.balances.insert(account, balance);
+        let block = self.vm().block_number();
+        push_checkpoint(
+            &mut self.account_checkpoints.setter(account),
+            block,
+            balance,
+        );
+    }
+
+    /// Rejects lookups at or after the current block, whose value may still change.
+    fn past_block(&self, block_number: U256) -> Result<u64, Vec<u8>> {
+        if block_number >= U256::from(self.vm().block_number()) {
+            return Err("Block not yet mined".into());
+        }
+        Ok(block_number.to())
+    }
+}
+
+/// Records `votes` as of `block`, overwriting the last checkpoint if it is from the same block.
+fn push_checkpoint(checkpoints: &mut StorageVec<Checkpoint>, block: u64, votes: U256) {
+    let votes = votes.to::<U192>();
+    if let Some(mut last) = checkpoints
+        .len()
+        .checked_sub(1)
+        .and_then(|index| checkpoints.setter(index))
+    {
+        if last.block_number.get().to::<u64>() == block {
+            last.votes.set(votes);
+            return;
+        }
+    }
+    let mut checkpoint = checkpoints.grow();
+    checkpoint.block_number.set(U64::from(block));
+    checkpoint.votes.set(votes);
+}
+
+/// Binary search for the last checkpoint at or before `block`; zero if there is none.
+fn lookup(checkpoints: &StorageVec<Checkpoint>, block: u64) -> U256 {
+    let (mut low, mut high) = (0, checkpoints.len());
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let checkpoint = checkpoints.getter(mid).expect("index is below len");
+        if checkpoint.block_number.get().to::<u64>() > block {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    match high
+        .checked_sub(1)
+        .and_then(|index| checkpoints.getter(index))
+    {
+        Some(checkpoint) => U256::from(checkpoint.votes.get()),
+        None => U256::ZERO,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::new([0x01; 20]);
+    const ALICE: Address = Address::new([0x02; 20]);
+    const BOB: Address = Address::new([0x03; 20]);
+
+    fn setup() -> (TestVM, VotingToken) {
+        let vm = TestVM::default();
+        vm.set_block_number(10);
+        let mut contract = VotingToken::from(&vm);
+        contract.constructor(OWNER);
+        vm.set_sender(OWNER);
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_past_votes_follow_balance_history() {
+        let (vm, mut contract) = setup();
+        contract.mint(ALICE, U256::from(100)).unwrap();
+
+        vm.set_block_number(20);
+        vm.set_sender(ALICE);
+        contract.transfer(BOB, U256::from(30)).unwrap();
+
+        vm.set_block_number(30);
+        let votes = |block: u64| contract.get_past_votes(ALICE, U256::from(block)).unwrap();
+        assert_eq!(votes(9), U256::ZERO);
+        assert_eq!(votes(10), U256::from(100));
+        assert_eq!(votes(19), U256::from(100));
+        assert_eq!(votes(20), U256::from(70));
+        assert_eq!(votes(29), U256::from(70));
+        assert_eq!(
+            contract.get_past_votes(BOB, U256::from(15)).unwrap(),
+            U256::ZERO
+        );
+        assert_eq!(
+            contract.get_past_votes(BOB, U256::from(25)).unwrap(),
+            U256::from(30)
+        );
+        assert_eq!(contract.get_votes(ALICE), U256::from(70));
+    }
+
+    #[test]
+    fn test_same_block_changes_share_a_checkpoint() {
+        let (vm, mut contract) = setup();
+        contract.mint(ALICE, U256::from(5)).unwrap();
+        contract.mint(ALICE, U256::from(5)).unwrap();
+        assert_eq!(contract.num_checkpoints(ALICE), U256::from(1));
+        assert_eq!(
+            contract.checkpoints(ALICE, U256::ZERO).unwrap(),
+            (10, U256::from(10))
+        );
+
+        vm.set_block_number(11);
+        contract.mint(ALICE, U256::from(5)).unwrap();
+        assert_eq!(contract.num_checkpoints(ALICE), U256::from(2));
+        assert!(contract.checkpoints(ALICE, U256::from(2)).is_err());
+    }
+
+    #[test]
+    fn test_binary_search_over_many_checkpoints() {
+        let (vm, mut contract) = setup();
+        for block in 10..60u64 {
+            vm.set_block_number(block);
+            contract.mint(ALICE, U256::from(1)).unwrap();
+        }
+        vm.set_block_number(100);
+        for block in 10..60u64 {
+            assert_eq!(
+                contract.get_past_votes(ALICE, U256::from(block)).unwrap(),
+                U256::from(block - 9)
+            );
+        }
+        assert_eq!(
+            contract.get_past_total_supply(U256::from(34)).unwrap(),
+            U256::from(25)
+        );
+        assert_eq!(
+            contract.get_past_votes(ALICE, U256::from(99)).unwrap(),
+            U256::from(50)
+        );
+    }
+
+    #[test]
+    fn test_rejects_current_and_future_blocks() {
+        let (_vm, contract) = setup();
+        assert!(contract.get_past_votes(ALICE, U256::from(10)).is_err());
+        assert!(contract.get_past_total_supply(U256::from(11)).is_err());
+    }
+
+    #[test]
+    fn test_mint_is_owner_only_and_bounded() {
+        let (vm, mut contract) = setup();
+        contract.mint(ALICE, U256::from(U192::MAX)).unwrap();
+        assert!(contract.mint(ALICE, U256::from(1)).is_err());
+
+        vm.set_sender(ALICE);
+        assert!(contract.mint(ALICE, U256::from(1)).is_err());
+        assert_eq!(contract.total_supply(), U256::from(U192::MAX));
+    }
+
+    #[test]
+    fn test_transfer_requires_balance() {
+        let (vm, mut contract) = setup();
+        contract.mint(ALICE, U256::from(10)).unwrap();
+        vm.set_sender(ALICE);
+        assert!(contract.transfer(BOB, U256::from(11)).is_err());
+        assert!(contract.transfer(Address::ZERO, U256::from(1)).is_err());
+        assert!(contract.transfer(BOB, U256::from(10)).unwrap());
+        assert_eq!(contract.balance_of(BOB), U256::from(10));
+    }
+
+    #[test]
+    fn test_self_transfer_keeps_balance_and_votes() {
+        let (vm, mut contract) = setup();
+        contract.mint(ALICE, U256::from(10)).unwrap();
+        vm.set_sender(ALICE);
+        assert!(contract.transfer(ALICE, U256::from(4)).unwrap());
+        assert_eq!(contract.balance_of(ALICE), U256::from(10));
+        assert_eq!(contract.get_votes(ALICE), U256::from(10));
+        assert_eq!(contract.total_supply(), U256::from(10));
+    }
+}
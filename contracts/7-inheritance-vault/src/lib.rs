@@ -0,0 +1,378 @@
+//!
+//! Stylus Inheritance Vault
+//!
+//! A dead-man's switch: the owner funds the vault and must `ping()` at least
+//! once per inactivity window. Once the window lapses, beneficiaries can each
+//! `claim()` their share of the funds.
+//!
+//! The first claim locks the vault and snapshots its balance, so every
+//! beneficiary is paid `snapshot * shares / total_shares` no matter in which
+//! order they claim. Payouts are pulled by each beneficiary rather than pushed
+//! in a loop, so one failing recipient cannot block the others. Integer
+//! division can leave a few wei of dust in the vault.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    prelude::*,
+};
+
+sol! {
+    event Deposited(address indexed from, uint256 amount);
+    event Pinged(uint256 timestamp);
+    event BeneficiarySet(address indexed beneficiary, uint256 shares);
+    event Withdrawn(uint256 amount);
+    event Claimed(address indexed beneficiary, uint256 amount);
+
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error VaultLocked();
+    #[derive(Debug)]
+    error OwnerStillActive(uint256 claimableAt);
+    #[derive(Debug)]
+    error NotBeneficiary(address caller);
+    #[derive(Debug)]
+    error AlreadyClaimed(address beneficiary);
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum VaultError {
+    NotOwner(NotOwner),
+    VaultLocked(VaultLocked),
+    OwnerStillActive(OwnerStillActive),
+    NotBeneficiary(NotBeneficiary),
+    AlreadyClaimed(AlreadyClaimed),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct InheritanceVault {
+        address owner;
+        uint256 inactivity_window;
+        uint256 last_ping;
+        /// Set by the first claim; the owner can no longer act afterwards.
+        bool locked;
+        /// Vault balance when it locked, which all shares are computed from.
+        uint256 inheritance_pool;
+        uint256 total_shares;
+        address[] beneficiaries;
+        mapping(address => uint256) shares;
+        mapping(address => bool) claimed;
+    }
+}
+
+#[public]
+impl InheritanceVault {
+    #[constructor]
+    pub fn constructor(&mut self, owner: Address, inactivity_window: U256) {
+        self.owner.set(owner);
+        self.inactivity_window.set(inactivity_window);
+        self.last_ping.set(U256::from(self.vm().block_timestamp()));
+    }
+
+    /// Adds funds to the vault.
+    #[payable]
+    pub fn deposit(&mut self) {
+        let from = self.vm().msg_sender();
+        let amount = self.vm().msg_value();
+        log(self.vm(), Deposited { from, amount });
+    }
+
+    /// Proves the owner is still around and restarts the inactivity window.
+    pub fn ping(&mut self) -> Result<(), VaultError> {
+        self.only_active_owner()?;
+        Ok(())
+    }
+
+    /// Sets a beneficiary's share weight; zero removes them from the payout.
+    pub fn set_beneficiary(
+        &mut self,
+        beneficiary: Address,
+        shares: U256,
+    ) -> Result<(), VaultError> {
+        self.only_active_owner()?;
+        let previous = self.shares.get(beneficiary);
+        if previous.is_zero() && !shares.is_zero() && !self.is_listed(beneficiary) {
+            self.beneficiaries.push(beneficiary);
+        }
+        self.shares.insert(beneficiary, shares);
+        let total = self.total_shares.get() - previous + shares;
+        self.total_shares.set(total);
+        log(
+            self.vm(),
+            BeneficiarySet {
+                beneficiary,
+                shares,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn set_inactivity_window(&mut self, inactivity_window: U256) -> Result<(), VaultError> {
+        self.only_active_owner()?;
+        self.inactivity_window.set(inactivity_window);
+        Ok(())
+    }
+
+    /// Lets the owner take funds back out while the vault is not locked.
+    pub fn withdraw(&mut self, amount: U256) -> Result<(), VaultError> {
+        self.only_active_owner()?;
+        let owner = self.owner.get();
+        self.vm()
+            .transfer_eth(owner, amount)
+            .map_err(|_| VaultError::TransferFailed(TransferFailed { to: owner, amount }))?;
+        log(self.vm(), Withdrawn { amount });
+        Ok(())
+    }
+
+    /// Pays the caller their share once the owner has been inactive for a full window.
+    pub fn claim(&mut self) -> Result<U256, VaultError> {
+        let caller = self.vm().msg_sender();
+        let now = U256::from(self.vm().block_timestamp());
+        let claimable_at = self.claimable_at();
+        if now < claimable_at {
+            return Err(VaultError::OwnerStillActive(OwnerStillActive {
+                claimableAt: claimable_at,
+            }));
+        }
+        let shares = self.shares.get(caller);
+        if shares.is_zero() {
+            return Err(VaultError::NotBeneficiary(NotBeneficiary { caller }));
+        }
+        if self.claimed.get(caller) {
+            return Err(VaultError::AlreadyClaimed(AlreadyClaimed {
+                beneficiary: caller,
+            }));
+        }
+
+        if !self.locked.get() {
+            let balance = self.vm().balance(self.vm().contract_address());
+            self.inheritance_pool.set(balance);
+            self.locked.set(true);
+        }
+        let amount = self.inheritance_pool.get() * shares / self.total_shares.get();
+
+        // Effects before the interaction: a reentrant claim sees `claimed` already set.
+        self.claimed.insert(caller, true);
+        self.vm()
+            .transfer_eth(caller, amount)
+            .map_err(|_| VaultError::TransferFailed(TransferFailed { to: caller, amount }))?;
+        log(
+            self.vm(),
+            Claimed {
+                beneficiary: caller,
+                amount,
+            },
+        );
+        Ok(amount)
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    pub fn last_ping(&self) -> U256 {
+        self.last_ping.get()
+    }
+
+    pub fn inactivity_window(&self) -> U256 {
+        self.inactivity_window.get()
+    }
+
+    /// Timestamp from which beneficiaries may claim.
+    pub fn claimable_at(&self) -> U256 {
+        self.last_ping.get() + self.inactivity_window.get()
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked.get()
+    }
+
+    pub fn shares_of(&self, beneficiary: Address) -> U256 {
+        self.shares.get(beneficiary)
+    }
+
+    pub fn total_shares(&self) -> U256 {
+        self.total_shares.get()
+    }
+
+    pub fn beneficiary_count(&self) -> U256 {
+        U256::from(self.beneficiaries.len())
+    }
+
+    pub fn beneficiary_at(&self, index: U256) -> Address {
+        self.beneficiaries.get(index).unwrap_or_default()
+    }
+
+    pub fn has_claimed(&self, beneficiary: Address) -> bool {
+        self.claimed.get(beneficiary)
+    }
+}
+
+impl InheritanceVault {
+    /// Owner-only guard that also counts as a ping, since any owner action proves liveness.
+    fn only_active_owner(&mut self) -> Result<(), VaultError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(VaultError::NotOwner(NotOwner { caller }));
+        }
+        if self.locked.get() {
+            return Err(VaultError::VaultLocked(VaultLocked {}));
+        }
+        let now = self.vm().block_timestamp();
+        self.last_ping.set(U256::from(now));
+        log(
+            self.vm(),
+            Pinged {
+                timestamp: U256::from(now),
+            },
+        );
+        Ok(())
+    }
+
+    fn is_listed(&self, beneficiary: Address) -> bool {
+        (0..self.beneficiaries.len()).any(|i| self.beneficiaries.get(i) == Some(beneficiary))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::new([0x01; 20]);
+    const ALICE: Address = Address::new([0x02; 20]);
+    const BOB: Address = Address::new([0x03; 20]);
+    const WINDOW: u64 = 30 * 24 * 60 * 60;
+
+    /// Vault holding 900 wei, split 2:1 between Alice and Bob.
+    fn setup() -> (TestVM, InheritanceVault) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(1_000_000);
+        let mut contract = InheritanceVault::from(&vm);
+        contract.constructor(OWNER, U256::from(WINDOW));
+
+        vm.set_sender(OWNER);
+        vm.set_value(U256::from(900));
+        contract.deposit();
+        vm.set_value(U256::ZERO);
+        vm.set_balance(vm.contract_address(), U256::from(900));
+
+        contract.set_beneficiary(ALICE, U256::from(2)).unwrap();
+        contract.set_beneficiary(BOB, U256::from(1)).unwrap();
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_claims_rejected_while_owner_active() {
+        let (vm, mut contract) = setup();
+        vm.set_block_timestamp(1_000_000 + WINDOW - 1);
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.claim(),
+            Err(VaultError::OwnerStillActive(_))
+        ));
+    }
+
+    #[test]
+    fn test_ping_restarts_window() {
+        let (vm, mut contract) = setup();
+        vm.set_block_timestamp(1_000_000 + WINDOW - 1);
+        contract.ping().unwrap();
+        assert_eq!(
+            contract.claimable_at(),
+            U256::from(1_000_000 + 2 * WINDOW - 1)
+        );
+
+        vm.set_block_timestamp(1_000_000 + WINDOW + 1);
+        vm.set_sender(ALICE);
+        assert!(contract.claim().is_err());
+    }
+
+    #[test]
+    fn test_beneficiaries_claim_by_shares() {
+        let (vm, mut contract) = setup();
+        vm.set_block_timestamp(1_000_000 + WINDOW);
+
+        vm.set_sender(BOB);
+        assert_eq!(contract.claim().unwrap(), U256::from(300));
+        vm.set_sender(ALICE);
+        assert_eq!(contract.claim().unwrap(), U256::from(600));
+
+        assert_eq!(vm.balance(ALICE), U256::from(600));
+        assert_eq!(vm.balance(BOB), U256::from(300));
+        assert!(contract.is_locked());
+        assert!(matches!(
+            contract.claim(),
+            Err(VaultError::AlreadyClaimed(_))
+        ));
+    }
+
+    #[test]
+    fn test_owner_locked_out_after_first_claim() {
+        let (vm, mut contract) = setup();
+        vm.set_block_timestamp(1_000_000 + WINDOW);
+        vm.set_sender(ALICE);
+        contract.claim().unwrap();
+
+        vm.set_sender(OWNER);
+        assert!(matches!(contract.ping(), Err(VaultError::VaultLocked(_))));
+        assert!(contract.withdraw(U256::from(1)).is_err());
+        assert!(contract.set_beneficiary(OWNER, U256::from(5)).is_err());
+    }
+
+    #[test]
+    fn test_only_beneficiaries_claim() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(OWNER);
+        contract.set_beneficiary(BOB, U256::ZERO).unwrap();
+        assert_eq!(contract.total_shares(), U256::from(2));
+
+        vm.set_block_timestamp(1_000_000 + WINDOW);
+        vm.set_sender(BOB);
+        assert!(matches!(
+            contract.claim(),
+            Err(VaultError::NotBeneficiary(_))
+        ));
+        vm.set_sender(ALICE);
+        assert_eq!(contract.claim().unwrap(), U256::from(900));
+    }
+
+    #[test]
+    fn test_owner_withdraw_and_access_control() {
+        let (vm, mut contract) = setup();
+        contract.withdraw(U256::from(100)).unwrap();
+        assert_eq!(vm.balance(OWNER), U256::from(100));
+        assert!(contract.withdraw(U256::from(10_000)).is_err());
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.withdraw(U256::from(1)),
+            Err(VaultError::NotOwner(_))
+        ));
+        assert!(contract.set_inactivity_window(U256::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_re_adding_beneficiary_is_listed_once() {
+        let (_vm, mut contract) = setup();
+        contract.set_beneficiary(BOB, U256::ZERO).unwrap();
+        contract.set_beneficiary(BOB, U256::from(4)).unwrap();
+        assert_eq!(contract.beneficiary_count(), U256::from(2));
+        assert_eq!(contract.beneficiary_at(U256::from(1)), BOB);
+        assert_eq!(contract.total_shares(), U256::from(6));
+    }
+}
@@ -0,0 +1,417 @@
+//!
+//! Stylus Payroll
+//!
+//! The employer funds a pool and registers employees with a pay rate and a
+//! schedule: `rate` wei is earned for every completed `period_seconds`.
+//! Employees `claim()` whatever has accrued; nothing is pushed to them.
+//!
+//! Pay only vests per completed period while employed. When the employer
+//! adjusts a rate or terminates an employee, the current partial period is
+//! prorated to the second and kept as owed pay, so changes never cost the
+//! employee time already worked. Terminated employees can still claim what
+//! they are owed.
+//!
+//! Claims draw on the contract's balance. If the employer has not funded
+//! enough, the claim reverts with `InsufficientPool` and can be retried later.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    prelude::*,
+};
+
+sol! {
+    event Funded(address indexed from, uint256 amount);
+    event EmployeeAdded(address indexed employee, uint256 rate, uint256 periodSeconds);
+    event EmployeeAdjusted(address indexed employee, uint256 rate, uint256 periodSeconds);
+    event EmployeeTerminated(address indexed employee, uint256 owed);
+    event PayClaimed(address indexed employee, uint256 amount);
+
+    #[derive(Debug)]
+    error NotEmployer(address caller);
+    #[derive(Debug)]
+    error NotEmployed(address account);
+    #[derive(Debug)]
+    error AlreadyEmployed(address account);
+    #[derive(Debug)]
+    error InvalidSchedule();
+    #[derive(Debug)]
+    error NothingToClaim();
+    #[derive(Debug)]
+    error InsufficientPool(uint256 required, uint256 available);
+    #[derive(Debug)]
+    error TransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum PayrollError {
+    NotEmployer(NotEmployer),
+    NotEmployed(NotEmployed),
+    AlreadyEmployed(AlreadyEmployed),
+    InvalidSchedule(InvalidSchedule),
+    NothingToClaim(NothingToClaim),
+    InsufficientPool(InsufficientPool),
+    TransferFailed(TransferFailed),
+}
+
+sol_storage! {
+    pub struct Employee {
+        /// Wei earned per completed period.
+        uint256 rate;
+        uint256 period_seconds;
+        /// Start of the first period that has not been paid or settled yet.
+        uint256 accrual_start;
+        /// Prorated pay settled on adjustment or termination, not yet claimed.
+        uint256 owed;
+        bool active;
+    }
+
+    #[entrypoint]
+    pub struct Payroll {
+        address employer;
+        mapping(address => Employee) employees;
+        /// Every address ever registered, including terminated employees.
+        address[] employee_index;
+    }
+}
+
+#[public]
+impl Payroll {
+    #[constructor]
+    pub fn constructor(&mut self, employer: Address) {
+        self.employer.set(employer);
+    }
+
+    /// Adds ETH to the payroll pool. Anyone may fund it.
+    #[payable]
+    pub fn fund(&mut self) {
+        let from = self.vm().msg_sender();
+        let amount = self.vm().msg_value();
+        log(self.vm(), Funded { from, amount });
+    }
+
+    pub fn add_employee(
+        &mut self,
+        employee: Address,
+        rate: U256,
+        period_seconds: U256,
+    ) -> Result<(), PayrollError> {
+        self.only_employer()?;
+        check_schedule(rate, period_seconds)?;
+        let now = U256::from(self.vm().block_timestamp());
+        let mut record = self.employees.setter(employee);
+        if record.active.get() {
+            return Err(PayrollError::AlreadyEmployed(AlreadyEmployed {
+                account: employee,
+            }));
+        }
+        let first_hire = record.period_seconds.get().is_zero();
+        // A rehired employee keeps any pay still owed from the previous contract.
+        record.rate.set(rate);
+        record.period_seconds.set(period_seconds);
+        record.accrual_start.set(now);
+        record.active.set(true);
+        if first_hire {
+            self.employee_index.push(employee);
+        }
+        log(
+            self.vm(),
+            EmployeeAdded {
+                employee,
+                rate,
+                periodSeconds: period_seconds,
+            },
+        );
+        Ok(())
+    }
+
+    /// Changes an employee's rate or schedule, prorating the period in progress.
+    pub fn adjust(
+        &mut self,
+        employee: Address,
+        rate: U256,
+        period_seconds: U256,
+    ) -> Result<(), PayrollError> {
+        self.only_employer()?;
+        check_schedule(rate, period_seconds)?;
+        self.settle(employee)?;
+        let mut record = self.employees.setter(employee);
+        record.rate.set(rate);
+        record.period_seconds.set(period_seconds);
+        log(
+            self.vm(),
+            EmployeeAdjusted {
+                employee,
+                rate,
+                periodSeconds: period_seconds,
+            },
+        );
+        Ok(())
+    }
+
+    /// Stops accrual, prorating the period in progress into owed pay.
+    pub fn terminate(&mut self, employee: Address) -> Result<(), PayrollError> {
+        self.only_employer()?;
+        self.settle(employee)?;
+        let mut record = self.employees.setter(employee);
+        record.active.set(false);
+        let owed = record.owed.get();
+        log(self.vm(), EmployeeTerminated { employee, owed });
+        Ok(())
+    }
+
+    /// Pays the caller everything accrued so far and returns the amount.
+    pub fn claim(&mut self) -> Result<U256, PayrollError> {
+        let caller = self.vm().msg_sender();
+        let now = U256::from(self.vm().block_timestamp());
+        let (periods, amount) = self.accrued_at(caller, now);
+        if amount.is_zero() {
+            return Err(PayrollError::NothingToClaim(NothingToClaim {}));
+        }
+        let available = self.vm().balance(self.vm().contract_address());
+        if available < amount {
+            return Err(PayrollError::InsufficientPool(InsufficientPool {
+                required: amount,
+                available,
+            }));
+        }
+
+        // Effects before the interaction, so a reentrant claim finds nothing left.
+        let mut record = self.employees.setter(caller);
+        let start = record.accrual_start.get() + periods * record.period_seconds.get();
+        record.accrual_start.set(start);
+        record.owed.set(U256::ZERO);
+        self.vm()
+            .transfer_eth(caller, amount)
+            .map_err(|_| PayrollError::TransferFailed(TransferFailed { to: caller, amount }))?;
+        log(
+            self.vm(),
+            PayClaimed {
+                employee: caller,
+                amount,
+            },
+        );
+        Ok(amount)
+    }
+
+    /// Takes ETH back out of the pool.
+    pub fn withdraw(&mut self, amount: U256) -> Result<(), PayrollError> {
+        self.only_employer()?;
+        let employer = self.employer.get();
+        self.vm().transfer_eth(employer, amount).map_err(|_| {
+            PayrollError::TransferFailed(TransferFailed {
+                to: employer,
+                amount,
+            })
+        })?;
+        Ok(())
+    }
+
+    pub fn employer(&self) -> Address {
+        self.employer.get()
+    }
+
+    /// Pay `employee` could claim right now.
+    pub fn accrued(&self, employee: Address) -> U256 {
+        let now = U256::from(self.vm().block_timestamp());
+        self.accrued_at(employee, now).1
+    }
+
+    /// Returns `(rate, period_seconds, active)`.
+    pub fn employee_info(&self, employee: Address) -> (U256, U256, bool) {
+        let record = self.employees.getter(employee);
+        (
+            record.rate.get(),
+            record.period_seconds.get(),
+            record.active.get(),
+        )
+    }
+
+    pub fn employee_count(&self) -> U256 {
+        U256::from(self.employee_index.len())
+    }
+
+    pub fn employee_at(&self, index: U256) -> Address {
+        self.employee_index.get(index).unwrap_or_default()
+    }
+}
+
+impl Payroll {
+    fn only_employer(&self) -> Result<(), PayrollError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.employer.get() {
+            return Err(PayrollError::NotEmployer(NotEmployer { caller }));
+        }
+        Ok(())
+    }
+
+    /// Completed periods since the accrual start, and the total claimable at `now`.
+    fn accrued_at(&self, employee: Address, now: U256) -> (U256, U256) {
+        let record = self.employees.getter(employee);
+        let owed = record.owed.get();
+        if !record.active.get() {
+            return (U256::ZERO, owed);
+        }
+        let periods = (now - record.accrual_start.get()) / record.period_seconds.get();
+        (periods, owed + periods * record.rate.get())
+    }
+
+    /// Moves everything earned up to now, including the partial period, into `owed`.
+    fn settle(&mut self, employee: Address) -> Result<(), PayrollError> {
+        let now = U256::from(self.vm().block_timestamp());
+        let mut record = self.employees.setter(employee);
+        if !record.active.get() {
+            return Err(PayrollError::NotEmployed(NotEmployed { account: employee }));
+        }
+        let elapsed = now - record.accrual_start.get();
+        let earned = record.rate.get() * elapsed / record.period_seconds.get();
+        let owed = record.owed.get() + earned;
+        record.owed.set(owed);
+        record.accrual_start.set(now);
+        Ok(())
+    }
+}
+
+fn check_schedule(rate: U256, period_seconds: U256) -> Result<(), PayrollError> {
+    if rate.is_zero() || period_seconds.is_zero() {
+        return Err(PayrollError::InvalidSchedule(InvalidSchedule {}));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const EMPLOYER: Address = Address::new([0x01; 20]);
+    const ALICE: Address = Address::new([0x02; 20]);
+    const BOB: Address = Address::new([0x03; 20]);
+    const START: u64 = 1_000_000;
+    const WEEK: u64 = 7 * 24 * 60 * 60;
+
+    /// Pool of 10,000 wei; Alice earns 100 wei per week.
+    fn setup() -> (TestVM, Payroll) {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(START);
+        let mut contract = Payroll::from(&vm);
+        contract.constructor(EMPLOYER);
+        vm.set_sender(EMPLOYER);
+        vm.set_balance(vm.contract_address(), U256::from(10_000));
+        contract
+            .add_employee(ALICE, U256::from(100), U256::from(WEEK))
+            .unwrap();
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_pay_vests_per_completed_period() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.claim(),
+            Err(PayrollError::NothingToClaim(_))
+        ));
+
+        vm.set_block_timestamp(START + 2 * WEEK + WEEK / 2);
+        assert_eq!(contract.accrued(ALICE), U256::from(200));
+        assert_eq!(contract.claim().unwrap(), U256::from(200));
+        assert_eq!(vm.balance(ALICE), U256::from(200));
+
+        // The half week already worked still counts towards the next period.
+        vm.set_block_timestamp(START + 3 * WEEK);
+        assert_eq!(contract.claim().unwrap(), U256::from(100));
+    }
+
+    #[test]
+    fn test_adjust_prorates_current_period() {
+        let (vm, mut contract) = setup();
+        vm.set_block_timestamp(START + WEEK + WEEK / 4);
+        contract
+            .adjust(ALICE, U256::from(400), U256::from(WEEK))
+            .unwrap();
+        assert_eq!(contract.accrued(ALICE), U256::from(125));
+
+        vm.set_block_timestamp(START + 2 * WEEK + WEEK / 4);
+        vm.set_sender(ALICE);
+        assert_eq!(contract.claim().unwrap(), U256::from(525));
+    }
+
+    #[test]
+    fn test_terminated_employee_claims_prorated_pay() {
+        let (vm, mut contract) = setup();
+        vm.set_block_timestamp(START + WEEK / 2);
+        contract.terminate(ALICE).unwrap();
+        assert!(!contract.employee_info(ALICE).2);
+
+        vm.set_block_timestamp(START + 10 * WEEK);
+        assert_eq!(contract.accrued(ALICE), U256::from(50));
+        vm.set_sender(ALICE);
+        assert_eq!(contract.claim().unwrap(), U256::from(50));
+        assert!(contract.claim().is_err());
+    }
+
+    #[test]
+    fn test_claim_requires_funded_pool() {
+        let (vm, mut contract) = setup();
+        contract.withdraw(U256::from(9_950)).unwrap();
+        assert_eq!(vm.balance(EMPLOYER), U256::from(9_950));
+
+        vm.set_block_timestamp(START + WEEK);
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.claim(),
+            Err(PayrollError::InsufficientPool(InsufficientPool { required, available }))
+                if required == U256::from(100) && available == U256::from(50)
+        ));
+        assert_eq!(contract.accrued(ALICE), U256::from(100));
+    }
+
+    #[test]
+    fn test_employer_only_and_schedule_checks() {
+        let (vm, mut contract) = setup();
+        assert!(matches!(
+            contract.add_employee(ALICE, U256::from(1), U256::from(1)),
+            Err(PayrollError::AlreadyEmployed(_))
+        ));
+        assert!(matches!(
+            contract.add_employee(BOB, U256::from(1), U256::ZERO),
+            Err(PayrollError::InvalidSchedule(_))
+        ));
+        assert!(matches!(
+            contract.terminate(BOB),
+            Err(PayrollError::NotEmployed(_))
+        ));
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.adjust(ALICE, U256::from(1_000), U256::from(1)),
+            Err(PayrollError::NotEmployer(_))
+        ));
+        assert!(contract.withdraw(U256::from(1)).is_err());
+    }
+
+    #[test]
+    fn test_rehire_keeps_owed_pay_and_single_index_entry() {
+        let (vm, mut contract) = setup();
+        vm.set_block_timestamp(START + WEEK / 2);
+        contract.terminate(ALICE).unwrap();
+        contract
+            .add_employee(ALICE, U256::from(10), U256::from(WEEK))
+            .unwrap();
+        assert_eq!(contract.employee_count(), U256::from(1));
+        assert_eq!(contract.employee_at(U256::ZERO), ALICE);
+
+        vm.set_block_timestamp(START + WEEK / 2 + WEEK);
+        assert_eq!(contract.accrued(ALICE), U256::from(60));
+    }
+}
@@ -0,0 +1,583 @@
+//!
+//! Stylus ERC-20 Token
+//!
+//! A minimal but complete ERC-20, "Cupcake" (`CUPCAKE`), with 18 decimals:
+//!
+//! ```solidity
+//! function transfer(address to, uint256 value) external returns (bool);
+//! function transferFrom(address from, address to, uint256 value) external returns (bool);
+//! function approve(address spender, uint256 value) external returns (bool);
+//! function allowance(address owner, address spender) external view returns (uint256);
+//! ```
+//!
+//! Balances and allowances live in `sol_storage!` mappings. Failures revert
+//! with the ERC-6093 custom errors (`ERC20InsufficientBalance`, ...) that
+//! OpenZeppelin tokens use, so wallets and explorers decode them the same way.
+//! An allowance of `type(uint256).max` is treated as infinite and is never
+//! decremented. Only the owner passed to the constructor can mint, and a mint
+//! that would overflow the supply reverts with `SupplyOverflow`.
+//!
+//! EIP-2612 `permit` lets a holder approve a spender with an off-chain
+//! signature, so the spender (or a relayer) pays the gas:
+//!
+//! ```solidity
+//! function permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s) external;
+//! function nonces(address owner) external view returns (uint256);
+//! function DOMAIN_SEPARATOR() external view returns (bytes32);
+//! ```
+//!
+//! The signature is over the EIP-712 digest of a `Permit` struct, under a
+//! domain of name `Cupcake`, version `1`, the current chain id and this
+//! contract's address. The domain separator is rebuilt on every call, so a
+//! chain fork cannot replay permits signed for the other chain. Each owner's
+//! nonce is consumed by a successful permit, and signatures with a high `s`
+//! (EIP-2) are rejected as malleable.
+//!
+//! `supportsInterface` answers ERC-165 queries for ERC-165 itself and the
+//! ERC-20 interface id `0x36372b07`, using the ids from `common::introspection`.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use alloy_sol_types::sol;
+use common::{
+    ecdsa,
+    introspection::{ERC165_ID, ERC20_ID},
+};
+use stylus_sdk::{
+    alloy_primitives::{Address, FixedBytes, B256, U256},
+    prelude::*,
+};
+
+const EIP712_DOMAIN_TYPE: &[u8] =
+    b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+const PERMIT_TYPE: &[u8] =
+    b"Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)";
+const DOMAIN_VERSION: &str = "1";
+
+sol! {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+    event Approval(address indexed owner, address indexed spender, uint256 value);
+
+    #[derive(Debug)]
+    error ERC20InsufficientBalance(address sender, uint256 balance, uint256 needed);
+    #[derive(Debug)]
+    error ERC20InvalidSender(address sender);
+    #[derive(Debug)]
+    error ERC20InvalidReceiver(address receiver);
+    #[derive(Debug)]
+    error ERC20InsufficientAllowance(address spender, uint256 allowance, uint256 needed);
+    #[derive(Debug)]
+    error ERC20InvalidApprover(address approver);
+    #[derive(Debug)]
+    error ERC20InvalidSpender(address spender);
+    #[derive(Debug)]
+    error NotOwner(address caller);
+    #[derive(Debug)]
+    error SupplyOverflow(uint256 supply, uint256 value);
+    #[derive(Debug)]
+    error ERC2612ExpiredSignature(uint256 deadline);
+    #[derive(Debug)]
+    error ERC2612InvalidSigner(address signer, address owner);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum Erc20Error {
+    InsufficientBalance(ERC20InsufficientBalance),
+    InvalidSender(ERC20InvalidSender),
+    InvalidReceiver(ERC20InvalidReceiver),
+    InsufficientAllowance(ERC20InsufficientAllowance),
+    InvalidApprover(ERC20InvalidApprover),
+    InvalidSpender(ERC20InvalidSpender),
+    NotOwner(NotOwner),
+    SupplyOverflow(SupplyOverflow),
+    ExpiredSignature(ERC2612ExpiredSignature),
+    InvalidSigner(ERC2612InvalidSigner),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct Erc20Token {
+        address owner;
+        uint256 total_supply;
+        mapping(address => uint256) balances;
+        mapping(address => mapping(address => uint256)) allowances;
+        mapping(address => uint256) nonces;
+    }
+}
+
+#[public]
+impl Erc20Token {
+    #[constructor]
+    pub fn constructor(&mut self, owner: Address) {
+        self.owner.set(owner);
+    }
+
+    pub fn name(&self) -> String {
+        "Cupcake".into()
+    }
+
+    pub fn symbol(&self) -> String {
+        "CUPCAKE".into()
+    }
+
+    pub fn decimals(&self) -> u8 {
+        18
+    }
+
+    pub fn total_supply(&self) -> U256 {
+        self.total_supply.get()
+    }
+
+    pub fn balance_of(&self, account: Address) -> U256 {
+        self.balances.get(account)
+    }
+
+    pub fn allowance(&self, owner: Address, spender: Address) -> U256 {
+        self.allowances.getter(owner).get(spender)
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    /// ERC-165: whether this token implements the interface `interface_id`.
+    pub fn supports_interface(&self, interface_id: FixedBytes<4>) -> bool {
+        [ERC165_ID, ERC20_ID].contains(&interface_id)
+    }
+
+    pub fn transfer(&mut self, to: Address, value: U256) -> Result<bool, Erc20Error> {
+        let from = self.vm().msg_sender();
+        self.move_tokens(from, to, value)?;
+        Ok(true)
+    }
+
+    pub fn approve(&mut self, spender: Address, value: U256) -> Result<bool, Erc20Error> {
+        let owner = self.vm().msg_sender();
+        self.set_allowance(owner, spender, value)?;
+        Ok(true)
+    }
+
+    /// EIP-2612: sets `owner`'s allowance for `spender` from `owner`'s signature.
+    ///
+    /// Anyone may submit the signature; it is bound to the current nonce and
+    /// is rejected once `deadline` has passed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn permit(
+        &mut self,
+        owner: Address,
+        spender: Address,
+        value: U256,
+        deadline: U256,
+        v: u8,
+        r: B256,
+        s: B256,
+    ) -> Result<(), Erc20Error> {
+        if U256::from(self.vm().block_timestamp()) > deadline {
+            return Err(Erc20Error::ExpiredSignature(ERC2612ExpiredSignature {
+                deadline,
+            }));
+        }
+        let nonce = self.nonces.get(owner);
+        let digest = self.permit_digest(owner, spender, value, nonce, deadline);
+        // A rejected signature reports the zero address as its signer.
+        let signer = ecdsa::recover_parts(self.vm(), digest, v, r, s).unwrap_or_default();
+        if signer.is_zero() || signer != owner {
+            return Err(Erc20Error::InvalidSigner(ERC2612InvalidSigner {
+                signer,
+                owner,
+            }));
+        }
+        self.nonces.insert(owner, nonce + U256::from(1));
+        self.set_allowance(owner, spender, value)
+    }
+
+    /// The next nonce `permit` expects from `owner`.
+    pub fn nonces(&self, owner: Address) -> U256 {
+        self.nonces.get(owner)
+    }
+
+    /// The EIP-712 domain separator permits are signed under.
+    #[selector(name = "DOMAIN_SEPARATOR")]
+    pub fn domain_separator(&self) -> B256 {
+        let mut encoded = Vec::with_capacity(5 * 32);
+        encoded.extend_from_slice(self.vm().native_keccak256(EIP712_DOMAIN_TYPE).as_slice());
+        encoded.extend_from_slice(
+            self.vm()
+                .native_keccak256(self.name().as_bytes())
+                .as_slice(),
+        );
+        encoded.extend_from_slice(
+            self.vm()
+                .native_keccak256(DOMAIN_VERSION.as_bytes())
+                .as_slice(),
+        );
+        encoded.extend_from_slice(&U256::from(self.vm().chain_id()).to_be_bytes::<32>());
+        encoded.extend_from_slice(self.vm().contract_address().into_word().as_slice());
+        self.vm().native_keccak256(&encoded)
+    }
+
+    pub fn transfer_from(
+        &mut self,
+        from: Address,
+        to: Address,
+        value: U256,
+    ) -> Result<bool, Erc20Error> {
+        let spender = self.vm().msg_sender();
+        let allowance = self.allowances.getter(from).get(spender);
+        if allowance != U256::MAX {
+            if allowance < value {
+                return Err(Erc20Error::InsufficientAllowance(
+                    ERC20InsufficientAllowance {
+                        spender,
+                        allowance,
+                        needed: value,
+                    },
+                ));
+            }
+            self.allowances
+                .setter(from)
+                .insert(spender, allowance - value);
+        }
+        self.move_tokens(from, to, value)?;
+        Ok(true)
+    }
+
+    /// Creates `value` new tokens for `to`. Only the owner may mint.
+    pub fn mint(&mut self, to: Address, value: U256) -> Result<(), Erc20Error> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() {
+            return Err(Erc20Error::NotOwner(NotOwner { caller }));
+        }
+        if to.is_zero() {
+            return Err(Erc20Error::InvalidReceiver(ERC20InvalidReceiver {
+                receiver: to,
+            }));
+        }
+        let supply = self.total_supply.get();
+        let overflow = || Erc20Error::SupplyOverflow(SupplyOverflow { supply, value });
+        let new_supply = supply.checked_add(value).ok_or_else(overflow)?;
+        let balance = self
+            .balances
+            .get(to)
+            .checked_add(value)
+            .ok_or_else(overflow)?;
+        self.total_supply.set(new_supply);
+        self.balances.insert(to, balance);
+        log(
+            self.vm(),
+            Transfer {
+                from: Address::ZERO,
+                to,
+                value,
+            },
+        );
+        Ok(())
+    }
+}
+
+impl Erc20Token {
+    fn set_allowance(
+        &mut self,
+        owner: Address,
+        spender: Address,
+        value: U256,
+    ) -> Result<(), Erc20Error> {
+        if owner.is_zero() {
+            return Err(Erc20Error::InvalidApprover(ERC20InvalidApprover {
+                approver: owner,
+            }));
+        }
+        if spender.is_zero() {
+            return Err(Erc20Error::InvalidSpender(ERC20InvalidSpender { spender }));
+        }
+        self.allowances.setter(owner).insert(spender, value);
+        log(
+            self.vm(),
+            Approval {
+                owner,
+                spender,
+                value,
+            },
+        );
+        Ok(())
+    }
+
+    /// `keccak256("\x19\x01" || DOMAIN_SEPARATOR || hashStruct(Permit))`.
+    fn permit_digest(
+        &self,
+        owner: Address,
+        spender: Address,
+        value: U256,
+        nonce: U256,
+        deadline: U256,
+    ) -> B256 {
+        let mut encoded = Vec::with_capacity(6 * 32);
+        encoded.extend_from_slice(self.vm().native_keccak256(PERMIT_TYPE).as_slice());
+        encoded.extend_from_slice(owner.into_word().as_slice());
+        encoded.extend_from_slice(spender.into_word().as_slice());
+        encoded.extend_from_slice(&value.to_be_bytes::<32>());
+        encoded.extend_from_slice(&nonce.to_be_bytes::<32>());
+        encoded.extend_from_slice(&deadline.to_be_bytes::<32>());
+        let struct_hash = self.vm().native_keccak256(&encoded);
+
+        let mut message = b"\x19\x01".to_vec();
+        message.extend_from_slice(self.domain_separator().as_slice());
+        message.extend_from_slice(struct_hash.as_slice());
+        self.vm().native_keccak256(&message)
+    }
+
+    fn move_tokens(&mut self, from: Address, to: Address, value: U256) -> Result<(), Erc20Error> {
+        if from.is_zero() {
+            return Err(Erc20Error::InvalidSender(ERC20InvalidSender {
+                sender: from,
+            }));
+        }
+        if to.is_zero() {
+            return Err(Erc20Error::InvalidReceiver(ERC20InvalidReceiver {
+                receiver: to,
+            }));
+        }
+        let from_balance = self.balances.get(from);
+        if from_balance < value {
+            return Err(Erc20Error::InsufficientBalance(ERC20InsufficientBalance {
+                sender: from,
+                balance: from_balance,
+                needed: value,
+            }));
+        }
+        self.balances.insert(from, from_balance - value);
+        // Read after the debit so a self-transfer leaves the balance unchanged.
+        let to_balance = self.balances.get(to);
+        self.balances.insert(to, to_balance + value);
+        log(self.vm(), Transfer { from, to, value });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::{SolError, SolEvent};
+    use stylus_sdk::{alloy_primitives::keccak256, testing::*};
+
+    const OWNER: Address = Address::new([0x01; 20]);
+    const ALICE: Address = Address::new([0x02; 20]);
+    const BOB: Address = Address::new([0x03; 20]);
+
+    /// Alice starts with 100 tokens.
+    fn setup() -> (TestVM, Erc20Token) {
+        let vm = TestVM::default();
+        let mut contract = Erc20Token::from(&vm);
+        contract.constructor(OWNER);
+        vm.set_sender(OWNER);
+        contract.mint(ALICE, U256::from(100)).unwrap();
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_metadata_and_mint() {
+        let (vm, mut contract) = setup();
+        assert_eq!(contract.symbol(), "CUPCAKE");
+        assert_eq!(contract.decimals(), 18);
+        assert_eq!(contract.total_supply(), U256::from(100));
+        assert_eq!(contract.balance_of(ALICE), U256::from(100));
+
+        assert!(matches!(
+            contract.mint(BOB, U256::MAX),
+            Err(Erc20Error::SupplyOverflow(_))
+        ));
+        assert_eq!(contract.total_supply(), U256::from(100));
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            contract.mint(ALICE, U256::from(1)),
+            Err(Erc20Error::NotOwner(_))
+        ));
+    }
+
+    #[test]
+    fn test_supports_erc165_and_erc20() {
+        let (_vm, contract) = setup();
+        assert!(contract.supports_interface(ERC165_ID));
+        assert!(contract.supports_interface(ERC20_ID));
+        assert!(!contract.supports_interface(FixedBytes::new([0xff; 4])));
+    }
+
+    #[test]
+    fn test_transfer_moves_balance_and_logs() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(ALICE);
+        assert!(contract.transfer(BOB, U256::from(40)).unwrap());
+        assert_eq!(contract.balance_of(ALICE), U256::from(60));
+        assert_eq!(contract.balance_of(BOB), U256::from(40));
+
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = logs.last().unwrap();
+        let event = Transfer::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!((event.from, event.to), (ALICE, BOB));
+        assert_eq!(event.value, U256::from(40));
+    }
+
+    #[test]
+    fn test_transfer_errors() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(ALICE);
+        let err = contract.transfer(BOB, U256::from(101)).unwrap_err();
+        assert!(matches!(
+            &err,
+            Erc20Error::InsufficientBalance(e) if e.balance == U256::from(100)
+        ));
+        let encoded: Vec<u8> = err.into();
+        assert_eq!(encoded[..4], ERC20InsufficientBalance::SELECTOR);
+
+        assert!(matches!(
+            contract.transfer(Address::ZERO, U256::from(1)),
+            Err(Erc20Error::InvalidReceiver(_))
+        ));
+        contract.transfer(ALICE, U256::from(100)).unwrap();
+        assert_eq!(contract.balance_of(ALICE), U256::from(100));
+    }
+
+    #[test]
+    fn test_transfer_from_spends_allowance() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(ALICE);
+        contract.approve(BOB, U256::from(50)).unwrap();
+        assert_eq!(contract.allowance(ALICE, BOB), U256::from(50));
+
+        vm.set_sender(BOB);
+        contract.transfer_from(ALICE, BOB, U256::from(30)).unwrap();
+        assert_eq!(contract.allowance(ALICE, BOB), U256::from(20));
+        assert_eq!(contract.balance_of(BOB), U256::from(30));
+        assert!(matches!(
+            contract.transfer_from(ALICE, BOB, U256::from(21)),
+            Err(Erc20Error::InsufficientAllowance(_))
+        ));
+    }
+
+    #[test]
+    fn test_infinite_allowance_is_not_decremented() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(ALICE);
+        contract.approve(BOB, U256::MAX).unwrap();
+        assert!(matches!(
+            contract.approve(Address::ZERO, U256::from(1)),
+            Err(Erc20Error::InvalidSpender(_))
+        ));
+
+        vm.set_sender(BOB);
+        contract
+            .transfer_from(ALICE, OWNER, U256::from(70))
+            .unwrap();
+        assert_eq!(contract.allowance(ALICE, BOB), U256::MAX);
+        assert_eq!(contract.balance_of(OWNER), U256::from(70));
+    }
+
+    const DEADLINE: u64 = 5_000;
+    const R: B256 = B256::new([0x22; 32]);
+    const S: B256 = B256::new([0x33; 32]);
+
+    /// The digest Alice signs to let Bob spend `value`, built independently of the contract.
+    fn permit_digest(vm: &TestVM, value: U256, nonce: u64) -> B256 {
+        let domain = [
+            keccak256(EIP712_DOMAIN_TYPE).to_vec(),
+            keccak256("Cupcake").to_vec(),
+            keccak256("1").to_vec(),
+            U256::from(vm.chain_id()).to_be_bytes::<32>().to_vec(),
+            vm.contract_address().into_word().to_vec(),
+        ]
+        .concat();
+        let permit = [
+            keccak256(PERMIT_TYPE).to_vec(),
+            ALICE.into_word().to_vec(),
+            BOB.into_word().to_vec(),
+            value.to_be_bytes::<32>().to_vec(),
+            U256::from(nonce).to_be_bytes::<32>().to_vec(),
+            U256::from(DEADLINE).to_be_bytes::<32>().to_vec(),
+        ]
+        .concat();
+        let message = [
+            b"\x19\x01".to_vec(),
+            keccak256(domain).to_vec(),
+            keccak256(permit).to_vec(),
+        ]
+        .concat();
+        keccak256(message)
+    }
+
+    /// Makes the ecrecover precompile return `signer` for (`digest`, 27, R, S).
+    fn mock_recover(vm: &TestVM, digest: B256, signer: Address) {
+        let input = [
+            digest.to_vec(),
+            U256::from(27).to_be_bytes::<32>().to_vec(),
+            R.to_vec(),
+            S.to_vec(),
+        ]
+        .concat();
+        vm.mock_static_call(ecdsa::ECRECOVER, input, Ok(signer.into_word().to_vec()));
+    }
+
+    #[test]
+    fn test_permit_sets_allowance_and_consumes_nonce() {
+        let (vm, mut contract) = setup();
+        let value = U256::from(60);
+        mock_recover(&vm, permit_digest(&vm, value, 0), ALICE);
+        assert_eq!(contract.nonces(ALICE), U256::ZERO);
+
+        // Anyone may relay the signature.
+        vm.set_sender(OWNER);
+        contract
+            .permit(ALICE, BOB, value, U256::from(DEADLINE), 27, R, S)
+            .unwrap();
+        assert_eq!(contract.allowance(ALICE, BOB), value);
+        assert_eq!(contract.nonces(ALICE), U256::from(1));
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.last().unwrap().0[0], Approval::SIGNATURE_HASH);
+
+        // The nonce moved on, so the same signature no longer recovers to Alice.
+        assert!(matches!(
+            contract.permit(ALICE, BOB, value, U256::from(DEADLINE), 27, R, S),
+            Err(Erc20Error::InvalidSigner(_))
+        ));
+
+        vm.set_sender(BOB);
+        contract.transfer_from(ALICE, BOB, value).unwrap();
+        assert_eq!(contract.balance_of(BOB), value);
+    }
+
+    #[test]
+    fn test_permit_rejects_bad_signatures() {
+        let (vm, mut contract) = setup();
+        let value = U256::from(60);
+        let deadline = U256::from(DEADLINE);
+        mock_recover(&vm, permit_digest(&vm, value, 0), BOB);
+        assert!(matches!(
+            contract.permit(ALICE, BOB, value, deadline, 27, R, S),
+            Err(Erc20Error::InvalidSigner(ERC2612InvalidSigner { signer, owner }))
+                if signer == BOB && owner == ALICE
+        ));
+        assert!(matches!(
+            contract.permit(ALICE, BOB, value, deadline, 29, R, S),
+            Err(Erc20Error::InvalidSigner(ERC2612InvalidSigner { signer, .. })) if signer.is_zero()
+        ));
+        assert!(matches!(
+            contract.permit(ALICE, BOB, value, deadline, 27, R, B256::new([0xff; 32])),
+            Err(Erc20Error::InvalidSigner(_))
+        ));
+
+        vm.set_block_timestamp(DEADLINE + 1);
+        assert!(matches!(
+            contract.permit(ALICE, BOB, value, deadline, 27, R, S),
+            Err(Erc20Error::ExpiredSignature(_))
+        ));
+        assert_eq!(contract.nonces(ALICE), U256::ZERO);
+        assert_eq!(contract.allowance(ALICE, BOB), U256::ZERO);
+    }
+}
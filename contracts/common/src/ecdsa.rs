@@ -0,0 +1,151 @@
+//! Signature recovery through the `ecrecover` precompile.
+//!
+//! The precompile accepts more than a contract should: `recover` also insists
+//! on a recovery id of 27 or 28 (0 and 1 are shifted up, as wallets produce
+//! both) and rejects malleable signatures whose `s` lies in the upper half of
+//! the curve order, as EIP-2 does for transactions. The precompile returns
+//! nothing, rather than reverting, for an unrecoverable signature; `recover`
+//! reports that as `Unrecoverable` instead of the zero address.
+//!
+//! Contracts whose signers use `personal_sign` hash their message to 32 bytes
+//! and pass it through `eip191_digest` before recovering.
+//!
+//! `TestVM` cannot recover signatures, so tests mock the precompile with
+//! `vm.mock_static_call(ECRECOVER, input, Ok(signer.into_word().to_vec()))`,
+//! where `input` is `digest ‖ v ‖ r ‖ s`, each padded to 32 bytes.
+
+use alloc::vec::Vec;
+use alloy_primitives::{uint, Address, B256, U256};
+use stylus_sdk::{prelude::*, stylus_core::Host};
+
+/// The `ecrecover` precompile.
+pub const ECRECOVER: Address = Address::with_last_byte(1);
+
+/// Half the secp256k1 curve order; signatures with a larger `s` are malleable (EIP-2).
+pub const HALF_CURVE_ORDER: U256 =
+    uint!(0x7FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF5D576E7357A4501DDFE92F46681B20A0_U256);
+
+/// Why a signature was rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecoverError {
+    InvalidLength,
+    InvalidRecoveryId,
+    Malleable,
+    Unrecoverable,
+}
+
+impl RecoverError {
+    /// A short reason, for contracts that report signature problems as strings.
+    pub fn reason(self) -> &'static str {
+        match self {
+            RecoverError::InvalidLength => "Signature must be 65 bytes",
+            RecoverError::InvalidRecoveryId => "Invalid recovery id",
+            RecoverError::Malleable => "Malleable signature",
+            RecoverError::Unrecoverable => "Recovery failed",
+        }
+    }
+}
+
+/// The EIP-191 digest `personal_sign` produces for a 32-byte `message`:
+/// `keccak256("\x19Ethereum Signed Message:\n32" ‖ message)`.
+pub fn eip191_digest(vm: &dyn Host, message: B256) -> B256 {
+    let mut prefixed = b"\x19Ethereum Signed Message:\n32".to_vec();
+    prefixed.extend_from_slice(message.as_slice());
+    vm.native_keccak256(&prefixed)
+}
+
+/// Recovers the address that produced the 65-byte `r ‖ s ‖ v` `signature` over `digest`.
+pub fn recover(vm: &dyn Host, digest: B256, signature: &[u8]) -> Result<Address, RecoverError> {
+    let [rs @ .., v] =
+        <[u8; 65]>::try_from(signature).map_err(|_| RecoverError::InvalidLength)?;
+    let (r, s) = rs.split_at(32);
+    recover_parts(vm, digest, v, B256::from_slice(r), B256::from_slice(s))
+}
+
+/// Recovers the address that signed `digest`, from a signature already split into
+/// `v`, `r` and `s` as EIP-2612 `permit` takes it.
+pub fn recover_parts(
+    vm: &dyn Host,
+    digest: B256,
+    v: u8,
+    r: B256,
+    s: B256,
+) -> Result<Address, RecoverError> {
+    let v = if v < 27 { v + 27 } else { v };
+    if v != 27 && v != 28 {
+        return Err(RecoverError::InvalidRecoveryId);
+    }
+    if U256::from_be_bytes(s.0) > HALF_CURVE_ORDER {
+        return Err(RecoverError::Malleable);
+    }
+
+    let mut input = Vec::with_capacity(128);
+    input.extend_from_slice(digest.as_slice());
+    input.extend_from_slice(&U256::from(v).to_be_bytes::<32>());
+    input.extend_from_slice(r.as_slice());
+    input.extend_from_slice(s.as_slice());
+    let output = vm
+        .static_call(&calls::context::Call::new(), ECRECOVER, &input)
+        .map_err(|_| RecoverError::Unrecoverable)?;
+    match output.get(12..32) {
+        Some(signer) if output.len() == 32 && signer != [0u8; 20] => {
+            Ok(Address::from_slice(signer))
+        }
+        _ => Err(RecoverError::Unrecoverable),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::keccak256;
+    use stylus_sdk::testing::*;
+
+    const SIGNER: Address = Address::new([0x5a; 20]);
+    const DIGEST: B256 = B256::new([0xd1; 32]);
+
+    fn signature(v: u8) -> Vec<u8> {
+        let mut sig = vec![0x11; 64];
+        sig.push(v);
+        sig
+    }
+
+    #[test]
+    fn test_recover_checks_the_signature_before_the_precompile() {
+        let vm = TestVM::default();
+        let mut input = DIGEST.to_vec();
+        input.extend_from_slice(&U256::from(28).to_be_bytes::<32>());
+        input.extend_from_slice(&[0x11; 64]);
+        vm.mock_static_call(ECRECOVER, input, Ok(SIGNER.into_word().to_vec()));
+
+        assert_eq!(recover(&vm, DIGEST, &signature(1)), Ok(SIGNER));
+        assert_eq!(recover(&vm, DIGEST, &signature(28)), Ok(SIGNER));
+        assert_eq!(
+            recover(&vm, DIGEST, &signature(29)),
+            Err(RecoverError::InvalidRecoveryId)
+        );
+        assert_eq!(
+            recover(&vm, DIGEST, &[0x11; 64]),
+            Err(RecoverError::InvalidLength)
+        );
+        let high_s = B256::from((HALF_CURVE_ORDER + U256::from(1)).to_be_bytes::<32>());
+        assert_eq!(
+            recover_parts(&vm, DIGEST, 28, B256::new([0x11; 32]), high_s),
+            Err(RecoverError::Malleable)
+        );
+        // Unmocked, the precompile returns nothing, as it does for a bad signature.
+        assert_eq!(
+            recover(&vm, DIGEST, &signature(27)),
+            Err(RecoverError::Unrecoverable)
+        );
+    }
+
+    #[test]
+    fn test_eip191_digest_prefixes_the_message() {
+        let vm = TestVM::default();
+        let expected = keccak256(
+            [&b"\x19Ethereum Signed Message:\n32"[..], DIGEST.as_slice()].concat(),
+        );
+        assert_eq!(eip191_digest(&vm, DIGEST), expected);
+    }
+}
@@ -0,0 +1,46 @@
+//! Tiered fee schedules.
+//!
+//! A schedule is a list of `(cap, price)` tiers with strictly increasing caps:
+//! while fewer than `cap` paid spots are taken, the next spot costs `price`.
+//! The last cap is the total number of paid spots. A contract keeps the caps
+//! and prices in its own storage and checks them with `validate` when they are
+//! set.
+
+use stylus_sdk::alloy_primitives::U256;
+
+/// Most tiers a schedule may have.
+pub const MAX_TIERS: usize = 8;
+
+/// Why a schedule was rejected.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ScheduleError {
+    LengthMismatch,
+    TooManyTiers,
+    CapsNotIncreasing,
+}
+
+/// Checks that `caps` and `prices` form a valid schedule.
+pub fn validate(caps: &[U256], prices: &[U256]) -> Result<(), ScheduleError> {
+    if caps.len() != prices.len() {
+        return Err(ScheduleError::LengthMismatch);
+    }
+    if caps.len() > MAX_TIERS {
+        return Err(ScheduleError::TooManyTiers);
+    }
+    let mut previous = U256::ZERO;
+    for &cap in caps {
+        if cap <= previous {
+            return Err(ScheduleError::CapsNotIncreasing);
+        }
+        previous = cap;
+    }
+    Ok(())
+}
+
+/// Price of the next paid spot once `taken` are gone, or `None` when every tier is full.
+pub fn price_for(caps: &[U256], prices: &[U256], taken: U256) -> Option<U256> {
+    caps.iter()
+        .zip(prices)
+        .find(|(&cap, _)| taken < cap)
+        .map(|(_, &price)| price)
+}
@@ -0,0 +1,110 @@
+//! ERC-165 interface discovery.
+//!
+//! An interface id is the XOR of the selectors of every function in the
+//! interface, as Solidity's `type(I).interfaceId` computes it. `interface_id`
+//! does the same at compile time from the selectors `sol!` generates, so a
+//! contract's `supports_interface` compares against constants:
+//!
+//! ```ignore
+//! pub fn supports_interface(&self, interface_id: FixedBytes<4>) -> bool {
+//!     [ERC165_ID, ERC20_ID].contains(&interface_id)
+//! }
+//! ```
+//!
+//! The interfaces below list only the functions each standard's id covers;
+//! `supportsInterface` itself is never part of another standard's id.
+
+use alloy_primitives::FixedBytes;
+use alloy_sol_types::sol;
+
+sol! {
+    interface IERC165 {
+        function supportsInterface(bytes4 interfaceId) external view returns (bool);
+    }
+
+    interface IERC20 {
+        function totalSupply() external view returns (uint256);
+        function balanceOf(address account) external view returns (uint256);
+        function transfer(address to, uint256 value) external returns (bool);
+        function allowance(address owner, address spender) external view returns (uint256);
+        function approve(address spender, uint256 value) external returns (bool);
+        function transferFrom(address from, address to, uint256 value) external returns (bool);
+    }
+
+    interface IERC721 {
+        function balanceOf(address owner) external view returns (uint256);
+        function ownerOf(uint256 tokenId) external view returns (address);
+        function safeTransferFrom(address from, address to, uint256 tokenId, bytes data) external;
+        function safeTransferFrom(address from, address to, uint256 tokenId) external;
+        function transferFrom(address from, address to, uint256 tokenId) external;
+        function approve(address to, uint256 tokenId) external;
+        function setApprovalForAll(address operator, bool approved) external;
+        function getApproved(uint256 tokenId) external view returns (address);
+        function isApprovedForAll(address owner, address operator) external view returns (bool);
+    }
+
+    interface IERC721Metadata {
+        function name() external view returns (string);
+        function symbol() external view returns (string);
+        function tokenURI(uint256 tokenId) external view returns (string);
+    }
+
+    interface IERC1155 {
+        function balanceOf(address account, uint256 id) external view returns (uint256);
+        function balanceOfBatch(address[] accounts, uint256[] ids) external view returns (uint256[]);
+        function setApprovalForAll(address operator, bool approved) external;
+        function isApprovedForAll(address account, address operator) external view returns (bool);
+        function safeTransferFrom(address from, address to, uint256 id, uint256 value, bytes data) external;
+        function safeBatchTransferFrom(address from, address to, uint256[] ids, uint256[] values, bytes data) external;
+    }
+
+    interface IERC1155MetadataURI {
+        function uri(uint256 id) external view returns (string);
+    }
+}
+
+/// XORs `selectors` into an ERC-165 interface id.
+pub const fn interface_id(selectors: &[[u8; 4]]) -> FixedBytes<4> {
+    let mut id = [0u8; 4];
+    let mut i = 0;
+    while i < selectors.len() {
+        let mut byte = 0;
+        while byte < 4 {
+            id[byte] ^= selectors[i][byte];
+            byte += 1;
+        }
+        i += 1;
+    }
+    FixedBytes::new(id)
+}
+
+/// `0x01ffc9a7`
+pub const ERC165_ID: FixedBytes<4> = interface_id(IERC165::IERC165Calls::SELECTORS);
+/// `0x36372b07`
+pub const ERC20_ID: FixedBytes<4> = interface_id(IERC20::IERC20Calls::SELECTORS);
+/// `0x80ac58cd`
+pub const ERC721_ID: FixedBytes<4> = interface_id(IERC721::IERC721Calls::SELECTORS);
+/// `0x5b5e139f`
+pub const ERC721_METADATA_ID: FixedBytes<4> =
+    interface_id(IERC721Metadata::IERC721MetadataCalls::SELECTORS);
+/// `0xd9b67a26`
+pub const ERC1155_ID: FixedBytes<4> = interface_id(IERC1155::IERC1155Calls::SELECTORS);
+/// `0x0e89341c`
+pub const ERC1155_METADATA_URI_ID: FixedBytes<4> =
+    interface_id(IERC1155MetadataURI::IERC1155MetadataURICalls::SELECTORS);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::fixed_bytes;
+
+    #[test]
+    fn test_interface_ids_match_the_standards() {
+        assert_eq!(ERC165_ID, fixed_bytes!("01ffc9a7"));
+        assert_eq!(ERC20_ID, fixed_bytes!("36372b07"));
+        assert_eq!(ERC721_ID, fixed_bytes!("80ac58cd"));
+        assert_eq!(ERC721_METADATA_ID, fixed_bytes!("5b5e139f"));
+        assert_eq!(ERC1155_ID, fixed_bytes!("d9b67a26"));
+        assert_eq!(ERC1155_METADATA_URI_ID, fixed_bytes!("0e89341c"));
+    }
+}
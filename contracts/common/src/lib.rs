@@ -0,0 +1,287 @@
+//!
+//! Stylus Common Components
+//!
+//! Storage components shared by the demo contracts. Each is an ordinary
+//! `sol_storage!` struct, so a contract embeds it as a field of its own
+//! storage and calls its methods through that field:
+//!
+//! ```ignore
+//! sol_storage! {
+//!     #[entrypoint]
+//!     pub struct Shop {
+//!         address owner;
+//!         Pausable pausable;
+//!         ReentrancyGuard reentrancy;
+//!     }
+//! }
+//! ```
+//!
+//! An embedded struct starts on a fresh storage slot and takes as many slots
+//! as its own fields need, exactly like a struct inside a Solidity contract.
+//! The components only hold state and enforce it; access control stays with
+//! the contract, which decides who may pause.
+//!
+//! `RateLimiter` caps how much of something, usually wei, can be consumed per
+//! fixed window of time. The contract configures the limit and window and
+//! calls `consume` before acting; a fresh window opens on the first use after
+//! the previous one ends.
+//!
+//! The `introspection` module adds ERC-165 interface ids computed at compile
+//! time, for contracts that implement `supportsInterface`, and the `ecdsa`
+//! module builds EIP-191 digests and recovers signers through the `ecrecover`
+//! precompile.
+//!
+//! `merkle` verifies sorted-pair Merkle proofs and `fees` prices spots from a
+//! tiered schedule. `paging` sizes the pages of list views, and `token` makes
+//! ERC-20 transfers that tolerate tokens returning nothing. All four are plain
+//! functions, with no storage.
+//!
+//! Errors use OpenZeppelin's names (`EnforcedPause`,
+//! `ReentrancyGuardReentrantCall`) where it has one, and contracts wrap them
+//! in their own `SolidityError` enums, or map them to an error of their own.
+//!
+//! Note: this code is a template-only and has not been audited.
+//!
+#![cfg_attr(not(test), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+pub mod ecdsa;
+pub mod fees;
+pub mod introspection;
+pub mod merkle;
+pub mod paging;
+pub mod token;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{U256, U64},
+    prelude::*,
+};
+
+sol! {
+    event Paused(address account);
+    event Unpaused(address account);
+
+    #[derive(Debug)]
+    error EnforcedPause();
+    #[derive(Debug)]
+    error ReentrancyGuardReentrantCall();
+    #[derive(Debug)]
+    error RateLimitExceeded(uint256 requested, uint256 available, uint64 resetsAt);
+}
+
+sol_storage! {
+    /// An on/off switch for the parts of a contract that should stop in an emergency.
+    pub struct Pausable {
+        bool paused;
+    }
+
+    /// Rejects calls that re-enter a guarded function before it has returned.
+    pub struct ReentrancyGuard {
+        bool entered;
+    }
+
+    /// Allows at most `limit` units per `window_seconds`-long window.
+    pub struct RateLimiter {
+        uint256 limit;
+        uint64 window_seconds;
+        /// Start of the current window; zero before the first use.
+        uint64 window_start;
+        /// Units consumed in the current window.
+        uint256 used;
+    }
+}
+
+impl Pausable {
+    pub fn paused(&self) -> bool {
+        self.paused.get()
+    }
+
+    /// Fails with `EnforcedPause` while paused.
+    pub fn when_not_paused(&self) -> Result<(), EnforcedPause> {
+        if self.paused.get() {
+            return Err(EnforcedPause {});
+        }
+        Ok(())
+    }
+
+    /// Pauses and emits `Paused` with the caller. The contract checks who may call it.
+    pub fn pause(&mut self) {
+        self.paused.set(true);
+        log(
+            self.vm(),
+            Paused {
+                account: self.vm().msg_sender(),
+            },
+        );
+    }
+
+    /// Unpauses and emits `Unpaused` with the caller. The contract checks who may call it.
+    pub fn unpause(&mut self) {
+        self.paused.set(false);
+        log(
+            self.vm(),
+            Unpaused {
+                account: self.vm().msg_sender(),
+            },
+        );
+    }
+}
+
+impl ReentrancyGuard {
+    /// Marks a guarded function as running; fails if one already is.
+    ///
+    /// Every successful `enter` must be followed by `exit` on all paths, so
+    /// guarded functions usually delegate to an inner function:
+    ///
+    /// ```ignore
+    /// self.reentrancy.enter()?;
+    /// let result = self.withdraw_inner();
+    /// self.reentrancy.exit();
+    /// result
+    /// ```
+    pub fn enter(&mut self) -> Result<(), ReentrancyGuardReentrantCall> {
+        if self.entered.get() {
+            return Err(ReentrancyGuardReentrantCall {});
+        }
+        self.entered.set(true);
+        Ok(())
+    }
+
+    pub fn exit(&mut self) {
+        self.entered.set(false);
+    }
+
+    pub fn entered(&self) -> bool {
+        self.entered.get()
+    }
+}
+
+impl RateLimiter {
+    /// Sets the limit and window length. Units already consumed in the current window
+    /// still count against the new limit.
+    pub fn configure(&mut self, limit: U256, window_seconds: u64) {
+        self.limit.set(limit);
+        self.window_seconds.set(U64::from(window_seconds));
+    }
+
+    pub fn limit(&self) -> U256 {
+        self.limit.get()
+    }
+
+    pub fn window_seconds(&self) -> u64 {
+        self.window_seconds.get().to()
+    }
+
+    /// Units that can still be consumed right now.
+    pub fn available(&self) -> U256 {
+        if self.window_ended() {
+            return self.limit.get();
+        }
+        self.limit.get().saturating_sub(self.used.get())
+    }
+
+    /// When the full limit is available again; zero if it already is.
+    pub fn resets_at(&self) -> u64 {
+        if self.window_ended() || self.used.get().is_zero() {
+            return 0;
+        }
+        self.window_end()
+    }
+
+    /// Counts `amount` against the current window, opening a new one if the last has ended.
+    /// Fails with `RateLimitExceeded` and consumes nothing if `amount` exceeds `available`.
+    pub fn consume(&mut self, amount: U256) -> Result<(), RateLimitExceeded> {
+        let available = self.available();
+        if amount > available {
+            return Err(RateLimitExceeded {
+                requested: amount,
+                available,
+                resetsAt: self.resets_at(),
+            });
+        }
+        if self.window_ended() {
+            let now = self.vm().block_timestamp();
+            self.window_start.set(U64::from(now));
+            self.used.set(amount);
+        } else {
+            self.used.set(self.used.get() + amount);
+        }
+        Ok(())
+    }
+
+    fn window_end(&self) -> u64 {
+        let start = self.window_start.get().to::<u64>();
+        start.saturating_add(self.window_seconds())
+    }
+
+    fn window_ended(&self) -> bool {
+        self.window_start.get().is_zero() || self.vm().block_timestamp() >= self.window_end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::Address;
+    use alloy_sol_types::SolEvent;
+    use stylus_sdk::testing::*;
+
+    #[test]
+    fn test_pausable_toggles_and_logs() {
+        let vm = TestVM::default();
+        let caller = Address::from([0x01; 20]);
+        vm.set_sender(caller);
+        let mut pausable = Pausable::from(&vm);
+        assert!(pausable.when_not_paused().is_ok());
+
+        pausable.pause();
+        assert!(pausable.paused());
+        assert!(pausable.when_not_paused().is_err());
+        pausable.unpause();
+        assert!(!pausable.paused());
+
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.len(), 2);
+        let paused = Paused::decode_raw_log(logs[0].0.iter().copied(), &logs[0].1, true).unwrap();
+        assert_eq!(paused.account, caller);
+        assert_eq!(logs[1].0[0], Unpaused::SIGNATURE_HASH);
+    }
+
+    #[test]
+    fn test_reentrancy_guard_rejects_nested_entry() {
+        let vm = TestVM::default();
+        let mut guard = ReentrancyGuard::from(&vm);
+        guard.enter().unwrap();
+        assert!(guard.entered());
+        assert!(guard.enter().is_err());
+        guard.exit();
+        guard.enter().unwrap();
+    }
+
+    #[test]
+    fn test_rate_limiter_resets_each_window() {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(1_000);
+        let mut limiter = RateLimiter::from(&vm);
+        limiter.configure(U256::from(100), 60);
+        assert_eq!(limiter.available(), U256::from(100));
+        assert_eq!(limiter.resets_at(), 0);
+
+        limiter.consume(U256::from(70)).unwrap();
+        vm.set_block_timestamp(1_059);
+        let err = limiter.consume(U256::from(31)).unwrap_err();
+        assert_eq!((err.available, err.resetsAt), (U256::from(30), 1_060));
+        limiter.consume(U256::from(30)).unwrap();
+        assert_eq!(limiter.available(), U256::ZERO);
+        assert_eq!(limiter.resets_at(), 1_060);
+
+        vm.set_block_timestamp(1_060);
+        assert_eq!(limiter.available(), U256::from(100));
+        limiter.consume(U256::from(100)).unwrap();
+        assert_eq!(limiter.resets_at(), 1_120);
+    }
+}
@@ -0,0 +1,47 @@
+//! Merkle proof verification with sorted-pair hashing.
+//!
+//! Each internal node is `keccak256(min(a, b) || max(a, b))`, so a proof is
+//! just the list of sibling hashes from leaf to root with no left/right flags.
+//! Internal nodes are hashed the same way as in OpenZeppelin's `MerkleProof`.
+//!
+//! How leaves are hashed is up to each contract. A leaf preimage should not be
+//! 64 bytes long, or it could be mistaken for a pair of nodes.
+
+use stylus_sdk::{alloy_primitives::B256, crypto::keccak};
+
+/// Hashes two nodes in sorted order.
+pub fn hash_pair(a: B256, b: B256) -> B256 {
+    let (low, high) = if a <= b { (a, b) } else { (b, a) };
+    let mut preimage = [0u8; 64];
+    preimage[..32].copy_from_slice(low.as_slice());
+    preimage[32..].copy_from_slice(high.as_slice());
+    keccak(preimage)
+}
+
+/// Root reached by folding `proof` into `leaf`.
+pub fn process_proof(proof: &[B256], leaf: B256) -> B256 {
+    proof
+        .iter()
+        .fold(leaf, |node, &sibling| hash_pair(node, sibling))
+}
+
+/// Whether `proof` shows that `leaf` is in the tree with `root`.
+pub fn verify(proof: &[B256], root: B256, leaf: B256) -> bool {
+    process_proof(proof, leaf) == root
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_proof_order_does_not_matter_for_a_pair() {
+        let (a, b) = (B256::repeat_byte(0x01), B256::repeat_byte(0x02));
+        let root = hash_pair(a, b);
+        assert_eq!(root, hash_pair(b, a));
+        assert!(verify(&[b], root, a));
+        assert!(verify(&[a], root, b));
+        assert!(!verify(&[a], root, a));
+        assert!(verify(&[], a, a));
+    }
+}
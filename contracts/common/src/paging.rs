@@ -0,0 +1,33 @@
+//! Offset/limit paging for list views.
+//!
+//! A view returning a whole storage vector costs more gas as the vector
+//! grows, until no node will serve it. List views therefore take an `offset`
+//! and a `limit` and return one page. `page` turns those into the range of
+//! indices to read: `limit` is capped at the contract's largest page, and an
+//! offset past the end gives an empty page rather than an error, so a client
+//! can keep paging until a page comes back short.
+
+use alloy_primitives::U256;
+use core::ops::Range;
+
+/// Indices of the page of up to `limit` items, and at most `max_page`, starting at
+/// `offset` in a list of `len` items.
+pub fn page(len: usize, offset: U256, limit: U256, max_page: u64) -> Range<usize> {
+    let start = offset.saturating_to::<usize>().min(len);
+    let count = limit.saturating_to::<usize>().min(max_page as usize);
+    start..start.saturating_add(count).min(len)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_page_is_capped_and_clamped() {
+        let n = U256::from;
+        assert_eq!(page(10, n(2), n(3), 100), 2..5);
+        assert_eq!(page(10, n(8), n(5), 100), 8..10);
+        assert_eq!(page(10, n(4), U256::MAX, 3), 4..7);
+        assert_eq!(page(10, U256::MAX, n(5), 100), 10..10);
+    }
+}
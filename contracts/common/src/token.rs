@@ -0,0 +1,85 @@
+//! ERC-20 transfers that also accept non-standard tokens.
+//!
+//! ERC-20 says `transfer` and `transferFrom` return `true`, but some widely
+//! used tokens, USDT among them, return nothing at all. Like OpenZeppelin's
+//! `SafeERC20`, `safe_transfer` and `safe_transfer_from` accept an empty
+//! return as success and fail on a revert, a `false`, or return data that is
+//! not a `bool`. The caller maps `TokenTransferFailed` to its own error, which
+//! usually names the token and amount.
+
+use alloy_primitives::{Address, U256};
+use alloy_sol_types::{sol, sol_data, SolCall, SolType};
+use stylus_sdk::{prelude::*, stylus_core::Host};
+
+sol! {
+    interface IERC20Transfers {
+        function transfer(address to, uint256 value) external returns (bool);
+        function transferFrom(address from, address to, uint256 value) external returns (bool);
+    }
+}
+
+/// A token transfer reverted or reported failure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenTransferFailed;
+
+/// Sends `value` of `token` from the calling contract to `to`.
+pub fn safe_transfer(
+    vm: &dyn Host,
+    token: Address,
+    to: Address,
+    value: U256,
+) -> Result<(), TokenTransferFailed> {
+    let call = IERC20Transfers::transferCall { to, value };
+    call_token(vm, token, &call.abi_encode())
+}
+
+/// Moves `value` of `token` from `from` to `to`, using the calling contract's allowance.
+pub fn safe_transfer_from(
+    vm: &dyn Host,
+    token: Address,
+    from: Address,
+    to: Address,
+    value: U256,
+) -> Result<(), TokenTransferFailed> {
+    let call = IERC20Transfers::transferFromCall { from, to, value };
+    call_token(vm, token, &call.abi_encode())
+}
+
+fn call_token(vm: &dyn Host, token: Address, data: &[u8]) -> Result<(), TokenTransferFailed> {
+    let returned = vm
+        .call(&calls::context::Call::new(), token, data)
+        .map_err(|_| TokenTransferFailed)?;
+    if returned.is_empty() {
+        return Ok(());
+    }
+    match <sol_data::Bool as SolType>::abi_decode(&returned, true) {
+        Ok(true) => Ok(()),
+        _ => Err(TokenTransferFailed),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    const TOKEN: Address = Address::new([0x70; 20]);
+    const TO: Address = Address::new([0x02; 20]);
+
+    #[test]
+    fn test_safe_transfer_accepts_true_or_nothing() {
+        let vm = TestVM::default();
+        let value = U256::from(5);
+        let call = IERC20Transfers::transferCall { to: TO, value }.abi_encode();
+        let encode = |ok: bool| <sol_data::Bool as SolType>::abi_encode(&ok);
+
+        vm.mock_call(TOKEN, call.clone(), Ok(encode(true)));
+        assert_eq!(safe_transfer(&vm, TOKEN, TO, value), Ok(()));
+        vm.mock_call(TOKEN, call.clone(), Ok(Vec::new()));
+        assert_eq!(safe_transfer(&vm, TOKEN, TO, value), Ok(()));
+        vm.mock_call(TOKEN, call.clone(), Ok(encode(false)));
+        assert_eq!(safe_transfer(&vm, TOKEN, TO, value), Err(TokenTransferFailed));
+        vm.mock_call(TOKEN, call, Err(b"paused".to_vec()));
+        assert_eq!(safe_transfer(&vm, TOKEN, TO, value), Err(TokenTransferFailed));
+    }
+}
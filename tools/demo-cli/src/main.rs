@@ -0,0 +1,160 @@
+//!
+//! Command-line access to deployed demo contracts
+//!
+//! Signs and sends transactions, or makes read-only calls, through Foundry's
+//! `cast`. Deployments are configured through the environment:
+//!
+//! - `ETH_RPC_URL`: RPC endpoint, e.g. `http://localhost:8547`
+//! - `PRIVATE_KEY`: signer used for transactions and as the default user
+//! - `VENDING_MACHINE_ADDRESS`, `DATA_VALIDATOR_ADDRESS`: deployed contracts
+//!
+
+use std::process::Command;
+
+const USAGE: &str = "usage: demo-cli <command> [args]
+
+commands:
+  vend                   get a cupcake from the vending machine
+  balance-of [address]   cupcake balance (defaults to the signer)
+  submit-data <value>    submit a decimal string to the data validator
+  get-last [address]     last valid submission (defaults to the signer)";
+
+/// What a subcommand turns into: a transaction or a call against one contract.
+#[derive(Debug, PartialEq, Eq)]
+struct Invocation {
+    contract_env: &'static str,
+    send: bool,
+    signature: &'static str,
+    args: Vec<String>,
+}
+
+/// Maps a subcommand to an invocation. `signer` is used when an address is omitted.
+fn invocation(
+    args: &[String],
+    signer: impl FnOnce() -> Result<String, String>,
+) -> Result<Invocation, String> {
+    let (command, rest) = args.split_first().ok_or(USAGE)?;
+    let address = |rest: &[String]| match rest.first() {
+        Some(address) => Ok(address.clone()),
+        None => signer(),
+    };
+    let invocation = match command.as_str() {
+        "vend" => Invocation {
+            contract_env: "VENDING_MACHINE_ADDRESS",
+            send: true,
+            signature: "vend()",
+            args: vec![],
+        },
+        "balance-of" => Invocation {
+            contract_env: "VENDING_MACHINE_ADDRESS",
+            send: false,
+            signature: "balanceOf(address)(uint256)",
+            args: vec![address(rest)?],
+        },
+        "submit-data" => Invocation {
+            contract_env: "DATA_VALIDATOR_ADDRESS",
+            send: true,
+            signature: "submitData(string)",
+            args: vec![rest.first().ok_or("submit-data needs a value")?.clone()],
+        },
+        "get-last" => Invocation {
+            contract_env: "DATA_VALIDATOR_ADDRESS",
+            send: false,
+            signature: "getLastSubmission(address)(string)",
+            args: vec![address(rest)?],
+        },
+        other => return Err(format!("unknown command `{other}`\n{USAGE}")),
+    };
+    Ok(invocation)
+}
+
+fn env(name: &str) -> Result<String, String> {
+    std::env::var(name).map_err(|_| format!("{name} is not set"))
+}
+
+fn cast(args: &[String]) -> Result<String, String> {
+    let output = Command::new("cast")
+        .args(args)
+        .output()
+        .map_err(|err| format!("failed to run `cast`: {err}"))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+fn run(args: &[String]) -> Result<String, String> {
+    let signer = || {
+        cast(&[
+            "wallet".to_string(),
+            "address".to_string(),
+            "--private-key".to_string(),
+            env("PRIVATE_KEY")?,
+        ])
+    };
+    let invocation = invocation(args, signer)?;
+
+    let mut cast_args = vec![
+        if invocation.send { "send" } else { "call" }.to_string(),
+        "--rpc-url".to_string(),
+        env("ETH_RPC_URL")?,
+    ];
+    if invocation.send {
+        cast_args.extend(["--private-key".to_string(), env("PRIVATE_KEY")?]);
+    }
+    cast_args.push(env(invocation.contract_env)?);
+    cast_args.push(invocation.signature.to_string());
+    cast_args.extend(invocation.args);
+    cast(&cast_args)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(output) => println!("{output}"),
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn signer() -> Result<String, String> {
+        Ok("0xsigner".to_string())
+    }
+
+    #[test]
+    fn test_vend_is_a_transaction() {
+        let vend = invocation(&args(&["vend"]), signer).unwrap();
+        assert!(vend.send);
+        assert_eq!(vend.signature, "vend()");
+        assert_eq!(vend.contract_env, "VENDING_MACHINE_ADDRESS");
+    }
+
+    #[test]
+    fn test_views_default_to_signer() {
+        let balance = invocation(&args(&["balance-of"]), signer).unwrap();
+        assert!(!balance.send);
+        assert_eq!(balance.args, args(&["0xsigner"]));
+
+        let last = invocation(&args(&["get-last", "0xuser"]), signer).unwrap();
+        assert_eq!(last.args, args(&["0xuser"]));
+        assert_eq!(last.contract_env, "DATA_VALIDATOR_ADDRESS");
+    }
+
+    #[test]
+    fn test_rejects_missing_and_unknown_commands() {
+        assert!(invocation(&args(&["submit-data"]), signer).is_err());
+        assert!(invocation(&args(&["withdraw"]), signer).is_err());
+        assert!(invocation(&[], signer).is_err());
+    }
+}
@@ -0,0 +1,464 @@
+//!
+//! Multi-contract deployment from a manifest
+//!
+//! Deploys a set of contracts that refer to each other, described by a
+//! line-based manifest:
+//!
+//! ```text
+//! deploy <name> <wasm-file> [constructor args...]
+//! call <address> <signature> [args...]
+//! ```
+//!
+//! Any argument may contain `${name}`, replaced by the address of the
+//! deployment called `name`, or `${deployer}`, the address of `PRIVATE_KEY`.
+//! Deployments run in dependency order, so a contract is deployed after every
+//! contract its constructor arguments refer to, and ties keep manifest order.
+//! Calls wire up contracts that can only learn each other's address after
+//! both exist, and run in manifest order once everything is deployed.
+//!
+//! ```text
+//! deploy [--manifest <path>] [--state <path>]
+//! ```
+//!
+//! `ETH_RPC_URL` and `PRIVATE_KEY` select the network and the key.
+//! Deployments go through `cargo stylus deploy` and calls through Foundry's
+//! `cast send`. Addresses are saved to the state file after every step, so
+//! rerunning the same command after a failure resumes where it stopped.
+//!
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::process::Command;
+
+const USAGE: &str = "usage: deploy [--manifest <path>] [--state <path>]";
+
+/// Placeholder for the address of `PRIVATE_KEY`.
+const DEPLOYER: &str = "deployer";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Deployment {
+    name: String,
+    wasm_file: String,
+    args: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Call {
+    target: String,
+    signature: String,
+    args: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Manifest {
+    deployments: Vec<Deployment>,
+    calls: Vec<Call>,
+}
+
+/// Parses a manifest; blank lines and lines starting with `#` are skipped.
+fn parse_manifest(text: &str) -> Result<Manifest, String> {
+    let mut manifest = Manifest::default();
+    for (index, line) in text.lines().enumerate() {
+        let line_no = index + 1;
+        let words: Vec<String> = line.split_whitespace().map(str::to_string).collect();
+        match words.first().map(String::as_str) {
+            None => {}
+            Some(word) if word.starts_with('#') => {}
+            Some("deploy") => {
+                let [_, name, wasm_file, args @ ..] = words.as_slice() else {
+                    return Err(format!(
+                        "line {line_no}: expected `deploy <name> <wasm-file>`"
+                    ));
+                };
+                let valid = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                if !valid || name == DEPLOYER || name == "calls_done" {
+                    return Err(format!("line {line_no}: invalid deployment name `{name}`"));
+                }
+                if manifest.deployments.iter().any(|d| &d.name == name) {
+                    return Err(format!("line {line_no}: `{name}` is deployed twice"));
+                }
+                manifest.deployments.push(Deployment {
+                    name: name.clone(),
+                    wasm_file: wasm_file.clone(),
+                    args: args.to_vec(),
+                });
+            }
+            Some("call") => {
+                let [_, target, signature, args @ ..] = words.as_slice() else {
+                    return Err(format!(
+                        "line {line_no}: expected `call <address> <signature>`"
+                    ));
+                };
+                manifest.calls.push(Call {
+                    target: target.clone(),
+                    signature: signature.clone(),
+                    args: args.to_vec(),
+                });
+            }
+            Some(word) => return Err(format!("line {line_no}: unknown directive `{word}`")),
+        }
+    }
+    let known =
+        |name: &str| name == DEPLOYER || manifest.deployments.iter().any(|d| d.name == name);
+    let args = manifest.deployments.iter().flat_map(|d| &d.args).chain(
+        manifest
+            .calls
+            .iter()
+            .flat_map(|c| c.args.iter().chain([&c.target])),
+    );
+    for arg in args {
+        if let Some(name) = references(arg)?.into_iter().find(|name| !known(name)) {
+            return Err(format!("`${{{name}}}` does not name a deployment"));
+        }
+    }
+    Ok(manifest)
+}
+
+/// The names an argument refers to with `${name}`.
+fn references(arg: &str) -> Result<Vec<&str>, String> {
+    let mut names = Vec::new();
+    let mut rest = arg;
+    while let Some(start) = rest.find("${") {
+        let end = rest[start..]
+            .find('}')
+            .ok_or(format!("unclosed `${{` in `{arg}`"))?;
+        names.push(&rest[start + 2..start + end]);
+        rest = &rest[start + end + 1..];
+    }
+    Ok(names)
+}
+
+/// Replaces every `${name}` in `arg` with its address.
+fn substitute(arg: &str, addresses: &BTreeMap<String, String>) -> Result<String, String> {
+    let mut out = arg.to_string();
+    for name in references(arg)? {
+        let address = addresses
+            .get(name)
+            .ok_or(format!("`{name}` has not been deployed"))?;
+        out = out.replace(&format!("${{{name}}}"), address);
+    }
+    Ok(out)
+}
+
+/// Indices of the deployments, each after every deployment it refers to.
+fn deploy_order(deployments: &[Deployment]) -> Result<Vec<usize>, String> {
+    let depends_on = |index: usize| -> Result<Vec<usize>, String> {
+        let mut deps = Vec::new();
+        for arg in &deployments[index].args {
+            for name in references(arg)? {
+                if let Some(dep) = deployments.iter().position(|d| d.name == name) {
+                    deps.push(dep);
+                }
+            }
+        }
+        Ok(deps)
+    };
+    let deps = (0..deployments.len())
+        .map(depends_on)
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut order = Vec::with_capacity(deployments.len());
+    while order.len() < deployments.len() {
+        // The first deployment, in manifest order, whose dependencies are all placed.
+        let next = (0..deployments.len())
+            .find(|i| !order.contains(i) && deps[*i].iter().all(|dep| order.contains(dep)));
+        match next {
+            Some(index) => order.push(index),
+            None => {
+                let stuck: Vec<&str> = (0..deployments.len())
+                    .filter(|i| !order.contains(i))
+                    .map(|i| deployments[i].name.as_str())
+                    .collect();
+                return Err(format!("dependency cycle between {}", stuck.join(", ")));
+            }
+        }
+    }
+    Ok(order)
+}
+
+/// How far a deployment has got; saved between runs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct State {
+    addresses: BTreeMap<String, String>,
+    calls_done: usize,
+}
+
+fn load_state(path: &str) -> State {
+    let text = fs::read_to_string(path).unwrap_or_default();
+    let mut state = State::default();
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once(' ') else {
+            continue;
+        };
+        match key {
+            "calls_done" => state.calls_done = value.parse().unwrap_or(0),
+            name => {
+                state.addresses.insert(name.to_string(), value.to_string());
+            }
+        }
+    }
+    state
+}
+
+fn save_state(path: &str, state: &State) -> Result<(), String> {
+    let mut text = String::new();
+    for (name, address) in &state.addresses {
+        text.push_str(&format!("{name} {address}\n"));
+    }
+    text.push_str(&format!("calls_done {}\n", state.calls_done));
+    fs::write(path, text).map_err(|err| format!("failed to write {path}: {err}"))
+}
+
+fn is_address(word: &str) -> bool {
+    word.len() == 42 && word.starts_with("0x") && word[2..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Finds the contract address in `cargo stylus deploy` output.
+fn deployed_address(output: &str) -> Option<String> {
+    output
+        .lines()
+        .filter(|line| line.contains("deployed code at address"))
+        .flat_map(str::split_whitespace)
+        .find(|word| is_address(word))
+        .map(str::to_string)
+}
+
+fn run_program(program: &str, args: &[String]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|err| format!("failed to run `{program}`: {err}"))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+fn env(name: &str) -> Result<String, String> {
+    std::env::var(name).map_err(|_| format!("{name} is not set"))
+}
+
+struct Args {
+    manifest_path: String,
+    state_path: String,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut manifest_path = "tools/deploy/demo.manifest".to_string();
+    let mut state_path = "target/deploy-state.txt".to_string();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or(format!("missing value for {arg}\n{USAGE}"))?;
+        match arg.as_str() {
+            "--manifest" => manifest_path = value.clone(),
+            "--state" => state_path = value.clone(),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    Ok(Args {
+        manifest_path,
+        state_path,
+    })
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let args = parse_args(args)?;
+    let text = fs::read_to_string(&args.manifest_path)
+        .map_err(|err| format!("failed to read {}: {err}", args.manifest_path))?;
+    let manifest = parse_manifest(&text)?;
+    let order = deploy_order(&manifest.deployments)?;
+    let rpc_url = env("ETH_RPC_URL")?;
+    let private_key = env("PRIVATE_KEY")?;
+    let deployer = run_program(
+        "cast",
+        &[
+            "wallet".to_string(),
+            "address".to_string(),
+            "--private-key".to_string(),
+            private_key.clone(),
+        ],
+    )?;
+    let mut state = load_state(&args.state_path);
+    state.addresses.insert(DEPLOYER.to_string(), deployer);
+
+    for deployment in order.into_iter().map(|index| &manifest.deployments[index]) {
+        if let Some(address) = state.addresses.get(&deployment.name) {
+            println!("{} already deployed at {address}", deployment.name);
+            continue;
+        }
+        let mut command = vec![
+            "stylus".to_string(),
+            "deploy".to_string(),
+            "--endpoint".to_string(),
+            rpc_url.clone(),
+            "--private-key".to_string(),
+            private_key.clone(),
+            "--wasm-file".to_string(),
+            deployment.wasm_file.clone(),
+        ];
+        if !deployment.args.is_empty() {
+            command.push("--constructor-args".to_string());
+            for arg in &deployment.args {
+                command.push(substitute(arg, &state.addresses)?);
+            }
+        }
+        let output = run_program("cargo", &command)?;
+        let address = deployed_address(&output)
+            .ok_or("could not find the deployed address in `cargo stylus` output")?;
+        println!("deployed {} at {address}", deployment.name);
+        state.addresses.insert(deployment.name.clone(), address);
+        save_state(&args.state_path, &state)?;
+    }
+
+    for call in manifest.calls.iter().skip(state.calls_done) {
+        let target = substitute(&call.target, &state.addresses)?;
+        let mut command = vec![
+            "send".to_string(),
+            "--rpc-url".to_string(),
+            rpc_url.clone(),
+            "--private-key".to_string(),
+            private_key.clone(),
+            target.clone(),
+            call.signature.clone(),
+        ];
+        for arg in &call.args {
+            command.push(substitute(arg, &state.addresses)?);
+        }
+        run_program("cast", &command)?;
+        println!("called {} on {target}", call.signature);
+        state.calls_done += 1;
+        save_state(&args.state_path, &state)?;
+    }
+
+    println!("deployment complete");
+    for deployment in &manifest.deployments {
+        println!("{} {}", deployment.name, state.addresses[&deployment.name]);
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(err) = run(&args) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn names(manifest: &Manifest, order: &[usize]) -> Vec<String> {
+        order
+            .iter()
+            .map(|i| manifest.deployments[*i].name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_demo_manifest_deploys_dependencies_first() {
+        let manifest = parse_manifest(include_str!("../demo.manifest")).unwrap();
+        let order = deploy_order(&manifest.deployments).unwrap();
+        assert_eq!(
+            names(&manifest, &order),
+            [
+                "vending_machine",
+                "cupcake_token",
+                "data_validator",
+                "price_feed"
+            ]
+        );
+        assert_eq!(manifest.calls.len(), 1);
+        assert_eq!(manifest.calls[0].signature, "setCupcakeToken(address)");
+    }
+
+    #[test]
+    fn test_order_follows_references_not_manifest_order() {
+        let manifest = parse_manifest(
+            "deploy consumer c.wasm ${feed} 3600\n\
+             deploy vending v.wasm ${deployer} ${token}\n\
+             deploy token t.wasm ${deployer}\n\
+             deploy feed f.wasm ${deployer}\n",
+        )
+        .unwrap();
+        let order = deploy_order(&manifest.deployments).unwrap();
+        assert_eq!(
+            names(&manifest, &order),
+            ["token", "vending", "feed", "consumer"]
+        );
+    }
+
+    #[test]
+    fn test_cycles_and_unknown_names_are_rejected() {
+        let manifest = parse_manifest(
+            "deploy a a.wasm ${b}\n\
+             deploy b b.wasm ${a}\n\
+             deploy c c.wasm\n",
+        )
+        .unwrap();
+        assert_eq!(
+            deploy_order(&manifest.deployments),
+            Err("dependency cycle between a, b".to_string())
+        );
+        assert_eq!(
+            parse_manifest("deploy a a.wasm ${missing}"),
+            Err("`${missing}` does not name a deployment".to_string())
+        );
+        assert_eq!(
+            parse_manifest("deploy a a.wasm\ncall ${nope} f()"),
+            Err("`${nope}` does not name a deployment".to_string())
+        );
+        assert!(parse_manifest("deploy a a.wasm\ndeploy a b.wasm").is_err());
+        assert!(parse_manifest("deploy deployer a.wasm").is_err());
+        assert!(parse_manifest("deploy a").is_err());
+        assert!(parse_manifest("upgrade a a.wasm").is_err());
+        assert!(parse_manifest("deploy a a.wasm ${b").is_err());
+    }
+
+    #[test]
+    fn test_substitute_replaces_every_reference() {
+        let addresses = BTreeMap::from([
+            ("deployer".to_string(), "0xaa".to_string()),
+            ("token".to_string(), "0xbb".to_string()),
+        ]);
+        assert_eq!(substitute("${token}", &addresses).unwrap(), "0xbb");
+        assert_eq!(
+            substitute("[${deployer},${token}]", &addresses).unwrap(),
+            "[0xaa,0xbb]"
+        );
+        assert_eq!(substitute("3600", &addresses).unwrap(), "3600");
+        assert!(substitute("${vending}", &addresses).is_err());
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let path = std::env::temp_dir().join("deploy-state-test.txt");
+        let path = path.to_str().unwrap();
+        assert_eq!(load_state(path), State::default());
+        let state = State {
+            addresses: BTreeMap::from([(
+                "token".to_string(),
+                "0x33f4b2f0c1d5cd0e5b4a2bf3c1a1f0a0b0c0d0e0".to_string(),
+            )]),
+            calls_done: 2,
+        };
+        save_state(path, &state).unwrap();
+        assert_eq!(load_state(path), state);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_deployed_address_from_stylus_output() {
+        let output = "stripped custom section from user wasm\n\
+                      deployed code at address: 0x33f4b2f0c1d5cd0e5b4a2bf3c1a1f0a0b0c0d0e0\n\
+                      deployment tx hash: 0x1234";
+        assert_eq!(
+            deployed_address(output).as_deref(),
+            Some("0x33f4b2f0c1d5cd0e5b4a2bf3c1a1f0a0b0c0d0e0")
+        );
+        assert_eq!(deployed_address("error: insufficient funds"), None);
+    }
+}
@@ -0,0 +1,295 @@
+//!
+//! Local Nitro dev node bootstrap
+//!
+//! Starts an Arbitrum Nitro dev node in Docker, waits for its RPC endpoint to
+//! come up and funds a set of test accounts from the pre-funded dev key, so the
+//! demo contracts can be deployed and exercised locally.
+//!
+//! Requires `docker` and Foundry's `cast` on the `PATH`.
+//!
+//! Note: the keys below are publicly known and must never hold real funds.
+//!
+
+use std::fmt;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Docker image used when `NITRO_IMAGE` is not set.
+pub const DEFAULT_IMAGE: &str = "offchainlabs/nitro-node:v3.2.1-d81324d";
+
+/// Host port the RPC endpoint is published on.
+pub const DEFAULT_PORT: u16 = 8547;
+
+/// Host port the WebSocket endpoint is published on, for log subscriptions.
+pub const DEFAULT_WS_PORT: u16 = 8548;
+
+/// Private key of the account the dev node pre-funds at genesis.
+pub const DEV_PRIVATE_KEY: &str =
+    "0xb6b15c8cb491557369f3c7d2c287b053eb229daa9c22138887752191c9520659";
+
+/// Address of the pre-funded dev account.
+pub const DEV_ADDRESS: &str = "0x3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E";
+
+/// A well-known development key pair.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TestAccount {
+    pub address: &'static str,
+    pub private_key: &'static str,
+}
+
+/// The first accounts of the standard Foundry/Hardhat test mnemonic.
+pub const TEST_ACCOUNTS: [TestAccount; 3] = [
+    TestAccount {
+        address: "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+        private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+    },
+    TestAccount {
+        address: "0x70997970C51812dc3A010C7d01b50e0d17dc79C8",
+        private_key: "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d",
+    },
+    TestAccount {
+        address: "0x3C44CdDdB6a900fa2b585dd299e03d12FA4293BC",
+        private_key: "0x5de4111afa1a4b94908f83103eb1f1706367c2e68ca870fc3fb9a804cdab365a",
+    },
+];
+
+/// Errors raised while driving Docker or `cast`.
+#[derive(Debug)]
+pub enum Error {
+    /// The program could not be spawned at all (usually: not installed).
+    Spawn {
+        program: String,
+        source: std::io::Error,
+    },
+    /// The program ran but exited unsuccessfully.
+    Failed { program: String, stderr: String },
+    /// The RPC endpoint did not answer within the startup timeout.
+    Timeout { rpc_url: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Spawn { program, source } => write!(f, "failed to run `{program}`: {source}"),
+            Error::Failed { program, stderr } => write!(f, "`{program}` failed: {}", stderr.trim()),
+            Error::Timeout { rpc_url } => write!(f, "dev node at {rpc_url} did not become ready"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Settings for a dev node instance.
+#[derive(Clone, Debug)]
+pub struct DevNodeConfig {
+    pub image: String,
+    pub container_name: String,
+    pub port: u16,
+    pub ws_port: u16,
+    /// Amount of ETH sent to each test account, as understood by `cast --value`.
+    pub funding: String,
+    pub accounts: Vec<TestAccount>,
+    pub startup_timeout: Duration,
+}
+
+impl Default for DevNodeConfig {
+    fn default() -> Self {
+        Self {
+            image: std::env::var("NITRO_IMAGE").unwrap_or_else(|_| DEFAULT_IMAGE.to_string()),
+            container_name: "stylus-demo-devnode".to_string(),
+            port: DEFAULT_PORT,
+            ws_port: DEFAULT_WS_PORT,
+            funding: "10ether".to_string(),
+            accounts: TEST_ACCOUNTS.to_vec(),
+            startup_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+impl DevNodeConfig {
+    /// Arguments passed to `docker` to start the node in the background.
+    pub fn docker_run_args(&self) -> Vec<String> {
+        vec![
+            "run".to_string(),
+            "--detach".to_string(),
+            "--rm".to_string(),
+            "--name".to_string(),
+            self.container_name.clone(),
+            "--publish".to_string(),
+            format!("{}:8547", self.port),
+            "--publish".to_string(),
+            format!("{}:8548", self.ws_port),
+            self.image.clone(),
+            "--dev".to_string(),
+            "--http.addr".to_string(),
+            "0.0.0.0".to_string(),
+            "--http.api=net,web3,eth,debug".to_string(),
+            "--ws.addr".to_string(),
+            "0.0.0.0".to_string(),
+            "--ws.port=8548".to_string(),
+            "--ws.api=net,web3,eth".to_string(),
+        ]
+    }
+
+    pub fn rpc_url(&self) -> String {
+        format!("http://localhost:{}", self.port)
+    }
+
+    pub fn ws_url(&self) -> String {
+        format!("ws://localhost:{}", self.ws_port)
+    }
+}
+
+/// Handle to a running dev node. The container is removed when dropped,
+/// unless the handle was [`detach`](DevNode::detach)ed.
+pub struct DevNode {
+    config: DevNodeConfig,
+    rpc_url: String,
+    stop_on_drop: bool,
+}
+
+impl DevNode {
+    /// Starts the container, waits for the RPC endpoint and funds the test accounts.
+    pub fn start(config: DevNodeConfig) -> Result<Self, Error> {
+        run("docker", &config.docker_run_args())?;
+        let node = Self {
+            rpc_url: config.rpc_url(),
+            config,
+            stop_on_drop: true,
+        };
+        node.wait_until_ready()?;
+        for account in &node.config.accounts {
+            node.fund(account.address, &node.config.funding)?;
+        }
+        Ok(node)
+    }
+
+    /// Attaches to a node that is already running, e.g. one started by `devnode up`.
+    pub fn attach(config: DevNodeConfig) -> Result<Self, Error> {
+        let node = Self {
+            rpc_url: config.rpc_url(),
+            config,
+            stop_on_drop: false,
+        };
+        node.wait_until_ready()?;
+        Ok(node)
+    }
+
+    pub fn rpc_url(&self) -> &str {
+        &self.rpc_url
+    }
+
+    pub fn ws_url(&self) -> String {
+        self.config.ws_url()
+    }
+
+    pub fn accounts(&self) -> &[TestAccount] {
+        &self.config.accounts
+    }
+
+    /// Sends `amount` (e.g. `"1ether"`) from the pre-funded dev account to `address`.
+    pub fn fund(&self, address: &str, amount: &str) -> Result<(), Error> {
+        run(
+            "cast",
+            &[
+                "send".to_string(),
+                "--rpc-url".to_string(),
+                self.rpc_url.clone(),
+                "--private-key".to_string(),
+                DEV_PRIVATE_KEY.to_string(),
+                "--value".to_string(),
+                amount.to_string(),
+                address.to_string(),
+            ],
+        )
+        .map(|_| ())
+    }
+
+    /// Leaves the container running after this handle goes away.
+    pub fn detach(mut self) {
+        self.stop_on_drop = false;
+    }
+
+    /// Stops and removes the container.
+    pub fn stop(mut self) -> Result<(), Error> {
+        self.stop_on_drop = false;
+        stop_container(&self.config.container_name)
+    }
+
+    fn wait_until_ready(&self) -> Result<(), Error> {
+        let started = Instant::now();
+        let args = [
+            "block-number".to_string(),
+            "--rpc-url".to_string(),
+            self.rpc_url.clone(),
+        ];
+        while started.elapsed() < self.config.startup_timeout {
+            if run("cast", &args).is_ok() {
+                return Ok(());
+            }
+            thread::sleep(Duration::from_millis(500));
+        }
+        Err(Error::Timeout {
+            rpc_url: self.rpc_url.clone(),
+        })
+    }
+}
+
+impl Drop for DevNode {
+    fn drop(&mut self) {
+        if self.stop_on_drop {
+            let _ = stop_container(&self.config.container_name);
+        }
+    }
+}
+
+/// Stops a dev node container by name. `--rm` on start takes care of removal.
+pub fn stop_container(name: &str) -> Result<(), Error> {
+    run("docker", &["stop".to_string(), name.to_string()]).map(|_| ())
+}
+
+/// Runs `program` with `args`, returning its trimmed stdout on success.
+fn run(program: &str, args: &[String]) -> Result<String, Error> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|source| Error::Spawn {
+            program: program.to_string(),
+            source,
+        })?;
+    if !output.status.success() {
+        return Err(Error::Failed {
+            program: program.to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_docker_args_publish_configured_port() {
+        let config = DevNodeConfig {
+            port: 9000,
+            ws_port: 9001,
+            ..DevNodeConfig::default()
+        };
+        let args = config.docker_run_args();
+        assert!(args.contains(&"9000:8547".to_string()));
+        assert!(args.contains(&"9001:8548".to_string()));
+        assert!(args.contains(&"--dev".to_string()));
+        assert_eq!(config.rpc_url(), "http://localhost:9000");
+        assert_eq!(config.ws_url(), "ws://localhost:9001");
+    }
+
+    #[test]
+    fn test_default_config_funds_all_test_accounts() {
+        let config = DevNodeConfig::default();
+        assert_eq!(config.accounts, TEST_ACCOUNTS.to_vec());
+        assert_eq!(config.port, DEFAULT_PORT);
+    }
+}
@@ -0,0 +1,30 @@
+use devnode::{stop_container, DevNode, DevNodeConfig};
+
+const USAGE: &str = "usage: devnode <up|down>";
+
+fn main() {
+    let config = DevNodeConfig::default();
+    let result = match std::env::args().nth(1).as_deref() {
+        Some("up") => DevNode::start(config).map(|node| {
+            println!(
+                "Nitro dev node running at {} ({})",
+                node.rpc_url(),
+                node.ws_url()
+            );
+            for account in node.accounts() {
+                println!("  {} {}", account.address, account.private_key);
+            }
+            node.detach();
+        }),
+        Some("down") => stop_container(&config.container_name),
+        _ => {
+            eprintln!("{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(err) = result {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,296 @@
+//!
+//! Testnet ETH faucet for developer accounts
+//!
+//! Tops up each given address to a target balance from a funded key, sending
+//! through Foundry's `cast`. Like `VendingMachine::vend`, every address has a
+//! cooldown between top-ups, and additionally a lifetime cap on what it can
+//! receive. Funding history is kept in a small state file between runs.
+//!
+//! ```text
+//! faucet [--target <wei>] [--cap <wei>] [--cooldown <seconds>] [--state <path>] <address>...
+//! ```
+//!
+//! `ETH_RPC_URL` and `PRIVATE_KEY` select the network and the funding key.
+//!
+//! A top-up is written to the state file before it is sent, and taken back out
+//! if `cast send` fails. If the faucet is killed between the two, the address
+//! is recorded as funded without having been paid. That is the safe way round:
+//! recording after sending could let an address be funded past its cap and
+//! cooldown.
+//!
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const USAGE: &str = "usage: faucet [--target <wei>] [--cap <wei>] [--cooldown <seconds>] [--state <path>] <address>...";
+
+const ONE_ETHER: u128 = 1_000_000_000_000_000_000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Policy {
+    /// Balance each address is topped up to.
+    target: u128,
+    /// Total an address may ever receive from the faucet.
+    cap: u128,
+    /// Minimum seconds between two top-ups of the same address.
+    cooldown: u64,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            target: ONE_ETHER,
+            cap: 5 * ONE_ETHER,
+            cooldown: 60,
+        }
+    }
+}
+
+/// What the faucet has already sent to an address.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Record {
+    last_funded_at: u64,
+    total_funded: u128,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Decision {
+    Send(u128),
+    AlreadyFunded,
+    Cooldown { remaining: u64 },
+    CapReached,
+}
+
+/// Decides how much to send to an address with `balance` wei at time `now`.
+fn decide(policy: &Policy, record: &Record, balance: u128, now: u64) -> Decision {
+    if balance >= policy.target {
+        return Decision::AlreadyFunded;
+    }
+    let ready_at = record.last_funded_at + policy.cooldown;
+    if record.total_funded > 0 && now < ready_at {
+        return Decision::Cooldown {
+            remaining: ready_at - now,
+        };
+    }
+    let allowance = policy.cap.saturating_sub(record.total_funded);
+    match (policy.target - balance).min(allowance) {
+        0 => Decision::CapReached,
+        amount => Decision::Send(amount),
+    }
+}
+
+fn load_state(path: &str) -> BTreeMap<String, Record> {
+    let text = fs::read_to_string(path).unwrap_or_default();
+    text.lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let address = parts.next()?.to_lowercase();
+            let record = Record {
+                last_funded_at: parts.next()?.parse().ok()?,
+                total_funded: parts.next()?.parse().ok()?,
+            };
+            Some((address, record))
+        })
+        .collect()
+}
+
+/// Creates the directory holding the state file, so a first run can record its top-ups.
+fn ensure_state_dir(path: &str) -> Result<(), String> {
+    match Path::new(path).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => fs::create_dir_all(dir)
+            .map_err(|err| format!("failed to create {}: {err}", dir.display())),
+        _ => Ok(()),
+    }
+}
+
+fn save_state(path: &str, state: &BTreeMap<String, Record>) -> Result<(), String> {
+    let text: String = state
+        .iter()
+        .map(|(address, r)| format!("{address} {} {}\n", r.last_funded_at, r.total_funded))
+        .collect();
+    fs::write(path, text).map_err(|err| format!("failed to write {path}: {err}"))
+}
+
+fn cast(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("cast")
+        .args(args)
+        .output()
+        .map_err(|err| format!("failed to run `cast`: {err}"))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+fn env(name: &str) -> Result<String, String> {
+    std::env::var(name).map_err(|_| format!("{name} is not set"))
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let mut policy = Policy::default();
+    let mut state_path = "target/faucet-state.txt".to_string();
+    let mut addresses = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or(format!("missing value for {arg}\n{USAGE}"))
+        };
+        match arg.as_str() {
+            "--target" => policy.target = value()?.parse().map_err(|_| "invalid --target")?,
+            "--cap" => policy.cap = value()?.parse().map_err(|_| "invalid --cap")?,
+            "--cooldown" => policy.cooldown = value()?.parse().map_err(|_| "invalid --cooldown")?,
+            "--state" => state_path = value()?.clone(),
+            _ => addresses.push(arg.to_lowercase()),
+        }
+    }
+    if addresses.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    let rpc_url = env("ETH_RPC_URL")?;
+    let private_key = env("PRIVATE_KEY")?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|err| err.to_string())?
+        .as_secs();
+    ensure_state_dir(&state_path)?;
+    let mut state = load_state(&state_path);
+
+    for address in addresses {
+        let balance = cast(&["balance", "--rpc-url", &rpc_url, &address])?
+            .parse::<u128>()
+            .map_err(|_| format!("unexpected balance for {address}"))?;
+        let record = state.get(&address).copied().unwrap_or_default();
+        match decide(&policy, &record, balance, now) {
+            Decision::Send(amount) => {
+                let previous = state.insert(
+                    address.clone(),
+                    Record {
+                        last_funded_at: now,
+                        total_funded: record.total_funded + amount,
+                    },
+                );
+                save_state(&state_path, &state)?;
+                let value = amount.to_string();
+                let sent = cast(&[
+                    "send",
+                    "--rpc-url",
+                    &rpc_url,
+                    "--private-key",
+                    &private_key,
+                    "--value",
+                    &value,
+                    &address,
+                ]);
+                if let Err(err) = sent {
+                    match previous {
+                        Some(previous) => state.insert(address.clone(), previous),
+                        None => state.remove(&address),
+                    };
+                    save_state(&state_path, &state)?;
+                    return Err(format!("{address}: send failed: {err}"));
+                }
+                println!("{address}: sent {amount} wei");
+            }
+            Decision::AlreadyFunded => println!("{address}: already funded"),
+            Decision::Cooldown { remaining } => {
+                println!("{address}: cooldown, retry in {remaining}s")
+            }
+            Decision::CapReached => println!("{address}: lifetime cap reached"),
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(err) = run(&args) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tops_up_to_target() {
+        let policy = Policy::default();
+        let decision = decide(&policy, &Record::default(), ONE_ETHER / 4, 1_000);
+        assert_eq!(decision, Decision::Send(ONE_ETHER * 3 / 4));
+        assert_eq!(
+            decide(&policy, &Record::default(), ONE_ETHER, 1_000),
+            Decision::AlreadyFunded
+        );
+    }
+
+    #[test]
+    fn test_enforces_cooldown() {
+        let policy = Policy::default();
+        let record = Record {
+            last_funded_at: 1_000,
+            total_funded: ONE_ETHER,
+        };
+        assert_eq!(
+            decide(&policy, &record, 0, 1_030),
+            Decision::Cooldown { remaining: 30 }
+        );
+        assert_eq!(
+            decide(&policy, &record, 0, 1_060),
+            Decision::Send(ONE_ETHER)
+        );
+    }
+
+    #[test]
+    fn test_enforces_lifetime_cap() {
+        let policy = Policy::default();
+        let nearly_capped = Record {
+            last_funded_at: 0,
+            total_funded: policy.cap - ONE_ETHER / 2,
+        };
+        assert_eq!(
+            decide(&policy, &nearly_capped, 0, 1_000),
+            Decision::Send(ONE_ETHER / 2)
+        );
+        let capped = Record {
+            last_funded_at: 0,
+            total_funded: policy.cap,
+        };
+        assert_eq!(decide(&policy, &capped, 0, 1_000), Decision::CapReached);
+    }
+
+    #[test]
+    fn test_state_dir_is_created_before_saving() {
+        let dir = std::env::temp_dir().join("faucet-state-dir-test");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("nested").join("state.txt");
+        let path = path.to_str().unwrap();
+        ensure_state_dir(path).unwrap();
+        save_state(path, &BTreeMap::new()).unwrap();
+        assert!(load_state(path).is_empty());
+        ensure_state_dir("state.txt").unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let path = std::env::temp_dir().join("faucet-state-test.txt");
+        let path = path.to_str().unwrap();
+        let state = BTreeMap::from([(
+            "0xabc".to_string(),
+            Record {
+                last_funded_at: 42,
+                total_funded: ONE_ETHER,
+            },
+        )]);
+        save_state(path, &state).unwrap();
+        assert_eq!(load_state(path), state);
+        fs::remove_file(path).unwrap();
+    }
+}
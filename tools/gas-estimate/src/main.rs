@@ -0,0 +1,252 @@
+//!
+//! Gas estimation for deployed demo contracts
+//!
+//! Simulates a method call with `eth_estimateGas` and `eth_call` (through
+//! Foundry's `cast`) and prints the gas estimate together with the decoded
+//! return value or revert reason.
+//!
+//! ```text
+//! gas-estimate --rpc-url <url> [--from <address>] [--value <amount>] \
+//!     <contract> "<signature>(<returns>)" [args...]
+//! ```
+//!
+//! Stylus contracts returning `Result<_, Vec<u8>>` revert with the raw error
+//! bytes rather than a Solidity `Error(string)`, so both encodings are decoded.
+//! Custom errors from a `SolidityError` enum are printed as their 4-byte
+//! selector and arguments, ready for `cast decode-error` or `cast 4byte`.
+//!
+
+use std::process::Command;
+
+const USAGE: &str = "usage: gas-estimate --rpc-url <url> [--from <address>] [--value <amount>] <contract> <signature> [args...]";
+
+/// Selector of Solidity's `Error(string)`.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of Solidity's `Panic(uint256)`.
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    rpc_url: String,
+    from: Option<String>,
+    value: Option<String>,
+    contract: String,
+    signature: String,
+    call_args: Vec<String>,
+}
+
+fn parse_args(mut raw: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut args = Args::default();
+    let mut positional = Vec::new();
+    while let Some(arg) = raw.next() {
+        let mut value_of = |flag: &str| raw.next().ok_or(format!("missing value for {flag}"));
+        match arg.as_str() {
+            "--rpc-url" => args.rpc_url = value_of("--rpc-url")?,
+            "--from" => args.from = Some(value_of("--from")?),
+            "--value" => args.value = Some(value_of("--value")?),
+            _ => positional.push(arg),
+        }
+    }
+    if args.rpc_url.is_empty() {
+        args.rpc_url = std::env::var("ETH_RPC_URL").map_err(|_| "missing --rpc-url")?;
+    }
+    let mut positional = positional.into_iter();
+    args.contract = positional.next().ok_or("missing contract address")?;
+    args.signature = positional.next().ok_or("missing method signature")?;
+    args.call_args = positional.collect();
+    Ok(args)
+}
+
+/// Arguments shared by `cast estimate` and `cast call`.
+fn cast_args(subcommand: &str, args: &Args) -> Vec<String> {
+    let mut out = vec![
+        subcommand.to_string(),
+        "--rpc-url".to_string(),
+        args.rpc_url.clone(),
+    ];
+    if let Some(from) = &args.from {
+        out.extend(["--from".to_string(), from.clone()]);
+    }
+    if let Some(value) = &args.value {
+        out.extend(["--value".to_string(), value.clone()]);
+    }
+    out.push(args.contract.clone());
+    out.push(args.signature.clone());
+    out.extend(args.call_args.iter().cloned());
+    out
+}
+
+fn cast(args: &[String]) -> Result<String, String> {
+    let output = Command::new("cast")
+        .args(args)
+        .output()
+        .map_err(|err| format!("failed to run `cast`: {err}"))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let digits = hex.strip_prefix("0x").unwrap_or(hex);
+    // Checked first, so the byte slicing below never splits a character.
+    if !digits.len().is_multiple_of(2) || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("malformed revert data {hex}"));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).map_err(|err| err.to_string()))
+        .collect()
+}
+
+/// Pulls the `data: "0x…"` payload out of an RPC revert error message, or `None` if
+/// the message has none.
+fn revert_data(message: &str) -> Option<Result<Vec<u8>, String>> {
+    let start = message.find("data: \"0x")? + "data: \"".len();
+    let end = message[start..].find('"')? + start;
+    Some(decode_hex(&message[start..end]))
+}
+
+fn to_hex(data: &[u8]) -> String {
+    let hex: String = data.iter().map(|b| format!("{b:02x}")).collect();
+    format!("0x{hex}")
+}
+
+/// Renders revert data as `Error(string)`, `Panic(uint256)`, raw UTF-8, a custom
+/// error's selector and arguments, or hex.
+fn decode_revert(data: &[u8]) -> String {
+    if data.len() >= 4 + 64 && data[..4] == ERROR_SELECTOR {
+        let len = u64::from_be_bytes(data[4 + 56..4 + 64].try_into().unwrap()) as usize;
+        // `len` comes from the revert data, so bound it without adding to it.
+        if let Some(reason) = data.get(4 + 64..).and_then(|rest| rest.get(..len)) {
+            return format!("Error(\"{}\")", String::from_utf8_lossy(reason));
+        }
+    }
+    if data.len() == 4 + 32 && data[..4] == PANIC_SELECTOR {
+        return format!("Panic(0x{:02x})", data[35]);
+    }
+    match core::str::from_utf8(data) {
+        Ok(text) if !text.is_empty() && !text.contains(char::is_control) => {
+            format!("\"{text}\"")
+        }
+        _ if data.len() >= 4 => format!(
+            "custom error {} with data {}",
+            to_hex(&data[..4]),
+            to_hex(&data[4..])
+        ),
+        _ => to_hex(data),
+    }
+}
+
+fn main() {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {err}\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    match cast(&cast_args("estimate", &args)) {
+        Ok(gas) => println!("gas:      {gas}"),
+        Err(err) => println!("gas:      n/a ({})", err.lines().next().unwrap_or_default()),
+    }
+
+    match cast(&cast_args("call", &args)) {
+        Ok(output) => println!("returns:  {}", output.replace('\n', ", ")),
+        Err(err) => match revert_data(&err) {
+            Some(Ok(data)) => println!("reverted: {}", decode_revert(&data)),
+            Some(Err(reason)) => {
+                println!("reverted: {reason}");
+                std::process::exit(1);
+            }
+            None => {
+                println!("reverted: {}", err.lines().next().unwrap_or_default());
+                std::process::exit(1);
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(list: &[&str]) -> Result<Args, String> {
+        parse_args(list.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = args(&[
+            "--rpc-url",
+            "http://localhost:8547",
+            "0xabc",
+            "--value",
+            "1ether",
+            "addNumber(uint256)",
+            "3",
+        ])
+        .unwrap();
+        assert_eq!(parsed.contract, "0xabc");
+        assert_eq!(parsed.signature, "addNumber(uint256)");
+        assert_eq!(parsed.value.as_deref(), Some("1ether"));
+        assert_eq!(parsed.call_args, vec!["3".to_string()]);
+
+        let cast = cast_args("call", &parsed);
+        assert_eq!(cast[0], "call");
+        assert_eq!(cast.last().unwrap(), "3");
+    }
+
+    #[test]
+    fn test_decode_raw_bytes_revert() {
+        let message = r#"server returned an error response: error code 3: execution reverted, data: "0x436f6f6c646f776e""#;
+        let data = revert_data(message).unwrap().unwrap();
+        assert_eq!(decode_revert(&data), "\"Cooldown\"");
+    }
+
+    #[test]
+    fn test_malformed_revert_data_is_an_error() {
+        assert!(revert_data(r#"data: "0xé1""#).unwrap().is_err());
+        assert!(revert_data(r#"data: "0x123""#).unwrap().is_err());
+        assert!(revert_data("execution reverted").is_none());
+    }
+
+    #[test]
+    fn test_decode_custom_error_shows_selector_and_data() {
+        // CooldownActive(uint256 remaining) with remaining = 30.
+        let mut data = vec![0x5c, 0x0d, 0x87, 0x6a];
+        data.extend([0u8; 31]);
+        data.push(30);
+        let decoded = decode_revert(&data);
+        assert!(decoded.starts_with("custom error 0x5c0d876a with data 0x0000"));
+        assert!(decoded.ends_with("1e"));
+    }
+
+    #[test]
+    fn test_decode_solidity_error_string() {
+        let mut data = ERROR_SELECTOR.to_vec();
+        data.extend([0u8; 31]);
+        data.push(0x20);
+        data.extend([0u8; 31]);
+        data.push(2);
+        data.extend(b"no");
+        data.extend([0u8; 30]);
+        assert_eq!(decode_revert(&data), "Error(\"no\")");
+    }
+
+    #[test]
+    fn test_decode_error_string_with_oversized_length() {
+        let mut data = ERROR_SELECTOR.to_vec();
+        data.extend([0u8; 31]);
+        data.push(0x20);
+        data.extend([0xffu8; 32]);
+        assert!(decode_revert(&data).starts_with("custom error 0x08c379a0"));
+    }
+
+    #[test]
+    fn test_decode_non_utf8_falls_back_to_hex() {
+        assert_eq!(decode_revert(&[0xff, 0xfe]), "0xfffe");
+    }
+}
@@ -0,0 +1,230 @@
+//!
+//! Gas report for the deployed demo contracts
+//!
+//! Estimates the gas of a fixed set of common calls with `eth_estimateGas`
+//! (through Foundry's `cast`) and prints them as one table, so the cost of a
+//! change can be compared by running the report before and after it, e.g.
+//! against a dev node started with `devnode up`. `TestVM` does not meter ink,
+//! so the numbers have to come from a real node.
+//!
+//! Deployments are configured through the environment, like `demo-cli`:
+//!
+//! - `ETH_RPC_URL`: RPC endpoint, e.g. `http://localhost:8547`
+//! - `PRIVATE_KEY`: the account the calls are estimated from
+//! - `VENDING_MACHINE_ADDRESS`, `DATA_VALIDATOR_ADDRESS`,
+//!   `CUPCAKE_TOKEN_ADDRESS`: deployed contracts; rows for unset ones are skipped
+//!
+//! Estimates run against current state, so a call that would revert (a vend
+//! during the cooldown, a transfer without balance) is reported as such.
+//!
+
+use std::process::Command;
+
+/// One call to estimate.
+#[derive(Debug, PartialEq, Eq)]
+struct Benchmark {
+    contract_env: &'static str,
+    label: &'static str,
+    signature: &'static str,
+    args: Vec<String>,
+}
+
+/// The calls in the report. Transfers and lookups use the estimating account itself.
+fn benchmarks(from: &str) -> Vec<Benchmark> {
+    let bench = |contract_env, label, signature, args: &[&str]| Benchmark {
+        contract_env,
+        label,
+        signature,
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+    };
+    vec![
+        bench("VENDING_MACHINE_ADDRESS", "vend", "vend()", &[]),
+        bench(
+            "VENDING_MACHINE_ADDRESS",
+            "balance_of",
+            "balanceOf(address)",
+            &[from],
+        ),
+        bench(
+            "DATA_VALIDATOR_ADDRESS",
+            "submit_data",
+            "submitData(string)",
+            &["12345.6789"],
+        ),
+        bench(
+            "DATA_VALIDATOR_ADDRESS",
+            "get_last_submission",
+            "getLastSubmission(address)",
+            &[from],
+        ),
+        bench(
+            "CUPCAKE_TOKEN_ADDRESS",
+            "transfer",
+            "transfer(address,uint256)",
+            &[from, "1"],
+        ),
+        bench(
+            "CUPCAKE_TOKEN_ADDRESS",
+            "balance_of",
+            "balanceOf(address)",
+            &[from],
+        ),
+    ]
+}
+
+/// Display name of the contract behind an address variable.
+fn contract_name(contract_env: &str) -> &'static str {
+    match contract_env {
+        "VENDING_MACHINE_ADDRESS" => "VendingMachine",
+        "DATA_VALIDATOR_ADDRESS" => "DataValidator",
+        "CUPCAKE_TOKEN_ADDRESS" => "CupcakeToken",
+        _ => "?",
+    }
+}
+
+fn env(name: &str) -> Result<String, String> {
+    std::env::var(name).map_err(|_| format!("{name} is not set"))
+}
+
+fn cast(args: &[String]) -> Result<String, String> {
+    let output = Command::new("cast")
+        .args(args)
+        .output()
+        .map_err(|err| format!("failed to run `cast`: {err}"))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// The gas column for one benchmark: the estimate, or why there is none.
+fn estimate(rpc_url: &str, from: &str, bench: &Benchmark) -> String {
+    let Ok(contract) = std::env::var(bench.contract_env) else {
+        return format!("skipped ({} not set)", bench.contract_env);
+    };
+    let mut args = vec![
+        "estimate".to_string(),
+        "--rpc-url".to_string(),
+        rpc_url.to_string(),
+        "--from".to_string(),
+        from.to_string(),
+        contract,
+        bench.signature.to_string(),
+    ];
+    args.extend(bench.args.iter().cloned());
+    match cast(&args) {
+        Ok(gas) => gas,
+        Err(err) => format!("reverted ({})", err.lines().next().unwrap_or_default()),
+    }
+}
+
+/// Lays rows out in left-aligned columns under a header and a rule.
+fn render_table(header: [&str; 3], rows: &[[String; 3]]) -> String {
+    let mut widths = header.map(str::len);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let line = |cells: [&str; 3]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect();
+        padded.join("  ").trim_end().to_string()
+    };
+    let rule = widths.map(|width| "-".repeat(width));
+    let mut out = vec![line(header), line(rule.each_ref().map(String::as_str))];
+    out.extend(
+        rows.iter()
+            .map(|row| line(row.each_ref().map(String::as_str))),
+    );
+    out.join("\n")
+}
+
+fn run() -> Result<String, String> {
+    let rpc_url = env("ETH_RPC_URL")?;
+    let from = cast(&[
+        "wallet".to_string(),
+        "address".to_string(),
+        "--private-key".to_string(),
+        env("PRIVATE_KEY")?,
+    ])?;
+    let rows: Vec<[String; 3]> = benchmarks(&from)
+        .iter()
+        .map(|bench| {
+            [
+                contract_name(bench.contract_env).to_string(),
+                bench.label.to_string(),
+                estimate(&rpc_url, &from, bench),
+            ]
+        })
+        .collect();
+    Ok(render_table(["contract", "call", "gas"], &rows))
+}
+
+fn main() {
+    match run() {
+        Ok(table) => println!("{table}"),
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn row(cells: [&str; 3]) -> [String; 3] {
+        cells.map(str::to_string)
+    }
+
+    #[test]
+    fn test_benchmarks_cover_each_contract() {
+        let benches = benchmarks("0xme");
+        for contract_env in [
+            "VENDING_MACHINE_ADDRESS",
+            "DATA_VALIDATOR_ADDRESS",
+            "CUPCAKE_TOKEN_ADDRESS",
+        ] {
+            assert!(benches.iter().any(|b| b.contract_env == contract_env));
+            assert_ne!(contract_name(contract_env), "?");
+        }
+        let transfer = benches.iter().find(|b| b.label == "transfer").unwrap();
+        assert_eq!(transfer.args, vec!["0xme".to_string(), "1".to_string()]);
+    }
+
+    #[test]
+    fn test_unset_contract_is_skipped() {
+        let bench = Benchmark {
+            contract_env: "GAS_REPORT_TEST_UNSET_ADDRESS",
+            label: "noop",
+            signature: "noop()",
+            args: vec![],
+        };
+        assert_eq!(
+            estimate("http://localhost:8547", "0xme", &bench),
+            "skipped (GAS_REPORT_TEST_UNSET_ADDRESS not set)"
+        );
+    }
+
+    #[test]
+    fn test_render_table_aligns_columns() {
+        let table = render_table(
+            ["contract", "call", "gas"],
+            &[
+                row(["VendingMachine", "vend", "61234"]),
+                row(["CupcakeToken", "transfer", "48000"]),
+            ],
+        );
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "contract        call      gas");
+        assert_eq!(lines[1], "--------------  --------  -----");
+        assert_eq!(lines[2], "VendingMachine  vend      61234");
+        assert_eq!(lines[3], "CupcakeToken    transfer  48000");
+    }
+}
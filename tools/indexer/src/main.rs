@@ -0,0 +1,365 @@
+//!
+//! Off-chain event indexer for the demo contracts
+//!
+//! Streams the logs of a deployed `VendingMachine` and `DataValidator` over a
+//! WebSocket RPC with Foundry's `cast logs --subscribe`, decodes
+//! `CupcakeVended` and `DataSubmitted` with `alloy-sol-types`, and mirrors
+//! them into SQLite through the `sqlite3` shell:
+//!
+//! - `balances`: each user's latest cupcake balance
+//! - `submissions`: every accepted data submission, in chain order
+//! - `cursor`: the last block indexed per contract
+//!
+//! ```text
+//! indexer [--db <path>]
+//! ```
+//!
+//! `ETH_WS_URL` selects the node, and `VENDING_MACHINE_ADDRESS` and
+//! `DATA_VALIDATOR_ADDRESS` the contracts. On restart each stream resumes
+//! from its cursor block; rows are keyed by transaction and log index, so
+//! logs from that block are replayed without duplicates. Reorgs are not
+//! rolled back.
+//!
+
+use std::io::{BufReader, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+
+use alloy_primitives::{Address, Bytes, B256, U256};
+use alloy_sol_types::{sol, SolEvent};
+use serde_json::Value;
+
+const USAGE: &str = "usage: indexer [--db <path>]";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS balances (
+    user TEXT PRIMARY KEY,
+    balance TEXT NOT NULL,
+    block INTEGER NOT NULL,
+    log_index INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS submissions (
+    tx_hash TEXT NOT NULL,
+    log_index INTEGER NOT NULL,
+    block INTEGER NOT NULL,
+    sender TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (tx_hash, log_index)
+);
+CREATE TABLE IF NOT EXISTS cursor (
+    contract TEXT PRIMARY KEY,
+    block INTEGER NOT NULL
+);
+";
+
+sol! {
+    event CupcakeVended(address indexed user, uint256 newBalance);
+    event DataSubmitted(address indexed sender, string value);
+}
+
+/// Where a log sits in the chain.
+#[derive(Debug, PartialEq, Eq)]
+struct Position {
+    block: u64,
+    log_index: u64,
+    tx_hash: B256,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Event {
+    Vended { user: Address, new_balance: U256 },
+    Submitted { sender: Address, value: String },
+}
+
+/// Reads a JSON-RPC quantity, which `cast` prints as a hex string or a number.
+fn quantity(log: &Value, field: &str) -> Result<u64, String> {
+    match &log[field] {
+        Value::Number(number) => number.as_u64(),
+        Value::String(text) => u64::from_str_radix(text.trim_start_matches("0x"), 16).ok(),
+        _ => None,
+    }
+    .ok_or(format!("log has no valid `{field}`"))
+}
+
+fn parsed<T: std::str::FromStr>(value: &Value, field: &str) -> Result<T, String> {
+    value
+        .as_str()
+        .and_then(|text| text.parse().ok())
+        .ok_or(format!("log has no valid `{field}`"))
+}
+
+/// Decodes a log as printed by `cast logs --json`; `None` for events the indexer ignores.
+fn decode(log: &Value) -> Result<(Position, Option<Event>), String> {
+    let position = Position {
+        block: quantity(log, "blockNumber")?,
+        log_index: quantity(log, "logIndex")?,
+        tx_hash: parsed(&log["transactionHash"], "transactionHash")?,
+    };
+    let topics = log["topics"]
+        .as_array()
+        .ok_or("log has no `topics`")?
+        .iter()
+        .map(|topic| parsed::<B256>(topic, "topics"))
+        .collect::<Result<Vec<_>, _>>()?;
+    let data: Bytes = parsed(&log["data"], "data")?;
+
+    let event = match topics.first() {
+        Some(&CupcakeVended::SIGNATURE_HASH) => {
+            let event = CupcakeVended::decode_raw_log(topics, &data, true)
+                .map_err(|err| format!("bad CupcakeVended log: {err}"))?;
+            Some(Event::Vended {
+                user: event.user,
+                new_balance: event.newBalance,
+            })
+        }
+        Some(&DataSubmitted::SIGNATURE_HASH) => {
+            let event = DataSubmitted::decode_raw_log(topics, &data, true)
+                .map_err(|err| format!("bad DataSubmitted log: {err}"))?;
+            Some(Event::Submitted {
+                sender: event.sender,
+                value: event.value,
+            })
+        }
+        _ => None,
+    };
+    Ok((position, event))
+}
+
+/// Quotes `text` as an SQL string literal.
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// The SQL that applies one log from `contract` and advances its cursor, as one transaction.
+fn statements(contract: &str, position: &Position, event: Option<&Event>) -> String {
+    let Position {
+        block,
+        log_index,
+        tx_hash,
+    } = position;
+    let mut sql = String::from("BEGIN;\n");
+    match event {
+        // A replayed older log must not overwrite a newer balance.
+        Some(Event::Vended { user, new_balance }) => sql.push_str(&format!(
+            "INSERT INTO balances (user, balance, block, log_index) \
+             VALUES ('{user:#x}', '{new_balance}', {block}, {log_index}) \
+             ON CONFLICT (user) DO UPDATE SET balance = excluded.balance, \
+             block = excluded.block, log_index = excluded.log_index \
+             WHERE (excluded.block, excluded.log_index) > (balances.block, balances.log_index);\n"
+        )),
+        Some(Event::Submitted { sender, value }) => sql.push_str(&format!(
+            "INSERT OR IGNORE INTO submissions (tx_hash, log_index, block, sender, value) \
+             VALUES ('{tx_hash:#x}', {log_index}, {block}, '{sender:#x}', {});\n",
+            quote(value)
+        )),
+        None => {}
+    }
+    sql.push_str(&format!(
+        "INSERT INTO cursor (contract, block) VALUES ({}, {block}) \
+         ON CONFLICT (contract) DO UPDATE SET block = MAX(block, excluded.block);\nCOMMIT;\n",
+        quote(contract)
+    ));
+    sql
+}
+
+fn env(name: &str) -> Result<String, String> {
+    std::env::var(name).map_err(|_| format!("{name} is not set"))
+}
+
+/// Creates the tables if needed and returns the block to resume `contract` from.
+fn resume_block(db: &str, contract: &str) -> Result<u64, String> {
+    let query = format!(
+        "{SCHEMA}SELECT COALESCE(MAX(block), 0) FROM cursor WHERE contract = {};",
+        quote(contract)
+    );
+    let output = Command::new("sqlite3")
+        .arg(db)
+        .arg(query)
+        .output()
+        .map_err(|err| format!("failed to run `sqlite3`: {err}"))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .map_err(|_| format!("unexpected cursor for {contract}"))
+}
+
+/// Streams `contract`'s logs from `from_block` on, sending each to `logs`.
+fn subscribe(
+    ws_url: &str,
+    contract: String,
+    from_block: u64,
+    logs: mpsc::Sender<(String, Value)>,
+) -> Result<(), String> {
+    let mut child = Command::new("cast")
+        .args(["logs", "--subscribe", "--json", "--rpc-url", ws_url])
+        .args(["--from-block", &from_block.to_string()])
+        .args(["--address", &contract])
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("failed to run `cast`: {err}"))?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    thread::spawn(move || {
+        // Backfilled logs may arrive as one array, streamed ones as single objects.
+        let stream = serde_json::Deserializer::from_reader(BufReader::new(stdout));
+        for value in stream.into_iter::<Value>().map_while(Result::ok) {
+            let batch = match value {
+                Value::Array(batch) => batch,
+                log => vec![log],
+            };
+            for log in batch {
+                if logs.send((contract.clone(), log)).is_err() {
+                    return;
+                }
+            }
+        }
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let mut db = "target/indexer.sqlite".to_string();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--db" => db = args.next().ok_or(USAGE)?.clone(),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let ws_url = env("ETH_WS_URL")?;
+    let contracts = [
+        env("VENDING_MACHINE_ADDRESS")?.to_lowercase(),
+        env("DATA_VALIDATOR_ADDRESS")?.to_lowercase(),
+    ];
+    let (sender, logs) = mpsc::channel();
+    for contract in contracts {
+        let from_block = resume_block(&db, &contract)?;
+        println!("{contract}: indexing from block {from_block}");
+        subscribe(&ws_url, contract, from_block, sender.clone())?;
+    }
+    drop(sender);
+
+    // One writer, so the streams never contend for the database.
+    let mut sqlite = Command::new("sqlite3")
+        .args(["-bail", &db])
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("failed to run `sqlite3`: {err}"))?;
+    let mut stdin = sqlite.stdin.take().expect("stdin is piped");
+    for (contract, log) in logs {
+        let (position, event) = decode(&log)?;
+        if let Some(event) = &event {
+            println!("block {}: {event:?}", position.block);
+        }
+        stdin
+            .write_all(statements(&contract, &position, event.as_ref()).as_bytes())
+            .and_then(|()| stdin.flush())
+            .map_err(|err| format!("failed to write to `sqlite3`: {err}"))?;
+    }
+    drop(stdin);
+    let _ = sqlite.wait();
+    Err("log streams ended".to_string())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(err) = run(&args) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    const USER: Address = Address::new([0x11; 20]);
+
+    fn log_json(event: &impl SolEvent, block: u64) -> Value {
+        let data = event.encode_log_data();
+        json!({
+            "address": "0x00000000000000000000000000000000000000aa",
+            "topics": data.topics().iter().map(|topic| format!("{topic:#x}")).collect::<Vec<_>>(),
+            "data": format!("{:#x}", data.data),
+            "blockNumber": format!("{block:#x}"),
+            "transactionHash": format!("{:#x}", B256::repeat_byte(0x22)),
+            "logIndex": "0x3",
+        })
+    }
+
+    #[test]
+    fn test_decodes_cupcake_vended() {
+        let event = CupcakeVended {
+            user: USER,
+            newBalance: U256::from(7),
+        };
+        let (position, event) = decode(&log_json(&event, 26)).unwrap();
+        assert_eq!((position.block, position.log_index), (26, 3));
+        assert_eq!(
+            event,
+            Some(Event::Vended {
+                user: USER,
+                new_balance: U256::from(7)
+            })
+        );
+    }
+
+    #[test]
+    fn test_decodes_data_submitted_and_skips_other_events() {
+        let event = DataSubmitted {
+            sender: USER,
+            value: "12.5".to_string(),
+        };
+        let (_, event) = decode(&log_json(&event, 1)).unwrap();
+        assert_eq!(
+            event,
+            Some(Event::Submitted {
+                sender: USER,
+                value: "12.5".to_string()
+            })
+        );
+
+        let mut other = log_json(&event_with_unknown_signature(), 1);
+        other["blockNumber"] = json!(5);
+        let (position, event) = decode(&other).unwrap();
+        assert_eq!((position.block, event), (5, None));
+
+        assert!(decode(&json!({ "topics": [] })).is_err());
+    }
+
+    fn event_with_unknown_signature() -> impl SolEvent {
+        sol! {
+            event PriceUpdated(uint256 price);
+        }
+        PriceUpdated {
+            price: U256::from(1),
+        }
+    }
+
+    #[test]
+    fn test_statements_quote_values_and_advance_cursor() {
+        let position = Position {
+            block: 9,
+            log_index: 0,
+            tx_hash: B256::ZERO,
+        };
+        let event = Event::Submitted {
+            sender: USER,
+            value: "it's".to_string(),
+        };
+        let sql = statements("0xdv", &position, Some(&event));
+        assert!(sql.starts_with("BEGIN;\nINSERT OR IGNORE INTO submissions"));
+        assert!(sql.contains("'it''s'"));
+        assert!(sql.contains("VALUES ('0xdv', 9)"));
+        assert!(sql.ends_with("COMMIT;\n"));
+
+        let skipped = statements("0xvm", &position, None);
+        assert!(!skipped.contains("balances") && skipped.contains("cursor"));
+    }
+}
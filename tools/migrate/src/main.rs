@@ -0,0 +1,432 @@
+//!
+//! Migration runner for the vending machine
+//!
+//! Moves a deployed `VendingMachine` to a fresh deployment with its snapshot
+//! API, as a sequence of steps:
+//!
+//! 1. deploy the new machine with the old one's cooldown and `max_users`
+//! 2. pause the old machine, so no vend lands after it has been exported
+//! 3. copy the price
+//! 4. copy users page by page with `export_users` / `import_users`
+//! 5. close importing on the new machine
+//! 6. hand the new machine over to `--new-owner`
+//!
+//! ```text
+//! migrate --old <address> --new-owner <address> [--wasm-file <path>] [--state <path>]
+//! ```
+//!
+//! `ETH_RPC_URL` and `PRIVATE_KEY` select the network and the key, which must
+//! own the old machine. Transactions go through Foundry's `cast` and the
+//! deployment through `cargo stylus deploy`. Progress is saved to the state
+//! file after every step and page, so rerunning the same command after a
+//! failure resumes where it stopped. The old machine is left paused.
+//!
+
+use std::fs;
+use std::process::Command;
+
+use alloy_primitives::{hex, Address, U256};
+use alloy_sol_types::{sol, SolCall};
+
+const USAGE: &str =
+    "usage: migrate --old <address> --new-owner <address> [--wasm-file <path>] [--state <path>]";
+
+/// Users copied per `import_users` transaction; the contract exports at most 100 per call.
+const PAGE_SIZE: u64 = 50;
+
+sol! {
+    interface IVendingMachine {
+        function owner() external view returns (address);
+        function cooldown() external view returns (uint64);
+        function maxUsers() external view returns (uint64);
+        function price() external view returns (uint256);
+        function userCount() external view returns (uint256);
+        function exportUsers(uint256 offset, uint256 limit) external view returns ((address,uint256,uint256,uint256)[]);
+        function importUsers((address,uint256,uint256,uint256)[] users) external returns (uint256);
+        function closeImport() external;
+        function transferOwnership(address newOwner) external;
+        function pause() external;
+        function setPrice(uint256 price) external;
+    }
+}
+
+/// How far a migration has got; saved between runs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct State {
+    new_machine: Option<Address>,
+    paused: bool,
+    price_copied: bool,
+    users_copied: u64,
+    import_closed: bool,
+    handed_off: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Step {
+    Deploy,
+    PauseOld,
+    CopyPrice,
+    CopyUsers { offset: u64 },
+    CloseImport,
+    HandOff,
+    Done,
+}
+
+/// The next step, given how many users the old machine has.
+fn next_step(state: &State, user_count: u64) -> Step {
+    if state.new_machine.is_none() {
+        Step::Deploy
+    } else if !state.paused {
+        Step::PauseOld
+    } else if !state.price_copied {
+        Step::CopyPrice
+    } else if state.users_copied < user_count {
+        Step::CopyUsers {
+            offset: state.users_copied,
+        }
+    } else if !state.import_closed {
+        Step::CloseImport
+    } else if !state.handed_off {
+        Step::HandOff
+    } else {
+        Step::Done
+    }
+}
+
+fn load_state(path: &str) -> State {
+    let text = fs::read_to_string(path).unwrap_or_default();
+    let mut state = State::default();
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once(' ') else {
+            continue;
+        };
+        match key {
+            "new_machine" => state.new_machine = value.parse().ok(),
+            "paused" => state.paused = value == "true",
+            "price_copied" => state.price_copied = value == "true",
+            "users_copied" => state.users_copied = value.parse().unwrap_or(0),
+            "import_closed" => state.import_closed = value == "true",
+            "handed_off" => state.handed_off = value == "true",
+            _ => {}
+        }
+    }
+    state
+}
+
+fn save_state(path: &str, state: &State) -> Result<(), String> {
+    let mut text = String::new();
+    if let Some(new_machine) = state.new_machine {
+        text.push_str(&format!("new_machine {new_machine:#x}\n"));
+    }
+    text.push_str(&format!(
+        "paused {}\nprice_copied {}\nusers_copied {}\nimport_closed {}\nhanded_off {}\n",
+        state.paused, state.price_copied, state.users_copied, state.import_closed, state.handed_off
+    ));
+    fs::write(path, text).map_err(|err| format!("failed to write {path}: {err}"))
+}
+
+/// Finds the contract address in `cargo stylus deploy` output.
+fn deployed_address(output: &str) -> Option<Address> {
+    output
+        .lines()
+        .filter(|line| line.contains("deployed code at address"))
+        .flat_map(str::split_whitespace)
+        .find_map(|word| word.parse().ok())
+}
+
+fn run_program(program: &str, args: &[String]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|err| format!("failed to run `{program}`: {err}"))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+fn env(name: &str) -> Result<String, String> {
+    std::env::var(name).map_err(|_| format!("{name} is not set"))
+}
+
+/// Sends calls and queries through `cast` with raw ABI-encoded calldata.
+struct Chain {
+    rpc_url: String,
+    private_key: String,
+}
+
+impl Chain {
+    fn call<C: SolCall>(&self, to: Address, call: &C) -> Result<C::Return, String> {
+        let output = run_program(
+            "cast",
+            &[
+                "call".to_string(),
+                "--rpc-url".to_string(),
+                self.rpc_url.clone(),
+                format!("{to:#x}"),
+                hex::encode_prefixed(call.abi_encode()),
+            ],
+        )?;
+        let data = hex::decode(&output).map_err(|err| format!("bad call output: {err}"))?;
+        C::abi_decode_returns(&data, true)
+            .map_err(|err| format!("failed to decode {}: {err}", C::SIGNATURE))
+    }
+
+    fn send<C: SolCall>(&self, to: Address, call: &C) -> Result<(), String> {
+        run_program(
+            "cast",
+            &[
+                "send".to_string(),
+                "--rpc-url".to_string(),
+                self.rpc_url.clone(),
+                "--private-key".to_string(),
+                self.private_key.clone(),
+                format!("{to:#x}"),
+                hex::encode_prefixed(call.abi_encode()),
+            ],
+        )
+        .map(drop)
+    }
+
+    fn signer(&self) -> Result<Address, String> {
+        run_program(
+            "cast",
+            &[
+                "wallet".to_string(),
+                "address".to_string(),
+                "--private-key".to_string(),
+                self.private_key.clone(),
+            ],
+        )?
+        .parse()
+        .map_err(|_| "unexpected signer address".to_string())
+    }
+}
+
+struct Args {
+    old: Address,
+    new_owner: Address,
+    wasm_file: String,
+    state_path: String,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let (mut old, mut new_owner) = (None, None);
+    let mut wasm_file = "target/wasm32-unknown-unknown/release/vending_machine.wasm".to_string();
+    let mut state_path = "target/migrate-state.txt".to_string();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or(format!("missing value for {arg}\n{USAGE}"))?;
+        let address = || {
+            value
+                .parse::<Address>()
+                .map_err(|_| format!("invalid address for {arg}"))
+        };
+        match arg.as_str() {
+            "--old" => old = Some(address()?),
+            "--new-owner" => new_owner = Some(address()?),
+            "--wasm-file" => wasm_file = value.clone(),
+            "--state" => state_path = value.clone(),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    Ok(Args {
+        old: old.ok_or(USAGE)?,
+        new_owner: new_owner.ok_or(USAGE)?,
+        wasm_file,
+        state_path,
+    })
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let args = parse_args(args)?;
+    let chain = Chain {
+        rpc_url: env("ETH_RPC_URL")?,
+        private_key: env("PRIVATE_KEY")?,
+    };
+    let signer = chain.signer()?;
+    let old = args.old;
+    let mut state = load_state(&args.state_path);
+
+    loop {
+        let user_count = chain
+            .call(old, &IVendingMachine::userCountCall {})?
+            ._0
+            .saturating_to::<u64>();
+        let step = next_step(&state, user_count);
+        match step {
+            Step::Deploy => {
+                let owner = chain.call(old, &IVendingMachine::ownerCall {})?._0;
+                if owner != signer {
+                    return Err(format!("{signer:#x} does not own the old machine"));
+                }
+                let cooldown = chain.call(old, &IVendingMachine::cooldownCall {})?._0;
+                let max_users = chain.call(old, &IVendingMachine::maxUsersCall {})?._0;
+                // The runner owns the new machine until the hand-off so it can import.
+                let output = run_program(
+                    "cargo",
+                    &[
+                        "stylus".to_string(),
+                        "deploy".to_string(),
+                        "--endpoint".to_string(),
+                        chain.rpc_url.clone(),
+                        "--private-key".to_string(),
+                        chain.private_key.clone(),
+                        "--wasm-file".to_string(),
+                        args.wasm_file.clone(),
+                        "--constructor-args".to_string(),
+                        format!("{signer:#x}"),
+                        cooldown.to_string(),
+                        max_users.to_string(),
+                    ],
+                )?;
+                let new_machine = deployed_address(&output)
+                    .ok_or("could not find the deployed address in `cargo stylus` output")?;
+                println!("deployed new machine at {new_machine:#x}");
+                state.new_machine = Some(new_machine);
+            }
+            Step::PauseOld => {
+                chain.send(old, &IVendingMachine::pauseCall {})?;
+                println!("paused {old:#x}");
+                state.paused = true;
+            }
+            Step::CopyPrice => {
+                let price = chain.call(old, &IVendingMachine::priceCall {})?._0;
+                let new_machine = state.new_machine.expect("deployed");
+                chain.send(new_machine, &IVendingMachine::setPriceCall { price })?;
+                println!("copied price {price}");
+                state.price_copied = true;
+            }
+            Step::CopyUsers { offset } => {
+                let users = chain
+                    .call(
+                        old,
+                        &IVendingMachine::exportUsersCall {
+                            offset: U256::from(offset),
+                            limit: U256::from(PAGE_SIZE),
+                        },
+                    )?
+                    ._0;
+                if users.is_empty() {
+                    return Err(format!("old machine exported no users at {offset}"));
+                }
+                let count = users.len() as u64;
+                let new_machine = state.new_machine.expect("deployed");
+                chain.send(new_machine, &IVendingMachine::importUsersCall { users })?;
+                state.users_copied = offset + count;
+                println!("copied users {}/{user_count}", state.users_copied);
+            }
+            Step::CloseImport => {
+                let new_machine = state.new_machine.expect("deployed");
+                chain.send(new_machine, &IVendingMachine::closeImportCall {})?;
+                println!("closed import");
+                state.import_closed = true;
+            }
+            Step::HandOff => {
+                let new_machine = state.new_machine.expect("deployed");
+                let call = IVendingMachine::transferOwnershipCall {
+                    newOwner: args.new_owner,
+                };
+                chain.send(new_machine, &call)?;
+                println!("handed {new_machine:#x} to {:#x}", args.new_owner);
+                state.handed_off = true;
+            }
+            Step::Done => {
+                println!("migration complete");
+                return Ok(());
+            }
+        }
+        save_state(&args.state_path, &state)?;
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(err) = run(&args) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_steps_run_in_order_and_page_through_users() {
+        let mut state = State::default();
+        assert_eq!(next_step(&state, 120), Step::Deploy);
+        state.new_machine = Some(Address::repeat_byte(0x11));
+        assert_eq!(next_step(&state, 120), Step::PauseOld);
+        state.paused = true;
+        assert_eq!(next_step(&state, 120), Step::CopyPrice);
+        state.price_copied = true;
+        assert_eq!(next_step(&state, 120), Step::CopyUsers { offset: 0 });
+        state.users_copied = 100;
+        assert_eq!(next_step(&state, 120), Step::CopyUsers { offset: 100 });
+        state.users_copied = 120;
+        assert_eq!(next_step(&state, 120), Step::CloseImport);
+        state.import_closed = true;
+        assert_eq!(next_step(&state, 120), Step::HandOff);
+        state.handed_off = true;
+        assert_eq!(next_step(&state, 120), Step::Done);
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let path = std::env::temp_dir().join("migrate-state-test.txt");
+        let path = path.to_str().unwrap();
+        assert_eq!(load_state(path), State::default());
+        let state = State {
+            new_machine: Some(Address::repeat_byte(0xab)),
+            paused: true,
+            price_copied: true,
+            users_copied: 42,
+            ..State::default()
+        };
+        save_state(path, &state).unwrap();
+        assert_eq!(load_state(path), state);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_deployed_address_from_stylus_output() {
+        let output = "stripped custom section from user wasm\n\
+                      deployed code at address: 0x33f4b2f0c1d5cd0e5b4a2bf3c1a1f0a0b0c0d0e0\n\
+                      deployment tx hash: 0x1234";
+        assert_eq!(
+            deployed_address(output),
+            Some(
+                "0x33f4b2f0c1d5cd0e5b4a2bf3c1a1f0a0b0c0d0e0"
+                    .parse()
+                    .unwrap()
+            )
+        );
+        assert_eq!(deployed_address("error: insufficient funds"), None);
+    }
+
+    #[test]
+    fn test_export_page_feeds_import_calldata() {
+        let users = vec![(
+            Address::repeat_byte(0x01),
+            U256::from(3),
+            U256::from(1_000),
+            U256::from(12),
+        )];
+        let returned = IVendingMachine::exportUsersCall::abi_encode_returns(&(users.clone(),));
+        let decoded = IVendingMachine::exportUsersCall::abi_decode_returns(&returned, true)
+            .unwrap()
+            ._0;
+        let import = IVendingMachine::importUsersCall { users: decoded }.abi_encode();
+        assert_eq!(import[..4], IVendingMachine::importUsersCall::SELECTOR);
+        assert_eq!(
+            IVendingMachine::importUsersCall::abi_decode(&import, true)
+                .unwrap()
+                .users,
+            users
+        );
+    }
+}
@@ -0,0 +1,51 @@
+//!
+//! Repository automation, run as `cargo xtask <command>`.
+//!
+
+mod optimize;
+mod storage_layout;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const USAGE: &str = "usage: cargo xtask <command>
+
+commands:
+  optimize [package...]              build, run wasm-opt and check size budgets
+  storage-layout                     print the storage layout of every contract
+  storage-layout --diff <old> [new]  compare layouts between two git revisions";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("optimize") => optimize::run(&args[1..]),
+        Some("storage-layout") => storage_layout::run(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    };
+
+    if let Err(err) = result {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
+
+/// Root of the workspace, one level above this crate.
+pub fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask lives inside the workspace")
+        .to_path_buf()
+}
+
+/// Runs `git` in the workspace root and returns its stdout.
+pub fn git(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(workspace_root())
+        .output()
+        .map_err(|err| format!("failed to run `git`: {err}"))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
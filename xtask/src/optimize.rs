@@ -0,0 +1,169 @@
+//! Release build followed by `wasm-opt`/`wasm-strip`, with per-contract size budgets.
+//!
+//! Budgets are on the uncompressed module; `cargo stylus check` still has the
+//! final say on the compressed size that the chain enforces.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::workspace_root;
+
+/// How a contract is optimized and how large the result may be.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Profile {
+    pub package: &'static str,
+    pub wasm_opt_flags: &'static [&'static str],
+    pub budget_bytes: u64,
+}
+
+/// Stylus caps uncompressed programs at 128 KiB.
+const DEFAULT_PROFILE: Profile = Profile {
+    package: "",
+    wasm_opt_flags: &["-Oz"],
+    budget_bytes: 64 * 1024,
+};
+
+const PROFILES: &[Profile] = &[
+    // rust_decimal dominates these modules; squeeze harder and allow more headroom.
+    Profile {
+        package: "onchain-data-validator",
+        wasm_opt_flags: &["-Oz", "--converge", "--inlining-optimizing"],
+        budget_bytes: 120 * 1024,
+    },
+    Profile {
+        package: "sensor-registry",
+        wasm_opt_flags: &["-Oz", "--converge", "--inlining-optimizing"],
+        budget_bytes: 120 * 1024,
+    },
+];
+
+pub fn profile(package: &str) -> Profile {
+    PROFILES
+        .iter()
+        .find(|p| p.package == package)
+        .copied()
+        .unwrap_or(DEFAULT_PROFILE)
+}
+
+/// File name cargo gives the `cdylib` of `package`.
+fn artifact_name(package: &str) -> String {
+    format!("{}.wasm", package.replace('-', "_"))
+}
+
+/// Reads `name = "…"` from a contract manifest.
+fn package_name(manifest: &str) -> Option<String> {
+    manifest.lines().find_map(|line| {
+        let value = line.trim().strip_prefix("name")?.trim().strip_prefix('=')?;
+        Some(value.trim().trim_matches('"').to_string())
+    })
+}
+
+fn contract_packages() -> Result<Vec<String>, String> {
+    let contracts = workspace_root().join("contracts");
+    let entries = fs::read_dir(&contracts).map_err(|err| format!("{contracts:?}: {err}"))?;
+    let mut packages: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| fs::read_to_string(entry.path().join("Cargo.toml")).ok())
+        // Shared library crates such as `contracts/common` produce no `.wasm` of their own.
+        .filter(|manifest| manifest.contains("cdylib"))
+        .filter_map(|manifest| package_name(&manifest))
+        .collect();
+    packages.sort();
+    Ok(packages)
+}
+
+fn command(program: &str, args: &[&str]) -> Result<(), String> {
+    let status = Command::new(program)
+        .args(args)
+        .current_dir(workspace_root())
+        .status()
+        .map_err(|err| format!("failed to run `{program}`: {err}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("`{program}` exited with {status}"))
+    }
+}
+
+fn size(path: &Path) -> Result<u64, String> {
+    fs::metadata(path)
+        .map(|meta| meta.len())
+        .map_err(|err| format!("{path:?}: {err}"))
+}
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let packages = match args {
+        [] => contract_packages()?,
+        selected => selected.to_vec(),
+    };
+
+    let mut build = vec!["build", "--release", "--target", "wasm32-unknown-unknown"];
+    for package in &packages {
+        build.extend(["--package", package.as_str()]);
+    }
+    command("cargo", &build)?;
+
+    let release = workspace_root().join("target/wasm32-unknown-unknown/release");
+    let out_dir = workspace_root().join("target/optimized");
+    fs::create_dir_all(&out_dir).map_err(|err| format!("{out_dir:?}: {err}"))?;
+
+    println!(
+        "{:<28}{:>10}{:>10}{:>10}",
+        "contract", "before", "after", "budget"
+    );
+    let mut over_budget = Vec::new();
+    for package in &packages {
+        let profile = profile(package);
+        let input = release.join(artifact_name(package));
+        let output = out_dir.join(artifact_name(package));
+        let (input_str, output_str) = (input.to_string_lossy(), output.to_string_lossy());
+
+        let mut wasm_opt = profile.wasm_opt_flags.to_vec();
+        wasm_opt.extend([
+            "--enable-bulk-memory",
+            "--strip-debug",
+            "--strip-producers",
+            &input_str,
+            "-o",
+            &output_str,
+        ]);
+        command("wasm-opt", &wasm_opt)?;
+        // wasm-strip is optional; wasm-opt already removed the bulk of custom sections.
+        let _ = command("wasm-strip", &[&output_str]);
+
+        let (before, after) = (size(&input)?, size(&output)?);
+        println!(
+            "{package:<28}{before:>10}{after:>10}{:>10}",
+            profile.budget_bytes
+        );
+        if after > profile.budget_bytes {
+            over_budget.push(package.clone());
+        }
+    }
+
+    if over_budget.is_empty() {
+        println!("optimized modules written to {}", out_dir.display());
+        Ok(())
+    } else {
+        Err(format!("over size budget: {}", over_budget.join(", ")))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_profile_lookup_falls_back_to_default() {
+        assert_eq!(profile("counter-contract"), DEFAULT_PROFILE);
+        assert_eq!(profile("onchain-data-validator").budget_bytes, 120 * 1024);
+    }
+
+    #[test]
+    fn test_artifact_and_package_names() {
+        assert_eq!(artifact_name("vending-machine"), "vending_machine.wasm");
+        let manifest = "[package]\nname = \"vending-machine\"\nversion = \"0.1.0\"\n";
+        assert_eq!(package_name(manifest).as_deref(), Some("vending-machine"));
+    }
+}
@@ -0,0 +1,354 @@
+//! Storage layout derived from the `sol_storage!` definitions.
+//!
+//! Fields are laid out with Solidity's rules, which `sol_storage!` follows:
+//! value types pack into 32-byte slots in declaration order, while mappings,
+//! dynamic types, fixed arrays and nested structs always start a new slot.
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::{git, workspace_root};
+
+/// A field's position in storage.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub ty: String,
+    pub slot: u64,
+    pub offset: u8,
+}
+
+/// A struct declared inside `sol_storage!`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StorageStruct {
+    pub name: String,
+    pub entrypoint: bool,
+    pub fields: Vec<Field>,
+    pub slots: u64,
+}
+
+/// Contract directory name → structs declared in its `lib.rs`.
+type Layouts = BTreeMap<String, Vec<StorageStruct>>;
+
+/// Struct name, entrypoint flag and `(type, name)` pairs as written in the source.
+type RawStruct = (String, bool, Vec<(String, String)>);
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    match args.first().map(String::as_str) {
+        None => {
+            for (contract, structs) in working_tree_layouts()? {
+                print_layout(&contract, &structs);
+            }
+            Ok(())
+        }
+        Some("--diff") => {
+            let old = args.get(1).ok_or("--diff needs a git revision")?;
+            let old_layouts = revision_layouts(old)?;
+            let new_layouts = match args.get(2) {
+                Some(new) => revision_layouts(new)?,
+                None => working_tree_layouts()?,
+            };
+            let problems = diff(&old_layouts, &new_layouts);
+            if problems.is_empty() {
+                println!("storage layouts are upgrade-compatible");
+                return Ok(());
+            }
+            for problem in &problems {
+                println!("{problem}");
+            }
+            Err(format!("{} upgrade-incompatible change(s)", problems.len()))
+        }
+        Some(other) => Err(format!("unknown storage-layout option `{other}`")),
+    }
+}
+
+fn print_layout(contract: &str, structs: &[StorageStruct]) {
+    for item in structs {
+        let marker = if item.entrypoint { " (entrypoint)" } else { "" };
+        println!(
+            "{contract} :: {}{marker}, {} slot(s)",
+            item.name, item.slots
+        );
+        println!("  {:<6}{:<8}{:<40}field", "slot", "offset", "type");
+        for field in &item.fields {
+            println!(
+                "  {:<6}{:<8}{:<40}{}",
+                field.slot, field.offset, field.ty, field.name
+            );
+        }
+        println!();
+    }
+}
+
+fn working_tree_layouts() -> Result<Layouts, String> {
+    let contracts = workspace_root().join("contracts");
+    let entries = fs::read_dir(&contracts).map_err(|err| format!("{contracts:?}: {err}"))?;
+    let mut layouts = Layouts::new();
+    for entry in entries.flatten() {
+        let source = entry.path().join("src/lib.rs");
+        if let Ok(text) = fs::read_to_string(&source) {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            layouts.insert(name, parse(&text));
+        }
+    }
+    Ok(layouts)
+}
+
+fn revision_layouts(rev: &str) -> Result<Layouts, String> {
+    let listing = git(&["ls-tree", "--name-only", rev, "contracts/"])?;
+    let mut layouts = Layouts::new();
+    for dir in listing.lines() {
+        if let Ok(text) = git(&["show", &format!("{rev}:{dir}/src/lib.rs")]) {
+            let name = dir.trim_start_matches("contracts/").to_string();
+            layouts.insert(name, parse(&text));
+        }
+    }
+    Ok(layouts)
+}
+
+/// Lists every field of `old` that moved, changed type or disappeared in `new`.
+/// Fields appended after the existing ones are fine and not reported.
+pub fn diff(old: &Layouts, new: &Layouts) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (contract, old_structs) in old {
+        let Some(new_structs) = new.get(contract) else {
+            continue;
+        };
+        for old_struct in old_structs {
+            let Some(new_struct) = new_structs.iter().find(|s| s.name == old_struct.name) else {
+                problems.push(format!("{contract} :: {} removed", old_struct.name));
+                continue;
+            };
+            for field in &old_struct.fields {
+                let location = format!("{contract} :: {}.{}", old_struct.name, field.name);
+                match new_struct.fields.iter().find(|f| f.name == field.name) {
+                    None => problems.push(format!("{location} removed")),
+                    Some(moved) if moved.slot != field.slot || moved.offset != field.offset => {
+                        problems.push(format!(
+                            "{location} moved from slot {}+{} to slot {}+{}",
+                            field.slot, field.offset, moved.slot, moved.offset
+                        ))
+                    }
+                    Some(retyped) if retyped.ty != field.ty => problems.push(format!(
+                        "{location} changed type from `{}` to `{}`",
+                        field.ty, retyped.ty
+                    )),
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+    problems
+}
+
+/// Extracts and lays out every struct declared in the `sol_storage!` blocks of `source`.
+pub fn parse(source: &str) -> Vec<StorageStruct> {
+    let mut declared = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("sol_storage!") {
+        rest = &rest[start..];
+        let Some(open) = rest.find('{') else { break };
+        let body = matching_block(&rest[open..]);
+        declared.extend(parse_block(body));
+        rest = &rest[open + body.len()..];
+    }
+
+    let mut laid_out: Vec<StorageStruct> = Vec::new();
+    for (name, entrypoint, raw_fields) in declared {
+        let item = lay_out(name, entrypoint, &raw_fields, &laid_out);
+        laid_out.push(item);
+    }
+    laid_out
+}
+
+/// Returns the text of the brace-delimited block `text` starts with.
+fn matching_block(text: &str) -> &str {
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return &text[..=i];
+                }
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Splits a `sol_storage!` body into `(struct name, is entrypoint, [(type, field)])`.
+fn parse_block(body: &str) -> Vec<RawStruct> {
+    let mut structs = Vec::new();
+    let mut entrypoint = false;
+    let mut current: Option<RawStruct> = None;
+    for line in body.lines() {
+        let line = line.split("//").next().unwrap_or_default().trim();
+        if line.is_empty() || line.starts_with("#[") {
+            entrypoint |= line == "#[entrypoint]";
+            continue;
+        }
+        if let Some(header) = line
+            .strip_prefix("pub struct ")
+            .or(line.strip_prefix("struct "))
+        {
+            let name = header.trim_end_matches('{').trim().to_string();
+            current = Some((name, entrypoint, Vec::new()));
+            entrypoint = false;
+        } else if line.starts_with('}') {
+            structs.extend(current.take());
+        } else if let (Some(field), Some((_, _, fields))) =
+            (line.strip_suffix(';'), current.as_mut())
+        {
+            if let Some((ty, name)) = field.rsplit_once(char::is_whitespace) {
+                fields.push((ty.trim().to_string(), name.to_string()));
+            }
+        }
+    }
+    structs
+}
+
+fn lay_out(
+    name: String,
+    entrypoint: bool,
+    raw_fields: &[(String, String)],
+    known: &[StorageStruct],
+) -> StorageStruct {
+    let mut fields = Vec::new();
+    let mut slot = 0;
+    let mut used = 0;
+    for (ty, field) in raw_fields {
+        let offset = match packed_size(ty) {
+            Some(size) => {
+                if used + size > 32 {
+                    slot += 1;
+                    used = 0;
+                }
+                used += size;
+                used - size
+            }
+            None => {
+                if used > 0 {
+                    slot += 1;
+                    used = 0;
+                }
+                0
+            }
+        };
+        fields.push(Field {
+            name: field.clone(),
+            ty: ty.clone(),
+            slot,
+            offset,
+        });
+        if packed_size(ty).is_none() {
+            slot += slot_count(ty, known);
+        }
+    }
+    let slots = slot + u64::from(used > 0);
+    StorageStruct {
+        name,
+        entrypoint,
+        fields,
+        slots,
+    }
+}
+
+/// Byte size of value types that share slots; `None` for everything else.
+fn packed_size(ty: &str) -> Option<u8> {
+    let bits = |digits: &str| digits.parse::<u16>().ok().map(|bits| (bits / 8) as u8);
+    match ty {
+        "address" => Some(20),
+        "bool" => Some(1),
+        "uint" | "int" => Some(32),
+        _ => ty
+            .strip_prefix("uint")
+            .or(ty.strip_prefix("int"))
+            .and_then(bits)
+            .or_else(|| ty.strip_prefix("bytes").and_then(|n| n.parse().ok())),
+    }
+}
+
+/// Number of whole slots taken by a non-packed type.
+fn slot_count(ty: &str, known: &[StorageStruct]) -> u64 {
+    if let Some(element) = ty.strip_suffix(']') {
+        let (element, len) = element.rsplit_once('[').unwrap_or((element, ""));
+        let Ok(len) = len.parse::<u64>() else {
+            return 1; // dynamic array: length slot only
+        };
+        return match packed_size(element) {
+            Some(size) => len.div_ceil(u64::from(32 / size)),
+            None => len * slot_count(element, known),
+        };
+    }
+    known.iter().find(|s| s.name == ty).map_or(1, |s| s.slots)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SOURCE: &str = r#"
+sol_storage! {
+    pub struct Record {
+        address user;
+        uint64 last_time;
+        uint256 balance;
+    }
+
+    #[entrypoint]
+    pub struct Machine {
+        /// Comments and attributes are skipped.
+        bool paused;
+        uint8 tier;
+        mapping(address => uint256) balances;
+        Record[3] records;
+        uint128 total;
+        uint128 price;
+    }
+}
+"#;
+
+    #[test]
+    fn test_parse_packs_value_types() {
+        let structs = parse(SOURCE);
+        assert_eq!(structs.len(), 2);
+
+        let record = &structs[0];
+        assert!(!record.entrypoint);
+        assert_eq!((record.fields[1].slot, record.fields[1].offset), (0, 20));
+        assert_eq!((record.fields[2].slot, record.fields[2].offset), (1, 0));
+        assert_eq!(record.slots, 2);
+
+        let machine = &structs[1];
+        assert!(machine.entrypoint);
+        let positions: Vec<_> = machine.fields.iter().map(|f| (f.slot, f.offset)).collect();
+        assert_eq!(
+            positions,
+            vec![(0, 0), (0, 1), (1, 0), (2, 0), (8, 0), (8, 16)]
+        );
+        assert_eq!(machine.slots, 9);
+    }
+
+    #[test]
+    fn test_diff_allows_appended_fields() {
+        let old = Layouts::from([("demo".to_string(), parse(SOURCE))]);
+        let appended = SOURCE.replace("uint128 price;", "uint128 price;\n        uint256 extra;");
+        let new = Layouts::from([("demo".to_string(), parse(&appended))]);
+        assert!(diff(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn test_diff_flags_moved_and_retyped_fields() {
+        let old = Layouts::from([("demo".to_string(), parse(SOURCE))]);
+        let reordered = SOURCE
+            .replace("bool paused;", "")
+            .replace("uint128 price;", "uint256 price;\n        bool paused;");
+        let new = Layouts::from([("demo".to_string(), parse(&reordered))]);
+        let problems = diff(&old, &new);
+        assert!(problems.iter().any(|p| p.contains("Machine.paused moved")));
+        assert!(problems.iter().any(|p| p.contains("Machine.tier moved")));
+    }
+}